@@ -1,15 +1,19 @@
+use std::collections::HashMap;
 use std::future::Future;
 use std::io;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use crate::net::common::ConnectionId;
-use crate::net::server::{Reply, RequestCtx, ServerHandler};
+use crate::net::common::{ConnectionId, Header, Id};
+use crate::net::server::{QueuedServerReply, Reply, RequestCtx, ServerHandler};
+use futures::future::{AbortHandle, Aborted};
 use log::*;
+use tokio::time::Instant;
 
 use crate::protocol::{
-    self, ChangeKind, DirEntry, Environment, Error, Metadata, Permissions, ProcessId, PtySize,
-    RemotePath, SearchId, SearchQuery, SetPermissionsOptions, StatusInfo, SystemInfo, TunnelId,
-    Version,
+    self, ChangeKind, DirEntry, Environment, Error, ListeningPort, Metadata, Permissions,
+    ProcSpawnOptions, ProcessId, PtySize, RemotePath, SearchId, SearchQuery, SetPermissionsOptions,
+    StatusInfo, SystemInfo, TunnelId, TunnelProtocol, Version,
 };
 
 mod reply;
@@ -19,6 +23,19 @@ use reply::SingleReply;
 pub struct Ctx {
     pub connection_id: ConnectionId,
     pub reply: Box<dyn Reply<Data = protocol::Response>>,
+
+    /// Point in time by which the request must be answered, after which the server stops
+    /// handling it and responds with a timed out error
+    pub deadline: Option<Instant>,
+}
+
+impl Ctx {
+    /// Returns the time left until the deadline of the request, if it has one, which can be
+    /// passed down to operations that support timeouts of their own
+    pub fn timeout(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
 }
 
 /// Represents a [`ServerHandler`] that leverages an API compliant with `distant`
@@ -27,6 +44,9 @@ where
     T: Api,
 {
     api: Arc<T>,
+
+    /// Requests being handled that can still be cancelled, keyed by connection and request id
+    requests: Mutex<HashMap<(ConnectionId, Id), AbortHandle>>,
 }
 
 impl<T> ApiServerHandler<T>
@@ -34,7 +54,46 @@ where
     T: Api,
 {
     pub fn new(api: T) -> Self {
-        Self { api: Arc::new(api) }
+        Self {
+            api: Arc::new(api),
+            requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Aborts the request with `request_id` that was sent over the connection with
+    /// `connection_id`.
+    ///
+    /// NOTE: A manager forwarding requests prefixes their ids with the id of the channel they came
+    ///       from, and rewrites the id being cancelled in the same way, which also keeps one
+    ///       channel from cancelling the requests of another.
+    fn cancel(&self, connection_id: ConnectionId, request_id: &str) -> protocol::Response {
+        match self
+            .requests
+            .lock()
+            .unwrap()
+            .remove(&(connection_id, request_id.to_string()))
+        {
+            Some(handle) => {
+                handle.abort();
+                protocol::Response::Ok
+            }
+            None => protocol::Response::Error(protocol::Error {
+                kind: protocol::ErrorKind::NotFound,
+                description: format!("No request {request_id} to cancel"),
+            }),
+        }
+    }
+}
+
+/// Aborts a collection of tasks when dropped, used to stop the tasks of a batch when the batch
+/// itself is cancelled
+struct AbortOnDrop(Vec<tokio::task::AbortHandle>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
     }
 }
 
@@ -353,6 +412,7 @@ pub trait Api {
     /// * `environment` - the environment variables to associate with the process
     /// * `current_dir` - the alternative current directory to use with the process
     /// * `pty` - if provided, will run the process within a PTY of the given size
+    /// * `options` - additional options such as resource limits to apply to the process
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
     #[allow(unused_variables)]
@@ -363,6 +423,7 @@ pub trait Api {
         environment: Environment,
         current_dir: Option<RemotePath>,
         pty: Option<PtySize>,
+        options: ProcSpawnOptions,
     ) -> impl Future<Output = io::Result<ProcessId>> + Send {
         async { unsupported("proc_spawn") }
     }
@@ -393,6 +454,20 @@ pub trait Api {
         async { unsupported("proc_stdin") }
     }
 
+    /// Closes the stdin of the process with the specified id, signaling end of input.
+    ///
+    /// * `id` - the unique id of the process
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    fn proc_stdin_close(
+        &self,
+        ctx: Ctx,
+        id: ProcessId,
+    ) -> impl Future<Output = io::Result<()>> + Send {
+        async { unsupported("proc_stdin_close") }
+    }
+
     /// Resizes the PTY of the process with the specified id.
     ///
     /// * `id` - the unique id of the process
@@ -450,6 +525,74 @@ pub trait Api {
         async { unsupported("tunnel_listen") }
     }
 
+    /// Opens a forward UDP tunnel sending datagrams to the specified host and port.
+    ///
+    /// Each write to the tunnel is sent as a single datagram, and each datagram received back
+    /// is reported as its own `TunnelData`. The tunnel closes once it has been idle for a while.
+    ///
+    /// * `host` - the host to send datagrams to
+    /// * `port` - the port to send datagrams to
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    fn udp_tunnel_open(
+        &self,
+        ctx: Ctx,
+        host: String,
+        port: u16,
+    ) -> impl Future<Output = io::Result<TunnelId>> + Send {
+        async { unsupported("udp_tunnel_open") }
+    }
+
+    /// Starts a reverse UDP tunnel listener on the specified host and port.
+    /// Returns the tunnel id and the actual bound port.
+    ///
+    /// Each distinct peer sending datagrams to the listener is reported via `TunnelIncoming` as
+    /// its own sub-tunnel, which closes once the peer has been idle for a while.
+    ///
+    /// * `host` - the host to bind on
+    /// * `port` - the port to listen on (0 for OS-assigned)
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    fn udp_tunnel_listen(
+        &self,
+        ctx: Ctx,
+        host: String,
+        port: u16,
+    ) -> impl Future<Output = io::Result<(TunnelId, u16)>> + Send {
+        async { unsupported("udp_tunnel_listen") }
+    }
+
+    /// Opens a forward tunnel to the Unix domain socket at the specified path.
+    ///
+    /// * `path` - the path of the socket to connect to
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    fn unix_tunnel_open(
+        &self,
+        ctx: Ctx,
+        path: String,
+    ) -> impl Future<Output = io::Result<TunnelId>> + Send {
+        async { unsupported("unix_tunnel_open") }
+    }
+
+    /// Starts a reverse tunnel listener on a Unix domain socket at the specified path.
+    /// Returns the tunnel id.
+    ///
+    /// * `path` - the path of the socket to create and listen on
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    fn unix_tunnel_listen(
+        &self,
+        ctx: Ctx,
+        path: String,
+    ) -> impl Future<Output = io::Result<TunnelId>> + Send {
+        async { unsupported("unix_tunnel_listen") }
+    }
+
     /// Writes data to an active tunnel.
     ///
     /// * `id` - the unique id of the tunnel
@@ -483,6 +626,17 @@ pub trait Api {
     fn status(&self, ctx: Ctx) -> impl Future<Output = io::Result<StatusInfo>> + Send {
         async { Ok(StatusInfo::default()) }
     }
+
+    /// Lists the TCP ports that processes on the server's machine are listening on.
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    fn listening_ports(
+        &self,
+        ctx: Ctx,
+    ) -> impl Future<Output = io::Result<Vec<ListeningPort>>> + Send {
+        async { unsupported("listening_ports") }
+    }
 }
 
 impl<T> ServerHandler for ApiServerHandler<T>
@@ -513,12 +667,96 @@ where
         // of an API function is sent back before anything else
         let reply = reply.queue();
 
-        // Process single vs batch requests
         let response = match request.payload {
+            protocol::Msg::Single(protocol::Request::Cancel { request_id }) => {
+                protocol::Msg::Single(self.cancel(connection_id, &request_id))
+            }
+            payload => {
+                let batch_len = payload.as_batch().map(<[_]>::len);
+                let deadline = match request.header.deadline() {
+                    Some(Ok(timeout)) => Some(Instant::now() + timeout),
+                    Some(Err(x)) => {
+                        warn!("[Conn {}] Ignoring invalid deadline: {}", connection_id, x);
+                        None
+                    }
+                    None => None,
+                };
+
+                // Track the request while it is being processed so it can be cancelled, which
+                // drops the processing future and aborts any tasks it spawned
+                let key = (connection_id, request.id);
+                let (process, handle) = futures::future::abortable(self.process(
+                    connection_id,
+                    &request.header,
+                    deadline,
+                    payload,
+                    &reply,
+                ));
+                self.requests.lock().unwrap().insert(key.clone(), handle);
+                let result = match deadline {
+                    Some(deadline) => tokio::time::timeout_at(deadline, process).await.ok(),
+                    None => Some(process.await),
+                };
+                self.requests.lock().unwrap().remove(&key);
+
+                match result {
+                    Some(Ok(response)) => response,
+                    Some(Err(Aborted)) => {
+                        debug!("[Conn {}] Request {} was cancelled", connection_id, key.1);
+                        stopped(
+                            batch_len,
+                            protocol::ErrorKind::Interrupted,
+                            "Canceled by request",
+                        )
+                    }
+                    None => {
+                        debug!("[Conn {}] Request {} timed out", connection_id, key.1);
+                        stopped(
+                            batch_len,
+                            protocol::ErrorKind::TimedOut,
+                            "Deadline of request exceeded",
+                        )
+                    }
+                }
+            }
+        };
+
+        // Queue up our result to go before ANY of the other messages that might be sent.
+        // This is important to avoid situations such as when a process is started, but before
+        // the confirmation can be sent some stdout or stderr is captured and sent first.
+        if let Err(x) = reply.send_before(response) {
+            error!("[Conn {}] Failed to send response: {}", connection_id, x);
+        }
+
+        // Flush out all of our replies thus far and toggle to no longer hold submissions
+        if let Err(x) = reply.flush(false) {
+            error!(
+                "[Conn {}] Failed to flush response queue: {}",
+                connection_id, x
+            );
+        }
+    }
+}
+
+impl<T> ApiServerHandler<T>
+where
+    T: Api + Send + Sync + 'static,
+{
+    /// Processes single and batch requests, returning the response to send back
+    async fn process(
+        &self,
+        connection_id: ConnectionId,
+        header: &Header,
+        deadline: Option<Instant>,
+        payload: protocol::Msg<protocol::Request>,
+        reply: &QueuedServerReply<protocol::Msg<protocol::Response>>,
+    ) -> protocol::Msg<protocol::Response> {
+        match payload {
             protocol::Msg::Single(data) => {
                 let ctx = Ctx {
                     connection_id,
                     reply: Box::new(SingleReply::from(reply.clone_reply())),
+                    deadline,
                 };
 
                 let data = handle_request(Arc::clone(&self.api), ctx, data).await;
@@ -530,9 +768,7 @@ where
 
                 protocol::Msg::Single(data)
             }
-            protocol::Msg::Batch(list)
-                if matches!(request.header.get_as("sequence"), Some(Ok(true))) =>
-            {
+            protocol::Msg::Batch(list) if matches!(header.get_as("sequence"), Some(Ok(true))) => {
                 let mut out = Vec::new();
                 let mut has_failed = false;
 
@@ -549,6 +785,7 @@ where
                     let ctx = Ctx {
                         connection_id,
                         reply: Box::new(SingleReply::from(reply.clone_reply())),
+                        deadline,
                     };
 
                     let data = handle_request(Arc::clone(&self.api), ctx, data).await;
@@ -576,6 +813,7 @@ where
                     let ctx = Ctx {
                         connection_id,
                         reply: Box::new(SingleReply::from(reply.clone_reply())),
+                        deadline,
                     };
 
                     let task = tokio::spawn(async move {
@@ -592,6 +830,8 @@ where
                     tasks.push(task);
                 }
 
+                let _abort_on_drop =
+                    AbortOnDrop(tasks.iter().map(|task| task.abort_handle()).collect());
                 let out = futures::future::join_all(tasks)
                     .await
                     .into_iter()
@@ -602,22 +842,27 @@ where
                     .collect();
                 protocol::Msg::Batch(out)
             }
-        };
-
-        // Queue up our result to go before ANY of the other messages that might be sent.
-        // This is important to avoid situations such as when a process is started, but before
-        // the confirmation can be sent some stdout or stderr is captured and sent first.
-        if let Err(x) = reply.send_before(response) {
-            error!("[Conn {}] Failed to send response: {}", connection_id, x);
         }
+    }
+}
 
-        // Flush out all of our replies thus far and toggle to no longer hold submissions
-        if let Err(x) = reply.flush(false) {
-            error!(
-                "[Conn {}] Failed to flush response queue: {}",
-                connection_id, x
-            );
-        }
+/// Returns the errors sent back in place of the response to a request that was stopped before
+/// finishing, one for each request of a batch with `batch_len` requests
+fn stopped(
+    batch_len: Option<usize>,
+    kind: protocol::ErrorKind,
+    description: &str,
+) -> protocol::Msg<protocol::Response> {
+    let error = || {
+        protocol::Response::Error(protocol::Error {
+            kind,
+            description: description.to_string(),
+        })
+    };
+
+    match batch_len {
+        Some(len) => protocol::Msg::Batch((0..len).map(|_| error()).collect()),
+        None => protocol::Msg::Single(error()),
     }
 }
 
@@ -749,8 +994,9 @@ where
             environment,
             current_dir,
             pty,
+            options,
         } => api
-            .proc_spawn(ctx, cmd.into(), environment, current_dir, pty)
+            .proc_spawn(ctx, cmd.into(), environment, current_dir, pty, options)
             .await
             .map(|id| protocol::Response::ProcSpawned { id })
             .unwrap_or_else(protocol::Response::from),
@@ -764,6 +1010,11 @@ where
             .await
             .map(|_| protocol::Response::Ok)
             .unwrap_or_else(protocol::Response::from),
+        protocol::Request::ProcStdinClose { id } => api
+            .proc_stdin_close(ctx, id)
+            .await
+            .map(|_| protocol::Response::Ok)
+            .unwrap_or_else(protocol::Response::from),
         protocol::Request::ProcResizePty { id, size } => api
             .proc_resize_pty(ctx, id, size)
             .await
@@ -774,16 +1025,28 @@ where
             .await
             .map(protocol::Response::SystemInfo)
             .unwrap_or_else(protocol::Response::from),
-        protocol::Request::TunnelOpen { host, port } => api
-            .tunnel_open(ctx, host, port)
-            .await
-            .map(|id| protocol::Response::TunnelOpened { id })
-            .unwrap_or_else(protocol::Response::from),
-        protocol::Request::TunnelListen { host, port } => api
-            .tunnel_listen(ctx, host, port)
-            .await
-            .map(|(id, port)| protocol::Response::TunnelListening { id, port })
-            .unwrap_or_else(protocol::Response::from),
+        protocol::Request::TunnelOpen {
+            host,
+            port,
+            protocol: tunnel_protocol,
+        } => match tunnel_protocol {
+            TunnelProtocol::Tcp => api.tunnel_open(ctx, host, port).await,
+            TunnelProtocol::Udp => api.udp_tunnel_open(ctx, host, port).await,
+            TunnelProtocol::Unix => api.unix_tunnel_open(ctx, host).await,
+        }
+        .map(|id| protocol::Response::TunnelOpened { id })
+        .unwrap_or_else(protocol::Response::from),
+        protocol::Request::TunnelListen {
+            host,
+            port,
+            protocol: tunnel_protocol,
+        } => match tunnel_protocol {
+            TunnelProtocol::Tcp => api.tunnel_listen(ctx, host, port).await,
+            TunnelProtocol::Udp => api.udp_tunnel_listen(ctx, host, port).await,
+            TunnelProtocol::Unix => api.unix_tunnel_listen(ctx, host).await.map(|id| (id, 0)),
+        }
+        .map(|(id, port)| protocol::Response::TunnelListening { id, port })
+        .unwrap_or_else(protocol::Response::from),
         protocol::Request::TunnelWrite { id, data } => api
             .tunnel_write(ctx, id, data)
             .await
@@ -799,13 +1062,23 @@ where
             .await
             .map(protocol::Response::StatusInfo)
             .unwrap_or_else(protocol::Response::from),
+        protocol::Request::ListeningPorts {} => api
+            .listening_ports(ctx)
+            .await
+            .map(|ports| protocol::Response::ListeningPorts { ports })
+            .unwrap_or_else(protocol::Response::from),
+        protocol::Request::Cancel { .. } => protocol::Response::Error(protocol::Error {
+            kind: protocol::ErrorKind::Unsupported,
+            description: String::from("Cancel cannot be sent as part of a batch"),
+        }),
     }
 }
 
 #[cfg(test)]
 mod tests {
     //! Tests for the Api trait default implementations, the unsupported() helper, and
-    //! ApiServerHandler request dispatch (single, parallel batch, sequential batch with fail-fast).
+    //! ApiServerHandler request dispatch (single, parallel batch, sequential batch with fail-fast,
+    //! cancellation, and deadlines).
 
     use super::*;
     use crate::protocol::RemotePath;
@@ -847,6 +1120,7 @@ mod tests {
         let ctx = Ctx {
             connection_id: 1,
             reply: Box::new(tx),
+            deadline: None,
         };
         (ctx, rx)
     }
@@ -1071,7 +1345,14 @@ mod tests {
         let api = DefaultApi;
         let (ctx, _rx) = make_ctx();
         let err = api
-            .proc_spawn(ctx, String::from("echo"), Default::default(), None, None)
+            .proc_spawn(
+                ctx,
+                String::from("echo"),
+                Default::default(),
+                None,
+                None,
+                Default::default(),
+            )
             .await
             .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::Unsupported);
@@ -1093,6 +1374,14 @@ mod tests {
         assert_eq!(err.kind(), io::ErrorKind::Unsupported);
     }
 
+    #[test_log::test(tokio::test)]
+    async fn default_proc_stdin_close_returns_unsupported() {
+        let api = DefaultApi;
+        let (ctx, _rx) = make_ctx();
+        let err = api.proc_stdin_close(ctx, 0).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
     #[test_log::test(tokio::test)]
     async fn default_proc_resize_pty_returns_unsupported() {
         let api = DefaultApi;
@@ -1143,6 +1432,50 @@ mod tests {
         assert_eq!(err.kind(), io::ErrorKind::Unsupported);
     }
 
+    #[test_log::test(tokio::test)]
+    async fn default_udp_tunnel_open_returns_unsupported() {
+        let api = DefaultApi;
+        let (ctx, _rx) = make_ctx();
+        let err = api
+            .udp_tunnel_open(ctx, String::from("localhost"), 53)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn default_udp_tunnel_listen_returns_unsupported() {
+        let api = DefaultApi;
+        let (ctx, _rx) = make_ctx();
+        let err = api
+            .udp_tunnel_listen(ctx, String::from("0.0.0.0"), 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn default_unix_tunnel_open_returns_unsupported() {
+        let api = DefaultApi;
+        let (ctx, _rx) = make_ctx();
+        let err = api
+            .unix_tunnel_open(ctx, String::from("/var/run/docker.sock"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn default_unix_tunnel_listen_returns_unsupported() {
+        let api = DefaultApi;
+        let (ctx, _rx) = make_ctx();
+        let err = api
+            .unix_tunnel_listen(ctx, String::from("/tmp/app.sock"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
     #[test_log::test(tokio::test)]
     async fn default_tunnel_write_returns_unsupported() {
         let api = DefaultApi;
@@ -1516,6 +1849,242 @@ mod tests {
         }
     }
 
+    // ---------------------------------------------------------------
+    // ApiServerHandler::on_request - cancellation
+    // ---------------------------------------------------------------
+
+    /// Api whose read_dir never completes, used to test cancelling requests
+    struct PendingApi;
+
+    impl Api for PendingApi {
+        async fn read_dir(
+            &self,
+            _ctx: Ctx,
+            _path: RemotePath,
+            _depth: usize,
+            _absolute: bool,
+            _canonicalize: bool,
+            _include_root: bool,
+        ) -> io::Result<(Vec<DirEntry>, Vec<io::Error>)> {
+            std::future::pending().await
+        }
+    }
+
+    fn read_dir_request() -> protocol::Request {
+        protocol::Request::DirRead {
+            path: RemotePath::from("/"),
+            depth: 0,
+            absolute: false,
+            canonicalize: false,
+            include_root: false,
+        }
+    }
+
+    /// Spawns the handling of `payload` sent with `id`, waiting until it can be cancelled
+    async fn spawn_pending_request(
+        handler: &Arc<ApiServerHandler<PendingApi>>,
+        id: &str,
+        payload: Msg<protocol::Request>,
+    ) -> (tokio::task::JoinHandle<()>, TestRx) {
+        let (mut ctx, rx) = make_request_ctx(payload, Header::new());
+        ctx.request.id = id.to_string();
+
+        let task = tokio::spawn({
+            let handler = Arc::clone(handler);
+            async move { handler.on_request(ctx).await }
+        });
+
+        while !handler
+            .requests
+            .lock()
+            .unwrap()
+            .contains_key(&(1, id.to_string()))
+        {
+            tokio::task::yield_now().await;
+        }
+
+        (task, rx)
+    }
+
+    /// Sends a cancel request with `id` for the request with `request_id`, returning the response
+    async fn cancel_request<T>(
+        handler: &ApiServerHandler<T>,
+        id: &str,
+        request_id: &str,
+    ) -> protocol::Response
+    where
+        T: Api + Send + Sync + 'static,
+    {
+        let (mut ctx, mut rx) = make_request_ctx(
+            Msg::Single(protocol::Request::Cancel {
+                request_id: request_id.to_string(),
+            }),
+            Header::new(),
+        );
+        ctx.request.id = id.to_string();
+
+        handler.on_request(ctx).await;
+        rx.recv().await.unwrap().payload.into_single().unwrap()
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn on_request_cancel_interrupts_pending_request() {
+        let handler = Arc::new(ApiServerHandler::new(PendingApi));
+        let (task, mut rx) =
+            spawn_pending_request(&handler, "req-1", Msg::Single(read_dir_request())).await;
+
+        let resp = cancel_request(&handler, "cancel-1", "req-1").await;
+        assert_eq!(resp, protocol::Response::Ok);
+
+        task.await.unwrap();
+        let msg = rx.recv().await.unwrap().payload.into_single().unwrap();
+        match msg {
+            protocol::Response::Error(x) => assert_eq!(x.kind, protocol::ErrorKind::Interrupted),
+            other => panic!("Expected Error response, got {other:?}"),
+        }
+        assert!(handler.requests.lock().unwrap().is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn on_request_cancel_interrupts_every_request_in_pending_batch() {
+        let handler = Arc::new(ApiServerHandler::new(PendingApi));
+        let (task, mut rx) = spawn_pending_request(
+            &handler,
+            "req-1",
+            Msg::Batch(vec![read_dir_request(), read_dir_request()]),
+        )
+        .await;
+
+        let resp = cancel_request(&handler, "cancel-1", "req-1").await;
+        assert_eq!(resp, protocol::Response::Ok);
+
+        task.await.unwrap();
+        let batch = rx.recv().await.unwrap().payload.into_batch().unwrap();
+        assert_eq!(batch.len(), 2);
+        for msg in batch {
+            match msg {
+                protocol::Response::Error(x) => {
+                    assert_eq!(x.kind, protocol::ErrorKind::Interrupted)
+                }
+                other => panic!("Expected Error response, got {other:?}"),
+            }
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn on_request_cancel_returns_not_found_for_unknown_request() {
+        let handler = ApiServerHandler::new(MockApi);
+        let resp = cancel_request(&handler, "cancel-1", "req-1").await;
+        match resp {
+            protocol::Response::Error(x) => assert_eq!(x.kind, protocol::ErrorKind::NotFound),
+            other => panic!("Expected Error response, got {other:?}"),
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn on_request_cancel_in_batch_returns_error() {
+        let handler = ApiServerHandler::new(MockApi);
+        let (ctx, mut rx) = make_request_ctx(
+            Msg::Batch(vec![protocol::Request::Cancel {
+                request_id: String::from("req-0"),
+            }]),
+            Header::new(),
+        );
+
+        handler.on_request(ctx).await;
+
+        let batch = rx.recv().await.unwrap().payload.into_batch().unwrap();
+        assert!(batch[0].is_error());
+    }
+
+    // ---------------------------------------------------------------
+    // ApiServerHandler::on_request - deadlines
+    // ---------------------------------------------------------------
+
+    /// Api whose exists reports whether the request came with a deadline
+    struct DeadlineApi;
+
+    impl Api for DeadlineApi {
+        async fn exists(&self, ctx: Ctx, _path: RemotePath) -> io::Result<bool> {
+            Ok(ctx.deadline.is_some() && ctx.timeout().is_some())
+        }
+    }
+
+    fn deadline_header(millis: u64) -> Header {
+        let mut header = Header::new();
+        header.set_deadline(Duration::from_millis(millis));
+        header
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn on_request_returns_timed_out_once_deadline_passes() {
+        let handler = ApiServerHandler::new(PendingApi);
+        let (ctx, mut rx) = make_request_ctx(Msg::Single(read_dir_request()), deadline_header(50));
+
+        handler.on_request(ctx).await;
+
+        let msg = rx.recv().await.unwrap().payload.into_single().unwrap();
+        match msg {
+            protocol::Response::Error(x) => assert_eq!(x.kind, protocol::ErrorKind::TimedOut),
+            other => panic!("Expected Error response, got {other:?}"),
+        }
+        assert!(handler.requests.lock().unwrap().is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn on_request_returns_timed_out_for_every_request_in_batch_once_deadline_passes() {
+        let handler = ApiServerHandler::new(PendingApi);
+        let (ctx, mut rx) = make_request_ctx(
+            Msg::Batch(vec![read_dir_request(), read_dir_request()]),
+            deadline_header(50),
+        );
+
+        handler.on_request(ctx).await;
+
+        let batch = rx.recv().await.unwrap().payload.into_batch().unwrap();
+        assert_eq!(batch.len(), 2);
+        assert!(batch.iter().all(|msg| matches!(
+            msg,
+            protocol::Response::Error(x) if x.kind == protocol::ErrorKind::TimedOut
+        )));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn on_request_passes_deadline_to_api() {
+        let handler = ApiServerHandler::new(DeadlineApi);
+        let request = || protocol::Request::Exists {
+            path: RemotePath::from("/test"),
+        };
+
+        let (ctx, mut rx) = make_request_ctx(Msg::Single(request()), deadline_header(60_000));
+        handler.on_request(ctx).await;
+        let msg = rx.recv().await.unwrap().payload.into_single().unwrap();
+        assert_eq!(msg, protocol::Response::Exists { value: true });
+
+        let (ctx, mut rx) = make_request_ctx(Msg::Single(request()), Header::new());
+        handler.on_request(ctx).await;
+        let msg = rx.recv().await.unwrap().payload.into_single().unwrap();
+        assert_eq!(msg, protocol::Response::Exists { value: false });
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn on_request_ignores_invalid_deadline() {
+        let handler = ApiServerHandler::new(DeadlineApi);
+        let mut header = Header::new();
+        header.insert("deadline", "soon");
+        let (ctx, mut rx) = make_request_ctx(
+            Msg::Single(protocol::Request::Exists {
+                path: RemotePath::from("/test"),
+            }),
+            header,
+        );
+
+        handler.on_request(ctx).await;
+
+        let msg = rx.recv().await.unwrap().payload.into_single().unwrap();
+        assert_eq!(msg, protocol::Response::Exists { value: false });
+    }
+
     #[test_log::test(tokio::test)]
     async fn on_request_empty_batch_returns_empty_batch() {
         let handler = ApiServerHandler::new(MockApi);
@@ -57,6 +57,12 @@ pub trait Authenticator: Send {
     /// Reports that the authentication has finished successfully, consuming the authenticator
     /// since no more challenges should be issued.
     fn finished<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = io::Result<()>> + Send + 'a>>;
+
+    /// Returns the identity of the party being authenticated as already verified by the
+    /// underlying connection (e.g. from a TLS client certificate), if there is one.
+    fn peer_identity(&self) -> Option<&str> {
+        None
+    }
 }
 
 /// Represents an implementator of [`Authenticator`] used purely for testing purposes.
@@ -69,6 +75,7 @@ pub struct TestAuthenticator {
     pub error: Box<dyn FnMut(Error) -> io::Result<()> + Send>,
     pub start_method: Box<dyn FnMut(StartMethod) -> io::Result<()> + Send>,
     pub finished: Box<dyn FnMut() -> io::Result<()> + Send>,
+    pub peer_identity: Option<String>,
 }
 
 #[cfg(any(test, feature = "tests"))]
@@ -90,6 +97,7 @@ impl Default for TestAuthenticator {
             error: Box::new(|_| Ok(())),
             start_method: Box::new(|_| Ok(())),
             finished: Box::new(|| Ok(())),
+            peer_identity: None,
         }
     }
 }
@@ -141,6 +149,10 @@ impl Authenticator for TestAuthenticator {
     fn finished<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = io::Result<()>> + Send + 'a>> {
         Box::pin(async move { (self.finished)() })
     }
+
+    fn peer_identity(&self) -> Option<&str> {
+        self.peer_identity.as_deref()
+    }
 }
 
 #[cfg(test)]
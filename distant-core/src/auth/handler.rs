@@ -1199,6 +1199,7 @@ mod tests {
             error: Box::new(|_| Err(io::Error::other("error failed"))),
             start_method: Box::new(|_| Err(io::Error::other("start failed"))),
             finished: Box::new(|| Err(io::Error::other("finished failed"))),
+            peer_identity: None,
         };
 
         let mut handler = ProxyAuthHandler::new(&mut authenticator);
@@ -9,9 +9,11 @@ use log::*;
 use crate::auth::authenticator::Authenticator;
 use crate::auth::msg::*;
 
+mod certificate;
 mod none;
 mod static_key;
 
+pub use certificate::*;
 pub use none::*;
 pub use static_key::*;
 
@@ -58,6 +60,14 @@ impl Verifier {
         ])
     }
 
+    /// Creates a verifier that uses the [`CertificateAuthenticationMethod`] exclusively,
+    /// accepting any certificate verified by the transport.
+    pub fn certificate() -> Self {
+        Self::new(vec![
+            Box::new(CertificateAuthenticationMethod::new()) as Box<dyn AuthenticationMethod>
+        ])
+    }
+
     /// Returns an iterator over the ids of the methods supported by the verifier
     pub fn methods(&self) -> impl Iterator<Item = &'static str> + '_ {
         self.methods.keys().copied()
@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+
+use log::*;
+
+use crate::auth::authenticator::Authenticator;
+use crate::auth::methods::AuthenticationMethod;
+use crate::auth::msg::Error;
+
+/// Authentication method that accepts any party whose certificate was already verified by the
+/// underlying connection, such as a client certificate presented over mutual TLS.
+///
+/// No challenges are issued. Instead, the identity named by the verified certificate is checked
+/// against an optional set of allowed identities.
+#[derive(Clone, Debug, Default)]
+pub struct CertificateAuthenticationMethod {
+    allowed: Option<HashSet<String>>,
+}
+
+impl CertificateAuthenticationMethod {
+    pub const ID: &str = "certificate";
+
+    /// Creates a method that accepts any verified certificate.
+    #[inline]
+    pub fn new() -> Self {
+        Self { allowed: None }
+    }
+
+    /// Creates a method that only accepts verified certificates naming one of `identities`.
+    pub fn with_allowed_identities<I, S>(identities: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            allowed: Some(identities.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+impl AuthenticationMethod for CertificateAuthenticationMethod {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn authenticate<'a>(
+        &'a self,
+        authenticator: &'a mut dyn Authenticator,
+    ) -> Pin<Box<dyn Future<Output = io::Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            let identity = match authenticator.peer_identity() {
+                Some(identity) => identity.to_string(),
+                None => {
+                    return Err(Error::non_fatal("missing verified certificate")
+                        .into_io_permission_denied());
+                }
+            };
+
+            match &self.allowed {
+                Some(allowed) if !allowed.contains(&identity) => {
+                    Err(Error::non_fatal("certificate identity is not allowed")
+                        .into_io_permission_denied())
+                }
+                _ => {
+                    debug!("Authenticated certificate for {identity}");
+                    Ok(())
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+    use crate::auth::authenticator::TestAuthenticator;
+
+    #[test]
+    fn id_constant_is_certificate() {
+        assert_eq!(CertificateAuthenticationMethod::ID, "certificate");
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_fail_if_no_verified_identity() {
+        let method = CertificateAuthenticationMethod::new();
+        let mut authenticator = TestAuthenticator::default();
+
+        let err = method.authenticate(&mut authenticator).await.unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(err.to_string(), "Error: missing verified certificate");
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_succeed_for_any_identity_if_none_allowed_explicitly() {
+        let method = CertificateAuthenticationMethod::new();
+        let mut authenticator = TestAuthenticator {
+            peer_identity: Some("alice".to_string()),
+            ..Default::default()
+        };
+
+        method.authenticate(&mut authenticator).await.unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_fail_if_identity_is_not_allowed() {
+        let method = CertificateAuthenticationMethod::with_allowed_identities(["bob"]);
+        let mut authenticator = TestAuthenticator {
+            peer_identity: Some("alice".to_string()),
+            ..Default::default()
+        };
+
+        let err = method.authenticate(&mut authenticator).await.unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            err.to_string(),
+            "Error: certificate identity is not allowed"
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_succeed_if_identity_is_allowed() {
+        let method = CertificateAuthenticationMethod::with_allowed_identities(["alice", "bob"]);
+        let mut authenticator = TestAuthenticator {
+            peer_identity: Some("bob".to_string()),
+            ..Default::default()
+        };
+
+        method.authenticate(&mut authenticator).await.unwrap();
+    }
+}
@@ -1,18 +1,19 @@
 use std::future::Future;
 use std::io;
 use std::pin::Pin;
+use std::time::Duration;
 
-use crate::net::client::Channel;
-use crate::net::common::Request;
+use crate::net::client::{Cancelable, Channel};
+use crate::net::common::{Id, Request};
 
 use crate::client::{
     RemoteCommand, RemoteLspCommand, RemoteLspProcess, RemoteOutput, RemoteProcess, RemoteTunnel,
     RemoteTunnelListener, Searcher, Watcher,
 };
 use crate::protocol::{
-    self, ChangeKindSet, DirEntry, Environment, Error as Failure, Metadata, Permissions, PtySize,
-    RemotePath, SearchId, SearchQuery, SetPermissionsOptions, StatusInfo, SystemInfo, TunnelId,
-    Version,
+    self, ChangeKindSet, DirEntry, Environment, Error as Failure, ListeningPort, Metadata,
+    Permissions, PtySize, RemotePath, SearchId, SearchQuery, SetPermissionsOptions, StatusInfo,
+    SystemInfo, TunnelId, Version,
 };
 
 pub type AsyncReturn<'a, T, E = io::Error> =
@@ -31,6 +32,15 @@ pub trait ChannelExt {
         data: impl Into<Vec<u8>>,
     ) -> AsyncReturn<'_, ()>;
 
+    /// Like [`ChannelExt::append_file`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn append_file_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        data: impl Into<Vec<u8>>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Appends to a remote file using the data from a string
     fn append_file_text(
         &mut self,
@@ -38,6 +48,15 @@ pub trait ChannelExt {
         data: impl Into<String>,
     ) -> AsyncReturn<'_, ()>;
 
+    /// Like [`ChannelExt::append_file_text`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn append_file_text_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        data: impl Into<String>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Copies a remote file or directory from src to dst
     fn copy(
         &mut self,
@@ -45,12 +64,38 @@ pub trait ChannelExt {
         dst: impl Into<RemotePath>,
     ) -> AsyncReturn<'_, ()>;
 
+    /// Like [`ChannelExt::copy`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn copy_timeout(
+        &mut self,
+        src: impl Into<RemotePath>,
+        dst: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Creates a remote directory, optionally creating all parent components if specified
     fn create_dir(&mut self, path: impl Into<RemotePath>, all: bool) -> AsyncReturn<'_, ()>;
 
+    /// Like [`ChannelExt::create_dir`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn create_dir_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        all: bool,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Checks whether the `path` exists on the remote machine
     fn exists(&mut self, path: impl Into<RemotePath>) -> AsyncReturn<'_, bool>;
 
+    /// Like [`ChannelExt::exists`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn exists_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, bool>;
+
     /// Checks whether this client is compatible with the remote server
     fn is_compatible(&mut self) -> AsyncReturn<'_, bool>;
 
@@ -62,6 +107,16 @@ pub trait ChannelExt {
         resolve_file_type: bool,
     ) -> AsyncReturn<'_, Metadata>;
 
+    /// Like [`ChannelExt::metadata`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn metadata_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        canonicalize: bool,
+        resolve_file_type: bool,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, Metadata>;
+
     /// Sets permissions for a path on a remote machine
     fn set_permissions(
         &mut self,
@@ -70,6 +125,16 @@ pub trait ChannelExt {
         options: SetPermissionsOptions,
     ) -> AsyncReturn<'_, ()>;
 
+    /// Like [`ChannelExt::set_permissions`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn set_permissions_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        permissions: Permissions,
+        options: SetPermissionsOptions,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Perform a search
     fn search(&mut self, query: impl Into<SearchQuery>) -> AsyncReturn<'_, Searcher>;
 
@@ -86,16 +151,53 @@ pub trait ChannelExt {
         include_root: bool,
     ) -> AsyncReturn<'_, (Vec<DirEntry>, Vec<Failure>)>;
 
+    /// Like [`ChannelExt::read_dir`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn read_dir_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        depth: usize,
+        absolute: bool,
+        canonicalize: bool,
+        include_root: bool,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, (Vec<DirEntry>, Vec<Failure>)>;
+
     /// Reads a remote file as a collection of bytes
     fn read_file(&mut self, path: impl Into<RemotePath>) -> AsyncReturn<'_, Vec<u8>>;
 
+    /// Like [`ChannelExt::read_file`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn read_file_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, Vec<u8>>;
+
     /// Returns a remote file as a string
     fn read_file_text(&mut self, path: impl Into<RemotePath>) -> AsyncReturn<'_, String>;
 
+    /// Like [`ChannelExt::read_file_text`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn read_file_text_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, String>;
+
     /// Removes a remote file or directory, supporting removal of non-empty directories if
     /// force is true
     fn remove(&mut self, path: impl Into<RemotePath>, force: bool) -> AsyncReturn<'_, ()>;
 
+    /// Like [`ChannelExt::remove`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn remove_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        force: bool,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Renames a remote file or directory from src to dst
     fn rename(
         &mut self,
@@ -103,6 +205,15 @@ pub trait ChannelExt {
         dst: impl Into<RemotePath>,
     ) -> AsyncReturn<'_, ()>;
 
+    /// Like [`ChannelExt::rename`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn rename_timeout(
+        &mut self,
+        src: impl Into<RemotePath>,
+        dst: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Watches a remote file or directory
     fn watch(
         &mut self,
@@ -158,6 +269,15 @@ pub trait ChannelExt {
         data: impl Into<Vec<u8>>,
     ) -> AsyncReturn<'_, ()>;
 
+    /// Like [`ChannelExt::write_file`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn write_file_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        data: impl Into<Vec<u8>>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Writes a remote file with the data from a string
     fn write_file_text(
         &mut self,
@@ -165,6 +285,15 @@ pub trait ChannelExt {
         data: impl Into<String>,
     ) -> AsyncReturn<'_, ()>;
 
+    /// Like [`ChannelExt::write_file_text`], but sends `timeout` as the deadline of the
+    /// request, failing with a timed out error once it has passed
+    fn write_file_text_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        data: impl Into<String>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Opens a forward tunnel to the specified host and port
     fn tunnel_open(&mut self, host: impl Into<String>, port: u16) -> AsyncReturn<'_, RemoteTunnel>;
 
@@ -175,16 +304,42 @@ pub trait ChannelExt {
         port: u16,
     ) -> AsyncReturn<'_, RemoteTunnelListener>;
 
+    /// Opens a forward UDP tunnel to the specified host and port
+    fn udp_tunnel_open(
+        &mut self,
+        host: impl Into<String>,
+        port: u16,
+    ) -> AsyncReturn<'_, RemoteTunnel>;
+
+    /// Starts a reverse UDP tunnel listener on the specified host and port
+    fn udp_tunnel_listen(
+        &mut self,
+        host: impl Into<String>,
+        port: u16,
+    ) -> AsyncReturn<'_, RemoteTunnelListener>;
+
+    /// Opens a forward tunnel to the Unix domain socket at the specified path
+    fn unix_tunnel_open(&mut self, path: impl Into<String>) -> AsyncReturn<'_, RemoteTunnel>;
+
+    /// Starts a reverse tunnel listener on a Unix domain socket at the specified path
+    fn unix_tunnel_listen(
+        &mut self,
+        path: impl Into<String>,
+    ) -> AsyncReturn<'_, RemoteTunnelListener>;
+
     /// Closes an active tunnel or listener
     fn tunnel_close(&mut self, id: TunnelId) -> AsyncReturn<'_, ()>;
 
     /// Returns aggregated status information from the server
     fn status(&mut self) -> AsyncReturn<'_, StatusInfo>;
+
+    /// Lists the TCP ports that processes on the remote machine are listening on
+    fn listening_ports(&mut self) -> AsyncReturn<'_, Vec<ListeningPort>>;
 }
 
 macro_rules! make_body {
-    ($self:expr, $data:expr, @ok) => {
-        make_body!($self, $data, |data| {
+    ($self:expr, $timeout:expr, $data:expr, @ok) => {
+        make_body!($self, $timeout, $data, |data| {
             match data {
                 protocol::Response::Ok => Ok(()),
                 protocol::Response::Error(x) => Err(io::Error::from(x)),
@@ -193,11 +348,16 @@ macro_rules! make_body {
         })
     };
 
-    ($self:expr, $data:expr, $and_then:expr) => {{
+    ($self:expr, $data:expr, @ok) => {
+        make_body!($self, None, $data, @ok)
+    };
+
+    ($self:expr, $timeout:expr, $data:expr, $and_then:expr) => {{
         let req = Request::new(protocol::Msg::Single($data));
+        let timeout: Option<Duration> = $timeout.into();
         Box::pin(async move {
             $self
-                .send(req)
+                .send_cancelable_timeout(req, timeout)
                 .await
                 .and_then(|res| match res.payload {
                     protocol::Msg::Single(x) => Ok(x),
@@ -206,6 +366,18 @@ macro_rules! make_body {
                 .and_then($and_then)
         })
     }};
+
+    ($self:expr, $data:expr, $and_then:expr) => {
+        make_body!($self, None, $data, $and_then)
+    };
+}
+
+impl Cancelable for protocol::Msg<protocol::Request> {
+    fn cancel(id: &Id) -> Self {
+        protocol::Msg::Single(protocol::Request::Cancel {
+            request_id: id.clone(),
+        })
+    }
 }
 
 impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<protocol::Response>> {
@@ -213,9 +385,19 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         &mut self,
         path: impl Into<RemotePath>,
         data: impl Into<Vec<u8>>,
+    ) -> AsyncReturn<'_, ()> {
+        self.append_file_timeout(path, data, None)
+    }
+
+    fn append_file_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        data: impl Into<Vec<u8>>,
+        timeout: impl Into<Option<Duration>>,
     ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
+            timeout,
             protocol::Request::FileAppend { path: path.into(), data: data.into() },
             @ok
         )
@@ -225,9 +407,19 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         &mut self,
         path: impl Into<RemotePath>,
         data: impl Into<String>,
+    ) -> AsyncReturn<'_, ()> {
+        self.append_file_text_timeout(path, data, None)
+    }
+
+    fn append_file_text_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        data: impl Into<String>,
+        timeout: impl Into<Option<Duration>>,
     ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
+            timeout,
             protocol::Request::FileAppendText { path: path.into(), text: data.into() },
             @ok
         )
@@ -237,25 +429,54 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         &mut self,
         src: impl Into<RemotePath>,
         dst: impl Into<RemotePath>,
+    ) -> AsyncReturn<'_, ()> {
+        self.copy_timeout(src, dst, None)
+    }
+
+    fn copy_timeout(
+        &mut self,
+        src: impl Into<RemotePath>,
+        dst: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
     ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
+            timeout,
             protocol::Request::Copy { src: src.into(), dst: dst.into() },
             @ok
         )
     }
 
     fn create_dir(&mut self, path: impl Into<RemotePath>, all: bool) -> AsyncReturn<'_, ()> {
+        self.create_dir_timeout(path, all, None)
+    }
+
+    fn create_dir_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        all: bool,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
+            timeout,
             protocol::Request::DirCreate { path: path.into(), all },
             @ok
         )
     }
 
     fn exists(&mut self, path: impl Into<RemotePath>) -> AsyncReturn<'_, bool> {
+        self.exists_timeout(path, None)
+    }
+
+    fn exists_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, bool> {
         make_body!(
             self,
+            timeout,
             protocol::Request::Exists { path: path.into() },
             |data| match data {
                 protocol::Response::Exists { value } => Ok(value),
@@ -279,9 +500,20 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         path: impl Into<RemotePath>,
         canonicalize: bool,
         resolve_file_type: bool,
+    ) -> AsyncReturn<'_, Metadata> {
+        self.metadata_timeout(path, canonicalize, resolve_file_type, None)
+    }
+
+    fn metadata_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        canonicalize: bool,
+        resolve_file_type: bool,
+        timeout: impl Into<Option<Duration>>,
     ) -> AsyncReturn<'_, Metadata> {
         make_body!(
             self,
+            timeout,
             protocol::Request::Metadata {
                 path: path.into(),
                 canonicalize,
@@ -300,9 +532,20 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         path: impl Into<RemotePath>,
         permissions: Permissions,
         options: SetPermissionsOptions,
+    ) -> AsyncReturn<'_, ()> {
+        self.set_permissions_timeout(path, permissions, options, None)
+    }
+
+    fn set_permissions_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        permissions: Permissions,
+        options: SetPermissionsOptions,
+        timeout: impl Into<Option<Duration>>,
     ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
+            timeout,
             protocol::Request::SetPermissions {
                 path: path.into(),
                 permissions,
@@ -332,9 +575,22 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         absolute: bool,
         canonicalize: bool,
         include_root: bool,
+    ) -> AsyncReturn<'_, (Vec<DirEntry>, Vec<Failure>)> {
+        self.read_dir_timeout(path, depth, absolute, canonicalize, include_root, None)
+    }
+
+    fn read_dir_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        depth: usize,
+        absolute: bool,
+        canonicalize: bool,
+        include_root: bool,
+        timeout: impl Into<Option<Duration>>,
     ) -> AsyncReturn<'_, (Vec<DirEntry>, Vec<Failure>)> {
         make_body!(
             self,
+            timeout,
             protocol::Request::DirRead {
                 path: path.into(),
                 depth,
@@ -351,8 +607,17 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
     }
 
     fn read_file(&mut self, path: impl Into<RemotePath>) -> AsyncReturn<'_, Vec<u8>> {
+        self.read_file_timeout(path, None)
+    }
+
+    fn read_file_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, Vec<u8>> {
         make_body!(
             self,
+            timeout,
             protocol::Request::FileRead { path: path.into() },
             |data| match data {
                 protocol::Response::Blob { data } => Ok(data),
@@ -363,8 +628,17 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
     }
 
     fn read_file_text(&mut self, path: impl Into<RemotePath>) -> AsyncReturn<'_, String> {
+        self.read_file_text_timeout(path, None)
+    }
+
+    fn read_file_text_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, String> {
         make_body!(
             self,
+            timeout,
             protocol::Request::FileReadText { path: path.into() },
             |data| match data {
                 protocol::Response::Text { data } => Ok(data),
@@ -375,8 +649,18 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
     }
 
     fn remove(&mut self, path: impl Into<RemotePath>, force: bool) -> AsyncReturn<'_, ()> {
+        self.remove_timeout(path, force, None)
+    }
+
+    fn remove_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        force: bool,
+        timeout: impl Into<Option<Duration>>,
+    ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
+            timeout,
             protocol::Request::Remove { path: path.into(), force },
             @ok
         )
@@ -386,9 +670,19 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         &mut self,
         src: impl Into<RemotePath>,
         dst: impl Into<RemotePath>,
+    ) -> AsyncReturn<'_, ()> {
+        self.rename_timeout(src, dst, None)
+    }
+
+    fn rename_timeout(
+        &mut self,
+        src: impl Into<RemotePath>,
+        dst: impl Into<RemotePath>,
+        timeout: impl Into<Option<Duration>>,
     ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
+            timeout,
             protocol::Request::Rename { src: src.into(), dst: dst.into() },
             @ok
         )
@@ -507,9 +801,19 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         &mut self,
         path: impl Into<RemotePath>,
         data: impl Into<Vec<u8>>,
+    ) -> AsyncReturn<'_, ()> {
+        self.write_file_timeout(path, data, None)
+    }
+
+    fn write_file_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        data: impl Into<Vec<u8>>,
+        timeout: impl Into<Option<Duration>>,
     ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
+            timeout,
             protocol::Request::FileWrite { path: path.into(), data: data.into() },
             @ok
         )
@@ -519,9 +823,19 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         &mut self,
         path: impl Into<RemotePath>,
         data: impl Into<String>,
+    ) -> AsyncReturn<'_, ()> {
+        self.write_file_text_timeout(path, data, None)
+    }
+
+    fn write_file_text_timeout(
+        &mut self,
+        path: impl Into<RemotePath>,
+        data: impl Into<String>,
+        timeout: impl Into<Option<Duration>>,
     ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
+            timeout,
             protocol::Request::FileWriteText { path: path.into(), text: data.into() },
             @ok
         )
@@ -541,6 +855,37 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
         Box::pin(async move { RemoteTunnelListener::listen(self.clone(), host, port).await })
     }
 
+    fn udp_tunnel_open(
+        &mut self,
+        host: impl Into<String>,
+        port: u16,
+    ) -> AsyncReturn<'_, RemoteTunnel> {
+        let host = host.into();
+        Box::pin(async move { RemoteTunnel::open_udp(self.clone(), host, port).await })
+    }
+
+    fn udp_tunnel_listen(
+        &mut self,
+        host: impl Into<String>,
+        port: u16,
+    ) -> AsyncReturn<'_, RemoteTunnelListener> {
+        let host = host.into();
+        Box::pin(async move { RemoteTunnelListener::listen_udp(self.clone(), host, port).await })
+    }
+
+    fn unix_tunnel_open(&mut self, path: impl Into<String>) -> AsyncReturn<'_, RemoteTunnel> {
+        let path = path.into();
+        Box::pin(async move { RemoteTunnel::open_unix(self.clone(), path).await })
+    }
+
+    fn unix_tunnel_listen(
+        &mut self,
+        path: impl Into<String>,
+    ) -> AsyncReturn<'_, RemoteTunnelListener> {
+        let path = path.into();
+        Box::pin(async move { RemoteTunnelListener::listen_unix(self.clone(), path).await })
+    }
+
     fn tunnel_close(&mut self, id: TunnelId) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
@@ -556,6 +901,18 @@ impl ChannelExt for Channel<protocol::Msg<protocol::Request>, protocol::Msg<prot
             _ => Err(mismatched_response()),
         })
     }
+
+    fn listening_ports(&mut self) -> AsyncReturn<'_, Vec<ListeningPort>> {
+        make_body!(
+            self,
+            protocol::Request::ListeningPorts {},
+            |data| match data {
+                protocol::Response::ListeningPorts { ports } => Ok(ports),
+                protocol::Response::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
 }
 
 #[cfg(test)]
@@ -1104,6 +1461,44 @@ mod tests {
         assert_eq!(result, [10, 20, 30]);
     }
 
+    #[test(tokio::test)]
+    async fn read_file_timeout_should_send_deadline_and_cancel_request_once_timed_out() {
+        let (mut transport, session) = make_session();
+        let mut channel = session.clone_channel();
+
+        let task = tokio::spawn(async move {
+            channel
+                .read_file_timeout("/test/file", Duration::from_millis(50))
+                .await
+        });
+
+        let req: Request<protocol::Request> = transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(
+            req.header.deadline().unwrap().unwrap(),
+            Duration::from_millis(50)
+        );
+
+        let err = task.await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        let cancel: Request<protocol::Request> = transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(
+            cancel.payload,
+            protocol::Request::Cancel { request_id: req.id }
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn read_file_should_not_send_deadline() {
+        let (mut transport, session) = make_session();
+        let mut channel = session.clone_channel();
+
+        let _task = tokio::spawn(async move { channel.read_file("/test/file").await });
+
+        let req: Request<protocol::Request> = transport.read_frame_as().await.unwrap().unwrap();
+        assert!(req.header.deadline().is_none());
+    }
+
     #[test(tokio::test)]
     async fn read_file_should_return_error_on_error_response() {
         let (mut transport, session) = make_session();
@@ -1524,7 +1919,7 @@ mod tests {
 
     #[test(tokio::test)]
     async fn status_should_return_status_info_on_success() {
-        use crate::protocol::{TunnelDirection, TunnelInfo};
+        use crate::protocol::{TunnelDirection, TunnelInfo, TunnelProtocol, TunnelStats};
 
         let (mut transport, session) = make_session();
         let mut channel = session.clone_channel();
@@ -1543,6 +1938,8 @@ mod tests {
                 direction: TunnelDirection::Forward,
                 host: String::from("localhost"),
                 port: 8080,
+                protocol: TunnelProtocol::Tcp,
+                stats: TunnelStats::default(),
             }],
         };
 
@@ -1597,4 +1994,59 @@ mod tests {
         let err = task.await.unwrap().unwrap_err();
         assert_eq!(err.to_string(), "Mismatched response");
     }
+
+    #[test(tokio::test)]
+    async fn listening_ports_should_return_ports_on_success() {
+        let (mut transport, session) = make_session();
+        let mut channel = session.clone_channel();
+
+        let task = tokio::spawn(async move { channel.listening_ports().await });
+
+        let req: Request<protocol::Request> = transport.read_frame_as().await.unwrap().unwrap();
+        match req.payload {
+            protocol::Request::ListeningPorts {} => {}
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        let expected = vec![ListeningPort {
+            address: String::from("0.0.0.0"),
+            port: 3000,
+        }];
+
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                protocol::Response::ListeningPorts {
+                    ports: expected.clone(),
+                },
+            ))
+            .await
+            .unwrap();
+
+        let result = task.await.unwrap().unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test(tokio::test)]
+    async fn listening_ports_should_return_error_on_error_response() {
+        let (mut transport, session) = make_session();
+        let mut channel = session.clone_channel();
+
+        let task = tokio::spawn(async move { channel.listening_ports().await });
+
+        let req: Request<protocol::Request> = transport.read_frame_as().await.unwrap().unwrap();
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                protocol::Response::Error(protocol::Error {
+                    kind: protocol::ErrorKind::Unsupported,
+                    description: String::from("listening_ports is unsupported"),
+                }),
+            ))
+            .await
+            .unwrap();
+
+        let err = task.await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
 }
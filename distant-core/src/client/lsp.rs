@@ -9,7 +9,7 @@ use tokio::task::JoinHandle;
 use crate::client::{
     Channel, RemoteCommand, RemoteProcess, RemoteStatus, RemoteStderr, RemoteStdin, RemoteStdout,
 };
-use crate::protocol::{Environment, PtySize, RemotePath};
+use crate::protocol::{Environment, ProcSpawnOptions, ProcessLimits, PtySize, RemotePath};
 
 mod msg;
 pub use msg::*;
@@ -21,6 +21,7 @@ pub struct RemoteLspCommand {
     environment: Environment,
     current_dir: Option<RemotePath>,
     scheme: Option<String>,
+    options: ProcSpawnOptions,
 }
 
 impl Default for RemoteLspCommand {
@@ -37,6 +38,7 @@ impl RemoteLspCommand {
             environment: Environment::new(),
             current_dir: None,
             scheme: None,
+            options: ProcSpawnOptions::default(),
         }
     }
 
@@ -64,6 +66,19 @@ impl RemoteLspCommand {
         self
     }
 
+    /// Configures the resource limits (timeout, memory, cpu, etc.) to apply to the process
+    pub fn limits(&mut self, limits: ProcessLimits) -> &mut Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Replaces all of the additional options used to spawn the process, such as resource limits
+    /// and environment controls
+    pub fn options(&mut self, options: ProcSpawnOptions) -> &mut Self {
+        self.options = options;
+        self
+    }
+
     /// Spawns the specified process on the remote machine using the given session, treating
     /// the process like an LSP server
     pub async fn spawn(
@@ -75,6 +90,7 @@ impl RemoteLspCommand {
         command.environment(self.environment.clone());
         command.current_dir(self.current_dir.clone());
         command.pty(self.pty);
+        command.options(self.options.clone());
 
         let mut inner = command.spawn(channel, cmd).await?;
         let stdin = inner
@@ -110,7 +126,10 @@ pub struct RemoteLspProcess {
 
 impl RemoteLspProcess {
     /// Waits for the process to terminate, returning the success status and an optional exit code
+    ///
+    /// The stdin handle of the process, if still attached, is dropped before waiting
     pub async fn wait(self) -> io::Result<RemoteStatus> {
+        drop(self.stdin);
         self.inner.wait().await
     }
 }
@@ -713,6 +732,7 @@ mod tests {
                         "field1": "a",
                         "field2": "b",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -746,6 +766,7 @@ mod tests {
                 protocol::Response::ProcStdout {
                     id: proc.id(),
                     data: msg_a.to_vec(),
+                    stamp: None,
                 },
             ))
             .await
@@ -764,6 +785,7 @@ mod tests {
                 protocol::Response::ProcStdout {
                     id: proc.id(),
                     data: msg_b.to_vec(),
+                    stamp: None,
                 },
             ))
             .await
@@ -798,6 +820,7 @@ mod tests {
                 protocol::Response::ProcStdout {
                     id: proc.id(),
                     data: format!("{}{}", String::from_utf8(msg).unwrap(), extra).into_bytes(),
+                    stamp: None,
                 },
             ))
             .await
@@ -846,6 +869,7 @@ mod tests {
                         String::from_utf8(msg_2).unwrap()
                     )
                     .into_bytes(),
+                    stamp: None,
                 },
             ))
             .await
@@ -886,6 +910,7 @@ mod tests {
                         "field1": "distant://some/path",
                         "field2": "file://other/path",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -916,6 +941,7 @@ mod tests {
                         "field1": "a",
                         "field2": "b",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -949,6 +975,7 @@ mod tests {
                 protocol::Response::ProcStderr {
                     id: proc.id(),
                     data: msg_a.to_vec(),
+                    stamp: None,
                 },
             ))
             .await
@@ -967,6 +994,7 @@ mod tests {
                 protocol::Response::ProcStderr {
                     id: proc.id(),
                     data: msg_b.to_vec(),
+                    stamp: None,
                 },
             ))
             .await
@@ -1001,6 +1029,7 @@ mod tests {
                 protocol::Response::ProcStderr {
                     id: proc.id(),
                     data: format!("{}{}", String::from_utf8(msg).unwrap(), extra).into_bytes(),
+                    stamp: None,
                 },
             ))
             .await
@@ -1049,6 +1078,7 @@ mod tests {
                         String::from_utf8(msg_2).unwrap()
                     )
                     .into_bytes(),
+                    stamp: None,
                 },
             ))
             .await
@@ -1089,6 +1119,7 @@ mod tests {
                         "field1": "distant://some/path",
                         "field2": "file://other/path",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -1427,6 +1458,7 @@ mod tests {
                         "field1": "file://some/path",
                         "field2": "custom://other/path",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -1455,6 +1487,7 @@ mod tests {
                         "field1": "file://some/path",
                         "field2": "custom://other/path",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -1494,6 +1527,7 @@ mod tests {
                     data: make_lsp_msg(serde_json::json!({
                         "field1": "a",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -1527,6 +1561,7 @@ mod tests {
                         "field1": "a",
                         "field2": "b",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -1567,6 +1602,7 @@ mod tests {
                     data: make_lsp_msg(serde_json::json!({
                         "field1": "a",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -1600,6 +1636,7 @@ mod tests {
                         "field1": "a",
                         "field2": "b",
                     })),
+                    stamp: None,
                 },
             ))
             .await
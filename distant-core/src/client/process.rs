@@ -10,7 +10,10 @@ use tokio::task::{AbortHandle, JoinHandle};
 
 use crate::client::Channel;
 use crate::constants::CLIENT_PIPE_CAPACITY;
-use crate::protocol::{self, Cmd, Environment, ProcessId, PtySize, RemotePath};
+use crate::protocol::{
+    self, Cmd, Environment, ProcOutputStamp, ProcSpawnOptions, ProcessId, ProcessLimits, PtySize,
+    RemotePath,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RemoteOutput {
@@ -18,8 +21,47 @@ pub struct RemoteOutput {
     pub code: Option<i32>,
     pub stdout: Vec<u8>,
     pub stderr: Vec<u8>,
+
+    /// Stamped chunks of both stdout and stderr ordered by their sequence number, which is only
+    /// populated if the process was spawned with timestamps
+    pub chunks: Vec<RemoteOutputChunk>,
+}
+
+impl RemoteOutput {
+    /// Returns stdout and stderr combined in the order that the server read them, or `None` if
+    /// the process was not spawned with timestamps
+    pub fn interleaved(&self) -> Option<Vec<u8>> {
+        if self.chunks.is_empty() && !(self.stdout.is_empty() && self.stderr.is_empty()) {
+            return None;
+        }
+
+        Some(
+            self.chunks
+                .iter()
+                .flat_map(|chunk| chunk.data.iter().copied())
+                .collect(),
+        )
+    }
+}
+
+/// Stream that a chunk of output was read from
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RemoteOutputStream {
+    Stdout,
+    Stderr,
 }
 
+/// Chunk of output from a remote process along with the stamp provided by the server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteOutputChunk {
+    pub stream: RemoteOutputStream,
+    pub data: Vec<u8>,
+    pub stamp: ProcOutputStamp,
+}
+
+/// Data read from either stdout or stderr along with its stamp (if any)
+type StampedData = (Vec<u8>, Option<ProcOutputStamp>);
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct RemoteStatus {
     pub success: bool,
@@ -40,6 +82,7 @@ pub struct RemoteCommand {
     pty: Option<PtySize>,
     environment: Environment,
     current_dir: Option<RemotePath>,
+    options: ProcSpawnOptions,
 }
 
 impl Default for RemoteCommand {
@@ -55,6 +98,7 @@ impl RemoteCommand {
             pty: None,
             environment: Environment::new(),
             current_dir: None,
+            options: ProcSpawnOptions::default(),
         }
     }
 
@@ -76,6 +120,53 @@ impl RemoteCommand {
         self
     }
 
+    /// Configures the resource limits (timeout, memory, cpu, etc.) to apply to the process
+    pub fn limits(&mut self, limits: ProcessLimits) -> &mut Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Configures whether the process starts with an empty environment rather than inheriting
+    /// the environment of the server
+    pub fn clear_env(&mut self, clear_env: bool) -> &mut Self {
+        self.options.clear_env = clear_env;
+        self
+    }
+
+    /// Configures the names of variables to remove from the environment inherited from the server
+    pub fn unset_env(&mut self, unset_env: Vec<String>) -> &mut Self {
+        self.options.unset_env = unset_env;
+        self
+    }
+
+    /// Configures a file on the remote machine to load environment variables from, which are
+    /// overridden by those provided through [`RemoteCommand::environment`]
+    pub fn env_file(&mut self, env_file: Option<RemotePath>) -> &mut Self {
+        self.options.env_file = env_file;
+        self
+    }
+
+    /// Configures whether the command is run through the user's login shell, which loads their
+    /// profile (and any changes it makes to `PATH`) before running the command
+    pub fn login(&mut self, login: bool) -> &mut Self {
+        self.options.login = login;
+        self
+    }
+
+    /// Replaces all of the additional options used to spawn the process
+    pub fn options(&mut self, options: ProcSpawnOptions) -> &mut Self {
+        self.options = options;
+        self
+    }
+
+    /// Configures whether stdout and stderr chunks are stamped by the server with a sequence
+    /// number and timestamp, which allows [`RemoteOutput::interleaved`] to reconstruct the
+    /// order of the output across both streams
+    pub fn timestamps(&mut self, timestamps: bool) -> &mut Self {
+        self.options.timestamps = timestamps;
+        self
+    }
+
     /// Spawns the specified process on the remote machine using the given `channel` and `cmd`
     pub async fn spawn(
         &mut self,
@@ -92,6 +183,7 @@ impl RemoteCommand {
                     pty: self.pty,
                     environment: self.environment.clone(),
                     current_dir: self.current_dir.clone(),
+                    options: self.options.clone(),
                 },
             )))
             .await?;
@@ -236,7 +328,12 @@ impl RemoteProcess {
     }
 
     /// Waits for the process to terminate, returning the success status and an optional exit code
-    pub async fn wait(self) -> io::Result<RemoteStatus> {
+    ///
+    /// The stdin handle of the process, if still attached, is dropped before waiting so that
+    /// processes reading until end of input are able to complete
+    pub async fn wait(mut self) -> io::Result<RemoteStatus> {
+        drop(self.stdin.take());
+
         // Wait for the process to complete before we try to get the status
         let _ = self.wait_task.await;
 
@@ -250,31 +347,52 @@ impl RemoteProcess {
 
     /// Waits for the process to terminate, returning the success status, an optional exit code,
     /// and any remaining stdout and stderr (if still attached to the process)
+    ///
+    /// If the process was spawned with timestamps, the stamped chunks of stdout and stderr are
+    /// also collected in order, which can be combined using [`RemoteOutput::interleaved`]
     pub async fn output(mut self) -> io::Result<RemoteOutput> {
         let maybe_stdout = self.stdout.take();
         let maybe_stderr = self.stderr.take();
 
         let status = self.wait().await?;
 
+        let mut chunks = Vec::new();
         let mut stdout = Vec::new();
         if let Some(mut reader) = maybe_stdout {
-            while let Ok(data) = reader.read().await {
+            while let Ok((data, stamp)) = reader.read_stamped().await {
                 stdout.extend(&data);
+                if let Some(stamp) = stamp {
+                    chunks.push(RemoteOutputChunk {
+                        stream: RemoteOutputStream::Stdout,
+                        data,
+                        stamp,
+                    });
+                }
             }
         }
 
         let mut stderr = Vec::new();
         if let Some(mut reader) = maybe_stderr {
-            while let Ok(data) = reader.read().await {
+            while let Ok((data, stamp)) = reader.read_stamped().await {
                 stderr.extend(&data);
+                if let Some(stamp) = stamp {
+                    chunks.push(RemoteOutputChunk {
+                        stream: RemoteOutputStream::Stderr,
+                        data,
+                        stamp,
+                    });
+                }
             }
         }
 
+        chunks.sort_by_key(|chunk| chunk.stamp.seq);
+
         Ok(RemoteOutput {
             success: status.success,
             code: status.code,
             stdout,
             stderr,
+            chunks,
         })
     }
 
@@ -335,6 +453,9 @@ impl RemoteProcessKiller {
 }
 
 /// A handle to a remote process' standard input (stdin)
+///
+/// Once this handle and all of its clones are dropped, the remote process' stdin is closed,
+/// meaning that the process will receive end of input (EOF)
 #[derive(Clone, Debug)]
 pub struct RemoteStdin(mpsc::Sender<Vec<u8>>);
 
@@ -381,14 +502,14 @@ impl RemoteStdin {
 
 /// A handle to a remote process' standard output (stdout)
 #[derive(Debug)]
-pub struct RemoteStdout(mpsc::Receiver<Vec<u8>>);
+pub struct RemoteStdout(mpsc::Receiver<StampedData>);
 
 impl RemoteStdout {
     /// Tries to receive latest stdout for a remote process, yielding `None`
     /// if no stdout is available, and `BrokenPipe` if stdout has been closed
     pub fn try_read(&mut self) -> io::Result<Option<Vec<u8>>> {
         match self.0.try_recv() {
-            Ok(data) => Ok(Some(data)),
+            Ok((data, _)) => Ok(Some(data)),
             Err(TryRecvError::Empty) => Ok(None),
             Err(TryRecvError::Disconnected) => Err(io::Error::from(io::ErrorKind::BrokenPipe)),
         }
@@ -407,6 +528,12 @@ impl RemoteStdout {
     /// Retrieves the latest stdout for a specific remote process, and `BrokenPipe` if stdout has
     /// been closed
     pub async fn read(&mut self) -> io::Result<Vec<u8>> {
+        self.read_stamped().await.map(|(data, _)| data)
+    }
+
+    /// Same as `read`, but also returns the stamp of the stdout if the process was spawned with
+    /// timestamps
+    pub async fn read_stamped(&mut self) -> io::Result<(Vec<u8>, Option<ProcOutputStamp>)> {
         self.0
             .recv()
             .await
@@ -423,14 +550,14 @@ impl RemoteStdout {
 
 /// A handle to a remote process' stderr
 #[derive(Debug)]
-pub struct RemoteStderr(mpsc::Receiver<Vec<u8>>);
+pub struct RemoteStderr(mpsc::Receiver<StampedData>);
 
 impl RemoteStderr {
     /// Tries to receive latest stderr for a remote process, yielding `None`
     /// if no stderr is available, and `BrokenPipe` if stderr has been closed
     pub fn try_read(&mut self) -> io::Result<Option<Vec<u8>>> {
         match self.0.try_recv() {
-            Ok(data) => Ok(Some(data)),
+            Ok((data, _)) => Ok(Some(data)),
             Err(TryRecvError::Empty) => Ok(None),
             Err(TryRecvError::Disconnected) => Err(io::Error::from(io::ErrorKind::BrokenPipe)),
         }
@@ -449,6 +576,12 @@ impl RemoteStderr {
     /// Retrieves the latest stderr for a specific remote process, and `BrokenPipe` if stderr has
     /// been closed
     pub async fn read(&mut self) -> io::Result<Vec<u8>> {
+        self.read_stamped().await.map(|(data, _)| data)
+    }
+
+    /// Same as `read`, but also returns the stamp of the stderr if the process was spawned with
+    /// timestamps
+    pub async fn read_stamped(&mut self) -> io::Result<(Vec<u8>, Option<ProcOutputStamp>)> {
         self.0
             .recv()
             .await
@@ -465,6 +598,9 @@ impl RemoteStderr {
 
 /// Helper function that loops, processing outgoing stdin requests to a remote process as well as
 /// supporting a kill request to terminate the remote process
+///
+/// When all stdin handles have been dropped, a request is sent to close the stdin of the remote
+/// process while resize and kill requests continue to be processed
 async fn process_outgoing_requests(
     id: ProcessId,
     mut channel: Channel,
@@ -472,16 +608,23 @@ async fn process_outgoing_requests(
     mut resize_rx: mpsc::Receiver<PtySize>,
     mut kill_rx: mpsc::Receiver<()>,
 ) -> io::Result<()> {
+    let mut stdin_open = true;
     let result = loop {
         tokio::select! {
-            data = stdin_rx.recv() => {
+            data = stdin_rx.recv(), if stdin_open => {
                 match data {
                     Some(data) => channel.fire(
                         Request::new(
                             protocol::Msg::Single(protocol::Request::ProcStdin { id, data })
                         )
                     ).await?,
-                    None => break Err(errors::dead_channel()),
+                    None => {
+                        trace!("Process {id} stdin dropped, closing remote stdin");
+                        stdin_open = false;
+                        channel.fire(Request::new(
+                            protocol::Msg::Single(protocol::Request::ProcStdinClose { id })
+                        )).await?;
+                    }
                 }
             }
             size = resize_rx.recv() => {
@@ -515,8 +658,8 @@ async fn process_outgoing_requests(
 async fn process_incoming_responses(
     proc_id: ProcessId,
     mut mailbox: Mailbox<Response<protocol::Msg<protocol::Response>>>,
-    stdout_tx: mpsc::Sender<Vec<u8>>,
-    stderr_tx: mpsc::Sender<Vec<u8>>,
+    stdout_tx: mpsc::Sender<StampedData>,
+    stderr_tx: mpsc::Sender<StampedData>,
     kill_tx: mpsc::Sender<()>,
 ) -> io::Result<(bool, Option<i32>)> {
     while let Some(res) = mailbox.next().await {
@@ -534,11 +677,11 @@ async fn process_incoming_responses(
         // TODO: What should we do about unexpected data? For now, just ignore
         for data in payload {
             match data {
-                protocol::Response::ProcStdout { id, data } if id == proc_id => {
-                    let _ = stdout_tx.send(data).await;
+                protocol::Response::ProcStdout { id, data, stamp } if id == proc_id => {
+                    let _ = stdout_tx.send((data, stamp)).await;
                 }
-                protocol::Response::ProcStderr { id, data } if id == proc_id => {
-                    let _ = stderr_tx.send(data).await;
+                protocol::Response::ProcStderr { id, data, stamp } if id == proc_id => {
+                    let _ = stderr_tx.send((data, stamp)).await;
                 }
                 _ => {}
             }
@@ -655,6 +798,87 @@ mod tests {
         }
     }
 
+    #[test(tokio::test)]
+    async fn spawn_should_include_limits_in_request() {
+        let (mut transport, session) = make_session();
+
+        let limits = ProcessLimits {
+            timeout_ms: Some(5000),
+            max_memory: Some(1024 * 1024),
+            ..Default::default()
+        };
+
+        // Create a task for process spawning as we need to handle the request and a response
+        // in a separate async block
+        let spawn_task = tokio::spawn({
+            let limits = limits.clone();
+            async move {
+                RemoteCommand::new()
+                    .limits(limits)
+                    .spawn(session.clone_channel(), String::from("cmd arg"))
+                    .await
+            }
+        });
+
+        // Wait until we get the request from the session and verify the limits were sent
+        let req: Request<protocol::Msg<protocol::Request>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match &req.payload {
+            protocol::Msg::Single(protocol::Request::ProcSpawn { options, .. }) => {
+                assert_eq!(options.limits, limits);
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                protocol::Msg::Single(protocol::Response::ProcSpawned { id: 1 }),
+            ))
+            .await
+            .unwrap();
+
+        spawn_task.await.unwrap().unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn spawn_should_include_env_controls_in_request() {
+        let (mut transport, session) = make_session();
+
+        // Create a task for process spawning as we need to handle the request and a response
+        // in a separate async block
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .clear_env(true)
+                .unset_env(vec![String::from("SECRET")])
+                .env_file(Some(RemotePath::new(".env")))
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        // Wait until we get the request from the session and verify the env controls were sent
+        let req: Request<protocol::Msg<protocol::Request>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match &req.payload {
+            protocol::Msg::Single(protocol::Request::ProcSpawn { options, .. }) => {
+                assert!(options.clear_env);
+                assert_eq!(options.unset_env, vec![String::from("SECRET")]);
+                assert_eq!(options.env_file, Some(RemotePath::new(".env")));
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                protocol::Msg::Single(protocol::Response::ProcSpawned { id: 1 }),
+            ))
+            .await
+            .unwrap();
+
+        spawn_task.await.unwrap().unwrap();
+    }
+
     #[test(tokio::test)]
     async fn kill_should_return_error_if_internal_tasks_already_completed() {
         let (mut transport, session) = make_session();
@@ -794,6 +1018,120 @@ mod tests {
         }
     }
 
+    #[test(tokio::test)]
+    async fn dropping_stdin_should_send_proc_stdin_close_request() {
+        let (mut transport, session) = make_session();
+
+        // Create a task for process spawning as we need to handle the request and a response
+        // in a separate async block
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        // Wait until we get the request from the session
+        let req: Request<protocol::Msg<protocol::Request>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+
+        // Send back a response through the session
+        let id = 12345;
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                protocol::Msg::Single(protocol::Response::ProcSpawned { id }),
+            ))
+            .await
+            .unwrap();
+
+        // Receive the process and then drop its stdin
+        let mut proc = spawn_task.await.unwrap().unwrap();
+        drop(proc.stdin.take());
+
+        // Verify that a request to close stdin is made through the session
+        let req: Request<protocol::Msg<protocol::Request>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match req.payload {
+            protocol::Msg::Single(protocol::Request::ProcStdinClose { id }) => {
+                assert_eq!(id, 12345);
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        // Verify that the process can still be killed after stdin is closed
+        assert!(proc.kill().await.is_ok(), "Failed to send kill request");
+        let req: Request<protocol::Msg<protocol::Request>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match req.payload {
+            protocol::Msg::Single(protocol::Request::ProcKill { id }) => {
+                assert_eq!(id, 12345);
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn wait_should_close_stdin_of_process() {
+        let (mut transport, session) = make_session();
+
+        // Create a task for process spawning as we need to handle the request and a response
+        // in a separate async block
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        // Wait until we get the request from the session
+        let req: Request<protocol::Msg<protocol::Request>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+
+        // Send back a response through the session
+        let id = 12345;
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                protocol::Msg::Single(protocol::Response::ProcSpawned { id }),
+            ))
+            .await
+            .unwrap();
+
+        // Receive the process and then spawn a task for it to complete
+        let proc = spawn_task.await.unwrap().unwrap();
+        let proc_wait_task = tokio::spawn(proc.wait());
+
+        // Verify that a request to close stdin is made through the session
+        let stdin_req: Request<protocol::Msg<protocol::Request>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match stdin_req.payload {
+            protocol::Msg::Single(protocol::Request::ProcStdinClose { id }) => {
+                assert_eq!(id, 12345);
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        // Send a process completion response to conclude wait
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                protocol::Msg::Single(protocol::Response::ProcDone {
+                    id,
+                    success: true,
+                    code: Some(0),
+                }),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            proc_wait_task.await.unwrap().unwrap(),
+            RemoteStatus {
+                success: true,
+                code: Some(0)
+            }
+        );
+    }
+
     #[test(tokio::test)]
     async fn stdout_should_be_forwarded_to_receiver_field() {
         let (mut transport, session) = make_session();
@@ -829,6 +1167,7 @@ mod tests {
                 protocol::Msg::Single(protocol::Response::ProcStdout {
                     id,
                     data: b"some out".to_vec(),
+                    stamp: None,
                 }),
             ))
             .await
@@ -873,6 +1212,7 @@ mod tests {
                 protocol::Msg::Single(protocol::Response::ProcStderr {
                     id,
                     data: b"some err".to_vec(),
+                    stamp: None,
                 }),
             ))
             .await
@@ -1186,6 +1526,7 @@ mod tests {
                 protocol::Msg::Single(protocol::Response::ProcStdout {
                     id,
                     data: b"some out".to_vec(),
+                    stamp: None,
                 }),
             ))
             .await
@@ -1198,6 +1539,7 @@ mod tests {
                 protocol::Msg::Single(protocol::Response::ProcStderr {
                     id,
                     data: b"some err".to_vec(),
+                    stamp: None,
                 }),
             ))
             .await
@@ -1224,7 +1566,123 @@ mod tests {
                 code: Some(123),
                 stdout: b"some out".to_vec(),
                 stderr: b"some err".to_vec(),
+                chunks: Vec::new(),
             }
         );
     }
+
+    #[test(tokio::test)]
+    async fn output_should_order_stamped_chunks_by_sequence_when_timestamps_requested() {
+        let (mut transport, session) = make_session();
+
+        // Create a task for process spawning as we need to handle the request and a response
+        // in a separate async block
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .timestamps(true)
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        // Wait until we get the request from the session and verify timestamps were requested
+        let req: Request<protocol::Msg<protocol::Request>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match &req.payload {
+            protocol::Msg::Single(protocol::Request::ProcSpawn { options, .. }) => {
+                assert!(options.timestamps);
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        let id = 12345;
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                protocol::Msg::Single(protocol::Response::ProcSpawned { id }),
+            ))
+            .await
+            .unwrap();
+
+        let proc = spawn_task.await.unwrap().unwrap();
+        let proc_output_task = tokio::spawn(proc.output());
+
+        // Send stdout and stderr alternating between the two streams
+        let stamp = |seq| ProcOutputStamp {
+            seq,
+            timestamp_us: seq * 10,
+        };
+        for (seq, is_stdout, data) in [(0, true, "a"), (1, false, "b"), (2, true, "c")] {
+            let data = data.as_bytes().to_vec();
+            let payload = if is_stdout {
+                protocol::Response::ProcStdout {
+                    id,
+                    data,
+                    stamp: Some(stamp(seq)),
+                }
+            } else {
+                protocol::Response::ProcStderr {
+                    id,
+                    data,
+                    stamp: Some(stamp(seq)),
+                }
+            };
+
+            transport
+                .write_frame_for(&Response::new(
+                    req.id.clone(),
+                    protocol::Msg::Single(payload),
+                ))
+                .await
+                .unwrap();
+        }
+
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                protocol::Msg::Single(protocol::Response::ProcDone {
+                    id,
+                    success: true,
+                    code: Some(0),
+                }),
+            ))
+            .await
+            .unwrap();
+
+        let output = proc_output_task.await.unwrap().unwrap();
+        assert_eq!(output.stdout, b"ac");
+        assert_eq!(output.stderr, b"b");
+        assert_eq!(
+            output.chunks,
+            vec![
+                RemoteOutputChunk {
+                    stream: RemoteOutputStream::Stdout,
+                    data: b"a".to_vec(),
+                    stamp: stamp(0),
+                },
+                RemoteOutputChunk {
+                    stream: RemoteOutputStream::Stderr,
+                    data: b"b".to_vec(),
+                    stamp: stamp(1),
+                },
+                RemoteOutputChunk {
+                    stream: RemoteOutputStream::Stdout,
+                    data: b"c".to_vec(),
+                    stamp: stamp(2),
+                },
+            ]
+        );
+        assert_eq!(output.interleaved().unwrap(), b"abc");
+    }
+
+    #[test]
+    fn interleaved_should_return_none_if_output_was_not_stamped() {
+        let output = RemoteOutput {
+            success: true,
+            code: Some(0),
+            stdout: b"out".to_vec(),
+            stderr: Vec::new(),
+            chunks: Vec::new(),
+        };
+        assert_eq!(output.interleaved(), None);
+    }
 }
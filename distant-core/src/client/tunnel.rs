@@ -3,24 +3,29 @@ use std::fmt;
 use std::io;
 
 use log::*;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::error::{TryRecvError, TrySendError};
 use tokio::task::{AbortHandle, JoinHandle};
 
 use crate::client::Channel;
-use crate::constants::{CLIENT_TUNNEL_CAPACITY, TUNNEL_RELAY_BUFFER_SIZE};
+use crate::constants::{
+    CLIENT_TUNNEL_CAPACITY, TUNNEL_RELAY_BUFFER_SIZE, UDP_TUNNEL_DATAGRAM_BUFFER_SIZE,
+};
 use crate::net::client::Mailbox;
 use crate::net::common::{Request, Response};
-use crate::protocol::{self, TunnelId};
+use crate::protocol::{self, TunnelId, TunnelProtocol};
 
-/// Represents a bidirectional TCP tunnel to a remote host.
+/// Represents a bidirectional TCP or UDP tunnel to a remote host.
 ///
 /// A `RemoteTunnel` provides a channel for sending and receiving raw bytes
 /// through a tunnel established on the remote server. The tunnel connects
 /// to a specified host and port on the remote side, forwarding data
 /// bidirectionally between the client and the remote endpoint.
 ///
+/// For UDP tunnels, each write is sent as a single datagram and each read
+/// yields a single datagram.
+///
 /// # Lifecycle
 ///
 /// 1. Open the tunnel via [`RemoteTunnel::open`] or [`ChannelExt::tunnel_open`].
@@ -76,13 +81,53 @@ impl RemoteTunnel {
     ///
     /// Returns `Err` if the server returns an error, sends an unexpected
     /// response, or the connection is lost before confirmation.
-    pub async fn open(mut channel: Channel, host: String, port: u16) -> io::Result<Self> {
-        trace!("Opening tunnel to {host}:{port}");
+    pub async fn open(channel: Channel, host: String, port: u16) -> io::Result<Self> {
+        Self::open_with_protocol(channel, host, port, TunnelProtocol::Tcp).await
+    }
+
+    /// Opens a forward UDP tunnel to the specified host and port on the remote server.
+    ///
+    /// Behaves like [`RemoteTunnel::open`], except that data is relayed as datagrams. The
+    /// remote server closes the tunnel once it has been idle for a while.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the server returns an error, sends an unexpected
+    /// response, or the connection is lost before confirmation.
+    pub async fn open_udp(channel: Channel, host: String, port: u16) -> io::Result<Self> {
+        Self::open_with_protocol(channel, host, port, TunnelProtocol::Udp).await
+    }
+
+    /// Opens a forward tunnel to the Unix domain socket at the specified path on the remote
+    /// server.
+    ///
+    /// Behaves like [`RemoteTunnel::open`], except that the remote server connects to the
+    /// socket at `path` instead of a host and port.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the server returns an error, sends an unexpected
+    /// response, or the connection is lost before confirmation.
+    pub async fn open_unix(channel: Channel, path: String) -> io::Result<Self> {
+        Self::open_with_protocol(channel, path, 0, TunnelProtocol::Unix).await
+    }
+
+    async fn open_with_protocol(
+        mut channel: Channel,
+        host: String,
+        port: u16,
+        protocol: TunnelProtocol,
+    ) -> io::Result<Self> {
+        trace!("Opening {protocol:?} tunnel to {host}:{port}");
 
         // Submit the open request and get a mailbox for responses
         let mut mailbox = channel
             .mail(Request::new(protocol::Msg::Single(
-                protocol::Request::TunnelOpen { host, port },
+                protocol::Request::TunnelOpen {
+                    host,
+                    port,
+                    protocol,
+                },
             )))
             .await?;
 
@@ -395,13 +440,55 @@ impl RemoteTunnelListener {
     ///
     /// Returns `Err` if the server returns an error, sends an unexpected
     /// response, or the connection is lost before confirmation.
-    pub async fn listen(mut channel: Channel, host: String, port: u16) -> io::Result<Self> {
-        trace!("Starting tunnel listener on {host}:{port}");
+    pub async fn listen(channel: Channel, host: String, port: u16) -> io::Result<Self> {
+        Self::listen_with_protocol(channel, host, port, TunnelProtocol::Tcp).await
+    }
+
+    /// Starts a reverse UDP tunnel listener on the specified host and port on the
+    /// remote server.
+    ///
+    /// Behaves like [`RemoteTunnelListener::listen`], except that each distinct peer
+    /// sending datagrams to the listener is yielded as its own [`IncomingTunnel`], which
+    /// the remote server closes once the peer has been idle for a while.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the server returns an error, sends an unexpected
+    /// response, or the connection is lost before confirmation.
+    pub async fn listen_udp(channel: Channel, host: String, port: u16) -> io::Result<Self> {
+        Self::listen_with_protocol(channel, host, port, TunnelProtocol::Udp).await
+    }
+
+    /// Starts a reverse tunnel listener on a Unix domain socket created at the specified path
+    /// on the remote server.
+    ///
+    /// Behaves like [`RemoteTunnelListener::listen`], except that the listener's
+    /// [`port`](Self::port) is always `0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the server returns an error, sends an unexpected
+    /// response, or the connection is lost before confirmation.
+    pub async fn listen_unix(channel: Channel, path: String) -> io::Result<Self> {
+        Self::listen_with_protocol(channel, path, 0, TunnelProtocol::Unix).await
+    }
+
+    async fn listen_with_protocol(
+        mut channel: Channel,
+        host: String,
+        port: u16,
+        protocol: TunnelProtocol,
+    ) -> io::Result<Self> {
+        trace!("Starting {protocol:?} tunnel listener on {host}:{port}");
 
         // Submit the listen request and get a mailbox for responses
         let mut mailbox = channel
             .mail(Request::new(protocol::Msg::Single(
-                protocol::Request::TunnelListen { host, port },
+                protocol::Request::TunnelListen {
+                    host,
+                    port,
+                    protocol,
+                },
             )))
             .await?;
 
@@ -675,7 +762,24 @@ async fn process_tunnel_outgoing(
 
 /// Relays data bidirectionally between a local TCP stream and a remote tunnel.
 ///
-/// Splits the TCP stream into read/write halves, then runs two concurrent tasks:
+/// See [`relay_stream_to_tunnel`] for details.
+///
+/// # Errors
+///
+/// Always returns `Ok(())`. I/O errors on either direction are logged at
+/// debug level and cause the relay to stop gracefully.
+pub async fn relay_tcp_to_tunnel(
+    tcp_stream: tokio::net::TcpStream,
+    writer: RemoteTunnelWriter,
+    reader: RemoteTunnelReader,
+) -> io::Result<()> {
+    relay_stream_to_tunnel(tcp_stream, writer, reader).await
+}
+
+/// Relays data bidirectionally between a local stream (such as a TCP stream or a Unix
+/// socket stream) and a remote tunnel.
+///
+/// Splits the stream into read/write halves, then runs two concurrent tasks:
 /// - local read -> tunnel write
 /// - tunnel read -> local write
 ///
@@ -685,17 +789,20 @@ async fn process_tunnel_outgoing(
 ///
 /// Always returns `Ok(())`. I/O errors on either direction are logged at
 /// debug level and cause the relay to stop gracefully.
-pub async fn relay_tcp_to_tunnel(
-    tcp_stream: tokio::net::TcpStream,
+pub async fn relay_stream_to_tunnel<S>(
+    stream: S,
     mut writer: RemoteTunnelWriter,
     mut reader: RemoteTunnelReader,
-) -> io::Result<()> {
-    let (mut tcp_read, mut tcp_write) = tcp_stream.into_split();
+) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut local_read, mut local_write) = tokio::io::split(stream);
 
     let mut local_to_remote = tokio::spawn(async move {
         let mut buf = vec![0u8; TUNNEL_RELAY_BUFFER_SIZE];
         loop {
-            let n = tcp_read.read(&mut buf).await?;
+            let n = local_read.read(&mut buf).await?;
             if n == 0 {
                 break;
             }
@@ -710,7 +817,7 @@ pub async fn relay_tcp_to_tunnel(
             if data.is_empty() {
                 break;
             }
-            tcp_write.write_all(&data).await?;
+            local_write.write_all(&data).await?;
         }
         io::Result::Ok(())
     });
@@ -737,3 +844,58 @@ pub async fn relay_tcp_to_tunnel(
 
     Ok(())
 }
+
+/// Relays datagrams bidirectionally between a local UDP socket and a remote UDP tunnel.
+///
+/// The socket must already be connected to its peer. Each datagram received on the
+/// socket is written to the tunnel as-is, and each datagram read from the tunnel is
+/// sent on the socket, preserving datagram boundaries in both directions.
+///
+/// Returns once the tunnel is closed, which for UDP tunnels happens when the remote
+/// server closes an idle association.
+///
+/// # Errors
+///
+/// Always returns `Ok(())`. I/O errors are logged at debug level and cause the
+/// relay to stop gracefully.
+pub async fn relay_udp_to_tunnel(
+    socket: tokio::net::UdpSocket,
+    mut writer: RemoteTunnelWriter,
+    mut reader: RemoteTunnelReader,
+) -> io::Result<()> {
+    let mut buf = vec![0u8; UDP_TUNNEL_DATAGRAM_BUFFER_SIZE];
+
+    loop {
+        tokio::select! {
+            result = socket.recv(&mut buf) => match result {
+                Ok(n) => {
+                    if let Err(e) = writer.write(buf[..n].to_vec()).await {
+                        debug!("Local-to-remote relay error: {e}");
+                        break;
+                    }
+                }
+
+                // A previous datagram was rejected by the peer (ICMP port unreachable),
+                // which should not tear down the association
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => continue,
+                Err(e) => {
+                    debug!("Local-to-remote relay error: {e}");
+                    break;
+                }
+            },
+            result = reader.read() => match result {
+                Ok(data) => {
+                    if let Err(e) = socket.send(&data).await
+                        && e.kind() != io::ErrorKind::ConnectionRefused
+                    {
+                        debug!("Remote-to-local relay error: {e}");
+                        break;
+                    }
+                }
+                Err(_) => break,
+            },
+        }
+    }
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 /// Capacity associated stdin, stdout, and stderr pipes receiving data from remote server
 pub const CLIENT_PIPE_CAPACITY: usize = 10000;
 
@@ -13,6 +15,12 @@ pub const CLIENT_TUNNEL_CAPACITY: usize = 10000;
 /// Buffer size for reading from tunnel relay connections (SSH channels, TCP streams).
 pub const TUNNEL_RELAY_BUFFER_SIZE: usize = 8192;
 
+/// Buffer size for receiving datagrams on UDP tunnels, large enough for any UDP payload.
+pub const UDP_TUNNEL_DATAGRAM_BUFFER_SIZE: usize = 65536;
+
+/// How long a UDP tunnel association can go without any datagrams before it is closed.
+pub const UDP_TUNNEL_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Channel capacity for queuing tunnel write data before backpressure.
 pub const TUNNEL_CHANNEL_CAPACITY: usize = 1024;
 
@@ -189,6 +189,10 @@ where
             Ok(())
         })
     }
+
+    fn peer_identity(&self) -> Option<&str> {
+        self.as_inner().peer_identity()
+    }
 }
 
 #[cfg(test)]
@@ -359,7 +359,9 @@ impl UntypedClient {
                                 String::from_utf8_lossy(&request.to_bytes())
                             );
                         }
-                        match connection.try_write_frame(request.to_bytes()) {
+                        let format = connection.packet_format();
+                        let frame = request.to_bytes_with_format(format);
+                        match connection.try_write_frame_on(&request.id, frame) {
                             Ok(()) => (),
                             Err(x) if x.kind() == io::ErrorKind::WouldBlock => write_blocked = true,
                             Err(x) => {
@@ -516,6 +518,23 @@ impl Client<(), ()> {
         ClientBuilder::new().connector(connector.into())
     }
 
+    /// Creates a new [`ClientBuilder`] configured to use a [`QuicConnector`].
+    pub fn quic<T>(connector: impl Into<QuicConnector<T>>) -> ClientBuilder<(), QuicConnector<T>> {
+        ClientBuilder::new().connector(connector.into())
+    }
+
+    /// Creates a new [`ClientBuilder`] configured to use a [`TlsConnector`].
+    pub fn tls(connector: TlsConnector) -> ClientBuilder<(), TlsConnector> {
+        ClientBuilder::new().connector(connector)
+    }
+
+    /// Creates a new [`ClientBuilder`] configured to use a [`WebSocketConnector`].
+    pub fn websocket(
+        connector: impl Into<WebSocketConnector>,
+    ) -> ClientBuilder<(), WebSocketConnector> {
+        ClientBuilder::new().connector(connector.into())
+    }
+
     /// Creates a new [`ClientBuilder`] configured to use a [`UnixSocketConnector`].
     #[cfg(unix)]
     pub fn unix_socket(
@@ -1,6 +1,14 @@
+mod quic;
 mod tcp;
+pub use quic::*;
 pub use tcp::*;
 
+mod tls;
+pub use tls::*;
+
+mod websocket;
+pub use websocket::*;
+
 #[cfg(unix)]
 mod unix;
 
@@ -20,7 +28,7 @@ pub use windows::*;
 
 use super::ClientConfig;
 use crate::net::client::{Client, UntypedClient};
-use crate::net::common::{Connection, Transport, Version};
+use crate::net::common::{Connection, KnownServers, RekeyConfig, Transport, Version};
 
 /// Interface that performs the connection to produce a [`Transport`] for use by the [`Client`].
 pub trait Connector {
@@ -45,6 +53,8 @@ pub struct ClientBuilder<H, C> {
     config: ClientConfig,
     connect_timeout: Option<Duration>,
     version: Version,
+    known_servers: Option<(KnownServers, String)>,
+    rekey: RekeyConfig,
 }
 
 impl<H, C> ClientBuilder<H, C> {
@@ -56,6 +66,8 @@ impl<H, C> ClientBuilder<H, C> {
             connector: self.connector,
             connect_timeout: self.connect_timeout,
             version: self.version,
+            known_servers: self.known_servers,
+            rekey: self.rekey,
         }
     }
 
@@ -67,6 +79,8 @@ impl<H, C> ClientBuilder<H, C> {
             connector: self.connector,
             connect_timeout: self.connect_timeout,
             version: self.version,
+            known_servers: self.known_servers,
+            rekey: self.rekey,
         }
     }
 
@@ -78,6 +92,8 @@ impl<H, C> ClientBuilder<H, C> {
             connector,
             connect_timeout: self.connect_timeout,
             version: self.version,
+            known_servers: self.known_servers,
+            rekey: self.rekey,
         }
     }
 
@@ -89,6 +105,8 @@ impl<H, C> ClientBuilder<H, C> {
             connector: self.connector,
             connect_timeout: connect_timeout.into(),
             version: self.version,
+            known_servers: self.known_servers,
+            rekey: self.rekey,
         }
     }
 
@@ -100,6 +118,36 @@ impl<H, C> ClientBuilder<H, C> {
             connector: self.connector,
             connect_timeout: self.connect_timeout,
             version,
+            known_servers: self.known_servers,
+            rekey: self.rekey,
+        }
+    }
+
+    /// Configure the known servers to check the identity of the server against, where `name` is
+    /// how the server is remembered (typically `host:port`).
+    pub fn known_servers(self, known_servers: KnownServers, name: impl Into<String>) -> Self {
+        Self {
+            auth_handler: self.auth_handler,
+            config: self.config,
+            connector: self.connector,
+            connect_timeout: self.connect_timeout,
+            version: self.version,
+            known_servers: Some((known_servers, name.into())),
+            rekey: self.rekey,
+        }
+    }
+
+    /// Configure the thresholds at which the connection replaces the key used to encrypt its
+    /// traffic.
+    pub fn rekey(self, rekey: RekeyConfig) -> Self {
+        Self {
+            auth_handler: self.auth_handler,
+            config: self.config,
+            connector: self.connector,
+            connect_timeout: self.connect_timeout,
+            version: self.version,
+            known_servers: self.known_servers,
+            rekey,
         }
     }
 }
@@ -112,6 +160,8 @@ impl ClientBuilder<(), ()> {
             connector: (),
             connect_timeout: None,
             version: Default::default(),
+            known_servers: None,
+            rekey: RekeyConfig::default(),
         }
     }
 }
@@ -135,6 +185,8 @@ where
         let config = self.config;
         let connect_timeout = self.connect_timeout;
         let version = self.version;
+        let known_servers = self.known_servers;
+        let rekey = self.rekey;
 
         let f = async move {
             let transport = match connect_timeout {
@@ -144,7 +196,20 @@ where
                     .and_then(convert::identity)?,
                 None => self.connector.connect().await?,
             };
-            let connection = Connection::client(transport, auth_handler, version).await?;
+            let mut connection = match known_servers {
+                Some((known_servers, name)) => {
+                    Connection::client_with_known_servers(
+                        transport,
+                        auth_handler,
+                        version,
+                        &known_servers,
+                        &name,
+                    )
+                    .await?
+                }
+                None => Connection::client(transport, auth_handler, version).await?,
+            };
+            connection.set_rekey_config(rekey);
             Ok(UntypedClient::spawn(connection, config))
         };
 
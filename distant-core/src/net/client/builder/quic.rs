@@ -0,0 +1,31 @@
+use std::io;
+
+use tokio::net::ToSocketAddrs;
+
+use super::Connector;
+use crate::net::common::QuicTransport;
+
+/// Implementation of [`Connector`] to support connecting via QUIC.
+pub struct QuicConnector<T> {
+    addr: T,
+}
+
+impl<T> QuicConnector<T> {
+    pub fn new(addr: T) -> Self {
+        Self { addr }
+    }
+}
+
+impl<T> From<T> for QuicConnector<T> {
+    fn from(addr: T) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl<T: ToSocketAddrs + Send> Connector for QuicConnector<T> {
+    type Transport = QuicTransport;
+
+    async fn connect(self) -> io::Result<Self::Transport> {
+        QuicTransport::connect(self.addr).await
+    }
+}
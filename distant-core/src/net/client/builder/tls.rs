@@ -0,0 +1,31 @@
+use std::io;
+
+use super::Connector;
+use crate::net::common::{TlsClientConfig, TlsTransport};
+
+/// Implementation of [`Connector`] to support connecting via TLS over TCP.
+pub struct TlsConnector {
+    host: String,
+    port: u16,
+    config: TlsClientConfig,
+}
+
+impl TlsConnector {
+    /// Creates a connector for the server at `host` and `port`, where `host` is also the name
+    /// that the server's certificate must be valid for.
+    pub fn new(host: impl Into<String>, port: u16, config: TlsClientConfig) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            config,
+        }
+    }
+}
+
+impl Connector for TlsConnector {
+    type Transport = TlsTransport;
+
+    async fn connect(self) -> io::Result<Self::Transport> {
+        TlsTransport::connect(self.host, self.port, self.config).await
+    }
+}
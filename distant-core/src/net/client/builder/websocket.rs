@@ -0,0 +1,30 @@
+use std::io;
+
+use super::Connector;
+use crate::net::common::WebSocketTransport;
+
+/// Implementation of [`Connector`] to support connecting via a WebSocket at a `ws://` or `wss://`
+/// url.
+pub struct WebSocketConnector {
+    url: String,
+}
+
+impl WebSocketConnector {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+impl<T: Into<String>> From<T> for WebSocketConnector {
+    fn from(url: T) -> Self {
+        Self::new(url)
+    }
+}
+
+impl Connector for WebSocketConnector {
+    type Transport = WebSocketTransport;
+
+    async fn connect(self) -> io::Result<Self::Transport> {
+        WebSocketTransport::connect(self.url).await
+    }
+}
@@ -8,7 +8,7 @@ use serde::de::DeserializeOwned;
 use tokio::sync::mpsc;
 use tokio::time::Duration;
 
-use crate::net::common::{Request, Response, UntypedRequest, UntypedResponse};
+use crate::net::common::{Id, Request, Response, UntypedRequest, UntypedResponse};
 
 mod mailbox;
 pub use mailbox::*;
@@ -16,6 +16,13 @@ pub use mailbox::*;
 /// Capacity associated with a channel's mailboxes for receiving multiple responses to a request
 const CHANNEL_MAILBOX_CAPACITY: usize = 10000;
 
+/// Represents a request payload that can ask the server to cancel an earlier request, which is
+/// what [`Channel::send_cancelable`] sends when waiting on a response is abandoned.
+pub trait Cancelable {
+    /// Creates a payload that cancels the request with `id`
+    fn cancel(id: &Id) -> Self;
+}
+
 /// Represents a sender of requests tied to a session, holding onto a weak reference of
 /// mailboxes to relay responses, meaning that once the [`Client`] is closed or dropped,
 /// any sent request will no longer be able to receive responses.
@@ -114,21 +121,72 @@ where
             .ok_or_else(|| io::Error::from(io::ErrorKind::ConnectionAborted))
     }
 
-    /// Sends a request and waits for a response, timing out after duration has passed
+    /// Sends a request and waits for a response like [`Channel::send`], but if the returned
+    /// future is dropped before a response is received, a request to cancel the original request
+    /// is sent so that the server can stop working on it
+    pub async fn send_cancelable(&mut self, req: impl Into<Request<T>>) -> io::Result<Response<U>>
+    where
+        T: Cancelable,
+    {
+        let req = req.into();
+        let mut guard = CancelOnDrop::<T> {
+            tx: self.inner.tx.clone(),
+            id: Some(req.id.clone()),
+            _request: PhantomData,
+        };
+
+        let res = self.send(req).await;
+        guard.id = None;
+        res
+    }
+
+    /// Sends a request and waits for a response, timing out after duration has passed.
+    ///
+    /// The duration is also set as the deadline of the request (see [`Header::set_deadline`]),
+    /// so that a server honoring it stops working on the request once the wait is over.
+    ///
+    /// [`Header::set_deadline`]: crate::net::common::Header::set_deadline
     pub async fn send_timeout(
         &mut self,
         req: impl Into<Request<T>>,
         duration: impl Into<Option<Duration>>,
     ) -> io::Result<Response<U>> {
+        let mut req = req.into();
         match duration.into() {
-            Some(duration) => tokio::time::timeout(duration, self.send(req))
-                .await
-                .map_err(|x| io::Error::new(io::ErrorKind::TimedOut, x))
-                .and_then(convert::identity),
+            Some(duration) => {
+                req.header.set_deadline(duration);
+                tokio::time::timeout(duration, self.send(req))
+                    .await
+                    .map_err(|x| io::Error::new(io::ErrorKind::TimedOut, x))
+                    .and_then(convert::identity)
+            }
             None => self.send(req).await,
         }
     }
 
+    /// Sends a request and waits for a response like [`Channel::send_cancelable`], timing out
+    /// after duration has passed, which is also set as the deadline of the request
+    pub async fn send_cancelable_timeout(
+        &mut self,
+        req: impl Into<Request<T>>,
+        duration: impl Into<Option<Duration>>,
+    ) -> io::Result<Response<U>>
+    where
+        T: Cancelable,
+    {
+        let mut req = req.into();
+        match duration.into() {
+            Some(duration) => {
+                req.header.set_deadline(duration);
+                tokio::time::timeout(duration, self.send_cancelable(req))
+                    .await
+                    .map_err(|x| io::Error::new(io::ErrorKind::TimedOut, x))
+                    .and_then(convert::identity)
+            }
+            None => self.send_cancelable(req).await,
+        }
+    }
+
     /// Sends a request without waiting for a response; this method is able to be used even
     /// if the session's receiving line to the remote server has been severed
     pub async fn fire(&mut self, req: impl Into<Request<T>>) -> io::Result<()> {
@@ -151,6 +209,34 @@ where
     }
 }
 
+/// Sends a request to cancel the request with `id` when dropped, unless `id` is cleared first
+struct CancelOnDrop<T: Cancelable + Serialize> {
+    tx: mpsc::Sender<UntypedRequest<'static>>,
+    id: Option<Id>,
+    _request: PhantomData<T>,
+}
+
+impl<T: Cancelable + Serialize> Drop for CancelOnDrop<T> {
+    fn drop(&mut self) {
+        let Some(id) = self.id.take() else {
+            return;
+        };
+
+        trace!("Canceling request {id} as its response is no longer awaited");
+        let result = Request::new(T::cancel(&id))
+            .to_untyped_request()
+            .and_then(|req| {
+                self.tx
+                    .try_send(req.into_owned())
+                    .map_err(|x| io::Error::new(io::ErrorKind::BrokenPipe, x.to_string()))
+            });
+
+        if let Err(x) = result {
+            debug!("Failed to cancel request {id}: {x}");
+        }
+    }
+}
+
 fn map_to_typed_mailbox<T: Send + DeserializeOwned + 'static>(
     mailbox: Mailbox<UntypedResponse<'static>>,
 ) -> Mailbox<Response<T>> {
@@ -370,6 +456,66 @@ mod tests {
             (channel.into_typed_channel(), rx, post_office)
         }
 
+        #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+        enum CancelableRequest {
+            Work,
+            Cancel(Id),
+        }
+
+        impl Cancelable for CancelableRequest {
+            fn cancel(id: &Id) -> Self {
+                Self::Cancel(id.clone())
+            }
+        }
+
+        fn setup_cancelable() -> (
+            Channel<CancelableRequest, u8>,
+            mpsc::Receiver<UntypedRequest<'static>>,
+            Arc<PostOffice<UntypedResponse<'static>>>,
+        ) {
+            let (channel, rx, post_office) = setup(100);
+            (
+                channel.into_untyped_channel().into_typed_channel(),
+                rx,
+                post_office,
+            )
+        }
+
+        #[test(tokio::test)]
+        async fn send_cancelable_should_send_cancel_request_if_dropped_before_response() {
+            let (mut channel, mut server, _post_office) = setup_cancelable();
+
+            let req = Request::new(CancelableRequest::Work);
+            let id = req.id.clone();
+            let result =
+                tokio::time::timeout(Duration::from_millis(50), channel.send_cancelable(req)).await;
+            assert!(result.is_err(), "Unexpectedly got response: {result:?}");
+
+            let req = server.recv().await.unwrap();
+            assert_eq!(req.id, id);
+
+            let req: Request<CancelableRequest> =
+                server.recv().await.unwrap().to_typed_request().unwrap();
+            assert_eq!(req.payload, CancelableRequest::Cancel(id));
+        }
+
+        #[test(tokio::test)]
+        async fn send_cancelable_should_not_send_cancel_request_once_response_received() {
+            let (mut channel, mut server, post_office) = setup_cancelable();
+
+            let req = Request::new(CancelableRequest::Work);
+            let res = Response::new(req.id.clone(), 1);
+            let task = tokio::spawn(async move { channel.send_cancelable(req).await });
+
+            server.recv().await.unwrap();
+            post_office
+                .deliver_untyped_response(res.to_untyped_response().unwrap().into_owned())
+                .await;
+            assert_eq!(task.await.unwrap().unwrap(), res);
+
+            assert!(server.try_recv().is_err(), "Unexpected cancel request");
+        }
+
         #[test(tokio::test)]
         async fn mail_should_return_mailbox_that_receives_responses_until_post_office_drops_it() {
             let (mut channel, _server, post_office) = setup(100);
@@ -70,12 +70,13 @@ where
     /// Delivers some value to appropriate mailbox, returning false if no mailbox is found
     /// for the specified id or if the mailbox is no longer receiving values
     pub async fn deliver(&self, id: &Id, value: T) -> bool {
-        if let Some(tx) = self.mailboxes.lock().await.get_mut(id) {
+        let mut mailboxes = self.mailboxes.lock().await;
+        if let Some(tx) = mailboxes.get_mut(id) {
             let success = tx.send(value).await.is_ok();
 
             // If failed, we want to remove the mailbox sender as it is no longer valid
             if !success {
-                self.mailboxes.lock().await.remove(id);
+                mailboxes.remove(id);
             }
 
             success
@@ -378,6 +379,25 @@ mod tests {
         assert!(!po.deliver(&"id1".to_string(), 99).await);
     }
 
+    #[test(tokio::test)]
+    async fn post_office_deliver_returns_false_and_removes_mailbox_after_it_is_dropped() {
+        let po = PostOffice::<u32>::default();
+        let mb = po.make_mailbox("id1".to_string(), 4).await;
+        drop(mb);
+
+        let delivered = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            po.deliver(&"id1".to_string(), 1),
+        )
+        .await
+        .expect("Delivery to a dropped mailbox should not block");
+        assert!(!delivered);
+
+        // The stale sender was removed, so subsequent deliveries also fail without blocking
+        assert!(!po.deliver(&"id1".to_string(), 2).await);
+        assert!(po.mailboxes.lock().await.is_empty());
+    }
+
     #[test(tokio::test)]
     async fn post_office_assign_default_mailbox_catches_undelivered() {
         let po = PostOffice::<u32>::default();
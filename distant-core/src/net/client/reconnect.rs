@@ -2,6 +2,7 @@ use std::io;
 use std::time::Duration;
 
 use log::*;
+use serde::{Deserialize, Serialize};
 use strum::Display;
 use tokio::sync::watch;
 use tokio::task::JoinHandle;
@@ -47,13 +48,15 @@ impl ConnectionWatcher {
 }
 
 /// Represents the state of a connection.
-#[derive(Copy, Clone, Debug, Display, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, Display, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
 pub enum ConnectionState {
     /// Connection is not active, but currently going through reconnection process.
     Reconnecting,
 
     /// Connection is active.
+    #[default]
     Connected,
 
     /// Connection is not active.
@@ -349,6 +352,26 @@ mod tests {
         assert_eq!(ConnectionState::Disconnected.to_string(), "disconnected");
     }
 
+    #[test]
+    fn connection_state_should_serialize_to_snake_case_json() {
+        for state in [
+            ConnectionState::Reconnecting,
+            ConnectionState::Connected,
+            ConnectionState::Disconnected,
+        ] {
+            let value = serde_json::to_value(state).unwrap();
+            assert_eq!(value, serde_json::json!(state.to_string()));
+
+            let parsed: ConnectionState = serde_json::from_value(value).unwrap();
+            assert_eq!(parsed, state);
+        }
+    }
+
+    #[test]
+    fn connection_state_should_default_to_connected() {
+        assert_eq!(ConnectionState::default(), ConnectionState::Connected);
+    }
+
     // ---------------------------------------------------------------
     // ConnectionWatcher tests
     // ---------------------------------------------------------------
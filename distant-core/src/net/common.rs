@@ -1,25 +1,31 @@
 mod any;
+mod cidr;
 mod connection;
 mod destination;
 mod key;
 mod keychain;
+mod known_servers;
 mod listener;
 mod map;
 mod packet;
 mod port;
 mod transport;
+mod udp;
 pub(crate) mod utils;
 mod version;
 
 pub use any::*;
+pub use cidr::*;
 pub(crate) use connection::Connection;
 pub use connection::ConnectionId;
 pub use destination::*;
 pub use key::*;
 pub use keychain::*;
+pub use known_servers::*;
 pub use listener::*;
 pub use map::*;
 pub use packet::*;
 pub use port::*;
 pub use transport::*;
+pub use udp::*;
 pub use version::*;
@@ -0,0 +1,228 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use serde::de::Deserializer;
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use super::utils::{deserialize_from_str, serialize_to_str};
+
+/// Represents a block of IP addresses in CIDR notation, such as `10.0.0.0/8` or `fd00::/8`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IpCidr {
+    pub addr: IpAddr,
+    pub prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Creates a block containing only `addr`.
+    pub fn single(addr: impl Into<IpAddr>) -> Self {
+        let addr = addr.into();
+        Self {
+            addr,
+            prefix_len: Self::max_prefix_len(&addr),
+        }
+    }
+
+    /// Returns true if `addr` falls within the block. IPv4 addresses mapped into IPv6 are
+    /// treated as the IPv4 addresses they represent.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        let bits = u32::from(self.prefix_len);
+        let addr = match (self.addr, addr.to_canonical()) {
+            (IpAddr::V6(_), IpAddr::V4(addr)) => IpAddr::V6(addr.to_ipv6_mapped()),
+            (_, addr) => addr,
+        };
+
+        match (self.addr, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX.checked_shl(128 - bits).unwrap_or(0);
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+
+    fn max_prefix_len(addr: &IpAddr) -> u8 {
+        match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+}
+
+impl From<IpAddr> for IpCidr {
+    fn from(addr: IpAddr) -> Self {
+        Self::single(addr)
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    /// Parses ADDR/PREFIX into a block of addresses, or ADDR into a block of that single address
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s, None),
+        };
+
+        let addr: IpAddr = addr
+            .trim()
+            .parse()
+            .map_err(|x| format!("Invalid address '{addr}': {x}"))?;
+        let max_prefix_len = Self::max_prefix_len(&addr);
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_prefix_len)
+                .ok_or_else(|| {
+                    format!("Invalid prefix length '{prefix_len}', must be 0 to {max_prefix_len}")
+                })?,
+            None => max_prefix_len,
+        };
+
+        Ok(Self { addr, prefix_len })
+    }
+}
+
+impl Serialize for IpCidr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_to_str(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpCidr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_from_str(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for IpCidr: parsing with and without a prefix length, display, membership for
+    //! IPv4, IPv6, and IPv4-mapped IPv6 addresses, and serde round-trips.
+
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_should_support_address_with_prefix_length() {
+        let cidr: IpCidr = "10.0.0.0/8".parse().unwrap();
+        assert_eq!(
+            cidr,
+            IpCidr {
+                addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)),
+                prefix_len: 8,
+            }
+        );
+
+        let cidr: IpCidr = "fd00::/8".parse().unwrap();
+        assert_eq!(cidr.prefix_len, 8);
+    }
+
+    #[test]
+    fn parse_should_treat_bare_address_as_single_address() {
+        assert_eq!(
+            "127.0.0.1".parse::<IpCidr>().unwrap(),
+            IpCidr::single(Ipv4Addr::LOCALHOST)
+        );
+        assert_eq!(
+            "::1".parse::<IpCidr>().unwrap(),
+            IpCidr::single(Ipv6Addr::LOCALHOST)
+        );
+    }
+
+    #[test]
+    fn parse_should_fail_if_address_or_prefix_length_is_invalid() {
+        assert!("example.com".parse::<IpCidr>().is_err());
+        assert!("10.0.0.0/".parse::<IpCidr>().is_err());
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("::/129".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn display_should_include_prefix_length() {
+        assert_eq!(
+            "10.0.0.0/8".parse::<IpCidr>().unwrap().to_string(),
+            "10.0.0.0/8"
+        );
+        assert_eq!(
+            IpCidr::single(Ipv4Addr::LOCALHOST).to_string(),
+            "127.0.0.1/32"
+        );
+    }
+
+    #[test]
+    fn contains_should_match_addresses_within_ipv4_block() {
+        let cidr: IpCidr = "192.168.1.0/24".parse().unwrap();
+        assert!(cidr.contains(&ip("192.168.1.0")));
+        assert!(cidr.contains(&ip("192.168.1.255")));
+        assert!(!cidr.contains(&ip("192.168.2.1")));
+        assert!(!cidr.contains(&ip("::1")));
+    }
+
+    #[test]
+    fn contains_should_match_addresses_within_ipv6_block() {
+        let cidr: IpCidr = "fd00::/8".parse().unwrap();
+        assert!(cidr.contains(&ip("fd12:3456::1")));
+        assert!(!cidr.contains(&ip("fe80::1")));
+        assert!(!cidr.contains(&ip("10.0.0.1")));
+    }
+
+    #[test]
+    fn contains_should_match_everything_with_zero_prefix_length() {
+        let cidr: IpCidr = "0.0.0.0/0".parse().unwrap();
+        assert!(cidr.contains(&ip("1.2.3.4")));
+        assert!(cidr.contains(&ip("255.255.255.255")));
+
+        let cidr: IpCidr = "::/0".parse().unwrap();
+        assert!(cidr.contains(&ip("::1")));
+    }
+
+    #[test]
+    fn contains_should_treat_ipv4_mapped_addresses_as_ipv4() {
+        let cidr: IpCidr = "127.0.0.0/8".parse().unwrap();
+        assert!(cidr.contains(&ip("::ffff:127.0.0.1")));
+        assert!(!cidr.contains(&ip("::ffff:10.0.0.1")));
+
+        let cidr: IpCidr = "::ffff:10.0.0.0/104".parse().unwrap();
+        assert!(cidr.contains(&ip("10.1.2.3")));
+    }
+
+    #[test]
+    fn serde_should_use_string_form() {
+        let cidr: IpCidr = "10.0.0.0/8".parse().unwrap();
+        assert_eq!(
+            serde_json::to_value(cidr).unwrap(),
+            serde_json::Value::String("10.0.0.0/8".to_string())
+        );
+        assert_eq!(
+            serde_json::from_str::<IpCidr>("\"10.0.0.0/8\"").unwrap(),
+            cidr
+        );
+    }
+}
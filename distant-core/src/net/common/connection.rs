@@ -11,8 +11,8 @@ use tokio::sync::oneshot;
 #[cfg(test)]
 use crate::net::common::InmemoryTransport;
 use crate::net::common::{
-    Backup, FramedTransport, HeapSecretKey, Keychain, KeychainResult, Reconnectable, Transport,
-    TransportExt, Version,
+    Backup, FramedTransport, Handshake, HeapSecretKey, Keychain, KeychainResult, KnownServers,
+    Reconnectable, RekeyConfig, ServerIdentity, Transport, TransportExt, Version,
 };
 
 /// Id of the connection
@@ -127,8 +127,19 @@ where
 
                 // Perform a handshake to ensure that the connection is properly established and encrypted
                 debug!("[Conn {id}] Performing handshake");
+                let server_identity = transport.server_identity().copied();
                 transport.client_handshake().await?;
 
+                // Make sure that we are talking to the same server as before
+                if server_identity.is_some()
+                    && transport.server_identity() != server_identity.as_ref()
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "Server identity changed during reconnect",
+                    ));
+                }
+
                 // Communicate that we are an existing connection
                 debug!("[Conn {id}] Performing re-authentication");
                 transport
@@ -217,6 +228,29 @@ where
         transport: T,
         handler: H,
         version: Version,
+    ) -> io::Result<Self> {
+        Self::client_impl(transport, handler, version, None).await
+    }
+
+    /// Like [`Connection::client`], but additionally checks the identity presented by the server
+    /// during the handshake against `known_servers` using `name` (typically `host:port`) to look
+    /// up the server. This happens prior to authentication, and any prompt to trust a new server
+    /// is sent to the `handler`.
+    pub async fn client_with_known_servers<H: AuthHandler + Send>(
+        transport: T,
+        handler: H,
+        version: Version,
+        known_servers: &KnownServers,
+        name: &str,
+    ) -> io::Result<Self> {
+        Self::client_impl(transport, handler, version, Some((known_servers, name))).await
+    }
+
+    async fn client_impl<H: AuthHandler + Send>(
+        transport: T,
+        mut handler: H,
+        version: Version,
+        known_servers: Option<(&KnownServers, &str)>,
     ) -> io::Result<Self> {
         let id: ConnectionId = rand::random();
 
@@ -246,6 +280,14 @@ where
         let mut transport: FramedTransport<T> =
             FramedTransport::from_client_handshake(transport).await?;
 
+        // Verify that the server is one we trust before sending anything else its way
+        if let Some((known_servers, name)) = known_servers {
+            debug!("[Conn {id}] Verifying identity of server {name}");
+            known_servers
+                .verify(name, transport.server_identity(), &mut handler)
+                .await?;
+        }
+
         // Communicate that we are a new connection
         debug!("[Conn {id}] Communicating that this is a new connection");
         transport.write_frame_for(&ConnectType::Connect).await?;
@@ -281,7 +323,8 @@ where
     /// performing the following:
     ///
     /// 1. Performs a version check with the client
-    /// 2. Handshakes to derive the appropriate [`Codec`](crate::Codec) to use
+    /// 2. Handshakes to derive the appropriate [`Codec`](crate::Codec) to use, signing the key
+    ///    exchange with the `identity` if provided
     /// 3. Authenticates the established connection to ensure it is valid by either using the
     ///    given `verifier` or, if working with an existing client connection, will validate an OTP
     ///    from our database
@@ -292,6 +335,7 @@ where
         verifier: &Verifier,
         keychain: Keychain<oneshot::Receiver<Backup>>,
         version: Version,
+        identity: Option<ServerIdentity>,
     ) -> io::Result<Self> {
         let id: ConnectionId = rand::random();
 
@@ -301,8 +345,13 @@ where
 
         // Perform a handshake to ensure that the connection is properly established and encrypted
         debug!("[Conn {id}] Performing handshake");
-        let mut transport: FramedTransport<T> =
-            FramedTransport::from_server_handshake(transport).await?;
+        let mut transport = FramedTransport::plain(transport);
+        transport
+            .handshake(match identity {
+                Some(identity) => Handshake::server_with_identity(identity),
+                None => Handshake::server(),
+            })
+            .await?;
 
         // Receive a client id, look up to see if the client id exists already
         //
@@ -461,6 +510,15 @@ impl<T> Connection<T> {
             Self::Server { id, .. } => *id,
         }
     }
+
+    /// Sets the thresholds at which the connection replaces the key used to encrypt its traffic.
+    /// See [`FramedTransport::set_rekey_config`] for more details.
+    pub fn set_rekey_config(&mut self, config: RekeyConfig) {
+        match self {
+            Self::Client { transport, .. } => transport.set_rekey_config(config),
+            Self::Server { transport, .. } => transport.set_rekey_config(config),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -510,7 +568,7 @@ mod tests {
     use test_log::test;
 
     use super::*;
-    use crate::net::common::Frame;
+    use crate::net::common::{Frame, ServerIdentityPolicy};
 
     macro_rules! server_version {
         () => {
@@ -755,9 +813,15 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                .await
-                .unwrap()
+            Connection::server(
+                t2.into_inner(),
+                &verifier,
+                keychain,
+                server_version!(),
+                None,
+            )
+            .await
+            .unwrap()
         });
 
         // Receive the version from the server
@@ -779,9 +843,15 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                .await
-                .unwrap()
+            Connection::server(
+                t2.into_inner(),
+                &verifier,
+                keychain,
+                server_version!(),
+                None,
+            )
+            .await
+            .unwrap()
         });
 
         // Receive the version from the server
@@ -803,9 +873,15 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                .await
-                .unwrap()
+            Connection::server(
+                t2.into_inner(),
+                &verifier,
+                keychain,
+                server_version!(),
+                None,
+            )
+            .await
+            .unwrap()
         });
 
         // Receive the version from the server
@@ -830,9 +906,15 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                .await
-                .unwrap()
+            Connection::server(
+                t2.into_inner(),
+                &verifier,
+                keychain,
+                server_version!(),
+                None,
+            )
+            .await
+            .unwrap()
         });
 
         // Receive the version from the server
@@ -867,9 +949,15 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                .await
-                .unwrap()
+            Connection::server(
+                t2.into_inner(),
+                &verifier,
+                keychain,
+                server_version!(),
+                None,
+            )
+            .await
+            .unwrap()
         });
 
         // Receive the version from the server
@@ -903,9 +991,15 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                .await
-                .unwrap()
+            Connection::server(
+                t2.into_inner(),
+                &verifier,
+                keychain,
+                server_version!(),
+                None,
+            )
+            .await
+            .unwrap()
         });
 
         // Receive the version from the server
@@ -946,9 +1040,15 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                .await
-                .unwrap()
+            Connection::server(
+                t2.into_inner(),
+                &verifier,
+                keychain,
+                server_version!(),
+                None,
+            )
+            .await
+            .unwrap()
         });
 
         // Receive the version from the server
@@ -987,9 +1087,15 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                .await
-                .unwrap()
+            Connection::server(
+                t2.into_inner(),
+                &verifier,
+                keychain,
+                server_version!(),
+                None,
+            )
+            .await
+            .unwrap()
         });
 
         // Receive the version from the server
@@ -1028,9 +1134,15 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                .await
-                .unwrap()
+            Connection::server(
+                t2.into_inner(),
+                &verifier,
+                keychain,
+                server_version!(),
+                None,
+            )
+            .await
+            .unwrap()
         });
 
         // Receive the version from the server
@@ -1069,9 +1181,15 @@ mod tests {
         let task = tokio::spawn({
             let keychain = keychain.clone();
             async move {
-                Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                    .await
-                    .unwrap()
+                Connection::server(
+                    t2.into_inner(),
+                    &verifier,
+                    keychain,
+                    server_version!(),
+                    None,
+                )
+                .await
+                .unwrap()
             }
         });
 
@@ -1137,9 +1255,15 @@ mod tests {
         let task = tokio::spawn({
             let keychain = keychain.clone();
             async move {
-                Connection::server(t2.into_inner(), &verifier, keychain, server_version!())
-                    .await
-                    .unwrap()
+                Connection::server(
+                    t2.into_inner(),
+                    &verifier,
+                    keychain,
+                    server_version!(),
+                    None,
+                )
+                .await
+                .unwrap()
             }
         });
 
@@ -1200,7 +1324,7 @@ mod tests {
 
         // Spawn a task to perform the server connection so we don't deadlock
         let task = tokio::spawn(async move {
-            Connection::server(t2, &verifier, keychain, server_version!())
+            Connection::server(t2, &verifier, keychain, server_version!(), None)
                 .await
                 .expect("Failed to connect from server")
         });
@@ -1218,6 +1342,80 @@ mod tests {
         assert_eq!(client.read_frame().await.unwrap().unwrap(), b"goodbye");
     }
 
+    #[test(tokio::test)]
+    async fn client_server_e2e_should_remember_server_identity_in_known_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        let known_servers = KnownServers::new(dir.path().join("known_servers"))
+            .with_policy(ServerIdentityPolicy::AcceptNew);
+        let identity = ServerIdentity::generate();
+
+        let (t1, t2) = InmemoryTransport::pair(100);
+        let server_identity = identity.clone();
+        let task = tokio::spawn(async move {
+            Connection::server(
+                t2,
+                &Verifier::none(),
+                Keychain::new(),
+                server_version!(),
+                Some(server_identity),
+            )
+            .await
+        });
+
+        let client = Connection::client_with_known_servers(
+            t1,
+            DummyAuthHandler,
+            server_version!(),
+            &known_servers,
+            "example.com:8080",
+        )
+        .await
+        .expect("Failed to connect from client");
+        task.await.unwrap().expect("Failed to connect from server");
+
+        assert_eq!(
+            client.transport().server_identity(),
+            Some(&identity.public_key())
+        );
+        assert_eq!(
+            known_servers.get("example.com:8080").unwrap(),
+            Some(identity.public_key())
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn client_should_fail_if_server_identity_differs_from_known_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        let known_servers = KnownServers::new(dir.path().join("known_servers"))
+            .with_policy(ServerIdentityPolicy::AcceptNew);
+        known_servers
+            .insert("example.com:8080", &ServerIdentity::generate().public_key())
+            .unwrap();
+
+        let (t1, t2) = InmemoryTransport::pair(100);
+        tokio::spawn(async move {
+            Connection::server(
+                t2,
+                &Verifier::none(),
+                Keychain::new(),
+                server_version!(),
+                Some(ServerIdentity::generate()),
+            )
+            .await
+        });
+
+        let err = Connection::client_with_known_servers(
+            t1,
+            DummyAuthHandler,
+            server_version!(),
+            &known_servers,
+            "example.com:8080",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
     /// Helper utility to set up for a client reconnection
     async fn setup_reconnect_scenario() -> (
         Connection<InmemoryTransport>,
@@ -1234,7 +1432,7 @@ mod tests {
             let verifier = Arc::clone(&verifier);
             let keychain = keychain.clone();
             tokio::spawn(async move {
-                Connection::server(t2, &verifier, keychain, server_version!())
+                Connection::server(t2, &verifier, keychain, server_version!(), None)
                     .await
                     .expect("Failed to connect from server")
             })
@@ -1461,7 +1659,7 @@ mod tests {
 
         // Spawn a task to perform the server reconnection so we don't deadlock
         let task = tokio::spawn(async move {
-            Connection::server(transport, &verifier, keychain, server_version!())
+            Connection::server(transport, &verifier, keychain, server_version!(), None)
                 .await
                 .expect("Failed to connect from server")
         });
@@ -0,0 +1,419 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+use crate::auth::AuthHandler;
+use crate::auth::msg::{Verification, VerificationKind};
+use crate::net::common::ServerPublicKey;
+
+/// Policy applied when checking a server's identity against the [`KnownServers`].
+#[derive(
+    Copy, Clone, Debug, Default, Display, EnumString, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum ServerIdentityPolicy {
+    /// Ask whether to trust a server that is not known yet, remembering it if trusted.
+    #[default]
+    Ask,
+
+    /// Trust and remember a server that is not known yet without asking.
+    AcceptNew,
+
+    /// Refuse to connect to a server that is not known yet.
+    Strict,
+
+    /// Do not check the identity of servers.
+    Off,
+}
+
+/// File of server identities that have been trusted before, with each line in the form
+/// `<host>:<port> <algorithm> <base64 key>`. Blank lines and lines starting with `#` are ignored.
+///
+/// Servers are trusted on first use: once a server's identity is remembered, connecting to a
+/// server at the same address with a different identity fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KnownServers {
+    path: PathBuf,
+    policy: ServerIdentityPolicy,
+}
+
+impl KnownServers {
+    /// Creates a new instance backed by the file at `path`, asking before trusting new servers.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            policy: ServerIdentityPolicy::default(),
+        }
+    }
+
+    /// Returns a copy of this instance that applies `policy` when checking servers.
+    pub fn with_policy(self, policy: ServerIdentityPolicy) -> Self {
+        Self {
+            path: self.path,
+            policy,
+        }
+    }
+
+    /// Returns the path to the file of known servers.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the policy applied when checking servers.
+    pub fn policy(&self) -> ServerIdentityPolicy {
+        self.policy
+    }
+
+    /// Looks up the remembered identity of the server at `host`, returning `None` if the server
+    /// is not known or the file does not exist.
+    pub fn get(&self, host: &str) -> io::Result<Option<ServerPublicKey>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(x) if x.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(x) => return Err(x),
+        };
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some((name, key)) = line.split_once(char::is_whitespace)
+                && name == host
+            {
+                return key.parse().map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Remembers `key` as the identity of the server at `host`, creating the file if needed.
+    pub fn insert(&self, host: &str, key: &ServerPublicKey) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{host} {key}")
+    }
+
+    /// Checks the `key` presented by the server at `host` against the known servers according to
+    /// the policy, using `handler` to ask whether to trust a new server.
+    pub async fn verify(
+        &self,
+        host: &str,
+        key: Option<&ServerPublicKey>,
+        handler: &mut dyn AuthHandler,
+    ) -> io::Result<()> {
+        if self.policy == ServerIdentityPolicy::Off {
+            return Ok(());
+        }
+
+        let denied = |msg: String| io::Error::new(io::ErrorKind::PermissionDenied, msg);
+        let key = match (self.get(host)?, key) {
+            (Some(known), Some(key)) if known == *key => {
+                debug!("Server {host} matches known identity {}", key.fingerprint());
+                return Ok(());
+            }
+            (Some(known), Some(key)) => {
+                return Err(denied(format!(
+                    "Identity of server {host} has changed from {} to {}! If this is expected, \
+                     remove its entry from {}",
+                    known.fingerprint(),
+                    key.fingerprint(),
+                    self.path.display(),
+                )));
+            }
+            (Some(_), None) => {
+                return Err(denied(format!(
+                    "Server {host} did not present the identity it is known by"
+                )));
+            }
+            (None, None) if self.policy == ServerIdentityPolicy::Strict => {
+                return Err(denied(format!("Server {host} did not present an identity")));
+            }
+            (None, None) => {
+                warn!("Server {host} did not present an identity, so it cannot be verified");
+                return Ok(());
+            }
+            (None, Some(key)) => key,
+        };
+
+        let trusted = match self.policy {
+            ServerIdentityPolicy::Ask => {
+                handler
+                    .on_verification(Verification {
+                        kind: VerificationKind::Host,
+                        text: format!(
+                            "The authenticity of server '{host}' can't be established.\n\
+                             Its identity key fingerprint is {}.\n\
+                             Are you sure you want to trust this server?",
+                            key.fingerprint()
+                        ),
+                    })
+                    .await?
+                    .valid
+            }
+            ServerIdentityPolicy::AcceptNew => true,
+            ServerIdentityPolicy::Strict | ServerIdentityPolicy::Off => false,
+        };
+
+        if !trusted {
+            return Err(denied(format!(
+                "Server {host} with identity {} is not trusted",
+                key.fingerprint()
+            )));
+        }
+
+        info!(
+            "Adding server {host} with identity {} to {}",
+            key.fingerprint(),
+            self.path.display()
+        );
+        self.insert(host, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+
+    use test_log::test;
+
+    use super::*;
+    use crate::auth::msg::{Challenge, ChallengeResponse, Error, Info, VerificationResponse};
+    use crate::auth::{AuthMethodHandler, DummyAuthHandler};
+    use crate::net::common::ServerIdentity;
+
+    /// Answers host verifications with a fixed response, recording the text asked.
+    struct VerifyHandler {
+        valid: bool,
+        asked: Vec<String>,
+    }
+
+    impl AuthHandler for VerifyHandler {}
+
+    impl AuthMethodHandler for VerifyHandler {
+        fn on_challenge<'a>(
+            &'a mut self,
+            _: Challenge,
+        ) -> Pin<Box<dyn Future<Output = io::Result<ChallengeResponse>> + Send + 'a>> {
+            Box::pin(async move { Err(io::Error::from(io::ErrorKind::Unsupported)) })
+        }
+
+        fn on_verification<'a>(
+            &'a mut self,
+            verification: Verification,
+        ) -> Pin<Box<dyn Future<Output = io::Result<VerificationResponse>> + Send + 'a>> {
+            Box::pin(async move {
+                self.asked.push(verification.text);
+                Ok(VerificationResponse { valid: self.valid })
+            })
+        }
+
+        fn on_info<'a>(
+            &'a mut self,
+            _: Info,
+        ) -> Pin<Box<dyn Future<Output = io::Result<()>> + Send + 'a>> {
+            Box::pin(async move { Ok(()) })
+        }
+
+        fn on_error<'a>(
+            &'a mut self,
+            _: Error,
+        ) -> Pin<Box<dyn Future<Output = io::Result<()>> + Send + 'a>> {
+            Box::pin(async move { Ok(()) })
+        }
+    }
+
+    fn known_servers(dir: &tempfile::TempDir, policy: ServerIdentityPolicy) -> KnownServers {
+        KnownServers::new(dir.path().join("known_servers")).with_policy(policy)
+    }
+
+    #[test]
+    fn policy_should_parse_from_kebab_case() {
+        assert_eq!(
+            "accept-new".parse::<ServerIdentityPolicy>().unwrap(),
+            ServerIdentityPolicy::AcceptNew
+        );
+        assert_eq!(ServerIdentityPolicy::AcceptNew.to_string(), "accept-new");
+        assert!("sometimes".parse::<ServerIdentityPolicy>().is_err());
+    }
+
+    #[test]
+    fn get_should_return_none_if_file_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::Ask);
+        assert_eq!(known.get("example.com:8080").unwrap(), None);
+    }
+
+    #[test]
+    fn insert_should_make_identity_available_to_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::Ask);
+        let a = ServerIdentity::generate().public_key();
+        let b = ServerIdentity::generate().public_key();
+
+        known.insert("a:1", &a).unwrap();
+        known.insert("b:2", &b).unwrap();
+
+        assert_eq!(known.get("a:1").unwrap(), Some(a));
+        assert_eq!(known.get("b:2").unwrap(), Some(b));
+        assert_eq!(known.get("c:3").unwrap(), None);
+    }
+
+    #[test]
+    fn get_should_skip_comments_and_blank_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::Ask);
+        let key = ServerIdentity::generate().public_key();
+        fs::write(known.path(), format!("# comment\n\nhost:1 {key}\n")).unwrap();
+
+        assert_eq!(known.get("host:1").unwrap(), Some(key));
+    }
+
+    #[test(tokio::test)]
+    async fn verify_should_ask_and_remember_new_server_if_trusted() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::Ask);
+        let key = ServerIdentity::generate().public_key();
+        let mut handler = VerifyHandler {
+            valid: true,
+            asked: Vec::new(),
+        };
+
+        known
+            .verify("host:1", Some(&key), &mut handler)
+            .await
+            .unwrap();
+        assert_eq!(handler.asked.len(), 1);
+        assert!(handler.asked[0].contains(&key.fingerprint()));
+        assert_eq!(known.get("host:1").unwrap(), Some(key));
+
+        // Once known, the server is not asked about again
+        known
+            .verify("host:1", Some(&key), &mut handler)
+            .await
+            .unwrap();
+        assert_eq!(handler.asked.len(), 1);
+    }
+
+    #[test(tokio::test)]
+    async fn verify_should_fail_and_not_remember_new_server_if_not_trusted() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::Ask);
+        let key = ServerIdentity::generate().public_key();
+        let mut handler = VerifyHandler {
+            valid: false,
+            asked: Vec::new(),
+        };
+
+        let err = known
+            .verify("host:1", Some(&key), &mut handler)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(known.get("host:1").unwrap(), None);
+    }
+
+    #[test(tokio::test)]
+    async fn verify_should_remember_new_server_without_asking_if_accepting_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::AcceptNew);
+        let key = ServerIdentity::generate().public_key();
+
+        known
+            .verify("host:1", Some(&key), &mut DummyAuthHandler)
+            .await
+            .unwrap();
+        assert_eq!(known.get("host:1").unwrap(), Some(key));
+    }
+
+    #[test(tokio::test)]
+    async fn verify_should_refuse_new_server_if_strict() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::Strict);
+        let key = ServerIdentity::generate().public_key();
+
+        let err = known
+            .verify("host:1", Some(&key), &mut DummyAuthHandler)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(known.get("host:1").unwrap(), None);
+    }
+
+    #[test(tokio::test)]
+    async fn verify_should_fail_if_identity_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::AcceptNew);
+        let old = ServerIdentity::generate().public_key();
+        let new = ServerIdentity::generate().public_key();
+        known.insert("host:1", &old).unwrap();
+
+        let err = known
+            .verify("host:1", Some(&new), &mut DummyAuthHandler)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(err.to_string().contains("has changed"), "{err}");
+    }
+
+    #[test(tokio::test)]
+    async fn verify_should_fail_if_known_server_presents_no_identity() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::AcceptNew);
+        known
+            .insert("host:1", &ServerIdentity::generate().public_key())
+            .unwrap();
+
+        let err = known
+            .verify("host:1", None, &mut DummyAuthHandler)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test(tokio::test)]
+    async fn verify_should_allow_unknown_server_without_identity_unless_strict() {
+        let dir = tempfile::tempdir().unwrap();
+
+        known_servers(&dir, ServerIdentityPolicy::Ask)
+            .verify("host:1", None, &mut DummyAuthHandler)
+            .await
+            .unwrap();
+
+        known_servers(&dir, ServerIdentityPolicy::Strict)
+            .verify("host:1", None, &mut DummyAuthHandler)
+            .await
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn verify_should_skip_checks_if_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = known_servers(&dir, ServerIdentityPolicy::Off);
+        known
+            .insert("host:1", &ServerIdentity::generate().public_key())
+            .unwrap();
+
+        known
+            .verify(
+                "host:1",
+                Some(&ServerIdentity::generate().public_key()),
+                &mut DummyAuthHandler,
+            )
+            .await
+            .unwrap();
+    }
+}
@@ -10,9 +10,18 @@ pub use mpsc::*;
 mod oneshot;
 pub use oneshot::*;
 
+mod quic;
+pub use quic::*;
+
 mod tcp;
 pub use tcp::*;
 
+mod tls;
+pub use tls::*;
+
+mod websocket;
+pub use websocket::*;
+
 #[cfg(unix)]
 mod unix;
 
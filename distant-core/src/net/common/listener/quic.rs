@@ -0,0 +1,181 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fmt, io};
+
+use log::*;
+use quinn::{Endpoint, EndpointConfig, TokioRuntime};
+use tokio::task::JoinHandle;
+
+use super::{Listener, MpscListener};
+use crate::net::common::{PortRange, QuicTransport, quic_server_config};
+
+/// Maximum time a new connection has to complete the QUIC handshake and open its stream.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Represents a [`Listener`] for incoming QUIC connections over UDP.
+///
+/// Handshakes are performed in the background so that a slow or misbehaving connection neither
+/// holds up nor stops the acceptance of others.
+pub struct QuicListener {
+    addr: IpAddr,
+    port: u16,
+    endpoint: Endpoint,
+    inner: MpscListener<QuicTransport>,
+    task: JoinHandle<()>,
+}
+
+impl QuicListener {
+    /// Creates a new listener by binding to the specified IP address and port
+    /// in the given port range
+    pub async fn bind(addr: IpAddr, port: impl Into<PortRange>) -> io::Result<Self> {
+        let socket = std::net::UdpSocket::bind(port.into().make_socket_addrs(addr).as_slice())?;
+        let port = socket.local_addr()?.port();
+        let endpoint = Endpoint::new(
+            EndpointConfig::default(),
+            Some(quic_server_config()?),
+            socket,
+            Arc::new(TokioRuntime),
+        )?;
+        let (tx, inner) = MpscListener::channel(1);
+
+        let task = tokio::spawn({
+            let endpoint = endpoint.clone();
+            async move {
+                while let Some(incoming) = endpoint.accept().await {
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let peer = incoming.remote_address();
+                        let result = tokio::time::timeout(HANDSHAKE_TIMEOUT, async move {
+                            QuicTransport::accept(incoming.await?).await
+                        })
+                        .await;
+
+                        match result {
+                            Ok(Ok(transport)) => {
+                                let _ = tx.send(transport).await;
+                            }
+                            Ok(Err(x)) => warn!("QUIC handshake with {peer} failed: {x}"),
+                            Err(_) => warn!("QUIC handshake with {peer} timed out"),
+                        }
+                    });
+                }
+
+                error!("QUIC listener no longer accepting connections");
+            }
+        });
+
+        Ok(Self {
+            addr,
+            port,
+            endpoint,
+            inner,
+            task,
+        })
+    }
+
+    /// Returns the IP address that the listener is bound to
+    pub fn ip_addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the port that the listener is bound to
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for QuicListener {
+    fn drop(&mut self) {
+        // Refuse new connections while leaving established ones running
+        self.endpoint.set_server_config(None);
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for QuicListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuicListener")
+            .field("addr", &self.addr)
+            .field("port", &self.port)
+            .finish()
+    }
+}
+
+impl Listener for QuicListener {
+    type Output = QuicTransport;
+
+    async fn accept(&mut self) -> io::Result<Self::Output> {
+        self.inner.accept().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use test_log::test;
+
+    use super::*;
+    use crate::net::common::{FramedTransport, quic_client_config};
+
+    #[test(tokio::test)]
+    async fn should_fail_to_bind_if_port_already_bound() {
+        let addr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let listener = QuicListener::bind(addr, 0).await.expect("Failed to bind");
+
+        QuicListener::bind(addr, listener.port())
+            .await
+            .expect_err("Unexpectedly bound to port already in use");
+    }
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_receive_connections_and_read_and_write_data_with_them() {
+        let mut listener = QuicListener::bind(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to bind");
+        let port = listener.port();
+
+        let client = tokio::spawn(async move {
+            let transport = QuicTransport::connect((Ipv4Addr::LOCALHOST, port))
+                .await
+                .unwrap();
+            let mut transport = FramedTransport::plain(transport);
+            transport.write_frame(b"hello server").await.unwrap();
+
+            let frame = transport.read_frame().await.unwrap().unwrap();
+            assert_eq!(frame.as_item(), b"hello conn");
+        });
+
+        let conn = listener.accept().await.expect("Failed to accept");
+        let mut conn = FramedTransport::plain(conn);
+        let frame = conn.read_frame().await.unwrap().unwrap();
+        assert_eq!(frame.as_item(), b"hello server");
+        conn.write_frame(b"hello conn").await.unwrap();
+
+        client.await.expect("Client failed unexpectedly");
+    }
+
+    #[test(tokio::test)]
+    async fn should_keep_accepting_connections_after_a_failed_handshake() {
+        let mut listener = QuicListener::bind(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to bind");
+        let port = listener.port();
+
+        // Complete the QUIC handshake, but open a stream that is not for distant
+        let mut endpoint = Endpoint::client((Ipv4Addr::UNSPECIFIED, 0).into()).unwrap();
+        endpoint.set_default_client_config(quic_client_config().unwrap());
+        let connection = endpoint
+            .connect((Ipv4Addr::LOCALHOST, port).into(), "distant")
+            .unwrap()
+            .await
+            .unwrap();
+        let (mut send, _recv) = connection.open_bi().await.unwrap();
+        send.write_all(b"invalid").await.unwrap();
+
+        let client = tokio::spawn(QuicTransport::connect((Ipv4Addr::LOCALHOST, port)));
+        listener.accept().await.expect("Failed to accept");
+        client.await.unwrap().expect("Client failed to connect");
+    }
+}
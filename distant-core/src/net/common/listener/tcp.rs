@@ -4,7 +4,7 @@ use std::{fmt, io};
 use tokio::net::TcpListener as TokioTcpListener;
 
 use super::Listener;
-use crate::net::common::{PortRange, TcpTransport};
+use crate::net::common::{PortRange, TcpTransport, enable_keepalive};
 
 /// Represents a [`Listener`] for incoming connections over TCP
 pub struct TcpListener {
@@ -55,6 +55,7 @@ impl Listener for TcpListener {
 
     async fn accept(&mut self) -> io::Result<Self::Output> {
         let (stream, peer_addr) = TokioTcpListener::accept(&self.inner).await?;
+        enable_keepalive(&stream);
         Ok(TcpTransport {
             addr: peer_addr.ip(),
             port: peer_addr.port(),
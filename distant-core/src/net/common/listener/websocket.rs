@@ -0,0 +1,163 @@
+use std::net::IpAddr;
+use std::time::Duration;
+use std::{fmt, io};
+
+use log::*;
+use tokio::task::JoinHandle;
+
+use super::{Listener, MpscListener, TcpListener};
+use crate::net::common::{PortRange, WebSocketTransport};
+
+/// Maximum time a new connection has to complete the WebSocket handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Represents a [`Listener`] for incoming WebSocket connections, which can be fronted by an
+/// HTTP reverse proxy such as nginx.
+///
+/// Handshakes are performed in the background so that a slow or misbehaving connection neither
+/// holds up nor stops the acceptance of others.
+pub struct WebSocketListener {
+    addr: IpAddr,
+    port: u16,
+    inner: MpscListener<WebSocketTransport>,
+    task: JoinHandle<()>,
+}
+
+impl WebSocketListener {
+    /// Creates a new listener by binding to the specified IP address and port
+    /// in the given port range
+    pub async fn bind(addr: IpAddr, port: impl Into<PortRange>) -> io::Result<Self> {
+        let mut listener = TcpListener::bind(addr, port).await?;
+        let port = listener.port();
+        let (tx, inner) = MpscListener::channel(1);
+
+        let task = tokio::spawn(async move {
+            loop {
+                let transport = match listener.accept().await {
+                    Ok(transport) => transport,
+                    Err(x) => {
+                        error!("WebSocket listener no longer accepting connections: {x}");
+                        break;
+                    }
+                };
+
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let peer = format!("{}:{}", transport.ip_addr(), transport.port());
+                    let result = tokio::time::timeout(
+                        HANDSHAKE_TIMEOUT,
+                        WebSocketTransport::accept(transport.inner),
+                    )
+                    .await;
+
+                    match result {
+                        Ok(Ok(transport)) => {
+                            let _ = tx.send(transport).await;
+                        }
+                        Ok(Err(x)) => warn!("WebSocket handshake with {peer} failed: {x}"),
+                        Err(_) => warn!("WebSocket handshake with {peer} timed out"),
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            addr,
+            port,
+            inner,
+            task,
+        })
+    }
+
+    /// Returns the IP address that the listener is bound to
+    pub fn ip_addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the port that the listener is bound to
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for WebSocketListener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for WebSocketListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketListener")
+            .field("addr", &self.addr)
+            .field("port", &self.port)
+            .finish()
+    }
+}
+
+impl Listener for WebSocketListener {
+    type Output = WebSocketTransport;
+
+    async fn accept(&mut self) -> io::Result<Self::Output> {
+        self.inner.accept().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use test_log::test;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpStream;
+
+    use super::*;
+    use crate::net::common::TransportExt;
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_receive_connections_and_read_and_write_data_with_them() {
+        let mut listener = WebSocketListener::bind(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to bind");
+        let url = format!("ws://127.0.0.1:{}", listener.port());
+
+        let client = tokio::spawn(async move {
+            let transport = WebSocketTransport::connect(url).await.unwrap();
+            transport.write_all(b"hello server").await.unwrap();
+
+            let mut buf = [0u8; 10];
+            transport.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello conn");
+        });
+
+        let conn = listener.accept().await.expect("Failed to accept");
+        let mut buf = [0u8; 12];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello server");
+        conn.write_all(b"hello conn").await.unwrap();
+
+        client.await.expect("Client failed unexpectedly");
+    }
+
+    #[test(tokio::test)]
+    async fn should_keep_accepting_connections_after_a_failed_handshake() {
+        let mut listener = WebSocketListener::bind(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to bind");
+        let port = listener.port();
+
+        // Send something that is not a websocket handshake and hang up
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        drop(stream);
+
+        let client = tokio::spawn(WebSocketTransport::connect(format!(
+            "ws://127.0.0.1:{port}"
+        )));
+        listener.accept().await.expect("Failed to accept");
+        client.await.unwrap().expect("Client failed to connect");
+    }
+}
@@ -1,3 +1,4 @@
+mod format;
 mod header;
 mod request;
 mod response;
@@ -5,6 +6,7 @@ mod value;
 
 use std::io::Cursor;
 
+pub use format::*;
 pub use header::*;
 pub use request::*;
 pub use response::*;
@@ -252,6 +254,23 @@ fn read_key_eq<'a>(input: &'a [u8], key: &str) -> Result<((), &'a [u8]), &'a [u8
     }
 }
 
+/// Reads the str key of a field from msgpack input when it is in the [`PacketFormat::Map`]
+/// format, checking that it matches `key`. Input in the [`PacketFormat::Compact`] format has no
+/// keys, so it is returned as is.
+///
+/// * If key read successfully and matches (or there is no key), returns (unit, remaining).
+/// * Otherwise, returns existing bytes.
+fn read_field_key<'a>(
+    input: &'a [u8],
+    key: &str,
+    format: PacketFormat,
+) -> Result<((), &'a [u8]), &'a [u8]> {
+    match format {
+        PacketFormat::Map => read_key_eq(input, key),
+        PacketFormat::Compact => Ok(((), input)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
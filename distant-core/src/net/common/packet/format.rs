@@ -0,0 +1,68 @@
+/// Represents how the fields of a [`Request`] or [`Response`] are laid out when sent over the
+/// wire, where both formats can always be parsed, but only [`PacketFormat::Map`] is understood
+/// by older peers.
+///
+/// [`Request`]: super::Request
+/// [`Response`]: super::Response
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PacketFormat {
+    /// Fields are written as a msgpack map keyed by the name of each field
+    #[default]
+    Map,
+
+    /// Fields are written as a msgpack array in a fixed order, leaving out the names of the
+    /// fields, which is only used once both sides negotiate it during the handshake
+    Compact,
+}
+
+impl PacketFormat {
+    /// Returns the format of the msgpack packet in `input` based on its first byte, or `None` if
+    /// it is neither a map nor an array.
+    pub fn of(input: &[u8]) -> Option<Self> {
+        match rmp::Marker::from_u8(*input.first()?) {
+            rmp::Marker::FixMap(_) | rmp::Marker::Map16 | rmp::Marker::Map32 => Some(Self::Map),
+            rmp::Marker::FixArray(_) | rmp::Marker::Array16 | rmp::Marker::Array32 => {
+                Some(Self::Compact)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if this is the compact format
+    pub fn is_compact(&self) -> bool {
+        matches!(self, Self::Compact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for PacketFormat: detecting the format of msgpack input from its marker.
+
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn of_should_detect_map_packets() {
+        assert_eq!(PacketFormat::of(&[0x82]), Some(PacketFormat::Map));
+        assert_eq!(PacketFormat::of(&[0xde, 0, 2]), Some(PacketFormat::Map));
+    }
+
+    #[test]
+    fn of_should_detect_compact_packets() {
+        assert_eq!(PacketFormat::of(&[0x92]), Some(PacketFormat::Compact));
+        assert_eq!(PacketFormat::of(&[0xdc, 0, 2]), Some(PacketFormat::Compact));
+    }
+
+    #[test]
+    fn of_should_return_none_for_other_input() {
+        assert_eq!(PacketFormat::of(&[]), None);
+        assert_eq!(PacketFormat::of(&[0xc3]), None);
+    }
+
+    #[test]
+    fn default_should_be_map() {
+        assert_eq!(PacketFormat::default(), PacketFormat::Map);
+        assert!(!PacketFormat::default().is_compact());
+    }
+}
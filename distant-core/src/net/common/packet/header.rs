@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 use std::{fmt, io};
 
 use derive_more::IntoIterator;
@@ -28,6 +29,9 @@ macro_rules! header {
     }};
 }
 
+/// Key of the header entry holding the deadline of a request.
+const DEADLINE_KEY: &str = "deadline";
+
 /// Represents a packet header comprised of arbitrary data tied to string keys.
 #[derive(Clone, Debug, Default, PartialEq, Eq, IntoIterator, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -68,6 +72,20 @@ impl Header {
             .map(|value| value.clone().cast_as())
     }
 
+    /// Sets the deadline of a request, which is the time in milliseconds that the server has to
+    /// respond, measured from when the server receives the request. A relative time is used
+    /// because the clocks of the client and server are not assumed to agree.
+    pub fn set_deadline(&mut self, timeout: Duration) {
+        let millis = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        self.insert(DEADLINE_KEY, millis);
+    }
+
+    /// Retrieves the deadline of a request set via [`Header::set_deadline`], if there is one.
+    pub fn deadline(&self) -> Option<io::Result<Duration>> {
+        self.get_as(DEADLINE_KEY)
+            .map(|millis| millis.map(Duration::from_millis))
+    }
+
     /// Serializes the header into bytes.
     pub fn to_vec(&self) -> io::Result<Vec<u8>> {
         utils::serialize_to_vec(self)
@@ -141,6 +159,42 @@ mod tests {
         }
     }
 
+    mod deadline {
+        use test_log::test;
+
+        use super::*;
+
+        #[test]
+        fn should_be_none_if_not_set() {
+            assert!(Header::new().deadline().is_none());
+        }
+
+        #[test]
+        fn should_return_deadline_that_was_set() {
+            let mut header = Header::new();
+            header.set_deadline(Duration::from_millis(1500));
+            assert_eq!(
+                header.deadline().unwrap().unwrap(),
+                Duration::from_millis(1500)
+            );
+        }
+
+        #[test]
+        fn should_survive_msgpack_round_trip() {
+            let mut header = Header::new();
+            header.set_deadline(Duration::from_secs(3));
+            let header = Header::from_slice(&header.to_vec().unwrap()).unwrap();
+            assert_eq!(header.deadline().unwrap().unwrap(), Duration::from_secs(3));
+        }
+
+        #[test]
+        fn should_fail_if_value_is_not_milliseconds() {
+            let mut header = Header::new();
+            header.insert("deadline", "soon");
+            assert!(header.deadline().unwrap().is_err());
+        }
+    }
+
     mod insert_and_get_as {
         use test_log::test;
 
@@ -5,7 +5,7 @@ use derive_more::{Display, Error};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use super::{Header, Id, read_header_bytes, read_key_eq, read_str_bytes};
+use super::{Header, Id, PacketFormat, read_field_key, read_header_bytes, read_str_bytes};
 use crate::header;
 use crate::net::common::utils;
 
@@ -43,6 +43,14 @@ where
         utils::serialize_to_vec(self)
     }
 
+    /// Serializes the request into bytes using the given `format`
+    pub fn to_vec_with_format(&self, format: PacketFormat) -> io::Result<Vec<u8>> {
+        match format {
+            PacketFormat::Map => self.to_vec(),
+            PacketFormat::Compact => Ok(self.to_untyped_request()?.to_bytes_with_format(format)),
+        }
+    }
+
     /// Serializes the request's payload into bytes
     pub fn to_payload_vec(&self) -> io::Result<Vec<u8>> {
         utils::serialize_to_vec(&self.payload)
@@ -66,9 +74,14 @@ impl<T> Request<T>
 where
     T: DeserializeOwned,
 {
-    /// Deserializes the request from bytes
+    /// Deserializes the request from bytes in either [`PacketFormat`]
     pub fn from_slice(slice: &[u8]) -> io::Result<Self> {
-        utils::deserialize_from_slice(slice)
+        match PacketFormat::of(slice) {
+            Some(PacketFormat::Compact) => UntypedRequest::from_slice(slice)
+                .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?
+                .to_typed_request(),
+            _ => utils::deserialize_from_slice(slice),
+        }
     }
 }
 
@@ -181,31 +194,43 @@ impl<'a> UntypedRequest<'a> {
 
     /// Allocates a new collection of bytes representing the request.
     pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_format(PacketFormat::Map)
+    }
+
+    /// Allocates a new collection of bytes representing the request in the given `format`.
+    pub fn to_bytes_with_format(&self, format: PacketFormat) -> Vec<u8> {
         let mut bytes = vec![];
 
         let has_header = !header_is_empty(&self.header);
-        if has_header {
-            rmp::encode::write_map_len(&mut bytes, 3).unwrap();
-        } else {
-            rmp::encode::write_map_len(&mut bytes, 2).unwrap();
-        }
+        let len = if has_header { 3 } else { 2 };
+        match format {
+            PacketFormat::Map => rmp::encode::write_map_len(&mut bytes, len).unwrap(),
+            PacketFormat::Compact => rmp::encode::write_array_len(&mut bytes, len).unwrap(),
+        };
+
+        // Only the map format names each field ahead of its value
+        let write_key = |bytes: &mut Vec<u8>, key: &str| {
+            if !format.is_compact() {
+                rmp::encode::write_str(bytes, key).unwrap();
+            }
+        };
 
         if has_header {
-            rmp::encode::write_str(&mut bytes, "header").unwrap();
+            write_key(&mut bytes, "header");
             bytes.extend_from_slice(&self.header);
         }
 
-        rmp::encode::write_str(&mut bytes, "id").unwrap();
+        write_key(&mut bytes, "id");
         rmp::encode::write_str(&mut bytes, &self.id).unwrap();
 
-        rmp::encode::write_str(&mut bytes, "payload").unwrap();
+        write_key(&mut bytes, "payload");
         bytes.extend_from_slice(&self.payload);
 
         bytes
     }
 
-    /// Parses a collection of bytes, returning a partial request if it can be potentially
-    /// represented as a [`Request`] depending on the payload.
+    /// Parses a collection of bytes in either [`PacketFormat`], returning a partial request if it
+    /// can be potentially represented as a [`Request`] depending on the payload.
     ///
     /// NOTE: This supports parsing an invalid request where the payload would not properly
     /// deserialize, but the bytes themselves represent a complete request of some kind.
@@ -214,9 +239,11 @@ impl<'a> UntypedRequest<'a> {
             return Err(UntypedRequestParseError::WrongType);
         }
 
-        let has_header = match rmp::Marker::from_u8(input[0]) {
-            rmp::Marker::FixMap(2) => false,
-            rmp::Marker::FixMap(3) => true,
+        let (format, has_header) = match rmp::Marker::from_u8(input[0]) {
+            rmp::Marker::FixMap(2) => (PacketFormat::Map, false),
+            rmp::Marker::FixMap(3) => (PacketFormat::Map, true),
+            rmp::Marker::FixArray(2) => (PacketFormat::Compact, false),
+            rmp::Marker::FixArray(3) => (PacketFormat::Compact, true),
             _ => return Err(UntypedRequestParseError::WrongType),
         };
 
@@ -225,7 +252,7 @@ impl<'a> UntypedRequest<'a> {
 
         // Parse the header if we have one
         let (header, input) = if has_header {
-            let (_, input) = read_key_eq(input, "header")
+            let (_, input) = read_field_key(input, "header", format)
                 .map_err(|_| UntypedRequestParseError::InvalidHeaderKey)?;
 
             let (header, input) =
@@ -236,14 +263,14 @@ impl<'a> UntypedRequest<'a> {
         };
 
         // Validate that next field is id
-        let (_, input) =
-            read_key_eq(input, "id").map_err(|_| UntypedRequestParseError::InvalidIdKey)?;
+        let (_, input) = read_field_key(input, "id", format)
+            .map_err(|_| UntypedRequestParseError::InvalidIdKey)?;
 
         // Get the id itself
         let (id, input) = read_str_bytes(input).map_err(|_| UntypedRequestParseError::InvalidId)?;
 
         // Validate that final field is payload
-        let (_, input) = read_key_eq(input, "payload")
+        let (_, input) = read_field_key(input, "payload", format)
             .map_err(|_| UntypedRequestParseError::InvalidPayloadKey)?;
 
         let header = Cow::Borrowed(header);
@@ -587,4 +614,102 @@ mod tests {
             Err(UntypedRequestParseError::InvalidPayloadKey)
         );
     }
+
+    #[test]
+    fn untyped_request_should_support_converting_to_compact_bytes() {
+        let request = UntypedRequest {
+            header: Cow::Borrowed(&[]),
+            id: Cow::Borrowed("test"),
+            payload: Cow::Borrowed(&[TRUE_BYTE]),
+        };
+
+        assert_eq!(
+            request.to_bytes_with_format(PacketFormat::Compact),
+            [&[0x92], TEST_STR_BYTES, &[TRUE_BYTE]].concat()
+        );
+    }
+
+    #[test]
+    fn untyped_request_should_support_converting_to_compact_bytes_with_header() {
+        let request = UntypedRequest {
+            header: Cow::Borrowed(HEADER_BYTES),
+            id: Cow::Borrowed("test"),
+            payload: Cow::Borrowed(&[TRUE_BYTE]),
+        };
+
+        assert_eq!(
+            request.to_bytes_with_format(PacketFormat::Compact),
+            [&[0x93], HEADER_BYTES, TEST_STR_BYTES, &[TRUE_BYTE]].concat()
+        );
+    }
+
+    #[test]
+    fn untyped_request_compact_bytes_should_be_smaller_than_map_bytes() {
+        let request = UntypedRequest {
+            header: Cow::Borrowed(HEADER_BYTES),
+            id: Cow::Borrowed("test"),
+            payload: Cow::Borrowed(&[TRUE_BYTE]),
+        };
+
+        let map_len = request.to_bytes().len();
+        let compact_len = request.to_bytes_with_format(PacketFormat::Compact).len();
+        assert_eq!(
+            map_len - compact_len,
+            HEADER_FIELD_BYTES.len() + ID_FIELD_BYTES.len() + PAYLOAD_FIELD_BYTES.len()
+        );
+    }
+
+    #[test]
+    fn untyped_request_should_support_parsing_compact_bytes() {
+        for header in [&[][..], HEADER_BYTES] {
+            let request = UntypedRequest {
+                header: Cow::Borrowed(header),
+                id: Cow::Borrowed("test"),
+                payload: Cow::Borrowed(&[TRUE_BYTE]),
+            };
+
+            let bytes = request.to_bytes_with_format(PacketFormat::Compact);
+            assert_eq!(UntypedRequest::from_slice(&bytes), Ok(request));
+        }
+    }
+
+    #[test]
+    fn untyped_request_should_fail_to_parse_compact_bytes_with_wrong_fields() {
+        // Wrong starting byte (fixarray of 0 fields)
+        assert_eq!(
+            UntypedRequest::from_slice(&[0x90]),
+            Err(UntypedRequestParseError::WrongType)
+        );
+
+        // Invalid header bytes
+        assert_eq!(
+            UntypedRequest::from_slice(
+                [&[0x93], TEST_STR_BYTES, TEST_STR_BYTES, &[TRUE_BYTE]]
+                    .concat()
+                    .as_slice()
+            ),
+            Err(UntypedRequestParseError::InvalidHeader)
+        );
+
+        // Non-str id field value
+        assert_eq!(
+            UntypedRequest::from_slice(&[0x92, TRUE_BYTE, TRUE_BYTE]),
+            Err(UntypedRequestParseError::InvalidId)
+        );
+    }
+
+    #[test]
+    fn typed_request_should_round_trip_through_either_format() {
+        let original = Request {
+            header: header!("key" -> 123),
+            id: "test-id".to_string(),
+            payload: true,
+        };
+
+        for format in [PacketFormat::Map, PacketFormat::Compact] {
+            let bytes = original.to_vec_with_format(format).unwrap();
+            assert_eq!(PacketFormat::of(&bytes), Some(format));
+            assert_eq!(Request::<bool>::from_slice(&bytes).unwrap(), original);
+        }
+    }
 }
@@ -5,7 +5,7 @@ use derive_more::{Display, Error};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use super::{Header, Id, read_header_bytes, read_key_eq, read_str_bytes};
+use super::{Header, Id, PacketFormat, read_field_key, read_header_bytes, read_str_bytes};
 use crate::header;
 use crate::net::common::utils;
 
@@ -47,6 +47,14 @@ where
         utils::serialize_to_vec(self)
     }
 
+    /// Serializes the response into bytes using the given `format`
+    pub fn to_vec_with_format(&self, format: PacketFormat) -> io::Result<Vec<u8>> {
+        match format {
+            PacketFormat::Map => self.to_vec(),
+            PacketFormat::Compact => Ok(self.to_untyped_response()?.to_bytes_with_format(format)),
+        }
+    }
+
     /// Serializes the response's payload into bytes
     pub fn to_payload_vec(&self) -> io::Result<Vec<u8>> {
         utils::serialize_to_vec(&self.payload)
@@ -71,9 +79,14 @@ impl<T> Response<T>
 where
     T: DeserializeOwned,
 {
-    /// Deserializes the response from bytes
+    /// Deserializes the response from bytes in either [`PacketFormat`]
     pub fn from_slice(slice: &[u8]) -> std::io::Result<Self> {
-        utils::deserialize_from_slice(slice)
+        match PacketFormat::of(slice) {
+            Some(PacketFormat::Compact) => UntypedResponse::from_slice(slice)
+                .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?
+                .to_typed_response(),
+            _ => utils::deserialize_from_slice(slice),
+        }
     }
 }
 
@@ -203,34 +216,46 @@ impl<'a> UntypedResponse<'a> {
 
     /// Allocates a new collection of bytes representing the response.
     pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_format(PacketFormat::Map)
+    }
+
+    /// Allocates a new collection of bytes representing the response in the given `format`.
+    pub fn to_bytes_with_format(&self, format: PacketFormat) -> Vec<u8> {
         let mut bytes = vec![];
 
         let has_header = !header_is_empty(&self.header);
-        if has_header {
-            rmp::encode::write_map_len(&mut bytes, 4).unwrap();
-        } else {
-            rmp::encode::write_map_len(&mut bytes, 3).unwrap();
-        }
+        let len = if has_header { 4 } else { 3 };
+        match format {
+            PacketFormat::Map => rmp::encode::write_map_len(&mut bytes, len).unwrap(),
+            PacketFormat::Compact => rmp::encode::write_array_len(&mut bytes, len).unwrap(),
+        };
+
+        // Only the map format names each field ahead of its value
+        let write_key = |bytes: &mut Vec<u8>, key: &str| {
+            if !format.is_compact() {
+                rmp::encode::write_str(bytes, key).unwrap();
+            }
+        };
 
         if has_header {
-            rmp::encode::write_str(&mut bytes, "header").unwrap();
+            write_key(&mut bytes, "header");
             bytes.extend_from_slice(&self.header);
         }
 
-        rmp::encode::write_str(&mut bytes, "id").unwrap();
+        write_key(&mut bytes, "id");
         rmp::encode::write_str(&mut bytes, &self.id).unwrap();
 
-        rmp::encode::write_str(&mut bytes, "origin_id").unwrap();
+        write_key(&mut bytes, "origin_id");
         rmp::encode::write_str(&mut bytes, &self.origin_id).unwrap();
 
-        rmp::encode::write_str(&mut bytes, "payload").unwrap();
+        write_key(&mut bytes, "payload");
         bytes.extend_from_slice(&self.payload);
 
         bytes
     }
 
-    /// Parses a collection of bytes, returning an untyped response if it can be potentially
-    /// represented as a [`Response`] depending on the payload.
+    /// Parses a collection of bytes in either [`PacketFormat`], returning an untyped response if
+    /// it can be potentially represented as a [`Response`] depending on the payload.
     ///
     /// NOTE: This supports parsing an invalid response where the payload would not properly
     /// deserialize, but the bytes themselves represent a complete response of some kind.
@@ -239,9 +264,11 @@ impl<'a> UntypedResponse<'a> {
             return Err(UntypedResponseParseError::WrongType);
         }
 
-        let has_header = match rmp::Marker::from_u8(input[0]) {
-            rmp::Marker::FixMap(3) => false,
-            rmp::Marker::FixMap(4) => true,
+        let (format, has_header) = match rmp::Marker::from_u8(input[0]) {
+            rmp::Marker::FixMap(3) => (PacketFormat::Map, false),
+            rmp::Marker::FixMap(4) => (PacketFormat::Map, true),
+            rmp::Marker::FixArray(3) => (PacketFormat::Compact, false),
+            rmp::Marker::FixArray(4) => (PacketFormat::Compact, true),
             _ => return Err(UntypedResponseParseError::WrongType),
         };
 
@@ -250,7 +277,7 @@ impl<'a> UntypedResponse<'a> {
 
         // Parse the header if we have one
         let (header, input) = if has_header {
-            let (_, input) = read_key_eq(input, "header")
+            let (_, input) = read_field_key(input, "header", format)
                 .map_err(|_| UntypedResponseParseError::InvalidHeaderKey)?;
 
             let (header, input) =
@@ -261,15 +288,15 @@ impl<'a> UntypedResponse<'a> {
         };
 
         // Validate that next field is id
-        let (_, input) =
-            read_key_eq(input, "id").map_err(|_| UntypedResponseParseError::InvalidIdKey)?;
+        let (_, input) = read_field_key(input, "id", format)
+            .map_err(|_| UntypedResponseParseError::InvalidIdKey)?;
 
         // Get the id itself
         let (id, input) =
             read_str_bytes(input).map_err(|_| UntypedResponseParseError::InvalidId)?;
 
         // Validate that next field is origin_id
-        let (_, input) = read_key_eq(input, "origin_id")
+        let (_, input) = read_field_key(input, "origin_id", format)
             .map_err(|_| UntypedResponseParseError::InvalidOriginIdKey)?;
 
         // Get the origin_id itself
@@ -277,7 +304,7 @@ impl<'a> UntypedResponse<'a> {
             read_str_bytes(input).map_err(|_| UntypedResponseParseError::InvalidOriginId)?;
 
         // Validate that final field is payload
-        let (_, input) = read_key_eq(input, "payload")
+        let (_, input) = read_field_key(input, "payload", format)
             .map_err(|_| UntypedResponseParseError::InvalidPayloadKey)?;
 
         let header = Cow::Borrowed(header);
@@ -708,4 +735,97 @@ mod tests {
             Err(UntypedResponseParseError::InvalidPayloadKey)
         );
     }
+
+    #[test]
+    fn untyped_response_should_support_converting_to_compact_bytes() {
+        let response = UntypedResponse {
+            header: Cow::Borrowed(&[]),
+            id: Cow::Borrowed("test"),
+            origin_id: Cow::Borrowed("test"),
+            payload: Cow::Borrowed(&[TRUE_BYTE]),
+        };
+
+        assert_eq!(
+            response.to_bytes_with_format(PacketFormat::Compact),
+            [&[0x93], TEST_STR_BYTES, TEST_STR_BYTES, &[TRUE_BYTE]].concat()
+        );
+    }
+
+    #[test]
+    fn untyped_response_should_support_converting_to_compact_bytes_with_header() {
+        let response = UntypedResponse {
+            header: Cow::Borrowed(HEADER_BYTES),
+            id: Cow::Borrowed("test"),
+            origin_id: Cow::Borrowed("test"),
+            payload: Cow::Borrowed(&[TRUE_BYTE]),
+        };
+
+        assert_eq!(
+            response.to_bytes_with_format(PacketFormat::Compact),
+            [
+                &[0x94],
+                HEADER_BYTES,
+                TEST_STR_BYTES,
+                TEST_STR_BYTES,
+                &[TRUE_BYTE]
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn untyped_response_should_support_parsing_compact_bytes() {
+        for header in [&[][..], HEADER_BYTES] {
+            let response = UntypedResponse {
+                header: Cow::Borrowed(header),
+                id: Cow::Borrowed("test"),
+                origin_id: Cow::Borrowed("origin"),
+                payload: Cow::Borrowed(&[TRUE_BYTE]),
+            };
+
+            let bytes = response.to_bytes_with_format(PacketFormat::Compact);
+            assert_eq!(UntypedResponse::from_slice(&bytes), Ok(response));
+        }
+    }
+
+    #[test]
+    fn untyped_response_should_fail_to_parse_compact_bytes_with_wrong_fields() {
+        // Wrong starting byte (fixarray of 2 fields)
+        assert_eq!(
+            UntypedResponse::from_slice(&[0x92]),
+            Err(UntypedResponseParseError::WrongType)
+        );
+
+        // Non-str id field value
+        assert_eq!(
+            UntypedResponse::from_slice(&[0x93, TRUE_BYTE, TRUE_BYTE, TRUE_BYTE]),
+            Err(UntypedResponseParseError::InvalidId)
+        );
+
+        // Non-str origin id field value
+        assert_eq!(
+            UntypedResponse::from_slice(
+                [&[0x93], TEST_STR_BYTES, &[TRUE_BYTE], &[TRUE_BYTE]]
+                    .concat()
+                    .as_slice()
+            ),
+            Err(UntypedResponseParseError::InvalidOriginId)
+        );
+    }
+
+    #[test]
+    fn typed_response_should_round_trip_through_either_format() {
+        let original = Response {
+            header: header!("key" -> 123),
+            id: "test-id".to_string(),
+            origin_id: "origin-id".to_string(),
+            payload: true,
+        };
+
+        for format in [PacketFormat::Map, PacketFormat::Compact] {
+            let bytes = original.to_vec_with_format(format).unwrap();
+            assert_eq!(PacketFormat::of(&bytes), Some(format));
+            assert_eq!(Response::<bool>::from_slice(&bytes).unwrap(), original);
+        }
+    }
 }
@@ -46,6 +46,12 @@ impl PortRange {
         socket_addrs
     }
 
+    /// Returns true if `port` falls within the range, inclusive of both ends.
+    #[inline]
+    pub fn contains(&self, port: u16) -> bool {
+        (self.start..=self.end.unwrap_or(self.start)).contains(&port)
+    }
+
     /// Returns true if port range represents the ephemeral port.
     #[inline]
     pub fn is_ephemeral(&self) -> bool {
@@ -285,6 +291,19 @@ mod tests {
         assert_eq!(p.into_iter().collect::<Vec<u16>>(), vec![1, 2, 3]);
     }
 
+    #[test]
+    fn contains_should_include_both_ends_of_range() {
+        let p = PortRange::single(80);
+        assert!(p.contains(80));
+        assert!(!p.contains(81));
+
+        let p = PortRange::from(3000..=3002);
+        assert!(!p.contains(2999));
+        assert!(p.contains(3000));
+        assert!(p.contains(3002));
+        assert!(!p.contains(3003));
+    }
+
     #[test]
     fn make_socket_addrs_should_produce_a_socket_addr_per_port() {
         let ip_addr = "127.0.0.1".parse::<IpAddr>().unwrap();
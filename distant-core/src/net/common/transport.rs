@@ -9,6 +9,9 @@ pub use framed::*;
 mod inmemory;
 pub use inmemory::*;
 
+mod quic;
+pub use quic::*;
+
 mod tcp;
 pub use tcp::*;
 
@@ -18,6 +21,15 @@ mod test;
 #[cfg(test)]
 pub use test::*;
 
+mod tls;
+pub use tls::*;
+
+#[cfg(test)]
+pub(crate) use tls::test_certs;
+
+mod websocket;
+pub use websocket::*;
+
 #[cfg(unix)]
 mod unix;
 
@@ -65,6 +77,32 @@ pub trait Transport: Reconnectable + fmt::Debug + Send + Sync {
         &'a self,
         interest: Interest,
     ) -> Pin<Box<dyn Future<Output = io::Result<Ready>> + Send + 'a>>;
+
+    /// Returns the identity of the other side of the transport as verified by the transport
+    /// itself (e.g. from a certificate), or `None` if the transport does not verify its peer.
+    fn peer_identity(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns true if the transport carries frames over independent streams, in which case
+    /// [`FramedTransport`] writes its frames using [`try_write_multiplexed`].
+    ///
+    /// [`try_write_multiplexed`]: Transport::try_write_multiplexed
+    fn is_multiplexed(&self) -> bool {
+        false
+    }
+
+    /// Tries to write a buffer of whole frames that are each preceded by their channel as a
+    /// big-endian `u64`, returning how many bytes were written. Frames of different channels
+    /// may be delivered out of order, while bytes written with [`try_write`] and frames of
+    /// channel `0` stay in order with everything else.
+    ///
+    /// Only called for a transport that [is multiplexed](Transport::is_multiplexed).
+    ///
+    /// [`try_write`]: Transport::try_write
+    fn try_write_multiplexed(&self, buf: &[u8]) -> io::Result<usize> {
+        self.try_write(buf)
+    }
 }
 
 impl Transport for Box<dyn Transport> {
@@ -82,6 +120,18 @@ impl Transport for Box<dyn Transport> {
     ) -> Pin<Box<dyn Future<Output = io::Result<Ready>> + Send + 'a>> {
         Box::pin(async move { Transport::ready(AsRef::as_ref(self), interest).await })
     }
+
+    fn peer_identity(&self) -> Option<&str> {
+        Transport::peer_identity(AsRef::as_ref(self))
+    }
+
+    fn is_multiplexed(&self) -> bool {
+        Transport::is_multiplexed(AsRef::as_ref(self))
+    }
+
+    fn try_write_multiplexed(&self, buf: &[u8]) -> io::Result<usize> {
+        Transport::try_write_multiplexed(AsRef::as_ref(self), buf)
+    }
 }
 
 impl Reconnectable for Box<dyn Transport> {
@@ -1,27 +1,32 @@
 use std::future::Future;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 use std::{fmt, io};
 
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, BufMut, BytesMut};
 use log::*;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 use super::{InmemoryTransport, Interest, Ready, Reconnectable, Transport};
-use crate::net::common::{SecretKey32, utils};
+use crate::net::common::{PacketFormat, SecretKey32, utils};
 
 mod backup;
 mod codec;
 mod exchange;
 mod frame;
 mod handshake;
+mod rekey;
 
 pub use backup::*;
 pub use codec::*;
 pub use exchange::*;
 pub use frame::*;
 pub use handshake::*;
+pub use rekey::RekeyConfig;
+use rekey::{DATA_FRAME, REKEY_FRAME, RekeyMessage, RekeyState};
 
 /// Size of the read buffer when reading bytes to construct a frame
 const READ_BUF_SIZE: usize = 8 * 1024;
@@ -29,6 +34,14 @@ const READ_BUF_SIZE: usize = 8 * 1024;
 /// Duration to wait after WouldBlock received during looping operations like `read_frame`
 const SLEEP_DURATION: Duration = Duration::from_millis(1);
 
+/// Channel of frames written without one, which a multiplexed transport keeps in order with
+/// every other frame.
+pub(super) const ORDERED_CHANNEL: u64 = 0;
+
+/// Written in place of the number of a frame sent over a multiplexed transport when the frame is
+/// not tracked by the [`Backup`], whereas tracked frames are written with their number plus one.
+const UNNUMBERED_FRAME: u64 = 0;
+
 /// Represents a wrapper around a [`Transport`] that reads and writes using frames defined by a
 /// [`Codec`].
 ///
@@ -49,6 +62,21 @@ pub struct FramedTransport<T> {
 
     /// Stores outgoing frames in case of transmission issues
     pub backup: Backup,
+
+    /// Identity of the server verified during the last client-side handshake
+    server_identity: Option<ServerPublicKey>,
+
+    /// Statistics of the adaptive compression negotiated during the last handshake
+    compression_stats: Option<CompressionStats>,
+
+    /// Thresholds at which to replace the key negotiated during the last handshake
+    rekey_config: RekeyConfig,
+
+    /// Present when the other side supports rekeying, tracking the usage of the current key
+    rekey: Option<RekeyState>,
+
+    /// Format of request and response packets negotiated during the last handshake
+    packet_format: PacketFormat,
 }
 
 impl<T> FramedTransport<T> {
@@ -59,6 +87,11 @@ impl<T> FramedTransport<T> {
             incoming: BytesMut::with_capacity(READ_BUF_SIZE * 2),
             outgoing: BytesMut::with_capacity(READ_BUF_SIZE * 2),
             backup: Backup::new(),
+            server_identity: None,
+            compression_stats: None,
+            rekey_config: RekeyConfig::default(),
+            rekey: None,
+            packet_format: PacketFormat::default(),
         }
     }
 
@@ -73,9 +106,14 @@ impl<T> FramedTransport<T> {
     ///
     /// For safety, use [`clear`] to wipe the buffers before further use.
     ///
+    /// This also stops any rekeying, which is only available for codecs established by a
+    /// [`handshake`].
+    ///
     /// [`clear`]: FramedTransport::clear
+    /// [`handshake`]: FramedTransport::handshake
     pub fn set_codec(&mut self, codec: BoxedCodec) {
         self.codec = codec;
+        self.rekey = None;
     }
 
     /// Returns a reference to the codec used by the transport.
@@ -98,6 +136,42 @@ impl<T> FramedTransport<T> {
         self.codec.as_mut()
     }
 
+    /// Returns the identity of the server, verified during the last handshake performed from the
+    /// client-side. This is `None` on the server-side, or if the server did not present an
+    /// identity.
+    pub fn server_identity(&self) -> Option<&ServerPublicKey> {
+        self.server_identity.as_ref()
+    }
+
+    /// Returns the statistics of frames sent using the adaptive compression negotiated during the
+    /// last handshake. This is `None` if no handshake has happened, or if the handshake did not
+    /// result in adaptive compression.
+    pub fn compression_stats(&self) -> Option<&CompressionStats> {
+        self.compression_stats.as_ref()
+    }
+
+    /// Returns the thresholds at which this transport replaces the key negotiated during the
+    /// last handshake.
+    pub fn rekey_config(&self) -> &RekeyConfig {
+        &self.rekey_config
+    }
+
+    /// Sets the thresholds at which this transport replaces the key negotiated during the last
+    /// handshake, applying to the current key as well as keys from future handshakes.
+    ///
+    /// Rekeying only happens when the other side also supports it, which is determined during
+    /// the handshake.
+    pub fn set_rekey_config(&mut self, config: RekeyConfig) {
+        self.rekey_config = config;
+    }
+
+    /// Returns the format of request and response packets negotiated during the last handshake,
+    /// which is [`PacketFormat::Map`] if no handshake has happened or the other side does not
+    /// support anything else.
+    pub fn packet_format(&self) -> PacketFormat {
+        self.packet_format
+    }
+
     /// Clears the internal transport buffers.
     pub fn clear(&mut self) {
         self.incoming.clear();
@@ -126,6 +200,10 @@ impl<T> fmt::Debug for FramedTransport<T> {
             .field("incoming", &self.incoming)
             .field("outgoing", &self.outgoing)
             .field("backup", &self.backup)
+            .field("server_identity", &self.server_identity)
+            .field("compression_stats", &self.compression_stats)
+            .field("rekey_config", &self.rekey_config)
+            .field("packet_format", &self.packet_format)
             .finish()
     }
 }
@@ -139,6 +217,11 @@ impl<T: Transport + 'static> FramedTransport<T> {
             incoming: self.incoming,
             outgoing: self.outgoing,
             backup: self.backup,
+            server_identity: self.server_identity,
+            compression_stats: self.compression_stats,
+            rekey_config: self.rekey_config,
+            rekey: self.rekey,
+            packet_format: self.packet_format,
         }
     }
 }
@@ -206,8 +289,15 @@ impl<T: Transport> FramedTransport<T> {
         let mut bytes_written = 0;
 
         // Continue to send from the outgoing buffer until we either finish or fail
+        let multiplexed = self.inner.is_multiplexed();
         while !self.outgoing.is_empty() {
-            match self.inner.try_write(self.outgoing.as_ref()) {
+            let result = if multiplexed {
+                self.inner.try_write_multiplexed(self.outgoing.as_ref())
+            } else {
+                self.inner.try_write(self.outgoing.as_ref())
+            };
+
+            match result {
                 // Getting 0 bytes on write indicates the channel has closed
                 Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero)),
 
@@ -253,18 +343,35 @@ impl<T: Transport> FramedTransport<T> {
     ///
     /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
     pub fn try_read_frame(&mut self) -> io::Result<Option<OwnedFrame>> {
+        Ok(self.try_read_numbered_frame()?.map(|(_, frame)| frame))
+    }
+
+    /// Reads a frame like [`try_read_frame`], along with the number that the other side gave it
+    /// when sent over a multiplexed transport.
+    ///
+    /// [`try_read_frame`]: FramedTransport::try_read_frame
+    fn try_read_numbered_frame(&mut self) -> io::Result<Option<(Option<u64>, OwnedFrame)>> {
         // Attempt to read a frame, returning the decoded frame if we get one, returning any error
         // that is encountered from reading frames or failing to decode, or otherwise doing nothing
         // and continuing forward.
+        //
+        // NOTE: Frames used by the transport itself to rekey are handled here and skipped over,
+        //       so that callers only ever see the frames sent by the other side's caller.
         macro_rules! read_next_frame {
             () => {{
-                match Frame::read(&mut self.incoming) {
-                    None => (),
-                    Some(frame) => {
-                        if frame.is_nonempty() {
+                while let Some(frame) = Frame::read(&mut self.incoming) {
+                    let is_nonempty = frame.is_nonempty();
+                    if let Some((seq, frame)) = self.decode_frame(frame)? {
+                        // NOTE: Over a multiplexed transport, frames can arrive out of order, so
+                        //       they are tracked by the number they were sent with instead
+                        if self.inner.is_multiplexed() {
+                            if let Some(seq) = seq {
+                                self.backup.mark_received(seq);
+                            }
+                        } else if is_nonempty {
                             self.backup.increment_received_cnt();
                         }
-                        return Ok(Some(self.codec.decode(frame)?.into_owned()));
+                        return Ok(Some((seq, frame)));
                     }
                 }
             }};
@@ -322,10 +429,18 @@ impl<T: Transport> FramedTransport<T> {
     /// [`try_read_frame`]: FramedTransport::try_read_frame
     /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
     pub async fn read_frame(&mut self) -> io::Result<Option<OwnedFrame>> {
+        Ok(self.read_numbered_frame().await?.map(|(_, frame)| frame))
+    }
+
+    /// Reads a frame like [`read_frame`], along with the number that the other side gave it when
+    /// sent over a multiplexed transport.
+    ///
+    /// [`read_frame`]: FramedTransport::read_frame
+    async fn read_numbered_frame(&mut self) -> io::Result<Option<(Option<u64>, OwnedFrame)>> {
         loop {
             self.readable().await?;
 
-            match self.try_read_frame() {
+            match self.try_read_numbered_frame() {
                 Err(x) if x.kind() == io::ErrorKind::WouldBlock => {
                     // NOTE: We sleep for a little bit before trying again to avoid pegging CPU
                     tokio::time::sleep(SLEEP_DURATION).await
@@ -357,6 +472,32 @@ impl<T: Transport> FramedTransport<T> {
     /// [`ErrorKind::WriteZero`]: io::ErrorKind::WriteZero
     /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
     pub fn try_write_frame<'a, F>(&mut self, frame: F) -> io::Result<()>
+    where
+        F: TryInto<Frame<'a>>,
+        F::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        self.try_write_frame_to(ORDERED_CHANNEL, frame)
+    }
+
+    /// Writes a `frame` of bytes like [`try_write_frame`], but as part of the given `channel`.
+    ///
+    /// When the inner transport [is multiplexed](Transport::is_multiplexed), frames of a channel
+    /// stay in order with each other and with frames written without a channel, but may arrive
+    /// before or after frames of other channels. Otherwise, this is the same as
+    /// [`try_write_frame`].
+    ///
+    /// [`try_write_frame`]: FramedTransport::try_write_frame
+    pub fn try_write_frame_on<'a, F>(&mut self, channel: &str, frame: F) -> io::Result<()>
+    where
+        F: TryInto<Frame<'a>>,
+        F::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let mut hasher = DefaultHasher::new();
+        channel.hash(&mut hasher);
+        self.try_write_frame_to(hasher.finish().max(ORDERED_CHANNEL + 1), frame)
+    }
+
+    fn try_write_frame_to<'a, F>(&mut self, channel: u64, frame: F) -> io::Result<()>
     where
         F: TryInto<Frame<'a>>,
         F::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
@@ -366,10 +507,12 @@ impl<T: Transport> FramedTransport<T> {
             .try_into()
             .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
 
+        // Replace our key first if it has been used for long enough
+        self.rekey_if_due()?;
+
         // Encode the frame and store it in our outgoing queue
-        self.codec
-            .encode(frame.as_borrowed())?
-            .write(&mut self.outgoing);
+        let seq = (frame.is_nonempty() && !self.backup.is_frozen()).then(|| self.backup.sent_cnt());
+        self.queue_frame(channel, DATA_FRAME, seq, frame.as_borrowed())?;
 
         // Update tracking stats and more of backup if frame is nonempty
         if frame.is_nonempty() {
@@ -436,6 +579,226 @@ impl<T: Transport> FramedTransport<T> {
         self.write_frame(data).await
     }
 
+    /// Encodes a frame of the given `kind` and stores it in the outgoing queue. The kind is only
+    /// included when the other side supports rekeying, as otherwise it would not expect it.
+    ///
+    /// For a multiplexed transport, the `channel` is written ahead of the encoded frame for the
+    /// transport to route it; it never reaches the other side. The number of the frame in the
+    /// [`Backup`], if it has one, is encoded along with it.
+    fn queue_frame(
+        &mut self,
+        channel: u64,
+        kind: u8,
+        seq: Option<u64>,
+        frame: Frame<'_>,
+    ) -> io::Result<()> {
+        let frame = self.wrap_frame(kind, seq, frame);
+        let frame = self.codec.encode(frame)?;
+        if let Some(rekey) = self.rekey.as_mut() {
+            rekey.record(frame.len());
+        }
+
+        if self.inner.is_multiplexed() {
+            self.outgoing.put_u64(channel);
+        }
+        frame.write(&mut self.outgoing);
+
+        Ok(())
+    }
+
+    /// Places a data `frame` numbered `seq` back into the incoming queue, encoding it the same
+    /// way as the other side would have.
+    fn requeue_incoming_frame(&mut self, seq: Option<u64>, frame: Frame<'_>) -> io::Result<()> {
+        let frame = self.wrap_frame(DATA_FRAME, seq, frame);
+        match self.rekey.as_mut() {
+            Some(rekey) => {
+                let codec = rekey.incoming_codec.as_mut().unwrap_or(&mut self.codec);
+                codec.encode(frame)?.write(&mut self.incoming);
+            }
+            None => self.codec.encode(frame)?.write(&mut self.incoming),
+        }
+
+        Ok(())
+    }
+
+    /// Prefixes the item of a `frame` with what the other side expects ahead of it prior to
+    /// encoding: the number of the frame for a multiplexed transport, and the `kind` of the frame
+    /// when the other side supports rekeying.
+    fn wrap_frame<'a>(&self, kind: u8, seq: Option<u64>, frame: Frame<'a>) -> Frame<'a> {
+        let multiplexed = self.inner.is_multiplexed();
+        if !multiplexed && self.rekey.is_none() {
+            return frame;
+        }
+
+        let mut item = Vec::with_capacity(frame.len() + 9);
+        if multiplexed {
+            item.put_u64(seq.map_or(UNNUMBERED_FRAME, |seq| seq + 1));
+        }
+        if self.rekey.is_some() {
+            item.push(kind);
+        }
+        item.extend_from_slice(frame.as_item());
+        Frame::from(item)
+    }
+
+    /// Decodes a `frame` read from the other side along with its number for a multiplexed
+    /// transport, returning `None` if the frame was meant for the transport itself rather than
+    /// the caller.
+    fn decode_frame(&mut self, frame: OwnedFrame) -> io::Result<Option<(Option<u64>, OwnedFrame)>> {
+        let frame = match self.rekey.as_mut() {
+            Some(rekey) => {
+                rekey.record(frame.len());
+                let codec = rekey.incoming_codec.as_mut().unwrap_or(&mut self.codec);
+                codec.decode(frame)?
+            }
+            None => self.codec.decode(frame)?,
+        };
+
+        let multiplexed = self.inner.is_multiplexed();
+        if !multiplexed && self.rekey.is_none() {
+            return Ok(Some((None, frame.into_owned())));
+        }
+
+        let (seq, item) = if multiplexed {
+            let Some((seq, item)) = frame.as_item().split_first_chunk::<8>() else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Frame is missing its number",
+                ));
+            };
+            (u64::from_be_bytes(*seq).checked_sub(1), item)
+        } else {
+            (None, frame.as_item())
+        };
+
+        if self.rekey.is_none() {
+            return Ok(Some((seq, Frame::from(item.to_vec()))));
+        }
+
+        match item.split_first() {
+            Some((&DATA_FRAME, item)) => {
+                let frame = Frame::from(item.to_vec());
+                self.rekey_if_due()?;
+                Ok(Some((seq, frame)))
+            }
+            Some((&REKEY_FRAME, item)) => {
+                let message = utils::deserialize_from_slice(item)?;
+                self.on_rekey_message(message)?;
+                Ok(None)
+            }
+            Some((kind, _)) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown frame kind {kind}"),
+            )),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Frame is missing its kind",
+            )),
+        }
+    }
+
+    /// Starts replacing the current key if it has reached any of the thresholds configured with
+    /// [`set_rekey_config`].
+    ///
+    /// [`set_rekey_config`]: FramedTransport::set_rekey_config
+    fn rekey_if_due(&mut self) -> io::Result<()> {
+        if self
+            .rekey
+            .as_ref()
+            .is_some_and(|rekey| rekey.is_due(&self.rekey_config))
+        {
+            debug!("Starting rekey");
+            self.start_rekey()?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends our half of a new key exchange to the other side using the current key.
+    fn start_rekey(&mut self) -> io::Result<()> {
+        let exchange = KeyExchange::default();
+        let message = RekeyMessage::KeyExchange {
+            public_key: exchange.pk_bytes(),
+            salt: *exchange.salt(),
+        };
+
+        if let Some(rekey) = self.rekey.as_mut() {
+            rekey.exchange = Some(Arc::new(exchange));
+        }
+
+        self.queue_rekey_message(&message)
+    }
+
+    /// Handles a message from the other side about replacing the current key.
+    ///
+    /// Each side sends its half of the key exchange, derives the new key once it has both
+    /// halves, and then sends [`RekeyMessage::NewKeys`] as the last frame using the old key. This
+    /// means that frames in flight in either direction are never lost, as each side keeps
+    /// decoding with the old key until the other side says that it switched.
+    fn on_rekey_message(&mut self, message: RekeyMessage) -> io::Result<()> {
+        match message {
+            RekeyMessage::KeyExchange { public_key, salt } => {
+                // If the other side started the rekey, we join in with our half
+                if self
+                    .rekey
+                    .as_ref()
+                    .is_some_and(|rekey| rekey.exchange.is_none())
+                {
+                    debug!("Other side started rekey");
+                    self.start_rekey()?;
+                }
+
+                let Some(exchange) = self.rekey.as_mut().and_then(|rekey| rekey.exchange.take())
+                else {
+                    return Ok(());
+                };
+                let Some(rekey) = self.rekey.as_ref() else {
+                    return Ok(());
+                };
+
+                let key = exchange.derive_shared_secret(public_key, salt)?;
+                let encryption_codec = rekey
+                    .encryption_type
+                    .new_codec(key.unprotected_as_bytes())?;
+                let codec = bundle_codecs(rekey.compression.clone(), None, Some(encryption_codec));
+
+                // Mark the last frame that uses the old key, and then switch to the new key for
+                // everything we send while still decoding with the old key until the other side
+                // switches as well
+                self.queue_rekey_message(&RekeyMessage::NewKeys)?;
+                let old_codec = std::mem::replace(&mut self.codec, codec);
+                if let Some(rekey) = self.rekey.as_mut() {
+                    rekey.incoming_codec = Some(old_codec);
+                    rekey.replaced();
+                }
+
+                Ok(())
+            }
+            RekeyMessage::NewKeys => match self.rekey.as_mut() {
+                Some(rekey) if rekey.incoming_codec.is_some() => {
+                    rekey.incoming_codec = None;
+                    debug!("Finished rekey #{}", rekey.generation);
+                    Ok(())
+                }
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Other side switched keys without a key exchange",
+                )),
+            },
+        }
+    }
+
+    /// Queues a rekey `message` and attempts to send it right away.
+    fn queue_rekey_message(&mut self, message: &RekeyMessage) -> io::Result<()> {
+        let data = utils::serialize_to_vec(message)?;
+        self.queue_frame(ORDERED_CHANNEL, REKEY_FRAME, None, Frame::from(data))?;
+
+        // NOTE: As this can happen while reading, any failure to write is left for the next
+        //       write or flush to report, which sends anything that is still queued
+        let _ = self.try_flush();
+        Ok(())
+    }
+
     /// Executes the async function while the [`Backup`] of this transport is frozen.
     pub async fn do_frozen<F, X>(&mut self, mut f: F) -> io::Result<()>
     where
@@ -454,6 +817,10 @@ impl<T: Transport> FramedTransport<T> {
     /// side are sent again and then this transport waits for any missing frames that it did not
     /// receive from the other side.
     ///
+    /// Over a multiplexed transport, each side also communicates which frames it received ahead
+    /// of the others, as frames of different channels can arrive out of order, so that only the
+    /// frames that are actually missing are sent again.
+    ///
     /// ### Note
     ///
     /// This will clear the internal incoming and outgoing buffers, so any frame that was in
@@ -464,10 +831,14 @@ impl<T: Transport> FramedTransport<T> {
             backup: &mut Backup,
         ) -> io::Result<()> {
             type Stats = (u64, u64, u64);
+            type MultiplexedStats = (u64, u64, u64, Vec<u64>);
 
-            // Stats in the form of (sent, received, available)
+            // Stats in the form of (sent, received, available), along with the frames received
+            // ahead of the others for a multiplexed transport
+            let multiplexed = this.inner.is_multiplexed();
             let sent_cnt: u64 = backup.sent_cnt();
             let received_cnt: u64 = backup.received_cnt();
+            let received_ahead: Vec<u64> = backup.received_ahead().collect();
             let available_cnt: u64 = backup
                 .frame_cnt()
                 .try_into()
@@ -480,41 +851,61 @@ impl<T: Transport> FramedTransport<T> {
             // and how many to receive. Wait until we get the stats from the other side, and then send
             // over any missing frames.
             trace!(
-                "Stats: sent = {sent_cnt}, received = {received_cnt}, available = {available_cnt}"
+                "Stats: sent = {sent_cnt}, received = {received_cnt}, available = {available_cnt}, ahead = {received_ahead:?}"
             );
-            this.write_frame_for(&(sent_cnt, received_cnt, available_cnt))
-                .await?;
-            let (other_sent_cnt, other_received_cnt, other_available_cnt) =
-                this.read_frame_as::<Stats>().await?.ok_or_else(|| {
+            let other_stats = if multiplexed {
+                this.write_frame_for(&(sent_cnt, received_cnt, available_cnt, &received_ahead))
+                    .await?;
+                this.read_frame_as::<MultiplexedStats>().await?
+            } else {
+                this.write_frame_for(&(sent_cnt, received_cnt, available_cnt))
+                    .await?;
+                this.read_frame_as::<Stats>()
+                    .await?
+                    .map(|(sent, received, available)| (sent, received, available, Vec::new()))
+            };
+            let (other_sent_cnt, other_received_cnt, other_available_cnt, other_received_ahead) =
+                other_stats.ok_or_else(|| {
                     io::Error::new(
                         io::ErrorKind::UnexpectedEof,
                         "Transport terminated before getting replay stats",
                     )
                 })?;
             trace!(
-                "Other stats: sent = {other_sent_cnt}, received = {other_received_cnt}, available = {other_available_cnt}"
+                "Other stats: sent = {other_sent_cnt}, received = {other_received_cnt}, available = {other_available_cnt}, ahead = {other_received_ahead:?}"
             );
 
             // Determine how many frames we need to resend. This will either be (sent - received) or
-            // available frames, whichever is smaller.
+            // available frames, whichever is smaller, skipping frames received ahead of the others.
             let resend_cnt =
                 std::cmp::min(sent_cnt.saturating_sub(other_received_cnt), available_cnt);
 
             // Determine how many frames we expect to receive. This will either be (received - sent) or
-            // available frames, whichever is smaller.
-            let expected_cnt = std::cmp::min(
-                other_sent_cnt.saturating_sub(received_cnt),
-                other_available_cnt,
+            // available frames, whichever is smaller, minus the frames we received ahead of the others.
+            let expected_start = std::cmp::max(
+                received_cnt,
+                other_sent_cnt.saturating_sub(other_available_cnt),
             );
+            let expected_cnt = other_sent_cnt.saturating_sub(expected_start)
+                - received_ahead
+                    .iter()
+                    .filter(|&&seq| (expected_start..other_sent_cnt).contains(&seq))
+                    .count() as u64;
 
             // Send all missing frames, removing any frames that we know have been received
             trace!("Reducing internal replay frames to {resend_cnt}");
             backup.truncate_front(resend_cnt.try_into().expect("Cannot cast usize to u64"));
 
-            debug!("Sending {resend_cnt} frames");
-            for frame in backup.frames() {
-                this.try_write_frame(frame.as_borrowed())?;
+            // NOTE: Frames are resent with their original numbers, so that the other side can
+            //       tell them apart from ones that it received ahead of the others
+            let mut sent = 0;
+            for (seq, frame) in backup.numbered_frames() {
+                if other_received_ahead.binary_search(&seq).is_err() {
+                    this.queue_frame(ORDERED_CHANNEL, DATA_FRAME, Some(seq), frame.as_borrowed())?;
+                    sent += 1;
+                }
             }
+            debug!("Sending {sent} frames");
             this.flush().await?;
 
             // Receive all expected frames, placing their contents into our incoming queue
@@ -524,7 +915,7 @@ impl<T: Transport> FramedTransport<T> {
             //       we do not increment the counter here.
             debug!("Waiting for {expected_cnt} frames");
             for i in 0..expected_cnt {
-                let frame = this.read_frame().await?.ok_or_else(|| {
+                let (seq, frame) = this.read_numbered_frame().await?.ok_or_else(|| {
                     io::Error::new(
                         io::ErrorKind::UnexpectedEof,
                         format!(
@@ -536,7 +927,7 @@ impl<T: Transport> FramedTransport<T> {
 
                 // Encode our frame and write it to be queued in our incoming data
                 // NOTE: We have to do encoding here as incoming bytes are expected to be encoded
-                this.codec.encode(frame)?.write(&mut this.incoming);
+                this.requeue_incoming_frame(seq, frame)?;
             }
 
             // Catch up our read count as we can have the case where the other side has a higher
@@ -616,14 +1007,14 @@ impl<T: Transport> FramedTransport<T> {
     /// 1. Wait for options from server
     /// 2. Send to server a compression and encryption choice
     /// 3. Configure framed transport using selected choices
-    /// 4. Invoke on_handshake function
+    /// 4. Verify the server's signature of the key exchange, if the server has an identity
     ///
     /// ### Server
     ///
     /// 1. Send options to client
     /// 2. Receive choices from client
     /// 3. Configure framed transport using client's choices
-    /// 4. Invoke on_handshake function
+    /// 4. Sign the key exchange with the server's identity, if it has one and the client asked
     ///
     /// ### Failure
     ///
@@ -632,6 +1023,7 @@ impl<T: Transport> FramedTransport<T> {
     /// * If any frame during the handshake fails to be serialized
     /// * If any unexpected frame is received during the handshake
     /// * If using encryption and unable to derive a shared secret key
+    /// * If the server's identity signature is invalid
     ///
     /// If a failure happens, the codec will be reset to what it was prior to the handshake
     /// request, and all internal buffers will be cleared to avoid corruption.
@@ -642,6 +1034,7 @@ impl<T: Transport> FramedTransport<T> {
         //
         // NOTE: We grab the old codec in case we encounter an error and need to reset it
         let old_codec = std::mem::replace(&mut self.codec, Box::new(PlainCodec::new()));
+        let old_rekey = self.rekey.take();
         self.clear();
 
         // Swap out our backup so we don't mutate it from synchronization efforts
@@ -651,13 +1044,18 @@ impl<T: Transport> FramedTransport<T> {
         // reset the codec back to what it was prior to attempting the handshake and clear the
         // internal buffers as they may be corrupt.
         match self.handshake_impl(handshake).await {
-            Ok(codec) => {
+            Ok((codec, server_identity, compression_stats, rekey, packet_format)) => {
                 self.set_codec(codec);
                 self.backup = backup;
+                self.server_identity = server_identity;
+                self.compression_stats = compression_stats;
+                self.rekey = rekey;
+                self.packet_format = packet_format;
                 Ok(())
             }
             Err(x) => {
                 self.set_codec(old_codec);
+                self.rekey = old_rekey;
                 self.clear();
                 self.backup = backup;
                 Err(x)
@@ -665,29 +1063,89 @@ impl<T: Transport> FramedTransport<T> {
         }
     }
 
-    async fn handshake_impl(&mut self, handshake: Handshake) -> io::Result<BoxedCodec> {
+    async fn handshake_impl(
+        &mut self,
+        handshake: Handshake,
+    ) -> io::Result<(
+        BoxedCodec,
+        Option<ServerPublicKey>,
+        Option<CompressionStats>,
+        Option<RekeyState>,
+        PacketFormat,
+    )> {
         #[derive(Debug, Serialize, Deserialize)]
         struct Choice {
             compression_level: Option<CompressionLevel>,
             compression_type: Option<CompressionType>,
             encryption_type: Option<EncryptionType>,
+
+            /// Whether to wrap the compression in an [`AdaptiveCompressionCodec`] applied before
+            /// encryption, defaulting to false for older clients
+            #[serde(default)]
+            adaptive_compression: bool,
+
+            /// Whether to tag frames so that either side can replace the key later on,
+            /// defaulting to false for older clients
+            #[serde(default)]
+            rekey: bool,
+
+            /// Whether to send requests and responses in the compact packet format, defaulting to
+            /// false for older clients
+            #[serde(default)]
+            compact_packets: bool,
+
+            /// Whether the client wants the server to prove its identity, defaulting to false for
+            /// older clients that do not know to ask
+            #[serde(default)]
+            identity: bool,
+        }
+
+        #[derive(Serialize, Deserialize)]
+        struct IdentityProof {
+            /// Bytes of the server's identity public key
+            #[serde(with = "serde_bytes")]
+            public_key: Vec<u8>,
+
+            /// Signature of the key exchange transcript
+            #[serde(with = "serde_bytes")]
+            signature: Vec<u8>,
         }
 
         #[derive(Debug, Serialize, Deserialize)]
         struct Options {
             compression_types: Vec<CompressionType>,
             encryption_types: Vec<EncryptionType>,
+
+            /// Whether the server supports adaptive compression, defaulting to false for older
+            /// servers
+            #[serde(default)]
+            adaptive_compression: bool,
+
+            /// Whether the server supports rekeying, defaulting to false for older servers
+            #[serde(default)]
+            rekey: bool,
+
+            /// Whether the server understands the compact packet format, defaulting to false for
+            /// older servers
+            #[serde(default)]
+            compact_packets: bool,
+
+            /// Whether the server knows how to prove its identity, defaulting to false for older
+            /// servers
+            #[serde(default)]
+            identity: bool,
         }
 
         // Define a label to distinguish log output for client and server
-        let log_label = if handshake.is_client() {
+        let handshake_is_client = handshake.is_client();
+        let log_label = if handshake_is_client {
             "Handshake | Client"
         } else {
             "Handshake | Server"
         };
 
         // Determine compression and encryption to apply to framed transport
-        let choice = match handshake {
+        let (choice, identity) = match handshake {
             Handshake::Client {
                 preferred_compression_type,
                 preferred_compression_level,
@@ -724,21 +1182,38 @@ impl<T: Transport> FramedTransport<T> {
                                 .find(|ty| !ty.is_unknown())
                                 .copied()
                         }),
+
+                    // Only compress frames that benefit whenever the server knows how to
+                    adaptive_compression: options.adaptive_compression,
+
+                    // Support replacing the key whenever the server knows how to
+                    rekey: options.rekey,
+
+                    // Shrink requests and responses whenever the server knows how to
+                    compact_packets: options.compact_packets,
+
+                    // Ask the server to prove its identity whenever it knows how to
+                    identity: options.identity,
                 };
 
                 // Report back to the server the choice
                 debug!("[{log_label}] Reporting choice: {choice:?}");
                 self.write_frame_for(&choice).await?;
 
-                choice
+                (choice, None)
             }
             Handshake::Server {
                 compression_types,
                 encryption_types,
+                identity,
             } => {
                 let options = Options {
                     compression_types: compression_types.to_vec(),
                     encryption_types: encryption_types.to_vec(),
+                    adaptive_compression: true,
+                    rekey: true,
+                    compact_packets: true,
+                    identity: true,
                 };
 
                 // Send options to the client
@@ -747,12 +1222,14 @@ impl<T: Transport> FramedTransport<T> {
 
                 // Get client's response with selected compression and encryption
                 debug!("[{log_label}] Waiting on choice");
-                self.read_frame_as::<Choice>().await?.ok_or_else(|| {
+                let choice = self.read_frame_as::<Choice>().await?.ok_or_else(|| {
                     io::Error::new(
                         io::ErrorKind::UnexpectedEof,
                         "Transport closed early while waiting for choice",
                     )
-                })?
+                })?;
+
+                (choice, identity)
             }
         };
 
@@ -760,13 +1237,22 @@ impl<T: Transport> FramedTransport<T> {
         let compression_level = choice.compression_level.unwrap_or_default();
 
         // Acquire a codec for the compression type
-        let compression_codec = choice
+        let mut compression_codec = choice
             .compression_type
             .map(|ty| ty.new_codec(compression_level))
             .transpose()?;
 
+        // Wrap the compression to skip frames that would not benefit if both sides support it
+        let adaptive_codec = if choice.adaptive_compression {
+            compression_codec.take().map(AdaptiveCompressionCodec::new)
+        } else {
+            None
+        };
+        let compression_stats = adaptive_codec.as_ref().map(|c| c.stats().clone());
+
         // In the case that we are using encryption, we derive a shared secret key to use with the
         // encryption type
+        let mut server_identity = None;
         let encryption_codec = match choice.encryption_type {
             // Fail early if we got an unknown encryption type
             Some(EncryptionType::Unknown) => {
@@ -776,39 +1262,83 @@ impl<T: Transport> FramedTransport<T> {
                 ));
             }
             Some(ty) => {
-                let key = self.exchange_keys_impl(log_label).await?;
-                Some(ty.new_codec(key.unprotected_as_bytes())?)
+                let (key, transcript) = self.exchange_keys_impl(log_label).await?;
+                let codec = ty.new_codec(key.unprotected_as_bytes())?;
+
+                // When asked, the server proves its identity by signing the exchange it just took
+                // part in, which the client verifies so that the signature cannot be replayed
+                if choice.identity && handshake_is_client {
+                    debug!("[{log_label}] Waiting on server identity");
+                    let proof = self
+                        .read_frame_as::<Option<IdentityProof>>()
+                        .await?
+                        .ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "Transport closed early while waiting for server identity",
+                            )
+                        })?;
+
+                    if let Some(proof) = proof {
+                        let key = ServerPublicKey::from_bytes(&proof.public_key)?;
+                        key.verify(&transcript, &proof.signature)?;
+                        debug!(
+                            "[{log_label}] Verified server identity {}",
+                            key.fingerprint()
+                        );
+                        server_identity = Some(key);
+                    }
+                } else if choice.identity {
+                    debug!("[{log_label}] Sending server identity");
+                    let proof = identity.as_ref().map(|identity| IdentityProof {
+                        public_key: identity.public_key().to_bytes(),
+                        signature: identity.sign(&transcript),
+                    });
+                    self.write_frame_for(&proof).await?;
+                }
+
+                Some(codec)
             }
             None => None,
         };
 
+        // Rekeying replaces the encryption codec while keeping the adaptive compression, which
+        // older peers using the legacy codec order never negotiate
+        let rekey = match (choice.rekey, &encryption_codec) {
+            (true, Some(codec)) if compression_codec.is_none() => {
+                Some(RekeyState::new(codec.ty(), adaptive_codec.clone()))
+            }
+            _ => None,
+        };
+
         // Bundle our compression and encryption codecs into a single, chained codec
         trace!("[{log_label}] Bundling codecs");
-        let codec: BoxedCodec = match (compression_codec, encryption_codec) {
-            // If we have both encryption and compression, do the encryption first and then
-            // compress in order to get smallest result
-            (Some(c), Some(e)) => Box::new(ChainCodec::new(e, c)),
-
-            // If we just have compression, pass along the compression codec
-            (Some(c), None) => Box::new(c),
+        let codec = bundle_codecs(adaptive_codec, compression_codec, encryption_codec);
 
-            // If we just have encryption, pass along the encryption codec
-            (None, Some(e)) => Box::new(e),
-
-            // If we have neither compression nor encryption, use a plaintext codec
-            (None, None) => Box::new(PlainCodec::new()),
+        let packet_format = if choice.compact_packets {
+            PacketFormat::Compact
+        } else {
+            PacketFormat::Map
         };
 
-        Ok(codec)
+        Ok((
+            codec,
+            server_identity,
+            compression_stats,
+            rekey,
+            packet_format,
+        ))
     }
 
     /// Places the transport into key-exchange mode where it attempts to derive a shared secret key
     /// with the other transport.
     pub async fn exchange_keys(&mut self) -> io::Result<SecretKey32> {
-        self.exchange_keys_impl("").await
+        Ok(self.exchange_keys_impl("").await?.0)
     }
 
-    async fn exchange_keys_impl(&mut self, label: &str) -> io::Result<SecretKey32> {
+    /// Derives a shared secret key with the other transport, returning it alongside the
+    /// transcript of the exchange.
+    async fn exchange_keys_impl(&mut self, label: &str) -> io::Result<(SecretKey32, Vec<u8>)> {
         let log_label = if label.is_empty() {
             String::new()
         } else {
@@ -850,8 +1380,38 @@ impl<T: Transport> FramedTransport<T> {
             })?;
 
         trace!("{log_label}Deriving shared secret key");
+        let transcript = exchange.transcript(&data.public_key, &data.salt);
         let key = exchange.derive_shared_secret(data.public_key, data.salt)?;
-        Ok(key)
+        Ok((key, transcript))
+    }
+}
+
+/// Bundles compression and encryption codecs into a single, chained codec.
+fn bundle_codecs(
+    adaptive_codec: Option<AdaptiveCompressionCodec>,
+    compression_codec: Option<CompressionCodec>,
+    encryption_codec: Option<EncryptionCodec>,
+) -> BoxedCodec {
+    match (adaptive_codec, compression_codec, encryption_codec) {
+        // If we have both encryption and adaptive compression, compress first as encrypted data
+        // never compresses, and then encrypt
+        (Some(a), _, Some(e)) => Box::new(ChainCodec::new(a, e)),
+
+        // If we just have adaptive compression, pass along the adaptive codec
+        (Some(a), _, None) => Box::new(a),
+
+        // If we have both encryption and compression with an older peer, do the encryption first
+        // and then compress as that is what the peer expects
+        (None, Some(c), Some(e)) => Box::new(ChainCodec::new(e, c)),
+
+        // If we just have compression, pass along the compression codec
+        (None, Some(c), None) => Box::new(c),
+
+        // If we just have encryption, pass along the encryption codec
+        (None, None, Some(e)) => Box::new(e),
+
+        // If we have neither compression nor encryption, use a plaintext codec
+        (None, None, None) => Box::new(PlainCodec::new()),
     }
 }
 
@@ -1230,6 +1790,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn try_write_frame_on_should_write_plain_frame_if_transport_is_not_multiplexed() {
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        let mut transport = FramedTransport::new(
+            TestTransport {
+                f_try_write: Box::new(move |buf| {
+                    let len = buf.len();
+                    tx.send(buf.to_vec()).unwrap();
+                    Ok(len)
+                }),
+                f_ready: Box::new(|_| Ok(Ready::WRITABLE)),
+                ..Default::default()
+            },
+            Box::new(OkCodec),
+        );
+
+        transport
+            .try_write_frame_on("channel", b"hello world")
+            .unwrap();
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            [11u64.to_be_bytes().as_slice(), b"hello world".as_slice()].concat()
+        );
+    }
+
     #[test]
     fn try_write_frame_should_write_any_prior_queued_bytes_before_writing_next_frame() {
         const STEP_SIZE: usize = Frame::HEADER_SIZE + 5;
@@ -1929,6 +2515,331 @@ mod tests {
         task.await.unwrap();
     }
 
+    #[test(tokio::test)]
+    async fn handshake_should_negotiate_compact_packets_by_default() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        assert_eq!(t1.packet_format(), PacketFormat::Map);
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+            t2
+        });
+
+        t1.client_handshake().await.unwrap();
+        let t2 = task.await.unwrap();
+        assert_eq!(t1.packet_format(), PacketFormat::Compact);
+        assert_eq!(t2.packet_format(), PacketFormat::Compact);
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_client_should_use_map_packets_if_server_does_not_offer_compact_packets()
+    {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Options {
+            compression_types: Vec<CompressionType>,
+            encryption_types: Vec<EncryptionType>,
+        }
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        // Offer options like an older server, which leaves out compact packets
+        t2.write_frame_for(&Options {
+            compression_types: Vec::new(),
+            encryption_types: Vec::new(),
+        })
+        .await
+        .unwrap();
+
+        t1.client_handshake().await.unwrap();
+        assert_eq!(t1.packet_format(), PacketFormat::Map);
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_server_should_use_map_packets_if_client_does_not_choose_compact_packets()
+    {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Choice {
+            compression_level: Option<CompressionLevel>,
+            compression_type: Option<CompressionType>,
+            encryption_type: Option<EncryptionType>,
+        }
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        // Choose like an older client, which leaves out compact packets
+        t2.write_frame_for(&Choice {
+            compression_level: None,
+            compression_type: None,
+            encryption_type: None,
+        })
+        .await
+        .unwrap();
+
+        t1.server_handshake().await.unwrap();
+        assert_eq!(t1.packet_format(), PacketFormat::Map);
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_should_negotiate_adaptive_compression_by_default() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        let data = b"hello world ".repeat(100);
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+            let frame = t2.read_frame().await.unwrap().unwrap();
+            t2.write_frame(frame).await.unwrap();
+            t2
+        });
+
+        t1.client_handshake().await.unwrap();
+        t1.write_frame(data.as_slice()).await.unwrap();
+        t1.write_frame(b"small").await.unwrap();
+        assert_eq!(t1.read_frame().await.unwrap().unwrap(), data.as_slice());
+
+        let t2 = task.await.unwrap();
+        let stats = t1.compression_stats().expect("Missing compression stats");
+        assert_eq!(stats.frames_compressed(), 1);
+        assert_eq!(stats.frames_skipped(), 1);
+        assert!(stats.ratio() > 1.0);
+        assert_eq!(
+            t2.compression_stats()
+                .expect("Missing compression stats")
+                .frames_compressed(),
+            1
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_should_not_have_compression_stats_without_compression() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+            assert!(t2.compression_stats().is_none());
+        });
+
+        t1.handshake(Handshake::Client {
+            preferred_compression_type: None,
+            preferred_compression_level: None,
+            preferred_encryption_type: Some(EncryptionType::XChaCha20Poly1305),
+        })
+        .await
+        .unwrap();
+        assert!(t1.compression_stats().is_none());
+
+        task.await.unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn rekey_should_replace_key_without_losing_frames() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+            for _ in 0..10 {
+                let frame = t2.read_frame().await.unwrap().unwrap();
+                t2.write_frame(frame).await.unwrap();
+            }
+            t2
+        });
+
+        t1.client_handshake().await.unwrap();
+        t1.set_rekey_config(RekeyConfig {
+            max_frames: Some(3),
+            ..RekeyConfig::disabled()
+        });
+
+        for i in 0..10 {
+            let data = format!("frame {i}");
+            t1.write_frame(data.as_bytes()).await.unwrap();
+            assert_eq!(t1.read_frame().await.unwrap().unwrap(), data.as_bytes());
+        }
+
+        let t2 = task.await.unwrap();
+        let generation = t1.rekey.as_ref().unwrap().generation;
+        assert!(generation >= 2, "Only rekeyed {generation} times");
+        assert!(t2.rekey.as_ref().unwrap().generation >= 2);
+
+        // Rekeying is invisible to the backup, which only tracks frames from callers
+        assert_eq!(t1.backup.sent_cnt(), 10);
+        assert_eq!(t1.backup.received_cnt(), 10);
+        assert_eq!(t2.backup.sent_cnt(), 10);
+        assert_eq!(t2.backup.received_cnt(), 10);
+    }
+
+    #[test(tokio::test)]
+    async fn rekey_should_succeed_if_both_sides_start_it_at_the_same_time() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        let config = RekeyConfig {
+            max_frames: Some(1),
+            ..RekeyConfig::disabled()
+        };
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+            t2.set_rekey_config(config);
+            for data in [b"one", b"two"] {
+                t2.write_frame(data).await.unwrap();
+            }
+            for data in [b"one", b"two"] {
+                assert_eq!(t2.read_frame().await.unwrap().unwrap(), data);
+            }
+            t2.set_rekey_config(RekeyConfig::disabled());
+            t2.write_frame(b"three").await.unwrap();
+            assert_eq!(t2.read_frame().await.unwrap().unwrap(), b"three");
+            t2
+        });
+
+        t1.client_handshake().await.unwrap();
+        t1.set_rekey_config(config);
+        for data in [b"one", b"two"] {
+            t1.write_frame(data).await.unwrap();
+        }
+        for data in [b"one", b"two"] {
+            assert_eq!(t1.read_frame().await.unwrap().unwrap(), data);
+        }
+        t1.set_rekey_config(RekeyConfig::disabled());
+        t1.write_frame(b"three").await.unwrap();
+        assert_eq!(t1.read_frame().await.unwrap().unwrap(), b"three");
+
+        // Both sides started a rekey, which they completed together as a single rekey
+        let t2 = task.await.unwrap();
+        for t in [&t1, &t2] {
+            let rekey = t.rekey.as_ref().unwrap();
+            assert_eq!(rekey.generation, 1);
+            assert!(rekey.exchange.is_none());
+            assert!(rekey.incoming_codec.is_none());
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn rekey_should_not_happen_without_encryption() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move {
+            t2.handshake(Handshake::Server {
+                compression_types: Vec::new(),
+                encryption_types: Vec::new(),
+                identity: None,
+            })
+            .await
+            .unwrap();
+            t2
+        });
+
+        t1.client_handshake().await.unwrap();
+        let t2 = task.await.unwrap();
+        assert!(t1.rekey.is_none());
+        assert!(t2.rekey.is_none());
+    }
+
+    #[test(tokio::test)]
+    async fn set_codec_should_stop_rekeying() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        let task = tokio::spawn(async move { t2.server_handshake().await.unwrap() });
+        t1.client_handshake().await.unwrap();
+        task.await.unwrap();
+        assert!(t1.rekey.is_some());
+
+        t1.set_codec(Box::new(PlainCodec::new()));
+        assert!(t1.rekey.is_none());
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_should_verify_identity_of_server_that_has_one() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        let identity = ServerIdentity::generate();
+
+        let task = tokio::spawn({
+            let identity = identity.clone();
+            async move {
+                t2.handshake(Handshake::server_with_identity(identity))
+                    .await
+                    .unwrap();
+                assert_eq!(t2.server_identity(), None);
+
+                // Receive one frame and echo it back
+                let frame = t2.read_frame().await.unwrap().unwrap();
+                t2.write_frame(frame).await.unwrap();
+            }
+        });
+
+        t1.client_handshake().await.unwrap();
+        assert_eq!(t1.server_identity(), Some(&identity.public_key()));
+
+        // Verify that the transports can still communicate with one another
+        t1.write_frame(b"hello world").await.unwrap();
+        assert_eq!(t1.read_frame().await.unwrap().unwrap(), b"hello world");
+
+        task.await.unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_should_have_no_server_identity_if_server_has_none() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+            let frame = t2.read_frame().await.unwrap().unwrap();
+            t2.write_frame(frame).await.unwrap();
+        });
+
+        t1.client_handshake().await.unwrap();
+        assert_eq!(t1.server_identity(), None);
+
+        t1.write_frame(b"hello world").await.unwrap();
+        assert_eq!(t1.read_frame().await.unwrap().unwrap(), b"hello world");
+
+        task.await.unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_client_should_fail_if_server_identity_signature_is_invalid() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Options {
+            compression_types: Vec<CompressionType>,
+            encryption_types: Vec<EncryptionType>,
+            identity: bool,
+        }
+
+        #[derive(Serialize, Deserialize)]
+        struct IdentityProof {
+            #[serde(with = "serde_bytes")]
+            public_key: Vec<u8>,
+            #[serde(with = "serde_bytes")]
+            signature: Vec<u8>,
+        }
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        // Act as a server that signs something other than the exchange it took part in
+        let task = tokio::spawn(async move {
+            t2.write_frame_for(&Options {
+                compression_types: Vec::new(),
+                encryption_types: EncryptionType::known_variants().to_vec(),
+                identity: true,
+            })
+            .await
+            .unwrap();
+            t2.read_frame().await.unwrap().unwrap();
+            t2.exchange_keys().await.unwrap();
+
+            let identity = ServerIdentity::generate();
+            t2.write_frame_for(&Some(IdentityProof {
+                public_key: identity.public_key().to_bytes(),
+                signature: identity.sign(b"some other exchange"),
+            }))
+            .await
+            .unwrap();
+        });
+
+        let err = t1.client_handshake().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(t1.server_identity(), None);
+
+        task.await.unwrap();
+    }
+
     #[test(tokio::test)]
     async fn handshake_failing_should_ensure_existing_codec_remains() {
         let (mut t1, t2) = FramedTransport::test_pair(100);
@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, VecDeque};
 
 use super::{Frame, OwnedFrame};
 
@@ -11,6 +11,11 @@ const MAX_BACKUP_SIZE: usize = 256 * 1024 * 1024;
 ///
 /// Empty [`Frame`]s are an exception and are not stored within the backup nor
 /// are they tracked in terms of sent/received counts.
+///
+/// Frames are numbered in the order that they are sent, starting at 0. Over a multiplexed
+/// transport, frames can arrive out of order, so the backup keeps track of which frames were
+/// received beyond the ones received in order, allowing exactly the missing frames to be sent
+/// again.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Backup {
     /// Maximum size (in bytes) to save frames in case we need to backup them
@@ -27,9 +32,14 @@ pub struct Backup {
     /// Counter keeping track of total frames sent
     sent_cnt: u64,
 
-    /// Counter keeping track of total frames received
+    /// Counter keeping track of total frames received, or over a multiplexed transport, the
+    /// number of frames received in order, such that every frame numbered below it was received
     received_cnt: u64,
 
+    /// Numbers of the frames received ahead of `received_cnt`, which only happens over a
+    /// multiplexed transport
+    received_ahead: BTreeSet<u64>,
+
     /// Indicates whether the backup is frozen, which indicates that mutations are ignored
     frozen: bool,
 }
@@ -49,6 +59,7 @@ impl Backup {
             frames: VecDeque::new(),
             sent_cnt: 0,
             received_cnt: 0,
+            received_ahead: BTreeSet::new(),
             frozen: false,
         }
     }
@@ -64,6 +75,7 @@ impl Backup {
             self.frames.clear();
             self.sent_cnt = 0;
             self.received_cnt = 0;
+            self.received_ahead.clear();
         }
     }
 
@@ -141,7 +153,32 @@ impl Backup {
         self.received_cnt
     }
 
-    /// Sets the total received frames to the specified `cnt`.
+    /// Marks the frame numbered `seq` as received, which can happen out of order over a
+    /// multiplexed transport. Frames already marked as received are ignored.
+    ///
+    /// ### Note
+    ///
+    /// Like all other modifications, this will do nothing if the backup is frozen.
+    pub(super) fn mark_received(&mut self, seq: u64) {
+        if self.frozen || seq < self.received_cnt {
+            return;
+        }
+
+        self.received_ahead.insert(seq);
+        while self.received_ahead.remove(&self.received_cnt) {
+            self.received_cnt += 1;
+        }
+    }
+
+    /// Returns the numbers of the frames received ahead of [`received_cnt`], in ascending order.
+    ///
+    /// [`received_cnt`]: Backup::received_cnt
+    pub(crate) fn received_ahead(&self) -> impl Iterator<Item = u64> + '_ {
+        self.received_ahead.iter().copied()
+    }
+
+    /// Sets the total received frames to the specified `cnt`, forgetting about any frames received
+    /// ahead of it.
     ///
     /// ### Note
     ///
@@ -149,6 +186,10 @@ impl Backup {
     pub(super) fn set_received_cnt(&mut self, cnt: u64) {
         if !self.frozen {
             self.received_cnt = cnt;
+            self.received_ahead.retain(|&seq| seq >= cnt);
+            while self.received_ahead.remove(&self.received_cnt) {
+                self.received_cnt += 1;
+            }
         }
     }
 
@@ -183,9 +224,13 @@ impl Backup {
         self.frames.len()
     }
 
-    /// Returns an iterator over the frames contained in the backup.
-    pub(super) fn frames(&self) -> impl Iterator<Item = &Frame<'_>> {
-        self.frames.iter()
+    /// Returns an iterator over the frames contained in the backup along with their numbers,
+    /// where the last frame is numbered one less than [`sent_cnt`].
+    ///
+    /// [`sent_cnt`]: Backup::sent_cnt
+    pub(super) fn numbered_frames(&self) -> impl Iterator<Item = (u64, &Frame<'_>)> {
+        let first = self.sent_cnt.saturating_sub(self.frames.len() as u64);
+        (first..).zip(self.frames.iter())
     }
 
     /// Truncates the stored frames to be no larger than `size` total frames by popping from the
@@ -294,7 +339,7 @@ mod tests {
         assert_eq!(backup.frame_cnt(), 2);
 
         // Verify the remaining frames are "67890" and "abc"
-        let frames: Vec<&[u8]> = backup.frames().map(|f| f.as_item()).collect();
+        let frames: Vec<&[u8]> = backup.numbered_frames().map(|(_, f)| f.as_item()).collect();
         assert_eq!(frames, vec![b"67890".as_slice(), b"abc".as_slice()]);
     }
 
@@ -424,8 +469,66 @@ mod tests {
         assert_eq!(backup.received_cnt(), 1);
     }
 
+    // ---- mark_received / received_ahead ----
+
+    #[test]
+    fn mark_received_in_order_increases_received_cnt() {
+        let mut backup = Backup::new();
+        backup.mark_received(0);
+        backup.mark_received(1);
+        assert_eq!(backup.received_cnt(), 2);
+        assert_eq!(backup.received_ahead().count(), 0);
+    }
+
+    #[test]
+    fn mark_received_out_of_order_tracks_frames_ahead_until_gap_is_filled() {
+        let mut backup = Backup::new();
+        backup.mark_received(2);
+        backup.mark_received(1);
+        assert_eq!(backup.received_cnt(), 0);
+        assert_eq!(backup.received_ahead().collect::<Vec<_>>(), vec![1, 2]);
+
+        backup.mark_received(0);
+        assert_eq!(backup.received_cnt(), 3);
+        assert_eq!(backup.received_ahead().count(), 0);
+    }
+
+    #[test]
+    fn mark_received_ignores_frames_already_received() {
+        let mut backup = Backup::new();
+        backup.mark_received(0);
+        backup.mark_received(2);
+        backup.mark_received(0);
+        backup.mark_received(2);
+        assert_eq!(backup.received_cnt(), 1);
+        assert_eq!(backup.received_ahead().collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn mark_received_while_frozen_does_nothing() {
+        let mut backup = Backup::new();
+        backup.freeze();
+        backup.mark_received(0);
+        backup.mark_received(2);
+        assert_eq!(backup.received_cnt(), 0);
+        assert_eq!(backup.received_ahead().count(), 0);
+    }
+
     // ---- set_received_cnt ----
 
+    #[test]
+    fn set_received_cnt_forgets_frames_received_ahead_of_it() {
+        let mut backup = Backup::new();
+        backup.mark_received(1);
+        backup.mark_received(3);
+        backup.mark_received(5);
+
+        // Frame 3 was already received, so the count continues past it
+        backup.set_received_cnt(3);
+        assert_eq!(backup.received_cnt(), 4);
+        assert_eq!(backup.received_ahead().collect::<Vec<_>>(), vec![5]);
+    }
+
     #[test]
     fn set_received_cnt_updates_value() {
         let mut backup = Backup::new();
@@ -479,7 +582,7 @@ mod tests {
         backup.truncate_front(1);
         assert_eq!(backup.frame_cnt(), 1);
 
-        let frames: Vec<&[u8]> = backup.frames().map(|f| f.as_item()).collect();
+        let frames: Vec<&[u8]> = backup.numbered_frames().map(|(_, f)| f.as_item()).collect();
         assert_eq!(frames, vec![b"third".as_slice()]);
     }
 
@@ -533,7 +636,7 @@ mod tests {
         backup.push_frame(Frame::new(b"second"));
         backup.push_frame(Frame::new(b"third"));
 
-        let items: Vec<&[u8]> = backup.frames().map(|f| f.as_item()).collect();
+        let items: Vec<&[u8]> = backup.numbered_frames().map(|(_, f)| f.as_item()).collect();
         assert_eq!(
             items,
             vec![
@@ -547,7 +650,27 @@ mod tests {
     #[test]
     fn frames_iterator_is_empty_for_new_backup() {
         let backup = Backup::new();
-        assert_eq!(backup.frames().count(), 0);
+        assert_eq!(backup.numbered_frames().count(), 0);
+    }
+
+    #[test]
+    fn numbered_frames_ends_one_below_sent_cnt() {
+        let mut backup = Backup::new();
+        backup.set_max_backup_size(10);
+        for item in [b"12345", b"67890", b"abcde"] {
+            backup.increment_sent_cnt();
+            backup.push_frame(Frame::new(item));
+        }
+
+        // The first frame was evicted, so the remaining ones are numbered from 1
+        let frames: Vec<(u64, &[u8])> = backup
+            .numbered_frames()
+            .map(|(seq, f)| (seq, f.as_item()))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![(1, b"67890".as_slice()), (2, b"abcde".as_slice())]
+        );
     }
 
     // ---- all mutations ignored when frozen ----
@@ -4,12 +4,14 @@ use dyn_clone::DynClone;
 
 use super::Frame;
 
+mod adaptive;
 mod chain;
 mod compression;
 mod encryption;
 mod plain;
 mod predicate;
 
+pub use adaptive::*;
 pub use chain::*;
 pub use compression::*;
 pub use encryption::*;
@@ -0,0 +1,338 @@
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{Codec, CompressionCodec, Frame};
+
+/// Flag prefixed to a frame's item that was sent as-is
+const RAW_FLAG: u8 = 0;
+
+/// Flag prefixed to a frame's item that was compressed
+const COMPRESSED_FLAG: u8 = 1;
+
+/// Maximum number of frames to send without trying compression after frames failed to compress
+const MAX_BACKOFF: u32 = 64;
+
+/// Represents a codec that only compresses frames when it pays off, wrapping a
+/// [`CompressionCodec`] with a policy that skips frames that are too small to benefit, as well as
+/// frames whose compressed form is not any smaller than the original.
+///
+/// Unlike a [`PredicateCodec`], the choice made when encoding is recorded as a flag at the start
+/// of each frame, so decoding does not need to (and could not) repeat it. Whenever a frame fails
+/// to compress, the codec backs off from trying again for an increasing number of frames, which
+/// keeps a stream of incompressible data (e.g. archives or media) from paying for compression.
+///
+/// [`PredicateCodec`]: super::PredicateCodec
+#[derive(Clone, Debug)]
+pub struct AdaptiveCompressionCodec {
+    codec: CompressionCodec,
+    min_size: usize,
+    backoff: u32,
+    skip: u32,
+    stats: CompressionStats,
+}
+
+impl AdaptiveCompressionCodec {
+    /// Default size in bytes that a frame's item must reach before compression is attempted
+    pub const DEFAULT_MIN_SIZE: usize = 256;
+
+    /// Creates a new adaptive codec that uses `codec` for frames worth compressing
+    pub fn new(codec: CompressionCodec) -> Self {
+        Self {
+            codec,
+            min_size: Self::DEFAULT_MIN_SIZE,
+            backoff: 0,
+            skip: 0,
+            stats: CompressionStats::default(),
+        }
+    }
+
+    /// Sets the size in bytes that a frame's item must reach before compression is attempted
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Returns a reference to the compression codec used for frames worth compressing
+    pub fn as_compression_codec(&self) -> &CompressionCodec {
+        &self.codec
+    }
+
+    /// Returns the size in bytes that a frame's item must reach before compression is attempted
+    pub fn min_size(&self) -> usize {
+        self.min_size
+    }
+
+    /// Returns the statistics of the frames encoded by this codec, which are shared with any
+    /// clones of the codec
+    pub fn stats(&self) -> &CompressionStats {
+        &self.stats
+    }
+
+    /// Attempts to compress `item`, returning `None` if the policy says to send it as-is
+    fn try_compress(&mut self, item: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        // NOTE: Items that would decompress past the peer's limit are sent as-is, as the peer would
+        //       otherwise reject them
+        if item.len() < self.min_size || item.len() > CompressionCodec::MAX_DECOMPRESSED_SIZE {
+            return Ok(None);
+        }
+
+        if self.skip > 0 {
+            self.skip -= 1;
+            return Ok(None);
+        }
+
+        let compressed = self
+            .codec
+            .encode(Frame::new(item))?
+            .into_item()
+            .into_owned();
+        if compressed.len() < item.len() {
+            self.backoff = 0;
+            Ok(Some(compressed))
+        } else {
+            self.backoff = (self.backoff * 2).clamp(1, MAX_BACKOFF);
+            self.skip = self.backoff;
+            Ok(None)
+        }
+    }
+}
+
+impl Codec for AdaptiveCompressionCodec {
+    fn encode<'a>(&mut self, frame: Frame<'a>) -> io::Result<Frame<'a>> {
+        let item = frame.as_item();
+
+        let buf = match self.try_compress(item)? {
+            Some(compressed) => {
+                self.stats.record_compressed(item.len(), compressed.len());
+                let mut buf = Vec::with_capacity(compressed.len() + 1);
+                buf.push(COMPRESSED_FLAG);
+                buf.extend_from_slice(&compressed);
+                buf
+            }
+            None => {
+                self.stats.record_skipped(item.len());
+                let mut buf = Vec::with_capacity(item.len() + 1);
+                buf.push(RAW_FLAG);
+                buf.extend_from_slice(item);
+                buf
+            }
+        };
+
+        Ok(Frame::from(buf))
+    }
+
+    fn decode<'a>(&mut self, frame: Frame<'a>) -> io::Result<Frame<'a>> {
+        match frame.as_item().split_first() {
+            Some((&RAW_FLAG, item)) => Ok(Frame::from(item.to_vec())),
+            Some((&COMPRESSED_FLAG, item)) => Ok(self.codec.decode(Frame::new(item))?.into_owned()),
+            Some((flag, _)) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown compression flag {flag}"),
+            )),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Frame is missing compression flag",
+            )),
+        }
+    }
+}
+
+/// Statistics about the frames sent through an [`AdaptiveCompressionCodec`], shared by all
+/// clones so that they can be observed for the lifetime of a connection.
+#[derive(Clone, Debug, Default)]
+pub struct CompressionStats(Arc<CompressionStatsInner>);
+
+#[derive(Debug, Default)]
+struct CompressionStatsInner {
+    frames_compressed: AtomicU64,
+    frames_skipped: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+impl CompressionStats {
+    /// Returns the total number of frames that were sent compressed
+    pub fn frames_compressed(&self) -> u64 {
+        self.0.frames_compressed.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total number of frames that were sent as-is
+    pub fn frames_skipped(&self) -> u64 {
+        self.0.frames_skipped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total size in bytes of all frames before being encoded
+    pub fn bytes_in(&self) -> u64 {
+        self.0.bytes_in.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total size in bytes of all frames after being encoded, excluding the flag
+    /// marking whether a frame was compressed
+    pub fn bytes_out(&self) -> u64 {
+        self.0.bytes_out.load(Ordering::Relaxed)
+    }
+
+    /// Returns the achieved compression ratio across all frames (e.g. `3.0` when frames shrank
+    /// to a third of their size), which is `1.0` when nothing has been sent
+    pub fn ratio(&self) -> f64 {
+        match self.bytes_out() {
+            0 => 1.0,
+            out => self.bytes_in() as f64 / out as f64,
+        }
+    }
+
+    fn record_compressed(&self, bytes_in: usize, bytes_out: usize) {
+        self.0.frames_compressed.fetch_add(1, Ordering::Relaxed);
+        self.0
+            .bytes_in
+            .fetch_add(bytes_in as u64, Ordering::Relaxed);
+        self.0
+            .bytes_out
+            .fetch_add(bytes_out as u64, Ordering::Relaxed);
+    }
+
+    fn record_skipped(&self, bytes: usize) {
+        self.0.frames_skipped.fetch_add(1, Ordering::Relaxed);
+        self.0.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.0.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for AdaptiveCompressionCodec and CompressionStats: skipping small and incompressible
+    //! frames, backing off after failed compression, flag handling when decoding, and ratios.
+
+    use test_log::test;
+
+    use super::super::CompressionLevel;
+    use super::*;
+
+    fn compressible(len: usize) -> Vec<u8> {
+        b"abcd".iter().copied().cycle().take(len).collect()
+    }
+
+    fn incompressible(len: usize) -> Vec<u8> {
+        // Simple xorshift sequence, which zstd cannot find patterns in
+        let mut x: u32 = 0x9E37_79B9;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect()
+    }
+
+    fn zstd_codec() -> AdaptiveCompressionCodec {
+        AdaptiveCompressionCodec::new(CompressionCodec::zstd(CompressionLevel::FAST))
+    }
+
+    #[test]
+    fn encode_should_send_small_frames_as_is() {
+        let mut codec = zstd_codec();
+        let frame = codec.encode(Frame::new(b"ls\n")).unwrap();
+        assert_eq!(frame, b"\0ls\n");
+        assert_eq!(codec.stats().frames_skipped(), 1);
+        assert_eq!(codec.stats().frames_compressed(), 0);
+    }
+
+    #[test]
+    fn encode_should_compress_large_compressible_frames() {
+        let mut codec = zstd_codec();
+        let data = compressible(4096);
+        let frame = codec.encode(Frame::new(&data)).unwrap();
+        assert_eq!(frame.as_item()[0], COMPRESSED_FLAG);
+        assert!(frame.len() < data.len());
+        assert_eq!(codec.stats().frames_compressed(), 1);
+        assert!(codec.stats().ratio() > 1.0);
+    }
+
+    #[test]
+    fn encode_should_send_frames_as_is_if_they_do_not_compress() {
+        let mut codec = zstd_codec();
+        let data = incompressible(4096);
+        let frame = codec.encode(Frame::new(&data)).unwrap();
+        assert_eq!(frame.as_item()[0], RAW_FLAG);
+        assert_eq!(&frame.as_item()[1..], data.as_slice());
+        assert_eq!(codec.stats().frames_skipped(), 1);
+        assert_eq!(codec.stats().ratio(), 1.0);
+    }
+
+    #[test]
+    fn encode_should_back_off_from_compression_after_a_frame_fails_to_compress() {
+        let mut codec = zstd_codec();
+        codec.encode(Frame::new(&incompressible(4096))).unwrap();
+
+        // Backing off skips the next frame even though it would compress
+        let data = compressible(4096);
+        let frame = codec.encode(Frame::new(&data)).unwrap();
+        assert_eq!(frame.as_item()[0], RAW_FLAG);
+
+        // After backing off, compression is attempted again
+        let frame = codec.encode(Frame::new(&data)).unwrap();
+        assert_eq!(frame.as_item()[0], COMPRESSED_FLAG);
+    }
+
+    #[test]
+    fn min_size_should_control_which_frames_are_worth_compressing() {
+        let data = compressible(4096);
+        let mut codec = zstd_codec().with_min_size(usize::MAX);
+        let frame = codec.encode(Frame::new(&data)).unwrap();
+        assert_eq!(frame.as_item()[0], RAW_FLAG);
+
+        let data = compressible(64);
+        let mut codec = zstd_codec().with_min_size(0);
+        let frame = codec.encode(Frame::new(&data)).unwrap();
+        assert_eq!(frame.as_item()[0], COMPRESSED_FLAG);
+    }
+
+    #[test]
+    fn decode_should_reverse_encode_for_all_kinds_of_frames() {
+        let mut codec = zstd_codec();
+        for data in [
+            b"ls\n".to_vec(),
+            Vec::new(),
+            compressible(4096),
+            incompressible(4096),
+        ] {
+            let frame = codec.encode(Frame::new(&data)).unwrap();
+            assert_eq!(codec.decode(frame).unwrap(), data.as_slice());
+        }
+    }
+
+    #[test]
+    fn decode_should_fail_if_frame_has_unknown_flag() {
+        let mut codec = zstd_codec();
+        let err = codec.decode(Frame::new(b"\x07abc")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn decode_should_fail_if_frame_is_missing_flag() {
+        let mut codec = zstd_codec();
+        let err = codec.decode(Frame::new(b"")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn stats_should_be_shared_between_clones() {
+        let mut codec = zstd_codec();
+        let stats = codec.stats().clone();
+        let mut clone = codec.clone();
+
+        codec.encode(Frame::new(&compressible(4096))).unwrap();
+        clone.encode(Frame::new(b"ls\n")).unwrap();
+
+        assert_eq!(stats.frames_compressed(), 1);
+        assert_eq!(stats.frames_skipped(), 1);
+        assert_eq!(stats.bytes_in(), 4096 + 3);
+    }
+
+    #[test]
+    fn ratio_should_be_one_if_nothing_has_been_sent() {
+        assert_eq!(CompressionStats::default().ratio(), 1.0);
+    }
+}
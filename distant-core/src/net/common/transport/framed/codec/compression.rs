@@ -50,7 +50,9 @@ impl Default for CompressionLevel {
 pub enum CompressionType {
     Deflate,
     Gzip,
+    Lz4,
     Zlib,
+    Zstd,
 
     /// Indicates an unknown compression type for use in handshakes
     #[serde(other)]
@@ -63,7 +65,9 @@ impl CompressionType {
         &[
             CompressionType::Deflate,
             CompressionType::Gzip,
+            CompressionType::Lz4,
             CompressionType::Zlib,
+            CompressionType::Zstd,
         ]
     }
 
@@ -88,11 +92,23 @@ pub enum CompressionCodec {
     /// Apply gzip compression/decompression using compression `level`
     Gzip { level: CompressionLevel },
 
+    /// Apply LZ4 compression/decompression, which has no notion of a compression `level` and
+    /// always optimizes for speed
+    Lz4 { level: CompressionLevel },
+
     /// Apply zlib compression/decompression using compression `level`
     Zlib { level: CompressionLevel },
+
+    /// Apply Zstandard compression/decompression using compression `level`
+    Zstd { level: CompressionLevel },
 }
 
 impl CompressionCodec {
+    /// Maximum size in bytes of a frame's item once decompressed. Compression is negotiated before
+    /// authentication, so this keeps a small frame from anyone from expanding into an allocation of
+    /// any size.
+    pub const MAX_DECOMPRESSED_SIZE: usize = 256 * 1024 * 1024;
+
     /// Makes a new [`CompressionCodec`] based on the [`CompressionType`] and [`CompressionLevel`],
     /// returning error if the type is unknown
     pub fn from_type_and_level(
@@ -102,7 +118,9 @@ impl CompressionCodec {
         match ty {
             CompressionType::Deflate => Ok(Self::Deflate { level }),
             CompressionType::Gzip => Ok(Self::Gzip { level }),
+            CompressionType::Lz4 => Ok(Self::Lz4 { level }),
             CompressionType::Zlib => Ok(Self::Zlib { level }),
+            CompressionType::Zstd => Ok(Self::Zstd { level }),
             CompressionType::Unknown => Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Unknown compression type",
@@ -124,6 +142,13 @@ impl CompressionCodec {
         }
     }
 
+    /// Create a new LZ4 compression codec with the specified `level`
+    pub fn lz4(level: impl Into<CompressionLevel>) -> Self {
+        Self::Lz4 {
+            level: level.into(),
+        }
+    }
+
     /// Create a new zlib compression codec with the specified `level`
     pub fn zlib(level: impl Into<CompressionLevel>) -> Self {
         Self::Zlib {
@@ -131,12 +156,21 @@ impl CompressionCodec {
         }
     }
 
+    /// Create a new Zstandard compression codec with the specified `level`
+    pub fn zstd(level: impl Into<CompressionLevel>) -> Self {
+        Self::Zstd {
+            level: level.into(),
+        }
+    }
+
     /// Returns the compression level associated with the codec
     pub fn level(&self) -> CompressionLevel {
         match self {
             Self::Deflate { level } => *level,
             Self::Gzip { level } => *level,
+            Self::Lz4 { level } => *level,
             Self::Zlib { level } => *level,
+            Self::Zstd { level } => *level,
         }
     }
 
@@ -145,7 +179,9 @@ impl CompressionCodec {
         match self {
             Self::Deflate { .. } => CompressionType::Deflate,
             Self::Gzip { .. } => CompressionType::Gzip,
+            Self::Lz4 { .. } => CompressionType::Lz4,
             Self::Zlib { .. } => CompressionType::Zlib,
+            Self::Zstd { .. } => CompressionType::Zstd,
         }
     }
 }
@@ -157,33 +193,69 @@ impl Codec for CompressionCodec {
         let mut buf = Vec::new();
         match *self {
             Self::Deflate { level } => {
-                DeflateEncoder::new(item, Compression::new(level as u32)).read_to_end(&mut buf)?
+                DeflateEncoder::new(item, Compression::new(level as u32)).read_to_end(&mut buf)?;
             }
             Self::Gzip { level } => {
-                GzEncoder::new(item, Compression::new(level as u32)).read_to_end(&mut buf)?
+                GzEncoder::new(item, Compression::new(level as u32)).read_to_end(&mut buf)?;
             }
+            Self::Lz4 { .. } => buf = lz4_flex::compress_prepend_size(item),
             Self::Zlib { level } => {
-                ZlibEncoder::new(item, Compression::new(level as u32)).read_to_end(&mut buf)?
+                ZlibEncoder::new(item, Compression::new(level as u32)).read_to_end(&mut buf)?;
             }
-        };
+
+            // NOTE: Zstandard has no level that disables compression, so zero maps to its
+            //       fastest level alongside one
+            Self::Zstd { level } => buf = zstd::encode_all(item, (level as i32).max(1))?,
+        }
 
         Ok(Frame::from(buf))
     }
 
     fn decode<'a>(&mut self, frame: Frame<'a>) -> io::Result<Frame<'a>> {
         let item = frame.as_item();
-
-        let mut buf = Vec::new();
-        match *self {
-            Self::Deflate { .. } => DeflateDecoder::new(item).read_to_end(&mut buf)?,
-            Self::Gzip { .. } => GzDecoder::new(item).read_to_end(&mut buf)?,
-            Self::Zlib { .. } => ZlibDecoder::new(item).read_to_end(&mut buf)?,
+        let max = Self::MAX_DECOMPRESSED_SIZE;
+
+        let buf = match *self {
+            Self::Deflate { .. } => read_to_end_bounded(DeflateDecoder::new(item), max)?,
+            Self::Gzip { .. } => read_to_end_bounded(GzDecoder::new(item), max)?,
+            Self::Lz4 { .. } => {
+                let (size, input) = lz4_flex::block::uncompressed_size(item)
+                    .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?;
+                if size > max {
+                    return Err(too_large(max));
+                }
+
+                lz4_flex::block::decompress(input, size)
+                    .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?
+            }
+            Self::Zlib { .. } => read_to_end_bounded(ZlibDecoder::new(item), max)?,
+            Self::Zstd { .. } => read_to_end_bounded(zstd::stream::read::Decoder::new(item)?, max)?,
         };
 
         Ok(Frame::from(buf))
     }
 }
 
+/// Reads all of the decompressed bytes from `reader`, failing once more than `max` bytes are read
+/// rather than continuing to allocate
+fn read_to_end_bounded(reader: impl Read, max: usize) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(max as u64 + 1).read_to_end(&mut buf)?;
+    if buf.len() > max {
+        return Err(too_large(max));
+    }
+
+    Ok(buf)
+}
+
+/// Returns the error for a frame whose item decompresses to more than `max` bytes
+fn too_large(max: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Decompressed frame exceeds maximum size of {max} bytes"),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     //! Tests for CompressionLevel, CompressionType, and CompressionCodec: defaults, constants,
@@ -305,10 +377,12 @@ mod tests {
     #[test]
     fn known_variants_returns_all_non_unknown_types() {
         let variants = CompressionType::known_variants();
-        assert_eq!(variants.len(), 3);
+        assert_eq!(variants.len(), 5);
         assert!(variants.contains(&CompressionType::Deflate));
         assert!(variants.contains(&CompressionType::Gzip));
+        assert!(variants.contains(&CompressionType::Lz4));
         assert!(variants.contains(&CompressionType::Zlib));
+        assert!(variants.contains(&CompressionType::Zstd));
     }
 
     #[test]
@@ -316,7 +390,9 @@ mod tests {
         assert!(CompressionType::Unknown.is_unknown());
         assert!(!CompressionType::Deflate.is_unknown());
         assert!(!CompressionType::Gzip.is_unknown());
+        assert!(!CompressionType::Lz4.is_unknown());
         assert!(!CompressionType::Zlib.is_unknown());
+        assert!(!CompressionType::Zstd.is_unknown());
     }
 
     #[test]
@@ -387,6 +463,20 @@ mod tests {
         assert_eq!(codec.level(), CompressionLevel::Eight);
     }
 
+    #[test]
+    fn lz4_constructor() {
+        let codec = CompressionCodec::lz4(CompressionLevel::Two);
+        assert_eq!(codec.ty(), CompressionType::Lz4);
+        assert_eq!(codec.level(), CompressionLevel::Two);
+    }
+
+    #[test]
+    fn zstd_constructor() {
+        let codec = CompressionCodec::zstd(CompressionLevel::Seven);
+        assert_eq!(codec.ty(), CompressionType::Zstd);
+        assert_eq!(codec.level(), CompressionLevel::Seven);
+    }
+
     // -----------------------------------------------------------------------
     // from_type_and_level
     // -----------------------------------------------------------------------
@@ -401,8 +491,14 @@ mod tests {
         let gzip = CompressionCodec::from_type_and_level(CompressionType::Gzip, level).unwrap();
         assert_eq!(gzip, CompressionCodec::Gzip { level });
 
+        let lz4 = CompressionCodec::from_type_and_level(CompressionType::Lz4, level).unwrap();
+        assert_eq!(lz4, CompressionCodec::Lz4 { level });
+
         let zlib = CompressionCodec::from_type_and_level(CompressionType::Zlib, level).unwrap();
         assert_eq!(zlib, CompressionCodec::Zlib { level });
+
+        let zstd = CompressionCodec::from_type_and_level(CompressionType::Zstd, level).unwrap();
+        assert_eq!(zstd, CompressionCodec::Zstd { level });
     }
 
     #[test]
@@ -497,6 +593,22 @@ mod tests {
         assert_eq!(decoded.as_item(), b"");
     }
 
+    #[test]
+    fn encode_decode_empty_data_lz4() {
+        let mut codec = CompressionCodec::lz4(CompressionLevel::default());
+        let encoded = codec.encode(Frame::new(b"")).unwrap();
+        let decoded = codec.decode(encoded).unwrap();
+        assert_eq!(decoded.as_item(), b"");
+    }
+
+    #[test]
+    fn encode_decode_empty_data_zstd() {
+        let mut codec = CompressionCodec::zstd(CompressionLevel::default());
+        let encoded = codec.encode(Frame::new(b"")).unwrap();
+        let decoded = codec.decode(encoded).unwrap();
+        assert_eq!(decoded.as_item(), b"");
+    }
+
     // -----------------------------------------------------------------------
     // Large data encode/decode round-trip
     // -----------------------------------------------------------------------
@@ -508,7 +620,9 @@ mod tests {
         for mut codec in [
             CompressionCodec::deflate(CompressionLevel::BEST),
             CompressionCodec::gzip(CompressionLevel::BEST),
+            CompressionCodec::lz4(CompressionLevel::BEST),
             CompressionCodec::zlib(CompressionLevel::BEST),
+            CompressionCodec::zstd(CompressionLevel::BEST),
         ] {
             let encoded = codec.encode(Frame::new(&large_data)).unwrap();
             // Compressed data should typically be smaller
@@ -543,10 +657,14 @@ mod tests {
         ];
 
         for level in levels {
-            let mut codec = CompressionCodec::deflate(level);
-            let encoded = codec.encode(Frame::new(data)).unwrap();
-            let decoded = codec.decode(encoded).unwrap();
-            assert_eq!(decoded.as_item(), data);
+            for mut codec in [
+                CompressionCodec::deflate(level),
+                CompressionCodec::zstd(level),
+            ] {
+                let encoded = codec.encode(Frame::new(data)).unwrap();
+                let decoded = codec.decode(encoded).unwrap();
+                assert_eq!(decoded.as_item(), data);
+            }
         }
     }
 
@@ -575,6 +693,55 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn decode_corrupted_data_should_fail_lz4() {
+        let mut codec = CompressionCodec::lz4(CompressionLevel::default());
+        let result = codec.decode(Frame::new(b"this is not compressed"));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn decode_corrupted_data_should_fail_zstd() {
+        let mut codec = CompressionCodec::zstd(CompressionLevel::default());
+        let result = codec.decode(Frame::new(b"this is not compressed"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn decode_should_fail_if_lz4_size_exceeds_maximum() {
+        let mut item = ((CompressionCodec::MAX_DECOMPRESSED_SIZE + 1) as u32)
+            .to_le_bytes()
+            .to_vec();
+        item.extend_from_slice(b"tiny");
+
+        let mut codec = CompressionCodec::lz4(CompressionLevel::default());
+        let err = codec.decode(Frame::from(item)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn read_to_end_bounded_should_fail_if_zstd_data_exceeds_maximum() {
+        let item = zstd::encode_all([0u8; 1025].as_slice(), 1).unwrap();
+
+        let decoder = zstd::stream::read::Decoder::new(item.as_slice()).unwrap();
+        let err = read_to_end_bounded(decoder, 1024).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let decoder = zstd::stream::read::Decoder::new(item.as_slice()).unwrap();
+        assert_eq!(read_to_end_bounded(decoder, 1025).unwrap(), [0u8; 1025]);
+    }
+
+    #[test]
+    fn read_to_end_bounded_should_fail_if_deflate_data_exceeds_maximum() {
+        let mut item = Vec::new();
+        DeflateEncoder::new([0u8; 1025].as_slice(), Compression::best())
+            .read_to_end(&mut item)
+            .unwrap();
+
+        let err = read_to_end_bounded(DeflateDecoder::new(item.as_slice()), 1024).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
     // -----------------------------------------------------------------------
     // Clone and Copy
     // -----------------------------------------------------------------------
@@ -8,6 +8,9 @@ use sha2::Sha256;
 
 use crate::net::common::SecretKey32;
 
+mod identity;
+pub use identity::{ServerIdentity, ServerPublicKey};
+
 mod pkb;
 pub use pkb::PublicKeyBytes;
 
@@ -42,6 +45,29 @@ impl KeyExchange {
         &self.salt
     }
 
+    /// Returns the bytes describing an exchange between this side and another side with the
+    /// given public key and salt. Both sides of an exchange produce the same transcript, which is
+    /// what a server signs to prove its identity.
+    pub fn transcript(&self, public_key: &PublicKeyBytes, salt: &Salt) -> Vec<u8> {
+        let mut ours = Vec::from(self.pk_bytes());
+        ours.extend_from_slice(self.salt.as_ref());
+
+        let mut theirs = Vec::from(public_key.clone());
+        theirs.extend_from_slice(salt.as_ref());
+
+        // Order each side's contribution so that both sides agree regardless of role
+        let (first, second) = if ours <= theirs {
+            (ours, theirs)
+        } else {
+            (theirs, ours)
+        };
+
+        let mut transcript = b"distant-server-identity".to_vec();
+        transcript.extend(first);
+        transcript.extend(second);
+        transcript
+    }
+
     /// Derives a shared secret using another key exchange's public key and salt
     pub fn derive_shared_secret(
         &self,
@@ -118,6 +144,29 @@ mod tests {
         assert_eq!(s1, s2);
     }
 
+    #[test]
+    fn transcript_should_be_the_same_for_both_sides() {
+        let a = KeyExchange::default();
+        let b = KeyExchange::default();
+
+        assert_eq!(
+            a.transcript(&b.pk_bytes(), b.salt()),
+            b.transcript(&a.pk_bytes(), a.salt())
+        );
+    }
+
+    #[test]
+    fn transcript_should_differ_for_different_exchanges() {
+        let a = KeyExchange::default();
+        let b = KeyExchange::default();
+        let c = KeyExchange::default();
+
+        assert_ne!(
+            a.transcript(&b.pk_bytes(), b.salt()),
+            a.transcript(&c.pk_bytes(), c.salt())
+        );
+    }
+
     #[test]
     fn derive_shared_secret_should_produce_same_key_for_both_sides() {
         let a = KeyExchange::default();
@@ -0,0 +1,291 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use p256::ecdsa::signature::{Signer, Verifier};
+use p256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use p256::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
+
+/// Persistent keypair that a server uses to sign key exchanges, proving to clients that they are
+/// talking to the same server as before.
+#[derive(Clone)]
+pub struct ServerIdentity {
+    key: SigningKey,
+}
+
+impl ServerIdentity {
+    /// Generates a new, random identity.
+    pub fn generate() -> Self {
+        Self {
+            key: SigningKey::random(&mut OsRng),
+        }
+    }
+
+    /// Loads an identity from a PKCS#8 PEM-encoded private key.
+    pub fn from_pem(pem: &str) -> io::Result<Self> {
+        let key = SigningKey::from_pkcs8_pem(pem)
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x.to_string()))?;
+        Ok(Self { key })
+    }
+
+    /// Encodes the identity's private key as PKCS#8 PEM.
+    pub fn to_pem(&self) -> io::Result<String> {
+        let pem = self
+            .key
+            .to_pkcs8_pem(LineEnding::LF)
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x.to_string()))?;
+        Ok(pem.to_string())
+    }
+
+    /// Loads the identity stored at `path`, generating and storing a new one if the file does not
+    /// exist yet.
+    pub fn load_or_generate(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(pem) => return Self::from_pem(&pem),
+            Err(x) if x.kind() != io::ErrorKind::NotFound => return Err(x),
+            Err(_) => {}
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let identity = Self::generate();
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+
+        // Only the owner should be able to read the private key
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        match options.open(path) {
+            Ok(mut file) => {
+                file.write_all(identity.to_pem()?.as_bytes())?;
+                Ok(identity)
+            }
+
+            // Another process created the identity first, so we use theirs
+            Err(x) if x.kind() == io::ErrorKind::AlreadyExists => {
+                Self::from_pem(&fs::read_to_string(path)?)
+            }
+            Err(x) => Err(x),
+        }
+    }
+
+    /// Returns the public half of the identity, which is what clients remember.
+    pub fn public_key(&self) -> ServerPublicKey {
+        ServerPublicKey(*self.key.verifying_key())
+    }
+
+    /// Signs `msg`, returning the fixed-size encoding of the signature.
+    pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        let signature: Signature = self.key.sign(msg);
+        signature.to_bytes().to_vec()
+    }
+}
+
+impl fmt::Debug for ServerIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerIdentity")
+            .field("public_key", &self.public_key().fingerprint())
+            .finish()
+    }
+}
+
+impl PartialEq for ServerIdentity {
+    fn eq(&self, other: &Self) -> bool {
+        self.public_key() == other.public_key()
+    }
+}
+
+impl Eq for ServerIdentity {}
+
+/// Public key of a [`ServerIdentity`].
+///
+/// Displayed as `ecdsa-p256 <base64>`, which is the form stored in a known servers file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ServerPublicKey(VerifyingKey);
+
+impl ServerPublicKey {
+    /// Name of the signature algorithm used by server identities.
+    pub const ALGORITHM: &str = "ecdsa-p256";
+
+    /// Decodes a public key from its SEC1 bytes.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        VerifyingKey::from_sec1_bytes(bytes)
+            .map(Self)
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x.to_string()))
+    }
+
+    /// Encodes the public key as compressed SEC1 bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_encoded_point(true).as_bytes().to_vec()
+    }
+
+    /// Returns the SHA256 fingerprint of the key in the form `SHA256:<base64>`.
+    pub fn fingerprint(&self) -> String {
+        let digest = Sha256::digest(self.to_bytes());
+        format!("SHA256:{}", STANDARD_NO_PAD.encode(digest))
+    }
+
+    /// Verifies that `signature` was produced by this key's identity over `msg`.
+    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> io::Result<()> {
+        let signature = Signature::from_slice(signature)
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x.to_string()))?;
+        self.0.verify(msg, &signature).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Server identity signature is invalid",
+            )
+        })
+    }
+}
+
+impl fmt::Display for ServerPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            Self::ALGORITHM,
+            STANDARD.encode(self.to_bytes())
+        )
+    }
+}
+
+impl FromStr for ServerPublicKey {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let (algorithm, key) = s
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| invalid("Missing server public key"))?;
+        if algorithm != Self::ALGORITHM {
+            return Err(invalid(&format!(
+                "Unsupported server key algorithm: {algorithm}"
+            )));
+        }
+
+        let bytes = STANDARD
+            .decode(key.trim())
+            .map_err(|x| invalid(&x.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn generate_should_create_unique_identities() {
+        assert_ne!(ServerIdentity::generate(), ServerIdentity::generate());
+    }
+
+    #[test]
+    fn pem_should_round_trip() {
+        let identity = ServerIdentity::generate();
+        let pem = identity.to_pem().unwrap();
+        assert_eq!(ServerIdentity::from_pem(&pem).unwrap(), identity);
+    }
+
+    #[test]
+    fn from_pem_should_fail_if_not_a_private_key() {
+        let err = ServerIdentity::from_pem("not a key").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn load_or_generate_should_persist_identity_for_future_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("server_identity.pem");
+
+        let identity = ServerIdentity::load_or_generate(&path).unwrap();
+        assert!(path.exists(), "Identity was not stored");
+        assert_eq!(ServerIdentity::load_or_generate(&path).unwrap(), identity);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn load_or_generate_should_restrict_identity_file_to_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server_identity.pem");
+        ServerIdentity::load_or_generate(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn verify_should_succeed_for_signature_from_identity() {
+        let identity = ServerIdentity::generate();
+        let signature = identity.sign(b"transcript");
+        identity
+            .public_key()
+            .verify(b"transcript", &signature)
+            .unwrap();
+    }
+
+    #[test]
+    fn verify_should_fail_for_different_message() {
+        let identity = ServerIdentity::generate();
+        let signature = identity.sign(b"transcript");
+        let err = identity
+            .public_key()
+            .verify(b"other", &signature)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn verify_should_fail_for_signature_from_another_identity() {
+        let signature = ServerIdentity::generate().sign(b"transcript");
+        let err = ServerIdentity::generate()
+            .public_key()
+            .verify(b"transcript", &signature)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn public_key_should_round_trip_through_bytes_and_string() {
+        let key = ServerIdentity::generate().public_key();
+        assert_eq!(ServerPublicKey::from_bytes(&key.to_bytes()).unwrap(), key);
+        assert_eq!(key.to_string().parse::<ServerPublicKey>().unwrap(), key);
+        assert!(key.to_string().starts_with("ecdsa-p256 "));
+    }
+
+    #[test]
+    fn public_key_from_str_should_fail_for_unknown_algorithm() {
+        let key = ServerIdentity::generate().public_key();
+        let s = key.to_string().replace("ecdsa-p256", "ssh-rsa");
+        assert_eq!(
+            s.parse::<ServerPublicKey>().unwrap_err().to_string(),
+            "Unsupported server key algorithm: ssh-rsa"
+        );
+    }
+
+    #[test]
+    fn fingerprint_should_be_sha256_of_key() {
+        let key = ServerIdentity::generate().public_key();
+        let fingerprint = key.fingerprint();
+        assert!(fingerprint.starts_with("SHA256:"));
+        assert_eq!(fingerprint, key.fingerprint());
+        assert_ne!(
+            fingerprint,
+            ServerIdentity::generate().public_key().fingerprint()
+        );
+    }
+}
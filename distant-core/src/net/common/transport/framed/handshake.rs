@@ -1,4 +1,4 @@
-use super::{CompressionLevel, CompressionType, EncryptionType};
+use super::{CompressionLevel, CompressionType, EncryptionType, ServerIdentity};
 
 /// Definition of the handshake to perform for a transport
 #[derive(Clone, Debug)]
@@ -22,16 +22,22 @@ pub enum Handshake {
 
         /// List of available encryption algorithms for use between client and server
         encryption_types: Vec<EncryptionType>,
+
+        /// Identity used to sign the key exchange for clients verifying the server
+        identity: Option<ServerIdentity>,
     },
 }
 
 impl Handshake {
     /// Creates a new client handshake definition, providing defaults for the preferred compression
     /// type, compression level, and encryption type
+    ///
+    /// Zstandard at its fastest level is preferred, leaving the connection uncompressed with
+    /// servers too old to offer it
     pub fn client() -> Self {
         Self::Client {
-            preferred_compression_type: None,
-            preferred_compression_level: None,
+            preferred_compression_type: Some(CompressionType::Zstd),
+            preferred_compression_level: Some(CompressionLevel::FAST),
             preferred_encryption_type: Some(EncryptionType::XChaCha20Poly1305),
         }
     }
@@ -42,6 +48,17 @@ impl Handshake {
         Self::Server {
             compression_types: CompressionType::known_variants().to_vec(),
             encryption_types: EncryptionType::known_variants().to_vec(),
+            identity: None,
+        }
+    }
+
+    /// Creates a new server handshake definition like [`Handshake::server`], but signing the key
+    /// exchange with `identity` so that clients can verify the server
+    pub fn server_with_identity(identity: ServerIdentity) -> Self {
+        Self::Server {
+            compression_types: CompressionType::known_variants().to_vec(),
+            encryption_types: EncryptionType::known_variants().to_vec(),
+            identity: Some(identity),
         }
     }
 
@@ -85,8 +102,8 @@ mod tests {
                 preferred_compression_level,
                 preferred_encryption_type,
             } => {
-                assert!(preferred_compression_type.is_none());
-                assert!(preferred_compression_level.is_none());
+                assert_eq!(preferred_compression_type, Some(CompressionType::Zstd));
+                assert_eq!(preferred_compression_level, Some(CompressionLevel::FAST));
                 assert_eq!(
                     preferred_encryption_type,
                     Some(EncryptionType::XChaCha20Poly1305)
@@ -102,11 +119,24 @@ mod tests {
             Handshake::Server {
                 compression_types,
                 encryption_types,
+                identity,
             } => {
                 assert_eq!(compression_types, CompressionType::known_variants());
                 assert_eq!(encryption_types, EncryptionType::known_variants());
+                assert!(identity.is_none());
             }
             _ => panic!("Expected Server variant"),
         }
     }
+
+    #[test]
+    fn server_with_identity_includes_identity() {
+        let identity = ServerIdentity::generate();
+        match Handshake::server_with_identity(identity.clone()) {
+            Handshake::Server {
+                identity: Some(x), ..
+            } => assert_eq!(x, identity),
+            _ => panic!("Expected Server variant with identity"),
+        }
+    }
 }
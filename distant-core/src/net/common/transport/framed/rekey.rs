@@ -0,0 +1,259 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AdaptiveCompressionCodec, BoxedCodec, EncryptionType, KeyExchange, PublicKeyBytes, Salt,
+};
+
+/// Default bytes sent and received using a key before rekeying (1 GiB)
+const DEFAULT_MAX_BYTES: u64 = 1 << 30;
+
+/// Default frames sent and received using a key before rekeying
+const DEFAULT_MAX_FRAMES: u64 = 1 << 24;
+
+/// Default time spent using a key before rekeying
+const DEFAULT_MAX_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// Kind of frame carrying data for the user of the transport
+pub(super) const DATA_FRAME: u8 = 0;
+
+/// Kind of frame carrying a [`RekeyMessage`] for the transport itself
+pub(super) const REKEY_FRAME: u8 = 1;
+
+/// Thresholds at which a [`FramedTransport`] re-runs the key exchange with the other side to
+/// replace the key used for encryption, similar to rekeying in SSH. Reaching any one of the
+/// thresholds triggers a rekey, while a threshold of `None` is never reached.
+///
+/// Either side can start a rekey based on its own thresholds, and a side always joins in on a
+/// rekey started by the other side, even if its own thresholds are disabled.
+///
+/// [`FramedTransport`]: super::FramedTransport
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RekeyConfig {
+    /// Maximum bytes sent and received using a key
+    pub max_bytes: Option<u64>,
+
+    /// Maximum frames sent and received using a key
+    pub max_frames: Option<u64>,
+
+    /// Maximum time spent using a key
+    pub max_duration: Option<Duration>,
+}
+
+impl RekeyConfig {
+    /// Creates a config that never starts a rekey
+    pub const fn disabled() -> Self {
+        Self {
+            max_bytes: None,
+            max_frames: None,
+            max_duration: None,
+        }
+    }
+
+    /// Returns true if this config never starts a rekey
+    pub fn is_disabled(&self) -> bool {
+        self.max_bytes.is_none() && self.max_frames.is_none() && self.max_duration.is_none()
+    }
+}
+
+impl Default for RekeyConfig {
+    /// Rekeys after 1 GiB, 2^24 frames, or an hour, whichever comes first
+    fn default() -> Self {
+        Self {
+            max_bytes: Some(DEFAULT_MAX_BYTES),
+            max_frames: Some(DEFAULT_MAX_FRAMES),
+            max_duration: Some(DEFAULT_MAX_DURATION),
+        }
+    }
+}
+
+/// Messages exchanged by the two sides of a transport to rekey
+#[derive(Serialize, Deserialize)]
+pub(super) enum RekeyMessage {
+    /// One side's half of the key exchange, sent using the old key
+    KeyExchange {
+        #[serde(with = "serde_bytes")]
+        public_key: PublicKeyBytes,
+
+        #[serde(with = "serde_bytes")]
+        salt: Salt,
+    },
+
+    /// Marks the last frame that a side sends using the old key
+    NewKeys,
+}
+
+/// Usage of the current key alongside any rekey in progress
+#[derive(Clone)]
+pub(super) struct RekeyState {
+    /// Encryption negotiated during the handshake, used again for each new key
+    pub encryption_type: EncryptionType,
+
+    /// Compression negotiated during the handshake, kept across keys
+    pub compression: Option<AdaptiveCompressionCodec>,
+
+    /// Our half of a key exchange that is waiting on the other side's half
+    pub exchange: Option<Arc<KeyExchange>>,
+
+    /// Codec using the old key, which decodes frames until the other side switches keys
+    pub incoming_codec: Option<BoxedCodec>,
+
+    /// Number of times that the key has been replaced
+    pub generation: u64,
+
+    bytes: u64,
+    frames: u64,
+    since: Instant,
+}
+
+impl RekeyState {
+    pub fn new(
+        encryption_type: EncryptionType,
+        compression: Option<AdaptiveCompressionCodec>,
+    ) -> Self {
+        Self {
+            encryption_type,
+            compression,
+            exchange: None,
+            incoming_codec: None,
+            generation: 0,
+            bytes: 0,
+            frames: 0,
+            since: Instant::now(),
+        }
+    }
+
+    /// Records an encoded frame of `len` bytes as sent or received using the current key
+    pub fn record(&mut self, len: usize) {
+        self.bytes = self.bytes.saturating_add(len as u64);
+        self.frames = self.frames.saturating_add(1);
+    }
+
+    /// Returns true if `config` says to start a rekey, which never happens while one is ongoing
+    pub fn is_due(&self, config: &RekeyConfig) -> bool {
+        if self.exchange.is_some() || self.incoming_codec.is_some() {
+            return false;
+        }
+
+        config.max_bytes.is_some_and(|max| self.bytes >= max)
+            || config.max_frames.is_some_and(|max| self.frames >= max)
+            || config
+                .max_duration
+                .is_some_and(|max| self.since.elapsed() >= max)
+    }
+
+    /// Marks that the current key was replaced, resetting its usage
+    pub fn replaced(&mut self) {
+        self.generation += 1;
+        self.bytes = 0;
+        self.frames = 0;
+        self.since = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for RekeyConfig defaults and serde, and for RekeyState deciding when a rekey is due.
+
+    use test_log::test;
+
+    use super::*;
+
+    fn state() -> RekeyState {
+        RekeyState::new(EncryptionType::XChaCha20Poly1305, None)
+    }
+
+    #[test]
+    fn default_config_should_enable_all_thresholds() {
+        let config = RekeyConfig::default();
+        assert_eq!(config.max_bytes, Some(DEFAULT_MAX_BYTES));
+        assert_eq!(config.max_frames, Some(DEFAULT_MAX_FRAMES));
+        assert_eq!(config.max_duration, Some(DEFAULT_MAX_DURATION));
+        assert!(!config.is_disabled());
+    }
+
+    #[test]
+    fn disabled_config_should_have_no_thresholds() {
+        assert!(RekeyConfig::disabled().is_disabled());
+    }
+
+    #[test]
+    fn config_should_fill_in_missing_fields_with_defaults_when_deserialized() {
+        let config: RekeyConfig = serde_json::from_str(r#"{"max_frames": 5}"#).unwrap();
+        assert_eq!(
+            config,
+            RekeyConfig {
+                max_frames: Some(5),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn is_due_should_be_true_once_any_threshold_is_reached() {
+        let mut state = state();
+        let bytes = RekeyConfig {
+            max_bytes: Some(10),
+            ..RekeyConfig::disabled()
+        };
+        let frames = RekeyConfig {
+            max_frames: Some(2),
+            ..RekeyConfig::disabled()
+        };
+
+        state.record(6);
+        assert!(!state.is_due(&bytes));
+        assert!(!state.is_due(&frames));
+
+        state.record(6);
+        assert!(state.is_due(&bytes));
+        assert!(state.is_due(&frames));
+    }
+
+    #[test]
+    fn is_due_should_be_true_once_max_duration_has_passed() {
+        let config = RekeyConfig {
+            max_duration: Some(Duration::ZERO),
+            ..RekeyConfig::disabled()
+        };
+        assert!(state().is_due(&config));
+    }
+
+    #[test]
+    fn is_due_should_be_false_if_config_is_disabled() {
+        let mut state = state();
+        state.record(usize::MAX);
+        assert!(!state.is_due(&RekeyConfig::disabled()));
+    }
+
+    #[test]
+    fn is_due_should_be_false_while_rekey_is_ongoing() {
+        let config = RekeyConfig {
+            max_frames: Some(0),
+            ..RekeyConfig::disabled()
+        };
+
+        let mut state = state();
+        state.exchange = Some(Arc::new(KeyExchange::default()));
+        assert!(!state.is_due(&config));
+    }
+
+    #[test]
+    fn replaced_should_reset_usage_and_bump_generation() {
+        let config = RekeyConfig {
+            max_frames: Some(1),
+            ..RekeyConfig::disabled()
+        };
+
+        let mut state = state();
+        state.record(1);
+        assert!(state.is_due(&config));
+
+        state.replaced();
+        assert!(!state.is_due(&config));
+        assert_eq!(state.generation, 1);
+    }
+}
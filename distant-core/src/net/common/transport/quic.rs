@@ -0,0 +1,1146 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fmt, io};
+
+use bytes::{Buf, BufMut, BytesMut};
+use log::*;
+use quinn::crypto::rustls::{QuicClientConfig, QuicServerConfig};
+use quinn::{Connection, Endpoint, RecvStream, SendStream, TransportConfig};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use tokio::net::{ToSocketAddrs, lookup_host};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use super::framed::ORDERED_CHANNEL;
+use super::{Frame, InmemoryTransport, Interest, Ready, Reconnectable, Transport};
+
+/// Capacity of the channels that move data between the QUIC streams and the transport.
+const CHANNEL_CAPACITY: usize = 100;
+
+/// Maximum size of a single chunk read from a QUIC stream.
+const MAX_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Application protocol negotiated during the QUIC handshake.
+const ALPN: &[u8] = b"distant";
+
+/// Bytes written when opening a stream, as the other side is not told about a new stream until
+/// data is sent on it.
+const STREAM_HEADER: &[u8] = b"distant";
+
+/// Marks bytes written with [`Transport::try_write`], which are sent as they are.
+const RAW_DATA: u8 = 0;
+
+/// Marks bytes written with [`Transport::try_write_multiplexed`], which are split into frames.
+const MULTIPLEXED_DATA: u8 = 1;
+
+/// Number of unidirectional streams that each side spreads the channels of its frames across.
+const CHANNEL_STREAMS: u64 = 16;
+
+/// Maximum number of frames held back while waiting on earlier bytes written without a channel,
+/// past which the other side is considered to be misbehaving.
+const MAX_HELD_FRAMES: usize = 4096;
+
+/// Time between keepalive packets sent while the connection is idle, which also keeps NAT
+/// mappings alive.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Time without hearing from the peer before the connection is considered dead.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum time to wait for the peer to acknowledge the remaining data when closing.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Represents a [`Transport`] that sends frames over the streams of a QUIC connection.
+///
+/// The transport is [multiplexed](Transport::is_multiplexed): frames written on a channel go
+/// over one of several unidirectional streams picked by the channel, so a large frame or a lost
+/// packet only holds up the channels sharing its stream. Everything written without a channel
+/// (e.g. the handshake) goes over a bidirectional stream and stays in order with every frame.
+///
+/// QUIC runs over UDP and identifies connections by id rather than address, so the connection
+/// migrates when the client's address changes (e.g. when switching networks) instead of dying
+/// like TCP would. Migration can also be forced via [`QuicTransport::rebind`].
+///
+/// The QUIC handshake is encrypted with a self-signed certificate that the client does not
+/// verify; the server is authenticated by the framed codec and authentication that run on top,
+/// exactly as with [`TcpTransport`](super::TcpTransport).
+pub struct QuicTransport {
+    addr: SocketAddr,
+    endpoint: Option<Endpoint>,
+    connection: Connection,
+    inner: InmemoryTransport,
+}
+
+impl QuicTransport {
+    /// Creates a new transport by connecting to a QUIC server at the specified address, trying
+    /// each resolved address in turn.
+    pub async fn connect(addrs: impl ToSocketAddrs) -> io::Result<Self> {
+        let mut err = None;
+        for addr in lookup_host(addrs).await? {
+            match Self::connect_addr(addr).await {
+                Ok(this) => return Ok(this),
+                Err(x) => err = Some(x),
+            }
+        }
+
+        Err(err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "No addresses to connect to")
+        }))
+    }
+
+    async fn connect_addr(addr: SocketAddr) -> io::Result<Self> {
+        let mut endpoint = Endpoint::client(unspecified_addr(addr))?;
+        endpoint.set_default_client_config(quic_client_config()?);
+
+        let connection = endpoint
+            .connect(addr, "distant")
+            .map_err(io::Error::other)?
+            .await?;
+        let (mut send, recv) = connection.open_bi().await?;
+        send.write_all(STREAM_HEADER).await?;
+
+        Ok(Self::from_parts(
+            addr,
+            Some(endpoint),
+            connection,
+            send,
+            recv,
+        ))
+    }
+
+    /// Creates a new transport by accepting the stream opened by the client of an
+    /// already-established `connection`.
+    ///
+    /// Transports created this way cannot be reconnected.
+    pub(crate) async fn accept(connection: Connection) -> io::Result<Self> {
+        let (send, mut recv) = connection.accept_bi().await?;
+        read_stream_header(&mut recv).await?;
+
+        let addr = connection.remote_address();
+        Ok(Self::from_parts(addr, None, connection, send, recv))
+    }
+
+    /// Returns the address that the transport is connected to, which for an accepted transport
+    /// is the client's current address.
+    pub fn addr(&self) -> SocketAddr {
+        if self.endpoint.is_some() {
+            self.addr
+        } else {
+            self.connection.remote_address()
+        }
+    }
+
+    /// Moves the connection to a new local UDP socket, migrating it to a new path in the same
+    /// way as when the client's address changes, failing with [`ErrorKind::Unsupported`] if the
+    /// transport was accepted by a server.
+    ///
+    /// [`ErrorKind::Unsupported`]: io::ErrorKind::Unsupported
+    pub fn rebind(&self) -> io::Result<()> {
+        let endpoint = self.endpoint.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Server-side QUIC connections cannot rebind",
+            )
+        })?;
+
+        let socket = std::net::UdpSocket::bind(unspecified_addr(self.addr))?;
+        endpoint.rebind(socket)
+    }
+
+    /// Writes `buf` to the bridge task, marked with the `kind` of write that it came from.
+    fn try_write_kind(&self, kind: u8, buf: &[u8]) -> io::Result<usize> {
+        let mut data = Vec::with_capacity(buf.len() + 1);
+        data.push(kind);
+        data.extend_from_slice(buf);
+
+        match self.inner.try_write(&data)? {
+            0 => Ok(0),
+            _ => Ok(buf.len()),
+        }
+    }
+
+    fn from_parts(
+        addr: SocketAddr,
+        endpoint: Option<Endpoint>,
+        connection: Connection,
+        send: SendStream,
+        recv: RecvStream,
+    ) -> Self {
+        let (incoming_tx, outgoing_rx, inner) = InmemoryTransport::make(CHANNEL_CAPACITY);
+        tokio::spawn(bridge_task(
+            connection.clone(),
+            send,
+            recv,
+            incoming_tx,
+            outgoing_rx,
+        ));
+
+        Self {
+            addr,
+            endpoint,
+            connection,
+            inner,
+        }
+    }
+}
+
+impl fmt::Debug for QuicTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuicTransport")
+            .field("addr", &self.addr())
+            .finish()
+    }
+}
+
+impl Reconnectable for QuicTransport {
+    /// Establishes a new connection to the same address, failing with
+    /// [`ErrorKind::Unsupported`] if the transport was accepted by a server.
+    ///
+    /// [`ErrorKind::Unsupported`]: io::ErrorKind::Unsupported
+    fn reconnect<'a>(
+        &'a mut self,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = io::Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            if self.endpoint.is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Server-side QUIC connections cannot reconnect",
+                ));
+            }
+
+            *self = Self::connect_addr(self.addr).await?;
+            Ok(())
+        })
+    }
+}
+
+impl Transport for QuicTransport {
+    fn try_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.try_read(buf)
+    }
+
+    fn try_write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.try_write_kind(RAW_DATA, buf)
+    }
+
+    fn ready<'a>(
+        &'a self,
+        interest: Interest,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = io::Result<Ready>> + Send + 'a>> {
+        self.inner.ready(interest)
+    }
+
+    fn is_multiplexed(&self) -> bool {
+        true
+    }
+
+    fn try_write_multiplexed(&self, buf: &[u8]) -> io::Result<usize> {
+        self.try_write_kind(MULTIPLEXED_DATA, buf)
+    }
+}
+
+/// Data received over one of the streams of a QUIC connection, along with the `u64` written
+/// ahead of it.
+enum Received {
+    /// Bytes written without a channel after `count` frames written on a channel since the
+    /// previous bytes, where missing bytes mean that the other side finished sending
+    Ordered { count: u64, data: Option<Vec<u8>> },
+
+    /// Frame written on a channel after `epoch` frames written without one
+    Channel { epoch: u64, frame: Vec<u8> },
+
+    /// Stream failed, meaning that frames may have been lost
+    Failed,
+}
+
+/// Moves frames between the streams of a QUIC connection and the channels of an
+/// [`InmemoryTransport`]. The `connection` is held so that it stays open for as long as the
+/// streams are in use.
+async fn bridge_task(
+    connection: Connection,
+    send: SendStream,
+    recv: RecvStream,
+    incoming_tx: mpsc::Sender<Vec<u8>>,
+    mut outgoing_rx: mpsc::Receiver<Vec<u8>>,
+) {
+    let (received_tx, mut received_rx) = mpsc::channel(CHANNEL_CAPACITY);
+    tokio::spawn(read_task(recv, true, received_tx.clone()));
+    tokio::spawn(accept_task(connection.clone(), received_tx));
+
+    let mut outgoing = Outgoing::new(connection.clone(), send);
+    let mut reorder = Reorder::default();
+
+    'bridge: loop {
+        tokio::select! {
+            received = received_rx.recv() => {
+                let ready = match received {
+                    Some(Received::Ordered { count, data }) => reorder.push_ordered(count, data),
+                    Some(Received::Channel { epoch, frame }) => reorder.push_channel(epoch, frame),
+                    Some(Received::Failed) | None => break,
+                };
+
+                let ready = match ready {
+                    Ok(ready) => ready,
+                    Err(x) => {
+                        error!("QUIC connection received invalid data: {x}");
+                        break;
+                    }
+                };
+
+                for data in ready {
+                    // Missing data marks the end of what the other side sent
+                    let Some(data) = data else {
+                        break 'bridge;
+                    };
+
+                    if incoming_tx.send(data).await.is_err() {
+                        break 'bridge;
+                    }
+                }
+            }
+            data = outgoing_rx.recv() => {
+                let Some(data) = data else {
+                    break;
+                };
+
+                if let Err(x) = outgoing.push(&data) {
+                    error!("QUIC connection failed to send data: {x}");
+                    break;
+                }
+            }
+        }
+    }
+
+    outgoing.finish().await;
+    connection.close(0u32.into(), b"");
+    trace!("QUIC bridge task closed");
+}
+
+/// Accepts the unidirectional streams opened by the other side, reading the frames of each.
+async fn accept_task(connection: Connection, tx: mpsc::Sender<Received>) {
+    while let Ok(mut recv) = connection.accept_uni().await {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match read_stream_header(&mut recv).await {
+                Ok(()) => read_task(recv, false, tx).await,
+                Err(x) => {
+                    error!("QUIC stream failed to start: {x}");
+                    let _ = tx.send(Received::Failed).await;
+                }
+            }
+        });
+    }
+}
+
+/// Reads the header written by the other side when opening a stream.
+async fn read_stream_header(recv: &mut RecvStream) -> io::Result<()> {
+    let mut header = [0u8; STREAM_HEADER.len()];
+    recv.read_exact(&mut header)
+        .await
+        .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?;
+    if header != STREAM_HEADER {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid QUIC stream header",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Reads from a stream, passing what it reads along as [`Received::Ordered`] if the stream is
+/// `ordered` or as [`Received::Channel`] otherwise.
+async fn read_task(mut recv: RecvStream, ordered: bool, tx: mpsc::Sender<Received>) {
+    let mut buf = BytesMut::new();
+
+    loop {
+        match recv.read_chunk(MAX_CHUNK_SIZE, true).await {
+            Ok(Some(chunk)) => buf.extend_from_slice(&chunk.bytes),
+
+            // The other side ends the ordered stream with the count of frames written on a
+            // channel since its last ordered bytes, so that those are not cut off
+            Ok(None) if ordered && buf.len() == 8 => {
+                let count = buf.get_u64();
+                let _ = tx.send(Received::Ordered { count, data: None }).await;
+                return;
+            }
+            Ok(None) if ordered => break,
+            Ok(None) => return,
+            Err(x) => {
+                error!("QUIC stream failed to receive data: {x}");
+                break;
+            }
+        }
+
+        loop {
+            let size = match tagged_frame_size(&buf) {
+                Ok(Some(size)) => size,
+                Ok(None) => break,
+                Err(x) => {
+                    error!("QUIC stream received invalid data: {x}");
+                    let _ = tx.send(Received::Failed).await;
+                    return;
+                }
+            };
+
+            // Bytes written without a channel are sent as {COUNT}{LEN}{BYTES}, while frames
+            // written on a channel are sent as {EPOCH}{FRAME}
+            let mut data = buf.split_to(size);
+            let tag = data.get_u64();
+            let received = if ordered {
+                data.advance(8);
+                Received::Ordered {
+                    count: tag,
+                    data: Some(data.to_vec()),
+                }
+            } else {
+                Received::Channel {
+                    epoch: tag,
+                    frame: data.to_vec(),
+                }
+            };
+
+            if tx.send(received).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    let _ = tx.send(Received::Failed).await;
+}
+
+/// Writes the data queued for a stream until the queue is closed, and then finishes the stream.
+async fn write_task(mut send: SendStream, mut rx: mpsc::UnboundedReceiver<Vec<u8>>) {
+    while let Some(data) = rx.recv().await {
+        if let Err(x) = send.write_all(&data).await {
+            error!("QUIC stream failed to send data: {x}");
+            return;
+        }
+    }
+
+    if send.finish().is_ok() {
+        let _ = send.stopped().await;
+    }
+}
+
+/// Returns the size of the frame at the start of `buf` including the `u64` written ahead of it,
+/// or `None` if `buf` does not hold all of it yet.
+///
+/// As bytes written without a channel are sent with their length, this works for those too.
+fn tagged_frame_size(buf: &[u8]) -> io::Result<Option<usize>> {
+    const HEADER_SIZE: usize = 8 + Frame::HEADER_SIZE;
+
+    if buf.len() < HEADER_SIZE {
+        return Ok(None);
+    }
+
+    let len = (&buf[8..HEADER_SIZE]).get_u64();
+    let size = usize::try_from(len)
+        .ok()
+        .and_then(|len| len.checked_add(HEADER_SIZE))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Frame is too large"))?;
+
+    Ok((buf.len() >= size).then_some(size))
+}
+
+/// Sends the bytes written to a [`QuicTransport`] over the streams of its connection.
+///
+/// Bytes written without a channel go over the bidirectional stream, tagged with the number of
+/// frames written on a channel since the last bytes written without one. Frames written on a
+/// channel go over the stream of their channel, tagged with the number of writes without a
+/// channel before them. This is all the other side needs to put them back in order.
+struct Outgoing {
+    connection: Connection,
+
+    /// Bytes written with their channel that do not yet make up a whole frame
+    buf: BytesMut,
+
+    /// Number of writes sent without a channel
+    epoch: u64,
+
+    /// Number of frames sent on a channel since the last write sent without one
+    count: u64,
+
+    /// Queue of the bidirectional stream that carries bytes written without a channel
+    ordered: mpsc::UnboundedSender<Vec<u8>>,
+
+    /// Queues of the unidirectional streams that carry frames written on a channel
+    channels: HashMap<u64, mpsc::UnboundedSender<Vec<u8>>>,
+
+    /// Tasks writing to each stream
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Outgoing {
+    fn new(connection: Connection, send: SendStream) -> Self {
+        let (ordered, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(write_task(send, rx));
+
+        Self {
+            connection,
+            buf: BytesMut::new(),
+            epoch: 0,
+            count: 0,
+            ordered,
+            channels: HashMap::new(),
+            tasks: vec![task],
+        }
+    }
+
+    /// Adds `data` written to the transport, marked with the kind of write that it came from.
+    fn push(&mut self, data: &[u8]) -> io::Result<()> {
+        match data.split_first() {
+            Some((&RAW_DATA, bytes)) => self.send_ordered(bytes),
+            Some((&MULTIPLEXED_DATA, bytes)) => {
+                self.buf.extend_from_slice(bytes);
+
+                // Frames are written as {CHANNEL}{LEN}{ITEM}
+                while let Some(size) = tagged_frame_size(&self.buf)? {
+                    let mut frame = self.buf.split_to(size);
+                    match frame.get_u64() {
+                        ORDERED_CHANNEL => self.send_ordered(&frame)?,
+                        channel => self.send_channel(channel, &frame)?,
+                    }
+                }
+
+                Ok(())
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown kind of write",
+            )),
+        }
+    }
+
+    /// Sends `bytes` written without a channel over the bidirectional stream.
+    fn send_ordered(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let mut data = Vec::with_capacity(16 + bytes.len());
+        data.put_u64(std::mem::take(&mut self.count));
+        data.put_u64(bytes.len() as u64);
+        data.extend_from_slice(bytes);
+        self.epoch += 1;
+
+        self.ordered
+            .send(data)
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+
+    /// Sends a `frame` written on `channel` over the unidirectional stream of the channel.
+    fn send_channel(&mut self, channel: u64, frame: &[u8]) -> io::Result<()> {
+        let mut data = Vec::with_capacity(8 + frame.len());
+        data.put_u64(self.epoch);
+        data.extend_from_slice(frame);
+        self.count += 1;
+
+        self.channel_queue(channel % CHANNEL_STREAMS)
+            .send(data)
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+
+    /// Returns the queue of the unidirectional stream at `index`, opening the stream first if
+    /// nothing has been sent over it yet.
+    fn channel_queue(&mut self, index: u64) -> &mpsc::UnboundedSender<Vec<u8>> {
+        self.channels.entry(index).or_insert_with(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            let connection = self.connection.clone();
+            self.tasks.push(tokio::spawn(async move {
+                let mut send = match connection.open_uni().await {
+                    Ok(send) => send,
+                    Err(x) => {
+                        error!("QUIC connection failed to open stream: {x}");
+                        return;
+                    }
+                };
+
+                match send.write_all(STREAM_HEADER).await {
+                    Ok(()) => write_task(send, rx).await,
+                    Err(x) => error!("QUIC stream failed to start: {x}"),
+                }
+            }));
+            tx
+        })
+    }
+
+    /// Finishes every stream, giving the other side a chance to receive anything still in
+    /// flight.
+    async fn finish(self) {
+        let _ = self.ordered.send(self.count.to_be_bytes().to_vec());
+        drop(self.ordered);
+        drop(self.channels);
+
+        let _ = tokio::time::timeout(CLOSE_TIMEOUT, futures::future::join_all(self.tasks)).await;
+    }
+}
+
+/// Puts data received over different streams back in order, such that bytes written without a
+/// channel are delivered after every frame written before them and before every frame written
+/// after them. Frames written on a channel in between are delivered as they arrive.
+#[derive(Default)]
+struct Reorder {
+    /// Number of writes delivered that were made without a channel
+    epoch: u64,
+
+    /// Number of frames delivered that were written on a channel since the last write delivered
+    /// that was made without one
+    delivered: u64,
+
+    /// Bytes written without a channel that wait on frames written before them
+    ordered: VecDeque<(u64, Option<Vec<u8>>)>,
+
+    /// Frames written on a channel that wait on bytes written without one, keyed by the number
+    /// of writes made without a channel before them
+    held: BTreeMap<u64, Vec<Vec<u8>>>,
+
+    /// Total number of frames in `held`
+    held_cnt: usize,
+}
+
+impl Reorder {
+    /// Adds bytes written without a channel after `count` frames written on a channel since the
+    /// previous bytes, returning the data that can now be delivered.
+    fn push_ordered(
+        &mut self,
+        count: u64,
+        data: Option<Vec<u8>>,
+    ) -> io::Result<Vec<Option<Vec<u8>>>> {
+        self.ordered.push_back((count, data));
+
+        let mut frames = Vec::new();
+        self.release(&mut frames)?;
+        Ok(frames)
+    }
+
+    /// Adds a frame written on a channel after `epoch` writes made without one, returning the
+    /// data that can now be delivered.
+    fn push_channel(&mut self, epoch: u64, frame: Vec<u8>) -> io::Result<Vec<Option<Vec<u8>>>> {
+        if epoch < self.epoch {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Frame arrived after ordered bytes written after it",
+            ));
+        }
+
+        if epoch > self.epoch {
+            if self.held_cnt >= MAX_HELD_FRAMES {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Too many frames waiting on ordered bytes",
+                ));
+            }
+
+            self.held.entry(epoch).or_default().push(frame);
+            self.held_cnt += 1;
+            return Ok(Vec::new());
+        }
+
+        let mut frames = vec![Some(frame)];
+        self.delivered += 1;
+        self.release(&mut frames)?;
+        Ok(frames)
+    }
+
+    /// Releases bytes written without a channel once the frames written before them were all
+    /// delivered, along with the frames written on a channel that waited on them.
+    fn release(&mut self, frames: &mut Vec<Option<Vec<u8>>>) -> io::Result<()> {
+        while let Some((count, data)) = self.ordered.pop_front() {
+            if count > self.delivered {
+                self.ordered.push_front((count, data));
+                break;
+            } else if count < self.delivered {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "More frames arrived than were written before ordered bytes",
+                ));
+            }
+
+            frames.push(data);
+            self.epoch += 1;
+
+            let held = self.held.remove(&self.epoch).unwrap_or_default();
+            self.held_cnt -= held.len();
+            self.delivered = held.len() as u64;
+            frames.extend(held.into_iter().map(Some));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the unspecified address of the same family as `addr`, used to bind the local socket
+/// of a client.
+fn unspecified_addr(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    }
+}
+
+fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn transport_config() -> io::Result<Arc<TransportConfig>> {
+    let mut config = TransportConfig::default();
+    config
+        .keep_alive_interval(Some(KEEPALIVE_INTERVAL))
+        .max_idle_timeout(Some(
+            IDLE_TIMEOUT
+                .try_into()
+                .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?,
+        ));
+    Ok(Arc::new(config))
+}
+
+/// Creates the configuration for a client, which accepts any certificate from the server.
+pub(crate) fn quic_client_config() -> io::Result<quinn::ClientConfig> {
+    let provider = crypto_provider();
+    let mut crypto = rustls::ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(io::Error::other)?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)))
+        .with_no_client_auth();
+    crypto.alpn_protocols = vec![ALPN.to_vec()];
+
+    let mut config = quinn::ClientConfig::new(Arc::new(
+        QuicClientConfig::try_from(crypto).map_err(io::Error::other)?,
+    ));
+    config.transport_config(transport_config()?);
+    Ok(config)
+}
+
+/// Creates the configuration for a server using a freshly-generated self-signed certificate,
+/// allowing clients to migrate to new addresses.
+pub(crate) fn quic_server_config() -> io::Result<quinn::ServerConfig> {
+    let certified = rcgen::generate_simple_self_signed(vec!["distant".to_string()])
+        .map_err(io::Error::other)?;
+    let cert = CertificateDer::from(certified.cert);
+    let key = PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der());
+
+    let mut crypto = rustls::ServerConfig::builder_with_provider(crypto_provider())
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(io::Error::other)?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key.into())
+        .map_err(io::Error::other)?;
+    crypto.alpn_protocols = vec![ALPN.to_vec()];
+
+    let mut config = quinn::ServerConfig::with_crypto(Arc::new(
+        QuicServerConfig::try_from(crypto).map_err(io::Error::other)?,
+    ));
+    config.transport_config(transport_config()?).migration(true);
+    Ok(config)
+}
+
+/// Certificate verifier that accepts any certificate, while still checking that the handshake
+/// was signed by the key of the certificate presented.
+#[derive(Debug)]
+struct SkipServerVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+    use crate::net::common::{FramedTransport, RekeyConfig};
+
+    /// Starts a QUIC server on an ephemeral loopback port that echoes back each frame it
+    /// receives, returning the address to reach it.
+    fn start_echo_server() -> SocketAddr {
+        let endpoint = Endpoint::server(
+            quic_server_config().unwrap(),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+        )
+        .expect("Failed to bind on an ephemeral port");
+        let addr = endpoint.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            while let Some(incoming) = endpoint.accept().await {
+                tokio::spawn(async move {
+                    let transport = QuicTransport::accept(incoming.await.unwrap())
+                        .await
+                        .unwrap();
+                    let mut transport = FramedTransport::plain(transport);
+                    while let Some(frame) = transport.read_frame().await.unwrap() {
+                        transport.write_frame(frame).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        addr
+    }
+
+    /// Connects to the QUIC server at `addr`, reading and writing plain frames.
+    async fn connect(addr: SocketAddr) -> FramedTransport<QuicTransport> {
+        FramedTransport::plain(QuicTransport::connect(addr).await.unwrap())
+    }
+
+    /// Writes `data` to `transport` and then reads the frame echoed back.
+    async fn echo(transport: &mut FramedTransport<QuicTransport>, data: &[u8]) -> Vec<u8> {
+        transport.write_frame(data).await.unwrap();
+        transport
+            .read_frame()
+            .await
+            .unwrap()
+            .unwrap()
+            .into_item()
+            .into_owned()
+    }
+
+    #[test(tokio::test)]
+    async fn should_fail_to_connect_if_nothing_listening() {
+        // Bind a plain UDP socket so that nothing answers the handshake
+        let socket = std::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = socket.local_addr().unwrap();
+        drop(socket);
+
+        let result =
+            tokio::time::timeout(Duration::from_secs(2), QuicTransport::connect(addr)).await;
+        assert!(
+            !matches!(result, Ok(Ok(_))),
+            "Unexpectedly connected to ghost address"
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_read_and_write_data() {
+        let addr = start_echo_server();
+        let mut transport = connect(addr).await;
+        assert_eq!(transport.as_inner().addr(), addr);
+
+        assert_eq!(echo(&mut transport, b"hello server").await, b"hello server");
+    }
+
+    #[test(tokio::test)]
+    async fn should_support_data_larger_than_a_single_chunk() {
+        let addr = start_echo_server();
+        let mut transport = connect(addr).await;
+
+        let data: Vec<u8> = (0..MAX_CHUNK_SIZE * 3).map(|i| i as u8).collect();
+        assert_eq!(echo(&mut transport, &data).await, data);
+    }
+
+    #[test(tokio::test)]
+    async fn should_keep_frames_without_a_channel_in_order_with_frames_of_every_channel() {
+        let endpoint = Endpoint::server(
+            quic_server_config().unwrap(),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+        )
+        .unwrap();
+        let addr = endpoint.local_addr().unwrap();
+
+        let client = tokio::spawn(async move {
+            let mut transport = connect(addr).await;
+            let large = vec![b'x'; MAX_CHUNK_SIZE * 4];
+            transport.try_write_frame_on("a", b"a1").unwrap();
+            transport.try_write_frame_on("b", large.as_slice()).unwrap();
+            transport.try_write_frame(b"ordered1").unwrap();
+            transport.try_write_frame_on("a", b"a2").unwrap();
+            transport.try_write_frame_on("b", b"b2").unwrap();
+            transport.try_write_frame(b"ordered2").unwrap();
+            transport.flush().await.unwrap();
+
+            // Keep the connection open until the server has read everything
+            transport.read_frame().await.unwrap();
+        });
+
+        let incoming = endpoint.accept().await.unwrap();
+        let transport = QuicTransport::accept(incoming.await.unwrap())
+            .await
+            .unwrap();
+        let mut transport = FramedTransport::plain(transport);
+
+        let mut frames = Vec::new();
+        for _ in 0..6 {
+            let frame = transport.read_frame().await.unwrap().unwrap();
+            frames.push(match frame.as_item() {
+                item if item.len() > 8 => b"large".to_vec(),
+                item => item.to_vec(),
+            });
+        }
+        transport.write_frame(b"done").await.unwrap();
+        client.await.unwrap();
+
+        let position = |item: &[u8]| frames.iter().position(|x| x == item).unwrap();
+        assert_eq!(position(b"ordered1"), 2);
+        assert_eq!(position(b"ordered2"), 5);
+        assert!(position(b"a1") < position(b"a2"));
+        assert!(position(b"large") < position(b"b2"));
+    }
+
+    #[test(tokio::test)]
+    async fn should_support_rekeying_while_frames_are_written_on_channels() {
+        let endpoint = Endpoint::server(
+            quic_server_config().unwrap(),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+        )
+        .unwrap();
+        let addr = endpoint.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let incoming = endpoint.accept().await.unwrap();
+            let transport = QuicTransport::accept(incoming.await.unwrap())
+                .await
+                .unwrap();
+            let mut transport = FramedTransport::from_server_handshake(transport)
+                .await
+                .unwrap();
+            transport.set_rekey_config(RekeyConfig {
+                max_frames: Some(3),
+                ..RekeyConfig::disabled()
+            });
+
+            // Echo each frame back on the channel named by its first byte
+            for _ in 0..20 {
+                let frame = transport.read_frame().await.unwrap().unwrap();
+                let channel = String::from_utf8_lossy(&frame.as_item()[..1]).to_string();
+                transport.try_write_frame_on(&channel, frame).unwrap();
+                transport.flush().await.unwrap();
+            }
+            transport.read_frame().await.unwrap();
+        });
+
+        let transport = QuicTransport::connect(addr).await.unwrap();
+        let mut transport = FramedTransport::from_client_handshake(transport)
+            .await
+            .unwrap();
+        transport.set_rekey_config(RekeyConfig {
+            max_frames: Some(3),
+            ..RekeyConfig::disabled()
+        });
+
+        let mut expected = Vec::new();
+        for i in 0..20 {
+            let data = format!("{}{i}", i % 4);
+            transport
+                .try_write_frame_on(&data[..1], data.as_bytes())
+                .unwrap();
+            expected.push(data);
+        }
+        transport.flush().await.unwrap();
+
+        let mut received = Vec::new();
+        for _ in 0..20 {
+            let frame = transport.read_frame().await.unwrap().unwrap();
+            received.push(String::from_utf8(frame.into_item().into_owned()).unwrap());
+        }
+        transport.write_frame(b"done").await.unwrap();
+        server.await.unwrap();
+
+        // Frames of a channel arrive in order, even if channels are interleaved differently
+        for channel in ["0", "1", "2", "3"] {
+            let of_channel = |frames: &[String]| {
+                frames
+                    .iter()
+                    .filter(|x| x.starts_with(channel))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(of_channel(&received), of_channel(&expected));
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn should_keep_working_after_migrating_to_a_new_address() {
+        let addr = start_echo_server();
+        let mut transport = connect(addr).await;
+
+        assert_eq!(echo(&mut transport, b"before").await, b"before");
+
+        let inner = transport.as_inner();
+        let old_local_addr = inner.endpoint.as_ref().unwrap().local_addr().unwrap();
+        inner.rebind().unwrap();
+        let new_local_addr = inner.endpoint.as_ref().unwrap().local_addr().unwrap();
+        assert_ne!(old_local_addr.port(), new_local_addr.port());
+
+        assert_eq!(echo(&mut transport, b"after").await, b"after");
+    }
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_reconnect() {
+        let addr = start_echo_server();
+        let mut transport = connect(addr).await;
+
+        transport.as_mut_inner().reconnect().await.unwrap();
+
+        assert_eq!(echo(&mut transport, b"hello again").await, b"hello again");
+    }
+
+    #[test(tokio::test)]
+    async fn should_resend_exactly_the_frames_lost_when_the_connection_dies() {
+        let endpoint = Endpoint::server(
+            quic_server_config().unwrap(),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+        )
+        .unwrap();
+        let addr = endpoint.local_addr().unwrap();
+
+        async fn accept(endpoint: Endpoint) -> FramedTransport<QuicTransport> {
+            let incoming = endpoint.accept().await.unwrap();
+            let transport = QuicTransport::accept(incoming.await.unwrap())
+                .await
+                .unwrap();
+            FramedTransport::plain(transport)
+        }
+
+        let server = tokio::spawn(accept(endpoint.clone()));
+
+        // The small frame goes over a different stream than the large one written before it, so
+        // it arrives while the large one is still in flight
+        let mut client = connect(addr).await;
+        let large = vec![b'x'; 16 * 1024 * 1024];
+        client.try_write_frame_on("a", large.as_slice()).unwrap();
+        client.try_write_frame_on("b", b"small").unwrap();
+        client.flush().await.unwrap();
+
+        let mut server = server.await.unwrap();
+        assert_eq!(server.read_frame().await.unwrap().unwrap(), b"small");
+
+        // Kill the connection with the large frame still in flight, keeping the backup around
+        // like a server does for a client to reconnect
+        let backup = std::mem::take(&mut server.backup);
+        server.as_inner().connection.close(0u32.into(), b"");
+        drop(server);
+
+        let server = tokio::spawn(async move {
+            let mut server = accept(endpoint).await;
+            server.backup = backup;
+            server.synchronize().await.unwrap();
+
+            let mut frames = Vec::new();
+            for _ in 0..2 {
+                let frame = server.read_frame().await.unwrap().unwrap();
+                frames.push(frame.into_item().into_owned());
+            }
+            (server, frames)
+        });
+
+        client.reconnect().await.unwrap();
+        client.synchronize().await.unwrap();
+        client.write_frame(b"done").await.unwrap();
+
+        // Only the lost frame is sent again, rather than the one that was already delivered
+        let (server, frames) = server.await.unwrap();
+        assert_eq!(frames, vec![large, b"done".to_vec()]);
+        assert_eq!(server.backup.received_cnt(), 3);
+        assert_eq!(server.backup.received_ahead().count(), 0);
+    }
+
+    #[test(tokio::test)]
+    async fn reconnect_and_rebind_should_fail_for_accepted_transport() {
+        let endpoint = Endpoint::server(
+            quic_server_config().unwrap(),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+        )
+        .unwrap();
+        let addr = endpoint.local_addr().unwrap();
+
+        let client = tokio::spawn(QuicTransport::connect(addr));
+        let incoming = endpoint.accept().await.unwrap();
+        let mut transport = QuicTransport::accept(incoming.await.unwrap())
+            .await
+            .unwrap();
+        let _client = client.await.unwrap().unwrap();
+
+        let err = transport.rebind().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+        let err = transport.reconnect().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn reorder_should_hold_ordered_frame_until_earlier_channel_frames_arrive() {
+        let mut reorder = Reorder::default();
+
+        assert!(
+            reorder
+                .push_ordered(2, Some(b"o".to_vec()))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            reorder.push_channel(0, b"a".to_vec()).unwrap(),
+            [Some(b"a".to_vec())]
+        );
+        assert_eq!(
+            reorder.push_channel(0, b"b".to_vec()).unwrap(),
+            [Some(b"b".to_vec()), Some(b"o".to_vec())]
+        );
+    }
+
+    #[test]
+    fn reorder_should_hold_channel_frames_until_earlier_ordered_frame_arrives() {
+        let mut reorder = Reorder::default();
+
+        assert!(reorder.push_channel(1, b"a".to_vec()).unwrap().is_empty());
+        assert_eq!(
+            reorder.push_ordered(0, Some(b"o".to_vec())).unwrap(),
+            [Some(b"o".to_vec()), Some(b"a".to_vec())]
+        );
+
+        // The frame released by the ordered frame counts towards the next one
+        assert_eq!(reorder.push_ordered(1, None).unwrap(), [None]);
+    }
+
+    #[test]
+    fn reorder_should_fail_if_more_channel_frames_arrive_than_were_written() {
+        let mut reorder = Reorder::default();
+
+        reorder.push_channel(0, b"a".to_vec()).unwrap();
+        reorder.push_channel(0, b"b".to_vec()).unwrap();
+        let err = reorder.push_ordered(1, None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
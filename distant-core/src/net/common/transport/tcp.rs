@@ -1,10 +1,36 @@
 use std::net::IpAddr;
+use std::time::Duration;
 use std::{fmt, io};
 
+use log::*;
+use socket2::{SockRef, TcpKeepalive};
 use tokio::net::{TcpStream, ToSocketAddrs};
 
 use super::{Interest, Ready, Reconnectable, Transport};
 
+/// Time a connection can sit idle before the first keepalive probe is sent.
+const KEEPALIVE_TIME: Duration = Duration::from_secs(15);
+
+/// Time between keepalive probes once the connection has gone idle.
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Enables TCP keepalive on `stream` so that dead peers (e.g. after the network changes) are
+/// detected by the operating system and NAT mappings are kept alive while the link is idle.
+///
+/// Failing to configure keepalive is not fatal, as the client still detects dead peers through
+/// missing server heartbeats, so the error is only logged.
+pub(crate) fn enable_keepalive(stream: &TcpStream) {
+    let keepalive = TcpKeepalive::new().with_time(KEEPALIVE_TIME);
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    let keepalive = keepalive.with_interval(KEEPALIVE_INTERVAL);
+
+    if let Err(x) = SockRef::from(stream).set_tcp_keepalive(&keepalive) {
+        warn!("Failed to enable TCP keepalive: {x}");
+    }
+}
+
 /// Represents a [`Transport`] that leverages a TCP stream
 pub struct TcpTransport {
     pub(crate) addr: IpAddr,
@@ -17,6 +43,7 @@ impl TcpTransport {
     /// IP address and port
     pub async fn connect(addrs: impl ToSocketAddrs) -> io::Result<Self> {
         let stream = TcpStream::connect(addrs).await?;
+        enable_keepalive(&stream);
         let addr = stream.peer_addr()?;
         Ok(Self {
             addr: addr.ip(),
@@ -51,6 +78,7 @@ impl Reconnectable for TcpTransport {
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = io::Result<()>> + Send + 'a>> {
         Box::pin(async move {
             self.inner = TcpStream::connect((self.addr, self.port)).await?;
+            enable_keepalive(&self.inner);
             Ok(())
         })
     }
@@ -144,6 +172,20 @@ mod tests {
         ));
     }
 
+    #[test(tokio::test)]
+    async fn connect_should_enable_keepalive() {
+        let (tx, rx) = oneshot::channel();
+        let task: JoinHandle<io::Result<()>> = tokio::spawn(start_and_run_server(tx));
+        let addr = rx.await.expect("Failed to get server server address");
+
+        let conn = TcpTransport::connect(&addr)
+            .await
+            .expect("Conn failed to connect");
+        assert!(SockRef::from(&conn.inner).keepalive().unwrap());
+
+        task.abort();
+    }
+
     #[test(tokio::test)]
     async fn should_be_able_to_read_and_write_data() {
         let (tx, rx) = oneshot::channel();
@@ -0,0 +1,656 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::{fmt, fs, io};
+
+use log::*;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+use super::{InmemoryTransport, Interest, Ready, Reconnectable, Transport, enable_keepalive};
+
+/// Capacity of the channels that move data between the TLS stream and the transport.
+const CHANNEL_CAPACITY: usize = 100;
+
+/// Maximum size of a single read from the TLS stream.
+const MAX_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Configuration for the client side of a TLS connection, describing which certificate
+/// authorities to trust and, for mutual TLS, the certificate presented to the server.
+#[derive(Clone)]
+pub struct TlsClientConfig {
+    inner: Arc<ClientConfig>,
+}
+
+impl TlsClientConfig {
+    /// Creates a configuration from PEM-encoded data.
+    ///
+    /// * `ca` - certificate authorities used to verify the server, falling back to the webpki
+    ///   roots if not provided.
+    /// * `identity` - certificate chain and private key presented to the server for mutual TLS.
+    pub fn from_pem(ca: Option<&[u8]>, identity: Option<(&[u8], &[u8])>) -> io::Result<Self> {
+        let roots = match ca {
+            Some(ca) => root_cert_store(ca)?,
+            None => RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            },
+        };
+
+        let builder = ClientConfig::builder_with_provider(crypto_provider())
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?
+            .with_root_certificates(roots);
+
+        let config = match identity {
+            Some((cert_chain, key)) => builder
+                .with_client_auth_cert(certificates(cert_chain)?, private_key(key)?)
+                .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?,
+            None => builder.with_no_client_auth(),
+        };
+
+        Ok(Self {
+            inner: Arc::new(config),
+        })
+    }
+
+    /// Creates a configuration by reading PEM files from disk. See [`TlsClientConfig::from_pem`].
+    pub fn from_pem_files(ca: Option<&Path>, identity: Option<(&Path, &Path)>) -> io::Result<Self> {
+        let ca = ca.map(read_pem_file).transpose()?;
+        let identity = match identity {
+            Some((cert_chain, key)) => Some((read_pem_file(cert_chain)?, read_pem_file(key)?)),
+            None => None,
+        };
+
+        Self::from_pem(
+            ca.as_deref(),
+            identity
+                .as_ref()
+                .map(|(cert_chain, key)| (cert_chain.as_slice(), key.as_slice())),
+        )
+    }
+}
+
+impl fmt::Debug for TlsClientConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsClientConfig")
+            .field(
+                "client_auth",
+                &self.inner.client_auth_cert_resolver.has_certs(),
+            )
+            .finish()
+    }
+}
+
+/// Configuration for the server side of a TLS connection, describing the certificate presented
+/// to clients and, for mutual TLS, the certificate authorities used to verify clients.
+#[derive(Clone)]
+pub struct TlsServerConfig {
+    inner: Arc<ServerConfig>,
+    requires_client_certificate: bool,
+}
+
+impl TlsServerConfig {
+    /// Creates a configuration from PEM-encoded data.
+    ///
+    /// * `cert_chain` - certificate chain presented to clients, starting with the server's own.
+    /// * `key` - private key of the server's certificate.
+    /// * `client_ca` - certificate authorities that issue client certificates. If provided, every
+    ///   client must present a certificate signed by one of them.
+    pub fn from_pem(cert_chain: &[u8], key: &[u8], client_ca: Option<&[u8]>) -> io::Result<Self> {
+        let provider = crypto_provider();
+        let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?;
+
+        let builder = match client_ca {
+            Some(client_ca) => {
+                let verifier = WebPkiClientVerifier::builder_with_provider(
+                    Arc::new(root_cert_store(client_ca)?),
+                    provider,
+                )
+                .build()
+                .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let config = builder
+            .with_single_cert(certificates(cert_chain)?, private_key(key)?)
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+
+        Ok(Self {
+            inner: Arc::new(config),
+            requires_client_certificate: client_ca.is_some(),
+        })
+    }
+
+    /// Creates a configuration by reading PEM files from disk. See [`TlsServerConfig::from_pem`].
+    pub fn from_pem_files(
+        cert_chain: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+        client_ca: Option<&Path>,
+    ) -> io::Result<Self> {
+        let client_ca = client_ca.map(read_pem_file).transpose()?;
+        Self::from_pem(
+            &read_pem_file(cert_chain.as_ref())?,
+            &read_pem_file(key.as_ref())?,
+            client_ca.as_deref(),
+        )
+    }
+
+    /// Returns true if clients must present a certificate (mutual TLS).
+    pub fn requires_client_certificate(&self) -> bool {
+        self.requires_client_certificate
+    }
+}
+
+impl fmt::Debug for TlsServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsServerConfig")
+            .field(
+                "requires_client_certificate",
+                &self.requires_client_certificate,
+            )
+            .finish()
+    }
+}
+
+/// Represents a [`Transport`] that encrypts bytes with TLS over a TCP stream, verifying the
+/// other side using X.509 certificates.
+///
+/// The stream itself is driven by a background task that bridges its data to an inner
+/// [`InmemoryTransport`]. Once the transport is dropped, the task sends any queued data and shuts
+/// down the TLS session.
+pub struct TlsTransport {
+    addr: SocketAddr,
+    server_name: Option<String>,
+    config: Option<TlsClientConfig>,
+    peer_identity: Option<String>,
+    inner: InmemoryTransport,
+}
+
+impl TlsTransport {
+    /// Creates a new transport by connecting to `host` at `port`, verifying that the certificate
+    /// presented by the server is valid for `host`.
+    pub async fn connect(
+        host: impl Into<String>,
+        port: u16,
+        config: TlsClientConfig,
+    ) -> io::Result<Self> {
+        let host = host.into();
+        let server_name = ServerName::try_from(host.clone())
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+
+        let stream = TcpStream::connect((host.as_str(), port)).await?;
+        enable_keepalive(&stream);
+        let addr = stream.peer_addr()?;
+
+        let stream = TlsConnector::from(Arc::clone(&config.inner))
+            .connect(server_name, stream)
+            .await?;
+        let peer_identity = stream
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(certificate_identity);
+
+        let mut this = Self::from_stream(addr, peer_identity, stream);
+        this.server_name = Some(host);
+        this.config = Some(config);
+        Ok(this)
+    }
+
+    /// Creates a new transport by performing the server side of the TLS handshake over an
+    /// already-accepted `stream`.
+    ///
+    /// Transports created this way cannot be reconnected.
+    pub async fn accept(stream: TcpStream, config: &TlsServerConfig) -> io::Result<Self> {
+        let addr = stream.peer_addr()?;
+        let stream = TlsAcceptor::from(Arc::clone(&config.inner))
+            .accept(stream)
+            .await?;
+        let peer_identity = stream
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(certificate_identity);
+
+        Ok(Self::from_stream(addr, peer_identity, stream))
+    }
+
+    /// Returns the address of the other side of the connection.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    fn from_stream<S>(addr: SocketAddr, peer_identity: Option<String>, stream: S) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (incoming_tx, outgoing_rx, inner) = InmemoryTransport::make(CHANNEL_CAPACITY);
+        tokio::spawn(bridge_task(stream, incoming_tx, outgoing_rx));
+
+        Self {
+            addr,
+            server_name: None,
+            config: None,
+            peer_identity,
+            inner,
+        }
+    }
+}
+
+impl fmt::Debug for TlsTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsTransport")
+            .field("addr", &self.addr)
+            .field("server_name", &self.server_name)
+            .field("peer_identity", &self.peer_identity)
+            .finish()
+    }
+}
+
+impl Reconnectable for TlsTransport {
+    /// Establishes a new TLS session with the same server, failing with
+    /// [`ErrorKind::Unsupported`] if the transport was accepted by a server.
+    ///
+    /// [`ErrorKind::Unsupported`]: io::ErrorKind::Unsupported
+    fn reconnect<'a>(
+        &'a mut self,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = io::Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            match (self.server_name.clone(), self.config.clone()) {
+                (Some(server_name), Some(config)) => {
+                    *self = Self::connect(server_name, self.addr.port(), config).await?;
+                    Ok(())
+                }
+                _ => Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Server-side TLS connections cannot reconnect",
+                )),
+            }
+        })
+    }
+}
+
+impl Transport for TlsTransport {
+    fn try_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.try_read(buf)
+    }
+
+    fn try_write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.try_write(buf)
+    }
+
+    fn ready<'a>(
+        &'a self,
+        interest: Interest,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = io::Result<Ready>> + Send + 'a>> {
+        self.inner.ready(interest)
+    }
+
+    /// Returns the identity from the certificate presented by the other side, which for a server
+    /// is only available when clients are required to present a certificate.
+    fn peer_identity(&self) -> Option<&str> {
+        self.peer_identity.as_deref()
+    }
+}
+
+/// Moves data between a TLS `stream` and the channels of an [`InmemoryTransport`].
+async fn bridge_task<S>(
+    stream: S,
+    incoming_tx: tokio::sync::mpsc::Sender<Vec<u8>>,
+    mut outgoing_rx: tokio::sync::mpsc::Receiver<Vec<u8>>,
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let mut buf = vec![0u8; MAX_CHUNK_SIZE];
+
+    loop {
+        tokio::select! {
+            result = reader.read(&mut buf) => {
+                let n = match result {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(x) => {
+                        error!("TLS stream failed to receive data: {x}");
+                        break;
+                    }
+                };
+
+                if incoming_tx.send(buf[..n].to_vec()).await.is_err() {
+                    break;
+                }
+            }
+            data = outgoing_rx.recv() => {
+                let Some(data) = data else {
+                    let _ = writer.shutdown().await;
+                    break;
+                };
+
+                if let Err(x) = writer.write_all(&data).await {
+                    error!("TLS stream failed to send data: {x}");
+                    break;
+                }
+            }
+        }
+    }
+
+    trace!("TLS bridge task closed");
+}
+
+/// Returns the identity named by a certificate, being its subject's common name or, failing that,
+/// the first DNS name or email address listed as a subject alternative name.
+fn certificate_identity(cert: &CertificateDer<'_>) -> Option<String> {
+    let (_, cert) = X509Certificate::from_der(cert.as_ref()).ok()?;
+
+    if let Some(cn) = cert
+        .subject()
+        .iter_common_name()
+        .find_map(|cn| cn.as_str().ok())
+    {
+        return Some(cn.to_string());
+    }
+
+    cert.subject_alternative_name()
+        .ok()
+        .flatten()?
+        .value
+        .general_names
+        .iter()
+        .find_map(|name| match name {
+            GeneralName::DNSName(name) | GeneralName::RFC822Name(name) => Some(name.to_string()),
+            _ => None,
+        })
+}
+
+fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn read_pem_file(path: &Path) -> io::Result<Vec<u8>> {
+    fs::read(path).map_err(|x| io::Error::new(x.kind(), format!("{}: {x}", path.display())))
+}
+
+fn certificates(pem: &[u8]) -> io::Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_slice_iter(pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?;
+
+    if certs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "No certificates found in PEM data",
+        ));
+    }
+
+    Ok(certs)
+}
+
+fn private_key(pem: &[u8]) -> io::Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_slice(pem).map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))
+}
+
+fn root_cert_store(pem: &[u8]) -> io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in certificates(pem)? {
+        roots
+            .add(cert)
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?;
+    }
+    Ok(roots)
+}
+
+#[cfg(test)]
+pub(crate) mod test_certs {
+    //! Certificates issued by a throwaway certificate authority for use in tests.
+
+    use rcgen::{
+        BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    };
+
+    /// PEM-encoded certificate authority along with a certificate and key it has issued.
+    pub struct TestCerts {
+        pub ca: String,
+        pub cert: String,
+        pub key: String,
+    }
+
+    /// Creates a certificate authority and uses it to issue a server certificate for `localhost`
+    /// and `127.0.0.1` and a client certificate for `client_name`, returning `(server, client)`.
+    pub fn generate(client_name: &str) -> (TestCerts, TestCerts) {
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "distant test ca");
+        let ca_key = KeyPair::generate().unwrap();
+        let ca_cert = params.self_signed(&ca_key).unwrap();
+
+        let issue = |names: Vec<String>, common_name: &str, usage| {
+            let mut params = CertificateParams::new(names).unwrap();
+            params
+                .distinguished_name
+                .push(DnType::CommonName, common_name);
+            params.extended_key_usages = vec![usage];
+            let key = KeyPair::generate().unwrap();
+            let cert = params.signed_by(&key, &ca_cert, &ca_key).unwrap();
+            TestCerts {
+                ca: ca_cert.pem(),
+                cert: cert.pem(),
+                key: key.serialize_pem(),
+            }
+        };
+
+        let server = issue(
+            vec!["localhost".to_string(), "127.0.0.1".to_string()],
+            "localhost",
+            ExtendedKeyUsagePurpose::ServerAuth,
+        );
+        let client = issue(Vec::new(), client_name, ExtendedKeyUsagePurpose::ClientAuth);
+        (server, client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use test_log::test;
+    use tokio::net::TcpListener;
+
+    use super::test_certs::{TestCerts, generate};
+    use super::*;
+    use crate::net::common::TransportExt;
+
+    fn server_config(server: &TestCerts, client_ca: Option<&str>) -> TlsServerConfig {
+        TlsServerConfig::from_pem(
+            server.cert.as_bytes(),
+            server.key.as_bytes(),
+            client_ca.map(str::as_bytes),
+        )
+        .unwrap()
+    }
+
+    /// Starts a TLS server on an ephemeral loopback port that echoes back what it receives,
+    /// sending each accepted transport's peer identity through the returned channel.
+    async fn start_echo_server(
+        config: TlsServerConfig,
+    ) -> (u16, tokio::sync::mpsc::UnboundedReceiver<Option<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind on an ephemeral port");
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let config = config.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let Ok(transport) = TlsTransport::accept(stream, &config).await else {
+                        return;
+                    };
+                    let _ = tx.send(transport.peer_identity().map(ToString::to_string));
+
+                    let mut buf = [0u8; 1024];
+                    loop {
+                        transport.readable().await.unwrap();
+                        match transport.try_read(&mut buf) {
+                            Ok(0) => break,
+                            Ok(n) => transport.write_all(&buf[..n]).await.unwrap(),
+                            Err(x) if x.kind() == io::ErrorKind::WouldBlock => {
+                                tokio::time::sleep(Duration::from_millis(1)).await
+                            }
+                            Err(x) => panic!("Failed to read: {x}"),
+                        }
+                    }
+                });
+            }
+        });
+
+        (port, rx)
+    }
+
+    #[test]
+    fn config_should_fail_if_pem_has_no_certificates() {
+        let (server, _) = generate("client");
+        TlsServerConfig::from_pem(b"", server.key.as_bytes(), None)
+            .expect_err("Unexpectedly created config without a certificate");
+        TlsClientConfig::from_pem(Some(b"not a certificate"), None)
+            .expect_err("Unexpectedly created config without a certificate");
+    }
+
+    #[test]
+    fn certificate_identity_should_prefer_common_name() {
+        let (server, client) = generate("alice");
+        let identity = |pem: &str| {
+            certificate_identity(&CertificateDer::from_pem_slice(pem.as_bytes()).unwrap())
+        };
+
+        assert_eq!(identity(&client.cert).as_deref(), Some("alice"));
+        assert_eq!(identity(&server.cert).as_deref(), Some("localhost"));
+    }
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_read_and_write_data() {
+        let (server, _) = generate("client");
+        let (port, mut identities) = start_echo_server(server_config(&server, None)).await;
+
+        let config = TlsClientConfig::from_pem(Some(server.ca.as_bytes()), None).unwrap();
+        let transport = TlsTransport::connect("localhost", port, config)
+            .await
+            .unwrap();
+        assert_eq!(transport.peer_identity(), Some("localhost"));
+
+        transport.write_all(b"hello server").await.unwrap();
+        let mut buf = [0u8; 12];
+        transport.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello server");
+
+        // Without mutual TLS, the server has no identity for the client
+        assert_eq!(identities.recv().await.unwrap(), None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_fail_to_connect_if_server_certificate_is_not_trusted() {
+        let (server, _) = generate("client");
+        let (other, _) = generate("client");
+        let (port, _) = start_echo_server(server_config(&server, None)).await;
+
+        let config = TlsClientConfig::from_pem(Some(other.ca.as_bytes()), None).unwrap();
+        TlsTransport::connect("localhost", port, config)
+            .await
+            .expect_err("Unexpectedly trusted certificate from another authority");
+    }
+
+    #[test(tokio::test)]
+    async fn should_provide_client_identity_to_server_with_mutual_tls() {
+        let (server, client) = generate("alice");
+        let (port, mut identities) =
+            start_echo_server(server_config(&server, Some(&client.ca))).await;
+
+        let config = TlsClientConfig::from_pem(
+            Some(server.ca.as_bytes()),
+            Some((client.cert.as_bytes(), client.key.as_bytes())),
+        )
+        .unwrap();
+        let transport = TlsTransport::connect("127.0.0.1", port, config)
+            .await
+            .unwrap();
+
+        transport.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        transport.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+
+        assert_eq!(identities.recv().await.unwrap().as_deref(), Some("alice"));
+    }
+
+    #[test(tokio::test)]
+    async fn should_reject_client_without_certificate_when_mutual_tls_required() {
+        let (server, client) = generate("alice");
+        let (port, _) = start_echo_server(server_config(&server, Some(&client.ca))).await;
+
+        // With TLS 1.3, the server rejects the client after the client finishes its handshake,
+        // so the failure shows up once data is exchanged
+        let config = TlsClientConfig::from_pem(Some(server.ca.as_bytes()), None).unwrap();
+        let result = async {
+            let transport = TlsTransport::connect("localhost", port, config).await?;
+            transport.write_all(b"hello").await?;
+            let mut buf = [0u8; 5];
+            transport.read_exact(&mut buf).await
+        };
+
+        tokio::time::timeout(Duration::from_secs(5), result)
+            .await
+            .expect("Timed out waiting for rejection")
+            .expect_err("Unexpectedly exchanged data without a client certificate");
+    }
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_reconnect() {
+        let (server, _) = generate("client");
+        let (port, _) = start_echo_server(server_config(&server, None)).await;
+
+        let config = TlsClientConfig::from_pem(Some(server.ca.as_bytes()), None).unwrap();
+        let mut transport = TlsTransport::connect("localhost", port, config)
+            .await
+            .unwrap();
+        transport.reconnect().await.unwrap();
+
+        transport.write_all(b"hello again").await.unwrap();
+        let mut buf = [0u8; 11];
+        transport.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello again");
+    }
+
+    #[test(tokio::test)]
+    async fn reconnect_should_fail_for_accepted_transport() {
+        let (server, _) = generate("client");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let config = TlsClientConfig::from_pem(Some(server.ca.as_bytes()), None).unwrap();
+        let client = tokio::spawn(TlsTransport::connect("localhost", port, config));
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut transport = TlsTransport::accept(stream, &server_config(&server, None))
+            .await
+            .unwrap();
+        let _client = client.await.unwrap().unwrap();
+
+        let err = transport.reconnect().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+}
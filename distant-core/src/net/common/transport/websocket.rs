@@ -0,0 +1,267 @@
+use std::{fmt, io};
+
+use futures::{SinkExt, StreamExt};
+use log::*;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use super::{InmemoryTransport, Interest, Ready, Reconnectable, Transport, enable_keepalive};
+
+/// Capacity of the channels that move messages between the websocket and the transport.
+const CHANNEL_CAPACITY: usize = 100;
+
+/// Represents a [`Transport`] that sends bytes as binary messages over a WebSocket, which allows
+/// a server to sit behind HTTP-only reverse proxies such as nginx.
+///
+/// The websocket itself is driven by a background task that bridges its messages to an inner
+/// [`InmemoryTransport`]. Once the transport is dropped, the task sends any queued data and closes
+/// the websocket.
+pub struct WebSocketTransport {
+    url: Option<String>,
+    inner: InmemoryTransport,
+}
+
+impl WebSocketTransport {
+    /// Creates a new transport by connecting to a WebSocket server at `url`, which is either a
+    /// `ws://` or `wss://` url (e.g. `wss://example.com/distant`).
+    pub async fn connect(url: impl Into<String>) -> io::Result<Self> {
+        let url = url.into();
+        let (stream, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .map_err(into_io_error)?;
+
+        match stream.get_ref() {
+            MaybeTlsStream::Plain(stream) => enable_keepalive(stream),
+            MaybeTlsStream::Rustls(stream) => enable_keepalive(stream.get_ref().0),
+            _ => {}
+        }
+
+        let mut this = Self::from_stream(stream);
+        this.url = Some(url);
+        Ok(this)
+    }
+
+    /// Creates a new transport by performing the server side of the WebSocket handshake over an
+    /// already-accepted `stream`.
+    ///
+    /// Transports created this way cannot be reconnected.
+    pub async fn accept<S>(stream: S) -> io::Result<Self>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let stream = tokio_tungstenite::accept_async(stream)
+            .await
+            .map_err(into_io_error)?;
+        Ok(Self::from_stream(stream))
+    }
+
+    /// Returns the url that the transport is connected to, or `None` if the transport was
+    /// accepted by a server.
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    fn from_stream<S>(stream: WebSocketStream<S>) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (incoming_tx, outgoing_rx, inner) = InmemoryTransport::make(CHANNEL_CAPACITY);
+        tokio::spawn(bridge_task(stream, incoming_tx, outgoing_rx));
+
+        Self { url: None, inner }
+    }
+}
+
+impl fmt::Debug for WebSocketTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketTransport")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+impl Reconnectable for WebSocketTransport {
+    /// Establishes a new websocket to the same url, failing with
+    /// [`ErrorKind::Unsupported`] if the transport was accepted by a server.
+    ///
+    /// [`ErrorKind::Unsupported`]: io::ErrorKind::Unsupported
+    fn reconnect<'a>(
+        &'a mut self,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = io::Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            match self.url.clone() {
+                Some(url) => {
+                    *self = Self::connect(url).await?;
+                    Ok(())
+                }
+                None => Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Server-side websockets cannot reconnect",
+                )),
+            }
+        })
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn try_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.try_read(buf)
+    }
+
+    fn try_write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.try_write(buf)
+    }
+
+    fn ready<'a>(
+        &'a self,
+        interest: Interest,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = io::Result<Ready>> + Send + 'a>> {
+        self.inner.ready(interest)
+    }
+}
+
+/// Moves data between `stream` and the channels of an [`InmemoryTransport`], where each write to
+/// the transport becomes a binary message and each message received becomes readable bytes.
+async fn bridge_task<S>(
+    mut stream: WebSocketStream<S>,
+    incoming_tx: tokio::sync::mpsc::Sender<Vec<u8>>,
+    mut outgoing_rx: tokio::sync::mpsc::Receiver<Vec<u8>>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    loop {
+        tokio::select! {
+            msg = stream.next() => {
+                let data = match msg {
+                    Some(Ok(Message::Binary(data))) => data.to_vec(),
+                    Some(Ok(Message::Text(text))) => text.as_bytes().to_vec(),
+                    Some(Ok(Message::Close(_))) | None => break,
+
+                    // Pings are answered by the websocket itself
+                    Some(Ok(_)) => continue,
+                    Some(Err(x)) => {
+                        error!("WebSocket failed to receive message: {x}");
+                        break;
+                    }
+                };
+
+                if incoming_tx.send(data).await.is_err() {
+                    break;
+                }
+            }
+            data = outgoing_rx.recv() => {
+                let Some(data) = data else {
+                    let _ = stream.close(None).await;
+                    break;
+                };
+
+                if let Err(x) = stream.send(Message::Binary(data.into())).await {
+                    error!("WebSocket failed to send message: {x}");
+                    break;
+                }
+            }
+        }
+    }
+
+    trace!("WebSocket bridge task closed");
+}
+
+/// Converts a websocket error into an [`io::Error`], preserving the kind of io errors.
+fn into_io_error(x: WsError) -> io::Error {
+    match x {
+        WsError::Io(x) => x,
+        x => io::Error::other(x),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::net::common::TransportExt;
+
+    /// Starts a websocket server on an ephemeral port that echoes back what it receives,
+    /// returning the url to reach it.
+    async fn start_echo_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind on an ephemeral port");
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let transport = WebSocketTransport::accept(stream).await.unwrap();
+                    let mut buf = [0u8; 1024];
+                    loop {
+                        transport.readable().await.unwrap();
+                        match transport.try_read(&mut buf) {
+                            Ok(0) => break,
+                            Ok(n) => transport.write_all(&buf[..n]).await.unwrap(),
+                            Err(x) if x.kind() == io::ErrorKind::WouldBlock => {
+                                tokio::time::sleep(std::time::Duration::from_millis(1)).await
+                            }
+                            Err(x) => panic!("Failed to read: {x}"),
+                        }
+                    }
+                });
+            }
+        });
+
+        format!("ws://{addr}")
+    }
+
+    #[test(tokio::test)]
+    async fn should_fail_to_connect_if_nothing_listening() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        WebSocketTransport::connect(format!("ws://{addr}"))
+            .await
+            .expect_err("Unexpectedly connected to ghost address");
+    }
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_read_and_write_data() {
+        let url = start_echo_server().await;
+        let transport = WebSocketTransport::connect(url.as_str()).await.unwrap();
+        assert_eq!(transport.url(), Some(url.as_str()));
+
+        transport.write_all(b"hello server").await.unwrap();
+
+        let mut buf = [0u8; 12];
+        transport.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello server");
+    }
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_reconnect() {
+        let url = start_echo_server().await;
+        let mut transport = WebSocketTransport::connect(url.as_str()).await.unwrap();
+
+        transport.reconnect().await.unwrap();
+
+        transport.write_all(b"hello again").await.unwrap();
+        let mut buf = [0u8; 11];
+        transport.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello again");
+    }
+
+    #[test(tokio::test)]
+    async fn reconnect_should_fail_for_accepted_transport() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = tokio::spawn(WebSocketTransport::connect(format!("ws://{addr}")));
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut transport = WebSocketTransport::accept(stream).await.unwrap();
+        let _client = client.await.unwrap().unwrap();
+
+        let err = transport.reconnect().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+}
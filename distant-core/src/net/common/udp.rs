@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use log::*;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::{self, Instant};
+
+use crate::constants::{
+    TUNNEL_CHANNEL_CAPACITY, UDP_TUNNEL_DATAGRAM_BUFFER_SIZE, UDP_TUNNEL_IDLE_TIMEOUT,
+};
+
+/// Returns a UDP socket bound to an ephemeral port and connected to `addr`, so that only
+/// datagrams from that address are received.
+pub async fn connect_udp(addr: SocketAddr) -> io::Result<UdpSocket> {
+    let bind_addr: SocketAddr = if addr.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+
+    let socket = UdpSocket::bind(bind_addr).await?;
+    socket.connect(addr).await?;
+    Ok(socket)
+}
+
+/// Splits the datagrams received on a UDP socket shared by many peers into one association per
+/// peer, which is how UDP tunnels keep the replies for each peer apart.
+///
+/// The first datagram from a peer starts a new [`UdpPeer`] association, and later datagrams from
+/// the same peer are routed to it until it closes from being idle, after which the next datagram
+/// from the peer starts a new association.
+pub struct UdpDemux {
+    socket: Arc<UdpSocket>,
+    peers: HashMap<SocketAddr, mpsc::Sender<Vec<u8>>>,
+    buf: Vec<u8>,
+}
+
+impl UdpDemux {
+    /// Creates a new demultiplexer over the datagrams received on `socket`
+    pub fn new(socket: UdpSocket) -> Self {
+        Self {
+            socket: Arc::new(socket),
+            peers: HashMap::new(),
+            buf: vec![0u8; UDP_TUNNEL_DATAGRAM_BUFFER_SIZE],
+        }
+    }
+
+    /// Returns a reference to the socket shared by all peers
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Waits for a datagram from a peer without an association, routing the datagrams received
+    /// in the meantime to the associations of their peers, and returns the new association with
+    /// that first datagram queued.
+    ///
+    /// Like a congested network, datagrams are dropped for a peer whose association is backed up.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if receiving on the socket fails, other than from an earlier datagram
+    /// being rejected by its peer (ICMP port unreachable).
+    pub async fn accept(&mut self) -> io::Result<UdpPeer> {
+        loop {
+            let (n, addr) = match self.socket.recv_from(&mut self.buf).await {
+                Ok(x) => x,
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => continue,
+                Err(e) => return Err(e),
+            };
+
+            let mut data = self.buf[..n].to_vec();
+            if let Some(inbound_tx) = self.peers.get(&addr) {
+                match inbound_tx.try_send(data) {
+                    Ok(()) | Err(TrySendError::Full(_)) => continue,
+                    Err(TrySendError::Closed(x)) => {
+                        self.peers.remove(&addr);
+                        data = x;
+                    }
+                }
+            }
+
+            // Forget about peers whose associations have since gone idle
+            self.peers.retain(|_, inbound_tx| !inbound_tx.is_closed());
+
+            let (inbound_tx, inbound_rx) = mpsc::channel(TUNNEL_CHANNEL_CAPACITY);
+            let _ = inbound_tx.try_send(data);
+            self.peers.insert(addr, inbound_tx);
+
+            return Ok(UdpPeer {
+                addr,
+                socket: Arc::clone(&self.socket),
+                inbound_rx,
+            });
+        }
+    }
+}
+
+/// Represents the association of a single peer with a [`UdpDemux`], receiving the datagrams
+/// sent by the peer and sending replies to it from the shared socket.
+pub struct UdpPeer {
+    addr: SocketAddr,
+    socket: Arc<UdpSocket>,
+    inbound_rx: mpsc::Receiver<Vec<u8>>,
+}
+
+impl UdpPeer {
+    /// Returns the address of the peer
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Relays datagrams between the peer and `target` until either side stops or the association
+    /// has been idle for [`UDP_TUNNEL_IDLE_TIMEOUT`].
+    ///
+    /// Datagrams from the peer are passed to [`UdpTarget::forward`], and datagrams produced by
+    /// [`UdpTarget::reply`] are sent back to the peer. Failing to send a reply drops it without
+    /// ending the association.
+    pub async fn relay(mut self, mut target: impl UdpTarget) {
+        let idle = time::sleep(UDP_TUNNEL_IDLE_TIMEOUT);
+        tokio::pin!(idle);
+
+        loop {
+            tokio::select! {
+                data = self.inbound_rx.recv() => match data {
+                    Some(data) => {
+                        if let Err(e) = target.forward(data).await {
+                            debug!("UDP relay for {} finished: {e}", self.addr);
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+                data = target.reply() => match data {
+                    Some(data) => match self.socket.send_to(&data, self.addr).await {
+                        Ok(n) => target.replied(n),
+                        Err(e) => debug!("Send to {} error: {e}", self.addr),
+                    },
+                    None => break,
+                },
+                _ = &mut idle => {
+                    debug!("Closing idle UDP association with {}", self.addr);
+                    break;
+                }
+            }
+
+            idle.as_mut()
+                .reset(Instant::now() + UDP_TUNNEL_IDLE_TIMEOUT);
+        }
+    }
+}
+
+/// Represents where the datagrams of a [`UdpPeer`] are relayed to, and where its replies come
+/// from.
+pub trait UdpTarget: Send {
+    /// Passes along a datagram received from the peer, failing if the target has stopped
+    fn forward(&mut self, data: Vec<u8>) -> impl Future<Output = io::Result<()>> + Send;
+
+    /// Waits for the next datagram to send back to the peer, returning `None` once the target
+    /// has stopped
+    fn reply(&mut self) -> impl Future<Output = Option<Vec<u8>>> + Send;
+
+    /// Invoked after a reply of `n` bytes was sent to the peer
+    fn replied(&mut self, _n: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    /// Target that passes datagrams from the peer to `forward_tx` and replies with the
+    /// datagrams received on `reply_rx`
+    struct ChannelTarget {
+        forward_tx: mpsc::UnboundedSender<Vec<u8>>,
+        reply_rx: mpsc::UnboundedReceiver<Vec<u8>>,
+    }
+
+    impl UdpTarget for ChannelTarget {
+        async fn forward(&mut self, data: Vec<u8>) -> io::Result<()> {
+            self.forward_tx
+                .send(data)
+                .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+        }
+
+        async fn reply(&mut self) -> Option<Vec<u8>> {
+            self.reply_rx.recv().await
+        }
+    }
+
+    async fn bind() -> UdpSocket {
+        UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap()
+    }
+
+    #[test(tokio::test)]
+    async fn accept_should_return_new_association_for_each_peer() {
+        let mut demux = UdpDemux::new(bind().await);
+        let addr = demux.socket().local_addr().unwrap();
+
+        let peer1 = connect_udp(addr).await.unwrap();
+        let peer2 = connect_udp(addr).await.unwrap();
+
+        peer1.send(b"a1").await.unwrap();
+        let mut assoc1 = demux.accept().await.unwrap();
+        assert_eq!(assoc1.addr(), peer1.local_addr().unwrap());
+
+        // Datagrams from a known peer are routed to its association while waiting for another
+        peer1.send(b"a2").await.unwrap();
+        peer2.send(b"b1").await.unwrap();
+        let mut assoc2 = demux.accept().await.unwrap();
+        assert_eq!(assoc2.addr(), peer2.local_addr().unwrap());
+
+        assert_eq!(assoc1.inbound_rx.recv().await.unwrap(), b"a1");
+        assert_eq!(assoc1.inbound_rx.recv().await.unwrap(), b"a2");
+        assert_eq!(assoc2.inbound_rx.recv().await.unwrap(), b"b1");
+    }
+
+    #[test(tokio::test)]
+    async fn accept_should_start_new_association_once_previous_one_closes() {
+        let mut demux = UdpDemux::new(bind().await);
+        let addr = demux.socket().local_addr().unwrap();
+        let peer = connect_udp(addr).await.unwrap();
+
+        peer.send(b"first").await.unwrap();
+        drop(demux.accept().await.unwrap());
+
+        peer.send(b"second").await.unwrap();
+        let mut assoc = demux.accept().await.unwrap();
+        assert_eq!(assoc.inbound_rx.recv().await.unwrap(), b"second");
+    }
+
+    #[test(tokio::test)]
+    async fn relay_should_pass_datagrams_between_peer_and_target() {
+        let mut demux = UdpDemux::new(bind().await);
+        let addr = demux.socket().local_addr().unwrap();
+        let peer = connect_udp(addr).await.unwrap();
+
+        peer.send(b"ping").await.unwrap();
+        let assoc = demux.accept().await.unwrap();
+
+        let (forward_tx, mut forward_rx) = mpsc::unbounded_channel();
+        let (reply_tx, reply_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(assoc.relay(ChannelTarget {
+            forward_tx,
+            reply_rx,
+        }));
+
+        assert_eq!(forward_rx.recv().await.unwrap(), b"ping");
+
+        reply_tx.send(b"pong".to_vec()).unwrap();
+        let mut buf = [0u8; 16];
+        let n = peer.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"pong");
+
+        // The relay stops once the target does
+        drop(reply_tx);
+        task.await.unwrap();
+    }
+}
@@ -4,12 +4,13 @@ use crate::auth::AuthHandler;
 use crate::auth::msg::{Authentication, AuthenticationResponse};
 use log::*;
 
-use crate::net::client::Client;
-use crate::net::common::{ConnectionId, Destination, Map, Request};
+use crate::net::client::{Client, Mailbox};
+use crate::net::common::{ConnectionId, Destination, Map, Request, Response};
 use crate::net::manager::data::{
-    ConnectionInfo, ConnectionList, ManagedTunnelId, ManagedTunnelInfo, ManagerRequest,
+    ConnectionInfo, ConnectionList, HttpRoute, ManagedTunnelId, ManagedTunnelInfo, ManagerRequest,
     ManagerResponse, SemVer,
 };
+use crate::protocol::TunnelProtocol;
 
 // NOTE: Destination is still used for the Launched response, but launch/connect accept raw strings.
 
@@ -19,6 +20,84 @@ pub use channel::*;
 /// Represents a client that can connect to a remote server manager.
 pub type ManagerClient = Client<ManagerRequest, ManagerResponse>;
 
+/// Notification about a port that the manager forwarded automatically.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PortEvent {
+    /// A remote process started listening on `remote_port`, which is now forwarded to
+    /// `local_port` through managed tunnel `id`
+    Forwarded {
+        connection_id: ConnectionId,
+        id: ManagedTunnelId,
+        remote_host: String,
+        remote_port: u16,
+        local_port: u16,
+    },
+
+    /// Nothing is listening on `remote_port` anymore, so managed tunnel `id` was closed
+    Closed {
+        connection_id: ConnectionId,
+        id: ManagedTunnelId,
+        remote_port: u16,
+    },
+}
+
+impl PortEvent {
+    /// Returns the id of the connection whose remote port the event is about.
+    pub fn connection_id(&self) -> ConnectionId {
+        match self {
+            Self::Forwarded { connection_id, .. } | Self::Closed { connection_id, .. } => {
+                *connection_id
+            }
+        }
+    }
+}
+
+/// Receives notifications about ports that the manager forwards automatically.
+pub struct PortWatcher {
+    mailbox: Mailbox<Response<ManagerResponse>>,
+}
+
+impl PortWatcher {
+    /// Waits for the next notification, returning `None` once the manager stops sending them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manager rejects the watch, such as when automatic port forwarding
+    /// is not enabled.
+    pub async fn next(&mut self) -> Option<io::Result<PortEvent>> {
+        let res = self.mailbox.next().await?;
+        Some(match res.payload {
+            ManagerResponse::PortForwarded {
+                connection_id,
+                id,
+                remote_host,
+                remote_port,
+                local_port,
+            } => Ok(PortEvent::Forwarded {
+                connection_id,
+                id,
+                remote_host,
+                remote_port,
+                local_port,
+            }),
+            ManagerResponse::PortForwardClosed {
+                connection_id,
+                id,
+                remote_port,
+            } => Ok(PortEvent::Closed {
+                connection_id,
+                id,
+                remote_port,
+            }),
+            ManagerResponse::Error { description } => Err(io::Error::other(description)),
+            x => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Got unexpected response: {x:?}"),
+            )),
+        })
+    }
+}
+
 impl ManagerClient {
     /// Request that the manager launches a new server at the given `destination` with `options`
     /// being passed for destination-specific details, returning the new `destination` of the
@@ -312,12 +391,224 @@ impl ManagerClient {
     ) -> io::Result<(ManagedTunnelId, u16)> {
         let remote_host = remote_host.into();
         trace!("forward_tunnel({connection_id}, {bind_port}, {remote_host}, {remote_port})");
+        self.start_tunnel(ManagerRequest::ForwardTunnel {
+            connection_id,
+            bind_port,
+            remote_host,
+            remote_port,
+            protocol: TunnelProtocol::Tcp,
+            bind_path: None,
+            remote_path: None,
+        })
+        .await
+    }
+
+    /// Requests the manager to start a forward UDP tunnel (local UDP port → remote target).
+    ///
+    /// Each local peer sending datagrams to the bound port gets its own association with the
+    /// remote target, which is closed once it has been idle for a while.
+    ///
+    /// Returns the managed tunnel ID and the actual bound local port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manager rejects the request or communication fails.
+    pub async fn udp_forward_tunnel(
+        &mut self,
+        connection_id: ConnectionId,
+        bind_port: u16,
+        remote_host: impl Into<String>,
+        remote_port: u16,
+    ) -> io::Result<(ManagedTunnelId, u16)> {
+        let remote_host = remote_host.into();
+        trace!("udp_forward_tunnel({connection_id}, {bind_port}, {remote_host}, {remote_port})");
+        self.start_tunnel(ManagerRequest::ForwardTunnel {
+            connection_id,
+            bind_port,
+            remote_host,
+            remote_port,
+            protocol: TunnelProtocol::Udp,
+            bind_path: None,
+            remote_path: None,
+        })
+        .await
+    }
+
+    /// Requests the manager to start a forward tunnel where either end may be a Unix domain
+    /// socket (local port or socket → remote target or socket).
+    ///
+    /// When `bind_path` is set, the manager listens on a local Unix socket at that path instead
+    /// of `bind_port`. When `remote_path` is set, the remote server connects to the Unix socket
+    /// at that path instead of `remote_host:remote_port`.
+    ///
+    /// Returns the managed tunnel ID and the actual bound local port, which is `0` when
+    /// listening on a Unix socket.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manager rejects the request or communication fails.
+    pub async fn unix_forward_tunnel(
+        &mut self,
+        connection_id: ConnectionId,
+        bind_port: u16,
+        bind_path: Option<String>,
+        remote_host: impl Into<String>,
+        remote_port: u16,
+        remote_path: Option<String>,
+    ) -> io::Result<(ManagedTunnelId, u16)> {
+        let remote_host = remote_host.into();
+        trace!(
+            "unix_forward_tunnel({connection_id}, {bind_port}, {bind_path:?}, {remote_host}, \
+             {remote_port}, {remote_path:?})"
+        );
+        self.start_tunnel(ManagerRequest::ForwardTunnel {
+            connection_id,
+            bind_port,
+            remote_host,
+            remote_port,
+            protocol: TunnelProtocol::Tcp,
+            bind_path,
+            remote_path,
+        })
+        .await
+    }
+
+    /// Requests the manager to start a reverse tunnel (remote listener → local target).
+    ///
+    /// Returns the managed tunnel ID and the actual remote port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manager rejects the request or communication fails.
+    pub async fn reverse_tunnel(
+        &mut self,
+        connection_id: ConnectionId,
+        remote_port: u16,
+        local_host: impl Into<String>,
+        local_port: u16,
+    ) -> io::Result<(ManagedTunnelId, u16)> {
+        let local_host = local_host.into();
+        trace!("reverse_tunnel({connection_id}, {remote_port}, {local_host}, {local_port})");
+        self.start_tunnel(ManagerRequest::ReverseTunnel {
+            connection_id,
+            remote_port,
+            local_host,
+            local_port,
+            protocol: TunnelProtocol::Tcp,
+            remote_path: None,
+            local_path: None,
+        })
+        .await
+    }
+
+    /// Requests the manager to start a reverse UDP tunnel (remote UDP listener → local target).
+    ///
+    /// Each remote peer sending datagrams to the listener gets its own association with the
+    /// local target, which is closed once it has been idle for a while.
+    ///
+    /// Returns the managed tunnel ID and the actual remote port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manager rejects the request or communication fails.
+    pub async fn udp_reverse_tunnel(
+        &mut self,
+        connection_id: ConnectionId,
+        remote_port: u16,
+        local_host: impl Into<String>,
+        local_port: u16,
+    ) -> io::Result<(ManagedTunnelId, u16)> {
+        let local_host = local_host.into();
+        trace!("udp_reverse_tunnel({connection_id}, {remote_port}, {local_host}, {local_port})");
+        self.start_tunnel(ManagerRequest::ReverseTunnel {
+            connection_id,
+            remote_port,
+            local_host,
+            local_port,
+            protocol: TunnelProtocol::Udp,
+            remote_path: None,
+            local_path: None,
+        })
+        .await
+    }
+
+    /// Requests the manager to start a reverse tunnel where either end may be a Unix domain
+    /// socket (remote listener or socket → local target or socket).
+    ///
+    /// When `remote_path` is set, the remote server listens on a Unix socket at that path
+    /// instead of `remote_port`. When `local_path` is set, the manager connects to the local
+    /// Unix socket at that path instead of `local_host:local_port`.
+    ///
+    /// Returns the managed tunnel ID and the actual remote port, which is `0` when listening
+    /// on a Unix socket.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manager rejects the request or communication fails.
+    pub async fn unix_reverse_tunnel(
+        &mut self,
+        connection_id: ConnectionId,
+        remote_port: u16,
+        remote_path: Option<String>,
+        local_host: impl Into<String>,
+        local_port: u16,
+        local_path: Option<String>,
+    ) -> io::Result<(ManagedTunnelId, u16)> {
+        let local_host = local_host.into();
+        trace!(
+            "unix_reverse_tunnel({connection_id}, {remote_port}, {remote_path:?}, {local_host}, \
+             {local_port}, {local_path:?})"
+        );
+        self.start_tunnel(ManagerRequest::ReverseTunnel {
+            connection_id,
+            remote_port,
+            local_host,
+            local_port,
+            protocol: TunnelProtocol::Tcp,
+            remote_path,
+            local_path,
+        })
+        .await
+    }
+
+    /// Sends a request to start a forward or reverse tunnel, returning the managed tunnel ID
+    /// and the port that it is bound to.
+    async fn start_tunnel(
+        &mut self,
+        request: ManagerRequest,
+    ) -> io::Result<(ManagedTunnelId, u16)> {
+        let res = self.send(request).await?;
+        match res.payload {
+            ManagerResponse::ManagedTunnelStarted { id, port } => Ok((id, port)),
+            ManagerResponse::Error { description } => Err(io::Error::other(description)),
+            x => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Got unexpected response: {x:?}"),
+            )),
+        }
+    }
+
+    /// Requests the manager to start a dynamic tunnel, which is a SOCKS4/SOCKS4a/SOCKS5 proxy
+    /// bound to `bind_host:bind_port` that opens a forward tunnel per client (like `ssh -D`).
+    ///
+    /// Returns the managed tunnel ID and the actual bound local port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manager rejects the request or communication fails.
+    pub async fn socks_tunnel(
+        &mut self,
+        connection_id: ConnectionId,
+        bind_host: impl Into<String>,
+        bind_port: u16,
+    ) -> io::Result<(ManagedTunnelId, u16)> {
+        let bind_host = bind_host.into();
+        trace!("socks_tunnel({connection_id}, {bind_host}, {bind_port})");
         let res = self
-            .send(ManagerRequest::ForwardTunnel {
+            .send(ManagerRequest::SocksTunnel {
                 connection_id,
+                bind_host,
                 bind_port,
-                remote_host,
-                remote_port,
             })
             .await?;
         match res.payload {
@@ -330,28 +621,30 @@ impl ManagerClient {
         }
     }
 
-    /// Requests the manager to start a reverse tunnel (remote listener → local target).
+    /// Requests the manager to start an HTTP/1.1 reverse proxy bound to `bind_host:bind_port`
+    /// that serves each of `routes` at `http://NAME.localhost:PORT`, forwarding its requests to
+    /// the route's remote target with the `Host` header rewritten.
     ///
-    /// Returns the managed tunnel ID and the actual remote port.
+    /// Returns the managed tunnel ID and the actual bound local port.
     ///
     /// # Errors
     ///
     /// Returns an error if the manager rejects the request or communication fails.
-    pub async fn reverse_tunnel(
+    pub async fn http_tunnel(
         &mut self,
         connection_id: ConnectionId,
-        remote_port: u16,
-        local_host: impl Into<String>,
-        local_port: u16,
+        bind_host: impl Into<String>,
+        bind_port: u16,
+        routes: Vec<HttpRoute>,
     ) -> io::Result<(ManagedTunnelId, u16)> {
-        let local_host = local_host.into();
-        trace!("reverse_tunnel({connection_id}, {remote_port}, {local_host}, {local_port})");
+        let bind_host = bind_host.into();
+        trace!("http_tunnel({connection_id}, {bind_host}, {bind_port}, {routes:?})");
         let res = self
-            .send(ManagerRequest::ReverseTunnel {
+            .send(ManagerRequest::HttpTunnel {
                 connection_id,
-                remote_port,
-                local_host,
-                local_port,
+                bind_host,
+                bind_port,
+                routes,
             })
             .await?;
         match res.payload {
@@ -399,6 +692,18 @@ impl ManagerClient {
             )),
         }
     }
+
+    /// Watches for ports that the manager forwards automatically as remote processes start and
+    /// stop listening on them, across all connections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the manager fails.
+    pub async fn watch_ports(&mut self) -> io::Result<PortWatcher> {
+        trace!("watch_ports()");
+        let mailbox = self.mail(ManagerRequest::WatchPorts).await?;
+        Ok(PortWatcher { mailbox })
+    }
 }
 
 #[cfg(test)]
@@ -409,8 +714,10 @@ mod tests {
     use crate::auth::DummyAuthHandler;
 
     use super::*;
-    use crate::net::client::UntypedClient;
+    use crate::net::client::{ConnectionState, UntypedClient};
     use crate::net::common::{Connection, InmemoryTransport, Request, Response};
+    use crate::net::manager::data::ManagedTunnelState;
+    use crate::protocol::TunnelStats;
 
     fn setup() -> (ManagerClient, Connection<InmemoryTransport>) {
         let (client, server) = Connection::pair(100);
@@ -575,6 +882,7 @@ mod tests {
                 id: 123,
                 destination: "scheme://host".to_string(),
                 options: "key=value".parse::<Map>().unwrap(),
+                state: ConnectionState::Reconnecting,
             };
 
             transport
@@ -587,6 +895,7 @@ mod tests {
         assert_eq!(info.id, 123);
         assert_eq!(info.destination, "scheme://host");
         assert_eq!(info.options, "key=value".parse::<Map>().unwrap());
+        assert_eq!(info.state, ConnectionState::Reconnecting);
     }
 
     #[tokio::test]
@@ -1292,11 +1601,14 @@ mod tests {
                     bind_port,
                     remote_host,
                     remote_port,
+                    protocol,
+                    ..
                 } => {
                     assert_eq!(*connection_id, 7);
                     assert_eq!(*bind_port, 8080);
                     assert_eq!(remote_host, "db-host");
                     assert_eq!(*remote_port, 5432);
+                    assert_eq!(*protocol, TunnelProtocol::Tcp);
                 }
                 other => panic!("Expected ForwardTunnel request, got {other:?}"),
             }
@@ -1316,6 +1628,204 @@ mod tests {
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn udp_forward_tunnel_should_send_udp_protocol_in_request() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            match &request.payload {
+                ManagerRequest::ForwardTunnel {
+                    connection_id,
+                    bind_port,
+                    remote_host,
+                    remote_port,
+                    protocol,
+                    ..
+                } => {
+                    assert_eq!(*connection_id, 7);
+                    assert_eq!(*bind_port, 0);
+                    assert_eq!(remote_host, "dns-host");
+                    assert_eq!(*remote_port, 53);
+                    assert_eq!(*protocol, TunnelProtocol::Udp);
+                }
+                other => panic!("Expected ForwardTunnel request, got {other:?}"),
+            }
+
+            transport
+                .write_frame_for(&Response::new(
+                    request.id,
+                    ManagerResponse::ManagedTunnelStarted { id: 4, port: 5300 },
+                ))
+                .await
+                .unwrap();
+        });
+
+        let (id, port) = client
+            .udp_forward_tunnel(7, 0, "dns-host", 53)
+            .await
+            .unwrap();
+        assert_eq!(id, 4);
+        assert_eq!(port, 5300);
+    }
+
+    #[tokio::test]
+    async fn udp_reverse_tunnel_should_send_udp_protocol_in_request() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            match &request.payload {
+                ManagerRequest::ReverseTunnel {
+                    connection_id,
+                    remote_port,
+                    local_host,
+                    local_port,
+                    protocol,
+                    ..
+                } => {
+                    assert_eq!(*connection_id, 5);
+                    assert_eq!(*remote_port, 8125);
+                    assert_eq!(local_host, "127.0.0.1");
+                    assert_eq!(*local_port, 8125);
+                    assert_eq!(*protocol, TunnelProtocol::Udp);
+                }
+                other => panic!("Expected ReverseTunnel request, got {other:?}"),
+            }
+
+            transport
+                .write_frame_for(&Response::new(
+                    request.id,
+                    ManagerResponse::ManagedTunnelStarted { id: 2, port: 8125 },
+                ))
+                .await
+                .unwrap();
+        });
+
+        let (id, port) = client
+            .udp_reverse_tunnel(5, 8125, "127.0.0.1", 8125)
+            .await
+            .unwrap();
+        assert_eq!(id, 2);
+        assert_eq!(port, 8125);
+    }
+
+    #[tokio::test]
+    async fn unix_forward_tunnel_should_send_socket_paths_in_request() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            match &request.payload {
+                ManagerRequest::ForwardTunnel {
+                    connection_id,
+                    bind_port,
+                    protocol,
+                    bind_path,
+                    remote_path,
+                    ..
+                } => {
+                    assert_eq!(*connection_id, 7);
+                    assert_eq!(*bind_port, 2375);
+                    assert_eq!(*protocol, TunnelProtocol::Tcp);
+                    assert_eq!(*bind_path, None);
+                    assert_eq!(remote_path.as_deref(), Some("/var/run/docker.sock"));
+                }
+                other => panic!("Expected ForwardTunnel request, got {other:?}"),
+            }
+
+            transport
+                .write_frame_for(&Response::new(
+                    request.id,
+                    ManagerResponse::ManagedTunnelStarted { id: 6, port: 2375 },
+                ))
+                .await
+                .unwrap();
+        });
+
+        let (id, port) = client
+            .unix_forward_tunnel(
+                7,
+                2375,
+                None,
+                "",
+                0,
+                Some("/var/run/docker.sock".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(id, 6);
+        assert_eq!(port, 2375);
+    }
+
+    #[tokio::test]
+    async fn unix_reverse_tunnel_should_send_socket_paths_in_request() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            match &request.payload {
+                ManagerRequest::ReverseTunnel {
+                    connection_id,
+                    local_host,
+                    local_port,
+                    remote_path,
+                    local_path,
+                    ..
+                } => {
+                    assert_eq!(*connection_id, 5);
+                    assert_eq!(local_host, "127.0.0.1");
+                    assert_eq!(*local_port, 3000);
+                    assert_eq!(remote_path.as_deref(), Some("/tmp/app.sock"));
+                    assert_eq!(*local_path, None);
+                }
+                other => panic!("Expected ReverseTunnel request, got {other:?}"),
+            }
+
+            transport
+                .write_frame_for(&Response::new(
+                    request.id,
+                    ManagerResponse::ManagedTunnelStarted { id: 3, port: 0 },
+                ))
+                .await
+                .unwrap();
+        });
+
+        let (id, port) = client
+            .unix_reverse_tunnel(
+                5,
+                0,
+                Some("/tmp/app.sock".to_string()),
+                "127.0.0.1",
+                3000,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(id, 3);
+        assert_eq!(port, 0);
+    }
+
     #[tokio::test]
     async fn reverse_tunnel_should_return_id_and_port_from_successful_response() {
         let (mut client, mut transport) = setup();
@@ -1410,11 +1920,14 @@ mod tests {
                     remote_port,
                     local_host,
                     local_port,
+                    protocol,
+                    ..
                 } => {
                     assert_eq!(*connection_id, 5);
                     assert_eq!(*remote_port, 9000);
                     assert_eq!(local_host, "127.0.0.1");
                     assert_eq!(*local_port, 3000);
+                    assert_eq!(*protocol, TunnelProtocol::Tcp);
                 }
                 other => panic!("Expected ReverseTunnel request, got {other:?}"),
             }
@@ -1434,6 +1947,146 @@ mod tests {
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn socks_tunnel_should_send_correct_request_and_return_started_tunnel() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            match &request.payload {
+                ManagerRequest::SocksTunnel {
+                    connection_id,
+                    bind_host,
+                    bind_port,
+                } => {
+                    assert_eq!(*connection_id, 5);
+                    assert_eq!(bind_host, "127.0.0.1");
+                    assert_eq!(*bind_port, 0);
+                }
+                other => panic!("Expected SocksTunnel request, got {other:?}"),
+            }
+
+            transport
+                .write_frame_for(&Response::new(
+                    request.id,
+                    ManagerResponse::ManagedTunnelStarted { id: 3, port: 1080 },
+                ))
+                .await
+                .unwrap();
+        });
+
+        let (id, port) = client.socks_tunnel(5, "127.0.0.1", 0).await.unwrap();
+        assert_eq!(id, 3);
+        assert_eq!(port, 1080);
+    }
+
+    #[tokio::test]
+    async fn socks_tunnel_should_report_error_if_receives_error_response() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            transport
+                .write_frame_for(&Response::new(request.id, test_error_response()))
+                .await
+                .unwrap();
+        });
+
+        let err = client.socks_tunnel(1, "127.0.0.1", 1080).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert_eq!(err.to_string(), test_error().to_string());
+    }
+
+    #[tokio::test]
+    async fn http_tunnel_should_send_correct_request_and_return_started_tunnel() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            match &request.payload {
+                ManagerRequest::HttpTunnel {
+                    connection_id,
+                    bind_host,
+                    bind_port,
+                    routes,
+                } => {
+                    assert_eq!(*connection_id, 5);
+                    assert_eq!(bind_host, "127.0.0.1");
+                    assert_eq!(*bind_port, 8080);
+                    assert_eq!(
+                        routes,
+                        &[HttpRoute {
+                            name: "web".to_string(),
+                            host: "127.0.0.1".to_string(),
+                            port: 3000,
+                        }]
+                    );
+                }
+                other => panic!("Expected HttpTunnel request, got {other:?}"),
+            }
+
+            transport
+                .write_frame_for(&Response::new(
+                    request.id,
+                    ManagerResponse::ManagedTunnelStarted { id: 3, port: 8080 },
+                ))
+                .await
+                .unwrap();
+        });
+
+        let routes = vec![HttpRoute {
+            name: "web".to_string(),
+            host: "127.0.0.1".to_string(),
+            port: 3000,
+        }];
+        let (id, port) = client
+            .http_tunnel(5, "127.0.0.1", 8080, routes)
+            .await
+            .unwrap();
+        assert_eq!(id, 3);
+        assert_eq!(port, 8080);
+    }
+
+    #[tokio::test]
+    async fn http_tunnel_should_report_error_if_receives_error_response() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            transport
+                .write_frame_for(&Response::new(request.id, test_error_response()))
+                .await
+                .unwrap();
+        });
+
+        let err = client
+            .http_tunnel(1, "127.0.0.1", 8080, Vec::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert_eq!(err.to_string(), test_error().to_string());
+    }
+
     #[tokio::test]
     async fn close_managed_tunnel_should_return_success_from_successful_response() {
         let (mut client, mut transport) = setup();
@@ -1541,6 +2194,18 @@ mod tests {
             bind_port: 8080,
             remote_host: "db-host".to_string(),
             remote_port: 5432,
+            protocol: TunnelProtocol::Tcp,
+            bind_path: None,
+            remote_path: None,
+            state: ManagedTunnelState::Failed,
+            last_error: Some("Lost connection to server".to_string()),
+            stats: TunnelStats {
+                bytes_out: 1024,
+                bytes_in: 4096,
+                active_connections: 0,
+                total_connections: 3,
+                last_activity: Some(1700000000),
+            },
         }];
         let tunnels_clone = expected_tunnels.clone();
 
@@ -1665,4 +2330,91 @@ mod tests {
 
         client.list_managed_tunnels().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn watch_ports_should_yield_events_until_the_manager_stops_sending() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert!(
+                matches!(&request.payload, ManagerRequest::WatchPorts),
+                "Expected WatchPorts request, got {:?}",
+                request.payload
+            );
+
+            transport
+                .write_frame_for(&Response::new(
+                    request.id.clone(),
+                    ManagerResponse::PortForwarded {
+                        connection_id: 1,
+                        id: 2,
+                        remote_host: "127.0.0.1".to_string(),
+                        remote_port: 3000,
+                        local_port: 3001,
+                    },
+                ))
+                .await
+                .unwrap();
+            transport
+                .write_frame_for(&Response::new(
+                    request.id,
+                    ManagerResponse::PortForwardClosed {
+                        connection_id: 1,
+                        id: 2,
+                        remote_port: 3000,
+                    },
+                ))
+                .await
+                .unwrap();
+        });
+
+        let mut watcher = client.watch_ports().await.unwrap();
+        assert_eq!(
+            watcher.next().await.unwrap().unwrap(),
+            PortEvent::Forwarded {
+                connection_id: 1,
+                id: 2,
+                remote_host: "127.0.0.1".to_string(),
+                remote_port: 3000,
+                local_port: 3001,
+            }
+        );
+        assert_eq!(
+            watcher.next().await.unwrap().unwrap(),
+            PortEvent::Closed {
+                connection_id: 1,
+                id: 2,
+                remote_port: 3000,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn watch_ports_should_report_error_if_receives_error_response() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            transport
+                .write_frame_for(&Response::new(request.id, test_error_response()))
+                .await
+                .unwrap();
+        });
+
+        let mut watcher = client.watch_ports().await.unwrap();
+        let err = watcher.next().await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert_eq!(err.to_string(), test_error().to_string());
+    }
 }
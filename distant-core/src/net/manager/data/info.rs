@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use crate::net::client::ConnectionState;
 use crate::net::common::{ConnectionId, Map};
 
 /// Information about a specific connection
@@ -13,4 +14,8 @@ pub struct ConnectionInfo {
 
     /// Additional options associated with this connection
     pub options: Map,
+
+    /// Current state of the connection (e.g. `reconnecting` after losing contact with the server)
+    #[serde(default)]
+    pub state: ConnectionState,
 }
@@ -1,8 +1,11 @@
+use std::fmt;
+
 use crate::auth::msg::AuthenticationResponse;
 use serde::{Deserialize, Serialize};
 
 use super::{ManagedTunnelId, ManagerAuthenticationId, ManagerChannelId};
 use crate::net::common::{ConnectionId, Map, UntypedRequest};
+use crate::protocol::TunnelProtocol;
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -70,19 +73,55 @@ pub enum ManagerRequest {
     List,
 
     /// Start a forward tunnel (local listener -> remote target) in the manager
+    ///
+    /// When `bind_path` is set, the manager listens on a local Unix socket at that path instead
+    /// of `bind_port`. When `remote_path` is set, the remote server connects to the Unix socket
+    /// at that path instead of `remote_host:remote_port`.
     ForwardTunnel {
         connection_id: ConnectionId,
         bind_port: u16,
         remote_host: String,
         remote_port: u16,
+        #[serde(default, skip_serializing_if = "TunnelProtocol::is_tcp")]
+        protocol: TunnelProtocol,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bind_path: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        remote_path: Option<String>,
     },
 
     /// Start a reverse tunnel (remote listener -> local target) in the manager
+    ///
+    /// When `remote_path` is set, the remote server listens on a Unix socket at that path
+    /// instead of `remote_port`. When `local_path` is set, the manager connects to the local
+    /// Unix socket at that path instead of `local_host:local_port`.
     ReverseTunnel {
         connection_id: ConnectionId,
         remote_port: u16,
         local_host: String,
         local_port: u16,
+        #[serde(default, skip_serializing_if = "TunnelProtocol::is_tcp")]
+        protocol: TunnelProtocol,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        remote_path: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        local_path: Option<String>,
+    },
+
+    /// Start a dynamic tunnel (local SOCKS proxy -> any remote target) in the manager
+    SocksTunnel {
+        connection_id: ConnectionId,
+        bind_host: String,
+        bind_port: u16,
+    },
+
+    /// Start an HTTP proxy in the manager that routes requests for `http://NAME.localhost` to
+    /// the remote target of the route with that name
+    HttpTunnel {
+        connection_id: ConnectionId,
+        bind_host: String,
+        bind_port: u16,
+        routes: Vec<HttpRoute>,
     },
 
     /// Close a managed tunnel by ID
@@ -90,6 +129,29 @@ pub enum ManagerRequest {
 
     /// List all managed tunnels
     ListManagedTunnels,
+
+    /// Subscribe to notifications about ports that the manager automatically forwards as
+    /// remote processes start and stop listening on them
+    WatchPorts,
+}
+
+/// Route of an HTTP proxy started by [`ManagerRequest::HttpTunnel`], sending requests for
+/// `http://{name}.localhost` to `host:port` as seen from the remote server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpRoute {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+}
+
+impl fmt::Display for HttpRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "{}=[{}]:{}", self.name, self.host, self.port)
+        } else {
+            write!(f, "{}={}:{}", self.name, self.host, self.port)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -103,6 +165,9 @@ mod tests {
             bind_port: 8080,
             remote_host: "db-host".to_string(),
             remote_port: 5432,
+            protocol: TunnelProtocol::Tcp,
+            bind_path: None,
+            remote_path: None,
         };
         let json = serde_json::to_string(&request).unwrap();
 
@@ -110,6 +175,10 @@ mod tests {
             json.contains("\"forward_tunnel\""),
             "Expected snake_case variant tag in JSON: {json}"
         );
+        assert!(
+            !json.contains("protocol") && !json.contains("path"),
+            "Expected TCP protocol and socket paths to be omitted from JSON: {json}"
+        );
 
         let deserialized: ManagerRequest = serde_json::from_str(&json).unwrap();
         match deserialized {
@@ -118,11 +187,17 @@ mod tests {
                 bind_port,
                 remote_host,
                 remote_port,
+                protocol,
+                bind_path,
+                remote_path,
             } => {
                 assert_eq!(connection_id, 5);
                 assert_eq!(bind_port, 8080);
                 assert_eq!(remote_host, "db-host");
                 assert_eq!(remote_port, 5432);
+                assert_eq!(protocol, TunnelProtocol::Tcp);
+                assert_eq!(bind_path, None);
+                assert_eq!(remote_path, None);
             }
             other => panic!("Expected ForwardTunnel, got {other:?}"),
         }
@@ -135,6 +210,9 @@ mod tests {
             remote_port: 9000,
             local_host: "localhost".to_string(),
             local_port: 3000,
+            protocol: TunnelProtocol::Udp,
+            remote_path: None,
+            local_path: None,
         };
         let json = serde_json::to_string(&request).unwrap();
 
@@ -150,16 +228,159 @@ mod tests {
                 remote_port,
                 local_host,
                 local_port,
+                protocol,
+                remote_path,
+                local_path,
             } => {
                 assert_eq!(connection_id, 3);
                 assert_eq!(remote_port, 9000);
                 assert_eq!(local_host, "localhost");
                 assert_eq!(local_port, 3000);
+                assert_eq!(protocol, TunnelProtocol::Udp);
+                assert_eq!(remote_path, None);
+                assert_eq!(local_path, None);
             }
             other => panic!("Expected ReverseTunnel, got {other:?}"),
         }
     }
 
+    #[test]
+    fn forward_tunnel_should_deserialize_unix_socket_paths_via_json() {
+        let json = r#"{"type":"forward_tunnel","connection_id":1,"bind_port":2375,"remote_host":"","remote_port":0,"remote_path":"/var/run/docker.sock"}"#;
+
+        match serde_json::from_str::<ManagerRequest>(json).unwrap() {
+            ManagerRequest::ForwardTunnel {
+                bind_port,
+                protocol,
+                bind_path,
+                remote_path,
+                ..
+            } => {
+                assert_eq!(bind_port, 2375);
+                assert_eq!(protocol, TunnelProtocol::Tcp);
+                assert_eq!(bind_path, None);
+                assert_eq!(remote_path.as_deref(), Some("/var/run/docker.sock"));
+            }
+            other => panic!("Expected ForwardTunnel, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reverse_tunnel_should_serialize_unix_socket_paths_via_json() {
+        let request = ManagerRequest::ReverseTunnel {
+            connection_id: 3,
+            remote_port: 0,
+            local_host: String::new(),
+            local_port: 0,
+            protocol: TunnelProtocol::Tcp,
+            remote_path: Some("/tmp/remote.sock".to_string()),
+            local_path: Some("/tmp/local.sock".to_string()),
+        };
+        let json = serde_json::to_string(&request).unwrap();
+
+        assert!(
+            json.contains("\"remote_path\":\"/tmp/remote.sock\"")
+                && json.contains("\"local_path\":\"/tmp/local.sock\""),
+            "Expected socket paths in JSON: {json}"
+        );
+
+        match serde_json::from_str::<ManagerRequest>(&json).unwrap() {
+            ManagerRequest::ReverseTunnel {
+                remote_path,
+                local_path,
+                ..
+            } => {
+                assert_eq!(remote_path.as_deref(), Some("/tmp/remote.sock"));
+                assert_eq!(local_path.as_deref(), Some("/tmp/local.sock"));
+            }
+            other => panic!("Expected ReverseTunnel, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn socks_tunnel_should_serialize_and_deserialize_via_json() {
+        let request = ManagerRequest::SocksTunnel {
+            connection_id: 7,
+            bind_host: "127.0.0.1".to_string(),
+            bind_port: 1080,
+        };
+        let json = serde_json::to_string(&request).unwrap();
+
+        assert!(
+            json.contains("\"socks_tunnel\""),
+            "Expected snake_case variant tag in JSON: {json}"
+        );
+
+        let deserialized: ManagerRequest = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            ManagerRequest::SocksTunnel {
+                connection_id,
+                bind_host,
+                bind_port,
+            } => {
+                assert_eq!(connection_id, 7);
+                assert_eq!(bind_host, "127.0.0.1");
+                assert_eq!(bind_port, 1080);
+            }
+            other => panic!("Expected SocksTunnel, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn http_tunnel_should_serialize_and_deserialize_via_json() {
+        let request = ManagerRequest::HttpTunnel {
+            connection_id: 7,
+            bind_host: "127.0.0.1".to_string(),
+            bind_port: 8080,
+            routes: vec![HttpRoute {
+                name: "web".to_string(),
+                host: "127.0.0.1".to_string(),
+                port: 3000,
+            }],
+        };
+        let json = serde_json::to_string(&request).unwrap();
+
+        assert!(
+            json.contains("\"http_tunnel\""),
+            "Expected snake_case variant tag in JSON: {json}"
+        );
+
+        let deserialized: ManagerRequest = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            ManagerRequest::HttpTunnel {
+                connection_id,
+                bind_host,
+                bind_port,
+                routes,
+            } => {
+                assert_eq!(connection_id, 7);
+                assert_eq!(bind_host, "127.0.0.1");
+                assert_eq!(bind_port, 8080);
+                assert_eq!(routes.len(), 1);
+                assert_eq!(routes[0].name, "web");
+                assert_eq!(routes[0].port, 3000);
+            }
+            other => panic!("Expected HttpTunnel, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn http_route_should_display_as_name_and_target() {
+        let route = HttpRoute {
+            name: "web".to_string(),
+            host: "127.0.0.1".to_string(),
+            port: 3000,
+        };
+        assert_eq!(route.to_string(), "web=127.0.0.1:3000");
+
+        let route = HttpRoute {
+            name: "api".to_string(),
+            host: "::1".to_string(),
+            port: 4000,
+        };
+        assert_eq!(route.to_string(), "api=[::1]:4000");
+    }
+
     #[test]
     fn close_managed_tunnel_should_serialize_and_deserialize_via_json() {
         let request = ManagerRequest::CloseManagedTunnel { id: 42 };
@@ -196,6 +417,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn watch_ports_should_serialize_and_deserialize_via_json() {
+        let request = ManagerRequest::WatchPorts;
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(json, r#"{"type":"watch_ports"}"#);
+
+        let deserialized: ManagerRequest = serde_json::from_str(&json).unwrap();
+        assert!(
+            matches!(deserialized, ManagerRequest::WatchPorts),
+            "Expected WatchPorts, got {deserialized:?}"
+        );
+    }
+
     #[test]
     fn forward_tunnel_should_reject_unknown_fields() {
         let json = r#"{"type":"forward_tunnel","connection_id":1,"bind_port":80,"remote_host":"h","remote_port":80,"extra":"bad"}"#;
@@ -1,6 +1,7 @@
 use crate::auth::msg::Authentication;
-use crate::protocol::TunnelDirection;
+use crate::protocol::{TunnelDirection, TunnelProtocol, TunnelStats};
 use serde::{Deserialize, Serialize};
+use strum::Display;
 
 use super::{
     ConnectionInfo, ConnectionList, ManagedTunnelId, ManagerAuthenticationId, ManagerChannelId,
@@ -9,6 +10,22 @@ use super::{
 use crate::net::common::{ConnectionId, Destination, UntypedResponse};
 
 /// Information about a tunnel managed by the manager process.
+///
+/// Dynamic tunnels have no single target, so they report a remote host of `*`
+/// and a remote port of `0`.
+///
+/// Either end of a stream tunnel can be a Unix domain socket, in which case its
+/// path is reported in `bind_path` or `remote_path` with a port of `0` (and an
+/// empty remote host), and the protocol is reported as [`TunnelProtocol::Unix`].
+/// As with the other fields, `bind_*` describes the side that listens, which is
+/// remote for a reverse tunnel, and `remote_*` describes the side connected to.
+///
+/// A tunnel whose connection is lost is kept around in the [`ManagedTunnelState::Failed`]
+/// state, along with the reason in `last_error`, so that it can be started again once the
+/// manager connects to the same destination.
+///
+/// Traffic through the tunnel is reported in `stats`, counting bytes sent to and received
+/// from the side connected to, along with the connections relayed through the tunnel.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ManagedTunnelInfo {
     pub id: ManagedTunnelId,
@@ -17,6 +34,35 @@ pub struct ManagedTunnelInfo {
     pub bind_port: u16,
     pub remote_host: String,
     pub remote_port: u16,
+    #[serde(default)]
+    pub protocol: TunnelProtocol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_path: Option<String>,
+    #[serde(default)]
+    pub state: ManagedTunnelState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(default, skip_serializing_if = "TunnelStats::is_empty")]
+    pub stats: TunnelStats,
+}
+
+/// State of a tunnel managed by the manager process.
+#[derive(Copy, Clone, Debug, Default, Display, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ManagedTunnelState {
+    /// Tunnel is relaying connections.
+    #[default]
+    Active,
+
+    /// Connection that the tunnel goes through is trying to reconnect to the server.
+    Reconnecting,
+
+    /// Tunnel is not running, either because its connection was lost or because it could not
+    /// be started again.
+    Failed,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -84,6 +130,37 @@ pub enum ManagerResponse {
 
     /// List of managed tunnels
     ManagedTunnels { tunnels: Vec<ManagedTunnelInfo> },
+
+    /// Notification that a port a remote process is listening on was automatically forwarded
+    PortForwarded {
+        /// Id of the connection whose remote machine is listening on the port
+        connection_id: ConnectionId,
+
+        /// Id of the managed tunnel created for the port
+        id: ManagedTunnelId,
+
+        /// Host on the remote machine that the tunnel connects to
+        remote_host: String,
+
+        /// Port on the remote machine that the tunnel connects to
+        remote_port: u16,
+
+        /// Port on the local machine that the tunnel is listening on
+        local_port: u16,
+    },
+
+    /// Notification that an automatically-forwarded port stopped listening and its tunnel
+    /// was closed
+    PortForwardClosed {
+        /// Id of the connection whose remote machine stopped listening on the port
+        connection_id: ConnectionId,
+
+        /// Id of the managed tunnel that was closed
+        id: ManagedTunnelId,
+
+        /// Port on the remote machine that is no longer being listened on
+        remote_port: u16,
+    },
 }
 
 impl<T: std::error::Error> From<T> for ManagerResponse {
@@ -97,7 +174,6 @@ impl<T: std::error::Error> From<T> for ManagerResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::TunnelDirection;
 
     fn make_tunnel_info() -> ManagedTunnelInfo {
         ManagedTunnelInfo {
@@ -107,6 +183,12 @@ mod tests {
             bind_port: 8080,
             remote_host: "db-host".to_string(),
             remote_port: 5432,
+            protocol: TunnelProtocol::Tcp,
+            bind_path: None,
+            remote_path: None,
+            state: ManagedTunnelState::Active,
+            last_error: None,
+            stats: TunnelStats::default(),
         }
     }
 
@@ -127,6 +209,12 @@ mod tests {
             bind_port: 0,
             remote_host: "[::1]".to_string(),
             remote_port: 443,
+            protocol: TunnelProtocol::Tcp,
+            bind_path: None,
+            remote_path: None,
+            state: ManagedTunnelState::Active,
+            last_error: None,
+            stats: TunnelStats::default(),
         };
 
         let json = serde_json::to_string(&info).unwrap();
@@ -160,6 +248,144 @@ mod tests {
         );
     }
 
+    #[test]
+    fn managed_tunnel_info_should_default_protocol_to_tcp_when_missing() {
+        let json = r#"{
+            "id": 1,
+            "connection_id": 2,
+            "direction": "forward",
+            "bind_port": 8080,
+            "remote_host": "db-host",
+            "remote_port": 5432
+        }"#;
+
+        let info: ManagedTunnelInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(info.protocol, TunnelProtocol::Tcp);
+    }
+
+    #[test]
+    fn managed_tunnel_info_should_serialize_udp_protocol() {
+        let info = ManagedTunnelInfo {
+            protocol: TunnelProtocol::Udp,
+            ..make_tunnel_info()
+        };
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains("\"udp\""), "Expected 'udp' in JSON: {json}");
+
+        let deserialized: ManagedTunnelInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, info);
+    }
+
+    #[test]
+    fn managed_tunnel_info_should_omit_socket_paths_when_missing() {
+        let json = serde_json::to_string(&make_tunnel_info()).unwrap();
+        assert!(
+            !json.contains("bind_path") && !json.contains("remote_path"),
+            "Expected socket paths to be omitted from JSON: {json}"
+        );
+    }
+
+    #[test]
+    fn managed_tunnel_info_should_serialize_unix_socket_paths() {
+        let info = ManagedTunnelInfo {
+            bind_port: 0,
+            remote_host: String::new(),
+            remote_port: 0,
+            protocol: TunnelProtocol::Unix,
+            bind_path: Some("/tmp/docker.sock".to_string()),
+            remote_path: Some("/var/run/docker.sock".to_string()),
+            ..make_tunnel_info()
+        };
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains("\"unix\""), "Expected 'unix' in JSON: {json}");
+        assert!(
+            json.contains("\"/var/run/docker.sock\""),
+            "Expected remote path in JSON: {json}"
+        );
+
+        let deserialized: ManagedTunnelInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, info);
+    }
+
+    #[test]
+    fn managed_tunnel_info_should_default_to_active_state_when_missing() {
+        let json = r#"{
+            "id": 1,
+            "connection_id": 2,
+            "direction": "forward",
+            "bind_port": 8080,
+            "remote_host": "db-host",
+            "remote_port": 5432
+        }"#;
+
+        let info: ManagedTunnelInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(info.state, ManagedTunnelState::Active);
+        assert_eq!(info.last_error, None);
+    }
+
+    #[test]
+    fn managed_tunnel_info_should_serialize_failed_state_with_last_error() {
+        let info = ManagedTunnelInfo {
+            state: ManagedTunnelState::Failed,
+            last_error: Some("Lost connection to server".to_string()),
+            ..make_tunnel_info()
+        };
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(
+            json.contains("\"failed\""),
+            "Expected 'failed' in JSON: {json}"
+        );
+        assert!(
+            json.contains("\"Lost connection to server\""),
+            "Expected last error in JSON: {json}"
+        );
+
+        let deserialized: ManagedTunnelInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, info);
+    }
+
+    #[test]
+    fn managed_tunnel_info_should_omit_last_error_when_missing() {
+        let json = serde_json::to_string(&make_tunnel_info()).unwrap();
+        assert!(
+            !json.contains("last_error"),
+            "Expected last error to be omitted from JSON: {json}"
+        );
+    }
+
+    #[test]
+    fn managed_tunnel_info_should_omit_stats_until_traffic_has_passed() {
+        let json = serde_json::to_string(&make_tunnel_info()).unwrap();
+        assert!(
+            !json.contains("stats"),
+            "Expected stats to be omitted from JSON: {json}"
+        );
+    }
+
+    #[test]
+    fn managed_tunnel_info_should_preserve_stats_through_json_roundtrip() {
+        let info = ManagedTunnelInfo {
+            stats: TunnelStats {
+                bytes_out: 100,
+                bytes_in: 2048,
+                active_connections: 2,
+                total_connections: 5,
+                last_activity: Some(1700000000),
+            },
+            ..make_tunnel_info()
+        };
+        let json = serde_json::to_string(&info).unwrap();
+        let deserialized: ManagedTunnelInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, info);
+    }
+
+    #[test]
+    fn managed_tunnel_state_should_display_as_snake_case() {
+        assert_eq!(ManagedTunnelState::Active.to_string(), "active");
+        assert_eq!(ManagedTunnelState::Reconnecting.to_string(), "reconnecting");
+        assert_eq!(ManagedTunnelState::Failed.to_string(), "failed");
+    }
+
     #[test]
     fn managed_tunnel_info_clone_should_produce_equal_value() {
         let info = make_tunnel_info();
@@ -224,6 +450,12 @@ mod tests {
                 bind_port: 8080,
                 remote_host: "host-a".to_string(),
                 remote_port: 80,
+                protocol: TunnelProtocol::Tcp,
+                bind_path: None,
+                remote_path: None,
+                state: ManagedTunnelState::Active,
+                last_error: None,
+                stats: TunnelStats::default(),
             },
             ManagedTunnelInfo {
                 id: 2,
@@ -232,6 +464,12 @@ mod tests {
                 bind_port: 3306,
                 remote_host: "host-b".to_string(),
                 remote_port: 3306,
+                protocol: TunnelProtocol::Tcp,
+                bind_path: None,
+                remote_path: None,
+                state: ManagedTunnelState::Active,
+                last_error: None,
+                stats: TunnelStats::default(),
             },
         ];
         let response = ManagerResponse::ManagedTunnels {
@@ -282,6 +520,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn port_forwarded_should_serialize_and_deserialize_via_json() {
+        let response = ManagerResponse::PortForwarded {
+            connection_id: 7,
+            id: 3,
+            remote_host: "127.0.0.1".to_string(),
+            remote_port: 3000,
+            local_port: 3000,
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(
+            json.contains("\"port_forwarded\""),
+            "Expected snake_case variant tag in JSON: {json}"
+        );
+
+        let deserialized: ManagerResponse = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            ManagerResponse::PortForwarded {
+                connection_id,
+                id,
+                remote_host,
+                remote_port,
+                local_port,
+            } => {
+                assert_eq!(connection_id, 7);
+                assert_eq!(id, 3);
+                assert_eq!(remote_host, "127.0.0.1");
+                assert_eq!(remote_port, 3000);
+                assert_eq!(local_port, 3000);
+            }
+            other => panic!("Expected PortForwarded, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn port_forward_closed_should_serialize_and_deserialize_via_json() {
+        let response = ManagerResponse::PortForwardClosed {
+            connection_id: 7,
+            id: 3,
+            remote_port: 3000,
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(
+            json.contains("\"port_forward_closed\""),
+            "Expected snake_case variant tag in JSON: {json}"
+        );
+
+        let deserialized: ManagerResponse = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            ManagerResponse::PortForwardClosed {
+                connection_id,
+                id,
+                remote_port,
+            } => {
+                assert_eq!(connection_id, 7);
+                assert_eq!(id, 3);
+                assert_eq!(remote_port, 3000);
+            }
+            other => panic!("Expected PortForwardClosed, got {other:?}"),
+        }
+    }
+
     #[test]
     fn managed_tunnel_started_should_reject_unknown_fields() {
         let json = r#"{"type":"managed_tunnel_started","id":1,"port":8080,"extra":"bad"}"#;
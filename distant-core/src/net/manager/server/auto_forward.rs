@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::Arc;
+
+use log::*;
+use tokio::sync::{RwLock, broadcast};
+use tokio::task::JoinHandle;
+use tokio::time::{self, MissedTickBehavior};
+
+use crate::client::ChannelExt;
+use crate::net::common::ConnectionId;
+use crate::net::manager::data::{ManagedTunnelId, ManagerResponse};
+use crate::protocol::ListeningPort;
+
+use super::{
+    AutoForwardConfig, InternalRawChannel, ManagedTunnel, ManagerConnection, StreamAddr,
+    start_forward_tunnel,
+};
+
+/// Host used by the loopback interface on the local machine for forwarded ports
+const LOCAL_HOST: &str = "127.0.0.1";
+
+/// Watches a connection for ports that remote processes start listening on, creating a forward
+/// managed tunnel for each new port and closing it once the port stops being listened on.
+///
+/// Ports that are already being listened on when the watch starts are left alone, as are ports
+/// that belong to one of the manager's own tunnels so that a server running on the same machine
+/// as the manager does not see its forwarded ports as new ones.
+pub(super) struct AutoForwarder {
+    pub config: AutoForwardConfig,
+    pub connection_id: ConnectionId,
+    pub connections: Arc<RwLock<HashMap<ConnectionId, ManagerConnection>>>,
+    pub managed_tunnels: Arc<RwLock<HashMap<ManagedTunnelId, ManagedTunnel>>>,
+    pub events: broadcast::Sender<ManagerResponse>,
+}
+
+impl AutoForwarder {
+    /// Spawns a task that polls the connection until it is removed from the manager.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(self.run())
+    }
+
+    async fn run(self) {
+        let id = self.connection_id;
+        let Some(internal) = self.open_internal_channel().await else {
+            return;
+        };
+        let (mut channel, manager_channel) = internal.into_parts();
+
+        // Ports listened on before the watch began are not the ones a user is waiting on, so
+        // they form the baseline rather than being forwarded
+        let mut known: HashSet<u16> = match channel.listening_ports().await {
+            Ok(ports) => ports.into_iter().map(|p| p.port).collect(),
+            Err(x) => {
+                if x.kind() == io::ErrorKind::Unsupported {
+                    info!("[Conn {id}] Server does not support listing ports, so not forwarding");
+                } else {
+                    error!("[Conn {id}] Failed to list listening ports: {x}");
+                }
+                let _ = manager_channel.close();
+                return;
+            }
+        };
+        let mut forwarded: HashMap<u16, ManagedTunnelId> = HashMap::new();
+
+        // A zero period would make the interval panic
+        let period = if self.config.interval.is_zero() {
+            AutoForwardConfig::DEFAULT_INTERVAL
+        } else {
+            self.config.interval
+        };
+        let mut interval = time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            if !self.connections.read().await.contains_key(&id) {
+                debug!("[Conn {id}] Connection is gone, so no longer watching ports");
+                break;
+            }
+
+            let ports = match channel.listening_ports().await {
+                Ok(ports) => ports,
+                Err(x) => {
+                    debug!("[Conn {id}] Failed to list listening ports: {x}");
+                    continue;
+                }
+            };
+
+            let listening: HashSet<u16> = ports.iter().map(|p| p.port).collect();
+            known.retain(|port| listening.contains(port));
+
+            let closed: Vec<(u16, ManagedTunnelId)> = forwarded
+                .iter()
+                .filter(|(port, _)| !listening.contains(port))
+                .map(|(port, tid)| (*port, *tid))
+                .collect();
+            for (port, tid) in closed {
+                forwarded.remove(&port);
+                self.close(port, tid).await;
+            }
+
+            for port in ports {
+                if !known.insert(port.port) || !self.config.allows(port.port) {
+                    continue;
+                }
+
+                let owned_by_tunnel = self
+                    .managed_tunnels
+                    .read()
+                    .await
+                    .values()
+                    .any(|t| t.info.bind_port == port.port);
+                if owned_by_tunnel {
+                    trace!("[Conn {id}] Port {} belongs to a tunnel", port.port);
+                    continue;
+                }
+
+                match self.forward(&port).await {
+                    Ok(tid) => {
+                        forwarded.insert(port.port, tid);
+                    }
+                    Err(x) => error!("[Conn {id}] Failed to forward port {}: {x}", port.port),
+                }
+            }
+        }
+
+        let _ = manager_channel.close();
+    }
+
+    async fn open_internal_channel(&self) -> Option<InternalRawChannel> {
+        let id = self.connection_id;
+        match self.connections.read().await.get(&id) {
+            Some(connection) => match InternalRawChannel::open(connection) {
+                Ok(internal) => Some(internal),
+                Err(x) => {
+                    error!("[Conn {id}] Failed to open channel to watch ports: {x}");
+                    None
+                }
+            },
+            None => None,
+        }
+    }
+
+    /// Forwards the remote `port` to the same port on the local machine, or to any free local
+    /// port if that one is taken.
+    async fn forward(&self, port: &ListeningPort) -> io::Result<ManagedTunnelId> {
+        let id = self.connection_id;
+        let remote_host = remote_host(port);
+
+        let tid = ManagedTunnel::next_id();
+        let mut result = Err(io::Error::from(io::ErrorKind::NotConnected));
+        for bind_port in [port.port, 0] {
+            let Some(internal) = self.open_internal_channel().await else {
+                break;
+            };
+            result = start_forward_tunnel(
+                internal,
+                tid,
+                id,
+                StreamAddr::new(LOCAL_HOST, bind_port, None),
+                StreamAddr::new(remote_host.clone(), port.port, None),
+            )
+            .await;
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        let (managed, local_port) = result?;
+        self.managed_tunnels.write().await.insert(tid, managed);
+        info!(
+            "[Conn {id}] Forwarding remote port {} to local port {local_port} with tunnel {tid}",
+            port.port
+        );
+
+        let _ = self.events.send(ManagerResponse::PortForwarded {
+            connection_id: id,
+            id: tid,
+            remote_host,
+            remote_port: port.port,
+            local_port,
+        });
+        Ok(tid)
+    }
+
+    /// Closes the tunnel for a remote `port` that is no longer listened on, unless it was
+    /// already closed some other way.
+    async fn close(&self, port: u16, tid: ManagedTunnelId) {
+        let id = self.connection_id;
+        let Some(tunnel) = self.managed_tunnels.write().await.remove(&tid) else {
+            return;
+        };
+
+        tunnel.abort();
+        info!("[Conn {id}] Remote port {port} closed, so closed tunnel {tid}");
+
+        let _ = self.events.send(ManagerResponse::PortForwardClosed {
+            connection_id: id,
+            id: tid,
+            remote_port: port,
+        });
+    }
+}
+
+/// Returns the host that the remote server should connect to in order to reach `port`, using
+/// the loopback address for sockets that accept connections on every interface.
+fn remote_host(port: &ListeningPort) -> String {
+    match port.address.as_str() {
+        "0.0.0.0" | "*" => "127.0.0.1".to_string(),
+        "::" => "::1".to_string(),
+        address => address.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(address: &str) -> ListeningPort {
+        ListeningPort {
+            address: address.to_string(),
+            port: 3000,
+        }
+    }
+
+    #[test]
+    fn remote_host_should_use_loopback_for_unspecified_addresses() {
+        assert_eq!(remote_host(&port("0.0.0.0")), "127.0.0.1");
+        assert_eq!(remote_host(&port("*")), "127.0.0.1");
+        assert_eq!(remote_host(&port("::")), "::1");
+    }
+
+    #[test]
+    fn remote_host_should_use_bound_address_otherwise() {
+        assert_eq!(remote_host(&port("127.0.0.1")), "127.0.0.1");
+        assert_eq!(remote_host(&port("::1")), "::1");
+        assert_eq!(remote_host(&port("10.0.0.5")), "10.0.0.5");
+    }
+}
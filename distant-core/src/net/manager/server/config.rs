@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
+use super::ManagedTunnelSpec;
+use crate::net::common::PortRange;
 use crate::plugin::Plugin;
 
 /// Configuration settings for a manager.
@@ -20,6 +23,13 @@ pub struct Config {
     /// Plugins keyed by scheme. Each scheme maps to a plugin that handles
     /// both launch and connect for that scheme.
     pub plugins: HashMap<String, Arc<dyn Plugin>>,
+
+    /// If provided, the manager watches each connection for ports that remote processes start
+    /// listening on and automatically forwards them to the local machine
+    pub auto_forward: Option<AutoForwardConfig>,
+
+    /// Tunnels to start whenever the manager connects to a destination, keyed by destination
+    pub tunnels: HashMap<String, Vec<ManagedTunnelSpec>>,
 }
 
 impl Default for Config {
@@ -34,6 +44,77 @@ impl Default for Config {
             connection_buffer_size: 100,
             user: false,
             plugins: HashMap::new(),
+            auto_forward: None,
+            tunnels: HashMap::new(),
+        }
+    }
+}
+
+/// Configuration settings for automatically forwarding remote listening ports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AutoForwardConfig {
+    /// How often to check each connection for new listening ports
+    pub interval: Duration,
+
+    /// Ports that are eligible for forwarding; an empty list allows every port
+    pub allow: Vec<PortRange>,
+
+    /// Ports that are never forwarded, taking precedence over `allow`
+    pub deny: Vec<PortRange>,
+}
+
+impl AutoForwardConfig {
+    /// Default time between checks for new listening ports.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+
+    /// Returns true if `port` should be forwarded based on the allow and deny lists.
+    pub fn allows(&self, port: u16) -> bool {
+        let allowed = self.allow.is_empty() || self.allow.iter().any(|r| r.contains(port));
+        let denied = self.deny.iter().any(|r| r.contains(port));
+        allowed && !denied
+    }
+}
+
+impl Default for AutoForwardConfig {
+    fn default() -> Self {
+        Self {
+            interval: Self::DEFAULT_INTERVAL,
+            allow: Vec::new(),
+            deny: Vec::new(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_should_permit_every_port_when_lists_are_empty() {
+        let config = AutoForwardConfig::default();
+        assert!(config.allows(1));
+        assert!(config.allows(65535));
+    }
+
+    #[test]
+    fn allows_should_only_permit_ports_in_allow_list_when_not_empty() {
+        let config = AutoForwardConfig {
+            allow: vec![PortRange::from(3000..=3999), PortRange::single(8080)],
+            ..Default::default()
+        };
+        assert!(config.allows(3000));
+        assert!(config.allows(8080));
+        assert!(!config.allows(22));
+    }
+
+    #[test]
+    fn allows_should_prefer_deny_list_over_allow_list() {
+        let config = AutoForwardConfig {
+            allow: vec![PortRange::from(3000..=3999)],
+            deny: vec![PortRange::single(3306)],
+            ..Default::default()
+        };
+        assert!(config.allows(3000));
+        assert!(!config.allows(3306));
+    }
+}
@@ -1,14 +1,17 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::{fmt, io};
 
 use log::*;
+use serde::Deserialize;
 use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
 
-use crate::net::client::{Mailbox, UntypedClient};
-use crate::net::common::{ConnectionId, Map, UntypedRequest, UntypedResponse};
+use crate::net::client::{ConnectionState, ConnectionWatcher, Mailbox, UntypedClient};
+use crate::net::common::{ConnectionId, Map, UntypedRequest, UntypedResponse, utils};
 use crate::net::manager::data::{ManagerChannelId, ManagerResponse};
 use crate::net::server::ServerReply;
+use crate::protocol;
 
 /// Represents a connection a distant manager has with some distant-compatible server
 pub struct ManagerConnection {
@@ -17,6 +20,7 @@ pub struct ManagerConnection {
     pub destination: String,
     pub options: Map,
     tx: mpsc::UnboundedSender<Action>,
+    watcher: ConnectionWatcher,
 
     action_task: JoinHandle<()>,
     request_task: JoinHandle<()>,
@@ -75,6 +79,13 @@ impl ManagerConnection {
         // never triggering!
         client.shutdown_on_drop(true);
 
+        // NOTE: Track the state of the connection as the client detects a dead peer and goes
+        //       through its reconnect strategy, so it can be reported to manager clients
+        let watcher = client.clone_connection_watcher();
+        watcher.on_change(move |state| {
+            info!("[Conn {connection_id}] Connection state changed to {state}");
+        });
+
         let (request_tx, request_rx) = mpsc::unbounded_channel();
         let action_task = tokio::spawn(action_task(connection_id, rx, request_tx));
         let response_task = tokio::spawn(response_task(
@@ -89,12 +100,23 @@ impl ManagerConnection {
             destination,
             options,
             tx,
+            watcher,
             action_task,
             request_task,
             response_task,
         })
     }
 
+    /// Returns the current state of the connection with the server.
+    pub fn state(&self) -> ConnectionState {
+        self.watcher.last()
+    }
+
+    /// Returns a watcher over the state of the connection with the server.
+    pub fn clone_connection_watcher(&self) -> ConnectionWatcher {
+        self.watcher.clone()
+    }
+
     pub fn open_channel(&self, reply: ServerReply<ManagerResponse>) -> io::Result<ManagerChannel> {
         let channel_id = rand::random();
         self.tx
@@ -275,6 +297,19 @@ async fn action_task(
                 // the response containing this in the origin id
                 req.set_id(format!("{id}_{}", req.id));
 
+                // Cancel requests name another request sent over this channel, so combine that
+                // request's id with the channel id in the same way
+                if let Some(request_id) = cancel_request_id(&req) {
+                    let payload = protocol::Msg::Single(protocol::Request::Cancel {
+                        request_id: format!("{id}_{request_id}"),
+                    });
+
+                    match utils::serialize_to_vec(&payload) {
+                        Ok(payload) => req.payload = Cow::Owned(payload),
+                        Err(x) => error!("[Conn {id}] {x}"),
+                    }
+                }
+
                 if let Err(x) = tx.send(req) {
                     error!("[Conn {id}] {x}");
                 }
@@ -285,6 +320,22 @@ async fn action_task(
     trace!("[Conn {id}] Manager action task closed");
 }
 
+/// Returns the id of the request that `req` cancels, if it is a cancel request. Only the fields
+/// of a cancel request are decoded, so the rest of any other payload is skipped over.
+fn cancel_request_id(req: &UntypedRequest) -> Option<String> {
+    #[derive(Deserialize)]
+    struct CancelPayload {
+        #[serde(rename = "type")]
+        ty: String,
+        request_id: String,
+    }
+
+    utils::deserialize_from_slice::<CancelPayload>(&req.payload)
+        .ok()
+        .filter(|payload| payload.ty == "cancel")
+        .map(|payload| payload.request_id)
+}
+
 #[cfg(test)]
 mod tests {
     //! Tests for ManagerChannel (send, close, clone), ManagerConnection (spawn, open_channel,
@@ -382,6 +433,38 @@ mod tests {
         let _ = conn.id;
     }
 
+    #[test_log::test(tokio::test)]
+    async fn manager_connection_state_starts_connected() {
+        let (client, _server) = make_untyped_client();
+
+        let conn = ManagerConnection::spawn("scheme://host", Map::new(), client)
+            .await
+            .unwrap();
+
+        assert_eq!(conn.state(), ConnectionState::Connected);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn manager_connection_state_reflects_lost_connection() {
+        let (client, server) = make_untyped_client();
+
+        let conn = ManagerConnection::spawn("scheme://host", Map::new(), client)
+            .await
+            .unwrap();
+
+        // Dropping the server side with the default fail strategy disconnects the client
+        drop(server);
+
+        let mut watcher = conn.watcher.clone();
+        while !conn.state().is_disconnected() {
+            tokio::time::timeout(std::time::Duration::from_secs(5), watcher.next())
+                .await
+                .expect("Timed out waiting for state change")
+                .expect("Watcher closed before disconnect");
+        }
+        assert_eq!(conn.state(), ConnectionState::Disconnected);
+    }
+
     #[test_log::test(tokio::test)]
     async fn manager_connection_open_channel_returns_channel_with_random_id() {
         let (client, _server) = make_untyped_client();
@@ -497,6 +580,71 @@ mod tests {
         assert!(!ids.contains(&channel_id));
     }
 
+    #[test_log::test(tokio::test)]
+    async fn manager_connection_channel_send_combines_channel_id_with_cancelled_request_id() {
+        let (client, mut server) = make_untyped_client();
+        let conn = ManagerConnection::spawn("scheme://host", Map::new(), client)
+            .await
+            .unwrap();
+
+        let (reply_tx, _reply_rx) = mpsc::unbounded_channel();
+        let reply = ServerReply {
+            origin_id: "test".to_string(),
+            tx: reply_tx,
+        };
+        let channel = conn.open_channel(reply).unwrap();
+        let channel_id = channel.id();
+
+        let req =
+            crate::net::common::Request::new(protocol::Msg::Single(protocol::Request::Cancel {
+                request_id: "req-1".to_string(),
+            }));
+        let cancel_id = req.id.clone();
+        channel
+            .send(req.to_untyped_request().unwrap().into_owned())
+            .unwrap();
+
+        let frame = server.read_frame().await.unwrap().unwrap();
+        let req = UntypedRequest::from_slice(frame.as_item()).unwrap();
+        assert_eq!(req.id, format!("{channel_id}_{cancel_id}"));
+
+        let req = req
+            .to_typed_request::<protocol::Msg<protocol::Request>>()
+            .unwrap();
+        assert_eq!(
+            req.payload,
+            protocol::Msg::Single(protocol::Request::Cancel {
+                request_id: format!("{channel_id}_req-1"),
+            })
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn manager_connection_channel_send_leaves_payload_of_other_requests_untouched() {
+        let (client, mut server) = make_untyped_client();
+        let conn = ManagerConnection::spawn("scheme://host", Map::new(), client)
+            .await
+            .unwrap();
+
+        let (reply_tx, _reply_rx) = mpsc::unbounded_channel();
+        let reply = ServerReply {
+            origin_id: "test".to_string(),
+            tx: reply_tx,
+        };
+        let channel = conn.open_channel(reply).unwrap();
+
+        let req = crate::net::common::Request::new(protocol::Msg::Single(
+            protocol::Request::CancelSearch { id: 5 },
+        ));
+        let req = req.to_untyped_request().unwrap().into_owned();
+        let payload = req.payload.clone();
+        channel.send(req).unwrap();
+
+        let frame = server.read_frame().await.unwrap().unwrap();
+        let req = UntypedRequest::from_slice(frame.as_item()).unwrap();
+        assert_eq!(req.payload, payload);
+    }
+
     #[test_log::test(tokio::test)]
     async fn manager_connection_abort_stops_tasks() {
         let (client, _server) = make_untyped_client();
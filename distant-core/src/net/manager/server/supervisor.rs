@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use log::*;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+use crate::net::client::{ConnectionState, ConnectionWatcher};
+use crate::net::common::ConnectionId;
+use crate::net::manager::data::{ManagedTunnelId, ManagedTunnelState};
+
+use super::ManagedTunnel;
+
+/// Error recorded for tunnels whose connection was lost for good
+const CONNECTION_LOST: &str = "Lost connection to server";
+
+/// Follows the state of a connection, reflecting it in the state of the connection's managed
+/// tunnels.
+///
+/// Once the connection is lost for good, its tunnels are stopped. Those that can be started
+/// again are kept in the failed state until the manager connects to the same destination,
+/// while the rest are removed.
+pub(super) struct TunnelSupervisor {
+    pub connection_id: ConnectionId,
+    pub watcher: ConnectionWatcher,
+    pub managed_tunnels: Arc<RwLock<HashMap<ManagedTunnelId, ManagedTunnel>>>,
+}
+
+impl TunnelSupervisor {
+    /// Spawns a task that follows the connection until it is lost or dropped.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(self.run())
+    }
+
+    async fn run(mut self) {
+        let id = self.connection_id;
+        while let Some(state) = self.watcher.next().await {
+            let mut tunnels = self.managed_tunnels.write().await;
+            update_tunnel_states(&mut tunnels, id, state);
+
+            if state.is_disconnected() {
+                break;
+            }
+        }
+
+        trace!("[Conn {id}] No longer supervising tunnels");
+    }
+}
+
+/// Updates the tunnels of the connection with the specified `id` to match its new `state`.
+fn update_tunnel_states(
+    tunnels: &mut HashMap<ManagedTunnelId, ManagedTunnel>,
+    id: ConnectionId,
+    state: ConnectionState,
+) {
+    let connection_tunnels = tunnels.values_mut().filter(|t| t.connection_id == id);
+
+    match state {
+        ConnectionState::Reconnecting => {
+            for tunnel in connection_tunnels {
+                if tunnel.info.state == ManagedTunnelState::Active {
+                    debug!("[Conn {id}] Tunnel {} is waiting on reconnect", tunnel.id);
+                    tunnel.info.state = ManagedTunnelState::Reconnecting;
+                }
+            }
+        }
+        ConnectionState::Connected => {
+            for tunnel in connection_tunnels {
+                if tunnel.info.state == ManagedTunnelState::Reconnecting {
+                    debug!("[Conn {id}] Tunnel {} is active again", tunnel.id);
+                    tunnel.info.state = ManagedTunnelState::Active;
+                }
+            }
+        }
+        ConnectionState::Disconnected => {
+            let mut lost = Vec::new();
+            for tunnel in connection_tunnels {
+                if tunnel.origin.is_some() {
+                    warn!("[Conn {id}] Tunnel {} failed: {CONNECTION_LOST}", tunnel.id);
+                    tunnel.fail(CONNECTION_LOST);
+                } else {
+                    lost.push(tunnel.id);
+                }
+            }
+
+            for tid in lost {
+                if let Some(tunnel) = tunnels.remove(&tid) {
+                    debug!("[Conn {id}] Removing tunnel {tid}");
+                    tunnel.abort();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::manager::server::{ManagedTunnelOrigin, ManagedTunnelSpec};
+    use crate::protocol::TunnelProtocol;
+
+    fn origin() -> ManagedTunnelOrigin {
+        ManagedTunnelOrigin {
+            destination: "ssh://host".to_string(),
+            spec: ManagedTunnelSpec::Forward {
+                bind_port: 8080,
+                remote_host: "127.0.0.1".to_string(),
+                remote_port: 80,
+                protocol: TunnelProtocol::Tcp,
+                bind_path: None,
+                remote_path: None,
+            },
+        }
+    }
+
+    /// Creates a tunnel for connection `connection_id` in the given `state`.
+    fn tunnel(
+        id: ManagedTunnelId,
+        connection_id: ConnectionId,
+        state: ManagedTunnelState,
+        restorable: bool,
+    ) -> ManagedTunnel {
+        let error = std::io::Error::other("not started");
+        let mut tunnel = ManagedTunnel::failed(id, connection_id, origin(), &error);
+        tunnel.info.state = state;
+        tunnel.info.last_error = None;
+        if !restorable {
+            tunnel.origin = None;
+        }
+        tunnel
+    }
+
+    fn states(
+        tunnels: &HashMap<ManagedTunnelId, ManagedTunnel>,
+    ) -> HashMap<ManagedTunnelId, ManagedTunnelState> {
+        tunnels.iter().map(|(id, t)| (*id, t.info.state)).collect()
+    }
+
+    #[test]
+    fn update_tunnel_states_should_mark_active_tunnels_of_connection_as_reconnecting() {
+        let mut tunnels = HashMap::from([
+            (1, tunnel(1, 10, ManagedTunnelState::Active, true)),
+            (2, tunnel(2, 10, ManagedTunnelState::Failed, true)),
+            (3, tunnel(3, 20, ManagedTunnelState::Active, true)),
+        ]);
+
+        update_tunnel_states(&mut tunnels, 10, ConnectionState::Reconnecting);
+
+        assert_eq!(
+            states(&tunnels),
+            HashMap::from([
+                (1, ManagedTunnelState::Reconnecting),
+                (2, ManagedTunnelState::Failed),
+                (3, ManagedTunnelState::Active),
+            ])
+        );
+    }
+
+    #[test]
+    fn update_tunnel_states_should_mark_reconnecting_tunnels_as_active_once_connected() {
+        let mut tunnels = HashMap::from([
+            (1, tunnel(1, 10, ManagedTunnelState::Reconnecting, true)),
+            (2, tunnel(2, 10, ManagedTunnelState::Failed, true)),
+        ]);
+
+        update_tunnel_states(&mut tunnels, 10, ConnectionState::Connected);
+
+        assert_eq!(
+            states(&tunnels),
+            HashMap::from([
+                (1, ManagedTunnelState::Active),
+                (2, ManagedTunnelState::Failed),
+            ])
+        );
+    }
+
+    #[test]
+    fn update_tunnel_states_should_fail_restorable_tunnels_and_remove_others_once_disconnected() {
+        let mut tunnels = HashMap::from([
+            (1, tunnel(1, 10, ManagedTunnelState::Reconnecting, true)),
+            (2, tunnel(2, 10, ManagedTunnelState::Active, false)),
+            (3, tunnel(3, 20, ManagedTunnelState::Active, false)),
+        ]);
+
+        update_tunnel_states(&mut tunnels, 10, ConnectionState::Disconnected);
+
+        assert_eq!(
+            states(&tunnels),
+            HashMap::from([
+                (1, ManagedTunnelState::Failed),
+                (3, ManagedTunnelState::Active),
+            ])
+        );
+        assert_eq!(
+            tunnels[&1].info.last_error.as_deref(),
+            Some(CONNECTION_LOST)
+        );
+    }
+}
@@ -1,83 +1,190 @@
+mod http;
+mod socks;
+mod spec;
+mod stream;
+
 use std::io;
+use std::net::IpAddr;
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use log::*;
-use tokio::net::TcpListener;
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
+use tokio::time;
 
-use crate::client::{self, ChannelExt, RemoteTunnelListener};
-use crate::net::common::ConnectionId;
-use crate::net::manager::data::{ManagedTunnelId, ManagedTunnelInfo};
-use crate::protocol::TunnelDirection;
+use crate::client::{
+    self, Channel, ChannelExt, RemoteTunnelListener, RemoteTunnelReader, RemoteTunnelWriter,
+};
+use crate::constants::UDP_TUNNEL_DATAGRAM_BUFFER_SIZE;
+use crate::net::common::{ConnectionId, UdpDemux, UdpPeer, UdpTarget};
+use crate::net::manager::data::{
+    HttpRoute, ManagedTunnelId, ManagedTunnelInfo, ManagedTunnelState,
+};
+use crate::protocol::{TunnelCounters, TunnelDirection, TunnelProtocol, TunnelStats};
 
 use super::InternalRawChannel;
 use super::connection::ManagerChannel;
+use http::RequestReader;
+use socks::SocksRequest;
+pub use spec::{ManagedTunnelOrigin, ManagedTunnelSpec};
+pub use stream::StreamAddr;
+use stream::{CountedStream, LocalListener};
 
 static NEXT_MANAGED_TUNNEL_ID: AtomicU32 = AtomicU32::new(1);
 
+/// Time that a SOCKS client has to send its request after connecting
+const SOCKS_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time that an HTTP client has to send its first request after connecting
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time that the upstream of an HTTP client that has stopped sending requests can go without
+/// sending anything before the connection is closed, which is the only way to tell that the
+/// response to the client's last request is over without parsing responses
+const HTTP_DRAIN_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// A tunnel whose lifecycle is managed by the manager process.
 ///
 /// The tunnel's relay loop runs in a spawned task. Dropping the `ManagedTunnel`
 /// does **not** abort the task — call [`abort`](Self::abort) explicitly.
+///
+/// A tunnel that has failed has no relay task, and is kept only so that it can be
+/// listed and started again from its [`origin`](Self::origin).
 pub struct ManagedTunnel {
     pub id: ManagedTunnelId,
     pub connection_id: ConnectionId,
     pub info: ManagedTunnelInfo,
-    task: JoinHandle<()>,
-    manager_channel: ManagerChannel,
+
+    /// What the tunnel was started from, or `None` if it is not started again after its
+    /// connection is lost
+    pub origin: Option<ManagedTunnelOrigin>,
+
+    task: Option<JoinHandle<()>>,
+    manager_channel: Option<ManagerChannel>,
+    counters: Arc<TunnelCounters>,
 }
 
 impl ManagedTunnel {
+    /// Returns a new id for a managed tunnel, unique within this process.
+    pub fn next_id() -> ManagedTunnelId {
+        NEXT_MANAGED_TUNNEL_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Creates a tunnel in the failed state for one that could not be started from `origin`
+    /// because of `error`.
+    pub fn failed(
+        id: ManagedTunnelId,
+        connection_id: ConnectionId,
+        origin: ManagedTunnelOrigin,
+        error: &io::Error,
+    ) -> Self {
+        let mut info = origin.spec.describe(id, connection_id);
+        info.state = ManagedTunnelState::Failed;
+        info.last_error = Some(error.to_string());
+
+        Self {
+            id,
+            connection_id,
+            info,
+            origin: Some(origin),
+            task: None,
+            manager_channel: None,
+            counters: TunnelCounters::new(),
+        }
+    }
+
+    /// Creates a tunnel whose relay loop is running in `task`.
+    fn running(
+        id: ManagedTunnelId,
+        connection_id: ConnectionId,
+        info: ManagedTunnelInfo,
+        task: JoinHandle<()>,
+        manager_channel: ManagerChannel,
+        counters: Arc<TunnelCounters>,
+    ) -> Self {
+        Self {
+            id,
+            connection_id,
+            info,
+            origin: None,
+            task: Some(task),
+            manager_channel: Some(manager_channel),
+            counters,
+        }
+    }
+
+    /// Returns information about the tunnel, including the traffic that has passed through it.
+    pub fn to_info(&self) -> ManagedTunnelInfo {
+        ManagedTunnelInfo {
+            stats: self.counters.stats(),
+            ..self.info.clone()
+        }
+    }
+
     /// Aborts the relay task and closes the underlying manager channel.
     pub fn abort(&self) {
-        self.task.abort();
-        let _ = self.manager_channel.close();
+        if let Some(task) = self.task.as_ref() {
+            task.abort();
+        }
+        if let Some(manager_channel) = self.manager_channel.as_ref() {
+            let _ = manager_channel.close();
+        }
+    }
+
+    /// Aborts the tunnel and marks it as failed because of `error`.
+    pub fn fail(&mut self, error: impl ToString) {
+        self.abort();
+        self.task = None;
+        self.manager_channel = None;
+        self.info.state = ManagedTunnelState::Failed;
+        self.info.last_error = Some(error.to_string());
     }
 }
 
-/// Starts a forward tunnel (local TCP listener → remote target) inside the
-/// manager process.
+/// Starts a forward tunnel (local listener → remote target) inside the manager
+/// process, where either end is a TCP address or a Unix socket path.
 ///
 /// The caller should open the [`InternalRawChannel`] while briefly holding the
-/// connection lock, then pass it here for the async setup.
+/// connection lock, then pass it here with the tunnel's `id` for the async setup.
 ///
 /// Returns the managed tunnel and the actual bound local port (which may differ
-/// from `bind_port` when `0` is passed).
+/// from the port of `bind` when `0` is passed, and is `0` for a Unix socket).
 ///
 /// # Errors
 ///
-/// Returns an error if binding the local TCP listener fails.
+/// Returns an error if binding the local listener fails.
 pub async fn start_forward_tunnel(
     internal: InternalRawChannel,
+    id: ManagedTunnelId,
     connection_id: ConnectionId,
-    bind_port: u16,
-    remote_host: String,
-    remote_port: u16,
+    bind: StreamAddr,
+    remote: StreamAddr,
 ) -> io::Result<(ManagedTunnel, u16)> {
     let (mut channel, manager_channel) = internal.into_parts();
 
-    let listener = TcpListener::bind(format!("127.0.0.1:{bind_port}"))
+    let (listener, bind) = LocalListener::bind(&bind)
         .await
-        .map_err(|e| io::Error::other(format!("Failed to bind on port {bind_port}: {e}")))?;
-    let actual_port = listener.local_addr()?.port();
+        .map_err(|e| io::Error::other(format!("Failed to bind on {bind}: {e}")))?;
+    let actual_port = bind.port();
 
-    let id = NEXT_MANAGED_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
-    let host = remote_host.clone();
-    let port = remote_port;
+    let target = remote.clone();
+    let counters = TunnelCounters::new();
+    let task_counters = Arc::clone(&counters);
 
     let task = tokio::spawn(async move {
         loop {
             match listener.accept().await {
-                Ok((tcp_stream, peer_addr)) => {
+                Ok((stream, peer_addr)) => {
                     debug!("[ManagedTunnel {id}] Accepted connection from {peer_addr}");
-                    let host = host.clone();
 
-                    let mut tunnel = match channel.tunnel_open(host.clone(), port).await {
+                    let mut tunnel = match target.open_remote(&mut channel).await {
                         Ok(t) => t,
                         Err(e) => {
-                            debug!(
-                                "[ManagedTunnel {id}] Failed to open tunnel to {host}:{port}: {e}"
-                            );
+                            debug!("[ManagedTunnel {id}] Failed to open tunnel to {target}: {e}");
                             continue;
                         }
                     };
@@ -91,9 +198,10 @@ pub async fn start_forward_tunnel(
                         None => continue,
                     };
 
+                    let stream =
+                        CountedStream::new(stream, &task_counters, TunnelDirection::Forward);
                     tokio::spawn(async move {
-                        if let Err(e) =
-                            client::relay_tcp_to_tunnel(tcp_stream, writer, reader).await
+                        if let Err(e) = client::relay_stream_to_tunnel(stream, writer, reader).await
                         {
                             debug!("Forward relay finished: {e}");
                         }
@@ -108,6 +216,601 @@ pub async fn start_forward_tunnel(
         }
     });
 
+    let info = stream_tunnel_info(id, connection_id, TunnelDirection::Forward, &bind, remote);
+
+    Ok((
+        ManagedTunnel::running(id, connection_id, info, task, manager_channel, counters),
+        actual_port,
+    ))
+}
+
+/// Starts a reverse tunnel (remote listener → local target) inside the manager
+/// process, where either end is a TCP address or a Unix socket path.
+///
+/// The caller should open the [`InternalRawChannel`] while briefly holding the
+/// connection lock, then pass it here with the tunnel's `id` for the async setup.
+///
+/// Returns the managed tunnel and the actual remote port (which may differ
+/// from the port of `remote` when `0` is passed, and is `0` for a Unix socket).
+///
+/// # Errors
+///
+/// Returns an error if the remote side fails to set up the listener.
+pub async fn start_reverse_tunnel(
+    internal: InternalRawChannel,
+    id: ManagedTunnelId,
+    connection_id: ConnectionId,
+    remote: StreamAddr,
+    local: StreamAddr,
+) -> io::Result<(ManagedTunnel, u16)> {
+    let (channel, manager_channel) = internal.into_parts();
+
+    // Ask the remote to listen on the specified port or socket path
+    let mut listener = remote.listen_remote(channel).await.map_err(|e| {
+        io::Error::other(format!("Failed to start remote listener on {remote}: {e}"))
+    })?;
+
+    let actual_port = listener.port();
+    let target = local.clone();
+    let counters = TunnelCounters::new();
+    let task_counters = Arc::clone(&counters);
+
+    let task = tokio::spawn(async move {
+        loop {
+            let incoming = match listener.next().await {
+                Some(incoming) => incoming,
+                None => {
+                    debug!("[ManagedTunnel {id}] Remote listener closed");
+                    break;
+                }
+            };
+
+            debug!(
+                "[ManagedTunnel {id}] Incoming connection (peer: {:?})",
+                incoming.peer_addr,
+            );
+
+            let target = target.clone();
+            let counters = Arc::clone(&task_counters);
+
+            tokio::spawn(async move {
+                let stream = match target.connect_local().await {
+                    Ok(s) => CountedStream::new(s, &counters, TunnelDirection::Reverse),
+                    Err(e) => {
+                        debug!("Failed to connect to local {target}: {e}");
+                        return;
+                    }
+                };
+
+                if let Err(e) =
+                    client::relay_stream_to_tunnel(stream, incoming.writer, incoming.reader).await
+                {
+                    debug!("Reverse relay finished: {e}");
+                }
+            });
+        }
+
+        let _ = listener.close().await;
+    });
+
+    let remote = match remote {
+        StreamAddr::Tcp { host, .. } => StreamAddr::Tcp {
+            host,
+            port: actual_port,
+        },
+        remote => remote,
+    };
+    let info = stream_tunnel_info(id, connection_id, TunnelDirection::Reverse, &remote, local);
+
+    Ok((
+        ManagedTunnel::running(id, connection_id, info, task, manager_channel, counters),
+        actual_port,
+    ))
+}
+
+/// Describes a stream-oriented tunnel listening on `bind` and connecting to `target`,
+/// reported as a Unix socket tunnel when either end is a socket path.
+fn stream_tunnel_info(
+    id: ManagedTunnelId,
+    connection_id: ConnectionId,
+    direction: TunnelDirection,
+    bind: &StreamAddr,
+    target: StreamAddr,
+) -> ManagedTunnelInfo {
+    let protocol = if bind.is_unix() || target.is_unix() {
+        TunnelProtocol::Unix
+    } else {
+        TunnelProtocol::Tcp
+    };
+
+    ManagedTunnelInfo {
+        id,
+        connection_id,
+        direction,
+        bind_port: bind.port(),
+        remote_host: target.host().to_string(),
+        remote_port: target.port(),
+        protocol,
+        bind_path: bind.path().map(ToString::to_string),
+        remote_path: target.path().map(ToString::to_string),
+        state: ManagedTunnelState::Active,
+        last_error: None,
+        stats: TunnelStats::default(),
+    }
+}
+
+/// Starts a dynamic tunnel (local SOCKS4/SOCKS4a/SOCKS5 proxy → any remote target)
+/// inside the manager process, similar to `ssh -D`.
+///
+/// Each client of the proxy gets its own forward tunnel to the host and port
+/// that it asks for, which the remote server connects to.
+///
+/// The caller should open the [`InternalRawChannel`] while briefly holding the
+/// connection lock, then pass it here with the tunnel's `id` for the async setup.
+///
+/// Returns the managed tunnel and the actual bound local port (which may differ
+/// from `bind_port` when `0` is passed).
+///
+/// # Errors
+///
+/// Returns an error if binding the local TCP listener fails.
+pub async fn start_socks_tunnel(
+    internal: InternalRawChannel,
+    id: ManagedTunnelId,
+    connection_id: ConnectionId,
+    bind_host: String,
+    bind_port: u16,
+) -> io::Result<(ManagedTunnel, u16)> {
+    let (channel, manager_channel) = internal.into_parts();
+
+    let listener = TcpListener::bind((bind_host.as_str(), bind_port))
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to bind on {bind_host}:{bind_port}: {e}")))?;
+    let actual_port = listener.local_addr()?.port();
+    let counters = TunnelCounters::new();
+    let task_counters = Arc::clone(&counters);
+
+    let task = tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((mut tcp_stream, peer_addr)) => {
+                    debug!("[ManagedTunnel {id}] Accepted SOCKS client from {peer_addr}");
+                    let mut channel = channel.clone();
+                    let counters = Arc::clone(&task_counters);
+
+                    // Serve each client in its own task so a slow handshake does not hold up
+                    // the clients behind it
+                    tokio::spawn(async move {
+                        let request = match tokio::time::timeout(
+                            SOCKS_REQUEST_TIMEOUT,
+                            SocksRequest::read(&mut tcp_stream),
+                        )
+                        .await
+                        {
+                            Ok(Ok(request)) => request,
+                            Ok(Err(e)) => {
+                                debug!("[ManagedTunnel {id}] Invalid SOCKS request: {e}");
+                                return;
+                            }
+                            Err(_) => {
+                                debug!("[ManagedTunnel {id}] Timed out waiting on SOCKS request");
+                                return;
+                            }
+                        };
+
+                        let (host, port) = (request.host.as_str(), request.port);
+                        let mut tunnel = match channel.tunnel_open(host, port).await {
+                            Ok(t) => t,
+                            Err(e) => {
+                                debug!(
+                                    "[ManagedTunnel {id}] Failed to open tunnel to {host}:{port}: {e}"
+                                );
+                                let _ = request.reply(&mut tcp_stream, false).await;
+                                return;
+                            }
+                        };
+
+                        let (Some(writer), Some(reader)) =
+                            (tunnel.writer.take(), tunnel.reader.take())
+                        else {
+                            let _ = request.reply(&mut tcp_stream, false).await;
+                            let _ = tunnel.close().await;
+                            return;
+                        };
+
+                        if request.reply(&mut tcp_stream, true).await.is_ok() {
+                            let stream =
+                                CountedStream::new(tcp_stream, &counters, TunnelDirection::Dynamic);
+                            if let Err(e) =
+                                client::relay_stream_to_tunnel(stream, writer, reader).await
+                            {
+                                debug!("SOCKS relay finished: {e}");
+                            }
+                        }
+                        let _ = tunnel.close().await;
+                    });
+                }
+                Err(e) => {
+                    debug!("[ManagedTunnel {id}] Accept error: {e}");
+                    break;
+                }
+            }
+        }
+    });
+
+    let info = ManagedTunnelInfo {
+        id,
+        connection_id,
+        direction: TunnelDirection::Dynamic,
+        bind_port: actual_port,
+        remote_host: String::from("*"),
+        remote_port: 0,
+        protocol: TunnelProtocol::Tcp,
+        bind_path: None,
+        remote_path: None,
+        state: ManagedTunnelState::Active,
+        last_error: None,
+        stats: TunnelStats::default(),
+    };
+
+    Ok((
+        ManagedTunnel::running(id, connection_id, info, task, manager_channel, counters),
+        actual_port,
+    ))
+}
+
+/// Starts an HTTP/1.1 reverse proxy inside the manager process that serves each
+/// of `routes` at `http://NAME.localhost:PORT`, so several remote web apps can
+/// share one local port.
+///
+/// Each client connection gets its own forward tunnel to the target of the route
+/// named by the `Host` of its first request. Requests are passed on with the
+/// `Host` header rewritten to the target and `X-Forwarded-*` headers describing
+/// the original request, and a connection that the target switches to another
+/// protocol (such as a WebSocket) with a `101` response is relayed as-is from
+/// then on, while one whose upgrade is refused is closed after the response. A request on the same connection for another
+/// route closes the connection, so that the client retries it on a new one.
+///
+/// The caller should open the [`InternalRawChannel`] while briefly holding the
+/// connection lock, then pass it here with the tunnel's `id` for the async setup.
+///
+/// Returns the managed tunnel and the actual bound local port (which may differ
+/// from `bind_port` when `0` is passed).
+///
+/// # Errors
+///
+/// Returns an error if `routes` is empty or binding the local TCP listener fails.
+pub async fn start_http_tunnel(
+    internal: InternalRawChannel,
+    id: ManagedTunnelId,
+    connection_id: ConnectionId,
+    bind_host: String,
+    bind_port: u16,
+    routes: Vec<HttpRoute>,
+) -> io::Result<(ManagedTunnel, u16)> {
+    if routes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "HTTP tunnel needs at least one route",
+        ));
+    }
+
+    let (channel, manager_channel) = internal.into_parts();
+
+    let listener = TcpListener::bind((bind_host.as_str(), bind_port))
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to bind on {bind_host}:{bind_port}: {e}")))?;
+    let actual_port = listener.local_addr()?.port();
+    let counters = TunnelCounters::new();
+    let task_counters = Arc::clone(&counters);
+
+    let remote_host = http::describe_routes(&routes);
+    let routes = Arc::new(routes);
+
+    let task = tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((tcp_stream, peer_addr)) => {
+                    debug!("[ManagedTunnel {id}] Accepted HTTP client from {peer_addr}");
+                    tokio::spawn(serve_http_client(
+                        id,
+                        channel.clone(),
+                        tcp_stream,
+                        peer_addr.ip(),
+                        Arc::clone(&routes),
+                        Arc::clone(&task_counters),
+                    ));
+                }
+                Err(e) => {
+                    debug!("[ManagedTunnel {id}] Accept error: {e}");
+                    break;
+                }
+            }
+        }
+    });
+
+    let info = ManagedTunnelInfo {
+        id,
+        connection_id,
+        direction: TunnelDirection::Dynamic,
+        bind_port: actual_port,
+        remote_host,
+        remote_port: 0,
+        protocol: TunnelProtocol::Tcp,
+        bind_path: None,
+        remote_path: None,
+        state: ManagedTunnelState::Active,
+        last_error: None,
+        stats: TunnelStats::default(),
+    };
+
+    Ok((
+        ManagedTunnel::running(id, connection_id, info, task, manager_channel, counters),
+        actual_port,
+    ))
+}
+
+/// Proxies the requests of one HTTP client connection to the route named by its first
+/// request, answering with an error response when there is nothing to proxy to.
+async fn serve_http_client(
+    id: ManagedTunnelId,
+    mut channel: Channel,
+    tcp_stream: TcpStream,
+    peer_ip: IpAddr,
+    routes: Arc<Vec<HttpRoute>>,
+    counters: Arc<TunnelCounters>,
+) {
+    let stream = CountedStream::new(tcp_stream, &counters, TunnelDirection::Dynamic);
+    let (read_half, mut write_half) = tokio::io::split(stream);
+    let mut requests = RequestReader::new(BufReader::new(read_half));
+
+    let head = match time::timeout(HTTP_REQUEST_TIMEOUT, requests.read_head()).await {
+        Ok(Ok(Some(head))) => head,
+        Ok(Ok(None)) => return,
+        Ok(Err(e)) => {
+            debug!("[ManagedTunnel {id}] Invalid HTTP request: {e}");
+            let response = http::error_response("400 Bad Request", &e.to_string());
+            let _ = write_half.write_all(&response).await;
+            return;
+        }
+        Err(_) => {
+            debug!("[ManagedTunnel {id}] Timed out waiting on HTTP request");
+            return;
+        }
+    };
+
+    let host = head.host().unwrap_or_default().to_string();
+    let Some(route) = http::route_name(&host)
+        .and_then(|name| routes.iter().find(|route| route.name == name))
+        .cloned()
+    else {
+        debug!("[ManagedTunnel {id}] No route for HTTP host '{host}'");
+        let known = routes
+            .iter()
+            .map(|route| format!("{}.localhost", route.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let response = http::error_response(
+            "404 Not Found",
+            &format!("No route for host '{host}', expected one of: {known}"),
+        );
+        let _ = write_half.write_all(&response).await;
+        return;
+    };
+
+    let (target_host, target_port) = (route.host.as_str(), route.port);
+    let mut tunnel = match channel.tunnel_open(target_host, target_port).await {
+        Ok(t) => t,
+        Err(e) => {
+            debug!(
+                "[ManagedTunnel {id}] Failed to open tunnel to {target_host}:{target_port}: {e}"
+            );
+            let response = http::error_response(
+                "502 Bad Gateway",
+                &format!("Failed to connect to {target_host}:{target_port}: {e}"),
+            );
+            let _ = write_half.write_all(&response).await;
+            return;
+        }
+    };
+
+    let (Some(mut writer), Some(mut reader)) = (tunnel.writer.take(), tunnel.reader.take()) else {
+        let response = http::error_response(
+            "502 Bad Gateway",
+            &format!("Failed to connect to {target_host}:{target_port}"),
+        );
+        let _ = write_half.write_all(&response).await;
+        let _ = tunnel.close().await;
+        return;
+    };
+
+    // Set before an upgrade request is sent, and told whether the upstream switched protocols
+    // once the status line of the response to it has been read
+    let upgrade = Arc::new(Mutex::new(None::<oneshot::Sender<bool>>));
+    let upstream_upgrade = Arc::clone(&upgrade);
+
+    // Responses are passed back untouched, so only requests need to be parsed, apart from the
+    // status line of the response to an upgrade request. Once signaled through `drain_tx` that
+    // the client is done sending requests, the response to its last one is still passed back
+    // until the upstream goes quiet
+    let (drain_tx, drain_rx) = oneshot::channel::<()>();
+    let mut upstream_to_client = tokio::spawn(async move {
+        let mut drain_rx = Some(drain_rx);
+        let mut switched_tx = None;
+        let mut status_line = Vec::new();
+        loop {
+            let data = match drain_rx.as_mut() {
+                Some(rx) => tokio::select! {
+                    data = reader.read() => data?,
+                    _ = rx => {
+                        drain_rx = None;
+                        continue;
+                    }
+                },
+                None => match time::timeout(HTTP_DRAIN_IDLE_TIMEOUT, reader.read()).await {
+                    Ok(data) => data?,
+                    Err(_) => break,
+                },
+            };
+            if data.is_empty() {
+                break;
+            }
+
+            // NOTE: Clients wait on the response to a request before sending an upgrade request,
+            //       so the first data after one is sent starts the response to it
+            if switched_tx.is_none() {
+                switched_tx = upstream_upgrade.lock().unwrap().take();
+            }
+            if let Some(tx) = switched_tx.take() {
+                status_line.extend_from_slice(&data);
+                match http::switches_protocols(&status_line) {
+                    Some(switched) => {
+                        let _ = tx.send(switched);
+                    }
+                    None => switched_tx = Some(tx),
+                }
+            }
+
+            write_half.write_all(&data).await?;
+        }
+        io::Result::Ok(())
+    });
+
+    let peer_ip = peer_ip.to_string();
+    let client_to_upstream = async {
+        let mut head = head;
+        loop {
+            let switched_rx = head.is_upgrade().then(|| {
+                let (tx, rx) = oneshot::channel();
+                *upgrade.lock().unwrap() = Some(tx);
+                rx
+            });
+
+            writer.write(head.encode_for(&route, &peer_ip)).await?;
+            while let Some(data) = requests.read_body().await? {
+                writer.write(data).await?;
+            }
+
+            // After switching protocols the connection no longer speaks HTTP, so relay it as-is,
+            // while a refused upgrade leaves the client in an unknown state, so close it
+            if let Some(switched_rx) = switched_rx {
+                if !switched_rx.await.unwrap_or(false) {
+                    debug!("[ManagedTunnel {id}] HTTP upgrade refused, closing connection");
+                    return io::Result::Ok(());
+                }
+
+                loop {
+                    let data = requests.read_raw().await?;
+                    if data.is_empty() {
+                        return Ok(());
+                    }
+                    writer.write(data).await?;
+                }
+            }
+
+            head = match requests.read_head().await? {
+                Some(head) => head,
+                None => return Ok(()),
+            };
+
+            let name = head.host().and_then(http::route_name);
+            if name.as_deref() != Some(route.name.as_str()) {
+                debug!("[ManagedTunnel {id}] HTTP client switched routes, closing connection");
+                return Ok(());
+            }
+        }
+    };
+
+    // A client that stops sending requests, by closing its half of the connection or switching
+    // routes, still gets the response to its last request, while an error ends both directions
+    let result = tokio::select! {
+        result = client_to_upstream => match result {
+            Ok(()) => {
+                let _ = drain_tx.send(());
+                upstream_to_client.await
+            }
+            Err(e) => {
+                upstream_to_client.abort();
+                debug!("[ManagedTunnel {id}] Client-to-upstream relay error: {e}");
+                Ok(Ok(()))
+            }
+        },
+        result = &mut upstream_to_client => result,
+    };
+
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => debug!("[ManagedTunnel {id}] Upstream-to-client relay error: {e}"),
+        Err(e) => debug!("[ManagedTunnel {id}] Upstream-to-client task panicked: {e}"),
+    }
+
+    let _ = tunnel.close().await;
+}
+
+/// Starts a forward UDP tunnel (local UDP socket → remote target) inside the
+/// manager process.
+///
+/// Each local peer sending datagrams to the bound port gets its own UDP tunnel
+/// to the remote target, preserving datagram boundaries in both directions.
+/// An association is closed once no datagrams have passed through it for
+/// [`UDP_TUNNEL_IDLE_TIMEOUT`](crate::constants::UDP_TUNNEL_IDLE_TIMEOUT).
+///
+/// The caller should open the [`InternalRawChannel`] while briefly holding the
+/// connection lock, then pass it here with the tunnel's `id` for the async setup.
+///
+/// Returns the managed tunnel and the actual bound local port (which may differ
+/// from `bind_port` when `0` is passed).
+///
+/// # Errors
+///
+/// Returns an error if binding the local UDP socket fails.
+pub async fn start_udp_forward_tunnel(
+    internal: InternalRawChannel,
+    id: ManagedTunnelId,
+    connection_id: ConnectionId,
+    bind_port: u16,
+    remote_host: String,
+    remote_port: u16,
+) -> io::Result<(ManagedTunnel, u16)> {
+    let (channel, manager_channel) = internal.into_parts();
+
+    let socket = UdpSocket::bind(format!("127.0.0.1:{bind_port}"))
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to bind on port {bind_port}: {e}")))?;
+    let actual_port = socket.local_addr()?.port();
+
+    let host = remote_host.clone();
+    let port = remote_port;
+    let counters = TunnelCounters::new();
+    let task_counters = Arc::clone(&counters);
+
+    let task = tokio::spawn(async move {
+        let mut demux = UdpDemux::new(socket);
+
+        loop {
+            let peer = match demux.accept().await {
+                Ok(peer) => peer,
+                Err(e) => {
+                    debug!("[ManagedTunnel {id}] Receive error: {e}");
+                    break;
+                }
+            };
+
+            debug!(
+                "[ManagedTunnel {id}] New UDP association with {}",
+                peer.addr()
+            );
+            tokio::spawn(relay_udp_peer(
+                id,
+                channel.clone(),
+                host.clone(),
+                port,
+                peer,
+                Arc::clone(&task_counters),
+            ));
+        }
+    });
+
     let info = ManagedTunnelInfo {
         id,
         connection_id,
@@ -115,25 +818,91 @@ pub async fn start_forward_tunnel(
         bind_port: actual_port,
         remote_host,
         remote_port,
+        protocol: TunnelProtocol::Udp,
+        bind_path: None,
+        remote_path: None,
+        state: ManagedTunnelState::Active,
+        last_error: None,
+        stats: TunnelStats::default(),
     };
 
     Ok((
-        ManagedTunnel {
-            id,
-            connection_id,
-            info,
-            task,
-            manager_channel,
-        },
+        ManagedTunnel::running(id, connection_id, info, task, manager_channel, counters),
         actual_port,
     ))
 }
 
-/// Starts a reverse tunnel (remote listener → local TCP target) inside the
+/// Relays datagrams between a single local peer of a forward UDP tunnel and a
+/// UDP tunnel opened for it to the remote target.
+///
+/// The remote tunnel is closed once the association has been idle for
+/// [`UDP_TUNNEL_IDLE_TIMEOUT`] or the managed tunnel stops.
+///
+/// [`UDP_TUNNEL_IDLE_TIMEOUT`]: crate::constants::UDP_TUNNEL_IDLE_TIMEOUT
+async fn relay_udp_peer(
+    id: ManagedTunnelId,
+    mut channel: Channel,
+    host: String,
+    port: u16,
+    peer: UdpPeer,
+    counters: Arc<TunnelCounters>,
+) {
+    let mut tunnel = match channel.udp_tunnel_open(host.clone(), port).await {
+        Ok(t) => t,
+        Err(e) => {
+            debug!("[ManagedTunnel {id}] Failed to open UDP tunnel to {host}:{port}: {e}");
+            return;
+        }
+    };
+
+    let (Some(writer), Some(reader)) = (tunnel.writer.take(), tunnel.reader.take()) else {
+        let _ = tunnel.close().await;
+        return;
+    };
+    let _connection = counters.connection();
+
+    peer.relay(RemoteUdpTarget {
+        writer,
+        reader,
+        counters: &counters,
+    })
+    .await;
+
+    let _ = tunnel.close().await;
+}
+
+/// Relays the datagrams of a local peer through a remote UDP tunnel, recording the traffic in
+/// the managed tunnel's `counters`
+struct RemoteUdpTarget<'a> {
+    writer: RemoteTunnelWriter,
+    reader: RemoteTunnelReader,
+    counters: &'a TunnelCounters,
+}
+
+impl UdpTarget for RemoteUdpTarget<'_> {
+    async fn forward(&mut self, data: Vec<u8>) -> io::Result<()> {
+        let n = data.len();
+        self.writer.write(data).await?;
+        self.counters.record_out(n);
+        Ok(())
+    }
+
+    async fn reply(&mut self) -> Option<Vec<u8>> {
+        let data = self.reader.read().await.ok()?;
+        self.counters.record_in(data.len());
+        Some(data)
+    }
+}
+
+/// Starts a reverse UDP tunnel (remote UDP listener → local target) inside the
 /// manager process.
 ///
+/// Each remote peer sending datagrams to the listener is relayed through its own
+/// local UDP socket associated with the target, so replies reach the right peer.
+/// The remote server closes an association once the peer has been idle for a while.
+///
 /// The caller should open the [`InternalRawChannel`] while briefly holding the
-/// connection lock, then pass it here for the async setup.
+/// connection lock, then pass it here with the tunnel's `id` for the async setup.
 ///
 /// Returns the managed tunnel and the actual remote port (which may differ
 /// from `remote_port` when `0` is passed).
@@ -141,8 +910,9 @@ pub async fn start_forward_tunnel(
 /// # Errors
 ///
 /// Returns an error if the remote side fails to set up the listener.
-pub async fn start_reverse_tunnel(
+pub async fn start_udp_reverse_tunnel(
     internal: InternalRawChannel,
+    id: ManagedTunnelId,
     connection_id: ConnectionId,
     remote_port: u16,
     local_host: String,
@@ -151,18 +921,20 @@ pub async fn start_reverse_tunnel(
     let (channel, manager_channel) = internal.into_parts();
 
     // Ask the remote to listen on the specified port
-    let mut listener = RemoteTunnelListener::listen(channel, "0.0.0.0".to_string(), remote_port)
-        .await
-        .map_err(|e| {
-            io::Error::other(format!(
-                "Failed to start remote listener on port {remote_port}: {e}"
-            ))
-        })?;
+    let mut listener =
+        RemoteTunnelListener::listen_udp(channel, "0.0.0.0".to_string(), remote_port)
+            .await
+            .map_err(|e| {
+                io::Error::other(format!(
+                    "Failed to start remote UDP listener on port {remote_port}: {e}"
+                ))
+            })?;
 
     let actual_port = listener.port();
-    let id = NEXT_MANAGED_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
     let host = local_host.clone();
     let port = local_port;
+    let counters = TunnelCounters::new();
+    let task_counters = Arc::clone(&counters);
 
     let task = tokio::spawn(async move {
         loop {
@@ -175,27 +947,23 @@ pub async fn start_reverse_tunnel(
             };
 
             debug!(
-                "[ManagedTunnel {id}] Incoming connection (peer: {:?})",
+                "[ManagedTunnel {id}] Incoming UDP association (peer: {:?})",
                 incoming.peer_addr,
             );
 
             let host = host.clone();
+            let counters = Arc::clone(&task_counters);
 
             tokio::spawn(async move {
-                let tcp_stream =
-                    match tokio::net::TcpStream::connect(format!("{host}:{port}")).await {
-                        Ok(s) => s,
-                        Err(e) => {
-                            debug!("Failed to connect to local {host}:{port}: {e}");
-                            return;
-                        }
-                    };
+                let socket = match connect_udp(&host, port).await {
+                    Ok(s) => s,
+                    Err(e) => {
+                        debug!("Failed to associate with local {host}:{port}: {e}");
+                        return;
+                    }
+                };
 
-                if let Err(e) =
-                    client::relay_tcp_to_tunnel(tcp_stream, incoming.writer, incoming.reader).await
-                {
-                    debug!("Reverse relay finished: {e}");
-                }
+                relay_udp_target(socket, incoming.writer, incoming.reader, counters).await;
             });
         }
 
@@ -209,16 +977,79 @@ pub async fn start_reverse_tunnel(
         bind_port: actual_port,
         remote_host: local_host,
         remote_port: local_port,
+        protocol: TunnelProtocol::Udp,
+        bind_path: None,
+        remote_path: None,
+        state: ManagedTunnelState::Active,
+        last_error: None,
+        stats: TunnelStats::default(),
     };
 
     Ok((
-        ManagedTunnel {
-            id,
-            connection_id,
-            info,
-            task,
-            manager_channel,
-        },
+        ManagedTunnel::running(id, connection_id, info, task, manager_channel, counters),
         actual_port,
     ))
 }
+
+/// Relays datagrams between a remote peer of a reverse UDP tunnel and a local UDP
+/// `socket` associated with the tunnel's target, like [`client::relay_udp_to_tunnel`]
+/// while recording the traffic in the tunnel's `counters`.
+async fn relay_udp_target(
+    socket: UdpSocket,
+    mut writer: RemoteTunnelWriter,
+    mut reader: RemoteTunnelReader,
+    counters: Arc<TunnelCounters>,
+) {
+    let _connection = counters.connection();
+    let mut buf = vec![0u8; UDP_TUNNEL_DATAGRAM_BUFFER_SIZE];
+
+    loop {
+        tokio::select! {
+            result = socket.recv(&mut buf) => match result {
+                Ok(n) => {
+                    counters.record_in(n);
+                    if let Err(e) = writer.write(buf[..n].to_vec()).await {
+                        debug!("Reverse UDP relay finished: {e}");
+                        break;
+                    }
+                }
+
+                // A previous datagram was rejected by the target (ICMP port unreachable),
+                // which should not tear down the association
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => continue,
+                Err(e) => {
+                    debug!("Reverse UDP relay finished: {e}");
+                    break;
+                }
+            },
+            result = reader.read() => match result {
+                Ok(data) => {
+                    if let Err(e) = socket.send(&data).await
+                        && e.kind() != io::ErrorKind::ConnectionRefused
+                    {
+                        debug!("Reverse UDP relay finished: {e}");
+                        break;
+                    }
+                    counters.record_out(data.len());
+                }
+                Err(_) => break,
+            },
+        }
+    }
+}
+
+/// Resolves `host:port` and returns a UDP socket bound to an ephemeral port and
+/// connected to the first resolved address.
+async fn connect_udp(host: &str, port: u16) -> io::Result<UdpSocket> {
+    let addr = tokio::net::lookup_host((host, port))
+        .await?
+        .next()
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No addresses found for {host}:{port}"),
+            )
+        })?;
+
+    crate::net::common::connect_udp(addr).await
+}
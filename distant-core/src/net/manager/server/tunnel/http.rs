@@ -0,0 +1,679 @@
+use std::io;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::net::manager::data::HttpRoute;
+
+/// Maximum size of the request line and headers of an HTTP request
+const MAX_HEAD_LEN: usize = 64 * 1024;
+
+/// Maximum length of a chunk size line or trailer of a chunked request body
+const MAX_LINE_LEN: usize = 8 * 1024;
+
+/// Suffix of the host names that the proxy serves, each naming one of its routes
+const LOCALHOST_SUFFIX: &str = ".localhost";
+
+/// Request line and headers of an HTTP/1.x request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestHead {
+    pub method: String,
+    pub target: String,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl RequestHead {
+    /// Returns the value of the first header named `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the host that the request is for, taken from an absolute target such as
+    /// `http://web.localhost:8080/` when used, and from the `Host` header otherwise.
+    pub fn host(&self) -> Option<&str> {
+        match self.target.strip_prefix("http://") {
+            Some(rest) => rest.split('/').next(),
+            None => self.header("host"),
+        }
+    }
+
+    /// Returns true if the request asks to switch protocols, such as to a WebSocket.
+    pub fn is_upgrade(&self) -> bool {
+        self.header("upgrade").is_some()
+            && self.header("connection").is_some_and(|value| {
+                value
+                    .split(',')
+                    .any(|token| token.trim().eq_ignore_ascii_case("upgrade"))
+            })
+    }
+
+    /// Returns how the body of the request is framed.
+    pub fn body(&self) -> io::Result<BodyKind> {
+        if let Some(encoding) = self.header("transfer-encoding") {
+            let chunked = encoding
+                .rsplit(',')
+                .next()
+                .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"));
+            return if chunked {
+                Ok(BodyKind::Chunked)
+            } else {
+                Err(invalid_data(format!(
+                    "Unsupported transfer encoding '{encoding}'"
+                )))
+            };
+        }
+
+        match self.header("content-length") {
+            Some(len) => len
+                .trim()
+                .parse()
+                .map(BodyKind::Length)
+                .map_err(|_| invalid_data(format!("Invalid content length '{len}'"))),
+            None => Ok(BodyKind::Length(0)),
+        }
+    }
+
+    /// Encodes the request as it is sent to `route`, with the `Host` header naming the route's
+    /// target and `X-Forwarded-*` headers describing the original request from `peer_ip`.
+    ///
+    /// A `Content-Length` sent alongside chunked transfer encoding is dropped, as RFC 9112 §6.1
+    /// requires of intermediaries, so that the target cannot frame the body differently.
+    pub fn encode_for(&self, route: &HttpRoute, peer_ip: &str) -> Vec<u8> {
+        let original_host = self.host().unwrap_or_default().to_string();
+        let chunked = matches!(self.body(), Ok(BodyKind::Chunked));
+        let target = match self.target.strip_prefix("http://") {
+            Some(rest) => match rest.find('/') {
+                Some(i) => &rest[i..],
+                None => "/",
+            },
+            None => self.target.as_str(),
+        };
+
+        let mut head = format!("{} {} {}\r\n", self.method, target, self.version);
+        head.push_str(&format!("Host: {}\r\n", target_authority(route)));
+
+        let mut forwarded_for = None;
+        for (name, value) in &self.headers {
+            if name.eq_ignore_ascii_case("host")
+                || name.eq_ignore_ascii_case("x-forwarded-host")
+                || name.eq_ignore_ascii_case("x-forwarded-proto")
+                || (chunked && name.eq_ignore_ascii_case("content-length"))
+            {
+                continue;
+            }
+
+            if name.eq_ignore_ascii_case("x-forwarded-for") {
+                forwarded_for = Some(format!("{value}, {peer_ip}"));
+                continue;
+            }
+
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+
+        head.push_str(&format!("X-Forwarded-Host: {original_host}\r\n"));
+        head.push_str("X-Forwarded-Proto: http\r\n");
+        head.push_str(&format!(
+            "X-Forwarded-For: {}\r\n\r\n",
+            forwarded_for.as_deref().unwrap_or(peer_ip)
+        ));
+        head.into_bytes()
+    }
+}
+
+/// Framing of an HTTP request body
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BodyKind {
+    /// Body of exactly this many bytes, which is 0 for requests without a body
+    Length(u64),
+
+    /// Body sent with chunked transfer encoding
+    Chunked,
+}
+
+/// Where a [`RequestReader`] is within the body of the current request
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum BodyState {
+    /// Bytes of the body, or of the current chunk and its trailing CRLF, left to read
+    Remaining { len: u64, chunked: bool },
+
+    /// Next line is the size of a chunk
+    ChunkSize,
+
+    /// Next line is a trailer, or the empty line ending the body
+    Trailers,
+
+    /// Body has been read
+    Done,
+}
+
+/// Reads the requests sent by an HTTP/1.x client, one head and body at a time, so that each
+/// request can be rewritten before being passed on while its body is passed on verbatim.
+pub struct RequestReader<R> {
+    reader: R,
+    body: BodyState,
+}
+
+impl<R> RequestReader<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            body: BodyState::Done,
+        }
+    }
+
+    /// Reads the head of the next request, returning `None` if the client closed the connection
+    /// instead of sending another request.
+    ///
+    /// The body of the previous request must have been read with [`read_body`](Self::read_body).
+    pub async fn read_head(&mut self) -> io::Result<Option<RequestHead>> {
+        let mut lines = Vec::new();
+        let mut len = 0;
+
+        loop {
+            let line = read_line(&mut self.reader, MAX_HEAD_LEN - len).await?;
+            if line.is_empty() {
+                return if len == 0 {
+                    Ok(None)
+                } else {
+                    Err(io::Error::from(io::ErrorKind::UnexpectedEof))
+                };
+            }
+            len += line.len();
+
+            let line = String::from_utf8(line)
+                .map_err(|_| invalid_data("Request head is not UTF-8".to_string()))?;
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                // Clients may send empty lines before the request line
+                if lines.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            lines.push(line.to_string());
+        }
+
+        let mut request_line = lines[0].split(' ');
+        let (Some(method), Some(target), Some(version), None) = (
+            request_line.next(),
+            request_line.next(),
+            request_line.next(),
+            request_line.next(),
+        ) else {
+            return Err(invalid_data(format!("Invalid request line '{}'", lines[0])));
+        };
+        if !version.starts_with("HTTP/1.") {
+            return Err(invalid_data(format!(
+                "Unsupported HTTP version '{version}'"
+            )));
+        }
+
+        let mut headers = Vec::new();
+        for line in &lines[1..] {
+            let Some((name, value)) = line.split_once(':') else {
+                return Err(invalid_data(format!("Invalid header '{line}'")));
+            };
+            if name.is_empty() || name.ends_with(char::is_whitespace) {
+                return Err(invalid_data(format!("Invalid header '{line}'")));
+            }
+            headers.push((name.to_string(), value.trim().to_string()));
+        }
+
+        let head = RequestHead {
+            method: method.to_string(),
+            target: target.to_string(),
+            version: version.to_string(),
+            headers,
+        };
+
+        self.body = match head.body()? {
+            BodyKind::Length(len) => BodyState::Remaining {
+                len,
+                chunked: false,
+            },
+            BodyKind::Chunked => BodyState::ChunkSize,
+        };
+
+        Ok(Some(head))
+    }
+
+    /// Reads the next part of the current request's body as sent by the client, including any
+    /// chunked framing, returning `None` once the whole body has been read.
+    pub async fn read_body(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            match self.body {
+                BodyState::Remaining { len: 0, chunked } => {
+                    self.body = if chunked {
+                        BodyState::ChunkSize
+                    } else {
+                        BodyState::Done
+                    };
+                }
+                BodyState::Remaining { len, chunked } => {
+                    let buf = self.reader.fill_buf().await?;
+                    if buf.is_empty() {
+                        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                    }
+
+                    let n = buf.len().min(usize::try_from(len).unwrap_or(usize::MAX));
+                    let data = buf[..n].to_vec();
+                    self.reader.consume(n);
+                    self.body = BodyState::Remaining {
+                        len: len - n as u64,
+                        chunked,
+                    };
+                    return Ok(Some(data));
+                }
+                BodyState::ChunkSize => {
+                    let line = read_full_line(&mut self.reader).await?;
+                    let size = std::str::from_utf8(&line)
+                        .ok()
+                        .and_then(|line| {
+                            let size = line.trim_end().split(';').next()?.trim();
+                            u64::from_str_radix(size, 16).ok()
+                        })
+                        .ok_or_else(|| invalid_data("Invalid chunk size".to_string()))?;
+
+                    // Every chunk but the last is followed by CRLF
+                    self.body = if size == 0 {
+                        BodyState::Trailers
+                    } else {
+                        let len = size
+                            .checked_add(2)
+                            .ok_or_else(|| invalid_data("Chunk size is too large".to_string()))?;
+                        BodyState::Remaining { len, chunked: true }
+                    };
+                    return Ok(Some(line));
+                }
+                BodyState::Trailers => {
+                    let line = read_full_line(&mut self.reader).await?;
+                    if line == b"\r\n" || line == b"\n" {
+                        self.body = BodyState::Done;
+                    }
+                    return Ok(Some(line));
+                }
+                BodyState::Done => return Ok(None),
+            }
+        }
+    }
+
+    /// Reads whatever the client sends next without interpreting it, which is how the connection
+    /// is relayed once it has switched protocols. Returns an empty buffer once the client has
+    /// closed the connection.
+    pub async fn read_raw(&mut self) -> io::Result<Vec<u8>> {
+        let buf = self.reader.fill_buf().await?;
+        let data = buf.to_vec();
+        self.reader.consume(data.len());
+        Ok(data)
+    }
+}
+
+/// Returns whether the response beginning with `data` switches protocols (`101`), or `None` if
+/// its status line has not been fully received yet.
+pub fn switches_protocols(data: &[u8]) -> Option<bool> {
+    let Some(end) = data.iter().position(|b| *b == b'\n') else {
+        return (data.len() >= MAX_LINE_LEN).then_some(false);
+    };
+
+    let mut status_line = data[..end].trim_ascii_end().split(|b| *b == b' ');
+    Some(
+        status_line
+            .next()
+            .is_some_and(|version| version.starts_with(b"HTTP/1."))
+            && status_line.next() == Some(&b"101"[..]),
+    )
+}
+
+/// Returns the name of the route that requests for `host` go to, which is the first label of
+/// `NAME.localhost`, ignoring any port and case.
+pub fn route_name(host: &str) -> Option<String> {
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if !host.ends_with(']') && port.bytes().all(|b| b.is_ascii_digit()) => {
+            host
+        }
+        _ => host,
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+
+    host.strip_suffix(LOCALHOST_SUFFIX)
+        .filter(|name| !name.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Describes `routes` as the remote end of an HTTP tunnel, e.g. `web=127.0.0.1:3000,api=127.0.0.1:4000`.
+pub fn describe_routes(routes: &[HttpRoute]) -> String {
+    routes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Builds a complete response with `status` and a plain text `message`, after which the proxy
+/// closes the connection.
+pub fn error_response(status: &str, message: &str) -> Vec<u8> {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{message}\n",
+        message.len() + 1
+    )
+    .into_bytes()
+}
+
+/// Returns the value of the `Host` header for requests sent to `route`.
+fn target_authority(route: &HttpRoute) -> String {
+    let host = if route.host.contains(':') {
+        format!("[{}]", route.host)
+    } else {
+        route.host.clone()
+    };
+
+    if route.port == 80 {
+        host
+    } else {
+        format!("{host}:{}", route.port)
+    }
+}
+
+/// Reads a line including its `\n`, failing if it is longer than `max` bytes. Returns an empty
+/// line at the end of the stream.
+async fn read_line<R>(reader: &mut R, max: usize) -> io::Result<Vec<u8>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = Vec::new();
+    loop {
+        let buf = reader.fill_buf().await?;
+        if buf.is_empty() {
+            return Ok(line);
+        }
+
+        let (n, done) = match buf.iter().position(|b| *b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (buf.len(), false),
+        };
+        if line.len() + n > max {
+            return Err(invalid_data("Request headers are too large".to_string()));
+        }
+
+        line.extend_from_slice(&buf[..n]);
+        reader.consume(n);
+        if done {
+            return Ok(line);
+        }
+    }
+}
+
+/// Reads a line of a chunked body, failing if the stream ends before the line does.
+async fn read_full_line<R>(reader: &mut R) -> io::Result<Vec<u8>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let line = read_line(reader, MAX_LINE_LEN).await?;
+    if line.ends_with(b"\n") {
+        Ok(line)
+    } else {
+        Err(io::Error::from(io::ErrorKind::UnexpectedEof))
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for the HTTP proxy: parsing request heads, reading bodies with either framing,
+    //! matching hosts to routes, and rewriting requests for their route.
+
+    use test_log::test;
+
+    use super::*;
+
+    fn route() -> HttpRoute {
+        HttpRoute {
+            name: String::from("web"),
+            host: String::from("127.0.0.1"),
+            port: 3000,
+        }
+    }
+
+    /// Reads every request in `input`, returning each head with its body as sent by the client
+    async fn read_requests(input: &[u8]) -> io::Result<Vec<(RequestHead, Vec<u8>)>> {
+        let mut reader = RequestReader::new(input);
+        let mut requests = Vec::new();
+        while let Some(head) = reader.read_head().await? {
+            let mut body = Vec::new();
+            while let Some(data) = reader.read_body().await? {
+                body.extend_from_slice(&data);
+            }
+            requests.push((head, body));
+        }
+        Ok(requests)
+    }
+
+    #[test(tokio::test)]
+    async fn should_read_request_head() {
+        let requests = read_requests(
+            b"GET /index.html HTTP/1.1\r\nHost: web.localhost:8080\r\nAccept:  */*\r\n\r\n",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            requests,
+            vec![(
+                RequestHead {
+                    method: String::from("GET"),
+                    target: String::from("/index.html"),
+                    version: String::from("HTTP/1.1"),
+                    headers: vec![
+                        (String::from("Host"), String::from("web.localhost:8080")),
+                        (String::from("Accept"), String::from("*/*")),
+                    ],
+                },
+                Vec::new()
+            )]
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn should_read_consecutive_requests_with_content_length_bodies() {
+        let requests = read_requests(
+            b"POST /a HTTP/1.1\r\nHost: web.localhost\r\nContent-Length: 5\r\n\r\nhello\
+              GET /b HTTP/1.1\r\nHost: web.localhost\r\n\r\n",
+        )
+        .await
+        .unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0.target, "/a");
+        assert_eq!(requests[0].1, b"hello");
+        assert_eq!(requests[1].0.target, "/b");
+        assert!(requests[1].1.is_empty());
+    }
+
+    #[test(tokio::test)]
+    async fn should_read_chunked_body_verbatim() {
+        let body = b"5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nTrailer: x\r\n\r\n";
+        let input = [
+            &b"POST / HTTP/1.1\r\nHost: web.localhost\r\nTransfer-Encoding: chunked\r\n\r\n"[..],
+            body,
+            b"GET /next HTTP/1.1\r\nHost: web.localhost\r\n\r\n",
+        ]
+        .concat();
+
+        let requests = read_requests(&input).await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].1, body);
+        assert_eq!(requests[1].0.target, "/next");
+    }
+
+    #[test(tokio::test)]
+    async fn should_fail_if_request_is_invalid() {
+        for input in [
+            &b"GET /\r\n\r\n"[..],
+            b"GET / HTTP/2\r\n\r\n",
+            b"GET / HTTP/1.1\r\nno colon\r\n\r\n",
+            b"POST / HTTP/1.1\r\nContent-Length: abc\r\n\r\n",
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n",
+        ] {
+            let err = read_requests(input).await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{input:?}");
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn should_fail_if_chunk_size_overflows() {
+        let err = read_requests(
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\nhello",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test(tokio::test)]
+    async fn should_fail_if_connection_closes_mid_request() {
+        let err = read_requests(b"GET / HTTP/1.1\r\nHost: web")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = read_requests(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nhello")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test(tokio::test)]
+    async fn should_fail_if_head_is_too_large() {
+        let input = [
+            &b"GET / HTTP/1.1\r\nCookie: "[..],
+            &vec![b'a'; MAX_HEAD_LEN],
+            b"\r\n\r\n",
+        ]
+        .concat();
+        let err = read_requests(&input).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test(tokio::test)]
+    async fn read_raw_should_return_remaining_bytes_after_head() {
+        let mut reader = RequestReader::new(
+            &b"GET /ws HTTP/1.1\r\nHost: web.localhost\r\nUpgrade: websocket\r\nConnection: keep-alive, Upgrade\r\n\r\nframe"[..],
+        );
+        let head = reader.read_head().await.unwrap().unwrap();
+        assert!(head.is_upgrade());
+        assert_eq!(reader.read_body().await.unwrap(), None);
+        assert_eq!(reader.read_raw().await.unwrap(), b"frame");
+        assert!(reader.read_raw().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn is_upgrade_should_require_upgrade_in_connection_header() {
+        let head = |headers: &[(&str, &str)]| RequestHead {
+            method: String::from("GET"),
+            target: String::from("/"),
+            version: String::from("HTTP/1.1"),
+            headers: headers
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+        };
+
+        assert!(head(&[("Upgrade", "websocket"), ("Connection", "Upgrade")]).is_upgrade());
+        assert!(!head(&[("Upgrade", "websocket"), ("Connection", "keep-alive")]).is_upgrade());
+        assert!(!head(&[("Connection", "Upgrade")]).is_upgrade());
+    }
+
+    #[test]
+    fn switches_protocols_should_require_complete_101_status_line() {
+        assert_eq!(
+            switches_protocols(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n"),
+            Some(true)
+        );
+        assert_eq!(switches_protocols(b"HTTP/1.1 101\r\n"), Some(true));
+        assert_eq!(switches_protocols(b"HTTP/1.1 200 OK\r\n"), Some(false));
+        assert_eq!(switches_protocols(b"GET / HTTP/1.1\r\n"), Some(false));
+        assert_eq!(switches_protocols(b"HTTP/1.1 10"), None);
+        assert_eq!(switches_protocols(&vec![b'a'; MAX_LINE_LEN]), Some(false));
+    }
+
+    #[test]
+    fn route_name_should_strip_localhost_suffix_and_port() {
+        assert_eq!(route_name("web.localhost:8080").as_deref(), Some("web"));
+        assert_eq!(route_name("WEB.Localhost").as_deref(), Some("web"));
+        assert_eq!(route_name("web.localhost.").as_deref(), Some("web"));
+        assert_eq!(route_name("api.v2.localhost").as_deref(), Some("api.v2"));
+        assert_eq!(route_name("localhost:8080"), None);
+        assert_eq!(route_name("example.com"), None);
+        assert_eq!(route_name("[::1]:8080"), None);
+    }
+
+    #[test(tokio::test)]
+    async fn encode_for_should_rewrite_host_and_add_forwarded_headers() {
+        let mut reader = RequestReader::new(
+            &b"GET /path?q=1 HTTP/1.1\r\nHost: web.localhost:8080\r\nX-Forwarded-For: 10.0.0.1\r\nAccept: */*\r\n\r\n"[..],
+        );
+        let head = reader.read_head().await.unwrap().unwrap();
+        assert_eq!(
+            String::from_utf8(head.encode_for(&route(), "127.0.0.1")).unwrap(),
+            "GET /path?q=1 HTTP/1.1\r\n\
+             Host: 127.0.0.1:3000\r\n\
+             Accept: */*\r\n\
+             X-Forwarded-Host: web.localhost:8080\r\n\
+             X-Forwarded-Proto: http\r\n\
+             X-Forwarded-For: 10.0.0.1, 127.0.0.1\r\n\r\n"
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn encode_for_should_drop_content_length_of_chunked_request() {
+        let mut reader = RequestReader::new(
+            &b"POST / HTTP/1.1\r\nHost: web.localhost\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n"[..],
+        );
+        let head = reader.read_head().await.unwrap().unwrap();
+        assert_eq!(
+            String::from_utf8(head.encode_for(&route(), "127.0.0.1")).unwrap(),
+            "POST / HTTP/1.1\r\n\
+             Host: 127.0.0.1:3000\r\n\
+             Transfer-Encoding: chunked\r\n\
+             X-Forwarded-Host: web.localhost\r\n\
+             X-Forwarded-Proto: http\r\n\
+             X-Forwarded-For: 127.0.0.1\r\n\r\n"
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn encode_for_should_turn_absolute_target_into_path() {
+        let mut reader = RequestReader::new(
+            &b"GET http://web.localhost:8080/a/b HTTP/1.1\r\nHost: web.localhost:8080\r\n\r\n"[..],
+        );
+        let head = reader.read_head().await.unwrap().unwrap();
+        assert_eq!(head.host(), Some("web.localhost:8080"));
+
+        let route = HttpRoute {
+            name: String::from("web"),
+            host: String::from("::1"),
+            port: 80,
+        };
+        let encoded = String::from_utf8(head.encode_for(&route, "127.0.0.1")).unwrap();
+        assert!(
+            encoded.starts_with("GET /a/b HTTP/1.1\r\nHost: [::1]\r\n"),
+            "{encoded}"
+        );
+    }
+
+    #[test]
+    fn error_response_should_close_connection() {
+        let response = String::from_utf8(error_response("404 Not Found", "No route")).unwrap();
+        assert_eq!(
+            response,
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 9\r\nConnection: close\r\n\r\nNo route\n"
+        );
+    }
+}
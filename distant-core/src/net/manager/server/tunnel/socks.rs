@@ -0,0 +1,415 @@
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// SOCKS command asking the proxy to connect to a target
+const CMD_CONNECT: u8 = 0x01;
+
+/// SOCKS5 method for clients that do not authenticate
+const SOCKS5_NO_AUTH: u8 = 0x00;
+
+/// SOCKS5 method reply rejecting all methods offered by the client
+const SOCKS5_NO_ACCEPTABLE_METHODS: u8 = 0xff;
+
+/// SOCKS5 address types
+const SOCKS5_ATYP_IPV4: u8 = 0x01;
+const SOCKS5_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS5_ATYP_IPV6: u8 = 0x04;
+
+/// SOCKS5 reply codes
+const SOCKS5_SUCCEEDED: u8 = 0x00;
+const SOCKS5_GENERAL_FAILURE: u8 = 0x01;
+const SOCKS5_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const SOCKS5_ADDRESS_TYPE_NOT_SUPPORTED: u8 = 0x08;
+
+/// SOCKS4 reply codes
+const SOCKS4_GRANTED: u8 = 0x5a;
+const SOCKS4_REJECTED: u8 = 0x5b;
+
+/// Maximum length of the null-terminated user id and domain name of a SOCKS4a request
+const SOCKS4_MAX_FIELD_LEN: usize = 255;
+
+/// Version of the SOCKS protocol spoken by a client
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SocksVersion {
+    /// SOCKS4, including the SOCKS4a extension for domain names
+    V4,
+
+    /// SOCKS5 without authentication
+    V5,
+}
+
+/// Target that a SOCKS client asked to connect to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SocksRequest {
+    pub version: SocksVersion,
+    pub host: String,
+    pub port: u16,
+}
+
+impl SocksRequest {
+    /// Reads the greeting and CONNECT request of a SOCKS4, SOCKS4a, or SOCKS5 client from
+    /// `stream`, answering the SOCKS5 method negotiation along the way.
+    ///
+    /// Requests that cannot be served (other commands, authentication, unknown address types)
+    /// are rejected on `stream` before failing.
+    pub async fn read<S>(stream: &mut S) -> io::Result<Self>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        match stream.read_u8().await? {
+            4 => read_socks4(stream).await,
+            5 => read_socks5(stream).await,
+            version => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported SOCKS version {version}"),
+            )),
+        }
+    }
+
+    /// Tells the client whether the connection to the target succeeded, after which the stream
+    /// carries the data of the connection when it did.
+    pub async fn reply<S>(&self, stream: &mut S, succeeded: bool) -> io::Result<()>
+    where
+        S: AsyncWrite + Unpin,
+    {
+        match self.version {
+            SocksVersion::V4 => {
+                let code = if succeeded {
+                    SOCKS4_GRANTED
+                } else {
+                    SOCKS4_REJECTED
+                };
+                stream.write_all(&socks4_reply(code)).await?;
+            }
+            SocksVersion::V5 => {
+                let code = if succeeded {
+                    SOCKS5_SUCCEEDED
+                } else {
+                    SOCKS5_GENERAL_FAILURE
+                };
+                stream.write_all(&socks5_reply(code)).await?;
+            }
+        }
+
+        stream.flush().await
+    }
+}
+
+/// Builds a SOCKS4 reply with `code`, leaving out the bound address as clients ignore it
+fn socks4_reply(code: u8) -> [u8; 8] {
+    [0, code, 0, 0, 0, 0, 0, 0]
+}
+
+/// Builds a SOCKS5 reply with `code`, using an unspecified IPv4 bound address as the actual
+/// address is on the remote machine
+fn socks5_reply(code: u8) -> [u8; 10] {
+    [5, code, 0, SOCKS5_ATYP_IPV4, 0, 0, 0, 0, 0, 0]
+}
+
+async fn read_socks5<S>(stream: &mut S) -> io::Result<SocksRequest>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Negotiate the method, where we only support clients that do not authenticate
+    let mut methods = vec![0u8; stream.read_u8().await? as usize];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&SOCKS5_NO_AUTH) {
+        stream.write_all(&[5, SOCKS5_NO_ACCEPTABLE_METHODS]).await?;
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "SOCKS5 client requires authentication",
+        ));
+    }
+    stream.write_all(&[5, SOCKS5_NO_AUTH]).await?;
+    stream.flush().await?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    let [version, cmd, _, atyp] = header;
+    if version != 5 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unexpected SOCKS version {version} in request"),
+        ));
+    }
+
+    let host = match atyp {
+        SOCKS5_ATYP_IPV4 => {
+            let mut octets = [0u8; 4];
+            stream.read_exact(&mut octets).await?;
+            Ipv4Addr::from(octets).to_string()
+        }
+        SOCKS5_ATYP_DOMAIN => {
+            let mut domain = vec![0u8; stream.read_u8().await? as usize];
+            stream.read_exact(&mut domain).await?;
+            String::from_utf8(domain).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "SOCKS5 domain is not UTF-8")
+            })?
+        }
+        SOCKS5_ATYP_IPV6 => {
+            let mut octets = [0u8; 16];
+            stream.read_exact(&mut octets).await?;
+            Ipv6Addr::from(octets).to_string()
+        }
+        atyp => {
+            stream
+                .write_all(&socks5_reply(SOCKS5_ADDRESS_TYPE_NOT_SUPPORTED))
+                .await?;
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Unsupported SOCKS5 address type {atyp}"),
+            ));
+        }
+    };
+    let port = stream.read_u16().await?;
+
+    if cmd != CMD_CONNECT {
+        stream
+            .write_all(&socks5_reply(SOCKS5_COMMAND_NOT_SUPPORTED))
+            .await?;
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Unsupported SOCKS5 command {cmd}"),
+        ));
+    }
+
+    Ok(SocksRequest {
+        version: SocksVersion::V5,
+        host,
+        port,
+    })
+}
+
+async fn read_socks4<S>(stream: &mut S) -> io::Result<SocksRequest>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let cmd = stream.read_u8().await?;
+    let port = stream.read_u16().await?;
+    let mut octets = [0u8; 4];
+    stream.read_exact(&mut octets).await?;
+
+    // The user id is ignored as there is no authentication
+    let _ = read_null_terminated(stream).await?;
+
+    // SOCKS4a marks a domain name following the user id with an address of 0.0.0.x (x != 0)
+    let host = match octets {
+        [0, 0, 0, x] if x != 0 => {
+            String::from_utf8(read_null_terminated(stream).await?).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "SOCKS4a domain is not UTF-8")
+            })?
+        }
+        _ => Ipv4Addr::from(octets).to_string(),
+    };
+
+    let request = SocksRequest {
+        version: SocksVersion::V4,
+        host,
+        port,
+    };
+
+    if cmd != CMD_CONNECT {
+        request.reply(stream, false).await?;
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Unsupported SOCKS4 command {cmd}"),
+        ));
+    }
+
+    Ok(request)
+}
+
+/// Reads bytes up to a null byte, failing if there are more than [`SOCKS4_MAX_FIELD_LEN`]
+async fn read_null_terminated<S>(stream: &mut S) -> io::Result<Vec<u8>>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut bytes = Vec::new();
+    loop {
+        match stream.read_u8().await? {
+            0 => return Ok(bytes),
+            _ if bytes.len() == SOCKS4_MAX_FIELD_LEN => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "SOCKS4 field is too long",
+                ));
+            }
+            b => bytes.push(b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for SocksRequest: parsing SOCKS4, SOCKS4a, and SOCKS5 CONNECT requests, rejecting
+    //! requests that cannot be served, and replying to clients.
+
+    use test_log::test;
+
+    use super::*;
+
+    /// Writes `input` as the client, returning the parsed request and what the proxy sent back
+    async fn read_request(input: &[u8]) -> (io::Result<SocksRequest>, Vec<u8>) {
+        let (mut client, mut proxy) = tokio::io::duplex(1024);
+        client.write_all(input).await.unwrap();
+
+        let result = SocksRequest::read(&mut proxy).await;
+        drop(proxy);
+
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+        (result, output)
+    }
+
+    #[test(tokio::test)]
+    async fn should_read_socks5_request_with_ipv4_address() {
+        let (result, output) =
+            read_request(&[5, 1, 0, 5, CMD_CONNECT, 0, 1, 10, 0, 0, 1, 0x1f, 0x90]).await;
+        assert_eq!(
+            result.unwrap(),
+            SocksRequest {
+                version: SocksVersion::V5,
+                host: String::from("10.0.0.1"),
+                port: 8080,
+            }
+        );
+        assert_eq!(output, [5, SOCKS5_NO_AUTH]);
+    }
+
+    #[test(tokio::test)]
+    async fn should_read_socks5_request_with_domain() {
+        let input = [
+            &[5, 2, 2, 0, 5, CMD_CONNECT, 0, 3, 9][..],
+            b"localhost",
+            &[0, 80],
+        ]
+        .concat();
+        let (result, _) = read_request(&input).await;
+        let request = result.unwrap();
+        assert_eq!(request.host, "localhost");
+        assert_eq!(request.port, 80);
+    }
+
+    #[test(tokio::test)]
+    async fn should_read_socks5_request_with_ipv6_address() {
+        let input = [
+            &[5, 1, 0, 5, CMD_CONNECT, 0, 4][..],
+            &Ipv6Addr::LOCALHOST.octets(),
+            &[0, 22],
+        ]
+        .concat();
+        let (result, _) = read_request(&input).await;
+        let request = result.unwrap();
+        assert_eq!(request.host, "::1");
+        assert_eq!(request.port, 22);
+    }
+
+    #[test(tokio::test)]
+    async fn should_reject_socks5_client_that_requires_authentication() {
+        let (result, output) = read_request(&[5, 1, 2]).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(output, [5, SOCKS5_NO_ACCEPTABLE_METHODS]);
+    }
+
+    #[test(tokio::test)]
+    async fn should_reject_socks5_commands_other_than_connect() {
+        // BIND command
+        let (result, output) = read_request(&[5, 1, 0, 5, 2, 0, 1, 10, 0, 0, 1, 0, 80]).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(output[2..], socks5_reply(SOCKS5_COMMAND_NOT_SUPPORTED));
+    }
+
+    #[test(tokio::test)]
+    async fn should_reject_socks5_unknown_address_type() {
+        let (result, output) = read_request(&[5, 1, 0, 5, CMD_CONNECT, 0, 9]).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(output[2..], socks5_reply(SOCKS5_ADDRESS_TYPE_NOT_SUPPORTED));
+    }
+
+    #[test(tokio::test)]
+    async fn should_read_socks4_request() {
+        let (result, output) =
+            read_request(&[4, CMD_CONNECT, 0, 80, 192, 168, 1, 1, b'm', b'e', 0]).await;
+        assert_eq!(
+            result.unwrap(),
+            SocksRequest {
+                version: SocksVersion::V4,
+                host: String::from("192.168.1.1"),
+                port: 80,
+            }
+        );
+        assert!(output.is_empty());
+    }
+
+    #[test(tokio::test)]
+    async fn should_read_socks4a_request_with_domain() {
+        let input = [
+            &[4, CMD_CONNECT, 0x01, 0xbb, 0, 0, 0, 1, 0][..],
+            b"example.com\0",
+        ]
+        .concat();
+        let (result, _) = read_request(&input).await;
+        let request = result.unwrap();
+        assert_eq!(request.version, SocksVersion::V4);
+        assert_eq!(request.host, "example.com");
+        assert_eq!(request.port, 443);
+    }
+
+    #[test(tokio::test)]
+    async fn should_reject_socks4_commands_other_than_connect() {
+        let (result, output) = read_request(&[4, 2, 0, 80, 127, 0, 0, 1, 0]).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(output, socks4_reply(SOCKS4_REJECTED));
+    }
+
+    #[test(tokio::test)]
+    async fn should_reject_socks4_user_id_that_is_too_long() {
+        let input = [&[4, CMD_CONNECT, 0, 80, 127, 0, 0, 1][..], &[b'a'; 300]].concat();
+        let (result, _) = read_request(&input).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test(tokio::test)]
+    async fn should_reject_unknown_socks_version() {
+        let (result, _) = read_request(&[3]).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test(tokio::test)]
+    async fn reply_should_match_version_of_request() {
+        for (version, succeeded, expected) in [
+            (
+                SocksVersion::V4,
+                true,
+                socks4_reply(SOCKS4_GRANTED).to_vec(),
+            ),
+            (
+                SocksVersion::V4,
+                false,
+                socks4_reply(SOCKS4_REJECTED).to_vec(),
+            ),
+            (
+                SocksVersion::V5,
+                true,
+                socks5_reply(SOCKS5_SUCCEEDED).to_vec(),
+            ),
+            (
+                SocksVersion::V5,
+                false,
+                socks5_reply(SOCKS5_GENERAL_FAILURE).to_vec(),
+            ),
+        ] {
+            let request = SocksRequest {
+                version,
+                host: String::from("localhost"),
+                port: 80,
+            };
+
+            let mut output = Vec::new();
+            request.reply(&mut output, succeeded).await.unwrap();
+            assert_eq!(output, expected);
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use std::io;
+
+use crate::net::common::ConnectionId;
+use crate::net::manager::data::{
+    HttpRoute, ManagedTunnelId, ManagedTunnelInfo, ManagedTunnelState,
+};
+use crate::protocol::{TunnelDirection, TunnelProtocol, TunnelStats};
+
+use super::{
+    InternalRawChannel, ManagedTunnel, StreamAddr, http, start_forward_tunnel, start_http_tunnel,
+    start_reverse_tunnel, start_socks_tunnel, start_udp_forward_tunnel, start_udp_reverse_tunnel,
+    stream_tunnel_info,
+};
+
+/// Description of a managed tunnel from which it can be started, mirroring the manager requests
+/// that start tunnels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ManagedTunnelSpec {
+    /// Forward tunnel (local listener → remote target)
+    Forward {
+        bind_port: u16,
+        remote_host: String,
+        remote_port: u16,
+        protocol: TunnelProtocol,
+        bind_path: Option<String>,
+        remote_path: Option<String>,
+    },
+
+    /// Reverse tunnel (remote listener → local target)
+    Reverse {
+        remote_port: u16,
+        local_host: String,
+        local_port: u16,
+        protocol: TunnelProtocol,
+        remote_path: Option<String>,
+        local_path: Option<String>,
+    },
+
+    /// Dynamic tunnel (local SOCKS proxy → any remote target)
+    Socks { bind_host: String, bind_port: u16 },
+
+    /// Local HTTP reverse proxy → remote target chosen by the `NAME.localhost` host of each request
+    Http {
+        bind_host: String,
+        bind_port: u16,
+        routes: Vec<HttpRoute>,
+    },
+}
+
+impl ManagedTunnelSpec {
+    /// Returns true if either end of the tunnel is a Unix socket path.
+    pub fn has_path(&self) -> bool {
+        match self {
+            Self::Forward {
+                bind_path,
+                remote_path,
+                ..
+            } => bind_path.is_some() || remote_path.is_some(),
+            Self::Reverse {
+                remote_path,
+                local_path,
+                ..
+            } => remote_path.is_some() || local_path.is_some(),
+            Self::Socks { .. } | Self::Http { .. } => false,
+        }
+    }
+
+    /// Returns the spec with the port that the tunnel listens on replaced by `port` when it
+    /// asked for any free port, so that starting it again listens on the same port as before.
+    pub fn with_bound_port(mut self, port: u16) -> Self {
+        let listen_port = match &mut self {
+            Self::Forward { bind_port, .. } => bind_port,
+            Self::Reverse { remote_port, .. } => remote_port,
+            Self::Socks { bind_port, .. } | Self::Http { bind_port, .. } => bind_port,
+        };
+
+        if *listen_port == 0 {
+            *listen_port = port;
+        }
+
+        self
+    }
+
+    /// Describes the tunnel as it would be listed once started, without starting it.
+    pub fn describe(&self, id: ManagedTunnelId, connection_id: ConnectionId) -> ManagedTunnelInfo {
+        let info =
+            |direction, bind_port, remote_host: &str, remote_port, protocol| ManagedTunnelInfo {
+                id,
+                connection_id,
+                direction,
+                bind_port,
+                remote_host: remote_host.to_string(),
+                remote_port,
+                protocol,
+                bind_path: None,
+                remote_path: None,
+                state: ManagedTunnelState::Active,
+                last_error: None,
+                stats: TunnelStats::default(),
+            };
+
+        match self {
+            Self::Forward {
+                bind_port,
+                remote_host,
+                remote_port,
+                protocol: TunnelProtocol::Udp,
+                ..
+            } => info(
+                TunnelDirection::Forward,
+                *bind_port,
+                remote_host,
+                *remote_port,
+                TunnelProtocol::Udp,
+            ),
+            Self::Forward {
+                bind_port,
+                remote_host,
+                remote_port,
+                bind_path,
+                remote_path,
+                ..
+            } => stream_tunnel_info(
+                id,
+                connection_id,
+                TunnelDirection::Forward,
+                &StreamAddr::new("127.0.0.1", *bind_port, bind_path.clone()),
+                StreamAddr::new(remote_host.as_str(), *remote_port, remote_path.clone()),
+            ),
+            Self::Reverse {
+                remote_port,
+                local_host,
+                local_port,
+                protocol: TunnelProtocol::Udp,
+                ..
+            } => info(
+                TunnelDirection::Reverse,
+                *remote_port,
+                local_host,
+                *local_port,
+                TunnelProtocol::Udp,
+            ),
+            Self::Reverse {
+                remote_port,
+                local_host,
+                local_port,
+                remote_path,
+                local_path,
+                ..
+            } => stream_tunnel_info(
+                id,
+                connection_id,
+                TunnelDirection::Reverse,
+                &StreamAddr::new("0.0.0.0", *remote_port, remote_path.clone()),
+                StreamAddr::new(local_host.as_str(), *local_port, local_path.clone()),
+            ),
+            Self::Socks { bind_port, .. } => info(
+                TunnelDirection::Dynamic,
+                *bind_port,
+                "*",
+                0,
+                TunnelProtocol::Tcp,
+            ),
+            Self::Http {
+                bind_port, routes, ..
+            } => info(
+                TunnelDirection::Dynamic,
+                *bind_port,
+                &http::describe_routes(routes),
+                0,
+                TunnelProtocol::Tcp,
+            ),
+        }
+    }
+
+    /// Starts the tunnel with the given `id` on the connection that `internal` was opened on.
+    ///
+    /// Returns the managed tunnel and the actual port that it listens on.
+    pub async fn start(
+        self,
+        internal: InternalRawChannel,
+        id: ManagedTunnelId,
+        connection_id: ConnectionId,
+    ) -> io::Result<(ManagedTunnel, u16)> {
+        match self {
+            Self::Forward {
+                bind_port,
+                remote_host,
+                remote_port,
+                protocol: TunnelProtocol::Udp,
+                ..
+            } => {
+                start_udp_forward_tunnel(
+                    internal,
+                    id,
+                    connection_id,
+                    bind_port,
+                    remote_host,
+                    remote_port,
+                )
+                .await
+            }
+            Self::Forward {
+                bind_port,
+                remote_host,
+                remote_port,
+                bind_path,
+                remote_path,
+                ..
+            } => {
+                start_forward_tunnel(
+                    internal,
+                    id,
+                    connection_id,
+                    StreamAddr::new("127.0.0.1", bind_port, bind_path),
+                    StreamAddr::new(remote_host, remote_port, remote_path),
+                )
+                .await
+            }
+            Self::Reverse {
+                remote_port,
+                local_host,
+                local_port,
+                protocol: TunnelProtocol::Udp,
+                ..
+            } => {
+                start_udp_reverse_tunnel(
+                    internal,
+                    id,
+                    connection_id,
+                    remote_port,
+                    local_host,
+                    local_port,
+                )
+                .await
+            }
+            Self::Reverse {
+                remote_port,
+                local_host,
+                local_port,
+                remote_path,
+                local_path,
+                ..
+            } => {
+                start_reverse_tunnel(
+                    internal,
+                    id,
+                    connection_id,
+                    StreamAddr::new("0.0.0.0", remote_port, remote_path),
+                    StreamAddr::new(local_host, local_port, local_path),
+                )
+                .await
+            }
+            Self::Socks {
+                bind_host,
+                bind_port,
+            } => start_socks_tunnel(internal, id, connection_id, bind_host, bind_port).await,
+            Self::Http {
+                bind_host,
+                bind_port,
+                routes,
+            } => start_http_tunnel(internal, id, connection_id, bind_host, bind_port, routes).await,
+        }
+    }
+}
+
+/// Where a managed tunnel came from, used to start it again on a new connection to the same
+/// destination after its own connection is lost.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManagedTunnelOrigin {
+    /// Destination of the connection that the tunnel was started on, as provided by the user
+    pub destination: String,
+
+    /// Description of the tunnel as it was requested
+    pub spec: ManagedTunnelSpec,
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for `ManagedTunnelSpec` describing tunnels and keeping the ports they listen on.
+
+    use super::*;
+
+    fn forward(bind_port: u16, protocol: TunnelProtocol) -> ManagedTunnelSpec {
+        ManagedTunnelSpec::Forward {
+            bind_port,
+            remote_host: "db-host".to_string(),
+            remote_port: 5432,
+            protocol,
+            bind_path: None,
+            remote_path: None,
+        }
+    }
+
+    #[test]
+    fn has_path_should_be_true_if_either_end_is_a_socket_path() {
+        assert!(!forward(8080, TunnelProtocol::Tcp).has_path());
+        assert!(
+            ManagedTunnelSpec::Reverse {
+                remote_port: 0,
+                local_host: String::new(),
+                local_port: 0,
+                protocol: TunnelProtocol::Unix,
+                remote_path: Some("/tmp/agent.sock".to_string()),
+                local_path: Some("/tmp/agent.sock".to_string()),
+            }
+            .has_path()
+        );
+        assert!(
+            !ManagedTunnelSpec::Socks {
+                bind_host: "127.0.0.1".to_string(),
+                bind_port: 1080,
+            }
+            .has_path()
+        );
+    }
+
+    #[test]
+    fn with_bound_port_should_only_replace_a_port_of_zero() {
+        assert_eq!(
+            forward(0, TunnelProtocol::Tcp).with_bound_port(41234),
+            forward(41234, TunnelProtocol::Tcp)
+        );
+        assert_eq!(
+            forward(8080, TunnelProtocol::Tcp).with_bound_port(41234),
+            forward(8080, TunnelProtocol::Tcp)
+        );
+
+        let socks = ManagedTunnelSpec::Socks {
+            bind_host: "127.0.0.1".to_string(),
+            bind_port: 0,
+        };
+        assert_eq!(
+            socks.with_bound_port(1080),
+            ManagedTunnelSpec::Socks {
+                bind_host: "127.0.0.1".to_string(),
+                bind_port: 1080,
+            }
+        );
+    }
+
+    #[test]
+    fn describe_should_report_forward_tunnel_ends() {
+        let info = forward(8080, TunnelProtocol::Udp).describe(3, 7);
+        assert_eq!(
+            info,
+            ManagedTunnelInfo {
+                id: 3,
+                connection_id: 7,
+                direction: TunnelDirection::Forward,
+                bind_port: 8080,
+                remote_host: "db-host".to_string(),
+                remote_port: 5432,
+                protocol: TunnelProtocol::Udp,
+                bind_path: None,
+                remote_path: None,
+                state: ManagedTunnelState::Active,
+                last_error: None,
+                stats: TunnelStats::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn describe_should_report_reverse_unix_socket_tunnel_paths() {
+        let spec = ManagedTunnelSpec::Reverse {
+            remote_port: 0,
+            local_host: String::new(),
+            local_port: 0,
+            protocol: TunnelProtocol::Unix,
+            remote_path: Some("/tmp/remote.sock".to_string()),
+            local_path: Some("/tmp/local.sock".to_string()),
+        };
+
+        let info = spec.describe(3, 7);
+        assert_eq!(info.direction, TunnelDirection::Reverse);
+        assert_eq!(info.protocol, TunnelProtocol::Unix);
+        assert_eq!(info.bind_path.as_deref(), Some("/tmp/remote.sock"));
+        assert_eq!(info.remote_path.as_deref(), Some("/tmp/local.sock"));
+    }
+
+    #[test]
+    fn describe_should_report_socks_tunnel_as_dynamic() {
+        let spec = ManagedTunnelSpec::Socks {
+            bind_host: "127.0.0.1".to_string(),
+            bind_port: 1080,
+        };
+
+        let info = spec.describe(3, 7);
+        assert_eq!(info.direction, TunnelDirection::Dynamic);
+        assert_eq!(info.bind_port, 1080);
+        assert_eq!(info.remote_host, "*");
+        assert_eq!(info.remote_port, 0);
+    }
+
+    #[test]
+    fn describe_should_report_http_tunnel_routes() {
+        let spec = ManagedTunnelSpec::Http {
+            bind_host: "127.0.0.1".to_string(),
+            bind_port: 8080,
+            routes: vec![
+                HttpRoute {
+                    name: "web".to_string(),
+                    host: "127.0.0.1".to_string(),
+                    port: 3000,
+                },
+                HttpRoute {
+                    name: "api".to_string(),
+                    host: "::1".to_string(),
+                    port: 4000,
+                },
+            ],
+        };
+        assert!(!spec.has_path());
+
+        let info = spec.with_bound_port(0).describe(3, 7);
+        assert_eq!(info.direction, TunnelDirection::Dynamic);
+        assert_eq!(info.bind_port, 8080);
+        assert_eq!(info.remote_host, "web=127.0.0.1:3000,api=[::1]:4000");
+        assert_eq!(info.remote_port, 0);
+    }
+}
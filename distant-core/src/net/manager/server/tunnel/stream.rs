@@ -0,0 +1,394 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::client::{Channel, ChannelExt, RemoteTunnel, RemoteTunnelListener};
+use crate::protocol::{TunnelConnectionGuard, TunnelCounters, TunnelDirection};
+
+/// Either end of a stream-oriented managed tunnel, which is a TCP address or the
+/// path to a Unix domain socket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamAddr {
+    Tcp { host: String, port: u16 },
+    Unix { path: String },
+}
+
+impl StreamAddr {
+    /// Creates an address that is the Unix socket at `path` when provided, or
+    /// otherwise `host:port`.
+    pub fn new(host: impl Into<String>, port: u16, path: Option<String>) -> Self {
+        match path {
+            Some(path) => Self::Unix { path },
+            None => Self::Tcp {
+                host: host.into(),
+                port,
+            },
+        }
+    }
+
+    /// Returns true if the address is a Unix socket path.
+    pub fn is_unix(&self) -> bool {
+        matches!(self, Self::Unix { .. })
+    }
+
+    /// Returns the host of a TCP address, or an empty string for a Unix socket.
+    pub fn host(&self) -> &str {
+        match self {
+            Self::Tcp { host, .. } => host,
+            Self::Unix { .. } => "",
+        }
+    }
+
+    /// Returns the port of a TCP address, or `0` for a Unix socket.
+    pub fn port(&self) -> u16 {
+        match self {
+            Self::Tcp { port, .. } => *port,
+            Self::Unix { .. } => 0,
+        }
+    }
+
+    /// Returns the path of a Unix socket, or `None` for a TCP address.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::Tcp { .. } => None,
+            Self::Unix { path } => Some(path),
+        }
+    }
+
+    /// Connects to the address from the manager's machine.
+    pub async fn connect_local(&self) -> io::Result<Box<dyn LocalStream>> {
+        match self {
+            Self::Tcp { host, port } => {
+                Ok(Box::new(TcpStream::connect((host.as_str(), *port)).await?))
+            }
+            #[cfg(unix)]
+            Self::Unix { path } => Ok(Box::new(UnixStream::connect(path).await?)),
+            #[cfg(not(unix))]
+            Self::Unix { .. } => Err(unix_sockets_unsupported()),
+        }
+    }
+
+    /// Opens a tunnel to the address from the remote server.
+    pub async fn open_remote(&self, channel: &mut Channel) -> io::Result<RemoteTunnel> {
+        match self {
+            Self::Tcp { host, port } => channel.tunnel_open(host.as_str(), *port).await,
+            Self::Unix { path } => channel.unix_tunnel_open(path.as_str()).await,
+        }
+    }
+
+    /// Asks the remote server to listen on the address, using every interface
+    /// for a TCP address.
+    pub async fn listen_remote(&self, channel: Channel) -> io::Result<RemoteTunnelListener> {
+        match self {
+            Self::Tcp { port, .. } => {
+                RemoteTunnelListener::listen(channel, "0.0.0.0".to_string(), *port).await
+            }
+            Self::Unix { path } => RemoteTunnelListener::listen_unix(channel, path.clone()).await,
+        }
+    }
+}
+
+impl fmt::Display for StreamAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp { host, port } => write!(f, "{host}:{port}"),
+            Self::Unix { path } => write!(f, "{path}"),
+        }
+    }
+}
+
+/// Bidirectional stream accepted or connected on the manager's machine.
+pub trait LocalStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> LocalStream for T {}
+
+/// Local end of a connection through a managed tunnel, which records the bytes passing
+/// through it in the tunnel's counters and counts as an active connection until dropped.
+///
+/// The local end is the tunnel's target for a reverse tunnel, so what is read from it is
+/// received from the target, whereas for other tunnels what is read from it is sent toward
+/// the target.
+pub struct CountedStream<S> {
+    inner: S,
+    counters: Arc<TunnelCounters>,
+    is_target: bool,
+    _connection: TunnelConnectionGuard,
+}
+
+impl<S> CountedStream<S> {
+    /// Wraps the local end `inner` of a connection through a tunnel going in `direction`.
+    pub fn new(inner: S, counters: &Arc<TunnelCounters>, direction: TunnelDirection) -> Self {
+        Self {
+            inner,
+            counters: Arc::clone(counters),
+            is_target: direction == TunnelDirection::Reverse,
+            _connection: counters.connection(),
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountedStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let n = buf.filled().len() - filled;
+        if n > 0 {
+            if self.is_target {
+                self.counters.record_in(n);
+            } else {
+                self.counters.record_out(n);
+            }
+        }
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountedStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = result
+            && n > 0
+        {
+            if self.is_target {
+                self.counters.record_out(n);
+            } else {
+                self.counters.record_in(n);
+            }
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Listener bound on the manager's machine for a forward tunnel.
+pub enum LocalListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix {
+        listener: UnixListener,
+        _socket_file: UnixSocketFile,
+    },
+}
+
+impl LocalListener {
+    /// Binds a listener to `addr`, returning it alongside the address that was
+    /// actually bound (a TCP port of `0` is replaced with the assigned port).
+    pub async fn bind(addr: &StreamAddr) -> io::Result<(Self, StreamAddr)> {
+        match addr {
+            StreamAddr::Tcp { host, port } => {
+                let listener = TcpListener::bind((host.as_str(), *port)).await?;
+                let port = listener.local_addr()?.port();
+                let addr = StreamAddr::Tcp {
+                    host: host.clone(),
+                    port,
+                };
+                Ok((Self::Tcp(listener), addr))
+            }
+            #[cfg(unix)]
+            StreamAddr::Unix { path } => {
+                let listener = UnixListener::bind(path)?;
+                let listener = Self::Unix {
+                    listener,
+                    _socket_file: UnixSocketFile(path.into()),
+                };
+                Ok((listener, addr.clone()))
+            }
+            #[cfg(not(unix))]
+            StreamAddr::Unix { .. } => Err(unix_sockets_unsupported()),
+        }
+    }
+
+    /// Accepts the next connection, returning it alongside a description of the peer.
+    pub async fn accept(&self) -> io::Result<(Box<dyn LocalStream>, String)> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, addr) = listener.accept().await?;
+                Ok((Box::new(stream), addr.to_string()))
+            }
+            #[cfg(unix)]
+            Self::Unix { listener, .. } => {
+                let (stream, addr) = listener.accept().await?;
+                let peer = addr
+                    .as_pathname()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| String::from("unnamed"));
+                Ok((Box::new(stream), peer))
+            }
+        }
+    }
+}
+
+/// Path of a socket file created by a Unix socket listener, which is removed when dropped.
+#[cfg(unix)]
+pub struct UnixSocketFile(std::path::PathBuf);
+
+#[cfg(unix)]
+impl Drop for UnixSocketFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.0) {
+            log::debug!("Failed to remove socket file {:?}: {e}", self.0);
+        }
+    }
+}
+
+/// Returns the error reported for Unix socket tunnels on platforms without Unix sockets.
+#[cfg(not(unix))]
+fn unix_sockets_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix socket tunnels are not supported on this platform",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for `StreamAddr` construction and display, and binding, accepting, and
+    //! connecting local TCP and Unix socket streams.
+
+    use test_log::test;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[test]
+    fn new_should_create_tcp_address_when_no_path_given() {
+        let addr = StreamAddr::new("localhost", 8080, None);
+        assert_eq!(
+            addr,
+            StreamAddr::Tcp {
+                host: "localhost".to_string(),
+                port: 8080
+            }
+        );
+        assert!(!addr.is_unix());
+        assert_eq!(addr.host(), "localhost");
+        assert_eq!(addr.port(), 8080);
+        assert_eq!(addr.path(), None);
+    }
+
+    #[test]
+    fn new_should_create_unix_address_when_path_given() {
+        let addr = StreamAddr::new("localhost", 8080, Some("/tmp/app.sock".to_string()));
+        assert_eq!(
+            addr,
+            StreamAddr::Unix {
+                path: "/tmp/app.sock".to_string()
+            }
+        );
+        assert!(addr.is_unix());
+        assert_eq!(addr.host(), "");
+        assert_eq!(addr.port(), 0);
+        assert_eq!(addr.path(), Some("/tmp/app.sock"));
+    }
+
+    #[test]
+    fn display_should_show_host_and_port_or_path() {
+        assert_eq!(
+            StreamAddr::new("example.com", 443, None).to_string(),
+            "example.com:443"
+        );
+        assert_eq!(
+            StreamAddr::new("", 0, Some("/var/run/docker.sock".to_string())).to_string(),
+            "/var/run/docker.sock"
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn local_listener_should_replace_port_zero_with_bound_port() {
+        let (listener, addr) = LocalListener::bind(&StreamAddr::new("127.0.0.1", 0, None))
+            .await
+            .unwrap();
+        assert_ne!(addr.port(), 0);
+
+        let mut client = addr.connect_local().await.unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn local_listener_should_accept_unix_connections_and_remove_socket_file_when_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.sock").to_string_lossy().into_owned();
+
+        let (listener, addr) = LocalListener::bind(&StreamAddr::new("", 0, Some(path.clone())))
+            .await
+            .unwrap();
+        assert_eq!(addr.path(), Some(path.as_str()));
+
+        let mut client = addr.connect_local().await.unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+
+        server.write_all(b"pong").await.unwrap();
+        let mut buf = [0u8; 4];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pong");
+
+        drop(listener);
+        assert!(!std::path::Path::new(&path).exists());
+    }
+
+    #[test(tokio::test)]
+    async fn counted_stream_should_count_reads_as_sent_toward_target_of_forward_tunnel() {
+        let counters = TunnelCounters::new();
+        let (local, mut peer) = tokio::io::duplex(64);
+        let mut stream = CountedStream::new(local, &counters, TunnelDirection::Forward);
+        assert_eq!(counters.stats().active_connections, 1);
+
+        peer.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(b"hi").await.unwrap();
+
+        let stats = counters.stats();
+        assert_eq!(stats.bytes_out, 5);
+        assert_eq!(stats.bytes_in, 2);
+
+        drop(stream);
+        let stats = counters.stats();
+        assert_eq!(stats.active_connections, 0);
+        assert_eq!(stats.total_connections, 1);
+    }
+
+    #[test(tokio::test)]
+    async fn counted_stream_should_count_reads_as_received_from_target_of_reverse_tunnel() {
+        let counters = TunnelCounters::new();
+        let (local, mut peer) = tokio::io::duplex(64);
+        let mut stream = CountedStream::new(local, &counters, TunnelDirection::Reverse);
+
+        peer.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(b"hi").await.unwrap();
+
+        let stats = counters.stats();
+        assert_eq!(stats.bytes_in, 5);
+        assert_eq!(stats.bytes_out, 2);
+    }
+}
@@ -9,7 +9,7 @@ use serde::Serialize;
 use serde::de::DeserializeOwned;
 use tokio::sync::{RwLock, broadcast};
 
-use crate::net::common::{ConnectionId, Listener, Response, Transport, Version};
+use crate::net::common::{ConnectionId, Listener, Response, TlsServerConfig, Transport, Version};
 
 mod builder;
 pub use builder::*;
@@ -95,6 +95,22 @@ impl Server<()> {
         TcpServerBuilder::default()
     }
 
+    /// Creates a new [`QuicServerBuilder`] that is used to construct a [`Server`].
+    pub fn quic() -> QuicServerBuilder<()> {
+        QuicServerBuilder::default()
+    }
+
+    /// Creates a new [`TlsServerBuilder`] that is used to construct a [`Server`] whose
+    /// connections are secured with the certificate in `config`.
+    pub fn tls(config: TlsServerConfig) -> TlsServerBuilder<()> {
+        TlsServerBuilder::new(config)
+    }
+
+    /// Creates a new [`WebSocketServerBuilder`] that is used to construct a [`Server`].
+    pub fn websocket() -> WebSocketServerBuilder<()> {
+        WebSocketServerBuilder::default()
+    }
+
     /// Creates a new [`UnixSocketServerBuilder`] that is used to construct a [`Server`].
     #[cfg(unix)]
     pub fn unix_socket() -> UnixSocketServerBuilder<()> {
@@ -242,6 +258,8 @@ where
                     .heartbeat_duration(config.connection_heartbeat)
                     .verifier(Arc::downgrade(&verifier))
                     .version(version.clone())
+                    .identity(config.identity.clone())
+                    .rekey(config.rekey)
                     .spawn(),
             );
 
@@ -1,4 +1,7 @@
+mod quic;
 mod tcp;
+mod tls;
+mod websocket;
 
 #[cfg(unix)]
 mod unix;
@@ -6,8 +9,11 @@ mod unix;
 #[cfg(windows)]
 mod windows;
 
+pub use quic::*;
 pub use tcp::*;
+pub use tls::*;
 #[cfg(unix)]
 pub use unix::*;
+pub use websocket::*;
 #[cfg(windows)]
 pub use windows::*;
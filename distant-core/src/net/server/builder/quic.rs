@@ -0,0 +1,109 @@
+use std::io;
+use std::net::IpAddr;
+
+use crate::auth::Verifier;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::net::common::{PortRange, QuicListener, Version};
+use crate::net::server::{Server, ServerConfig, ServerHandler, TcpServerRef};
+
+pub struct QuicServerBuilder<T>(Server<T>);
+
+impl<T> Server<T> {
+    /// Consume [`Server`] and produce a builder for a QUIC variant.
+    pub fn into_quic_builder(self) -> QuicServerBuilder<T> {
+        QuicServerBuilder(self)
+    }
+}
+
+impl Default for QuicServerBuilder<()> {
+    fn default() -> Self {
+        Self(Server::new())
+    }
+}
+
+impl<T> QuicServerBuilder<T> {
+    pub fn config(self, config: ServerConfig) -> Self {
+        Self(self.0.config(config))
+    }
+
+    pub fn handler<U>(self, handler: U) -> QuicServerBuilder<U> {
+        QuicServerBuilder(self.0.handler(handler))
+    }
+
+    pub fn verifier(self, verifier: Verifier) -> Self {
+        Self(self.0.verifier(verifier))
+    }
+
+    pub fn version(self, version: Version) -> Self {
+        Self(self.0.version(version))
+    }
+}
+
+impl<T> QuicServerBuilder<T>
+where
+    T: ServerHandler + Sync + 'static,
+    T::Request: DeserializeOwned + Send + Sync + 'static,
+    T::Response: Serialize + Send + 'static,
+{
+    /// Starts the server, accepting QUIC connections over UDP at the specified IP address
+    /// and port in the given port range.
+    pub async fn start<P>(self, addr: IpAddr, port: P) -> io::Result<TcpServerRef>
+    where
+        P: Into<PortRange> + Send,
+    {
+        let listener = QuicListener::bind(addr, port).await?;
+        let port = listener.port();
+        let inner = self.0.start(listener)?;
+        Ok(TcpServerRef { addr, port, inner })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use crate::auth::DummyAuthHandler;
+    use test_log::test;
+
+    use super::*;
+    use crate::net::client::Client;
+    use crate::net::common::Request;
+    use crate::net::server::RequestCtx;
+
+    pub struct TestServerHandler;
+
+    impl ServerHandler for TestServerHandler {
+        type Request = String;
+        type Response = String;
+
+        async fn on_request(&self, ctx: RequestCtx<Self::Request, Self::Response>) {
+            // Echo back what we received
+            ctx.reply.send(ctx.request.payload.to_string()).unwrap();
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn should_invoke_handler_upon_receiving_a_request() {
+        let server = QuicServerBuilder::default()
+            .handler(TestServerHandler)
+            .verifier(Verifier::none())
+            .start(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to start QUIC server");
+
+        let mut client: Client<String, String> =
+            Client::quic(SocketAddr::from((Ipv4Addr::LOCALHOST, server.port())))
+                .auth_handler(DummyAuthHandler)
+                .connect()
+                .await
+                .expect("Client failed to connect");
+
+        let response = client
+            .send(Request::new("hello".to_string()))
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.payload, "hello");
+    }
+}
@@ -0,0 +1,191 @@
+use std::io;
+use std::net::IpAddr;
+
+use crate::auth::Verifier;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::net::common::{PortRange, TlsListener, TlsServerConfig, Version};
+use crate::net::server::{Server, ServerConfig, ServerHandler, TcpServerRef};
+
+pub struct TlsServerBuilder<T> {
+    server: Server<T>,
+    tls: TlsServerConfig,
+}
+
+impl<T> Server<T> {
+    /// Consume [`Server`] and produce a builder for a TLS variant that secures connections with
+    /// the certificate in `config`.
+    pub fn into_tls_builder(self, config: TlsServerConfig) -> TlsServerBuilder<T> {
+        TlsServerBuilder {
+            server: self,
+            tls: config,
+        }
+    }
+}
+
+impl TlsServerBuilder<()> {
+    pub fn new(config: TlsServerConfig) -> Self {
+        Server::new().into_tls_builder(config)
+    }
+}
+
+impl<T> TlsServerBuilder<T> {
+    pub fn config(self, config: ServerConfig) -> Self {
+        Self {
+            server: self.server.config(config),
+            tls: self.tls,
+        }
+    }
+
+    pub fn handler<U>(self, handler: U) -> TlsServerBuilder<U> {
+        TlsServerBuilder {
+            server: self.server.handler(handler),
+            tls: self.tls,
+        }
+    }
+
+    pub fn verifier(self, verifier: Verifier) -> Self {
+        Self {
+            server: self.server.verifier(verifier),
+            tls: self.tls,
+        }
+    }
+
+    pub fn version(self, version: Version) -> Self {
+        Self {
+            server: self.server.version(version),
+            tls: self.tls,
+        }
+    }
+}
+
+impl<T> TlsServerBuilder<T>
+where
+    T: ServerHandler + Sync + 'static,
+    T::Request: DeserializeOwned + Send + Sync + 'static,
+    T::Response: Serialize + Send + 'static,
+{
+    /// Starts the server, accepting TLS connections over TCP at the specified IP address and port
+    /// in the given port range.
+    pub async fn start<P>(self, addr: IpAddr, port: P) -> io::Result<TcpServerRef>
+    where
+        P: Into<PortRange> + Send,
+    {
+        let listener = TlsListener::bind(addr, port, self.tls).await?;
+        let port = listener.port();
+        let inner = self.server.start(listener)?;
+        Ok(TcpServerRef { addr, port, inner })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use crate::auth::DummyAuthHandler;
+    use test_log::test;
+
+    use super::*;
+    use crate::net::client::{Client, TlsConnector};
+    use crate::net::common::{Request, TlsClientConfig, test_certs};
+    use crate::net::server::RequestCtx;
+
+    pub struct TestServerHandler;
+
+    impl ServerHandler for TestServerHandler {
+        type Request = String;
+        type Response = String;
+
+        async fn on_request(&self, ctx: RequestCtx<Self::Request, Self::Response>) {
+            // Echo back what we received
+            ctx.reply.send(ctx.request.payload.to_string()).unwrap();
+        }
+    }
+
+    async fn start_server(
+        server: &test_certs::TestCerts,
+        client_ca: Option<&str>,
+        verifier: Verifier,
+    ) -> TcpServerRef {
+        let config = TlsServerConfig::from_pem(
+            server.cert.as_bytes(),
+            server.key.as_bytes(),
+            client_ca.map(str::as_bytes),
+        )
+        .unwrap();
+
+        TlsServerBuilder::new(config)
+            .handler(TestServerHandler)
+            .verifier(verifier)
+            .start(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to start TLS server")
+    }
+
+    #[test(tokio::test)]
+    async fn should_invoke_handler_upon_receiving_a_request() {
+        let (server_certs, _) = test_certs::generate("client");
+        let server = start_server(&server_certs, None, Verifier::none()).await;
+        let config = TlsClientConfig::from_pem(Some(server_certs.ca.as_bytes()), None).unwrap();
+
+        let mut client: Client<String, String> =
+            Client::tls(TlsConnector::new("localhost", server.port(), config))
+                .auth_handler(DummyAuthHandler)
+                .connect()
+                .await
+                .expect("Client failed to connect");
+
+        let response = client
+            .send(Request::new("hello".to_string()))
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.payload, "hello");
+    }
+
+    #[test(tokio::test)]
+    async fn should_authenticate_client_using_its_certificate() {
+        let (server_certs, client_certs) = test_certs::generate("alice");
+        let server = start_server(
+            &server_certs,
+            Some(&client_certs.ca),
+            Verifier::certificate(),
+        )
+        .await;
+        let config = TlsClientConfig::from_pem(
+            Some(server_certs.ca.as_bytes()),
+            Some((client_certs.cert.as_bytes(), client_certs.key.as_bytes())),
+        )
+        .unwrap();
+
+        let mut client: Client<String, String> =
+            Client::tls(TlsConnector::new("localhost", server.port(), config))
+                .auth_handler(DummyAuthHandler)
+                .connect()
+                .await
+                .expect("Client failed to connect");
+
+        let response = client
+            .send(Request::new("hello".to_string()))
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.payload, "hello");
+    }
+
+    #[test(tokio::test)]
+    async fn should_fail_certificate_authentication_without_mutual_tls() {
+        let (server_certs, _) = test_certs::generate("alice");
+        let server = start_server(&server_certs, None, Verifier::certificate()).await;
+        let config = TlsClientConfig::from_pem(Some(server_certs.ca.as_bytes()), None).unwrap();
+
+        let result: io::Result<Client<String, String>> =
+            Client::tls(TlsConnector::new("localhost", server.port(), config))
+                .auth_handler(DummyAuthHandler)
+                .connect()
+                .await;
+        assert!(
+            result.is_err(),
+            "Client unexpectedly authenticated without a certificate"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::io;
+use std::net::IpAddr;
+
+use crate::auth::Verifier;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::net::common::{PortRange, Version, WebSocketListener};
+use crate::net::server::{Server, ServerConfig, ServerHandler, TcpServerRef};
+
+pub struct WebSocketServerBuilder<T>(Server<T>);
+
+impl<T> Server<T> {
+    /// Consume [`Server`] and produce a builder for a WebSocket variant.
+    pub fn into_websocket_builder(self) -> WebSocketServerBuilder<T> {
+        WebSocketServerBuilder(self)
+    }
+}
+
+impl Default for WebSocketServerBuilder<()> {
+    fn default() -> Self {
+        Self(Server::new())
+    }
+}
+
+impl<T> WebSocketServerBuilder<T> {
+    pub fn config(self, config: ServerConfig) -> Self {
+        Self(self.0.config(config))
+    }
+
+    pub fn handler<U>(self, handler: U) -> WebSocketServerBuilder<U> {
+        WebSocketServerBuilder(self.0.handler(handler))
+    }
+
+    pub fn verifier(self, verifier: Verifier) -> Self {
+        Self(self.0.verifier(verifier))
+    }
+
+    pub fn version(self, version: Version) -> Self {
+        Self(self.0.version(version))
+    }
+}
+
+impl<T> WebSocketServerBuilder<T>
+where
+    T: ServerHandler + Sync + 'static,
+    T::Request: DeserializeOwned + Send + Sync + 'static,
+    T::Response: Serialize + Send + 'static,
+{
+    /// Starts the server, accepting WebSocket connections over TCP at the specified IP address
+    /// and port in the given port range.
+    pub async fn start<P>(self, addr: IpAddr, port: P) -> io::Result<TcpServerRef>
+    where
+        P: Into<PortRange> + Send,
+    {
+        let listener = WebSocketListener::bind(addr, port).await?;
+        let port = listener.port();
+        let inner = self.0.start(listener)?;
+        Ok(TcpServerRef { addr, port, inner })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use crate::auth::DummyAuthHandler;
+    use test_log::test;
+
+    use super::*;
+    use crate::net::client::Client;
+    use crate::net::common::Request;
+    use crate::net::server::RequestCtx;
+
+    pub struct TestServerHandler;
+
+    impl ServerHandler for TestServerHandler {
+        type Request = String;
+        type Response = String;
+
+        async fn on_request(&self, ctx: RequestCtx<Self::Request, Self::Response>) {
+            // Echo back what we received
+            ctx.reply.send(ctx.request.payload.to_string()).unwrap();
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn should_invoke_handler_upon_receiving_a_request() {
+        let server = WebSocketServerBuilder::default()
+            .handler(TestServerHandler)
+            .verifier(Verifier::none())
+            .start(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to start WebSocket server");
+
+        let mut client: Client<String, String> =
+            Client::websocket(format!("ws://127.0.0.1:{}", server.port()))
+                .auth_handler(DummyAuthHandler)
+                .connect()
+                .await
+                .expect("Client failed to connect");
+
+        let response = client
+            .send(Request::new("hello".to_string()))
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.payload, "hello");
+    }
+}
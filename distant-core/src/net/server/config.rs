@@ -5,6 +5,8 @@ use std::time::Duration;
 use derive_more::{Display, Error};
 use serde::{Deserialize, Serialize};
 
+use crate::net::common::{RekeyConfig, ServerIdentity};
+
 const DEFAULT_CONNECTION_SLEEP: Duration = Duration::from_millis(1);
 const DEFAULT_HEARTBEAT_DURATION: Duration = Duration::from_secs(5);
 
@@ -19,6 +21,15 @@ pub struct ServerConfig {
 
     /// Rules for how a server will shutdown automatically
     pub shutdown: Shutdown,
+
+    /// Thresholds at which connections replace the key used to encrypt their traffic
+    #[serde(default)]
+    pub rekey: RekeyConfig,
+
+    /// Identity used to sign key exchanges so clients can verify that they are talking to this
+    /// server, which is not sent over the wire as part of the configuration
+    #[serde(skip)]
+    pub identity: Option<ServerIdentity>,
 }
 
 impl Default for ServerConfig {
@@ -27,6 +38,8 @@ impl Default for ServerConfig {
             connection_sleep: DEFAULT_CONNECTION_SLEEP,
             connection_heartbeat: DEFAULT_HEARTBEAT_DURATION,
             shutdown: Default::default(),
+            rekey: Default::default(),
+            identity: None,
         }
     }
 }
@@ -174,6 +187,8 @@ mod tests {
             connection_sleep: Duration::from_millis(50),
             connection_heartbeat: Duration::from_secs(10),
             shutdown: Shutdown::After(Duration::from_secs(30)),
+            rekey: RekeyConfig::disabled(),
+            identity: None,
         };
         let serialized = serde_json::to_string(&config).unwrap();
         let deserialized: ServerConfig = serde_json::from_str(&serialized).unwrap();
@@ -186,6 +201,11 @@ mod tests {
             connection_sleep: Duration::from_millis(10),
             connection_heartbeat: Duration::from_secs(3),
             shutdown: Shutdown::Lonely(Duration::from_secs(60)),
+            rekey: RekeyConfig {
+                max_frames: Some(100),
+                ..Default::default()
+            },
+            identity: None,
         };
         let serialized = serde_json::to_string(&config).unwrap();
         let deserialized: ServerConfig = serde_json::from_str(&serialized).unwrap();
@@ -14,7 +14,8 @@ use tokio::task::JoinHandle;
 
 use super::{ConnectionState, RequestCtx, ServerHandler, ServerReply, ServerState, ShutdownTimer};
 use crate::net::common::{
-    Backup, Connection, Frame, Interest, Keychain, Response, Transport, UntypedRequest, Version,
+    Backup, Connection, Frame, Interest, Keychain, RekeyConfig, Response, ServerIdentity,
+    Transport, UntypedRequest, Version,
 };
 
 pub type ServerKeychain = Keychain<oneshot::Receiver<Backup>>;
@@ -25,6 +26,9 @@ const SLEEP_DURATION: Duration = Duration::from_millis(1);
 /// Minimum time between heartbeats to communicate to the client connection.
 const MINIMUM_HEARTBEAT_DURATION: Duration = Duration::from_secs(5);
 
+/// Channel of heartbeats, which keeps them from holding up responses on a multiplexed transport.
+const HEARTBEAT_CHANNEL: &str = "heartbeat";
+
 /// Represents an individual connection on the server.
 pub(super) struct ConnectionTask(JoinHandle<io::Result<()>>);
 
@@ -66,6 +70,8 @@ pub(super) struct ConnectionTaskBuilder<H, S, T> {
     heartbeat_duration: Duration,
     verifier: Weak<Verifier>,
     version: Version,
+    identity: Option<ServerIdentity>,
+    rekey: RekeyConfig,
 }
 
 impl ConnectionTaskBuilder<(), (), ()> {
@@ -82,6 +88,8 @@ impl ConnectionTaskBuilder<(), (), ()> {
             heartbeat_duration: MINIMUM_HEARTBEAT_DURATION,
             verifier: Weak::new(),
             version: Version::default(),
+            identity: None,
+            rekey: RekeyConfig::default(),
         }
     }
 }
@@ -99,6 +107,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
             version: self.version,
+            identity: self.identity,
+            rekey: self.rekey,
         }
     }
 
@@ -114,6 +124,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
             version: self.version,
+            identity: self.identity,
+            rekey: self.rekey,
         }
     }
 
@@ -129,6 +141,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
             version: self.version,
+            identity: self.identity,
+            rekey: self.rekey,
         }
     }
 
@@ -144,6 +158,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
             version: self.version,
+            identity: self.identity,
+            rekey: self.rekey,
         }
     }
 
@@ -159,6 +175,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
             version: self.version,
+            identity: self.identity,
+            rekey: self.rekey,
         }
     }
 
@@ -177,6 +195,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
             version: self.version,
+            identity: self.identity,
+            rekey: self.rekey,
         }
     }
 
@@ -192,6 +212,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
             version: self.version,
+            identity: self.identity,
+            rekey: self.rekey,
         }
     }
 
@@ -210,6 +232,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration,
             verifier: self.verifier,
             version: self.version,
+            identity: self.identity,
+            rekey: self.rekey,
         }
     }
 
@@ -225,6 +249,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration: self.heartbeat_duration,
             verifier,
             version: self.version,
+            identity: self.identity,
+            rekey: self.rekey,
         }
     }
 
@@ -240,6 +266,42 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
             version,
+            identity: self.identity,
+            rekey: self.rekey,
+        }
+    }
+
+    pub fn identity(self, identity: Option<ServerIdentity>) -> ConnectionTaskBuilder<H, S, T> {
+        ConnectionTaskBuilder {
+            handler: self.handler,
+            state: self.state,
+            keychain: self.keychain,
+            transport: self.transport,
+            shutdown: self.shutdown,
+            shutdown_timer: self.shutdown_timer,
+            sleep_duration: self.sleep_duration,
+            heartbeat_duration: self.heartbeat_duration,
+            verifier: self.verifier,
+            version: self.version,
+            identity,
+            rekey: self.rekey,
+        }
+    }
+
+    pub fn rekey(self, rekey: RekeyConfig) -> ConnectionTaskBuilder<H, S, T> {
+        ConnectionTaskBuilder {
+            handler: self.handler,
+            state: self.state,
+            keychain: self.keychain,
+            transport: self.transport,
+            shutdown: self.shutdown,
+            shutdown_timer: self.shutdown_timer,
+            sleep_duration: self.sleep_duration,
+            heartbeat_duration: self.heartbeat_duration,
+            verifier: self.verifier,
+            version: self.version,
+            identity: self.identity,
+            rekey,
         }
     }
 }
@@ -267,6 +329,8 @@ where
             heartbeat_duration,
             verifier,
             version,
+            identity,
+            rekey,
         } = self;
 
         // NOTE: This exists purely to make the compiler happy for macro_rules declaration order.
@@ -436,7 +500,8 @@ where
                     transport,
                     verifier.as_ref(),
                     keychain,
-                    version
+                    version,
+                    identity
                 ))) {
                     Ok(connection) => connection,
                     Err(x) => {
@@ -451,6 +516,7 @@ where
 
         // Update our id to be the connection id
         let id = connection.id();
+        connection.set_rekey_config(rekey);
 
         // Create local data for the connection and then process it
         info!("[Conn {id}] Connection established");
@@ -556,7 +622,7 @@ where
                 // Send a heartbeat if we have exceeded our last time
                 if last_heartbeat.elapsed() >= heartbeat_duration {
                     trace!("[Conn {id}] Sending heartbeat via empty frame");
-                    match connection.try_write_frame(Frame::empty()) {
+                    match connection.try_write_frame_on(HEARTBEAT_CHANNEL, Frame::empty()) {
                         Ok(()) => (),
                         Err(x) if x.kind() == io::ErrorKind::WouldBlock => write_blocked = true,
                         Err(x) => error!("[Conn {id}] Send failed: {x}"),
@@ -578,12 +644,16 @@ where
                         );
                     }
 
-                    match response.to_vec() {
-                        Ok(data) => match connection.try_write_frame(data) {
-                            Ok(()) => (),
-                            Err(x) if x.kind() == io::ErrorKind::WouldBlock => write_blocked = true,
-                            Err(x) => error!("[Conn {id}] Send failed: {x}"),
-                        },
+                    match response.to_vec_with_format(connection.packet_format()) {
+                        Ok(data) => {
+                            match connection.try_write_frame_on(&response.origin_id, data) {
+                                Ok(()) => (),
+                                Err(x) if x.kind() == io::ErrorKind::WouldBlock => {
+                                    write_blocked = true
+                                }
+                                Err(x) => error!("[Conn {id}] Send failed: {x}"),
+                            }
+                        }
                         Err(x) => {
                             error!("[Conn {id}] Unable to serialize outgoing response: {x}");
                         }
@@ -953,11 +1023,8 @@ mod tests {
                 .expect("Fail to establish client-side connection");
 
             client.write_frame_for(&Request::new(123u16)).await.unwrap();
-            client
-                .read_frame_as::<Response<String>>()
-                .await
-                .unwrap()
-                .unwrap()
+            let frame = client.read_frame().await.unwrap().unwrap();
+            Response::<String>::from_slice(frame.as_item()).unwrap()
         });
 
         let response = task.await.unwrap();
@@ -4,6 +4,8 @@ mod error;
 mod filesystem;
 mod metadata;
 mod permissions;
+mod port;
+mod process;
 mod pty;
 mod remote_path;
 mod search;
@@ -18,6 +20,8 @@ pub use error::*;
 pub use filesystem::*;
 pub use metadata::*;
 pub use permissions::*;
+pub use port::*;
+pub use process::*;
 pub use pty::*;
 pub use remote_path::*;
 pub use search::*;
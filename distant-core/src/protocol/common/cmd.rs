@@ -1,3 +1,4 @@
+use std::io;
 use std::ops::{Deref, DerefMut};
 
 use derive_more::{Display, From, Into};
@@ -12,6 +13,106 @@ impl Cmd {
     pub fn new(cmd: impl Into<String>) -> Self {
         Self(cmd.into())
     }
+
+    /// Wraps this command such that it is run by `shell` as a login shell, which loads the user's
+    /// profile (e.g. `.profile`, `.bash_profile`, or `.zprofile`) before running it.
+    ///
+    /// * For POSIX shells such as `sh`, `bash`, and `zsh`, as well as `fish`, this produces
+    ///   `shell -l -c '...'`. The command is quoted such that both a POSIX shell and `fish` parse
+    ///   it back verbatim, as either could be the one interpreting the result.
+    /// * For `pwsh` on Unix, this produces `pwsh -Login -Command '...'` with the same quoting.
+    /// * For `powershell.exe` and `pwsh.exe`, which load the user's profile by default, this
+    ///   produces `shell -NoLogo -Command "..."`, escaping double quotes as `\"`.
+    /// * For `cmd.exe`, which runs its AutoRun commands by default, this produces
+    ///   `shell /S /C "..."`.
+    ///
+    /// An empty command starts the shell interactively as a login shell. Fails as unsupported for
+    /// shells that cannot run a command as a login shell, such as `csh`.
+    pub fn into_login_shell(self, shell: &str) -> io::Result<Self> {
+        let name = shell
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(shell)
+            .to_ascii_lowercase();
+        let is_exe = name.ends_with(".exe");
+        let name = name.strip_suffix(".exe").unwrap_or(&name);
+        let cmd = self.0;
+
+        // Paths containing spaces (e.g. within "Program Files") need to be quoted themselves
+        let posix_shell = if shell.contains(char::is_whitespace) {
+            quote_posix_and_fish(shell)
+        } else {
+            shell.to_string()
+        };
+        let windows_shell = if shell.contains(char::is_whitespace) {
+            format!("\"{shell}\"")
+        } else {
+            shell.to_string()
+        };
+
+        let wrapped = match name {
+            "sh" | "ash" | "bash" | "dash" | "ksh" | "loksh" | "mksh" | "pdksh" | "zsh"
+            | "fish" => {
+                if cmd.is_empty() {
+                    format!("{posix_shell} -l")
+                } else {
+                    format!("{posix_shell} -l -c {}", quote_posix_and_fish(&cmd))
+                }
+            }
+            "pwsh" if !is_exe => {
+                if cmd.is_empty() {
+                    format!("{posix_shell} -Login")
+                } else {
+                    format!(
+                        "{posix_shell} -Login -Command {}",
+                        quote_posix_and_fish(&cmd)
+                    )
+                }
+            }
+            "powershell" | "pwsh" => {
+                if cmd.is_empty() {
+                    format!("{windows_shell} -NoLogo")
+                } else {
+                    format!(
+                        "{windows_shell} -NoLogo -Command \"{}\"",
+                        cmd.replace('"', "\\\"")
+                    )
+                }
+            }
+            "cmd" => {
+                if cmd.is_empty() {
+                    windows_shell
+                } else {
+                    format!("{windows_shell} /S /C \"{cmd}\"")
+                }
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("Running a command through {shell} as a login shell is not supported"),
+                ));
+            }
+        };
+
+        Ok(Self(wrapped))
+    }
+}
+
+/// Wraps `s` in single quotes such that POSIX shells and `fish` both parse it back verbatim.
+///
+/// Single quotes are escaped with the `'\''` trick, and backslashes are placed outside of the
+/// quotes as `\\`, since `fish` (unlike POSIX shells) treats them as escapes within single quotes.
+fn quote_posix_and_fish(s: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in s.chars() {
+        match c {
+            '\'' => quoted.push_str(r"'\''"),
+            '\\' => quoted.push_str(r"'\\'"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
 }
 
 impl Deref for Cmd {
@@ -132,4 +233,69 @@ mod tests {
         let b = Cmd::new("bar");
         assert_ne!(a, b);
     }
+
+    #[test]
+    fn into_login_shell_should_use_login_flag_for_posix_shells() {
+        for shell in ["sh", "/bin/bash", "/usr/bin/zsh", "/bin/dash"] {
+            let cmd = Cmd::new("echo $PATH").into_login_shell(shell).unwrap();
+            assert_eq!(*cmd, format!("{shell} -l -c 'echo $PATH'"));
+        }
+    }
+
+    #[test]
+    fn into_login_shell_should_quote_single_quotes_and_backslashes() {
+        let cmd = Cmd::new(r"echo 'a' \n")
+            .into_login_shell("/usr/bin/fish")
+            .unwrap();
+        assert_eq!(&*cmd, r"/usr/bin/fish -l -c 'echo '\''a'\'' '\\'n'");
+    }
+
+    #[test]
+    fn into_login_shell_should_start_interactive_login_shell_if_cmd_empty() {
+        let cmd = Cmd::new("").into_login_shell("/bin/bash").unwrap();
+        assert_eq!(&*cmd, "/bin/bash -l");
+    }
+
+    #[test]
+    fn into_login_shell_should_support_pwsh_on_unix() {
+        let cmd = Cmd::new("Get-Location")
+            .into_login_shell("/usr/bin/pwsh")
+            .unwrap();
+        assert_eq!(&*cmd, "/usr/bin/pwsh -Login -Command 'Get-Location'");
+    }
+
+    #[test]
+    fn into_login_shell_should_escape_double_quotes_for_powershell() {
+        let cmd = Cmd::new(r#"echo "hi""#)
+            .into_login_shell("powershell.exe")
+            .unwrap();
+        assert_eq!(&*cmd, r#"powershell.exe -NoLogo -Command "echo \"hi\"""#);
+    }
+
+    #[test]
+    fn into_login_shell_should_quote_shell_paths_containing_spaces() {
+        let cmd = Cmd::new("Get-Location")
+            .into_login_shell(r"C:\Program Files\PowerShell\7\pwsh.exe")
+            .unwrap();
+        assert_eq!(
+            &*cmd,
+            r#""C:\Program Files\PowerShell\7\pwsh.exe" -NoLogo -Command "Get-Location""#
+        );
+    }
+
+    #[test]
+    fn into_login_shell_should_wrap_in_double_quotes_for_cmd_exe() {
+        let cmd = Cmd::new("echo %PATH%")
+            .into_login_shell(r"C:\Windows\system32\cmd.exe")
+            .unwrap();
+        assert_eq!(&*cmd, r#"C:\Windows\system32\cmd.exe /S /C "echo %PATH%""#);
+    }
+
+    #[test]
+    fn into_login_shell_should_fail_for_unsupported_shells() {
+        let err = Cmd::new("echo hi")
+            .into_login_shell("/bin/tcsh")
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
 }
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+/// A TCP port that a process on the server's machine is listening on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ListeningPort {
+    /// Local address that the socket is bound to, such as `127.0.0.1`, `0.0.0.0`, or `::`.
+    pub address: String,
+
+    /// Port that the socket is bound to.
+    pub port: u16,
+}
+
+impl ListeningPort {
+    /// Returns true if the socket accepts connections on every interface.
+    pub fn is_unspecified(&self) -> bool {
+        matches!(self.address.as_str(), "0.0.0.0" | "::" | "*")
+    }
+
+    /// Parses the listening TCP sockets from the output of `ss -ltn`, `netstat -ltn`, or (on
+    /// Windows) `netstat -an -p tcp`, skipping headers and any lines that cannot be understood.
+    ///
+    /// Sockets bound to the same address and port, which are reported once per address family
+    /// by some tools, are only returned once.
+    pub fn parse_socket_table(output: &str) -> Vec<Self> {
+        let mut ports: Vec<Self> = Vec::new();
+
+        for line in output.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            // ss:             LISTEN 0 4096 127.0.0.1:8080 0.0.0.0:*
+            // netstat:        tcp 0 0 0.0.0.0:22 0.0.0.0:* LISTEN
+            // Windows netstat: TCP 0.0.0.0:135 0.0.0.0:0 LISTENING
+            let local = match fields.as_slice() {
+                ["LISTEN", _, _, local, ..] => *local,
+                [proto, _, _, local, _, "LISTEN", ..] if proto.starts_with("tcp") => *local,
+                [proto, local, _, "LISTENING", ..] if proto.eq_ignore_ascii_case("tcp") => *local,
+                _ => continue,
+            };
+
+            if let Some(port) = Self::parse_local_address(local)
+                && !ports.contains(&port)
+            {
+                ports.push(port);
+            }
+        }
+
+        ports
+    }
+
+    /// Parses an `ADDRESS:PORT` pair, where the address can be bracketed (`[::1]:80`) or carry
+    /// an interface suffix (`127.0.0.53%lo:53`).
+    fn parse_local_address(local: &str) -> Option<Self> {
+        let (address, port) = local.rsplit_once(':')?;
+        let port = port.parse().ok()?;
+        let address = address.trim_start_matches('[').trim_end_matches(']');
+        let address = address.split('%').next().unwrap_or(address);
+
+        Some(Self {
+            address: address.to_string(),
+            port,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(address: &str, port: u16) -> ListeningPort {
+        ListeningPort {
+            address: address.to_string(),
+            port,
+        }
+    }
+
+    #[test]
+    fn should_be_able_to_serialize_to_json() {
+        let value = serde_json::to_value(port("127.0.0.1", 8080)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "address": "127.0.0.1",
+                "port": 8080,
+            })
+        );
+    }
+
+    #[test]
+    fn should_be_able_to_deserialize_from_json() {
+        let value = serde_json::json!({
+            "address": "::",
+            "port": 3000,
+        });
+
+        let listening: ListeningPort = serde_json::from_value(value).unwrap();
+        assert_eq!(listening, port("::", 3000));
+    }
+
+    #[test]
+    fn is_unspecified_should_be_true_only_for_wildcard_addresses() {
+        assert!(port("0.0.0.0", 80).is_unspecified());
+        assert!(port("::", 80).is_unspecified());
+        assert!(port("*", 80).is_unspecified());
+        assert!(!port("127.0.0.1", 80).is_unspecified());
+        assert!(!port("::1", 80).is_unspecified());
+    }
+
+    #[test]
+    fn parse_socket_table_should_support_ss_output() {
+        let output = "\
+State  Recv-Q Send-Q Local Address:Port  Peer Address:Port Process
+LISTEN 0      4096   127.0.0.53%lo:53         0.0.0.0:*
+LISTEN 0      128          0.0.0.0:22         0.0.0.0:*
+LISTEN 0      511                *:3000             *:*
+LISTEN 0      128             [::]:22            [::]:*
+LISTEN 0      511        [::1]:5173           [::]:*
+";
+        assert_eq!(
+            ListeningPort::parse_socket_table(output),
+            vec![
+                port("127.0.0.53", 53),
+                port("0.0.0.0", 22),
+                port("*", 3000),
+                port("::", 22),
+                port("::1", 5173),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_socket_table_should_support_netstat_output() {
+        let output = "\
+Active Internet connections (only servers)
+Proto Recv-Q Send-Q Local Address           Foreign Address         State
+tcp        0      0 0.0.0.0:22              0.0.0.0:*               LISTEN
+tcp        0      0 127.0.0.1:8080          0.0.0.0:*               LISTEN
+tcp6       0      0 :::22                   :::*                    LISTEN
+";
+        assert_eq!(
+            ListeningPort::parse_socket_table(output),
+            vec![port("0.0.0.0", 22), port("127.0.0.1", 8080), port("::", 22)]
+        );
+    }
+
+    #[test]
+    fn parse_socket_table_should_support_windows_netstat_output() {
+        let output = "
+Active Connections
+
+  Proto  Local Address          Foreign Address        State
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING
+  TCP    127.0.0.1:3000         0.0.0.0:0              LISTENING
+  TCP    10.0.0.5:49712         20.42.65.92:443        ESTABLISHED
+";
+        assert_eq!(
+            ListeningPort::parse_socket_table(output),
+            vec![port("0.0.0.0", 135), port("127.0.0.1", 3000)]
+        );
+    }
+
+    #[test]
+    fn parse_socket_table_should_skip_duplicates_and_unparseable_lines() {
+        let output = "\
+LISTEN 0 128 0.0.0.0:22 0.0.0.0:*
+LISTEN 0 128 0.0.0.0:22 0.0.0.0:*
+LISTEN 0 128 garbage 0.0.0.0:*
+ESTAB  0 0   10.0.0.5:22 10.0.0.9:51234
+";
+        assert_eq!(
+            ListeningPort::parse_socket_table(output),
+            vec![port("0.0.0.0", 22)]
+        );
+    }
+}
@@ -0,0 +1,585 @@
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::{Environment, RemotePath, utils};
+
+/// Additional options to supply when spawning a process
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "snake_case")]
+pub struct ProcSpawnOptions {
+    /// Resource limits and sandboxing to apply to the spawned process
+    #[serde(skip_serializing_if = "ProcessLimits::is_empty")]
+    pub limits: ProcessLimits,
+
+    /// If true, stdout and stderr chunks of the process will be stamped with a sequence number
+    /// and a timestamp, allowing the output of both streams to be interleaved in order
+    #[serde(skip_serializing_if = "utils::is_false")]
+    pub timestamps: bool,
+
+    /// If true, the process will not inherit the environment of the server, and will only be
+    /// given the variables from `env_file` and the environment of the request
+    #[serde(skip_serializing_if = "utils::is_false")]
+    pub clear_env: bool,
+
+    /// Names of variables to remove from the environment inherited from the server. Variables
+    /// provided by `env_file` or the environment of the request are still set
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unset_env: Vec<String>,
+
+    /// Path on the remote machine to a file of `KEY=VALUE` lines to load into the environment of
+    /// the process, where the environment of the request takes precedence over the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<RemotePath>,
+
+    /// If true, the command is run through the user's login shell (as reported by the shell of
+    /// the system info), so that it sees the environment set up by the user's profile
+    #[serde(skip_serializing_if = "utils::is_false")]
+    pub login: bool,
+
+    /// If true, the server forwards the SSH agent of the client to the process, pointing
+    /// `SSH_AUTH_SOCK` at a socket that relays to it. Clients must check that the server
+    /// advertises the [`CAP_AGENT_FORWARD`] capability before setting this, as servers without
+    /// it reject the request
+    ///
+    /// [`CAP_AGENT_FORWARD`]: crate::protocol::Version::CAP_AGENT_FORWARD
+    #[serde(skip_serializing_if = "utils::is_false")]
+    pub forward_agent: bool,
+}
+
+impl ProcSpawnOptions {
+    /// Returns true if no options deviate from the defaults, meaning that the options can be
+    /// omitted entirely when sending a request.
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns true if the process should not inherit the server's environment as-is, either
+    /// because it is cleared or because some of its variables are unset.
+    pub fn overrides_inherited_env(&self) -> bool {
+        self.clear_env || !self.unset_env.is_empty()
+    }
+}
+
+/// Parses the contents of an env file into an [`Environment`].
+///
+/// Each non-empty line that is not a `#` comment is a `KEY=VALUE` pair, optionally prefixed with
+/// `export`. Values can be wrapped in single quotes to be taken literally, or in double quotes to
+/// support the `\n`, `\t`, `\"`, and `\\` escapes. Unquoted values are trimmed and end at a ` #`
+/// comment.
+pub fn parse_env_file(contents: &str) -> io::Result<Environment> {
+    let mut environment = Environment::new();
+
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |msg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid env file on line {}: {msg}", i + 1),
+            )
+        };
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line.split_once('=').ok_or_else(|| invalid("missing '='"))?;
+
+        let key = key.trim();
+        let is_valid_key = key
+            .chars()
+            .enumerate()
+            .all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+        if key.is_empty() || !is_valid_key {
+            return Err(invalid(&format!("'{key}' is not a valid variable name")));
+        }
+
+        let value = value.trim();
+        let value = if let Some(rest) = value.strip_prefix('"') {
+            let rest = rest
+                .strip_suffix('"')
+                .ok_or_else(|| invalid("unterminated double quote"))?;
+            let mut unescaped = String::with_capacity(rest.len());
+            let mut chars = rest.chars();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    unescaped.push(c);
+                    continue;
+                }
+
+                match chars.next() {
+                    Some('n') => unescaped.push('\n'),
+                    Some('t') => unescaped.push('\t'),
+                    Some(c @ ('"' | '\\')) => unescaped.push(c),
+                    Some(c) => {
+                        unescaped.push('\\');
+                        unescaped.push(c);
+                    }
+                    None => unescaped.push('\\'),
+                }
+            }
+            unescaped
+        } else if let Some(rest) = value.strip_prefix('\'') {
+            rest.strip_suffix('\'')
+                .ok_or_else(|| invalid("unterminated single quote"))?
+                .to_string()
+        } else {
+            match value.find(" #") {
+                Some(idx) => value[..idx].trim_end().to_string(),
+                None => value.to_string(),
+            }
+        };
+
+        environment.insert(key.to_string(), value);
+    }
+
+    Ok(environment)
+}
+
+/// Resource limits and sandboxing to apply to a spawned process.
+///
+/// Every limit is optional, and only limits that are set (not `None`) will be applied. Backends
+/// that cannot honor a requested limit fail the spawn with an unsupported error rather than
+/// silently running the process without it.
+///
+/// The memory, CPU, and open file limits are applied per process with `setrlimit` (or `ulimit`
+/// when the process is started through a shell), so they are not shared by the children of the
+/// process, and `max_memory` caps the address space of the process rather than the memory that
+/// it actually uses. Where the server can create a cgroup (v2) for a process, as the host backend
+/// does on Linux for processes without a pty, `max_memory` instead caps the memory used by the
+/// process and its children together.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "snake_case")]
+pub struct ProcessLimits {
+    /// Maximum wall-clock time (in milliseconds) the process is allowed to run before it is
+    /// killed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+
+    /// Maximum size (in bytes) of the process' virtual memory, or of the memory used by the
+    /// process and its children when applied through a cgroup.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_memory: Option<u64>,
+
+    /// Maximum amount of CPU time (in seconds) the process can consume.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cpu_secs: Option<u64>,
+
+    /// Maximum number of file descriptors the process can have open at once.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_open_files: Option<u64>,
+
+    /// Increment added to the scheduling priority (niceness) that the process would otherwise
+    /// inherit from the server, like `nice -n`, where higher values are less favorable. A
+    /// negative increment typically requires elevated privileges.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+
+    /// Name or numeric id of the user to run the process as. Requires the server to have the
+    /// privileges to switch users.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+impl ProcessLimits {
+    /// Returns true if no limits are set.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns true if any of the resource limits (memory, cpu, open files) are set. These map
+    /// to `setrlimit` on Unix platforms.
+    pub fn has_rlimits(&self) -> bool {
+        self.max_memory.is_some() || self.max_cpu_secs.is_some() || self.max_open_files.is_some()
+    }
+
+    /// Returns the wall-clock timeout as a [`Duration`], if one was set.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Returns the shell commands that apply the resource limits and niceness before `exec`ing
+    /// whatever follows them, e.g. `ulimit -n 64 && exec nice -n 10`, or `None` if neither is
+    /// set.
+    ///
+    /// This is how backends that start processes through a shell apply the limits, appending
+    /// the command to run. The timeout and user are not covered, as a shell cannot apply them.
+    pub fn to_shell_prefix(&self) -> Option<String> {
+        if !self.has_rlimits() && self.nice.is_none() {
+            return None;
+        }
+
+        let mut parts = Vec::new();
+        if let Some(bytes) = self.max_memory {
+            // NOTE: ulimit -v takes kilobytes, so round up to avoid a limit of zero
+            parts.push(format!("ulimit -v {}", bytes.div_ceil(1024)));
+        }
+        if let Some(secs) = self.max_cpu_secs {
+            parts.push(format!("ulimit -t {secs}"));
+        }
+        if let Some(n) = self.max_open_files {
+            parts.push(format!("ulimit -n {n}"));
+        }
+        match self.nice {
+            Some(nice) => parts.push(format!("exec nice -n {nice}")),
+            None => parts.push(String::from("exec")),
+        }
+
+        Some(parts.join(" && "))
+    }
+}
+
+/// Stamp attached to a chunk of stdout or stderr when timestamps were requested for a process.
+///
+/// The sequence number is shared by both stdout and stderr of the same process, so sorting
+/// chunks by it reconstructs the order in which the server read them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub struct ProcOutputStamp {
+    /// Position of the chunk across both stdout and stderr, starting at 0
+    pub seq: u64,
+
+    /// Time (in microseconds) since the process was spawned, measured on the server using a
+    /// monotonic clock
+    pub timestamp_us: u64,
+}
+
+impl ProcOutputStamp {
+    /// Returns the timestamp as a [`Duration`] since the process was spawned.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.timestamp_us)
+    }
+}
+
+/// Produces [`ProcOutputStamp`]s for a single process. Clones share the same sequence and
+/// starting instant, so one clone can be handed to each output stream.
+#[derive(Clone, Debug)]
+pub struct ProcOutputStamper {
+    start: Instant,
+    seq: Arc<AtomicU64>,
+}
+
+impl Default for ProcOutputStamper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcOutputStamper {
+    /// Creates a new stamper whose timestamps are relative to now.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            seq: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Creates a stamper if `enabled` is true, which is a convenience for backends that only
+    /// stamp output when requested.
+    pub fn new_if(enabled: bool) -> Option<Self> {
+        enabled.then(Self::new)
+    }
+
+    /// Produces the next stamp, advancing the shared sequence.
+    pub fn next_stamp(&self) -> ProcOutputStamp {
+        ProcOutputStamp {
+            seq: self.seq.fetch_add(1, Ordering::Relaxed),
+            timestamp_us: self
+                .start
+                .elapsed()
+                .as_micros()
+                .try_into()
+                .unwrap_or(u64::MAX),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_be_able_to_serialize_minimal_options_to_json() {
+        let options = ProcSpawnOptions::default();
+
+        let value = serde_json::to_value(options).unwrap();
+        assert_eq!(value, serde_json::json!({}));
+    }
+
+    #[test]
+    fn should_be_able_to_serialize_full_options_to_json() {
+        let options = ProcSpawnOptions {
+            limits: ProcessLimits {
+                timeout_ms: Some(u64::MAX),
+                max_memory: Some(u64::MAX),
+                max_cpu_secs: Some(u64::MAX),
+                max_open_files: Some(u64::MAX),
+                nice: Some(i32::MIN),
+                user: Some(String::from("nobody")),
+            },
+            timestamps: true,
+            clear_env: true,
+            unset_env: vec![String::from("SECRET")],
+            env_file: Some(RemotePath::new("/path/to/.env")),
+            login: true,
+            forward_agent: true,
+        };
+
+        let value = serde_json::to_value(options).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "limits": {
+                    "timeout_ms": u64::MAX,
+                    "max_memory": u64::MAX,
+                    "max_cpu_secs": u64::MAX,
+                    "max_open_files": u64::MAX,
+                    "nice": i32::MIN,
+                    "user": "nobody",
+                },
+                "timestamps": true,
+                "clear_env": true,
+                "unset_env": ["SECRET"],
+                "env_file": "/path/to/.env",
+                "login": true,
+                "forward_agent": true,
+            })
+        );
+    }
+
+    #[test]
+    fn should_be_able_to_deserialize_minimal_options_from_json() {
+        let options: ProcSpawnOptions = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(options, ProcSpawnOptions::default());
+    }
+
+    #[test]
+    fn should_fail_to_deserialize_options_with_unknown_limits_from_json() {
+        let result: Result<ProcSpawnOptions, _> = serde_json::from_value(serde_json::json!({
+            "limits": { "max_threads": 5 },
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_be_able_to_serialize_and_deserialize_options_with_msgpack() {
+        // NOTE: It may seem odd that we are serializing just to deserialize, but this is to
+        // verify that we are not corrupting or causing issues when serializing on a
+        // client/server and then trying to deserialize on the other side. This has happened
+        // enough times with minor changes that we need tests to verify.
+        let options = ProcSpawnOptions {
+            limits: ProcessLimits {
+                timeout_ms: Some(1500),
+                nice: Some(10),
+                ..Default::default()
+            },
+            timestamps: true,
+            unset_env: vec![String::from("SECRET")],
+            env_file: Some(RemotePath::new(".env")),
+            login: true,
+            forward_agent: true,
+            ..Default::default()
+        };
+
+        let buf = rmp_serde::encode::to_vec_named(&options).unwrap();
+        let decoded: ProcSpawnOptions = rmp_serde::decode::from_slice(&buf).unwrap();
+        assert_eq!(decoded, options);
+    }
+
+    #[test]
+    fn is_default_should_be_false_when_any_limit_is_set() {
+        assert!(ProcSpawnOptions::default().is_default());
+        assert!(
+            !ProcSpawnOptions {
+                limits: ProcessLimits {
+                    max_open_files: Some(64),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+            .is_default()
+        );
+        assert!(
+            !ProcSpawnOptions {
+                timestamps: true,
+                ..Default::default()
+            }
+            .is_default()
+        );
+    }
+
+    #[test]
+    fn has_rlimits_should_only_consider_resource_limits() {
+        let limits = ProcessLimits {
+            timeout_ms: Some(1000),
+            nice: Some(5),
+            user: Some(String::from("nobody")),
+            ..Default::default()
+        };
+        assert!(!limits.has_rlimits());
+
+        let limits = ProcessLimits {
+            max_cpu_secs: Some(1),
+            ..Default::default()
+        };
+        assert!(limits.has_rlimits());
+    }
+
+    #[test]
+    fn to_shell_prefix_should_apply_ulimits_and_nice_before_exec() {
+        let limits = ProcessLimits {
+            max_memory: Some(1025),
+            max_cpu_secs: Some(5),
+            max_open_files: Some(64),
+            nice: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            limits.to_shell_prefix().as_deref(),
+            Some("ulimit -v 2 && ulimit -t 5 && ulimit -n 64 && exec nice -n 10")
+        );
+
+        let limits = ProcessLimits {
+            max_open_files: Some(64),
+            ..Default::default()
+        };
+        assert_eq!(
+            limits.to_shell_prefix().as_deref(),
+            Some("ulimit -n 64 && exec")
+        );
+    }
+
+    #[test]
+    fn to_shell_prefix_should_be_none_without_rlimits_or_nice() {
+        let limits = ProcessLimits {
+            timeout_ms: Some(1000),
+            user: Some(String::from("nobody")),
+            ..Default::default()
+        };
+        assert_eq!(limits.to_shell_prefix(), None);
+    }
+
+    #[test]
+    fn timeout_should_convert_milliseconds_into_duration() {
+        let limits = ProcessLimits {
+            timeout_ms: Some(2500),
+            ..Default::default()
+        };
+        assert_eq!(limits.timeout(), Some(Duration::from_millis(2500)));
+        assert_eq!(ProcessLimits::default().timeout(), None);
+    }
+
+    #[test]
+    fn should_be_able_to_serialize_output_stamp_to_json() {
+        let stamp = ProcOutputStamp {
+            seq: 3,
+            timestamp_us: 1500,
+        };
+
+        let value = serde_json::to_value(stamp).unwrap();
+        assert_eq!(value, serde_json::json!({ "seq": 3, "timestamp_us": 1500 }));
+    }
+
+    #[test]
+    fn output_stamper_should_share_sequence_between_clones() {
+        let stamper = ProcOutputStamper::new();
+        let clone = stamper.clone();
+
+        let first = stamper.next_stamp();
+        let second = clone.next_stamp();
+        let third = stamper.next_stamp();
+
+        assert_eq!(first.seq, 0);
+        assert_eq!(second.seq, 1);
+        assert_eq!(third.seq, 2);
+        assert!(first.timestamp_us <= second.timestamp_us);
+        assert!(second.timestamp_us <= third.timestamp_us);
+    }
+
+    #[test]
+    fn output_stamper_new_if_should_only_create_stamper_when_enabled() {
+        assert!(ProcOutputStamper::new_if(true).is_some());
+        assert!(ProcOutputStamper::new_if(false).is_none());
+    }
+
+    #[test]
+    fn overrides_inherited_env_should_be_true_if_clearing_or_unsetting() {
+        assert!(!ProcSpawnOptions::default().overrides_inherited_env());
+        assert!(
+            !ProcSpawnOptions {
+                env_file: Some(RemotePath::new(".env")),
+                ..Default::default()
+            }
+            .overrides_inherited_env()
+        );
+        assert!(
+            ProcSpawnOptions {
+                clear_env: true,
+                ..Default::default()
+            }
+            .overrides_inherited_env()
+        );
+        assert!(
+            ProcSpawnOptions {
+                unset_env: vec![String::from("SECRET")],
+                ..Default::default()
+            }
+            .overrides_inherited_env()
+        );
+    }
+
+    #[test]
+    fn parse_env_file_should_support_comments_exports_and_quotes() {
+        let environment = parse_env_file(
+            r#"
+# A comment
+PLAIN=value
+export EXPORTED=yes
+SPACED = some value # trailing comment
+SINGLE='literal \n # value'
+DOUBLE="line\nnext \"quoted\""
+EMPTY=
+"#,
+        )
+        .unwrap();
+
+        let expected: Environment = [
+            ("PLAIN", "value"),
+            ("EXPORTED", "yes"),
+            ("SPACED", "some value"),
+            ("SINGLE", "literal \\n # value"),
+            ("DOUBLE", "line\nnext \"quoted\""),
+            ("EMPTY", ""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        assert_eq!(environment, expected);
+    }
+
+    #[test]
+    fn parse_env_file_should_fail_if_line_is_missing_equals() {
+        let err = parse_env_file("GOOD=1\nBAD").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 2"), "{err}");
+    }
+
+    #[test]
+    fn parse_env_file_should_fail_if_key_is_invalid() {
+        let err = parse_env_file("1BAD=value").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = parse_env_file("BAD-KEY=value").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_env_file_should_fail_if_quote_is_unterminated() {
+        let err = parse_env_file("KEY=\"value").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
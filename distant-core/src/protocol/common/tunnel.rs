@@ -1,3 +1,7 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use serde::{Deserialize, Serialize};
 
 /// Id for a remote tunnel.
@@ -10,10 +14,145 @@ pub struct TunnelInfo {
     pub id: TunnelId,
     /// Direction of the tunnel (forward or reverse).
     pub direction: TunnelDirection,
-    /// The host the tunnel is connected or bound to.
+    /// The host the tunnel is connected or bound to, or the socket path for Unix socket tunnels.
     pub host: String,
-    /// The port the tunnel is connected or bound to.
+    /// The port the tunnel is connected or bound to (always 0 for Unix socket tunnels).
     pub port: u16,
+    /// Transport protocol carried by the tunnel.
+    #[serde(default, skip_serializing_if = "TunnelProtocol::is_tcp")]
+    pub protocol: TunnelProtocol,
+    /// Traffic that has passed through the tunnel.
+    #[serde(default, skip_serializing_if = "TunnelStats::is_empty")]
+    pub stats: TunnelStats,
+}
+
+/// Traffic that has passed through a tunnel.
+///
+/// Bytes are counted relative to the tunnel's target: the remote host for forward tunnels and
+/// the local host for reverse tunnels. A connection is one relayed stream or, for UDP, one
+/// peer association.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TunnelStats {
+    /// Bytes sent toward the tunnel's target.
+    pub bytes_out: u64,
+    /// Bytes received back from the tunnel's target.
+    pub bytes_in: u64,
+    /// Connections currently open through the tunnel.
+    pub active_connections: u32,
+    /// Connections opened through the tunnel since it started.
+    pub total_connections: u64,
+    /// Last time (in seconds since the Unix epoch) that data passed through the tunnel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_activity: Option<u64>,
+}
+
+impl TunnelStats {
+    /// Returns true if nothing has passed through the tunnel.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Counters updated by the tasks relaying a tunnel's data, from which its [`TunnelStats`] are
+/// taken.
+///
+/// Counters created with [`TunnelCounters::child`] also update their parent, so that a
+/// listener reports the traffic of all of the connections it accepted.
+#[derive(Debug, Default)]
+pub struct TunnelCounters {
+    parent: Option<Arc<TunnelCounters>>,
+    bytes_out: AtomicU64,
+    bytes_in: AtomicU64,
+    active_connections: AtomicU32,
+    total_connections: AtomicU64,
+    last_activity: AtomicU64,
+}
+
+impl TunnelCounters {
+    /// Creates new counters with no parent.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Creates new counters that also update `parent`.
+    pub fn child(parent: &Arc<Self>) -> Arc<Self> {
+        Arc::new(Self {
+            parent: Some(Arc::clone(parent)),
+            ..Self::default()
+        })
+    }
+
+    /// Records `n` bytes sent toward the tunnel's target.
+    pub fn record_out(&self, n: usize) {
+        self.bytes_out.fetch_add(n as u64, Ordering::Relaxed);
+        self.touch();
+        if let Some(parent) = self.parent.as_ref() {
+            parent.record_out(n);
+        }
+    }
+
+    /// Records `n` bytes received back from the tunnel's target.
+    pub fn record_in(&self, n: usize) {
+        self.bytes_in.fetch_add(n as u64, Ordering::Relaxed);
+        self.touch();
+        if let Some(parent) = self.parent.as_ref() {
+            parent.record_in(n);
+        }
+    }
+
+    /// Records a new connection through the tunnel, which is counted as active until the
+    /// returned guard is dropped.
+    pub fn connection(self: &Arc<Self>) -> TunnelConnectionGuard {
+        self.connection_opened();
+        TunnelConnectionGuard(Arc::clone(self))
+    }
+
+    /// Returns the stats counted so far.
+    pub fn stats(&self) -> TunnelStats {
+        let last_activity = self.last_activity.load(Ordering::Relaxed);
+        TunnelStats {
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            active_connections: self.active_connections.load(Ordering::Relaxed),
+            total_connections: self.total_connections.load(Ordering::Relaxed),
+            last_activity: (last_activity > 0).then_some(last_activity),
+        }
+    }
+
+    fn connection_opened(&self) {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        self.total_connections.fetch_add(1, Ordering::Relaxed);
+        self.touch();
+        if let Some(parent) = self.parent.as_ref() {
+            parent.connection_opened();
+        }
+    }
+
+    fn connection_closed(&self) {
+        self.active_connections.fetch_sub(1, Ordering::Relaxed);
+        if let Some(parent) = self.parent.as_ref() {
+            parent.connection_closed();
+        }
+    }
+
+    fn touch(&self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.last_activity.store(now, Ordering::Relaxed);
+    }
+}
+
+/// Marks a connection through a tunnel as active for as long as it is held.
+#[derive(Debug)]
+pub struct TunnelConnectionGuard(Arc<TunnelCounters>);
+
+impl Drop for TunnelConnectionGuard {
+    fn drop(&mut self) {
+        self.0.connection_closed();
+    }
 }
 
 /// Direction of a tunnel.
@@ -24,6 +163,44 @@ pub enum TunnelDirection {
     Forward,
     /// Reverse tunnel: server listens on a host:port for incoming connections.
     Reverse,
+    /// Dynamic tunnel: a SOCKS proxy that opens a forward tunnel to whichever host:port each of
+    /// its clients asks for.
+    Dynamic,
+}
+
+/// Transport protocol carried by a tunnel.
+///
+/// TCP tunnels relay a byte stream, whereas UDP tunnels relay datagrams where each
+/// `TunnelWrite` and `TunnelData` carries exactly one datagram. Unix socket tunnels relay
+/// a byte stream like TCP, but address a socket path (carried in place of the host) rather
+/// than a host and port.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TunnelProtocol {
+    /// Stream-oriented TCP tunnel.
+    #[default]
+    Tcp,
+    /// Datagram-oriented UDP tunnel.
+    Udp,
+    /// Stream-oriented tunnel over a Unix domain socket.
+    Unix,
+}
+
+impl TunnelProtocol {
+    /// Returns true if the protocol is TCP.
+    pub fn is_tcp(&self) -> bool {
+        matches!(self, Self::Tcp)
+    }
+
+    /// Returns true if the protocol is UDP.
+    pub fn is_udp(&self) -> bool {
+        matches!(self, Self::Udp)
+    }
+
+    /// Returns true if the protocol is a Unix domain socket.
+    pub fn is_unix(&self) -> bool {
+        matches!(self, Self::Unix)
+    }
 }
 
 #[cfg(test)]
@@ -40,6 +217,8 @@ mod tests {
                 direction: TunnelDirection::Forward,
                 host: String::from("localhost"),
                 port: 8080,
+                protocol: TunnelProtocol::Tcp,
+                stats: TunnelStats::default(),
             };
 
             let value = serde_json::to_value(info).unwrap();
@@ -71,6 +250,8 @@ mod tests {
                     direction: TunnelDirection::Forward,
                     host: String::from("localhost"),
                     port: 8080,
+                    protocol: TunnelProtocol::Tcp,
+                    stats: TunnelStats::default(),
                 }
             );
         }
@@ -82,6 +263,8 @@ mod tests {
                 direction: TunnelDirection::Forward,
                 host: String::from("localhost"),
                 port: 8080,
+                protocol: TunnelProtocol::Tcp,
+                stats: TunnelStats::default(),
             };
 
             // NOTE: We don't actually check the output here because it's an implementation detail
@@ -102,6 +285,8 @@ mod tests {
                 direction: TunnelDirection::Forward,
                 host: String::from("localhost"),
                 port: 8080,
+                protocol: TunnelProtocol::Tcp,
+                stats: TunnelStats::default(),
             })
             .unwrap();
 
@@ -113,11 +298,192 @@ mod tests {
                     direction: TunnelDirection::Forward,
                     host: String::from("localhost"),
                     port: 8080,
+                    protocol: TunnelProtocol::Tcp,
+                    stats: TunnelStats::default(),
                 }
             );
         }
     }
 
+    mod tunnel_stats {
+        use super::*;
+
+        #[test]
+        fn should_include_stats_in_tunnel_info_json_once_traffic_has_passed() {
+            let info = TunnelInfo {
+                id: 42,
+                direction: TunnelDirection::Forward,
+                host: String::from("localhost"),
+                port: 8080,
+                protocol: TunnelProtocol::Tcp,
+                stats: TunnelStats {
+                    bytes_out: 10,
+                    bytes_in: 20,
+                    active_connections: 1,
+                    total_connections: 2,
+                    last_activity: Some(1700000000),
+                },
+            };
+
+            let value = serde_json::to_value(&info).unwrap();
+            assert_eq!(
+                value["stats"],
+                serde_json::json!({
+                    "bytes_out": 10,
+                    "bytes_in": 20,
+                    "active_connections": 1,
+                    "total_connections": 2,
+                    "last_activity": 1700000000,
+                })
+            );
+
+            let decoded: TunnelInfo = serde_json::from_value(value).unwrap();
+            assert_eq!(decoded, info);
+        }
+
+        #[test]
+        fn should_be_able_to_roundtrip_msgpack() {
+            let stats = TunnelStats {
+                bytes_out: 10,
+                bytes_in: 20,
+                active_connections: 1,
+                total_connections: 2,
+                last_activity: None,
+            };
+
+            let buf = rmp_serde::encode::to_vec_named(&stats).unwrap();
+            let decoded: TunnelStats = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(decoded, stats);
+        }
+
+        #[test]
+        fn counters_should_start_empty() {
+            assert!(TunnelCounters::new().stats().is_empty());
+        }
+
+        #[test]
+        fn counters_should_record_bytes_and_last_activity() {
+            let counters = TunnelCounters::new();
+            counters.record_out(5);
+            counters.record_in(7);
+            counters.record_out(1);
+
+            let stats = counters.stats();
+            assert_eq!(stats.bytes_out, 6);
+            assert_eq!(stats.bytes_in, 7);
+            assert!(stats.last_activity.is_some());
+        }
+
+        #[test]
+        fn counters_should_count_connections_until_guard_is_dropped() {
+            let counters = TunnelCounters::new();
+            let first = counters.connection();
+            let second = counters.connection();
+            assert_eq!(counters.stats().active_connections, 2);
+
+            drop(first);
+            assert_eq!(counters.stats().active_connections, 1);
+            drop(second);
+
+            let stats = counters.stats();
+            assert_eq!(stats.active_connections, 0);
+            assert_eq!(stats.total_connections, 2);
+        }
+
+        #[test]
+        fn child_counters_should_also_update_parent() {
+            let parent = TunnelCounters::new();
+            let child = TunnelCounters::child(&parent);
+            let other = TunnelCounters::child(&parent);
+
+            let _guard = child.connection();
+            child.record_out(3);
+            other.record_in(4);
+
+            assert_eq!(child.stats().bytes_in, 0);
+            let stats = parent.stats();
+            assert_eq!(stats.bytes_out, 3);
+            assert_eq!(stats.bytes_in, 4);
+            assert_eq!(stats.active_connections, 1);
+            assert_eq!(stats.total_connections, 1);
+        }
+    }
+
+    mod tunnel_protocol {
+        use super::*;
+
+        #[test]
+        fn should_default_to_tcp() {
+            assert_eq!(TunnelProtocol::default(), TunnelProtocol::Tcp);
+            assert!(TunnelProtocol::default().is_tcp());
+            assert!(!TunnelProtocol::default().is_udp());
+            assert!(!TunnelProtocol::default().is_unix());
+        }
+
+        #[test]
+        fn should_be_able_to_serialize_to_json() {
+            let value = serde_json::to_value(TunnelProtocol::Tcp).unwrap();
+            assert_eq!(value, serde_json::json!("tcp"));
+
+            let value = serde_json::to_value(TunnelProtocol::Udp).unwrap();
+            assert_eq!(value, serde_json::json!("udp"));
+
+            let value = serde_json::to_value(TunnelProtocol::Unix).unwrap();
+            assert_eq!(value, serde_json::json!("unix"));
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_json() {
+            let protocol: TunnelProtocol =
+                serde_json::from_value(serde_json::json!("udp")).unwrap();
+            assert_eq!(protocol, TunnelProtocol::Udp);
+        }
+
+        #[test]
+        fn should_include_udp_protocol_in_tunnel_info_json() {
+            let info = TunnelInfo {
+                id: 7,
+                direction: TunnelDirection::Reverse,
+                host: String::from("0.0.0.0"),
+                port: 5353,
+                protocol: TunnelProtocol::Udp,
+                stats: TunnelStats::default(),
+            };
+
+            let value = serde_json::to_value(&info).unwrap();
+            assert_eq!(value["protocol"], serde_json::json!("udp"));
+
+            let decoded: TunnelInfo = serde_json::from_value(value).unwrap();
+            assert_eq!(decoded, info);
+        }
+
+        #[test]
+        fn should_include_unix_protocol_and_path_in_tunnel_info_json() {
+            let info = TunnelInfo {
+                id: 8,
+                direction: TunnelDirection::Forward,
+                host: String::from("/var/run/docker.sock"),
+                port: 0,
+                protocol: TunnelProtocol::Unix,
+                stats: TunnelStats::default(),
+            };
+
+            let value = serde_json::to_value(&info).unwrap();
+            assert_eq!(value["protocol"], serde_json::json!("unix"));
+            assert_eq!(value["host"], serde_json::json!("/var/run/docker.sock"));
+
+            let decoded: TunnelInfo = serde_json::from_value(value).unwrap();
+            assert_eq!(decoded, info);
+        }
+
+        #[test]
+        fn should_be_able_to_roundtrip_msgpack() {
+            let buf = rmp_serde::encode::to_vec_named(&TunnelProtocol::Udp).unwrap();
+            let protocol: TunnelProtocol = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(protocol, TunnelProtocol::Udp);
+        }
+    }
+
     mod tunnel_direction {
         use super::*;
 
@@ -133,6 +499,19 @@ mod tests {
             assert_eq!(value, serde_json::json!("reverse"));
         }
 
+        #[test]
+        fn should_be_able_to_serialize_dynamic_to_json() {
+            let value = serde_json::to_value(TunnelDirection::Dynamic).unwrap();
+            assert_eq!(value, serde_json::json!("dynamic"));
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_dynamic_from_json() {
+            let dir: TunnelDirection =
+                serde_json::from_value(serde_json::json!("dynamic")).unwrap();
+            assert_eq!(dir, TunnelDirection::Dynamic);
+        }
+
         #[test]
         fn should_be_able_to_deserialize_forward_from_json() {
             let dir: TunnelDirection =
@@ -33,9 +33,27 @@ impl Version {
     /// Supports TCP reverse tunneling.
     pub const CAP_TCP_REV_TUNNEL: &'static str = "tcp_rev_tunnel";
 
+    /// Supports UDP tunneling, both forward and reverse.
+    pub const CAP_UDP_TUNNEL: &'static str = "udp_tunnel";
+
+    /// Supports tunneling to and from Unix domain sockets, both forward and reverse.
+    pub const CAP_UNIX_TUNNEL: &'static str = "unix_tunnel";
+
+    /// Supports forwarding the client's SSH agent to spawned processes natively through the
+    /// `forward_agent` spawn option. Only advertised by backends that can reach the agent of the
+    /// client themselves, such as SSH, so it is not part of [`Version::capabilities`].
+    pub const CAP_AGENT_FORWARD: &'static str = "agent_forward";
+
+    /// Supports listing the TCP ports that processes on the server's machine are listening on.
+    pub const CAP_LISTENING_PORTS: &'static str = "listening_ports";
+
     /// Supports retrieving system information.
     pub const CAP_SYS_INFO: &'static str = "sys_info";
 
+    /// Supports sending requests and responses in the compact packet format, which was
+    /// introduced with protocol version 0.21 and is negotiated when connecting.
+    pub const CAP_COMPACT_PACKETS: &'static str = "compact_packets";
+
     pub const fn capabilities() -> &'static [&'static str] {
         &[
             Self::CAP_EXEC,
@@ -45,7 +63,11 @@ impl Version {
             Self::CAP_FS_WATCH,
             Self::CAP_TCP_TUNNEL,
             Self::CAP_TCP_REV_TUNNEL,
+            Self::CAP_UDP_TUNNEL,
+            Self::CAP_UNIX_TUNNEL,
+            Self::CAP_LISTENING_PORTS,
             Self::CAP_SYS_INFO,
+            Self::CAP_COMPACT_PACKETS,
         ]
     }
 }
@@ -4,8 +4,8 @@ use derive_more::IsVariant;
 use serde::{Deserialize, Serialize};
 
 use crate::protocol::common::{
-    ChangeKind, Cmd, Permissions, ProcessId, PtySize, RemotePath, SearchId, SearchQuery,
-    SetPermissionsOptions, TunnelId,
+    ChangeKind, Cmd, Permissions, ProcSpawnOptions, ProcessId, PtySize, RemotePath, SearchId,
+    SearchQuery, SetPermissionsOptions, TunnelId, TunnelProtocol,
 };
 use crate::protocol::utils;
 
@@ -229,6 +229,10 @@ pub enum Request {
         /// If provided, will spawn process in a pty, otherwise spawns directly
         #[serde(default, skip_serializing_if = "Option::is_none")]
         pty: Option<PtySize>,
+
+        /// Additional options to supply when spawning the process
+        #[serde(default, skip_serializing_if = "ProcSpawnOptions::is_default")]
+        options: ProcSpawnOptions,
     },
 
     /// Kills a process running on the remote machine
@@ -247,6 +251,12 @@ pub enum Request {
         data: Vec<u8>,
     },
 
+    /// Closes stdin of running process, signaling end of input (EOF)
+    ProcStdinClose {
+        /// Id of the actively-running process whose stdin to close
+        id: ProcessId,
+    },
+
     /// Resize pty of remote process
     ProcResizePty {
         /// Id of the actively-running process whose pty to resize
@@ -261,18 +271,28 @@ pub enum Request {
 
     /// Opens a forward tunnel to the specified host and port
     TunnelOpen {
-        /// The host to connect to
+        /// The host to connect to, or the socket path for a Unix socket tunnel
         host: String,
-        /// The port to connect to
+        /// The port to connect to (ignored for a Unix socket tunnel)
         port: u16,
+        /// The transport protocol of the tunnel (defaults to TCP)
+        #[serde(default, skip_serializing_if = "TunnelProtocol::is_tcp")]
+        protocol: TunnelProtocol,
     },
 
-    /// Listens on the specified host and port for incoming connections (reverse tunnel)
+    /// Listens on the specified host and port for incoming connections (reverse tunnel).
+    ///
+    /// For UDP, each distinct peer sending datagrams to the listener is reported as its own
+    /// incoming tunnel, which is closed once the peer has been idle for a while.
     TunnelListen {
-        /// The host to bind on
+        /// The host to bind on, or the socket path for a Unix socket tunnel
         host: String,
-        /// The port to listen on (0 for OS-assigned ephemeral port)
+        /// The port to listen on (0 for OS-assigned ephemeral port, ignored for a Unix socket
+        /// tunnel)
         port: u16,
+        /// The transport protocol of the tunnel (defaults to TCP)
+        #[serde(default, skip_serializing_if = "TunnelProtocol::is_tcp")]
+        protocol: TunnelProtocol,
     },
 
     /// Writes data to an active tunnel
@@ -290,9 +310,19 @@ pub enum Request {
         id: TunnelId,
     },
 
+    /// Cancels a request that the server is still processing, which stops the work tied to the
+    /// request and responds to it with an interrupted error
+    Cancel {
+        /// Id of the request to cancel
+        request_id: String,
+    },
+
     /// Requests aggregated status information from the server
     Status {},
 
+    /// Lists the TCP ports that processes on the server's machine are listening on
+    ListeningPorts {},
+
     /// Retrieve information about the server's protocol version
     Version {},
 }
@@ -2457,6 +2487,7 @@ mod tests {
 
     mod proc_spawn {
         use super::*;
+        use crate::protocol::common::ProcessLimits;
 
         #[test]
         fn should_be_able_to_serialize_minimal_payload_to_json() {
@@ -2465,6 +2496,7 @@ mod tests {
                 environment: Environment::new(),
                 current_dir: None,
                 pty: None,
+                options: ProcSpawnOptions::default(),
             };
 
             let value = serde_json::to_value(payload).unwrap();
@@ -2491,6 +2523,22 @@ mod tests {
                     pixel_width: u16::MAX,
                     pixel_height: u16::MAX,
                 }),
+                options: ProcSpawnOptions {
+                    limits: ProcessLimits {
+                        timeout_ms: Some(u64::MAX),
+                        max_memory: Some(u64::MAX),
+                        max_cpu_secs: Some(u64::MAX),
+                        max_open_files: Some(u64::MAX),
+                        nice: Some(i32::MIN),
+                        user: Some(String::from("user")),
+                    },
+                    timestamps: true,
+                    clear_env: true,
+                    unset_env: vec![String::from("SECRET")],
+                    env_file: Some(RemotePath::new("/path/to/.env")),
+                    login: true,
+                    forward_agent: false,
+                },
             };
 
             let value = serde_json::to_value(payload).unwrap();
@@ -2507,6 +2555,21 @@ mod tests {
                         "pixel_width": u16::MAX,
                         "pixel_height": u16::MAX,
                     },
+                    "options": {
+                        "limits": {
+                            "timeout_ms": u64::MAX,
+                            "max_memory": u64::MAX,
+                            "max_cpu_secs": u64::MAX,
+                            "max_open_files": u64::MAX,
+                            "nice": i32::MIN,
+                            "user": "user",
+                        },
+                        "timestamps": true,
+                        "clear_env": true,
+                        "unset_env": ["SECRET"],
+                        "env_file": "/path/to/.env",
+                        "login": true,
+                    },
                 })
             );
         }
@@ -2526,6 +2589,7 @@ mod tests {
                     environment: Environment::new(),
                     current_dir: None,
                     pty: None,
+                    options: ProcSpawnOptions::default(),
                 }
             );
         }
@@ -2543,6 +2607,21 @@ mod tests {
                     "pixel_width": u16::MAX,
                     "pixel_height": u16::MAX,
                 },
+                "options": {
+                    "limits": {
+                        "timeout_ms": u64::MAX,
+                        "max_memory": u64::MAX,
+                        "max_cpu_secs": u64::MAX,
+                        "max_open_files": u64::MAX,
+                        "nice": i32::MIN,
+                        "user": "user",
+                    },
+                    "timestamps": true,
+                    "clear_env": true,
+                    "unset_env": ["SECRET"],
+                    "env_file": "/path/to/.env",
+                    "login": true,
+                },
             });
 
             let payload: Request = serde_json::from_value(value).unwrap();
@@ -2560,6 +2639,22 @@ mod tests {
                         pixel_width: u16::MAX,
                         pixel_height: u16::MAX,
                     }),
+                    options: ProcSpawnOptions {
+                        limits: ProcessLimits {
+                            timeout_ms: Some(u64::MAX),
+                            max_memory: Some(u64::MAX),
+                            max_cpu_secs: Some(u64::MAX),
+                            max_open_files: Some(u64::MAX),
+                            nice: Some(i32::MIN),
+                            user: Some(String::from("user")),
+                        },
+                        timestamps: true,
+                        clear_env: true,
+                        unset_env: vec![String::from("SECRET")],
+                        env_file: Some(RemotePath::new("/path/to/.env")),
+                        login: true,
+                        forward_agent: false,
+                    },
                 }
             );
         }
@@ -2571,6 +2666,7 @@ mod tests {
                 environment: Environment::new(),
                 current_dir: None,
                 pty: None,
+                options: ProcSpawnOptions::default(),
             };
 
             // NOTE: We don't actually check the output here because it's an implementation detail
@@ -2594,6 +2690,22 @@ mod tests {
                     pixel_width: u16::MAX,
                     pixel_height: u16::MAX,
                 }),
+                options: ProcSpawnOptions {
+                    limits: ProcessLimits {
+                        timeout_ms: Some(u64::MAX),
+                        max_memory: Some(u64::MAX),
+                        max_cpu_secs: Some(u64::MAX),
+                        max_open_files: Some(u64::MAX),
+                        nice: Some(i32::MIN),
+                        user: Some(String::from("user")),
+                    },
+                    timestamps: true,
+                    clear_env: true,
+                    unset_env: vec![String::from("SECRET")],
+                    env_file: Some(RemotePath::new("/path/to/.env")),
+                    login: true,
+                    forward_agent: false,
+                },
             };
 
             // NOTE: We don't actually check the output here because it's an implementation detail
@@ -2614,6 +2726,7 @@ mod tests {
                 environment: Environment::new(),
                 current_dir: None,
                 pty: None,
+                options: ProcSpawnOptions::default(),
             })
             .unwrap();
 
@@ -2625,6 +2738,7 @@ mod tests {
                     environment: Environment::new(),
                     current_dir: None,
                     pty: None,
+                    options: ProcSpawnOptions::default(),
                 }
             );
         }
@@ -2647,6 +2761,22 @@ mod tests {
                     pixel_width: u16::MAX,
                     pixel_height: u16::MAX,
                 }),
+                options: ProcSpawnOptions {
+                    limits: ProcessLimits {
+                        timeout_ms: Some(u64::MAX),
+                        max_memory: Some(u64::MAX),
+                        max_cpu_secs: Some(u64::MAX),
+                        max_open_files: Some(u64::MAX),
+                        nice: Some(i32::MIN),
+                        user: Some(String::from("user")),
+                    },
+                    timestamps: true,
+                    clear_env: true,
+                    unset_env: vec![String::from("SECRET")],
+                    env_file: Some(RemotePath::new("/path/to/.env")),
+                    login: true,
+                    forward_agent: false,
+                },
             })
             .unwrap();
 
@@ -2665,6 +2795,22 @@ mod tests {
                         pixel_width: u16::MAX,
                         pixel_height: u16::MAX,
                     }),
+                    options: ProcSpawnOptions {
+                        limits: ProcessLimits {
+                            timeout_ms: Some(u64::MAX),
+                            max_memory: Some(u64::MAX),
+                            max_cpu_secs: Some(u64::MAX),
+                            max_open_files: Some(u64::MAX),
+                            nice: Some(i32::MIN),
+                            user: Some(String::from("user")),
+                        },
+                        timestamps: true,
+                        clear_env: true,
+                        unset_env: vec![String::from("SECRET")],
+                        env_file: Some(RemotePath::new("/path/to/.env")),
+                        login: true,
+                        forward_agent: false,
+                    },
                 }
             );
         }
@@ -2798,6 +2944,59 @@ mod tests {
         }
     }
 
+    mod proc_stdin_close {
+        use super::*;
+
+        #[test]
+        fn should_be_able_to_serialize_to_json() {
+            let payload = Request::ProcStdinClose { id: u32::MAX };
+
+            let value = serde_json::to_value(payload).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!({
+                    "type": "proc_stdin_close",
+                    "id": u32::MAX,
+                })
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_json() {
+            let value = serde_json::json!({
+                "type": "proc_stdin_close",
+                "id": u32::MAX,
+            });
+
+            let payload: Request = serde_json::from_value(value).unwrap();
+            assert_eq!(payload, Request::ProcStdinClose { id: u32::MAX });
+        }
+
+        #[test]
+        fn should_be_able_to_serialize_to_msgpack() {
+            let payload = Request::ProcStdinClose { id: u32::MAX };
+
+            // NOTE: We don't actually check the output here because it's an implementation detail
+            // and could change as we change how serialization is done. This is merely to verify
+            // that we can serialize since there are times when serde fails to serialize at
+            // runtime.
+            let _ = rmp_serde::encode::to_vec_named(&payload).unwrap();
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_msgpack() {
+            // NOTE: It may seem odd that we are serializing just to deserialize, but this is to
+            // verify that we are not corrupting or causing issues when serializing on a
+            // client/server and then trying to deserialize on the other side. This has happened
+            // enough times with minor changes that we need tests to verify.
+            let buf =
+                rmp_serde::encode::to_vec_named(&Request::ProcStdinClose { id: u32::MAX }).unwrap();
+
+            let payload: Request = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(payload, Request::ProcStdinClose { id: u32::MAX });
+        }
+    }
+
     mod proc_resize_pty {
         use super::*;
 
@@ -3017,6 +3216,7 @@ mod tests {
             let payload = Request::TunnelOpen {
                 host: String::from("db-host"),
                 port: 5432,
+                protocol: TunnelProtocol::Tcp,
             };
 
             let value = serde_json::to_value(payload).unwrap();
@@ -3044,6 +3244,7 @@ mod tests {
                 Request::TunnelOpen {
                     host: String::from("db-host"),
                     port: 5432,
+                    protocol: TunnelProtocol::Tcp,
                 }
             );
         }
@@ -3053,6 +3254,7 @@ mod tests {
             let payload = Request::TunnelOpen {
                 host: String::from("db-host"),
                 port: 5432,
+                protocol: TunnelProtocol::Tcp,
             };
 
             // NOTE: We don't actually check the output here because it's an implementation detail
@@ -3071,6 +3273,7 @@ mod tests {
             let buf = rmp_serde::encode::to_vec_named(&Request::TunnelOpen {
                 host: String::from("db-host"),
                 port: 5432,
+                protocol: TunnelProtocol::Tcp,
             })
             .unwrap();
 
@@ -3080,6 +3283,135 @@ mod tests {
                 Request::TunnelOpen {
                     host: String::from("db-host"),
                     port: 5432,
+                    protocol: TunnelProtocol::Tcp,
+                }
+            );
+        }
+    }
+
+    mod tunnel_open_udp {
+        use super::*;
+
+        #[test]
+        fn should_be_able_to_serialize_to_json() {
+            let payload = Request::TunnelOpen {
+                host: String::from("dns-host"),
+                port: 53,
+                protocol: TunnelProtocol::Udp,
+            };
+
+            let value = serde_json::to_value(payload).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!({
+                    "type": "tunnel_open",
+                    "host": "dns-host",
+                    "port": 53,
+                    "protocol": "udp",
+                })
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_json() {
+            let value = serde_json::json!({
+                "type": "tunnel_open",
+                "host": "dns-host",
+                "port": 53,
+                "protocol": "udp",
+            });
+
+            let payload: Request = serde_json::from_value(value).unwrap();
+            assert_eq!(
+                payload,
+                Request::TunnelOpen {
+                    host: String::from("dns-host"),
+                    port: 53,
+                    protocol: TunnelProtocol::Udp,
+                }
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_msgpack() {
+            let buf = rmp_serde::encode::to_vec_named(&Request::TunnelOpen {
+                host: String::from("dns-host"),
+                port: 53,
+                protocol: TunnelProtocol::Udp,
+            })
+            .unwrap();
+
+            let payload: Request = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(
+                payload,
+                Request::TunnelOpen {
+                    host: String::from("dns-host"),
+                    port: 53,
+                    protocol: TunnelProtocol::Udp,
+                }
+            );
+        }
+    }
+
+    mod tunnel_open_unix {
+        use super::*;
+
+        #[test]
+        fn should_be_able_to_serialize_to_json() {
+            let payload = Request::TunnelOpen {
+                host: String::from("/var/run/docker.sock"),
+                port: 0,
+                protocol: TunnelProtocol::Unix,
+            };
+
+            let value = serde_json::to_value(payload).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!({
+                    "type": "tunnel_open",
+                    "host": "/var/run/docker.sock",
+                    "port": 0,
+                    "protocol": "unix",
+                })
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_json() {
+            let value = serde_json::json!({
+                "type": "tunnel_open",
+                "host": "/var/run/docker.sock",
+                "port": 0,
+                "protocol": "unix",
+            });
+
+            let payload: Request = serde_json::from_value(value).unwrap();
+            assert_eq!(
+                payload,
+                Request::TunnelOpen {
+                    host: String::from("/var/run/docker.sock"),
+                    port: 0,
+                    protocol: TunnelProtocol::Unix,
+                }
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_msgpack() {
+            let buf = rmp_serde::encode::to_vec_named(&Request::TunnelOpen {
+                host: String::from("/var/run/docker.sock"),
+                port: 0,
+                protocol: TunnelProtocol::Unix,
+            })
+            .unwrap();
+
+            let payload: Request = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(
+                payload,
+                Request::TunnelOpen {
+                    host: String::from("/var/run/docker.sock"),
+                    port: 0,
+                    protocol: TunnelProtocol::Unix,
                 }
             );
         }
@@ -3093,6 +3425,7 @@ mod tests {
             let payload = Request::TunnelListen {
                 host: String::from("0.0.0.0"),
                 port: 9090,
+                protocol: TunnelProtocol::Tcp,
             };
 
             let value = serde_json::to_value(payload).unwrap();
@@ -3120,6 +3453,7 @@ mod tests {
                 Request::TunnelListen {
                     host: String::from("0.0.0.0"),
                     port: 9090,
+                    protocol: TunnelProtocol::Tcp,
                 }
             );
         }
@@ -3129,6 +3463,7 @@ mod tests {
             let payload = Request::TunnelListen {
                 host: String::from("0.0.0.0"),
                 port: 9090,
+                protocol: TunnelProtocol::Tcp,
             };
 
             // NOTE: We don't actually check the output here because it's an implementation detail
@@ -3147,6 +3482,7 @@ mod tests {
             let buf = rmp_serde::encode::to_vec_named(&Request::TunnelListen {
                 host: String::from("0.0.0.0"),
                 port: 9090,
+                protocol: TunnelProtocol::Tcp,
             })
             .unwrap();
 
@@ -3156,6 +3492,51 @@ mod tests {
                 Request::TunnelListen {
                     host: String::from("0.0.0.0"),
                     port: 9090,
+                    protocol: TunnelProtocol::Tcp,
+                }
+            );
+        }
+    }
+
+    mod tunnel_listen_udp {
+        use super::*;
+
+        #[test]
+        fn should_be_able_to_serialize_to_json() {
+            let payload = Request::TunnelListen {
+                host: String::from("0.0.0.0"),
+                port: 8125,
+                protocol: TunnelProtocol::Udp,
+            };
+
+            let value = serde_json::to_value(payload).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!({
+                    "type": "tunnel_listen",
+                    "host": "0.0.0.0",
+                    "port": 8125,
+                    "protocol": "udp",
+                })
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_msgpack() {
+            let buf = rmp_serde::encode::to_vec_named(&Request::TunnelListen {
+                host: String::from("0.0.0.0"),
+                port: 8125,
+                protocol: TunnelProtocol::Udp,
+            })
+            .unwrap();
+
+            let payload: Request = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(
+                payload,
+                Request::TunnelListen {
+                    host: String::from("0.0.0.0"),
+                    port: 8125,
+                    protocol: TunnelProtocol::Udp,
                 }
             );
         }
@@ -3289,6 +3670,75 @@ mod tests {
         }
     }
 
+    mod cancel {
+        use super::*;
+
+        #[test]
+        fn should_be_able_to_serialize_to_json() {
+            let payload = Request::Cancel {
+                request_id: String::from("123"),
+            };
+
+            let value = serde_json::to_value(payload).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!({
+                    "type": "cancel",
+                    "request_id": "123",
+                })
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_json() {
+            let value = serde_json::json!({
+                "type": "cancel",
+                "request_id": "123",
+            });
+
+            let payload: Request = serde_json::from_value(value).unwrap();
+            assert_eq!(
+                payload,
+                Request::Cancel {
+                    request_id: String::from("123"),
+                }
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_serialize_to_msgpack() {
+            let payload = Request::Cancel {
+                request_id: String::from("123"),
+            };
+
+            // NOTE: We don't actually check the output here because it's an implementation detail
+            // and could change as we change how serialization is done. This is merely to verify
+            // that we can serialize since there are times when serde fails to serialize at
+            // runtime.
+            let _ = rmp_serde::encode::to_vec_named(&payload).unwrap();
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_msgpack() {
+            // NOTE: It may seem odd that we are serializing just to deserialize, but this is to
+            // verify that we are not corrupting or causing issues when serializing on a
+            // client/server and then trying to deserialize on the other side. This has happened
+            // enough times with minor changes that we need tests to verify.
+            let buf = rmp_serde::encode::to_vec_named(&Request::Cancel {
+                request_id: String::from("123"),
+            })
+            .unwrap();
+
+            let payload: Request = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(
+                payload,
+                Request::Cancel {
+                    request_id: String::from("123"),
+                }
+            );
+        }
+    }
+
     mod status {
         use super::*;
 
@@ -3338,4 +3788,54 @@ mod tests {
             assert_eq!(payload, Request::Status {});
         }
     }
+
+    mod listening_ports {
+        use super::*;
+
+        #[test]
+        fn should_be_able_to_serialize_to_json() {
+            let payload = Request::ListeningPorts {};
+
+            let value = serde_json::to_value(payload).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!({
+                    "type": "listening_ports",
+                })
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_json() {
+            let value = serde_json::json!({
+                "type": "listening_ports",
+            });
+
+            let payload: Request = serde_json::from_value(value).unwrap();
+            assert_eq!(payload, Request::ListeningPorts {});
+        }
+
+        #[test]
+        fn should_be_able_to_serialize_to_msgpack() {
+            let payload = Request::ListeningPorts {};
+
+            // NOTE: We don't actually check the output here because it's an implementation detail
+            // and could change as we change how serialization is done. This is merely to verify
+            // that we can serialize since there are times when serde fails to serialize at
+            // runtime.
+            let _ = rmp_serde::encode::to_vec_named(&payload).unwrap();
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_msgpack() {
+            // NOTE: It may seem odd that we are serializing just to deserialize, but this is to
+            // verify that we are not corrupting or causing issues when serializing on a
+            // client/server and then trying to deserialize on the other side. This has happened
+            // enough times with minor changes that we need tests to verify.
+            let buf = rmp_serde::encode::to_vec_named(&Request::ListeningPorts {}).unwrap();
+
+            let payload: Request = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(payload, Request::ListeningPorts {});
+        }
+    }
 }
@@ -5,8 +5,8 @@ use serde::{Deserialize, Serialize};
 use strum::{AsRefStr, EnumDiscriminants, EnumIter, EnumMessage, EnumString};
 
 use crate::protocol::common::{
-    Change, DirEntry, Error, Metadata, ProcessId, SearchId, SearchQueryMatch, StatusInfo,
-    SystemInfo, TunnelId, Version,
+    Change, DirEntry, Error, ListeningPort, Metadata, ProcOutputStamp, ProcessId, SearchId,
+    SearchQueryMatch, StatusInfo, SystemInfo, TunnelId, Version,
 };
 
 /// Represents the payload of a successful response
@@ -105,6 +105,11 @@ pub enum Response {
         /// Data read from a process' stdout pipe
         #[serde(with = "serde_bytes")]
         data: Vec<u8>,
+
+        /// Sequence number and timestamp of the chunk, only present if timestamps were
+        /// requested when spawning the process
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stamp: Option<ProcOutputStamp>,
     },
 
     /// Actively-transmitted stderr as part of running process
@@ -115,6 +120,11 @@ pub enum Response {
         /// Data read from a process' stderr pipe
         #[serde(with = "serde_bytes")]
         data: Vec<u8>,
+
+        /// Sequence number and timestamp of the chunk, only present if timestamps were
+        /// requested when spawning the process
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stamp: Option<ProcOutputStamp>,
     },
 
     /// Response to a process finishing
@@ -178,6 +188,12 @@ pub enum Response {
 
     /// Response containing aggregated status information
     StatusInfo(StatusInfo),
+
+    /// Response containing the TCP ports being listened on
+    ListeningPorts {
+        /// Listening sockets, one per bound address and port
+        ports: Vec<ListeningPort>,
+    },
 }
 
 impl From<io::Error> for Response {
@@ -1636,10 +1652,11 @@ mod tests {
         use super::*;
 
         #[test]
-        fn should_be_able_to_serialize_to_json() {
+        fn should_be_able_to_serialize_minimal_payload_to_json() {
             let payload = Response::ProcStdout {
                 id: ProcessId::MAX,
                 data: vec![0, 1, 2, u8::MAX],
+                stamp: None,
             };
 
             let value = serde_json::to_value(payload).unwrap();
@@ -1654,7 +1671,7 @@ mod tests {
         }
 
         #[test]
-        fn should_be_able_to_deserialize_from_json() {
+        fn should_be_able_to_deserialize_minimal_payload_from_json() {
             let value = serde_json::json!({
                 "type": "proc_stdout",
                 "id": ProcessId::MAX,
@@ -1667,15 +1684,17 @@ mod tests {
                 Response::ProcStdout {
                     id: ProcessId::MAX,
                     data: vec![0, 1, 2, u8::MAX],
+                    stamp: None,
                 }
             );
         }
 
         #[test]
-        fn should_be_able_to_serialize_to_msgpack() {
+        fn should_be_able_to_serialize_minimal_payload_to_msgpack() {
             let payload = Response::ProcStdout {
                 id: ProcessId::MAX,
                 data: vec![0, 1, 2, u8::MAX],
+                stamp: None,
             };
 
             // NOTE: We don't actually check the output here because it's an implementation detail
@@ -1686,7 +1705,7 @@ mod tests {
         }
 
         #[test]
-        fn should_be_able_to_deserialize_from_msgpack() {
+        fn should_be_able_to_deserialize_minimal_payload_from_msgpack() {
             // NOTE: It may seem odd that we are serializing just to deserialize, but this is to
             // verify that we are not corrupting or causing issues when serializing on a
             // client/server and then trying to deserialize on the other side. This has happened
@@ -1694,6 +1713,7 @@ mod tests {
             let buf = rmp_serde::encode::to_vec_named(&Response::ProcStdout {
                 id: ProcessId::MAX,
                 data: vec![0, 1, 2, u8::MAX],
+                stamp: None,
             })
             .unwrap();
 
@@ -1703,6 +1723,83 @@ mod tests {
                 Response::ProcStdout {
                     id: ProcessId::MAX,
                     data: vec![0, 1, 2, u8::MAX],
+                    stamp: None,
+                }
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_serialize_full_payload_to_json() {
+            let payload = Response::ProcStdout {
+                id: ProcessId::MAX,
+                data: vec![0, 1, 2, u8::MAX],
+                stamp: Some(ProcOutputStamp {
+                    seq: u64::MAX,
+                    timestamp_us: u64::MAX,
+                }),
+            };
+
+            let value = serde_json::to_value(payload).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!({
+                    "type": "proc_stdout",
+                    "id": ProcessId::MAX,
+                    "data": vec![0, 1, 2, u8::MAX],
+                    "stamp": { "seq": u64::MAX, "timestamp_us": u64::MAX },
+                })
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_full_payload_from_json() {
+            let value = serde_json::json!({
+                "type": "proc_stdout",
+                "id": ProcessId::MAX,
+                "data": vec![0, 1, 2, u8::MAX],
+                "stamp": { "seq": u64::MAX, "timestamp_us": u64::MAX },
+            });
+
+            let payload: Response = serde_json::from_value(value).unwrap();
+            assert_eq!(
+                payload,
+                Response::ProcStdout {
+                    id: ProcessId::MAX,
+                    data: vec![0, 1, 2, u8::MAX],
+                    stamp: Some(ProcOutputStamp {
+                        seq: u64::MAX,
+                        timestamp_us: u64::MAX,
+                    }),
+                }
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_full_payload_from_msgpack() {
+            // NOTE: It may seem odd that we are serializing just to deserialize, but this is to
+            // verify that we are not corrupting or causing issues when serializing on a
+            // client/server and then trying to deserialize on the other side. This has happened
+            // enough times with minor changes that we need tests to verify.
+            let buf = rmp_serde::encode::to_vec_named(&Response::ProcStdout {
+                id: ProcessId::MAX,
+                data: vec![0, 1, 2, u8::MAX],
+                stamp: Some(ProcOutputStamp {
+                    seq: 1,
+                    timestamp_us: 2,
+                }),
+            })
+            .unwrap();
+
+            let payload: Response = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(
+                payload,
+                Response::ProcStdout {
+                    id: ProcessId::MAX,
+                    data: vec![0, 1, 2, u8::MAX],
+                    stamp: Some(ProcOutputStamp {
+                        seq: 1,
+                        timestamp_us: 2,
+                    }),
                 }
             );
         }
@@ -1712,10 +1809,11 @@ mod tests {
         use super::*;
 
         #[test]
-        fn should_be_able_to_serialize_to_json() {
+        fn should_be_able_to_serialize_minimal_payload_to_json() {
             let payload = Response::ProcStderr {
                 id: ProcessId::MAX,
                 data: vec![0, 1, 2, u8::MAX],
+                stamp: None,
             };
 
             let value = serde_json::to_value(payload).unwrap();
@@ -1730,7 +1828,7 @@ mod tests {
         }
 
         #[test]
-        fn should_be_able_to_deserialize_from_json() {
+        fn should_be_able_to_deserialize_minimal_payload_from_json() {
             let value = serde_json::json!({
                 "type": "proc_stderr",
                 "id": ProcessId::MAX,
@@ -1743,15 +1841,17 @@ mod tests {
                 Response::ProcStderr {
                     id: ProcessId::MAX,
                     data: vec![0, 1, 2, u8::MAX],
+                    stamp: None,
                 }
             );
         }
 
         #[test]
-        fn should_be_able_to_serialize_to_msgpack() {
+        fn should_be_able_to_serialize_minimal_payload_to_msgpack() {
             let payload = Response::ProcStderr {
                 id: ProcessId::MAX,
                 data: vec![0, 1, 2, u8::MAX],
+                stamp: None,
             };
 
             // NOTE: We don't actually check the errput here because it's an implementation detail
@@ -1762,7 +1862,7 @@ mod tests {
         }
 
         #[test]
-        fn should_be_able_to_deserialize_from_msgpack() {
+        fn should_be_able_to_deserialize_minimal_payload_from_msgpack() {
             // NOTE: It may seem odd that we are serializing just to deserialize, but this is to
             // verify that we are not corrupting or causing issues when serializing on a
             // client/server and then trying to deserialize on the other side. This has happened
@@ -1770,6 +1870,7 @@ mod tests {
             let buf = rmp_serde::encode::to_vec_named(&Response::ProcStderr {
                 id: ProcessId::MAX,
                 data: vec![0, 1, 2, u8::MAX],
+                stamp: None,
             })
             .unwrap();
 
@@ -1779,6 +1880,83 @@ mod tests {
                 Response::ProcStderr {
                     id: ProcessId::MAX,
                     data: vec![0, 1, 2, u8::MAX],
+                    stamp: None,
+                }
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_serialize_full_payload_to_json() {
+            let payload = Response::ProcStderr {
+                id: ProcessId::MAX,
+                data: vec![0, 1, 2, u8::MAX],
+                stamp: Some(ProcOutputStamp {
+                    seq: u64::MAX,
+                    timestamp_us: u64::MAX,
+                }),
+            };
+
+            let value = serde_json::to_value(payload).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!({
+                    "type": "proc_stderr",
+                    "id": ProcessId::MAX,
+                    "data": vec![0, 1, 2, u8::MAX],
+                    "stamp": { "seq": u64::MAX, "timestamp_us": u64::MAX },
+                })
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_full_payload_from_json() {
+            let value = serde_json::json!({
+                "type": "proc_stderr",
+                "id": ProcessId::MAX,
+                "data": vec![0, 1, 2, u8::MAX],
+                "stamp": { "seq": u64::MAX, "timestamp_us": u64::MAX },
+            });
+
+            let payload: Response = serde_json::from_value(value).unwrap();
+            assert_eq!(
+                payload,
+                Response::ProcStderr {
+                    id: ProcessId::MAX,
+                    data: vec![0, 1, 2, u8::MAX],
+                    stamp: Some(ProcOutputStamp {
+                        seq: u64::MAX,
+                        timestamp_us: u64::MAX,
+                    }),
+                }
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_full_payload_from_msgpack() {
+            // NOTE: It may seem odd that we are serializing just to deserialize, but this is to
+            // verify that we are not corrupting or causing issues when serializing on a
+            // client/server and then trying to deserialize on the other side. This has happened
+            // enough times with minor changes that we need tests to verify.
+            let buf = rmp_serde::encode::to_vec_named(&Response::ProcStderr {
+                id: ProcessId::MAX,
+                data: vec![0, 1, 2, u8::MAX],
+                stamp: Some(ProcOutputStamp {
+                    seq: 1,
+                    timestamp_us: 2,
+                }),
+            })
+            .unwrap();
+
+            let payload: Response = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(
+                payload,
+                Response::ProcStderr {
+                    id: ProcessId::MAX,
+                    data: vec![0, 1, 2, u8::MAX],
+                    stamp: Some(ProcOutputStamp {
+                        seq: 1,
+                        timestamp_us: 2,
+                    }),
                 }
             );
         }
@@ -2522,7 +2700,7 @@ mod tests {
 
     mod status_info {
         use super::*;
-        use crate::protocol::{TunnelDirection, TunnelInfo};
+        use crate::protocol::{TunnelDirection, TunnelInfo, TunnelProtocol, TunnelStats};
 
         #[test]
         fn should_be_able_to_serialize_to_json() {
@@ -2532,6 +2710,8 @@ mod tests {
                     direction: TunnelDirection::Forward,
                     host: String::from("localhost"),
                     port: 8080,
+                    protocol: TunnelProtocol::Tcp,
+                    stats: TunnelStats::default(),
                 }],
             });
 
@@ -2575,6 +2755,8 @@ mod tests {
                         direction: TunnelDirection::Forward,
                         host: String::from("localhost"),
                         port: 8080,
+                        protocol: TunnelProtocol::Tcp,
+                        stats: TunnelStats::default(),
                     }],
                 })
             );
@@ -2602,6 +2784,8 @@ mod tests {
                     direction: TunnelDirection::Forward,
                     host: String::from("localhost"),
                     port: 8080,
+                    protocol: TunnelProtocol::Tcp,
+                    stats: TunnelStats::default(),
                 }],
             });
 
@@ -2624,6 +2808,8 @@ mod tests {
                     direction: TunnelDirection::Forward,
                     host: String::from("localhost"),
                     port: 8080,
+                    protocol: TunnelProtocol::Tcp,
+                    stats: TunnelStats::default(),
                 }],
             }))
             .unwrap();
@@ -2637,9 +2823,105 @@ mod tests {
                         direction: TunnelDirection::Forward,
                         host: String::from("localhost"),
                         port: 8080,
+                        protocol: TunnelProtocol::Tcp,
+                        stats: TunnelStats::default(),
                     }],
                 })
             );
         }
     }
+
+    mod listening_ports {
+        use super::*;
+
+        #[test]
+        fn should_be_able_to_serialize_to_json() {
+            let payload = Response::ListeningPorts {
+                ports: vec![ListeningPort {
+                    address: String::from("127.0.0.1"),
+                    port: 3000,
+                }],
+            };
+
+            let value = serde_json::to_value(payload).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!({
+                    "type": "listening_ports",
+                    "ports": [
+                        {
+                            "address": "127.0.0.1",
+                            "port": 3000,
+                        }
+                    ],
+                })
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_json() {
+            let value = serde_json::json!({
+                "type": "listening_ports",
+                "ports": [
+                    {
+                        "address": "::",
+                        "port": 8080,
+                    }
+                ],
+            });
+
+            let payload: Response = serde_json::from_value(value).unwrap();
+            assert_eq!(
+                payload,
+                Response::ListeningPorts {
+                    ports: vec![ListeningPort {
+                        address: String::from("::"),
+                        port: 8080,
+                    }],
+                }
+            );
+        }
+
+        #[test]
+        fn should_be_able_to_serialize_to_msgpack() {
+            let payload = Response::ListeningPorts {
+                ports: vec![ListeningPort {
+                    address: String::from("127.0.0.1"),
+                    port: 3000,
+                }],
+            };
+
+            // NOTE: We don't actually check the output here because it's an implementation detail
+            // and could change as we change how serialization is done. This is merely to verify
+            // that we can serialize since there are times when serde fails to serialize at
+            // runtime.
+            let _ = rmp_serde::encode::to_vec_named(&payload).unwrap();
+        }
+
+        #[test]
+        fn should_be_able_to_deserialize_from_msgpack() {
+            // NOTE: It may seem odd that we are serializing just to deserialize, but this is to
+            // verify that we are not corrupting or causing issues when serializing on a
+            // client/server and then trying to deserialize on the other side. This has happened
+            // enough times with minor changes that we need tests to verify.
+            let buf = rmp_serde::encode::to_vec_named(&Response::ListeningPorts {
+                ports: vec![ListeningPort {
+                    address: String::from("127.0.0.1"),
+                    port: 3000,
+                }],
+            })
+            .unwrap();
+
+            let payload: Response = rmp_serde::decode::from_slice(&buf).unwrap();
+            assert_eq!(
+                payload,
+                Response::ListeningPorts {
+                    ports: vec![ListeningPort {
+                        address: String::from("127.0.0.1"),
+                        port: 3000,
+                    }],
+                }
+            );
+        }
+    }
 }
@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Weak};
 use std::time::Duration;
@@ -14,10 +15,11 @@ use bollard::exec::{CreateExecOptions, StartExecOptions, StartExecResults};
 use distant_core::constants::TUNNEL_CHANNEL_CAPACITY;
 use distant_core::net::server::Reply;
 use distant_core::protocol::{
-    ChangeKind, DirEntry, Environment, FileType, Metadata, PROTOCOL_VERSION, Permissions,
-    ProcessId, PtySize, RemotePath, Response, SearchId, SearchQuery, SearchQueryTarget,
-    SetPermissionsOptions, StatusInfo, SystemInfo, TunnelDirection, TunnelId, TunnelInfo,
-    UnixMetadata, Version,
+    ChangeKind, Cmd, DirEntry, Environment, FileType, ListeningPort, Metadata, PROTOCOL_VERSION,
+    Permissions, ProcOutputStamper, ProcSpawnOptions, ProcessId, PtySize, RemotePath, Response,
+    SearchId, SearchQuery, SearchQueryTarget, SetPermissionsOptions, StatusInfo, SystemInfo,
+    TunnelCounters, TunnelDirection, TunnelId, TunnelInfo, TunnelProtocol, TunnelStats,
+    UnixMetadata, Version, parse_env_file,
 };
 use distant_core::{Api, Ctx};
 use futures::StreamExt;
@@ -27,6 +29,7 @@ use tokio::task::JoinHandle;
 
 use crate::process::{self, Process, SpawnResult};
 use crate::search;
+use crate::tunnel::{ListenerEvent, ListenerEvents, ReverseTunnelTool};
 use crate::utils::{self, SearchTools, TunnelTools};
 use crate::{DockerClient, DockerOpts};
 
@@ -34,17 +37,91 @@ use crate::{DockerClient, DockerOpts};
 /// Allows the remote relay process to flush its response before the session is torn down.
 const TUNNEL_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Timeout for a reverse tunnel listener inside the container to report that it is listening.
+const TUNNEL_LISTEN_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Global counter for generating unique tunnel IDs across all Docker connections.
 static NEXT_TUNNEL_ID: AtomicU32 = AtomicU32::new(1);
 
-/// Internal state for a single active Docker tunnel (forward connection).
+/// Output of a Docker exec started with its streams attached.
+type ExecOutput = Pin<Box<dyn futures::Stream<Item = Result<LogOutput, BollardError>> + Send>>;
+
+/// Stdin of a Docker exec started with its streams attached.
+type ExecInput = Pin<Box<dyn tokio::io::AsyncWrite + Send>>;
+
+/// Internal state for a single active Docker tunnel: a forward connection, a reverse listener,
+/// or a connection accepted by a reverse listener.
 struct DockerTunnel {
     /// Metadata about the tunnel (id, direction, host, port).
     info: TunnelInfo,
-    /// Sender to write data into the tunnel's exec stdin.
+    /// Sender to write data into the tunnel's exec stdin. Dropping it stops a reverse listener.
     write_tx: mpsc::Sender<Vec<u8>>,
     /// Handle to the background relay task; detached on close to allow graceful drain.
     task: JoinHandle<()>,
+    /// Traffic through the tunnel.
+    counters: Arc<TunnelCounters>,
+}
+
+impl DockerTunnel {
+    /// Returns information about the tunnel, including the traffic that has passed through it.
+    fn to_info(&self) -> TunnelInfo {
+        TunnelInfo {
+            stats: self.counters.stats(),
+            ..self.info.clone()
+        }
+    }
+}
+
+/// Container and user that tunnel relay processes are run as.
+struct TunnelExec {
+    client: bollard::Docker,
+    container: String,
+    user: Option<String>,
+}
+
+impl TunnelExec {
+    /// Starts `cmd` inside the container with stdin and stdout attached, and stderr too if
+    /// `attach_stderr` is true.
+    async fn start(
+        &self,
+        cmd: Vec<String>,
+        attach_stderr: bool,
+    ) -> io::Result<(ExecOutput, ExecInput)> {
+        let created = self
+            .client
+            .create_exec(
+                &self.container,
+                CreateExecOptions {
+                    cmd: Some(cmd),
+                    attach_stdin: Some(true),
+                    attach_stdout: Some(true),
+                    attach_stderr: Some(attach_stderr),
+                    user: self.user.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(io::Error::other)?;
+
+        let start_result = self
+            .client
+            .start_exec(
+                &created.id,
+                Some(StartExecOptions {
+                    detach: false,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .map_err(io::Error::other)?;
+
+        match start_result {
+            StartExecResults::Attached { output, input } => Ok((output, input)),
+            StartExecResults::Detached => Err(io::Error::other(
+                "exec started in detached mode unexpectedly",
+            )),
+        }
+    }
 }
 
 /// Docker implementation of the distant [`Api`] trait.
@@ -135,6 +212,28 @@ impl DockerApi {
         }
     }
 
+    /// Get the shell of the container user via `$SHELL`, falling back to `/bin/sh`, querying it
+    /// once and caching it thereafter.
+    async fn shell(&self) -> io::Result<String> {
+        Ok(self
+            .cached_shell
+            .get_or_try_init(async {
+                match self.run_shell_cmd_stdout("echo $SHELL").await {
+                    Ok(output) => {
+                        let s = output.trim().to_string();
+                        if s.is_empty() {
+                            Ok::<String, io::Error>(String::from("/bin/sh"))
+                        } else {
+                            Ok(s)
+                        }
+                    }
+                    Err(_) => Ok(String::from("/bin/sh")),
+                }
+            })
+            .await?
+            .clone())
+    }
+
     /// Execute a shell command string using `sh -c`.
     async fn run_shell_cmd(&self, script: &str) -> io::Result<utils::ExecOutput> {
         self.run_cmd(&["sh", "-c", script]).await
@@ -183,6 +282,8 @@ impl Api for DockerApi {
                 Version::CAP_FS_IO.to_string(),
                 Version::CAP_SYS_INFO.to_string(),
                 Version::CAP_FS_PERM.to_string(),
+                Version::CAP_COMPACT_PACKETS.to_string(),
+                Version::CAP_LISTENING_PORTS.to_string(),
             ];
 
             // Only advertise search if we have tools
@@ -195,6 +296,10 @@ impl Api for DockerApi {
                 capabilities.push(Version::CAP_TCP_TUNNEL.to_string());
             }
 
+            if ReverseTunnelTool::detect(&self.tunnel_tools).is_some() {
+                capabilities.push(Version::CAP_TCP_REV_TUNNEL.to_string());
+            }
+
             let mut server_version: semver::Version = env!("CARGO_PKG_VERSION")
                 .parse()
                 .map_err(|e| io::Error::other(format!("Failed to parse version: {}", e)))?;
@@ -881,14 +986,48 @@ impl Api for DockerApi {
         environment: Environment,
         current_dir: Option<RemotePath>,
         pty: Option<PtySize>,
+        options: ProcSpawnOptions,
     ) -> impl std::future::Future<Output = io::Result<ProcessId>> + Send {
         let client = self.client.inner();
         let container = &self.container;
-        let user = self.user().map(|s| s.to_string());
         let processes = &self.processes;
         let global_processes = Arc::downgrade(processes);
 
+        // Requested user takes precedence over the one the container was configured with
+        let user = options
+            .limits
+            .user
+            .clone()
+            .or_else(|| self.user().map(|s| s.to_string()));
+
         async move {
+            debug!(
+                "[Conn {}] Spawning {} {{environment: {:?}, current_dir: {:?}, pty: {:?}, options: {:?}}}",
+                ctx.connection_id, cmd, environment, current_dir, pty, options
+            );
+
+            // Explicit variables take precedence over those loaded from the env file
+            let environment = match options.env_file.as_ref() {
+                Some(path) => {
+                    let contents = utils::tar_read_file(client, container, path.as_str()).await?;
+                    let contents = String::from_utf8(contents)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                    let mut env_file = parse_env_file(&contents)?;
+                    env_file.extend(environment);
+                    env_file
+                }
+                None => environment,
+            };
+
+            let cmd = if options.login {
+                Cmd::new(cmd).into_login_shell(&self.shell().await?)?.into()
+            } else {
+                cmd
+            };
+
+            let (cmd, environment) = process::wrap_with_env_controls(&cmd, environment, &options);
+            let cmd = process::wrap_with_limits(&cmd, &options.limits);
+            let stamper = ProcOutputStamper::new_if(options.timestamps);
             let make_cleanup = |processes_ref: Weak<RwLock<HashMap<ProcessId, Process>>>| {
                 move |id: ProcessId| async move {
                     if let Some(processes) = processes_ref.upgrade() {
@@ -914,6 +1053,7 @@ impl Api for DockerApi {
                         environment,
                         current_dir,
                         user.as_deref(),
+                        stamper,
                         ctx.reply.clone_reply(),
                         make_cleanup(global_processes),
                     )
@@ -928,6 +1068,7 @@ impl Api for DockerApi {
                         current_dir,
                         size,
                         user.as_deref(),
+                        stamper,
                         ctx.reply.clone_reply(),
                         make_cleanup(global_processes),
                     )
@@ -935,6 +1076,19 @@ impl Api for DockerApi {
                 }
             };
 
+            // Kill the process if it is still running once its timeout elapses. A weak sender is
+            // used so that we do not keep the process' kill channel open after it has exited.
+            if let Some(timeout) = options.limits.timeout() {
+                let killer = killer.downgrade();
+                tokio::spawn(async move {
+                    tokio::time::sleep(timeout).await;
+                    if let Some(killer) = killer.upgrade() {
+                        debug!("Process {id} exceeded timeout of {timeout:?}, so killing it");
+                        let _ = killer.send(()).await;
+                    }
+                });
+            }
+
             let process = Process {
                 id,
                 stdin_tx: Some(stdin),
@@ -1004,6 +1158,27 @@ impl Api for DockerApi {
         }
     }
 
+    fn proc_stdin_close(
+        &self,
+        _ctx: Ctx,
+        id: ProcessId,
+    ) -> impl std::future::Future<Output = io::Result<()>> + Send {
+        async move {
+            let mut processes = self.processes.write().await;
+            match processes.get_mut(&id) {
+                Some(process) => {
+                    // Dropping the sender shuts down the exec's stdin stream
+                    process.stdin_tx.take();
+                    Ok(())
+                }
+                None => Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No process found with id {}", id),
+                )),
+            }
+        }
+    }
+
     fn proc_resize_pty(
         &self,
         _ctx: Ctx,
@@ -1067,23 +1242,7 @@ impl Api for DockerApi {
                 .await?
                 .clone();
 
-            let shell = self
-                .cached_shell
-                .get_or_try_init(async {
-                    match self.run_shell_cmd_stdout("echo $SHELL").await {
-                        Ok(output) => {
-                            let s = output.trim().to_string();
-                            if s.is_empty() {
-                                Ok::<String, io::Error>(String::from("/bin/sh"))
-                            } else {
-                                Ok(s)
-                            }
-                        }
-                        Err(_) => Ok(String::from("/bin/sh")),
-                    }
-                })
-                .await?
-                .clone();
+            let shell = self.shell().await?;
 
             let arch = self
                 .run_cmd_stdout(&["uname", "-m"])
@@ -1140,92 +1299,137 @@ impl Api for DockerApi {
                 vec!["nc".to_string(), host.clone(), port.to_string()]
             };
 
-            let created = client
-                .create_exec(
-                    &container,
-                    CreateExecOptions {
-                        cmd: Some(cmd),
-                        attach_stdin: Some(true),
-                        attach_stdout: Some(true),
-                        attach_stderr: Some(false),
-                        user,
-                        ..Default::default()
-                    },
-                )
-                .await
-                .map_err(|e| {
-                    io::Error::other(format!(
-                        "Failed to create tunnel relay to {host}:{port}: {e}"
-                    ))
-                })?;
+            let exec = TunnelExec {
+                client,
+                container,
+                user,
+            };
+            let (output, input) = exec.start(cmd, false).await.map_err(|e| {
+                io::Error::other(format!(
+                    "Failed to start tunnel relay to {host}:{port}: {e}"
+                ))
+            })?;
+
+            let id = NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
+            let info = TunnelInfo {
+                id,
+                direction: TunnelDirection::Forward,
+                host,
+                port,
+                protocol: TunnelProtocol::Tcp,
+                stats: TunnelStats::default(),
+            };
+            spawn_docker_tunnel_relay(
+                info,
+                output,
+                input,
+                ctx.reply.clone_reply(),
+                tunnels,
+                TunnelCounters::new(),
+            )
+            .await;
 
-            let start_result = client
-                .start_exec(
-                    &created.id,
-                    Some(StartExecOptions {
-                        detach: false,
-                        ..Default::default()
-                    }),
+            Ok(id)
+        }
+    }
+
+    fn tunnel_listen(
+        &self,
+        ctx: Ctx,
+        host: String,
+        port: u16,
+    ) -> impl std::future::Future<Output = io::Result<(TunnelId, u16)>> + Send {
+        let tunnels = Arc::clone(&self.tunnels);
+        let exec = TunnelExec {
+            client: self.client.inner().clone(),
+            container: self.container.clone(),
+            user: self.user().map(|s| s.to_string()),
+        };
+        let tool = ReverseTunnelTool::detect(&self.tunnel_tools);
+
+        async move {
+            let tool = tool.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "No tools available to listen for tunnels in this container. \
+                     Install socat or python3 for reverse tunnel support.",
                 )
+            })?;
+
+            debug!(
+                "[Conn {}] Listening for reverse tunnels on {}:{} using {:?}",
+                ctx.connection_id, host, port, tool
+            );
+
+            let (output, input) = exec
+                .start(tool.listen_cmd(&host, port), true)
                 .await
                 .map_err(|e| {
                     io::Error::other(format!(
-                        "Failed to start tunnel relay to {host}:{port}: {e}"
+                        "Failed to start tunnel listener on {host}:{port}: {e}"
                     ))
                 })?;
 
-            let id = NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
+            // Wait for the listener to bind, which tells us the actual port when given 0
+            let mut events = ListenerEvents::new(output);
+            let bound_port = tokio::time::timeout(TUNNEL_LISTEN_TIMEOUT, async {
+                let mut error = None;
+                while let Some(event) = events.next().await {
+                    match event {
+                        ListenerEvent::Listening(port) => return Ok(port),
+                        ListenerEvent::Error(msg) => error = Some(msg),
+                        ListenerEvent::Accept { .. } => {}
+                    }
+                }
 
-            match start_result {
-                StartExecResults::Attached { output, input } => {
-                    let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(TUNNEL_CHANNEL_CAPACITY);
-                    let reply = ctx.reply.clone_reply();
-                    let tunnels_task = Arc::clone(&tunnels);
+                Err(io::Error::other(format!(
+                    "Failed to listen on {host}:{port}: {}",
+                    error.as_deref().unwrap_or("listener exited unexpectedly")
+                )))
+            })
+            .await
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("Timed out waiting for tunnel listener on {host}:{port}"),
+                )
+            })??;
 
-                    let task = tokio::spawn(docker_tunnel_relay_task(
-                        id,
-                        output,
-                        input,
-                        write_rx,
-                        reply,
-                        tunnels_task,
-                    ));
+            let id = NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
+            let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(1);
+            let counters = TunnelCounters::new();
 
-                    tunnels.write().await.insert(
+            // Hold the lock while spawning so that accepted connections register after us
+            let mut tunnels_lock = tunnels.write().await;
+            let task = tokio::spawn(docker_tunnel_listener_task(
+                id,
+                tool,
+                exec,
+                events,
+                input,
+                write_rx,
+                ctx.reply.clone_reply(),
+                Arc::clone(&tunnels),
+                Arc::clone(&counters),
+            ));
+            tunnels_lock.insert(
+                id,
+                DockerTunnel {
+                    info: TunnelInfo {
                         id,
-                        DockerTunnel {
-                            info: TunnelInfo {
-                                id,
-                                direction: TunnelDirection::Forward,
-                                host,
-                                port,
-                            },
-                            write_tx,
-                            task,
-                        },
-                    );
-
-                    Ok(id)
-                }
-                StartExecResults::Detached => Err(io::Error::other(
-                    "Tunnel relay started in detached mode unexpectedly",
-                )),
-            }
-        }
-    }
+                        direction: TunnelDirection::Reverse,
+                        host,
+                        port: bound_port,
+                        protocol: TunnelProtocol::Tcp,
+                        stats: TunnelStats::default(),
+                    },
+                    write_tx,
+                    task,
+                    counters,
+                },
+            );
 
-    #[allow(unused_variables)]
-    fn tunnel_listen(
-        &self,
-        _ctx: Ctx,
-        _host: String,
-        _port: u16,
-    ) -> impl std::future::Future<Output = io::Result<(TunnelId, u16)>> + Send {
-        async {
-            Err(io::Error::new(
-                io::ErrorKind::Unsupported,
-                "tunnel_listen is not supported for Docker backends",
-            ))
+            Ok((id, bound_port))
         }
     }
 
@@ -1270,32 +1474,183 @@ impl Api for DockerApi {
         async move {
             let tunnels = self.tunnels.read().await;
             Ok(StatusInfo {
-                tunnels: tunnels.values().map(|t| t.info.clone()).collect(),
+                tunnels: tunnels.values().map(DockerTunnel::to_info).collect(),
             })
         }
     }
+
+    fn listening_ports(
+        &self,
+        ctx: Ctx,
+    ) -> impl std::future::Future<Output = io::Result<Vec<ListeningPort>>> + Send {
+        async move {
+            debug!("[Conn {}] Listing listening ports", ctx.connection_id);
+
+            let output = self
+                .run_shell_cmd("ss -ltn 2>/dev/null || netstat -ltn 2>/dev/null")
+                .await?;
+            if !output.success() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Listing listening ports requires ss or netstat in the container",
+                ));
+            }
+
+            Ok(ListeningPort::parse_socket_table(&output.stdout_str()))
+        }
+    }
+}
+
+/// Spawns [`docker_tunnel_relay_task`] for a relay exec and registers it as a tunnel.
+async fn spawn_docker_tunnel_relay(
+    info: TunnelInfo,
+    output: ExecOutput,
+    input: ExecInput,
+    reply: Box<dyn Reply<Data = Response>>,
+    tunnels: Arc<RwLock<HashMap<TunnelId, DockerTunnel>>>,
+    counters: Arc<TunnelCounters>,
+) {
+    let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(TUNNEL_CHANNEL_CAPACITY);
+    let task = tokio::spawn(docker_tunnel_relay_task(
+        info.id,
+        info.direction.clone(),
+        output,
+        input,
+        write_rx,
+        reply,
+        Arc::clone(&tunnels),
+        Arc::clone(&counters),
+    ));
+
+    tunnels.write().await.insert(
+        info.id,
+        DockerTunnel {
+            info,
+            write_tx,
+            task,
+            counters,
+        },
+    );
+}
+
+/// Runs a reverse tunnel listener, relaying every connection it accepts as a new tunnel.
+///
+/// For each accepted connection, starts a relay exec attached to the Unix socket the connection
+/// is parked at, sends `TunnelIncoming`, and registers the connection as a tunnel sharing the
+/// listener's traffic counters. Stops the listener by closing its stdin once `write_rx` closes,
+/// then sends `TunnelClosed` for the listener and removes it from the map.
+#[allow(clippy::too_many_arguments)]
+async fn docker_tunnel_listener_task(
+    listener_id: TunnelId,
+    tool: ReverseTunnelTool,
+    exec: TunnelExec,
+    mut events: ListenerEvents<ExecOutput>,
+    mut input: ExecInput,
+    mut write_rx: mpsc::Receiver<Vec<u8>>,
+    reply: Box<dyn Reply<Data = Response>>,
+    tunnels: Arc<RwLock<HashMap<TunnelId, DockerTunnel>>>,
+    counters: Arc<TunnelCounters>,
+) {
+    use tokio::io::AsyncWriteExt;
+
+    loop {
+        tokio::select! {
+            event = events.next() => match event {
+                Some(ListenerEvent::Accept { socket, peer_host, peer_port }) => {
+                    let (output, input) = match exec.start(tool.connect_cmd(&socket), false).await {
+                        Ok(x) => x,
+                        Err(x) => {
+                            error!("[Listener {listener_id}] Failed to relay connection from {peer_host}:{peer_port}: {x}");
+                            continue;
+                        }
+                    };
+
+                    let tunnel_id = NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
+                    if reply
+                        .send(Response::TunnelIncoming {
+                            listener_id,
+                            tunnel_id,
+                            peer_addr: Some(ListenerEvent::peer_addr(&peer_host, peer_port)),
+                        })
+                        .is_err()
+                    {
+                        break;
+                    }
+
+                    let info = TunnelInfo {
+                        id: tunnel_id,
+                        direction: TunnelDirection::Reverse,
+                        host: peer_host,
+                        port: peer_port,
+                        protocol: TunnelProtocol::Tcp,
+                        stats: TunnelStats::default(),
+                    };
+                    spawn_docker_tunnel_relay(
+                        info,
+                        output,
+                        input,
+                        reply.clone_reply(),
+                        Arc::clone(&tunnels),
+                        TunnelCounters::child(&counters),
+                    )
+                    .await;
+                }
+                Some(ListenerEvent::Error(msg)) => {
+                    warn!("[Listener {listener_id}] {msg}");
+                }
+                Some(ListenerEvent::Listening(_)) => {}
+                None => break,
+            },
+            data = write_rx.recv() => {
+                // Data cannot be written to a listener, so only the channel closing matters
+                if data.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    let _ = input.shutdown().await;
+    drop(input);
+    drop(events);
+
+    let _ = reply.send(Response::TunnelClosed { id: listener_id });
+    tunnels.write().await.remove(&listener_id);
 }
 
 /// Manages the bidirectional I/O relay for a single Docker tunnel.
 ///
 /// Reads from the container relay process stdout and sends `TunnelData` responses via the
 /// reply channel. Writes data received on `write_rx` to the relay process stdin. Sends
-/// `TunnelClosed` and removes the tunnel from the map when the connection ends.
+/// `TunnelClosed` and removes the tunnel from the map when the connection ends, recording
+/// the traffic to and from the target in `counters`. For a reverse tunnel the target is the
+/// client, so the data read from the container is what is sent to the target.
+#[allow(clippy::too_many_arguments)]
 async fn docker_tunnel_relay_task(
     id: TunnelId,
+    direction: TunnelDirection,
     mut output: impl futures::Stream<Item = Result<LogOutput, BollardError>> + Unpin + Send,
     mut input: impl tokio::io::AsyncWrite + Unpin + Send + 'static,
     mut write_rx: mpsc::Receiver<Vec<u8>>,
     reply: Box<dyn Reply<Data = Response>>,
     tunnels: Arc<RwLock<HashMap<TunnelId, DockerTunnel>>>,
+    counters: Arc<TunnelCounters>,
 ) {
     use tokio::io::AsyncWriteExt;
 
+    let _connection = counters.connection();
+    let is_target = direction == TunnelDirection::Forward;
+    let write_counters = Arc::clone(&counters);
     let mut write_task = tokio::spawn(async move {
         while let Some(data) = write_rx.recv().await {
             if input.write_all(&data).await.is_err() {
                 break;
             }
+            if is_target {
+                write_counters.record_out(data.len());
+            } else {
+                write_counters.record_in(data.len());
+            }
         }
     });
 
@@ -1307,10 +1662,17 @@ async fn docker_tunnel_relay_task(
         id: TunnelId,
         output: &mut (impl futures::Stream<Item = Result<LogOutput, BollardError>> + Unpin),
         reply: &dyn Reply<Data = Response>,
+        counters: &TunnelCounters,
+        is_target: bool,
     ) {
         while let Some(msg) = output.next().await {
             match msg {
                 Ok(LogOutput::StdOut { message }) => {
+                    if is_target {
+                        counters.record_in(message.len());
+                    } else {
+                        counters.record_out(message.len());
+                    }
                     if !message.is_empty()
                         && reply
                             .send(Response::TunnelData {
@@ -1329,7 +1691,7 @@ async fn docker_tunnel_relay_task(
     }
 
     tokio::select! {
-        _ = drain_output(id, &mut output, &*reply) => {
+        _ = drain_output(id, &mut output, &*reply, &counters, is_target) => {
             write_task.abort();
         }
         _ = &mut write_task => {
@@ -1339,7 +1701,7 @@ async fn docker_tunnel_relay_task(
             // timeout so the remote process can flush its response.
             let _ = tokio::time::timeout(
                 TUNNEL_DRAIN_TIMEOUT,
-                drain_output(id, &mut output, &*reply),
+                drain_output(id, &mut output, &*reply, &counters, is_target),
             )
             .await;
         }
@@ -247,6 +247,7 @@ mod api;
 mod plugin;
 mod process;
 pub(crate) mod search;
+mod tunnel;
 pub mod utils;
 
 pub use plugin::DockerPlugin;
@@ -6,11 +6,15 @@ use std::io;
 use bollard::Docker;
 use bollard::exec::{CreateExecOptions, ResizeExecOptions, StartExecOptions, StartExecResults};
 use distant_core::net::server::Reply;
-use distant_core::protocol::{Environment, ProcessId, PtySize, Response};
+use distant_core::protocol::{
+    Environment, ProcOutputStamper, ProcSpawnOptions, ProcessId, ProcessLimits, PtySize, Response,
+};
 use futures::StreamExt;
 use log::*;
 use tokio::sync::mpsc;
 
+use crate::utils;
+
 /// Represents a spawned process tracked by the Docker API.
 #[allow(dead_code)]
 pub struct Process {
@@ -48,6 +52,59 @@ pub struct SpawnResult {
     pub exec_id: String,
 }
 
+/// Wraps a command so that `sh` applies resource limits via `ulimit` and niceness via `nice`
+/// before running it.
+///
+/// The command is run by a nested `sh -c` so that the limits cover everything it does, and an
+/// empty command (an interactive shell) becomes a limited `sh`. The timeout is not handled here
+/// as it is enforced by killing the process, and the user is applied to the exec itself.
+pub fn wrap_with_limits(cmd: &str, limits: &ProcessLimits) -> String {
+    let Some(prefix) = limits.to_shell_prefix() else {
+        return cmd.to_string();
+    };
+
+    if cmd.is_empty() {
+        format!("{prefix} sh")
+    } else {
+        format!("{prefix} sh -c {}", utils::shell_quote(cmd))
+    }
+}
+
+/// Wraps a command so that it is run by `env`, which clears or unsets the environment the exec
+/// inherits from the container before setting the explicit `environment`.
+///
+/// Variables are inlined into the `env` invocation, as those set on the exec itself would be
+/// cleared along with the rest of the container's environment, so the returned environment is
+/// empty whenever the command was wrapped. An empty command (an interactive shell) becomes `sh`.
+pub fn wrap_with_env_controls(
+    cmd: &str,
+    environment: Environment,
+    options: &ProcSpawnOptions,
+) -> (String, Environment) {
+    if !options.overrides_inherited_env() {
+        return (cmd.to_string(), environment);
+    }
+
+    let mut parts = vec![String::from("exec env")];
+    if options.clear_env {
+        parts.push(String::from("-i"));
+    }
+    for key in options.unset_env.iter() {
+        parts.push(format!("-u {}", utils::shell_quote(key)));
+    }
+    for (key, value) in environment {
+        parts.push(utils::shell_quote(&format!("{key}={value}")));
+    }
+
+    if cmd.is_empty() {
+        parts.push(String::from("sh"));
+    } else {
+        parts.push(format!("sh -c {}", utils::shell_quote(cmd)));
+    }
+
+    (parts.join(" "), Environment::new())
+}
+
 /// Spawns a simple (non-PTY) process in a Docker container.
 #[allow(clippy::too_many_arguments)]
 pub async fn spawn_simple<F, Fut>(
@@ -57,6 +114,7 @@ pub async fn spawn_simple<F, Fut>(
     environment: Environment,
     current_dir: Option<std::path::PathBuf>,
     user: Option<&str>,
+    stamper: Option<ProcOutputStamper>,
     reply: Box<dyn Reply<Data = Response>>,
     cleanup: F,
 ) -> io::Result<SpawnResult>
@@ -131,12 +189,14 @@ where
                             let _ = stdout_reply.send(Response::ProcStdout {
                                 id: msg_id,
                                 data: message.to_vec(),
+                                stamp: stamper.as_ref().map(ProcOutputStamper::next_stamp),
                             });
                         }
                         Ok(bollard::container::LogOutput::StdErr { message }) => {
                             let _ = stderr_reply.send(Response::ProcStderr {
                                 id: msg_id,
                                 data: message.to_vec(),
+                                stamp: stamper.as_ref().map(ProcOutputStamper::next_stamp),
                             });
                         }
                         Ok(_) => {}
@@ -171,13 +231,15 @@ where
                 cleanup(msg_id).await;
             });
 
-            // Writer task: forwards stdin from the client and handles kill
+            // Writer task: forwards stdin from the client and handles kill. Once the stdin
+            // sender is dropped, the write half is shut down so the process receives EOF.
             tokio::spawn(async move {
                 use tokio::io::AsyncWriteExt;
 
+                let mut stdin_open = true;
                 loop {
                     tokio::select! {
-                        data = stdin_rx.recv() => {
+                        data = stdin_rx.recv(), if stdin_open => {
                             match data {
                                 Some(data) => {
                                     if let Err(e) = input.write_all(&data).await {
@@ -185,7 +247,11 @@ where
                                         break;
                                     }
                                 }
-                                None => break,
+                                None => {
+                                    debug!("Stdin closed for process {}", id);
+                                    stdin_open = false;
+                                    let _ = input.shutdown().await;
+                                }
                             }
                         }
                         _ = kill_rx.recv() => {
@@ -221,6 +287,7 @@ pub async fn spawn_pty<F, Fut>(
     current_dir: Option<std::path::PathBuf>,
     size: PtySize,
     user: Option<&str>,
+    stamper: Option<ProcOutputStamper>,
     reply: Box<dyn Reply<Data = Response>>,
     cleanup: F,
 ) -> io::Result<SpawnResult>
@@ -317,6 +384,7 @@ where
                             let _ = stdout_reply.send(Response::ProcStdout {
                                 id: msg_id,
                                 data: message.to_vec(),
+                                stamp: stamper.as_ref().map(ProcOutputStamper::next_stamp),
                             });
                         }
                         Ok(_) => {}
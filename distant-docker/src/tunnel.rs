@@ -0,0 +1,307 @@
+//! Reverse tunnel support for Docker containers.
+//!
+//! Docker has no API for listening on a port inside a container, so reverse tunnels run a
+//! listener process inside the container via exec. For every connection it accepts, the
+//! listener parks the connection behind a fresh Unix socket in a private temporary directory
+//! and reports it on stderr. The backend then starts a second exec that connects to that socket
+//! and relays it over the exec's stdin/stdout, exactly like a forward tunnel.
+//!
+//! The listener reports events as single lines on stderr:
+//!
+//! - `DISTANT-LISTENING <port>` once bound (python3 only; socat's own `listening on` log line
+//!   is used instead when relaying with socat)
+//! - `DISTANT-ACCEPT <socket path> <peer address> <peer port>` per accepted connection
+//!
+//! The listener exits once its stdin is closed, which happens when the exec session is dropped.
+//! Connections that were already accepted keep running until either end closes them.
+
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+
+use bollard::container::LogOutput;
+use bollard::errors::Error as BollardError;
+use futures::{Stream, StreamExt};
+
+use crate::utils::TunnelTools;
+
+/// Shell script that runs a `socat` listener, where `$1` is the socat listen address.
+///
+/// Every accepted connection runs `accept.sh`, which reports the connection and then waits for
+/// the backend to connect to its Unix socket. Background jobs read from `/dev/null`, so the job
+/// that stops the listener once stdin closes reads a copy of stdin kept as fd 3.
+const SOCAT_LISTEN_SCRIPT: &str = r#"dir=$(mktemp -d "${TMPDIR:-/tmp}/distant-tunnel.XXXXXX") || exit 1
+trap 'rm -rf "$dir"' EXIT
+cat > "$dir/accept.sh" <<'EOF'
+sock="$(dirname "$0")/$$.sock"
+echo "DISTANT-ACCEPT $sock $SOCAT_PEERADDR $SOCAT_PEERPORT" >&2
+exec socat STDIO "UNIX-LISTEN:$sock,unlink-early,unlink-close"
+EOF
+socat -d -d "$1" "SYSTEM:sh $dir/accept.sh" &
+pid=$!
+exec 3<&0
+( cat <&3 >/dev/null; kill $pid ) >/dev/null 2>&1 &
+wait $pid"#;
+
+/// Python script that listens on `argv[1]`:`argv[2]` and relays every accepted connection
+/// through a Unix socket until both directions have closed.
+const PYTHON_LISTEN_SCRIPT: &str = r#"import os, shutil, socket, sys, tempfile, threading
+host, port = sys.argv[1], int(sys.argv[2])
+srv = socket.socket(socket.AF_INET6 if ':' in host else socket.AF_INET, socket.SOCK_STREAM)
+srv.setsockopt(socket.SOL_SOCKET, socket.SO_REUSEADDR, 1)
+srv.bind((host, port))
+srv.listen(128)
+tmp = tempfile.mkdtemp(prefix='distant-tunnel.')
+lock = threading.Lock()
+def report(line):
+    with lock:
+        sys.stderr.write(line + '\n')
+        sys.stderr.flush()
+def pipe(src, dst):
+    try:
+        while True:
+            data = src.recv(65536)
+            if not data:
+                break
+            dst.sendall(data)
+    except OSError:
+        pass
+    try:
+        dst.shutdown(socket.SHUT_WR)
+    except OSError:
+        pass
+def serve(conn, peer, n):
+    path = os.path.join(tmp, '%d.sock' % n)
+    unix = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+    unix.bind(path)
+    unix.listen(1)
+    unix.settimeout(30)
+    report('DISTANT-ACCEPT %s %s %d' % (path, peer[0], peer[1]))
+    try:
+        relay, _ = unix.accept()
+    except OSError:
+        conn.close()
+        return
+    finally:
+        unix.close()
+        os.unlink(path)
+    relay.settimeout(None)
+    t = threading.Thread(target=pipe, args=(conn, relay))
+    t.start()
+    pipe(relay, conn)
+    t.join()
+    conn.close()
+    relay.close()
+def accept():
+    n = 0
+    while True:
+        try:
+            conn, peer = srv.accept()
+        except OSError:
+            return
+        n += 1
+        threading.Thread(target=serve, args=(conn, peer, n)).start()
+report('DISTANT-LISTENING %d' % srv.getsockname()[1])
+threading.Thread(target=accept, daemon=True).start()
+sys.stdin.read()
+srv.close()
+shutil.rmtree(tmp, ignore_errors=True)"#;
+
+/// Python script that relays stdin/stdout through the Unix socket at `argv[1]`.
+const PYTHON_CONNECT_SCRIPT: &str = r#"import os, socket, sys, threading
+s = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+s.connect(sys.argv[1])
+def up():
+    while True:
+        data = os.read(0, 65536)
+        if not data:
+            break
+        s.sendall(data)
+    s.shutdown(socket.SHUT_WR)
+threading.Thread(target=up, daemon=True).start()
+while True:
+    data = s.recv(65536)
+    if not data:
+        break
+    sys.stdout.buffer.write(data)
+    sys.stdout.buffer.flush()"#;
+
+/// Tool used inside the container to listen for reverse tunnel connections.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReverseTunnelTool {
+    /// `socat`, preferred when available.
+    Socat,
+
+    /// `python3`, used when socat is missing.
+    Python,
+}
+
+impl ReverseTunnelTool {
+    /// Picks the tool to use for reverse tunnels, or `None` if the container has neither.
+    pub fn detect(tools: &TunnelTools) -> Option<Self> {
+        if tools.socat {
+            Some(Self::Socat)
+        } else if tools.python {
+            Some(Self::Python)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the command that listens on `host`:`port` inside the container.
+    pub fn listen_cmd(self, host: &str, port: u16) -> Vec<String> {
+        match self {
+            Self::Socat => {
+                // socat listens on IPv4 unless told otherwise, and needs IPv6 binds bracketed
+                let addr = if host.contains(':') {
+                    format!("TCP6-LISTEN:{port},bind=[{host}],fork,reuseaddr")
+                } else {
+                    format!("TCP-LISTEN:{port},bind={host},fork,reuseaddr")
+                };
+
+                vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    SOCAT_LISTEN_SCRIPT.to_string(),
+                    "sh".to_string(),
+                    addr,
+                ]
+            }
+            Self::Python => vec![
+                "python3".to_string(),
+                "-c".to_string(),
+                PYTHON_LISTEN_SCRIPT.to_string(),
+                host.to_string(),
+                port.to_string(),
+            ],
+        }
+    }
+
+    /// Returns the command that relays the connection parked at `socket` over stdin/stdout.
+    pub fn connect_cmd(self, socket: &str) -> Vec<String> {
+        match self {
+            // The accepting socat may not have created the socket yet, so keep retrying briefly
+            Self::Socat => vec![
+                "socat".to_string(),
+                "-".to_string(),
+                format!("UNIX-CONNECT:{socket},retry=100,interval=0.05"),
+            ],
+            Self::Python => vec![
+                "python3".to_string(),
+                "-c".to_string(),
+                PYTHON_CONNECT_SCRIPT.to_string(),
+                socket.to_string(),
+            ],
+        }
+    }
+}
+
+/// Event reported by a reverse tunnel listener.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListenerEvent {
+    /// Listener is bound to the given port.
+    Listening(u16),
+
+    /// Listener accepted a connection that is waiting at `socket`.
+    Accept {
+        socket: String,
+        peer_host: String,
+        peer_port: u16,
+    },
+
+    /// Listener reported an error.
+    Error(String),
+}
+
+impl ListenerEvent {
+    /// Parses a line of listener stderr, returning `None` for lines that are not events.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+
+        if let Some(rest) = line.strip_prefix("DISTANT-LISTENING ") {
+            return rest.trim().parse().ok().map(Self::Listening);
+        }
+
+        if let Some(rest) = line.strip_prefix("DISTANT-ACCEPT ") {
+            let mut parts = rest.split_whitespace();
+            let socket = parts.next()?.to_string();
+            let peer_host = parts.next()?.to_string();
+            let peer_port = parts.next()?.parse().ok()?;
+            return Some(Self::Accept {
+                socket,
+                peer_host,
+                peer_port,
+            });
+        }
+
+        // socat logs lines like "2024/01/01 00:00:00 socat[7] N listening on AF=2 0.0.0.0:8080"
+        if let Some((_, rest)) = line.split_once("] N listening on ") {
+            let (_, port) = rest.rsplit_once(':')?;
+            return port.trim().parse().ok().map(Self::Listening);
+        }
+
+        if let Some((_, msg)) = line.split_once("] E ") {
+            return Some(Self::Error(msg.to_string()));
+        }
+
+        // Last line of a python traceback, e.g. "OSError: [Errno 98] Address already in use"
+        match line.split_once(": ") {
+            Some((kind, _)) if kind.ends_with("Error") && !kind.contains(' ') => {
+                Some(Self::Error(line.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Formats the peer of an accepted connection as an address string.
+    pub fn peer_addr(peer_host: &str, peer_port: u16) -> String {
+        match peer_host.parse::<IpAddr>() {
+            Ok(ip) => SocketAddr::new(ip, peer_port).to_string(),
+            Err(_) => format!("{peer_host}:{peer_port}"),
+        }
+    }
+}
+
+/// Reads [`ListenerEvent`]s from the output of a listener exec.
+pub struct ListenerEvents<S> {
+    output: S,
+    buf: Vec<u8>,
+    pending: VecDeque<ListenerEvent>,
+}
+
+impl<S> ListenerEvents<S>
+where
+    S: Stream<Item = Result<LogOutput, BollardError>> + Unpin,
+{
+    pub fn new(output: S) -> Self {
+        Self {
+            output,
+            buf: Vec::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Returns the next event, or `None` once the listener has exited.
+    ///
+    /// Cancel safe: events are only removed from the internal queue once returned.
+    pub async fn next(&mut self) -> Option<ListenerEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            match self.output.next().await? {
+                Ok(LogOutput::StdErr { message }) | Ok(LogOutput::StdOut { message }) => {
+                    self.buf.extend_from_slice(&message);
+                    while let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+                        let line: Vec<u8> = self.buf.drain(..=pos).collect();
+                        if let Some(event) = ListenerEvent::parse(&String::from_utf8_lossy(&line)) {
+                            self.pending.push_back(event);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => return None,
+            }
+        }
+    }
+}
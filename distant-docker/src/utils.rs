@@ -465,7 +465,8 @@ pub async fn tar_path_exists(client: &Docker, container: &str, path: &str) -> bo
 /// Available tunnel relay tools detected in a container.
 ///
 /// Docker tunneling uses `socat` or `nc` inside the container to relay TCP connections.
-/// At least one tool must be present for forward tunneling to work.
+/// At least one tool must be present for forward tunneling to work. Reverse tunneling needs
+/// `socat` or `python3` to listen inside the container.
 #[derive(Debug, Clone, Default)]
 pub struct TunnelTools {
     /// Whether socat is available (preferred — supports bidirectional relay).
@@ -473,6 +474,9 @@ pub struct TunnelTools {
 
     /// Whether netcat (nc) is available (fallback).
     pub nc: bool,
+
+    /// Whether python3 is available (fallback for reverse tunnels).
+    pub python: bool,
 }
 
 impl TunnelTools {
@@ -484,7 +488,7 @@ impl TunnelTools {
 
 /// Probe the container for available tunnel relay tools.
 ///
-/// Uses `which` to check for socat and nc.
+/// Uses `which` to check for socat, nc, and python3.
 pub async fn probe_tunnel_tools(client: &Docker, container: &str) -> TunnelTools {
     let mut tools = TunnelTools::default();
 
@@ -496,7 +500,14 @@ pub async fn probe_tunnel_tools(client: &Docker, container: &str) -> TunnelTools
         tools.nc = output.success();
     }
 
-    debug!("Tunnel tools: socat={}, nc={}", tools.socat, tools.nc);
+    if let Ok(output) = execute_output(client, container, &["which", "python3"], None).await {
+        tools.python = output.success();
+    }
+
+    debug!(
+        "Tunnel tools: socat={}, nc={}, python={}",
+        tools.socat, tools.nc, tools.python
+    );
 
     tools
 }
@@ -3,10 +3,13 @@
 use std::path::PathBuf;
 
 use distant_core::protocol::{
-    FileType, SearchQueryCondition, SearchQueryMatch, SearchQueryOptions,
+    Environment, FileType, ProcessLimits, RemotePath, SearchQueryCondition, SearchQueryMatch,
+    SearchQueryOptions,
+};
+use distant_core::{ChannelExt, Client, RemoteCommand};
+use distant_test_harness::docker::{
+    Ctx, client, client_with_reverse_tunnel_tools, client_with_tunnel_tools,
 };
-use distant_core::{ChannelExt, Client};
-use distant_test_harness::docker::{Ctx, client, client_with_tunnel_tools};
 use distant_test_harness::skip_if_no_docker;
 use rstest::*;
 use test_log::test;
@@ -328,6 +331,165 @@ async fn proc_spawn_should_execute_command(#[future] client: Option<Ctx<Client>>
     );
 }
 
+#[rstest]
+#[test(tokio::test)]
+async fn proc_stdin_close_should_send_eof_to_process(#[future] client: Option<Ctx<Client>>) {
+    let mut client = skip_if_no_docker!(client.await);
+
+    let mut proc = client
+        .spawn("wc -c".to_string(), Default::default(), None, None)
+        .await
+        .unwrap();
+
+    proc.stdin
+        .as_mut()
+        .unwrap()
+        .write_str("hello\n")
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit after closing stdin")
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "6", "Unexpected process stdout: {stdout}");
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_apply_limits(#[future] client: Option<Ctx<Client>>) {
+    let client = skip_if_no_docker!(client.await);
+
+    let proc = RemoteCommand::new()
+        .limits(ProcessLimits {
+            max_open_files: Some(64),
+            ..Default::default()
+        })
+        .spawn(client.clone_channel(), "ulimit -n")
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit")
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "64", "Unexpected process stdout: {stdout}");
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_kill_process_once_timeout_exceeded(
+    #[future] client: Option<Ctx<Client>>,
+) {
+    let client = skip_if_no_docker!(client.await);
+
+    let proc = RemoteCommand::new()
+        .limits(ProcessLimits {
+            timeout_ms: Some(500),
+            ..Default::default()
+        })
+        .spawn(client.clone_channel(), "sleep 60")
+        .await
+        .unwrap();
+
+    let status = tokio::time::timeout(std::time::Duration::from_secs(30), proc.wait())
+        .await
+        .expect("Process was not killed after exceeding its timeout")
+        .unwrap();
+    assert!(!status.success, "Timed out process should not succeed");
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_stamp_output_when_timestamps_requested(
+    #[future] client: Option<Ctx<Client>>,
+) {
+    let client = skip_if_no_docker!(client.await);
+
+    let proc = RemoteCommand::new()
+        .timestamps(true)
+        .spawn(
+            client.clone_channel(),
+            "sh -c 'echo out1; sleep 0.2; echo err1 >&2; sleep 0.2; echo out2'",
+        )
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit")
+        .unwrap();
+    assert!(output.success, "Process failed: {:?}", output);
+    assert_eq!(output.stdout, b"out1\nout2\n");
+    assert_eq!(output.stderr, b"err1\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.interleaved().expect("Output should be stamped")),
+        "out1\nerr1\nout2\n"
+    );
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_apply_env_controls_and_env_file(#[future] client: Option<Ctx<Client>>) {
+    let mut client = skip_if_no_docker!(client.await);
+    let path = test_temp_dir().join("distant-test-proc.env");
+
+    client
+        .write_file_text(
+            path.clone(),
+            String::from("# comment\nFROM_FILE=file\nOVERRIDDEN=file\n"),
+        )
+        .await
+        .unwrap();
+
+    let mut environment = Environment::new();
+    environment.insert(String::from("OVERRIDDEN"), String::from("explicit"));
+
+    let proc = RemoteCommand::new()
+        .environment(environment)
+        .clear_env(true)
+        .env_file(Some(RemotePath::from(path.clone())))
+        .spawn(
+            client.clone_channel(),
+            "echo \"$FROM_FILE $OVERRIDDEN ${HOME-unset}\"",
+        )
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit")
+        .unwrap();
+    assert!(output.success, "Process failed: {:?}", output);
+    assert_eq!(output.stdout, b"file explicit unset\n");
+
+    let _ = client.remove(path, false).await;
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_run_command_through_login_shell_if_requested(
+    #[future] client: Option<Ctx<Client>>,
+) {
+    let mut client = skip_if_no_docker!(client.await);
+    let shell = client.system_info().await.unwrap().shell;
+
+    let proc = RemoteCommand::new()
+        .login(true)
+        .spawn(client.clone_channel(), "echo \"$0\"")
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit")
+        .unwrap();
+    assert!(output.success, "Process failed: {:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), shell);
+}
+
 // ---------------------------------------------------------------------------
 // System info
 // ---------------------------------------------------------------------------
@@ -636,3 +798,75 @@ async fn tunnel_open_should_send_and_receive_data(
     drop(reader);
     tunnel.wait().await;
 }
+
+#[rstest]
+#[test(tokio::test)]
+async fn tunnel_listen_should_fail_without_listener_tools(#[future] client: Option<Ctx<Client>>) {
+    let mut client = skip_if_no_docker!(client.await);
+    let err = client
+        .tunnel_listen("127.0.0.1", 0)
+        .await
+        .expect_err("Expected error without socat or python3");
+    let err_msg = err.to_string().to_lowercase();
+    assert!(
+        err_msg.contains("socat or python3"),
+        "Expected unsupported error, got: {err_msg}",
+    );
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn tunnel_listen_should_relay_connections_from_container(
+    #[future] client_with_reverse_tunnel_tools: Option<Ctx<Client>>,
+) {
+    let mut client = skip_if_no_docker!(client_with_reverse_tunnel_tools.await);
+    let timeout = std::time::Duration::from_secs(10);
+
+    let mut listener = client.tunnel_listen("127.0.0.1", 0).await.unwrap();
+    let port = listener.port();
+    assert_ne!(port, 0, "Expected the listener to report its bound port");
+
+    // Connect to the listener from inside the container, sending a line and printing the reply
+    let proc = client
+        .spawn(
+            format!("sh -c 'echo PING | socat -t 5 - TCP:127.0.0.1:{port}'"),
+            Default::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let mut incoming = tokio::time::timeout(timeout, listener.next())
+        .await
+        .expect("Timed out waiting for incoming tunnel")
+        .expect("Listener closed before receiving incoming tunnel");
+    assert!(
+        incoming
+            .peer_addr
+            .as_deref()
+            .is_some_and(|addr| addr.starts_with("127.0.0.1:")),
+        "Unexpected peer address: {:?}",
+        incoming.peer_addr,
+    );
+
+    let mut accumulated = Vec::new();
+    while !String::from_utf8_lossy(&accumulated).contains("PING") {
+        let data = tokio::time::timeout(timeout, incoming.reader.read())
+            .await
+            .expect("Timed out waiting for tunnel data")
+            .unwrap();
+        accumulated.extend_from_slice(&data);
+    }
+
+    incoming.writer.write(b"PONG\n".to_vec()).await.unwrap();
+
+    let output = tokio::time::timeout(timeout, proc.output())
+        .await
+        .expect("Timed out waiting for connecting process")
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "PONG");
+
+    drop(incoming);
+    listener.close().await.unwrap();
+}
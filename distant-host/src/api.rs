@@ -3,9 +3,10 @@ use std::time::SystemTime;
 use std::{env, io};
 
 use distant_core::protocol::{
-    ChangeKind, ChangeKindSet, DirEntry, Environment, FileType, Metadata, PROTOCOL_VERSION,
-    Permissions, ProcessId, PtySize, RemotePath, SearchId, SearchQuery, SetPermissionsOptions,
-    StatusInfo, SystemInfo, TunnelId, Version, semver,
+    ChangeKind, ChangeKindSet, Cmd, DirEntry, Environment, FileType, ListeningPort, Metadata,
+    PROTOCOL_VERSION, Permissions, ProcSpawnOptions, ProcessId, PtySize, RemotePath, SearchId,
+    SearchQuery, SetPermissionsOptions, StatusInfo, SystemInfo, TunnelId, Version, parse_env_file,
+    semver,
 };
 use distant_core::{Api as DistantApi, Ctx};
 use ignore::{DirEntry as WalkDirEntry, WalkBuilder};
@@ -15,6 +16,7 @@ use walkdir::WalkDir;
 
 use crate::config::Config;
 
+mod ports;
 mod process;
 mod state;
 use state::*;
@@ -588,15 +590,36 @@ impl DistantApi for Api {
         environment: Environment,
         current_dir: Option<RemotePath>,
         pty: Option<PtySize>,
+        options: ProcSpawnOptions,
     ) -> io::Result<ProcessId> {
         let current_dir = current_dir.map(PathBuf::from);
         let process = &self.state.process;
         debug!(
-            "[Conn {}] Spawning {} {{environment: {:?}, current_dir: {:?}, pty: {:?}}}",
-            ctx.connection_id, cmd, environment, current_dir, pty
+            "[Conn {}] Spawning {} {{environment: {:?}, current_dir: {:?}, pty: {:?}, options: {:?}}}",
+            ctx.connection_id, cmd, environment, current_dir, pty, options
         );
+
+        let cmd = if options.login {
+            Cmd::new(cmd).into_login_shell(&user_shell())?.into()
+        } else {
+            cmd
+        };
+
+        // Variables loaded from the env file are overridden by those provided explicitly
+        let environment = match options.env_file.as_ref() {
+            Some(path) => {
+                let contents = tokio::fs::read_to_string(PathBuf::from(path.clone()))
+                    .await
+                    .map_err(|x| io::Error::new(x.kind(), format!("Failed to read {path}: {x}")))?;
+                let mut env_file = parse_env_file(&contents)?;
+                env_file.extend(environment);
+                env_file
+            }
+            None => environment,
+        };
+
         process
-            .spawn(cmd, environment, current_dir, pty, ctx.reply)
+            .spawn(cmd, environment, current_dir, pty, options, ctx.reply)
             .await
     }
 
@@ -615,6 +638,15 @@ impl DistantApi for Api {
         process.send_stdin(id, data).await
     }
 
+    async fn proc_stdin_close(&self, ctx: Ctx, id: ProcessId) -> io::Result<()> {
+        let process = &self.state.process;
+        debug!(
+            "[Conn {}] Closing stdin of process {}",
+            ctx.connection_id, id
+        );
+        process.close_stdin(id).await
+    }
+
     async fn proc_resize_pty(&self, ctx: Ctx, id: ProcessId, size: PtySize) -> io::Result<()> {
         let process = &self.state.process;
         debug!(
@@ -645,6 +677,43 @@ impl DistantApi for Api {
         self.state.tunnel.listen(host, port, ctx.reply).await
     }
 
+    async fn udp_tunnel_open(&self, ctx: Ctx, host: String, port: u16) -> io::Result<TunnelId> {
+        debug!(
+            "[Conn {}] Opening UDP tunnel to {}:{}",
+            ctx.connection_id, host, port
+        );
+        self.state.tunnel.open_udp(host, port, ctx.reply).await
+    }
+
+    async fn udp_tunnel_listen(
+        &self,
+        ctx: Ctx,
+        host: String,
+        port: u16,
+    ) -> io::Result<(TunnelId, u16)> {
+        debug!(
+            "[Conn {}] Listening for UDP tunnels on {}:{}",
+            ctx.connection_id, host, port
+        );
+        self.state.tunnel.listen_udp(host, port, ctx.reply).await
+    }
+
+    async fn unix_tunnel_open(&self, ctx: Ctx, path: String) -> io::Result<TunnelId> {
+        debug!(
+            "[Conn {}] Opening Unix socket tunnel to {}",
+            ctx.connection_id, path
+        );
+        self.state.tunnel.open_unix(path, ctx.reply).await
+    }
+
+    async fn unix_tunnel_listen(&self, ctx: Ctx, path: String) -> io::Result<TunnelId> {
+        debug!(
+            "[Conn {}] Listening for Unix socket tunnels on {}",
+            ctx.connection_id, path
+        );
+        self.state.tunnel.listen_unix(path, ctx.reply).await
+    }
+
     async fn tunnel_write(&self, _ctx: Ctx, id: TunnelId, data: Vec<u8>) -> io::Result<()> {
         self.state.tunnel.write(id, data).await
     }
@@ -659,6 +728,11 @@ impl DistantApi for Api {
         Ok(StatusInfo { tunnels })
     }
 
+    async fn listening_ports(&self, ctx: Ctx) -> io::Result<Vec<ListeningPort>> {
+        debug!("[Conn {}] Listing listening ports", ctx.connection_id);
+        ports::listening_ports().await
+    }
+
     async fn system_info(&self, ctx: Ctx) -> io::Result<SystemInfo> {
         debug!("[Conn {}] Reading system information", ctx.connection_id);
         Ok(SystemInfo {
@@ -668,11 +742,7 @@ impl DistantApi for Api {
             current_dir: RemotePath::from(env::current_dir().unwrap_or_default()),
             main_separator: std::path::MAIN_SEPARATOR,
             username: whoami::username().unwrap_or_default(),
-            shell: if cfg!(windows) {
-                env::var("ComSpec").unwrap_or_else(|_| String::from("cmd.exe"))
-            } else {
-                env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"))
-            },
+            shell: user_shell(),
         })
     }
 
@@ -703,12 +773,24 @@ impl DistantApi for Api {
             protocol_version: PROTOCOL_VERSION,
             capabilities: Version::capabilities()
                 .iter()
+                .filter(|cap| cfg!(unix) || **cap != Version::CAP_UNIX_TUNNEL)
+                .filter(|cap| cfg!(target_os = "linux") || **cap != Version::CAP_LISTENING_PORTS)
                 .map(ToString::to_string)
                 .collect(),
         })
     }
 }
 
+/// Returns the shell of the user running the server, falling back to the default shell of the
+/// operating system.
+fn user_shell() -> String {
+    if cfg!(windows) {
+        env::var("ComSpec").unwrap_or_else(|_| String::from("cmd.exe"))
+    } else {
+        env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     //! Tests for the `Api` implementation of `DistantApi`, covering version info,
@@ -718,7 +800,7 @@ mod tests {
 
     use assert_fs::prelude::*;
     use distant_core::net::server::Reply;
-    use distant_core::protocol::Response;
+    use distant_core::protocol::{ProcessLimits, Response};
     use predicates::prelude::*;
     use test_log::test;
     use tokio::sync::mpsc;
@@ -735,6 +817,7 @@ mod tests {
                 debounce_timeout: DEBOUNCE_TIMEOUT,
                 ..Default::default()
             },
+            ..Default::default()
         })
         .unwrap();
         let (reply, rx) = make_reply();
@@ -744,6 +827,7 @@ mod tests {
         let ctx = Ctx {
             connection_id,
             reply,
+            deadline: None,
         };
         (api, ctx, rx)
     }
@@ -1729,6 +1813,7 @@ mod tests {
             let ctx = Ctx {
                 connection_id: ctx_1.connection_id,
                 reply,
+                deadline: None,
             };
             (ctx, rx)
         };
@@ -2511,6 +2596,7 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* options */ Default::default(),
             )
             .await
             .unwrap_err();
@@ -2533,6 +2619,7 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -2556,6 +2643,7 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -2574,7 +2662,7 @@ mod tests {
         let mut got_done = false;
 
         let mut check_data = |data: &Response| match data {
-            Response::ProcStdout { id, data } => {
+            Response::ProcStdout { id, data, .. } => {
                 assert_eq!(
                     *id, proc_id,
                     "Got {}, but expected {} as process id",
@@ -2621,6 +2709,7 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -2639,7 +2728,7 @@ mod tests {
         let mut got_done = false;
 
         let mut check_data = |data: &Response| match data {
-            Response::ProcStderr { id, data } => {
+            Response::ProcStderr { id, data, .. } => {
                 assert_eq!(
                     *id, proc_id,
                     "Got {}, but expected {} as process id",
@@ -2686,6 +2775,7 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -2701,6 +2791,155 @@ mod tests {
         }
     }
 
+    #[test(tokio::test)]
+    async fn proc_spawn_should_kill_process_once_timeout_exceeded() {
+        let (api, ctx, mut rx) = setup().await;
+
+        let proc_id = api
+            .proc_spawn(
+                ctx,
+                /* cmd */
+                format!(
+                    "{} {} {} 10",
+                    *SCRIPT_RUNNER,
+                    *SCRIPT_RUNNER_ARG,
+                    SLEEP.to_str().unwrap()
+                ),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* options */
+                ProcSpawnOptions {
+                    limits: ProcessLimits {
+                        timeout_ms: Some(100),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // Process should be killed well before it would have finished sleeping
+        let res = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("Process was not killed after exceeding its timeout");
+        match res.unwrap() {
+            Response::ProcDone { id, success, .. } => {
+                assert_eq!(id, proc_id);
+                assert!(!success, "Timed out process should not report success");
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn proc_spawn_should_stamp_output_when_timestamps_requested() {
+        let (api, ctx, mut rx) = setup().await;
+
+        let proc_id = api
+            .proc_spawn(
+                ctx,
+                /* cmd */
+                format!(
+                    "{} {} {} some stdout",
+                    *SCRIPT_RUNNER,
+                    *SCRIPT_RUNNER_ARG,
+                    ECHO_ARGS_TO_STDOUT.to_str().unwrap()
+                ),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* options */
+                ProcSpawnOptions {
+                    timestamps: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // Each chunk of stdout should continue the sequence, starting at zero
+        let mut next_seq = 0;
+        while let Some(res) = rx.recv().await {
+            match res {
+                Response::ProcStdout { id, stamp, .. } => {
+                    assert_eq!(id, proc_id);
+                    let stamp = stamp.expect("Stdout should have been stamped");
+                    assert_eq!(stamp.seq, next_seq, "Chunk is out of sequence");
+                    next_seq += 1;
+                }
+                Response::ProcDone { .. } => break,
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
+        assert!(next_seq > 0, "Missing stdout response");
+    }
+
+    #[test(tokio::test)]
+    async fn proc_spawn_should_fail_if_env_file_missing() {
+        let (api, ctx, _rx) = setup().await;
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let err = api
+            .proc_spawn(
+                ctx,
+                /* cmd */ DOES_NOT_EXIST_BIN.to_str().unwrap().to_string(),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* options */
+                ProcSpawnOptions {
+                    env_file: Some(temp.child("missing.env").path().into()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound, "{:?}", err);
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn proc_spawn_should_run_command_through_login_shell_if_requested() {
+        let (api, ctx, mut rx) = setup().await;
+
+        let proc_id = api
+            .proc_spawn(
+                ctx,
+                /* cmd */ String::from("echo \"$0\""),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* options */
+                ProcSpawnOptions {
+                    login: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // The command should have been run by the login shell, which it reports as $0
+        let mut stdout = Vec::new();
+        while let Some(res) = rx.recv().await {
+            match res {
+                Response::ProcStdout { id, data, .. } => {
+                    assert_eq!(id, proc_id);
+                    stdout.extend(data);
+                }
+                Response::ProcStderr { .. } => {}
+                Response::ProcDone { id, success, .. } => {
+                    assert_eq!(id, proc_id);
+                    assert!(success, "Login shell process failed");
+                    break;
+                }
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
+        assert_eq!(String::from_utf8_lossy(&stdout).trim(), user_shell());
+    }
+
     #[test(tokio::test)]
     async fn proc_spawn_should_clear_process_from_state_when_killed() {
         let (api, ctx_1, mut rx) = setup().await;
@@ -2709,6 +2948,7 @@ mod tests {
             let ctx = Ctx {
                 connection_id: ctx_1.connection_id,
                 reply,
+                deadline: None,
             };
             (ctx, rx)
         };
@@ -2726,6 +2966,7 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -2763,6 +3004,14 @@ mod tests {
             .unwrap_err();
     }
 
+    #[test(tokio::test)]
+    async fn proc_stdin_close_should_fail_if_given_non_existent_process() {
+        let (api, ctx, _rx) = setup().await;
+
+        // Close stdin of a non-existent process
+        let _ = api.proc_stdin_close(ctx, 0xDEADBEEF).await.unwrap_err();
+    }
+
     #[test(tokio::test)]
     async fn proc_stdin_should_send_stdin_to_process() {
         let (api, ctx_1, mut rx) = setup().await;
@@ -2771,6 +3020,7 @@ mod tests {
             let ctx = Ctx {
                 connection_id: ctx_1.connection_id,
                 reply,
+                deadline: None,
             };
             (ctx, rx)
         };
@@ -2789,6 +3039,7 @@ mod tests {
                 Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -0,0 +1,170 @@
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use distant_core::protocol::ListeningPort;
+
+/// State code used by the kernel's socket tables for a listening TCP socket
+const TCP_LISTEN: &str = "0A";
+
+/// Socket tables maintained by the kernel for IPv4 and IPv6 TCP sockets
+const PROC_NET_TCP_TABLES: [&str; 2] = ["/proc/net/tcp", "/proc/net/tcp6"];
+
+/// Lists the TCP ports that processes on this machine are listening on by reading the kernel's
+/// socket tables, which are only available on Linux.
+pub async fn listening_ports() -> io::Result<Vec<ListeningPort>> {
+    if !cfg!(target_os = "linux") {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Listing listening ports is only supported on Linux",
+        ));
+    }
+
+    let mut ports = Vec::new();
+    for path in PROC_NET_TCP_TABLES {
+        match tokio::fs::read_to_string(path).await {
+            Ok(table) => {
+                for port in parse_proc_net_tcp(&table) {
+                    if !ports.contains(&port) {
+                        ports.push(port);
+                    }
+                }
+            }
+
+            // IPv6 can be disabled, in which case its table does not exist
+            Err(x) if x.kind() == io::ErrorKind::NotFound => continue,
+            Err(x) => return Err(x),
+        }
+    }
+
+    Ok(ports)
+}
+
+/// Parses the listening sockets out of a `/proc/net/tcp` or `/proc/net/tcp6` table, skipping the
+/// header and any lines that cannot be understood.
+fn parse_proc_net_tcp(table: &str) -> Vec<ListeningPort> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            // sl local_address rem_address st ...
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [_, local, _, state, ..] if *state == TCP_LISTEN => parse_hex_address(local),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Parses an `ADDRESS:PORT` pair where both are hex encoded, the address being written as one
+/// (IPv4) or four (IPv6) 32-bit words in the machine's native byte order.
+fn parse_hex_address(local: &str) -> Option<ListeningPort> {
+    let (address, port) = local.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let words = (0..address.len())
+        .step_by(8)
+        .map(|i| {
+            let word = address.get(i..i + 8)?;
+            u32::from_str_radix(word, 16).ok().map(u32::to_ne_bytes)
+        })
+        .collect::<Option<Vec<[u8; 4]>>>()?;
+
+    let address = match words.as_slice() {
+        [a] => Ipv4Addr::from(*a).to_string(),
+        [a, b, c, d] => {
+            let mut octets = [0u8; 16];
+            for (chunk, word) in octets.chunks_exact_mut(4).zip([a, b, c, d]) {
+                chunk.copy_from_slice(word);
+            }
+            Ipv6Addr::from(octets).to_string()
+        }
+        _ => return None,
+    };
+
+    Some(ListeningPort { address, port })
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    fn port(address: &str, port: u16) -> ListeningPort {
+        ListeningPort {
+            address: address.to_string(),
+            port,
+        }
+    }
+
+    /// Encodes an address the way the kernel writes it on this machine
+    fn hex_words(octets: &[u8]) -> String {
+        octets
+            .chunks_exact(4)
+            .map(|chunk| format!("{:08X}", u32::from_ne_bytes(chunk.try_into().unwrap())))
+            .collect()
+    }
+
+    #[test]
+    fn parse_proc_net_tcp_should_return_listening_ipv4_sockets() {
+        let table = format!(
+            "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: {}:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1 1 0 100 0 0 10 0
+   1: {}:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 2 1 0 100 0 0 10 0
+   2: {}:0016 {}:D3A2 01 00000000:00000000 02:00000000 00000000     0        0 3 4 0 20 4 30 10 -1
+",
+            hex_words(&[127, 0, 0, 1]),
+            hex_words(&[0, 0, 0, 0]),
+            hex_words(&[10, 0, 0, 5]),
+            hex_words(&[10, 0, 0, 9]),
+        );
+
+        assert_eq!(
+            parse_proc_net_tcp(&table),
+            vec![port("127.0.0.1", 3000), port("0.0.0.0", 22)]
+        );
+    }
+
+    #[test]
+    fn parse_proc_net_tcp_should_return_listening_ipv6_sockets() {
+        let table = format!(
+            "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: {}:1435 {}:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1 1 0 100 0 0 10 0
+   1: {}:0016 {}:0000 0A 00000000:00000000 00:00000000 00000000     0        0 2 1 0 100 0 0 10 0
+",
+            hex_words(&Ipv6Addr::LOCALHOST.octets()),
+            hex_words(&Ipv6Addr::UNSPECIFIED.octets()),
+            hex_words(&Ipv6Addr::UNSPECIFIED.octets()),
+            hex_words(&Ipv6Addr::UNSPECIFIED.octets()),
+        );
+
+        assert_eq!(
+            parse_proc_net_tcp(&table),
+            vec![port("::1", 5173), port("::", 22)]
+        );
+    }
+
+    #[test]
+    fn parse_proc_net_tcp_should_skip_unparseable_lines() {
+        let table = "header
+   0: garbage 00000000:0000 0A
+   1: 0100007F:ZZZZ 00000000:0000 0A
+   2: 0100007:0050 00000000:0000 0A
+";
+        assert_eq!(parse_proc_net_tcp(table), Vec::new());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test(tokio::test)]
+    async fn listening_ports_should_include_a_bound_listener() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bound = listener.local_addr().unwrap().port();
+
+        let ports = listening_ports().await.unwrap();
+        assert!(
+            ports.contains(&port("127.0.0.1", bound)),
+            "Expected port {bound} in {ports:?}"
+        );
+    }
+}
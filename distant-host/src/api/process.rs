@@ -10,6 +10,12 @@ mod pty;
 #[cfg(feature = "pty")]
 pub use pty::*;
 
+mod env;
+pub use env::*;
+
+mod limits;
+pub use limits::*;
+
 mod simple;
 pub use simple::*;
 
@@ -0,0 +1,116 @@
+use distant_core::protocol::{Environment, ProcSpawnOptions};
+use tokio::io;
+use tokio::process::Command;
+
+/// Clears or unsets the inherited environment of a command prior to it being spawned, based on
+/// the provided options. Explicit variables set on the command afterwards are unaffected.
+pub fn apply_env_to_command(command: &mut Command, options: &ProcSpawnOptions) {
+    if options.clear_env {
+        command.env_clear();
+    }
+
+    for key in options.unset_env.iter() {
+        command.env_remove(key);
+    }
+}
+
+/// Wraps a program and its arguments such that they are executed by `env`, which clears or
+/// unsets the inherited environment before setting the explicit `environment`. Returns the new
+/// program, arguments, and the environment that remains to be set on the process.
+///
+/// This is used for processes whose spawning we do not control directly (e.g. within a pty).
+#[cfg(unix)]
+pub fn wrap_with_env(
+    program: String,
+    args: Vec<String>,
+    environment: Environment,
+    options: &ProcSpawnOptions,
+) -> io::Result<(String, Vec<String>, Environment)> {
+    if !options.overrides_inherited_env() {
+        return Ok((program, args, environment));
+    }
+
+    let mut wrapped_args = Vec::new();
+    if options.clear_env {
+        wrapped_args.push(String::from("-i"));
+    }
+    for key in options.unset_env.iter() {
+        wrapped_args.push(String::from("-u"));
+        wrapped_args.push(key.to_string());
+    }
+
+    // NOTE: Variables must be passed to env itself, otherwise they would be cleared or unset
+    //       along with the inherited environment
+    for (key, value) in environment {
+        wrapped_args.push(format!("{key}={value}"));
+    }
+
+    wrapped_args.push(program);
+    wrapped_args.extend(args);
+    Ok((String::from("env"), wrapped_args, Environment::new()))
+}
+
+#[cfg(windows)]
+pub fn wrap_with_env(
+    program: String,
+    args: Vec<String>,
+    environment: Environment,
+    options: &ProcSpawnOptions,
+) -> io::Result<(String, Vec<String>, Environment)> {
+    if options.overrides_inherited_env() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Clearing or unsetting the environment of a pty process is not supported on Windows",
+        ));
+    }
+
+    Ok((program, args, environment))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_with_env_should_return_original_command_if_inheriting_env() {
+        let environment: Environment = [(String::from("KEY"), String::from("value"))]
+            .into_iter()
+            .collect();
+        let options = ProcSpawnOptions {
+            env_file: Some(".env".into()),
+            ..Default::default()
+        };
+
+        let (program, args, env) = wrap_with_env(
+            "echo".into(),
+            vec!["hi".into()],
+            environment.clone(),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(program, "echo");
+        assert_eq!(args, vec!["hi"]);
+        assert_eq!(env, environment);
+    }
+
+    #[test]
+    fn wrap_with_env_should_clear_and_unset_before_setting_environment() {
+        let environment: Environment = [(String::from("KEY"), String::from("a value"))]
+            .into_iter()
+            .collect();
+        let options = ProcSpawnOptions {
+            clear_env: true,
+            unset_env: vec![String::from("SECRET")],
+            ..Default::default()
+        };
+
+        let (program, args, env) =
+            wrap_with_env("echo".into(), vec!["hi".into()], environment, &options).unwrap();
+        assert_eq!(program, "env");
+        assert_eq!(
+            args,
+            vec!["-i", "-u", "SECRET", "KEY=a value", "echo", "hi"]
+        );
+        assert!(env.is_empty());
+    }
+}
@@ -0,0 +1,507 @@
+use distant_core::protocol::ProcessLimits;
+use tokio::io;
+use tokio::process::Command;
+
+#[cfg(target_os = "linux")]
+mod cgroup;
+
+/// Resources set aside to enforce the limits of a spawned process, such as the cgroup that it
+/// runs in, which are released once dropped after the process has exited.
+#[derive(Default)]
+pub struct LimitsGuard {
+    #[cfg(target_os = "linux")]
+    cgroup: Option<cgroup::Cgroup>,
+}
+
+impl LimitsGuard {
+    /// Kills the processes that share the limits of the spawned process, such as its children,
+    /// if they are tracked together.
+    pub fn kill(&self) {
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = self.cgroup.as_ref() {
+            cgroup.kill();
+        }
+    }
+}
+
+/// Applies the resource limits, niceness, and user to a command prior to it being spawned,
+/// returning the resources that need to be held onto until the process exits.
+///
+/// On Linux, the memory limit is applied by running the process in a cgroup of its own, which
+/// covers the children of the process as well, if the server is able to create one. Otherwise,
+/// and on other Unix platforms, resource limits are applied within the child via `setrlimit`.
+///
+/// Niceness is applied via `setpriority` after forking, where it is incremented like `nice -n` so
+/// that it matches processes started through [`wrap_with_shell`]. The user is switched
+/// afterwards, along with its primary and supplementary groups, the same way that `login` would.
+#[cfg(unix)]
+pub fn apply_to_command(command: &mut Command, limits: &ProcessLimits) -> io::Result<LimitsGuard> {
+    let user = limits.user.as_deref().map(unix::lookup_user).transpose()?;
+    let nice = limits.nice;
+
+    #[cfg(target_os = "linux")]
+    let (guard, procs) = {
+        let cgroup = limits.max_memory.and_then(cgroup::Cgroup::create);
+        let procs = cgroup.as_ref().map(cgroup::Cgroup::procs);
+        (LimitsGuard { cgroup }, procs)
+    };
+    #[cfg(not(target_os = "linux"))]
+    let (guard, procs) = (LimitsGuard::default(), None::<()>);
+
+    // NOTE: The cgroup limits the memory that the process actually uses, so it takes the place
+    //       of the limit on its address space
+    let rlimits: Vec<_> = unix::rlimits(limits)
+        .into_iter()
+        .filter(|&(resource, _)| procs.is_none() || resource != unix::Resource::Memory)
+        .collect();
+
+    if !rlimits.is_empty() || nice.is_some() || user.is_some() || procs.is_some() {
+        // SAFETY: the closure runs in the child between fork and exec, so it only invokes
+        //         async-signal-safe system calls and does not allocate.
+        unsafe {
+            command.pre_exec(move || {
+                #[cfg(target_os = "linux")]
+                if let Some(procs) = procs.as_deref() {
+                    cgroup::enter(procs)?;
+                }
+
+                for &(resource, value) in rlimits.iter() {
+                    unix::set_rlimit(resource, value)?;
+                }
+
+                if let Some(nice) = nice {
+                    unix::adjust_nice(nice)?;
+                }
+
+                // NOTE: The user is switched last, as joining the cgroup and applying the limits
+                //       may need privileges that are given up with it
+                if let Some(user) = user.as_ref() {
+                    unix::switch_user(user)?;
+                }
+
+                Ok(())
+            });
+        }
+    }
+
+    Ok(guard)
+}
+
+#[cfg(windows)]
+pub fn apply_to_command(_command: &mut Command, limits: &ProcessLimits) -> io::Result<LimitsGuard> {
+    windows::ensure_supported(limits)?;
+    Ok(LimitsGuard::default())
+}
+
+/// Wraps a program and its arguments such that they are executed by `sh` after it has applied
+/// the resource limits and niceness, returning the new program and arguments.
+///
+/// This is used for processes whose spawning we do not control directly (e.g. within a pty),
+/// and does not support running the process as another user.
+#[cfg(unix)]
+pub fn wrap_with_shell(
+    program: String,
+    args: Vec<String>,
+    limits: &ProcessLimits,
+) -> io::Result<(String, Vec<String>)> {
+    if limits.user.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Running a pty process as another user is not supported",
+        ));
+    }
+
+    let Some(prefix) = limits.to_shell_prefix() else {
+        return Ok((program, args));
+    };
+
+    let mut wrapped_args = vec![
+        String::from("-c"),
+        format!("{prefix} \"$0\" \"$@\""),
+        program,
+    ];
+    wrapped_args.extend(args);
+    Ok((String::from("sh"), wrapped_args))
+}
+
+#[cfg(windows)]
+pub fn wrap_with_shell(
+    program: String,
+    args: Vec<String>,
+    limits: &ProcessLimits,
+) -> io::Result<(String, Vec<String>)> {
+    windows::ensure_supported(limits)?;
+    Ok((program, args))
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::ffi::CString;
+
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub enum Resource {
+        Memory,
+        Cpu,
+        OpenFiles,
+    }
+
+    /// Collects the resource limits that map to `setrlimit`.
+    pub fn rlimits(limits: &ProcessLimits) -> Vec<(Resource, u64)> {
+        let mut rlimits = Vec::new();
+        if let Some(bytes) = limits.max_memory {
+            rlimits.push((Resource::Memory, bytes));
+        }
+        if let Some(secs) = limits.max_cpu_secs {
+            rlimits.push((Resource::Cpu, secs));
+        }
+        if let Some(n) = limits.max_open_files {
+            rlimits.push((Resource::OpenFiles, n));
+        }
+        rlimits
+    }
+
+    /// Sets both the soft and hard limit of the resource for the current process so that it
+    /// cannot be raised again later.
+    // NOTE: rlim_t is not a u64 on every platform
+    #[allow(clippy::unnecessary_cast)]
+    pub fn set_rlimit(resource: Resource, value: u64) -> io::Result<()> {
+        let limit = libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value as libc::rlim_t,
+        };
+
+        // SAFETY: `limit` is a valid, initialized rlimit that outlives the call.
+        let ret = unsafe {
+            match resource {
+                Resource::Memory => libc::setrlimit(libc::RLIMIT_AS, &limit),
+                Resource::Cpu => libc::setrlimit(libc::RLIMIT_CPU, &limit),
+                Resource::OpenFiles => libc::setrlimit(libc::RLIMIT_NOFILE, &limit),
+            }
+        };
+
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Adjusts the niceness of the current process by `increment`, mirroring `nice -n`.
+    pub fn adjust_nice(increment: i32) -> io::Result<()> {
+        // NOTE: -1 is a valid priority, so getpriority can only be checked for failure by errno
+        clear_errno();
+
+        // SAFETY: querying the priority of the current process has no memory safety
+        //         requirements.
+        let current = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+        if current == -1 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(0) {
+                return Err(err);
+            }
+        }
+
+        // SAFETY: setting the priority of the current process has no memory safety requirements.
+        let ret =
+            unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, current.saturating_add(increment)) };
+
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Sets errno of the current thread to zero.
+    fn clear_errno() {
+        // SAFETY: errno is thread-local, and each function returns a valid pointer to it.
+        unsafe {
+            #[cfg(any(target_os = "linux", target_os = "emscripten", target_os = "redox"))]
+            {
+                *libc::__errno_location() = 0;
+            }
+
+            #[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+            {
+                *libc::__errno() = 0;
+            }
+
+            #[cfg(any(
+                target_os = "macos",
+                target_os = "ios",
+                target_os = "freebsd",
+                target_os = "dragonfly"
+            ))]
+            {
+                *libc::__error() = 0;
+            }
+        }
+    }
+
+    /// User to run a process as, resolved by [`lookup_user`].
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct User {
+        pub uid: libc::uid_t,
+
+        /// Primary group of the user, or `None` for a numeric id without a passwd entry
+        pub gid: Option<libc::gid_t>,
+
+        /// Groups the user is a member of, including the primary group
+        pub groups: Vec<libc::gid_t>,
+    }
+
+    /// Resolves a user name or numeric id into a uid and, if the user is known, its primary and
+    /// supplementary groups.
+    pub fn lookup_user(user: &str) -> io::Result<User> {
+        let c_user = CString::new(user)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "User contains a nul byte"))?;
+
+        // SAFETY: passwd is a plain C struct for which all-zeroes is a valid value.
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut buf = vec![0 as libc::c_char; 16 * 1024];
+        let mut result: *mut libc::passwd = std::ptr::null_mut();
+
+        // SAFETY: all pointers reference live buffers of the reported sizes, and the results
+        //         are only read when `result` points at `pwd`.
+        let ret = unsafe {
+            match user.parse::<libc::uid_t>() {
+                Ok(uid) => {
+                    libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result)
+                }
+                Err(_) => libc::getpwnam_r(
+                    c_user.as_ptr(),
+                    &mut pwd,
+                    buf.as_mut_ptr(),
+                    buf.len(),
+                    &mut result,
+                ),
+            }
+        };
+
+        // NOTE: Some platforms report a missing entry as an error rather than a null result
+        if ret != 0 && ret != libc::ENOENT && ret != libc::ESRCH {
+            return Err(io::Error::from_raw_os_error(ret));
+        }
+
+        if result.is_null() {
+            // Numeric ids do not need a passwd entry, but then we have no groups to switch to
+            return match user.parse::<libc::uid_t>() {
+                Ok(uid) => Ok(User {
+                    uid,
+                    gid: None,
+                    groups: Vec::new(),
+                }),
+                Err(_) => Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("User {user} not found"),
+                )),
+            };
+        }
+
+        // SAFETY: `pw_name` points into `buf`, which is still alive, and is nul-terminated.
+        let name = unsafe { std::ffi::CStr::from_ptr(pwd.pw_name) };
+        let groups = group_list(name, pwd.pw_gid)?;
+
+        Ok(User {
+            uid: pwd.pw_uid,
+            gid: Some(pwd.pw_gid),
+            groups,
+        })
+    }
+
+    /// Returns the groups that `name` is a member of, including `gid`, which is what
+    /// `initgroups` would give the process.
+    // NOTE: Apple platforms take the groups as c_int rather than gid_t
+    #[allow(clippy::unnecessary_cast)]
+    fn group_list(name: &std::ffi::CStr, gid: libc::gid_t) -> io::Result<Vec<libc::gid_t>> {
+        const MAX_GROUPS: usize = 64 * 1024;
+
+        let mut groups = vec![0; 64];
+        loop {
+            let mut n = groups.len() as libc::c_int;
+
+            // SAFETY: `groups` has room for `n` entries, and `name` is a valid C string.
+            let ret =
+                unsafe { libc::getgrouplist(name.as_ptr(), gid as _, groups.as_mut_ptr(), &mut n) };
+
+            if ret >= 0 {
+                groups.truncate(n.max(0) as usize);
+                return Ok(groups.into_iter().map(|g| g as libc::gid_t).collect());
+            }
+
+            // Some platforms report how many groups there are, while others leave it to us
+            let len = (n.max(0) as usize).max(groups.len() * 2);
+            if len > MAX_GROUPS {
+                return Err(io::Error::other(format!(
+                    "User {} is in too many groups",
+                    name.to_string_lossy()
+                )));
+            }
+            groups.resize(len, 0);
+        }
+    }
+
+    /// Switches the current process to `user`, setting its groups before giving up the
+    /// privileges needed to do so.
+    pub fn switch_user(user: &User) -> io::Result<()> {
+        // SAFETY: `groups` is a live slice of the reported length.
+        let ret = unsafe { libc::setgroups(user.groups.len() as _, user.groups.as_ptr()) };
+
+        // NOTE: Like std, an unprivileged process keeps its groups, as switching to another
+        //       user would fail anyway unless it is already that user
+        if ret != 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EPERM) {
+                return Err(err);
+            }
+        }
+
+        if let Some(gid) = user.gid {
+            // SAFETY: setting the group of the current process has no memory safety
+            //         requirements.
+            if unsafe { libc::setgid(gid) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        // SAFETY: setting the user of the current process has no memory safety requirements.
+        if unsafe { libc::setuid(user.uid) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::*;
+
+    /// Fails if any limit other than the timeout was requested, as Windows has no equivalent of
+    /// `setrlimit`, `nice`, or switching users at spawn.
+    pub fn ensure_supported(limits: &ProcessLimits) -> io::Result<()> {
+        if limits.has_rlimits() || limits.nice.is_some() || limits.user.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Only the timeout limit is supported for processes on Windows",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn wrap_with_shell_should_return_original_command_if_no_limits() {
+        let (program, args) =
+            wrap_with_shell("echo".into(), vec!["hi".into()], &ProcessLimits::default()).unwrap();
+        assert_eq!(program, "echo");
+        assert_eq!(args, vec!["hi"]);
+    }
+
+    #[test]
+    fn wrap_with_shell_should_apply_limits_before_exec() {
+        let limits = ProcessLimits {
+            max_memory: Some(1025),
+            max_cpu_secs: Some(5),
+            max_open_files: Some(64),
+            nice: Some(10),
+            ..Default::default()
+        };
+        let (program, args) = wrap_with_shell("echo".into(), vec!["hi".into()], &limits).unwrap();
+        assert_eq!(program, "sh");
+        assert_eq!(
+            args,
+            vec![
+                "-c",
+                "ulimit -v 2 && ulimit -t 5 && ulimit -n 64 && exec nice -n 10 \"$0\" \"$@\"",
+                "echo",
+                "hi",
+            ]
+        );
+    }
+
+    #[test]
+    fn wrap_with_shell_should_fail_if_user_provided() {
+        let limits = ProcessLimits {
+            user: Some(String::from("nobody")),
+            ..Default::default()
+        };
+        let err = wrap_with_shell("echo".into(), vec![], &limits).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn adjust_nice_should_keep_priority_when_incrementing_by_zero() {
+        // SAFETY: querying the priority of the current process has no memory safety
+        //         requirements.
+        let before = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+        unix::adjust_nice(0).unwrap();
+        let after = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn lookup_user_should_resolve_root() {
+        for name in ["root", "0"] {
+            let user = unix::lookup_user(name).unwrap();
+            assert_eq!((user.uid, user.gid), (0, Some(0)));
+            assert!(user.groups.contains(&0), "Missing primary group: {user:?}");
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn apply_to_command_should_switch_to_groups_of_user() {
+        // SAFETY: querying the user of the current process has no memory safety requirements.
+        let uid = unsafe { libc::getuid() };
+        let user = unix::lookup_user(&uid.to_string()).unwrap();
+        let Some(gid) = user.gid else {
+            return;
+        };
+
+        let mut command = Command::new("id");
+        command.arg("-G");
+        apply_to_command(
+            &mut command,
+            &ProcessLimits {
+                user: Some(uid.to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let output = command.output().await.unwrap();
+        assert!(output.status.success(), "{output:?}");
+        let groups: Vec<libc::gid_t> = String::from_utf8(output.stdout)
+            .unwrap()
+            .split_whitespace()
+            .map(|g| g.parse().unwrap())
+            .collect();
+        assert!(groups.contains(&gid), "Missing primary group: {groups:?}");
+
+        // Only a privileged process can change its supplementary groups
+        if uid == 0 {
+            let mut expected = user.groups.clone();
+            let mut actual = groups.clone();
+            expected.sort_unstable();
+            expected.dedup();
+            actual.sort_unstable();
+            actual.dedup();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn lookup_user_should_fail_for_unknown_user() {
+        let err = unix::lookup_user("distant-no-such-user").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,223 @@
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::*;
+
+/// Controller that needs to be enabled for the cgroups of processes
+const MEMORY_CONTROLLER: &str = "memory";
+
+/// Represents a cgroup (v2) created for a single process beneath the cgroup of the server, which
+/// applies its memory limit to the process and all of its children together.
+///
+/// The cgroup is removed once dropped, provided that every process within it has exited.
+pub struct Cgroup {
+    path: PathBuf,
+    procs: Arc<File>,
+}
+
+impl Cgroup {
+    /// Creates a cgroup limited to `max_memory` bytes, returning `None` if the server cannot
+    /// create one, e.g. because cgroup v2 is not mounted, the memory controller is unavailable,
+    /// or the cgroup of the server is not delegated to it.
+    pub fn create(max_memory: u64) -> Option<Self> {
+        let result = server_cgroup().and_then(|parent| Self::create_in(&parent, max_memory));
+        match result {
+            Ok(cgroup) => {
+                debug!("Created cgroup {:?}", cgroup.path);
+                Some(cgroup)
+            }
+            Err(x) => {
+                debug!("Unable to create cgroup, falling back to rlimits: {x}");
+                None
+            }
+        }
+    }
+
+    fn create_in(parent: &Path, max_memory: u64) -> io::Result<Self> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        enable_controller(parent, MEMORY_CONTROLLER)?;
+
+        let path = parent.join(format!(
+            "distant-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir(&path)?;
+
+        // NOTE: Opening cgroup.procs first means that the directory is removed on drop if any of
+        //       the limits fail to apply
+        let procs = OpenOptions::new()
+            .write(true)
+            .open(path.join("cgroup.procs"))
+            .inspect_err(|_| {
+                let _ = fs::remove_dir(&path);
+            })?;
+        let cgroup = Self {
+            path,
+            procs: Arc::new(procs),
+        };
+
+        fs::write(cgroup.path.join("memory.max"), max_memory.to_string())?;
+
+        // Swap does not count towards memory.max, so keep the process from spilling into it
+        match fs::write(cgroup.path.join("memory.swap.max"), "0") {
+            Err(x) if x.kind() != io::ErrorKind::NotFound => return Err(x),
+            _ => {}
+        }
+
+        Ok(cgroup)
+    }
+
+    /// Returns a handle to `cgroup.procs`, which is passed to [`enter`] by the process joining
+    /// the cgroup.
+    pub fn procs(&self) -> Arc<File> {
+        Arc::clone(&self.procs)
+    }
+
+    /// Kills every process within the cgroup, which includes children that outlived the process
+    /// that the cgroup was created for.
+    pub fn kill(&self) {
+        if let Err(x) = fs::write(self.path.join("cgroup.kill"), "1") {
+            debug!("Unable to kill cgroup {:?}: {x}", self.path);
+        }
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        if let Err(x) = fs::remove_dir(&self.path) {
+            debug!("Unable to remove cgroup {:?}: {x}", self.path);
+        }
+    }
+}
+
+/// Moves the calling process into the cgroup whose `cgroup.procs` is `procs`.
+///
+/// Only invokes `write`, so it is safe to call between fork and exec.
+pub fn enter(procs: &File) -> io::Result<()> {
+    // NOTE: Writing 0 to cgroup.procs moves the process doing the writing
+    // SAFETY: the buffer is a live static slice of the reported length.
+    let ret = unsafe { libc::write(procs.as_raw_fd(), b"0".as_ptr().cast(), 1) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Makes `controller` available to the cgroups beneath `parent`, failing if the controller is
+/// not available to `parent` itself or cannot be enabled.
+///
+/// Enabling a controller fails while `parent` contains processes (other than being the root),
+/// which is the case unless the server runs in a cgroup that was delegated to it.
+fn enable_controller(parent: &Path, controller: &str) -> io::Result<()> {
+    let has = |name: &str| -> io::Result<bool> {
+        let contents = fs::read_to_string(parent.join(name))?;
+        Ok(contents.split_whitespace().any(|x| x == controller))
+    };
+
+    if !has("cgroup.controllers")? {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("The {controller} controller is not available"),
+        ));
+    }
+
+    if !has("cgroup.subtree_control")? {
+        fs::write(
+            parent.join("cgroup.subtree_control"),
+            format!("+{controller}"),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns the path to the cgroup (v2) of the server.
+fn server_cgroup() -> io::Result<PathBuf> {
+    let mount = cgroup2_mount(&fs::read_to_string("/proc/self/mountinfo")?)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cgroup2 is not mounted"))?;
+    let contents = fs::read_to_string("/proc/self/cgroup")?;
+    let cgroup = cgroup2_path(&contents)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Not within a cgroup2 hierarchy"))?;
+
+    Ok(mount.join(cgroup.trim_start_matches('/')))
+}
+
+/// Finds the mount point of cgroup2 within the contents of `/proc/self/mountinfo`.
+fn cgroup2_mount(mountinfo: &str) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        // NOTE: The filesystem type follows the separator, as the optional fields before it
+        //       vary in number
+        let (fields, rest) = line.split_once(" - ")?;
+        if rest.split_whitespace().next()? != "cgroup2" {
+            return None;
+        }
+
+        fields.split_whitespace().nth(4).map(PathBuf::from)
+    })
+}
+
+/// Finds the path of the cgroup2 hierarchy (`0::<path>`) within the contents of
+/// `/proc/self/cgroup`.
+fn cgroup2_path(contents: &str) -> Option<&str> {
+    contents.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cgroup2_mount_should_find_mount_point_of_cgroup2() {
+        let mountinfo = "\
+            25 30 0:23 / /sys rw,nosuid shared:7 - sysfs sysfs rw\n\
+            35 25 0:30 / /sys/fs/cgroup rw,nosuid shared:9 - cgroup2 cgroup2 rw,nsdelegate\n";
+        assert_eq!(
+            cgroup2_mount(mountinfo),
+            Some(PathBuf::from("/sys/fs/cgroup"))
+        );
+    }
+
+    #[test]
+    fn cgroup2_mount_should_be_none_without_cgroup2() {
+        let mountinfo = "\
+            36 35 0:31 / /sys/fs/cgroup/memory rw shared:10 - cgroup cgroup rw,memory\n";
+        assert_eq!(cgroup2_mount(mountinfo), None);
+    }
+
+    #[test]
+    fn cgroup2_path_should_find_unified_hierarchy() {
+        let contents = "4:memory:/user.slice\n0::/user.slice/session-1.scope\n";
+        assert_eq!(cgroup2_path(contents), Some("/user.slice/session-1.scope"));
+        assert_eq!(cgroup2_path("4:memory:/user.slice\n"), None);
+    }
+
+    #[test]
+    fn enable_controller_should_enable_available_controller() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        fs::write(dir.path().join("cgroup.controllers"), "cpu memory pids\n").unwrap();
+        fs::write(dir.path().join("cgroup.subtree_control"), "cpu\n").unwrap();
+
+        enable_controller(dir.path(), MEMORY_CONTROLLER).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("cgroup.subtree_control")).unwrap(),
+            "+memory"
+        );
+    }
+
+    #[test]
+    fn enable_controller_should_fail_if_controller_unavailable() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        fs::write(dir.path().join("cgroup.controllers"), "cpu pids\n").unwrap();
+        fs::write(dir.path().join("cgroup.subtree_control"), "").unwrap();
+
+        let err = enable_controller(dir.path(), MEMORY_CONTROLLER).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+}
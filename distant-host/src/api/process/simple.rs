@@ -2,7 +2,7 @@ use std::ffi::OsStr;
 use std::path::PathBuf;
 use std::process::Stdio;
 
-use distant_core::protocol::Environment;
+use distant_core::protocol::{Environment, ProcSpawnOptions};
 use log::*;
 use tokio::io;
 use tokio::process::Command;
@@ -11,7 +11,7 @@ use tokio::task::JoinHandle;
 
 use super::{
     ExitStatus, FutureReturn, InputChannel, NoProcessPty, OutputChannel, Process, ProcessId,
-    ProcessKiller, WaitRx, wait,
+    ProcessKiller, WaitRx, env, limits, wait,
 };
 
 mod tasks;
@@ -30,12 +30,14 @@ pub struct SimpleProcess {
 }
 
 impl SimpleProcess {
-    /// Spawns a new simple process
+    /// Spawns a new simple process, applying the resource limits and environment controls of
+    /// the given `options` to it
     pub fn spawn<S, I, S2>(
         program: S,
         args: I,
         environment: Environment,
         current_dir: Option<PathBuf>,
+        options: &ProcSpawnOptions,
     ) -> io::Result<Self>
     where
         S: AsRef<OsStr>,
@@ -43,21 +45,25 @@ impl SimpleProcess {
         S2: AsRef<OsStr>,
     {
         let id = rand::random();
-        let mut child = {
+        let (mut child, limits_guard) = {
             let mut command = Command::new(program);
 
             if let Some(path) = current_dir {
                 command.current_dir(path);
             }
 
-            command
+            let limits_guard = limits::apply_to_command(&mut command, &options.limits)?;
+            env::apply_env_to_command(&mut command, options);
+
+            let child = command
                 .envs(environment)
                 .args(args)
                 .stdin(Stdio::piped())
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
                 .kill_on_drop(true)
-                .spawn()?
+                .spawn()?;
+            (child, limits_guard)
         };
 
         let stdout = child.stdout.take().unwrap();
@@ -81,6 +87,9 @@ impl SimpleProcess {
                         Err(x) => ExitStatus::from(x),
                     };
 
+                    // Take down any children sharing the limits of the process along with it
+                    limits_guard.kill();
+
                     trace!(
                         "Simple process {id} has exited: success = {}, code = {}",
                         status.success,
@@ -109,6 +118,9 @@ impl SimpleProcess {
                     }
                 }
             }
+
+            // NOTE: Released only now that the process has exited
+            drop(limits_guard);
         });
 
         Ok(Self {
@@ -236,12 +248,23 @@ mod tests {
 
     use super::*;
 
-    use distant_core::protocol::Environment;
+    use distant_core::protocol::{Environment, ProcSpawnOptions, ProcessLimits};
 
     fn empty_env() -> Environment {
         Environment::new()
     }
 
+    fn no_options() -> ProcSpawnOptions {
+        ProcSpawnOptions::default()
+    }
+
+    fn options_with_limits(limits: ProcessLimits) -> ProcSpawnOptions {
+        ProcSpawnOptions {
+            limits,
+            ..Default::default()
+        }
+    }
+
     /// Returns (program, args) for a command that echoes text to stdout.
     fn echo_cmd(msg: &str) -> (&'static str, Vec<String>) {
         if cfg!(windows) {
@@ -293,7 +316,7 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn with_valid_program_succeeds() {
             let (prog, args) = echo_cmd("hello");
-            let proc = SimpleProcess::spawn(prog, args, empty_env(), None);
+            let proc = SimpleProcess::spawn(prog, args, empty_env(), None, &no_options());
             assert!(proc.is_ok());
         }
 
@@ -304,6 +327,7 @@ mod tests {
                 Vec::<String>::new(),
                 empty_env(),
                 None,
+                &no_options(),
             );
             assert!(result.is_err());
         }
@@ -315,7 +339,7 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn id_returns_nonzero_value() {
             let (prog, args) = echo_cmd("test");
-            let proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let proc = SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             let id: ProcessId = proc.id();
             // Assert the id is actually nonzero, matching the test name.
             // With random u32 generation, the probability of 0 is negligible.
@@ -325,28 +349,29 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn stdin_is_some_initially() {
             let (prog, args) = echo_cmd("test");
-            let proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let proc = SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             assert!(proc.stdin().is_some());
         }
 
         #[test_log::test(tokio::test)]
         async fn stdout_is_some_initially() {
             let (prog, args) = echo_cmd("test");
-            let proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let proc = SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             assert!(proc.stdout().is_some());
         }
 
         #[test_log::test(tokio::test)]
         async fn stderr_is_some_initially() {
             let (prog, args) = echo_cmd("test");
-            let proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let proc = SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             assert!(proc.stderr().is_some());
         }
 
         #[test_log::test(tokio::test)]
         async fn take_stdin_removes_it() {
             let (prog, args) = echo_cmd("test");
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             let stdin = proc.take_stdin();
             assert!(stdin.is_some());
             assert!(proc.stdin().is_none());
@@ -356,7 +381,8 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn take_stdout_removes_it() {
             let (prog, args) = echo_cmd("test");
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             let stdout = proc.take_stdout();
             assert!(stdout.is_some());
             assert!(proc.stdout().is_none());
@@ -366,7 +392,8 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn take_stderr_removes_it() {
             let (prog, args) = echo_cmd("test");
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             let stderr = proc.take_stderr();
             assert!(stderr.is_some());
             assert!(proc.stderr().is_none());
@@ -376,21 +403,24 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn mut_stdin_is_some_initially() {
             let (prog, args) = echo_cmd("test");
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             assert!(proc.mut_stdin().is_some());
         }
 
         #[test_log::test(tokio::test)]
         async fn mut_stdout_is_some_initially() {
             let (prog, args) = echo_cmd("test");
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             assert!(proc.mut_stdout().is_some());
         }
 
         #[test_log::test(tokio::test)]
         async fn mut_stderr_is_some_initially() {
             let (prog, args) = echo_cmd("test");
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             assert!(proc.mut_stderr().is_some());
         }
     }
@@ -401,7 +431,8 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn echo_exits_successfully_with_code_zero() {
             let (prog, args) = echo_cmd("hello");
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             let status = proc.wait().await.unwrap();
             assert!(status.success);
             assert_eq!(status.code, Some(0));
@@ -410,7 +441,8 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn false_command_exits_with_nonzero_code() {
             let (prog, args) = failing_cmd();
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             let status = proc.wait().await.unwrap();
             assert!(!status.success);
             assert!(status.code.is_some());
@@ -420,7 +452,8 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn kill_then_wait_returns_killed_status() {
             let (prog, args) = long_running_cmd();
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
 
             ProcessKiller::kill(&mut proc).await.unwrap();
             let status = proc.wait().await.unwrap();
@@ -434,7 +467,8 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn captures_stdout_from_echo() {
             let (prog, args) = echo_cmd("hello");
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             let mut stdout = proc.take_stdout().unwrap();
 
             // Read from stdout
@@ -456,7 +490,8 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn cloned_killer_can_kill_process() {
             let (prog, args) = long_running_cmd();
-            let mut proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let mut proc =
+                SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             let mut killer = proc.clone_killer();
             killer.kill().await.unwrap();
 
@@ -472,14 +507,14 @@ mod tests {
         #[test_log::test(tokio::test)]
         async fn pty_size_returns_none() {
             let (prog, args) = echo_cmd("test");
-            let proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let proc = SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             assert!(proc.pty_size().is_none());
         }
 
         #[test_log::test(tokio::test)]
         async fn resize_pty_returns_error() {
             let (prog, args) = echo_cmd("test");
-            let proc = SimpleProcess::spawn(prog, args, empty_env(), None).unwrap();
+            let proc = SimpleProcess::spawn(prog, args, empty_env(), None, &no_options()).unwrap();
             let size = distant_core::protocol::PtySize {
                 rows: 24,
                 cols: 80,
@@ -498,9 +533,14 @@ mod tests {
         async fn uses_specified_current_dir() {
             let dir = tempfile::tempdir().unwrap();
             let (prog, args) = pwd_cmd();
-            let mut proc =
-                SimpleProcess::spawn(prog, args, empty_env(), Some(dir.path().to_path_buf()))
-                    .unwrap();
+            let mut proc = SimpleProcess::spawn(
+                prog,
+                args,
+                empty_env(),
+                Some(dir.path().to_path_buf()),
+                &no_options(),
+            )
+            .unwrap();
 
             let mut stdout = proc.take_stdout().unwrap();
             let data = stdout.recv().await.unwrap();
@@ -524,4 +564,153 @@ mod tests {
             assert!(status.success);
         }
     }
+
+    #[cfg(unix)]
+    mod spawn_with_limits {
+        use super::*;
+
+        async fn read_stdout(proc: &mut SimpleProcess) -> String {
+            let mut stdout = proc.take_stdout().unwrap();
+            let mut output = Vec::new();
+            while let Some(data) = stdout.recv().await.unwrap() {
+                output.extend(data);
+            }
+            String::from_utf8(output).unwrap()
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn applies_max_open_files() {
+            let limits = ProcessLimits {
+                max_open_files: Some(64),
+                ..Default::default()
+            };
+            let mut proc = SimpleProcess::spawn(
+                "sh",
+                ["-c", "ulimit -n"],
+                empty_env(),
+                None,
+                &options_with_limits(limits),
+            )
+            .unwrap();
+
+            assert_eq!(read_stdout(&mut proc).await.trim(), "64");
+            assert!(proc.wait().await.unwrap().success);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn applies_max_cpu_secs() {
+            let limits = ProcessLimits {
+                max_cpu_secs: Some(7),
+                ..Default::default()
+            };
+            let mut proc = SimpleProcess::spawn(
+                "sh",
+                ["-c", "ulimit -t"],
+                empty_env(),
+                None,
+                &options_with_limits(limits),
+            )
+            .unwrap();
+
+            assert_eq!(read_stdout(&mut proc).await.trim(), "7");
+            assert!(proc.wait().await.unwrap().success);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn applies_max_memory_through_cgroup_or_rlimit() {
+            let limits = ProcessLimits {
+                max_memory: Some(1024 * 1024 * 1024),
+                ..Default::default()
+            };
+            let mut proc = SimpleProcess::spawn(
+                "sh",
+                ["-c", "grep -s '^0::' /proc/self/cgroup; ulimit -v"],
+                empty_env(),
+                None,
+                &options_with_limits(limits),
+            )
+            .unwrap();
+
+            // NOTE: The address space is only limited when a cgroup could not be created
+            let output = read_stdout(&mut proc).await;
+            let ulimit = output.lines().last().unwrap();
+            if output.contains("/distant-") {
+                assert_eq!(ulimit, "unlimited");
+            } else {
+                assert_eq!(ulimit, "1048576");
+            }
+            assert!(proc.wait().await.unwrap().success);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn fails_for_unknown_user() {
+            let limits = ProcessLimits {
+                user: Some(String::from("distant-no-such-user")),
+                ..Default::default()
+            };
+            let result = SimpleProcess::spawn(
+                "true",
+                Vec::<String>::new(),
+                empty_env(),
+                None,
+                &options_with_limits(limits),
+            );
+            assert_eq!(result.err().unwrap().kind(), io::ErrorKind::NotFound);
+        }
+    }
+
+    #[cfg(unix)]
+    mod spawn_with_env_controls {
+        use super::*;
+
+        async fn read_stdout(proc: &mut SimpleProcess) -> String {
+            let mut stdout = proc.take_stdout().unwrap();
+            let mut output = Vec::new();
+            while let Some(data) = stdout.recv().await.unwrap() {
+                output.extend(data);
+            }
+            String::from_utf8(output).unwrap()
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn clear_env_keeps_only_explicit_variables() {
+            let options = ProcSpawnOptions {
+                clear_env: true,
+                ..Default::default()
+            };
+            let environment: Environment = [(String::from("DISTANT_KEY"), String::from("value"))]
+                .into_iter()
+                .collect();
+            let mut proc = SimpleProcess::spawn(
+                "/usr/bin/env",
+                Vec::<String>::new(),
+                environment,
+                None,
+                &options,
+            )
+            .unwrap();
+
+            assert_eq!(read_stdout(&mut proc).await.trim(), "DISTANT_KEY=value");
+            assert!(proc.wait().await.unwrap().success);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn unset_env_removes_inherited_variables() {
+            let options = ProcSpawnOptions {
+                unset_env: vec![String::from("HOME")],
+                ..Default::default()
+            };
+            let mut proc = SimpleProcess::spawn(
+                "sh",
+                ["-c", "echo \"${HOME-unset}\""],
+                empty_env(),
+                None,
+                &options,
+            )
+            .unwrap();
+
+            assert_eq!(read_stdout(&mut proc).await.trim(), "unset");
+            assert!(proc.wait().await.unwrap().success);
+        }
+    }
 }
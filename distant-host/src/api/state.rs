@@ -22,7 +22,8 @@ pub struct GlobalState {
     /// State that holds information about searches running on the server
     pub search: SearchState,
 
-    /// State that holds information about active TCP tunnels on the server
+    /// State that holds information about active tunnels on the server, limited by the
+    /// server's tunnel policy
     pub tunnel: TunnelState,
 
     /// Watcher used for filesystem events
@@ -34,7 +35,7 @@ impl GlobalState {
         Ok(Self {
             process: ProcessState::new(),
             search: SearchState::new(),
-            tunnel: TunnelState::new(),
+            tunnel: TunnelState::new(config.tunnel),
             watcher: WatcherBuilder::new()
                 .with_config(config.watch)
                 .initialize()?,
@@ -65,6 +66,7 @@ mod tests {
                 poll_interval: Some(Duration::from_secs(1)),
                 ..WatchConfig::default()
             },
+            ..Config::default()
         };
         let state = GlobalState::initialize(config);
         assert!(state.is_ok());
@@ -80,6 +82,7 @@ mod tests {
                 debounce_timeout: Duration::from_millis(100),
                 debounce_tick_rate: Some(Duration::from_millis(50)),
             },
+            ..Config::default()
         };
         let state = GlobalState::initialize(config);
         assert!(state.is_ok());
@@ -103,6 +106,7 @@ mod tests {
                 distant_core::protocol::Environment::new(),
                 None,
                 None,
+                distant_core::protocol::ProcSpawnOptions::default(),
                 Box::new(reply),
             )
             .await;
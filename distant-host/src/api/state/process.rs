@@ -4,7 +4,7 @@ use std::ops::Deref;
 use std::path::PathBuf;
 
 use distant_core::net::server::Reply;
-use distant_core::protocol::{Environment, ProcessId, PtySize, Response};
+use distant_core::protocol::{Environment, ProcSpawnOptions, ProcessId, PtySize, Response};
 use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
 
@@ -70,6 +70,7 @@ impl ProcessChannel {
         environment: Environment,
         current_dir: Option<PathBuf>,
         pty: Option<PtySize>,
+        options: ProcSpawnOptions,
         reply: Box<dyn Reply<Data = Response>>,
     ) -> io::Result<ProcessId> {
         let (cb, rx) = oneshot::channel();
@@ -79,6 +80,7 @@ impl ProcessChannel {
                 environment,
                 current_dir,
                 pty,
+                options: Box::new(options),
                 reply,
                 cb,
             })
@@ -110,6 +112,17 @@ impl ProcessChannel {
             .map_err(|_| io::Error::other("Response to stdin dropped"))?
     }
 
+    /// Closes stdin of a running process, signaling end of input.
+    pub async fn close_stdin(&self, id: ProcessId) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerProcessMsg::StdinClose { id, cb })
+            .await
+            .map_err(|_| io::Error::other("Internal process task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::other("Response to stdin close dropped"))?
+    }
+
     /// Kills a running process, including persistent processes if `force` is true. Will fail if
     /// unable to kill the process or `force` is false when the process is persistent.
     pub async fn kill(&self, id: ProcessId) -> io::Result<()> {
@@ -130,6 +143,7 @@ enum InnerProcessMsg {
         environment: Environment,
         current_dir: Option<PathBuf>,
         pty: Option<PtySize>,
+        options: Box<ProcSpawnOptions>,
         reply: Box<dyn Reply<Data = Response>>,
         cb: oneshot::Sender<io::Result<ProcessId>>,
     },
@@ -143,6 +157,10 @@ enum InnerProcessMsg {
         data: Vec<u8>,
         cb: oneshot::Sender<io::Result<()>>,
     },
+    StdinClose {
+        id: ProcessId,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
     Kill {
         id: ProcessId,
         cb: oneshot::Sender<io::Result<()>>,
@@ -162,11 +180,19 @@ async fn process_task(tx: mpsc::Sender<InnerProcessMsg>, mut rx: mpsc::Receiver<
                 environment,
                 current_dir,
                 pty,
+                options,
                 reply,
                 cb,
             } => {
                 let _ = cb.send(
-                    match ProcessInstance::spawn(cmd, environment, current_dir, pty, reply) {
+                    match ProcessInstance::spawn(
+                        cmd,
+                        environment,
+                        current_dir,
+                        pty,
+                        *options,
+                        reply,
+                    ) {
                         Ok(mut process) => {
                             let id = process.id;
 
@@ -199,6 +225,16 @@ async fn process_task(tx: mpsc::Sender<InnerProcessMsg>, mut rx: mpsc::Receiver<
                     None => Err(io::Error::other(format!("No process found with id {id}"))),
                 });
             }
+            InnerProcessMsg::StdinClose { id, cb } => {
+                let _ = cb.send(match processes.get_mut(&id) {
+                    Some(process) => {
+                        // Dropping our end of the pipe is what signals EOF to the process
+                        process.stdin = None;
+                        Ok(())
+                    }
+                    None => Err(io::Error::other(format!("No process found with id {id}"))),
+                });
+            }
             InnerProcessMsg::Kill { id, cb } => {
                 let _ = cb.send(match processes.get_mut(&id) {
                     Some(process) => process.killer.kill().await,
@@ -234,6 +270,7 @@ mod tests {
                 Environment::new(),
                 None,
                 None,
+                ProcSpawnOptions::default(),
                 Box::new(reply),
             )
             .await;
@@ -280,6 +317,19 @@ mod tests {
         );
     }
 
+    #[test(tokio::test)]
+    async fn default_channel_close_stdin_should_fail_with_closed_error() {
+        let channel = ProcessChannel::default();
+        let result = channel.close_stdin(1).await;
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Internal process task closed")
+        );
+    }
+
     #[test(tokio::test)]
     async fn default_channel_kill_should_fail_with_closed_error() {
         let channel = ProcessChannel::default();
@@ -309,7 +359,14 @@ mod tests {
         };
 
         let id = state
-            .spawn(cmd, Environment::new(), None, None, Box::new(reply))
+            .spawn(
+                cmd,
+                Environment::new(),
+                None,
+                None,
+                ProcSpawnOptions::default(),
+                Box::new(reply),
+            )
             .await
             .unwrap();
 
@@ -348,7 +405,14 @@ mod tests {
 
         // This uses Deref to call spawn on the ProcessChannel
         let result = state
-            .spawn(cmd, Environment::new(), None, None, Box::new(reply))
+            .spawn(
+                cmd,
+                Environment::new(),
+                None,
+                None,
+                ProcSpawnOptions::default(),
+                Box::new(reply),
+            )
             .await;
         assert!(result.is_ok());
     }
@@ -369,6 +433,7 @@ mod tests {
                 Environment::new(),
                 None,
                 None,
+                ProcSpawnOptions::default(),
                 Box::new(reply),
             )
             .await;
@@ -408,6 +473,19 @@ mod tests {
         );
     }
 
+    #[test(tokio::test)]
+    async fn close_stdin_should_fail_for_nonexistent_process() {
+        let state = ProcessState::new();
+        let result = state.close_stdin(99999).await;
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("No process found with id 99999")
+        );
+    }
+
     #[test(tokio::test)]
     async fn kill_should_fail_for_nonexistent_process() {
         let state = ProcessState::new();
@@ -434,7 +512,14 @@ mod tests {
         };
 
         let id = state
-            .spawn(cmd, Environment::new(), None, None, Box::new(reply))
+            .spawn(
+                cmd,
+                Environment::new(),
+                None,
+                None,
+                ProcSpawnOptions::default(),
+                Box::new(reply),
+            )
             .await
             .unwrap();
 
@@ -473,7 +558,14 @@ mod tests {
         };
 
         let id = state
-            .spawn(cmd, Environment::new(), None, None, Box::new(reply))
+            .spawn(
+                cmd,
+                Environment::new(),
+                None,
+                None,
+                ProcSpawnOptions::default(),
+                Box::new(reply),
+            )
             .await
             .unwrap();
 
@@ -491,6 +583,48 @@ mod tests {
         while rx.recv().await.is_some() {}
     }
 
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn close_stdin_should_cause_process_reading_until_eof_to_complete() {
+        let state = ProcessState::new();
+        let (reply, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let id = state
+            .spawn(
+                "wc -c".to_string(),
+                Environment::new(),
+                None,
+                None,
+                ProcSpawnOptions::default(),
+                Box::new(reply),
+            )
+            .await
+            .unwrap();
+
+        state.send_stdin(id, b"hello\n".to_vec()).await.unwrap();
+        state.close_stdin(id).await.unwrap();
+
+        // Writing after the close should fail as stdin is no longer available
+        assert!(state.send_stdin(id, b"more".to_vec()).await.is_err());
+
+        let mut stdout = Vec::new();
+        let mut got_done = false;
+        let deadline = tokio::time::Instant::now() + KILL_RESPONSE_TIMEOUT;
+        while let Ok(Some(resp)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+            match resp {
+                Response::ProcStdout { data, .. } => stdout.extend(data),
+                Response::ProcDone { success, .. } => {
+                    assert!(success);
+                    got_done = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert!(got_done, "Never received ProcDone after closing stdin");
+        assert_eq!(String::from_utf8_lossy(&stdout).trim(), "6");
+    }
+
     #[test(tokio::test)]
     async fn spawn_should_fail_for_empty_command() {
         let state = ProcessState::new();
@@ -502,6 +636,7 @@ mod tests {
                 Environment::new(),
                 None,
                 None,
+                ProcSpawnOptions::default(),
                 Box::new(reply),
             )
             .await;
@@ -530,7 +665,14 @@ mod tests {
         };
 
         let result = channel
-            .spawn(cmd, Environment::new(), None, None, Box::new(reply))
+            .spawn(
+                cmd,
+                Environment::new(),
+                None,
+                None,
+                ProcSpawnOptions::default(),
+                Box::new(reply),
+            )
             .await;
         assert!(result.is_ok());
     }
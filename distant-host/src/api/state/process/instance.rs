@@ -1,9 +1,12 @@
 use std::future::Future;
 use std::io;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use distant_core::net::server::Reply;
-use distant_core::protocol::{Environment, ProcessId, PtySize, Response};
+use distant_core::protocol::{
+    Environment, ProcOutputStamper, ProcSpawnOptions, ProcessId, PtySize, Response,
+};
 use log::*;
 use tokio::task::JoinHandle;
 
@@ -12,6 +15,8 @@ use crate::api::process::PtyProcess;
 use crate::api::process::{
     InputChannel, OutputChannel, Process, ProcessKiller, ProcessPty, SimpleProcess,
 };
+#[cfg(feature = "pty")]
+use crate::api::process::{wrap_with_env, wrap_with_shell};
 use crate::constants::OUTPUT_DRAIN_TIMEOUT;
 
 /// Holds information related to a spawned process on the server
@@ -71,8 +76,11 @@ impl ProcessInstance {
         environment: Environment,
         current_dir: Option<PathBuf>,
         pty: Option<PtySize>,
+        options: ProcSpawnOptions,
         reply: Box<dyn Reply<Data = Response>>,
     ) -> io::Result<Self> {
+        let limits = &options.limits;
+
         // Build out the command and args from our string
         let mut cmd_and_args = if cfg!(windows) {
             winsplit::split(&cmd)
@@ -91,16 +99,27 @@ impl ProcessInstance {
         let args = cmd_and_args.split_off(1);
         let cmd = cmd_and_args.into_iter().next().unwrap();
 
+        // Create the stamper prior to spawning so timestamps are relative to the process start
+        let stamper = ProcOutputStamper::new_if(options.timestamps);
+
         debug!("Spawning process: {cmd} {args:?}");
         let mut child: Box<dyn Process> = match pty {
             #[cfg(feature = "pty")]
-            Some(size) => Box::new(PtyProcess::spawn(
-                cmd.clone(),
-                args.clone(),
-                environment,
-                current_dir,
-                size,
-            )?),
+            Some(size) => {
+                // NOTE: We do not control how the pty spawns the process, so environment controls
+                //       are applied by `env` and limits are applied by a shell that then replaces
+                //       itself with the process
+                let (cmd, args, environment) =
+                    wrap_with_env(cmd.clone(), args.clone(), environment, &options)?;
+                let (program, args) = wrap_with_shell(cmd, args, limits)?;
+                Box::new(PtyProcess::spawn(
+                    program,
+                    args,
+                    environment,
+                    current_dir,
+                    size,
+                )?)
+            }
             #[cfg(not(feature = "pty"))]
             Some(_) => {
                 return Err(io::Error::other(
@@ -112,6 +131,7 @@ impl ProcessInstance {
                 args.clone(),
                 environment,
                 current_dir,
+                &options,
             )?),
         };
 
@@ -126,7 +146,7 @@ impl ProcessInstance {
         let stdout_task = match stdout {
             Some(stdout) => {
                 let reply = reply.clone_reply();
-                let task = tokio::spawn(stdout_task(id, stdout, reply));
+                let task = tokio::spawn(stdout_task(id, stdout, stamper.clone(), reply));
                 Some(task)
             }
             None => None,
@@ -136,17 +156,18 @@ impl ProcessInstance {
         let stderr_task = match stderr {
             Some(stderr) => {
                 let reply = reply.clone_reply();
-                let task = tokio::spawn(stderr_task(id, stderr, reply));
+                let task = tokio::spawn(stderr_task(id, stderr, stamper.clone(), reply));
                 Some(task)
             }
             None => None,
         };
 
-        // Spawn a task that waits on the process to exit, then drains
-        // stdout/stderr before sending ProcDone
+        // Spawn a task that waits on the process to exit (killing it if it exceeds its timeout),
+        // then drains stdout/stderr before sending ProcDone
         let wait_task = Some(tokio::spawn(wait_task(
             id,
             child,
+            limits.timeout(),
             reply,
             stdout_task,
             stderr_task,
@@ -186,12 +207,14 @@ impl ProcessInstance {
 async fn stdout_task(
     id: ProcessId,
     mut stdout: Box<dyn OutputChannel>,
+    stamper: Option<ProcOutputStamper>,
     reply: Box<dyn Reply<Data = Response>>,
 ) -> io::Result<()> {
     loop {
         match stdout.recv().await {
             Ok(Some(data)) => {
-                reply.send(Response::ProcStdout { id, data })?;
+                let stamp = stamper.as_ref().map(ProcOutputStamper::next_stamp);
+                reply.send(Response::ProcStdout { id, data, stamp })?;
             }
             Ok(None) => return Ok(()),
             Err(x) => return Err(x),
@@ -202,12 +225,14 @@ async fn stdout_task(
 async fn stderr_task(
     id: ProcessId,
     mut stderr: Box<dyn OutputChannel>,
+    stamper: Option<ProcOutputStamper>,
     reply: Box<dyn Reply<Data = Response>>,
 ) -> io::Result<()> {
     loop {
         match stderr.recv().await {
             Ok(Some(data)) => {
-                reply.send(Response::ProcStderr { id, data })?;
+                let stamp = stamper.as_ref().map(ProcOutputStamper::next_stamp);
+                reply.send(Response::ProcStderr { id, data, stamp })?;
             }
             Ok(None) => return Ok(()),
             Err(x) => return Err(x),
@@ -218,11 +243,24 @@ async fn stderr_task(
 async fn wait_task(
     id: ProcessId,
     mut child: Box<dyn Process>,
+    timeout: Option<Duration>,
     reply: Box<dyn Reply<Data = Response>>,
     stdout_task: Option<JoinHandle<io::Result<()>>>,
     stderr_task: Option<JoinHandle<io::Result<()>>>,
 ) -> io::Result<()> {
-    let status = child.wait().await;
+    let status = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, child.wait()).await {
+            Ok(status) => status,
+            Err(_) => {
+                debug!("Process {id} exceeded timeout of {timeout:?}, so killing it");
+                if let Err(x) = child.kill().await {
+                    error!("Failed to kill process {id} after timeout: {x}");
+                }
+                child.wait().await
+            }
+        },
+        None => child.wait().await,
+    };
 
     // Wait for output tasks to finish draining before sending ProcDone.
     // Timeout guards against Windows ConPTY readers that may never EOF.
@@ -252,6 +290,8 @@ mod tests {
     //! stdout/stderr response forwarding, `on_done` callbacks, standalone task functions,
     //! current_dir, PTY spawn, and the Drop-based kill behavior.
 
+    use distant_core::protocol::{ProcOutputStamp, ProcessLimits};
+
     use super::*;
     use crate::api::process::{ExitStatus, FutureReturn, NoProcessPty};
     use std::sync::{Arc, Mutex};
@@ -272,7 +312,14 @@ mod tests {
     #[test(tokio::test)]
     async fn spawn_should_fail_with_empty_command() {
         let (reply, _rx) = make_reply();
-        let result = ProcessInstance::spawn("".to_string(), Environment::new(), None, None, reply);
+        let result = ProcessInstance::spawn(
+            "".to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions::default(),
+            reply,
+        );
         assert!(result.is_err());
         let err = match result {
             Err(e) => e,
@@ -292,8 +339,15 @@ mod tests {
             "echo hello"
         };
 
-        let instance =
-            ProcessInstance::spawn(cmd.to_string(), Environment::new(), None, None, reply).unwrap();
+        let instance = ProcessInstance::spawn(
+            cmd.to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions::default(),
+            reply,
+        )
+        .unwrap();
 
         assert!(!instance.cmd.is_empty());
         assert!(instance.id > 0);
@@ -309,8 +363,15 @@ mod tests {
             "echo hello world"
         };
 
-        let instance =
-            ProcessInstance::spawn(cmd.to_string(), Environment::new(), None, None, reply).unwrap();
+        let instance = ProcessInstance::spawn(
+            cmd.to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions::default(),
+            reply,
+        )
+        .unwrap();
 
         if cfg!(windows) {
             assert_eq!(instance.cmd, "cmd");
@@ -328,6 +389,7 @@ mod tests {
             Environment::new(),
             None,
             None,
+            ProcSpawnOptions::default(),
             reply,
         );
         assert!(result.is_err());
@@ -343,8 +405,15 @@ mod tests {
             "cat"
         };
 
-        let instance =
-            ProcessInstance::spawn(cmd.to_string(), Environment::new(), None, None, reply).unwrap();
+        let instance = ProcessInstance::spawn(
+            cmd.to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions::default(),
+            reply,
+        )
+        .unwrap();
 
         assert!(instance.stdin.is_some());
     }
@@ -359,8 +428,15 @@ mod tests {
             "echo spawn_test"
         };
 
-        let _instance =
-            ProcessInstance::spawn(cmd.to_string(), Environment::new(), None, None, reply).unwrap();
+        let _instance = ProcessInstance::spawn(
+            cmd.to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions::default(),
+            reply,
+        )
+        .unwrap();
 
         // Collect responses until we get ProcDone
         let mut got_stdout = false;
@@ -396,8 +472,15 @@ mod tests {
             "sh -c 'echo stderr_output >&2'"
         };
 
-        let _instance =
-            ProcessInstance::spawn(cmd.to_string(), Environment::new(), None, None, reply).unwrap();
+        let _instance = ProcessInstance::spawn(
+            cmd.to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions::default(),
+            reply,
+        )
+        .unwrap();
 
         let mut got_stderr = false;
         let mut got_done = false;
@@ -433,8 +516,15 @@ mod tests {
             "echo done_test"
         };
 
-        let mut instance =
-            ProcessInstance::spawn(cmd.to_string(), Environment::new(), None, None, reply).unwrap();
+        let mut instance = ProcessInstance::spawn(
+            cmd.to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions::default(),
+            reply,
+        )
+        .unwrap();
 
         let (done_tx, done_rx) = tokio::sync::oneshot::channel();
         let done_tx = Arc::new(Mutex::new(Some(done_tx)));
@@ -467,8 +557,15 @@ mod tests {
             "echo on_done_twice"
         };
 
-        let mut instance =
-            ProcessInstance::spawn(cmd.to_string(), Environment::new(), None, None, reply).unwrap();
+        let mut instance = ProcessInstance::spawn(
+            cmd.to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions::default(),
+            reply,
+        )
+        .unwrap();
 
         let (done_tx1, done_rx1) = tokio::sync::oneshot::channel();
         let done_tx1 = Arc::new(Mutex::new(Some(done_tx1)));
@@ -527,12 +624,13 @@ mod tests {
         data_tx.send(b"world".to_vec()).await.unwrap();
         drop(data_tx);
 
-        let result = stdout_task(42, Box::new(data_rx), Box::new(tx)).await;
+        let result = stdout_task(42, Box::new(data_rx), None, Box::new(tx)).await;
         assert!(result.is_ok());
 
         let resp1 = rx.recv().await.unwrap();
         match resp1 {
-            Response::ProcStdout { id, data } => {
+            Response::ProcStdout { id, data, stamp } => {
+                assert_eq!(stamp, None);
                 assert_eq!(id, 42);
                 assert_eq!(data, b"hello");
             }
@@ -541,7 +639,8 @@ mod tests {
 
         let resp2 = rx.recv().await.unwrap();
         match resp2 {
-            Response::ProcStdout { id, data } => {
+            Response::ProcStdout { id, data, stamp } => {
+                assert_eq!(stamp, None);
                 assert_eq!(id, 42);
                 assert_eq!(data, b"world");
             }
@@ -557,12 +656,13 @@ mod tests {
         data_tx.send(b"err1".to_vec()).await.unwrap();
         drop(data_tx);
 
-        let result = stderr_task(99, Box::new(data_rx), Box::new(tx)).await;
+        let result = stderr_task(99, Box::new(data_rx), None, Box::new(tx)).await;
         assert!(result.is_ok());
 
         let resp = rx.recv().await.unwrap();
         match resp {
-            Response::ProcStderr { id, data } => {
+            Response::ProcStderr { id, data, stamp } => {
+                assert_eq!(stamp, None);
                 assert_eq!(id, 99);
                 assert_eq!(data, b"err1");
             }
@@ -570,6 +670,47 @@ mod tests {
         }
     }
 
+    #[test(tokio::test)]
+    async fn stdout_and_stderr_tasks_should_share_stamp_sequence() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let stamper = ProcOutputStamper::new();
+
+        let (data_tx, data_rx) = mpsc::channel::<Vec<u8>>(10);
+        data_tx.send(b"out".to_vec()).await.unwrap();
+        drop(data_tx);
+        stdout_task(
+            1,
+            Box::new(data_rx),
+            Some(stamper.clone()),
+            Box::new(tx.clone()),
+        )
+        .await
+        .unwrap();
+
+        let (data_tx, data_rx) = mpsc::channel::<Vec<u8>>(10);
+        data_tx.send(b"err".to_vec()).await.unwrap();
+        drop(data_tx);
+        stderr_task(1, Box::new(data_rx), Some(stamper), Box::new(tx))
+            .await
+            .unwrap();
+
+        match rx.recv().await.unwrap() {
+            Response::ProcStdout {
+                stamp: Some(ProcOutputStamp { seq, .. }),
+                ..
+            } => assert_eq!(seq, 0),
+            other => panic!("Expected stamped ProcStdout, got: {other:?}"),
+        }
+
+        match rx.recv().await.unwrap() {
+            Response::ProcStderr {
+                stamp: Some(ProcOutputStamp { seq, .. }),
+                ..
+            } => assert_eq!(seq, 1),
+            other => panic!("Expected stamped ProcStderr, got: {other:?}"),
+        }
+    }
+
     #[test(tokio::test)]
     async fn stdout_task_should_return_error_when_reply_channel_closed() {
         let (tx, rx) = mpsc::unbounded_channel();
@@ -579,7 +720,7 @@ mod tests {
         data_tx.send(b"data".to_vec()).await.unwrap();
 
         // Should get an error when trying to send the response
-        let result = stdout_task(1, Box::new(data_rx), Box::new(tx)).await;
+        let result = stdout_task(1, Box::new(data_rx), None, Box::new(tx)).await;
         assert!(result.is_err());
     }
 
@@ -591,7 +732,7 @@ mod tests {
         let (data_tx, data_rx) = mpsc::channel::<Vec<u8>>(10);
         data_tx.send(b"data".to_vec()).await.unwrap();
 
-        let result = stderr_task(1, Box::new(data_rx), Box::new(tx)).await;
+        let result = stderr_task(1, Box::new(data_rx), None, Box::new(tx)).await;
         assert!(result.is_err());
     }
 
@@ -607,7 +748,7 @@ mod tests {
         }
 
         let (tx, _rx) = mpsc::unbounded_channel();
-        let result = stdout_task(1, Box::new(ErrorChannel), Box::new(tx)).await;
+        let result = stdout_task(1, Box::new(ErrorChannel), None, Box::new(tx)).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("read error"));
     }
@@ -624,7 +765,7 @@ mod tests {
         }
 
         let (tx, _rx) = mpsc::unbounded_channel();
-        let result = stderr_task(1, Box::new(ErrorChannel), Box::new(tx)).await;
+        let result = stderr_task(1, Box::new(ErrorChannel), None, Box::new(tx)).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("stderr error"));
     }
@@ -641,6 +782,7 @@ mod tests {
             Environment::new(),
             Some(temp_dir.clone()),
             None,
+            ProcSpawnOptions::default(),
             reply,
         )
         .unwrap();
@@ -687,6 +829,7 @@ mod tests {
             Environment::new(),
             None,
             Some(size),
+            ProcSpawnOptions::default(),
             reply,
         )
         .unwrap();
@@ -706,6 +849,86 @@ mod tests {
         assert!(got_done, "Never received ProcDone from pty process");
     }
 
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn spawn_should_kill_process_that_exceeds_timeout() {
+        let (reply, mut rx) = make_reply();
+
+        let limits = ProcessLimits {
+            timeout_ms: Some(100),
+            ..Default::default()
+        };
+        let instance = ProcessInstance::spawn(
+            "sleep 60".to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions {
+                limits,
+                ..Default::default()
+            },
+            reply,
+        )
+        .unwrap();
+
+        let id = instance.id;
+        let resp = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match rx.recv().await {
+                    Some(Response::ProcDone { id, success, .. }) => return (id, success),
+                    Some(_) => continue,
+                    None => panic!("Reply channel closed before ProcDone"),
+                }
+            }
+        })
+        .await
+        .expect("Process was not killed after exceeding its timeout");
+
+        assert_eq!(resp, (id, false));
+    }
+
+    #[cfg(all(unix, feature = "pty"))]
+    #[test(tokio::test)]
+    async fn spawn_should_apply_limits_to_pty_process() {
+        let (reply, mut rx) = make_reply();
+
+        let size = distant_core::protocol::PtySize {
+            rows: 24,
+            cols: 80,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        let limits = ProcessLimits {
+            max_open_files: Some(64),
+            ..Default::default()
+        };
+
+        let _instance = ProcessInstance::spawn(
+            "sh -c 'ulimit -n'".to_string(),
+            Environment::new(),
+            None,
+            Some(size),
+            ProcSpawnOptions {
+                limits,
+                ..Default::default()
+            },
+            reply,
+        )
+        .unwrap();
+
+        let mut stdout_data = Vec::new();
+        while let Ok(Some(resp)) = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await {
+            match resp {
+                Response::ProcStdout { data, .. } => stdout_data.extend_from_slice(&data),
+                Response::ProcDone { .. } => break,
+                _ => {}
+            }
+        }
+
+        let output = String::from_utf8_lossy(&stdout_data);
+        assert_eq!(output.trim(), "64", "Unexpected pty output: {output:?}");
+    }
+
     #[test(tokio::test)]
     async fn drop_should_attempt_to_kill_running_process() {
         let (reply, mut rx) = make_reply();
@@ -716,8 +939,15 @@ mod tests {
             "sleep 60"
         };
 
-        let instance =
-            ProcessInstance::spawn(cmd.to_string(), Environment::new(), None, None, reply).unwrap();
+        let instance = ProcessInstance::spawn(
+            cmd.to_string(),
+            Environment::new(),
+            None,
+            None,
+            ProcSpawnOptions::default(),
+            reply,
+        )
+        .unwrap();
 
         let id = instance.id;
 
@@ -840,13 +1070,13 @@ mod tests {
             data: Some(b"delayed_data".to_vec()),
             delay: Duration::from_millis(200),
         });
-        let stdout_handle = tokio::spawn(stdout_task(42, delayed, Box::new(tx.clone())));
+        let stdout_handle = tokio::spawn(stdout_task(42, delayed, None, Box::new(tx.clone())));
 
         // Process exits instantly (no delay)
         let child: Box<dyn Process> = Box::new(InstantExitProcess { id: 42 });
 
         // wait_task should await stdout_handle before sending ProcDone
-        let _ = wait_task(42, child, Box::new(tx), Some(stdout_handle), None).await;
+        let _ = wait_task(42, child, None, Box::new(tx), Some(stdout_handle), None).await;
 
         // Collect all responses
         let mut responses = Vec::new();
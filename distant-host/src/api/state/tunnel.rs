@@ -1,19 +1,34 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::io;
+use std::net::SocketAddr;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU32, Ordering};
 
-use distant_core::constants::TUNNEL_RELAY_BUFFER_SIZE;
+use distant_core::constants::{
+    TUNNEL_CHANNEL_CAPACITY, TUNNEL_RELAY_BUFFER_SIZE, UDP_TUNNEL_DATAGRAM_BUFFER_SIZE,
+    UDP_TUNNEL_IDLE_TIMEOUT,
+};
+use distant_core::net::common::{UdpDemux, UdpPeer, UdpTarget};
 use distant_core::net::server::Reply;
-use distant_core::protocol::{Response, TunnelDirection, TunnelId, TunnelInfo};
+use distant_core::protocol::{
+    Response, TunnelConnectionGuard, TunnelCounters, TunnelDirection, TunnelId, TunnelInfo,
+    TunnelProtocol, TunnelStats,
+};
 use log::*;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
 use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
+use tokio::time::{self, Instant};
 
-/// Holds information related to active TCP tunnels on the server.
+use crate::config::TunnelConfig;
+
+/// Holds information related to active TCP, UDP, and Unix socket tunnels on the server.
+///
+/// Tunnels are only opened to targets, and listen on addresses, that are allowed by the
+/// server's [`TunnelConfig`]; anything else fails with [`io::ErrorKind::PermissionDenied`].
 pub struct TunnelState {
     channel: TunnelChannel,
     task: JoinHandle<()>,
@@ -27,10 +42,10 @@ impl Drop for TunnelState {
 }
 
 impl TunnelState {
-    /// Creates a new tunnel state, spawning the background actor task.
-    pub fn new() -> Self {
+    /// Creates a new tunnel state that enforces `config`, spawning the background actor task.
+    pub fn new(config: TunnelConfig) -> Self {
         let (tx, rx) = mpsc::channel(1);
-        let task = tokio::spawn(tunnel_task(tx.clone(), rx));
+        let task = tokio::spawn(tunnel_task(config, tx.clone(), rx));
 
         Self {
             channel: TunnelChannel { tx },
@@ -76,12 +91,52 @@ impl TunnelChannel {
         host: String,
         port: u16,
         reply: Box<dyn Reply<Data = Response>>,
+    ) -> io::Result<TunnelId> {
+        self.open_with_protocol(host, port, TunnelProtocol::Tcp, reply)
+            .await
+    }
+
+    /// Opens a forward UDP tunnel by associating a UDP socket with the specified host and port.
+    ///
+    /// Each write is sent as a single datagram, and each datagram received back is streamed
+    /// via the reply channel as its own `TunnelData` response. The tunnel is closed once no
+    /// datagrams have passed through it for [`UDP_TUNNEL_IDLE_TIMEOUT`].
+    pub async fn open_udp(
+        &self,
+        host: String,
+        port: u16,
+        reply: Box<dyn Reply<Data = Response>>,
+    ) -> io::Result<TunnelId> {
+        self.open_with_protocol(host, port, TunnelProtocol::Udp, reply)
+            .await
+    }
+
+    /// Opens a forward tunnel by connecting to the Unix domain socket at the specified path.
+    ///
+    /// Data received from the socket is streamed back via the reply channel as `TunnelData`
+    /// responses.
+    pub async fn open_unix(
+        &self,
+        path: String,
+        reply: Box<dyn Reply<Data = Response>>,
+    ) -> io::Result<TunnelId> {
+        self.open_with_protocol(path, 0, TunnelProtocol::Unix, reply)
+            .await
+    }
+
+    async fn open_with_protocol(
+        &self,
+        host: String,
+        port: u16,
+        protocol: TunnelProtocol,
+        reply: Box<dyn Reply<Data = Response>>,
     ) -> io::Result<TunnelId> {
         let (cb, rx) = oneshot::channel();
         self.tx
             .send(InnerTunnelMsg::Open {
                 host,
                 port,
+                protocol,
                 reply,
                 cb,
             })
@@ -101,12 +156,54 @@ impl TunnelChannel {
         host: String,
         port: u16,
         reply: Box<dyn Reply<Data = Response>>,
+    ) -> io::Result<(TunnelId, u16)> {
+        self.listen_with_protocol(host, port, TunnelProtocol::Tcp, reply)
+            .await
+    }
+
+    /// Starts a reverse UDP tunnel listener on the specified host and port.
+    ///
+    /// Returns the listener's tunnel id and the actual bound port. Each distinct peer that
+    /// sends datagrams to the listener is reported via a `TunnelIncoming` response as its own
+    /// sub-tunnel, which is closed once the peer has been idle for [`UDP_TUNNEL_IDLE_TIMEOUT`].
+    pub async fn listen_udp(
+        &self,
+        host: String,
+        port: u16,
+        reply: Box<dyn Reply<Data = Response>>,
+    ) -> io::Result<(TunnelId, u16)> {
+        self.listen_with_protocol(host, port, TunnelProtocol::Udp, reply)
+            .await
+    }
+
+    /// Starts a reverse tunnel listener on a Unix domain socket created at the specified path.
+    ///
+    /// Returns the listener's tunnel id. Incoming connections are reported via
+    /// `TunnelIncoming` responses, and their data is streamed via `TunnelData` responses
+    /// through the reply channel. The socket file is removed once the listener is closed.
+    pub async fn listen_unix(
+        &self,
+        path: String,
+        reply: Box<dyn Reply<Data = Response>>,
+    ) -> io::Result<TunnelId> {
+        self.listen_with_protocol(path, 0, TunnelProtocol::Unix, reply)
+            .await
+            .map(|(id, _)| id)
+    }
+
+    async fn listen_with_protocol(
+        &self,
+        host: String,
+        port: u16,
+        protocol: TunnelProtocol,
+        reply: Box<dyn Reply<Data = Response>>,
     ) -> io::Result<(TunnelId, u16)> {
         let (cb, rx) = oneshot::channel();
         self.tx
             .send(InnerTunnelMsg::Listen {
                 host,
                 port,
+                protocol,
                 reply,
                 cb,
             })
@@ -139,7 +236,8 @@ impl TunnelChannel {
             .map_err(|_| io::Error::other("Response to tunnel close dropped"))?
     }
 
-    /// Lists all active tunnels and listeners.
+    /// Lists all active tunnels and listeners, along with the traffic that has passed through
+    /// each of them.
     pub async fn list(&self) -> io::Result<Vec<TunnelInfo>> {
         let (cb, rx) = oneshot::channel();
         self.tx
@@ -156,12 +254,14 @@ enum InnerTunnelMsg {
     Open {
         host: String,
         port: u16,
+        protocol: TunnelProtocol,
         reply: Box<dyn Reply<Data = Response>>,
         cb: oneshot::Sender<io::Result<TunnelId>>,
     },
     Listen {
         host: String,
         port: u16,
+        protocol: TunnelProtocol,
         reply: Box<dyn Reply<Data = Response>>,
         cb: oneshot::Sender<io::Result<(TunnelId, u16)>>,
     },
@@ -187,6 +287,7 @@ enum InnerTunnelMsg {
         port: u16,
         write_tx: mpsc::Sender<Vec<u8>>,
         task: JoinHandle<()>,
+        counters: Arc<TunnelCounters>,
     },
 }
 
@@ -197,16 +298,38 @@ enum TunnelEntry {
         info: TunnelInfo,
         write_tx: mpsc::Sender<Vec<u8>>,
         task: JoinHandle<()>,
+        counters: Arc<TunnelCounters>,
     },
-    /// A reverse tunnel listener that accepts incoming connections.
+    /// A reverse tunnel listener that accepts incoming connections, whose counters include
+    /// the traffic of all of its sub-tunnels.
     Listener {
         info: TunnelInfo,
         sub_tunnel_ids: Vec<TunnelId>,
         task: JoinHandle<()>,
+        counters: Arc<TunnelCounters>,
     },
 }
 
-async fn tunnel_task(tx: mpsc::Sender<InnerTunnelMsg>, mut rx: mpsc::Receiver<InnerTunnelMsg>) {
+impl TunnelEntry {
+    /// Returns information about the tunnel, including the traffic that has passed through it.
+    fn to_info(&self) -> TunnelInfo {
+        let (info, counters) = match self {
+            Self::Connection { info, counters, .. } => (info, counters),
+            Self::Listener { info, counters, .. } => (info, counters),
+        };
+
+        TunnelInfo {
+            stats: counters.stats(),
+            ..info.clone()
+        }
+    }
+}
+
+async fn tunnel_task(
+    config: TunnelConfig,
+    tx: mpsc::Sender<InnerTunnelMsg>,
+    mut rx: mpsc::Receiver<InnerTunnelMsg>,
+) {
     let next_id = Arc::new(AtomicU32::new(1));
     let mut tunnels: HashMap<TunnelId, TunnelEntry> = HashMap::new();
 
@@ -215,22 +338,66 @@ async fn tunnel_task(tx: mpsc::Sender<InnerTunnelMsg>, mut rx: mpsc::Receiver<In
             InnerTunnelMsg::Open {
                 host,
                 port,
+                protocol,
                 reply,
                 cb,
             } => {
-                let stream = match TcpStream::connect((&*host, port)).await {
-                    Ok(s) => s,
+                let id = next_id.fetch_add(1, Ordering::Relaxed);
+                let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(1024);
+                let tx_clone = tx.clone();
+                let counters = TunnelCounters::new();
+                let traffic = Traffic::new(&counters, TunnelDirection::Forward);
+
+                let targets = match protocol {
+                    TunnelProtocol::Unix if !config.unix_sockets => {
+                        Err(policy_denied(format_args!("tunnel to Unix socket {host}")))
+                    }
+                    TunnelProtocol::Unix => Ok(Vec::new()),
+                    _ => resolve_targets(&config, &host, port).await,
+                };
+                let targets = match targets {
+                    Ok(targets) => targets,
                     Err(e) => {
                         let _ = cb.send(Err(e));
                         continue;
                     }
                 };
 
-                let id = next_id.fetch_add(1, Ordering::Relaxed);
-                let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(1024);
-                let tx_clone = tx.clone();
-
-                let task = tokio::spawn(connection_task(id, stream, reply, write_rx, tx_clone));
+                let task = match protocol {
+                    TunnelProtocol::Tcp => match TcpStream::connect(&*targets).await {
+                        Ok(stream) => tokio::spawn(connection_task(
+                            id, traffic, stream, reply, write_rx, tx_clone,
+                        )),
+                        Err(e) => {
+                            let _ = cb.send(Err(e));
+                            continue;
+                        }
+                    },
+                    TunnelProtocol::Udp => match connect_udp(&targets).await {
+                        Ok(socket) => tokio::spawn(udp_connection_task(
+                            id, traffic, socket, reply, write_rx, tx_clone,
+                        )),
+                        Err(e) => {
+                            let _ = cb.send(Err(e));
+                            continue;
+                        }
+                    },
+                    #[cfg(unix)]
+                    TunnelProtocol::Unix => match tokio::net::UnixStream::connect(&host).await {
+                        Ok(stream) => tokio::spawn(connection_task(
+                            id, traffic, stream, reply, write_rx, tx_clone,
+                        )),
+                        Err(e) => {
+                            let _ = cb.send(Err(e));
+                            continue;
+                        }
+                    },
+                    #[cfg(not(unix))]
+                    TunnelProtocol::Unix => {
+                        let _ = cb.send(Err(unix_sockets_unsupported()));
+                        continue;
+                    }
+                };
 
                 tunnels.insert(
                     id,
@@ -240,9 +407,12 @@ async fn tunnel_task(tx: mpsc::Sender<InnerTunnelMsg>, mut rx: mpsc::Receiver<In
                             direction: TunnelDirection::Forward,
                             host,
                             port,
+                            protocol,
+                            stats: TunnelStats::default(),
                         },
                         write_tx,
                         task,
+                        counters,
                     },
                 );
 
@@ -252,37 +422,83 @@ async fn tunnel_task(tx: mpsc::Sender<InnerTunnelMsg>, mut rx: mpsc::Receiver<In
             InnerTunnelMsg::Listen {
                 host,
                 port,
+                protocol,
                 reply,
                 cb,
             } => {
-                let listener = match TcpListener::bind((&*host, port)).await {
-                    Ok(l) => l,
+                let listener_id = next_id.fetch_add(1, Ordering::Relaxed);
+                let tx_clone = tx.clone();
+                let next_id_clone = Arc::clone(&next_id);
+                let counters = TunnelCounters::new();
+                let counters_clone = Arc::clone(&counters);
+
+                let binds = match protocol {
+                    TunnelProtocol::Unix if !config.unix_sockets => Err(policy_denied(
+                        format_args!("listening on Unix socket {host}"),
+                    )),
+                    TunnelProtocol::Unix => Ok(Vec::new()),
+                    _ => resolve_binds(&config, &host, port).await,
+                };
+                let binds = match binds {
+                    Ok(binds) => binds,
                     Err(e) => {
                         let _ = cb.send(Err(e));
                         continue;
                     }
                 };
 
-                let actual_port = match listener.local_addr() {
-                    Ok(addr) => addr.port(),
+                let result = match protocol {
+                    TunnelProtocol::Tcp => TcpListener::bind(&*binds).await.and_then(|listener| {
+                        let addr = listener.local_addr()?;
+                        check_bound(&config, addr)?;
+                        let task = tokio::spawn(listener_task(
+                            listener_id,
+                            listener,
+                            reply,
+                            tx_clone,
+                            next_id_clone,
+                            counters_clone,
+                        ));
+                        Ok((task, addr.port()))
+                    }),
+                    TunnelProtocol::Udp => UdpSocket::bind(&*binds).await.and_then(|socket| {
+                        let addr = socket.local_addr()?;
+                        check_bound(&config, addr)?;
+                        let task = tokio::spawn(udp_listener_task(
+                            listener_id,
+                            socket,
+                            reply,
+                            tx_clone,
+                            next_id_clone,
+                            counters_clone,
+                        ));
+                        Ok((task, addr.port()))
+                    }),
+                    #[cfg(unix)]
+                    TunnelProtocol::Unix => tokio::net::UnixListener::bind(&host).map(|listener| {
+                        let task = tokio::spawn(unix_listener_task(
+                            listener_id,
+                            listener,
+                            UnixSocketFile(host.clone().into()),
+                            reply,
+                            tx_clone,
+                            next_id_clone,
+                            counters_clone,
+                        ));
+                        (task, 0)
+                    }),
+                    #[cfg(not(unix))]
+                    TunnelProtocol::Unix => Err(unix_sockets_unsupported()),
+                };
+
+                let (task, actual_port) = match result {
+                    Ok(x) => x,
                     Err(e) => {
                         let _ = cb.send(Err(e));
                         continue;
                     }
                 };
 
-                let listener_id = next_id.fetch_add(1, Ordering::Relaxed);
-                let tx_clone = tx.clone();
-                let next_id_clone = Arc::clone(&next_id);
-
-                let task = tokio::spawn(listener_task(
-                    listener_id,
-                    listener,
-                    reply,
-                    tx_clone,
-                    next_id_clone,
-                ));
-
                 tunnels.insert(
                     listener_id,
                     TunnelEntry::Listener {
@@ -291,9 +507,12 @@ async fn tunnel_task(tx: mpsc::Sender<InnerTunnelMsg>, mut rx: mpsc::Receiver<In
                             direction: TunnelDirection::Reverse,
                             host,
                             port: actual_port,
+                            protocol,
+                            stats: TunnelStats::default(),
                         },
                         sub_tunnel_ids: Vec::new(),
                         task,
+                        counters,
                     },
                 );
 
@@ -319,13 +538,7 @@ async fn tunnel_task(tx: mpsc::Sender<InnerTunnelMsg>, mut rx: mpsc::Receiver<In
             }
 
             InnerTunnelMsg::List { cb } => {
-                let list: Vec<TunnelInfo> = tunnels
-                    .values()
-                    .map(|entry| match entry {
-                        TunnelEntry::Connection { info, .. } => info.clone(),
-                        TunnelEntry::Listener { info, .. } => info.clone(),
-                    })
-                    .collect();
+                let list: Vec<TunnelInfo> = tunnels.values().map(TunnelEntry::to_info).collect();
                 let _ = cb.send(Ok(list));
             }
 
@@ -351,7 +564,14 @@ async fn tunnel_task(tx: mpsc::Sender<InnerTunnelMsg>, mut rx: mpsc::Receiver<In
                 port,
                 write_tx,
                 task,
+                counters,
             } => {
+                // Sub-tunnels carry the same protocol as the listener that accepted them
+                let protocol = match tunnels.get(&listener_id) {
+                    Some(TunnelEntry::Listener { info, .. }) => info.protocol,
+                    _ => TunnelProtocol::Tcp,
+                };
+
                 // Register the sub-tunnel as a Connection entry
                 tunnels.insert(
                     tunnel_id,
@@ -361,9 +581,12 @@ async fn tunnel_task(tx: mpsc::Sender<InnerTunnelMsg>, mut rx: mpsc::Receiver<In
                             direction: TunnelDirection::Reverse,
                             host,
                             port,
+                            protocol,
+                            stats: TunnelStats::default(),
                         },
                         write_tx,
                         task,
+                        counters,
                     },
                 );
 
@@ -378,6 +601,49 @@ async fn tunnel_task(tx: mpsc::Sender<InnerTunnelMsg>, mut rx: mpsc::Receiver<In
     }
 }
 
+/// Records the traffic relayed by the server's end of a tunnel in the tunnel's counters.
+///
+/// The server's end of a forward tunnel is connected to the target, so what is read from it
+/// is received from the target, whereas the server's end of a reverse tunnel is a peer that
+/// connected to the listener, so what is read from it is sent toward the target.
+#[derive(Clone)]
+struct Traffic {
+    counters: Arc<TunnelCounters>,
+    is_target: bool,
+}
+
+impl Traffic {
+    fn new(counters: &Arc<TunnelCounters>, direction: TunnelDirection) -> Self {
+        Self {
+            counters: Arc::clone(counters),
+            is_target: direction == TunnelDirection::Forward,
+        }
+    }
+
+    /// Counts a connection through the tunnel as active until the guard is dropped.
+    fn connection(&self) -> TunnelConnectionGuard {
+        self.counters.connection()
+    }
+
+    /// Records `n` bytes read from the server's end of the tunnel.
+    fn read(&self, n: usize) {
+        if self.is_target {
+            self.counters.record_in(n);
+        } else {
+            self.counters.record_out(n);
+        }
+    }
+
+    /// Records `n` bytes written to the server's end of the tunnel.
+    fn written(&self, n: usize) {
+        if self.is_target {
+            self.counters.record_out(n);
+        } else {
+            self.counters.record_in(n);
+        }
+    }
+}
+
 /// Closes a tunnel or listener, signaling graceful shutdown and removing it from
 /// the map. If the target is a listener, all sub-tunnels are also closed.
 ///
@@ -414,27 +680,34 @@ fn close_tunnel(id: TunnelId, tunnels: &mut HashMap<TunnelId, TunnelEntry>) -> i
     }
 }
 
-/// Manages the I/O for a single TCP connection (forward or sub-tunnel).
+/// Manages the I/O for a single stream connection (forward or sub-tunnel), which is either a
+/// TCP stream or a Unix socket stream.
 ///
-/// Reads from the TCP stream and sends `TunnelData` responses via the reply channel.
-/// Writes data received on `write_rx` to the TCP stream. Uses `tokio::select!` to
+/// Reads from the stream and sends `TunnelData` responses via the reply channel.
+/// Writes data received on `write_rx` to the stream. Uses `tokio::select!` to
 /// exit when either the read side closes or the write channel is dropped (via
 /// `close_tunnel`). Sends `TunnelClosed` and an `InternalRemove` message when the
 /// connection ends.
-async fn connection_task(
+async fn connection_task<S>(
     id: TunnelId,
-    stream: TcpStream,
+    traffic: Traffic,
+    stream: S,
     reply: Box<dyn Reply<Data = Response>>,
     mut write_rx: mpsc::Receiver<Vec<u8>>,
     tx: mpsc::Sender<InnerTunnelMsg>,
-) {
-    let (mut read_half, mut write_half) = stream.into_split();
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let _connection = traffic.connection();
+    let (mut read_half, mut write_half) = tokio::io::split(stream);
 
+    let write_traffic = traffic.clone();
     let mut write_task = tokio::spawn(async move {
         while let Some(data) = write_rx.recv().await {
             if write_half.write_all(&data).await.is_err() {
                 break;
             }
+            write_traffic.written(data.len());
         }
     });
 
@@ -445,6 +718,7 @@ async fn connection_task(
                 match read_half.read(&mut buf).await {
                     Ok(0) => break,
                     Ok(n) => {
+                        traffic.read(n);
                         let data = buf[..n].to_vec();
                         if reply.send(Response::TunnelData { id, data }).is_err() {
                             break;
@@ -461,7 +735,7 @@ async fn connection_task(
         }
         _ = &mut write_task => {
             // Write channel closed (close_tunnel dropped write_tx).
-            // read_half will be dropped, closing the TCP read side.
+            // read_half will be dropped, closing the read side of the stream.
         }
     }
 
@@ -479,6 +753,7 @@ async fn listener_task(
     reply: Box<dyn Reply<Data = Response>>,
     tx: mpsc::Sender<InnerTunnelMsg>,
     next_id: Arc<AtomicU32>,
+    counters: Arc<TunnelCounters>,
 ) {
     loop {
         match listener.accept().await {
@@ -502,9 +777,11 @@ async fn listener_task(
                 let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(1024);
                 let sub_reply = reply.clone_reply();
                 let tx_clone = tx.clone();
+                let sub_counters = TunnelCounters::child(&counters);
+                let traffic = Traffic::new(&sub_counters, TunnelDirection::Reverse);
 
                 let task = tokio::spawn(connection_task(
-                    tunnel_id, stream, sub_reply, write_rx, tx_clone,
+                    tunnel_id, traffic, stream, sub_reply, write_rx, tx_clone,
                 ));
 
                 // Register the sub-tunnel in the main actor's map
@@ -516,6 +793,7 @@ async fn listener_task(
                         port,
                         write_tx,
                         task,
+                        counters: sub_counters,
                     })
                     .await;
             }
@@ -531,3 +809,375 @@ async fn listener_task(
         .send(InnerTunnelMsg::InternalRemove { id: listener_id })
         .await;
 }
+
+/// Accepts incoming connections on a `UnixListener` and spawns sub-tunnel tasks for each.
+///
+/// Behaves like [`listener_task`], except that sub-tunnels report the listener's socket path
+/// as their host because Unix socket clients are typically unnamed. The socket file is
+/// removed when the task ends or is aborted by `close_tunnel`.
+#[cfg(unix)]
+async fn unix_listener_task(
+    listener_id: TunnelId,
+    listener: tokio::net::UnixListener,
+    socket_file: UnixSocketFile,
+    reply: Box<dyn Reply<Data = Response>>,
+    tx: mpsc::Sender<InnerTunnelMsg>,
+    next_id: Arc<AtomicU32>,
+    counters: Arc<TunnelCounters>,
+) {
+    let path = socket_file.0.to_string_lossy().into_owned();
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer_addr)) => {
+                let tunnel_id = next_id.fetch_add(1, Ordering::Relaxed);
+                let peer_str = peer_addr
+                    .as_pathname()
+                    .map(|p| p.to_string_lossy().into_owned());
+
+                if reply
+                    .send(Response::TunnelIncoming {
+                        listener_id,
+                        tunnel_id,
+                        peer_addr: peer_str,
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+
+                let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(1024);
+                let sub_reply = reply.clone_reply();
+                let tx_clone = tx.clone();
+                let sub_counters = TunnelCounters::child(&counters);
+                let traffic = Traffic::new(&sub_counters, TunnelDirection::Reverse);
+
+                let task = tokio::spawn(connection_task(
+                    tunnel_id, traffic, stream, sub_reply, write_rx, tx_clone,
+                ));
+
+                // Register the sub-tunnel in the main actor's map
+                let _ = tx
+                    .send(InnerTunnelMsg::InternalRegisterSubTunnel {
+                        listener_id,
+                        tunnel_id,
+                        host: path.clone(),
+                        port: 0,
+                        write_tx,
+                        task,
+                        counters: sub_counters,
+                    })
+                    .await;
+            }
+            Err(e) => {
+                debug!("[Tunnel {listener_id}] Accept error: {e}");
+                break;
+            }
+        }
+    }
+
+    let _ = reply.send(Response::TunnelClosed { id: listener_id });
+    let _ = tx
+        .send(InnerTunnelMsg::InternalRemove { id: listener_id })
+        .await;
+}
+
+/// Path of a socket file created by a Unix socket listener, which is removed when dropped.
+#[cfg(unix)]
+struct UnixSocketFile(std::path::PathBuf);
+
+#[cfg(unix)]
+impl Drop for UnixSocketFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.0) {
+            debug!("Failed to remove socket file {:?}: {e}", self.0);
+        }
+    }
+}
+
+/// Returns the error reported for Unix socket tunnels on platforms without Unix sockets.
+#[cfg(not(unix))]
+fn unix_sockets_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix socket tunnels are not supported on this platform",
+    )
+}
+
+/// Returns the error for something that the server's tunnel policy does not allow, logging it
+/// so that denials can be audited on the server.
+fn policy_denied(what: impl fmt::Display) -> io::Error {
+    warn!("Denied {what} by tunnel policy");
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("The server's tunnel policy does not allow {what}"),
+    )
+}
+
+/// Resolves `host:port`, failing if no addresses are found.
+async fn resolve(host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await?.collect();
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No addresses found for {host}:{port}"),
+        ));
+    }
+
+    Ok(addrs)
+}
+
+/// Resolves `host:port` into the addresses that forward tunnels may connect to, failing if
+/// the tunnel policy allows none of them.
+///
+/// Connecting to the returned addresses rather than resolving `host` again ensures that the
+/// addresses checked are the ones connected to.
+async fn resolve_targets(
+    config: &TunnelConfig,
+    host: &str,
+    port: u16,
+) -> io::Result<Vec<SocketAddr>> {
+    let targets: Vec<SocketAddr> = resolve(host, port)
+        .await?
+        .into_iter()
+        .filter(|addr| config.allows_target(addr))
+        .collect();
+
+    if targets.is_empty() {
+        return Err(policy_denied(format_args!("tunnel to {host}:{port}")));
+    }
+
+    Ok(targets)
+}
+
+/// Resolves `host:port` into the addresses that reverse tunnels may listen on, failing if the
+/// tunnel policy allows none of them.
+///
+/// When `port` is 0, only the addresses are checked here, as the port is not known until the
+/// listener is bound and checked by [`check_bound`].
+async fn resolve_binds(
+    config: &TunnelConfig,
+    host: &str,
+    port: u16,
+) -> io::Result<Vec<SocketAddr>> {
+    let binds: Vec<SocketAddr> = resolve(host, port)
+        .await?
+        .into_iter()
+        .filter(|addr| {
+            config.allows_bind_addr(&addr.ip()) && (port == 0 || config.allows_bind_port(port))
+        })
+        .collect();
+
+    if binds.is_empty() {
+        return Err(policy_denied(format_args!("listening on {host}:{port}")));
+    }
+
+    Ok(binds)
+}
+
+/// Fails if the tunnel policy does not allow listening on `addr`, the address that a listener
+/// was actually bound to.
+fn check_bound(config: &TunnelConfig, addr: SocketAddr) -> io::Result<()> {
+    if config.allows_bind(&addr) {
+        Ok(())
+    } else {
+        Err(policy_denied(format_args!("listening on {addr}")))
+    }
+}
+
+/// Returns a UDP socket bound to an ephemeral port and connected to the first of `targets`,
+/// so that only datagrams from that address are received.
+async fn connect_udp(targets: &[SocketAddr]) -> io::Result<UdpSocket> {
+    let addr = *targets
+        .first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No addresses to connect to"))?;
+
+    distant_core::net::common::connect_udp(addr).await
+}
+
+/// Manages the I/O for a single forward UDP tunnel.
+///
+/// Each datagram received on the connected socket is sent as its own `TunnelData` response,
+/// and each chunk received on `write_rx` is sent as a single datagram. The tunnel ends when
+/// the write channel is dropped (via `close_tunnel`) or no datagrams have passed in either
+/// direction for [`UDP_TUNNEL_IDLE_TIMEOUT`]. Sends `TunnelClosed` and an `InternalRemove`
+/// message when the tunnel ends.
+async fn udp_connection_task(
+    id: TunnelId,
+    traffic: Traffic,
+    socket: UdpSocket,
+    reply: Box<dyn Reply<Data = Response>>,
+    mut write_rx: mpsc::Receiver<Vec<u8>>,
+    tx: mpsc::Sender<InnerTunnelMsg>,
+) {
+    let _connection = traffic.connection();
+    let mut buf = vec![0u8; UDP_TUNNEL_DATAGRAM_BUFFER_SIZE];
+    let idle = time::sleep(UDP_TUNNEL_IDLE_TIMEOUT);
+    tokio::pin!(idle);
+
+    loop {
+        tokio::select! {
+            result = socket.recv(&mut buf) => match result {
+                Ok(n) => {
+                    traffic.read(n);
+                    let data = buf[..n].to_vec();
+                    if reply.send(Response::TunnelData { id, data }).is_err() {
+                        break;
+                    }
+                }
+
+                // An earlier datagram was rejected by the target (ICMP port unreachable),
+                // which is not fatal for a connectionless protocol
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {}
+                Err(e) => {
+                    debug!("[Tunnel {id}] Receive error: {e}");
+                    break;
+                }
+            },
+            data = write_rx.recv() => match data {
+                Some(data) => {
+                    if let Err(e) = socket.send(&data).await
+                        && e.kind() != io::ErrorKind::ConnectionRefused
+                    {
+                        debug!("[Tunnel {id}] Send error: {e}");
+                        break;
+                    }
+                    traffic.written(data.len());
+                }
+                None => break,
+            },
+            _ = &mut idle => {
+                debug!("[Tunnel {id}] Closing idle UDP tunnel");
+                break;
+            }
+        }
+
+        idle.as_mut()
+            .reset(Instant::now() + UDP_TUNNEL_IDLE_TIMEOUT);
+    }
+
+    let _ = reply.send(Response::TunnelClosed { id });
+    let _ = tx.send(InnerTunnelMsg::InternalRemove { id }).await;
+}
+
+/// Receives datagrams on a `UdpSocket` and associates each distinct peer with a sub-tunnel.
+///
+/// The first datagram from a peer registers a new sub-tunnel via `InternalRegisterSubTunnel`
+/// and sends a `TunnelIncoming` notification through the reply channel. Later datagrams from
+/// the same peer are routed to that sub-tunnel until it closes from being idle, after which
+/// the next datagram from the peer starts a new association.
+async fn udp_listener_task(
+    listener_id: TunnelId,
+    socket: UdpSocket,
+    reply: Box<dyn Reply<Data = Response>>,
+    tx: mpsc::Sender<InnerTunnelMsg>,
+    next_id: Arc<AtomicU32>,
+    counters: Arc<TunnelCounters>,
+) {
+    let mut demux = UdpDemux::new(socket);
+
+    loop {
+        let peer = match demux.accept().await {
+            Ok(peer) => peer,
+            Err(e) => {
+                debug!("[Tunnel {listener_id}] Receive error: {e}");
+                break;
+            }
+        };
+        let peer_addr = peer.addr();
+
+        let tunnel_id = next_id.fetch_add(1, Ordering::Relaxed);
+        if reply
+            .send(Response::TunnelIncoming {
+                listener_id,
+                tunnel_id,
+                peer_addr: Some(peer_addr.to_string()),
+            })
+            .is_err()
+        {
+            break;
+        }
+
+        let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(TUNNEL_CHANNEL_CAPACITY);
+        let sub_counters = TunnelCounters::child(&counters);
+
+        let task = tokio::spawn(udp_peer_task(
+            tunnel_id,
+            Traffic::new(&sub_counters, TunnelDirection::Reverse),
+            peer,
+            reply.clone_reply(),
+            write_rx,
+            tx.clone(),
+        ));
+
+        // Register the sub-tunnel in the main actor's map
+        let _ = tx
+            .send(InnerTunnelMsg::InternalRegisterSubTunnel {
+                listener_id,
+                tunnel_id,
+                host: peer_addr.ip().to_string(),
+                port: peer_addr.port(),
+                write_tx,
+                task,
+                counters: sub_counters,
+            })
+            .await;
+    }
+
+    let _ = reply.send(Response::TunnelClosed { id: listener_id });
+    let _ = tx
+        .send(InnerTunnelMsg::InternalRemove { id: listener_id })
+        .await;
+}
+
+/// Manages the I/O for a single peer of a reverse UDP tunnel listener.
+///
+/// Datagrams from the peer are sent as `TunnelData` responses, and chunks received on
+/// `write_rx` are sent back to the peer from the listener's socket as single datagrams. The
+/// association ends when the write channel is dropped (via `close_tunnel`), the listener
+/// stops, or the peer is idle for [`UDP_TUNNEL_IDLE_TIMEOUT`]. Sends `TunnelClosed` and an
+/// `InternalRemove` message when the association ends.
+async fn udp_peer_task(
+    id: TunnelId,
+    traffic: Traffic,
+    peer: UdpPeer,
+    reply: Box<dyn Reply<Data = Response>>,
+    write_rx: mpsc::Receiver<Vec<u8>>,
+    tx: mpsc::Sender<InnerTunnelMsg>,
+) {
+    let _connection = traffic.connection();
+    peer.relay(ReplyUdpTarget {
+        id,
+        traffic: &traffic,
+        reply: &*reply,
+        write_rx,
+    })
+    .await;
+
+    let _ = reply.send(Response::TunnelClosed { id });
+    let _ = tx.send(InnerTunnelMsg::InternalRemove { id }).await;
+}
+
+/// Sends the datagrams of a peer of a reverse UDP tunnel listener as `TunnelData` responses,
+/// replying to the peer with the chunks written to the tunnel
+struct ReplyUdpTarget<'a> {
+    id: TunnelId,
+    traffic: &'a Traffic,
+    reply: &'a dyn Reply<Data = Response>,
+    write_rx: mpsc::Receiver<Vec<u8>>,
+}
+
+impl UdpTarget for ReplyUdpTarget<'_> {
+    async fn forward(&mut self, data: Vec<u8>) -> io::Result<()> {
+        self.traffic.read(data.len());
+        self.reply.send(Response::TunnelData { id: self.id, data })
+    }
+
+    async fn reply(&mut self) -> Option<Vec<u8>> {
+        self.write_rx.recv().await
+    }
+
+    fn replied(&mut self, n: usize) {
+        self.traffic.written(n);
+    }
+}
@@ -1,10 +1,89 @@
+use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 
+use distant_core::net::common::{IpCidr, PortRange};
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Config {
+    pub tunnel: TunnelConfig,
     pub watch: WatchConfig,
 }
 
+/// Configuration limiting where the tunnels opened by clients can connect to and listen on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TunnelConfig {
+    /// Networks that forward tunnels may connect to; an empty list allows every address
+    pub allow_targets: Vec<IpCidr>,
+
+    /// Networks that forward tunnels never connect to, taking precedence over `allow_targets`
+    pub deny_targets: Vec<IpCidr>,
+
+    /// Ports that forward tunnels may connect to; an empty list allows every port
+    pub allow_target_ports: Vec<PortRange>,
+
+    /// Ports that forward tunnels never connect to, taking precedence over `allow_target_ports`
+    pub deny_target_ports: Vec<PortRange>,
+
+    /// Addresses that reverse tunnels may listen on; an empty list allows every address
+    pub allow_bind_addrs: Vec<IpCidr>,
+
+    /// Ports that reverse tunnels may listen on; an empty list allows every port
+    pub allow_bind_ports: Vec<PortRange>,
+
+    /// Whether tunnels may connect to or listen on Unix domain sockets, which are not covered
+    /// by any of the address and port lists
+    pub unix_sockets: bool,
+}
+
+impl TunnelConfig {
+    /// Returns true if forward tunnels may connect to `addr`.
+    pub fn allows_target(&self, addr: &SocketAddr) -> bool {
+        let ip = addr.ip();
+        let allowed = self.allow_targets.is_empty()
+            || self.allow_targets.iter().any(|cidr| cidr.contains(&ip));
+        let denied = self.deny_targets.iter().any(|cidr| cidr.contains(&ip));
+        let port_allowed = self.allow_target_ports.is_empty()
+            || self
+                .allow_target_ports
+                .iter()
+                .any(|r| r.contains(addr.port()));
+        let port_denied = self
+            .deny_target_ports
+            .iter()
+            .any(|r| r.contains(addr.port()));
+        allowed && !denied && port_allowed && !port_denied
+    }
+
+    /// Returns true if reverse tunnels may listen on `addr`.
+    pub fn allows_bind(&self, addr: &SocketAddr) -> bool {
+        self.allows_bind_addr(&addr.ip()) && self.allows_bind_port(addr.port())
+    }
+
+    /// Returns true if reverse tunnels may listen on the address `ip`, regardless of port.
+    pub fn allows_bind_addr(&self, ip: &IpAddr) -> bool {
+        self.allow_bind_addrs.is_empty() || self.allow_bind_addrs.iter().any(|c| c.contains(ip))
+    }
+
+    /// Returns true if reverse tunnels may listen on `port`, regardless of address.
+    pub fn allows_bind_port(&self, port: u16) -> bool {
+        self.allow_bind_ports.is_empty() || self.allow_bind_ports.iter().any(|r| r.contains(port))
+    }
+}
+
+impl Default for TunnelConfig {
+    fn default() -> Self {
+        Self {
+            allow_targets: Vec::new(),
+            deny_targets: Vec::new(),
+            allow_target_ports: Vec::new(),
+            deny_target_ports: Vec::new(),
+            allow_bind_addrs: Vec::new(),
+            allow_bind_ports: Vec::new(),
+            unix_sockets: true,
+        }
+    }
+}
+
 /// Configuration specifically for watching files and directories.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct WatchConfig {
@@ -29,8 +108,8 @@ impl Default for WatchConfig {
 
 #[cfg(test)]
 mod tests {
-    //! Tests for `Config` and `WatchConfig` default values, equality, clone,
-    //! inequality when fields differ, and custom value round-trip.
+    //! Tests for `Config`, `TunnelConfig`, and `WatchConfig` default values, equality, clone,
+    //! inequality when fields differ, custom value round-trip, and tunnel policy checks.
 
     use super::*;
 
@@ -43,6 +122,12 @@ mod tests {
             assert_eq!(config.watch, WatchConfig::default());
         }
 
+        #[test]
+        fn has_default_tunnel_config() {
+            let config = Config::default();
+            assert_eq!(config.tunnel, TunnelConfig::default());
+        }
+
         #[test]
         fn equality() {
             let a = Config::default();
@@ -58,6 +143,69 @@ mod tests {
         }
     }
 
+    mod tunnel_config {
+        use super::*;
+
+        fn addr(s: &str) -> SocketAddr {
+            s.parse().unwrap()
+        }
+
+        fn cidr(s: &str) -> IpCidr {
+            s.parse().unwrap()
+        }
+
+        #[test]
+        fn default_allows_everything() {
+            let config = TunnelConfig::default();
+            assert!(config.allows_target(&addr("10.0.0.1:22")));
+            assert!(config.allows_target(&addr("[::1]:5432")));
+            assert!(config.allows_bind(&addr("0.0.0.0:80")));
+            assert!(config.unix_sockets);
+        }
+
+        #[test]
+        fn allows_target_should_only_permit_allowed_networks_when_not_empty() {
+            let config = TunnelConfig {
+                allow_targets: vec![cidr("10.0.0.0/8"), cidr("127.0.0.1")],
+                ..Default::default()
+            };
+            assert!(config.allows_target(&addr("10.1.2.3:80")));
+            assert!(config.allows_target(&addr("127.0.0.1:80")));
+            assert!(!config.allows_target(&addr("127.0.0.2:80")));
+            assert!(!config.allows_target(&addr("192.168.1.1:80")));
+        }
+
+        #[test]
+        fn allows_target_should_prefer_deny_lists_over_allow_lists() {
+            let config = TunnelConfig {
+                allow_targets: vec![cidr("10.0.0.0/8")],
+                deny_targets: vec![cidr("10.0.0.0/24")],
+                allow_target_ports: vec![PortRange::from(5000..=5999)],
+                deny_target_ports: vec![PortRange::single(5432)],
+                ..Default::default()
+            };
+            assert!(config.allows_target(&addr("10.1.0.1:5000")));
+            assert!(!config.allows_target(&addr("10.0.0.1:5000")));
+            assert!(!config.allows_target(&addr("10.1.0.1:5432")));
+            assert!(!config.allows_target(&addr("10.1.0.1:22")));
+        }
+
+        #[test]
+        fn allows_bind_should_check_both_address_and_port() {
+            let config = TunnelConfig {
+                allow_bind_addrs: vec![cidr("127.0.0.1"), cidr("::1")],
+                allow_bind_ports: vec![PortRange::from(8000..=8999)],
+                ..Default::default()
+            };
+            assert!(config.allows_bind(&addr("127.0.0.1:8080")));
+            assert!(config.allows_bind(&addr("[::1]:8999")));
+            assert!(!config.allows_bind(&addr("0.0.0.0:8080")));
+            assert!(!config.allows_bind(&addr("127.0.0.1:22")));
+            assert!(config.allows_bind_addr(&"127.0.0.1".parse().unwrap()));
+            assert!(!config.allows_bind_port(0));
+        }
+    }
+
     mod watch_config_default {
         use super::*;
 
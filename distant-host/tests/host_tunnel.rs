@@ -1,10 +1,12 @@
 use std::io;
 
 use distant_core::ChannelExt;
+use distant_core::net::common::PortRange;
+use distant_host::{Config, TunnelConfig};
 use rstest::*;
 use test_log::test;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
 use tokio::time;
 
 use distant_test_harness::host::*;
@@ -71,6 +73,54 @@ async fn tunnel_open_should_send_and_receive_data_then_auto_close_on_remote_end(
     assert_eq!(info.family, std::env::consts::FAMILY);
 }
 
+#[rstest]
+#[test(tokio::test)]
+async fn status_should_report_traffic_through_forward_tunnel(#[future] ctx: ClientCtx) {
+    let mut ctx = ctx.await;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target_port = listener.local_addr().unwrap().port();
+
+    let mut tunnel = ctx
+        .client
+        .tunnel_open("127.0.0.1", target_port)
+        .await
+        .unwrap();
+    let mut writer = tunnel.writer.take().unwrap();
+    let mut reader = tunnel.reader.take().unwrap();
+    let (mut stream, _) = listener.accept().await.unwrap();
+
+    writer.write(b"ping".to_vec()).await.unwrap();
+    let mut buf = vec![0u8; 256];
+    let n = stream.read(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"ping");
+
+    stream.write_all(b"pong!").await.unwrap();
+    assert_eq!(reader.read().await.unwrap(), b"pong!".as_slice());
+
+    // Bytes written to the target are counted once the write completes, which can be just
+    // after the target has read them
+    let deadline = time::Instant::now() + time::Duration::from_secs(10);
+    let stats = loop {
+        let status = ctx.client.status().await.unwrap();
+        let info = status
+            .tunnels
+            .into_iter()
+            .find(|t| t.port == target_port)
+            .expect("Tunnel missing from status");
+        if info.stats.bytes_out == 4 || time::Instant::now() >= deadline {
+            break info.stats;
+        }
+        time::sleep(time::Duration::from_millis(10)).await;
+    };
+
+    assert_eq!(stats.bytes_out, 4);
+    assert_eq!(stats.bytes_in, 5);
+    assert_eq!(stats.active_connections, 1);
+    assert_eq!(stats.total_connections, 1);
+    assert!(stats.last_activity.is_some());
+}
+
 #[rstest]
 #[test(tokio::test)]
 async fn tunnel_open_should_fail_if_port_not_listening(#[future] ctx: ClientCtx) {
@@ -314,3 +364,339 @@ async fn tunnel_listen_should_fail_if_port_already_in_use(#[future] ctx: ClientC
         "Expected address-in-use error, got: {err_msg}",
     );
 }
+
+#[rstest]
+#[test(tokio::test)]
+async fn udp_tunnel_open_should_preserve_datagram_boundaries(#[future] ctx: ClientCtx) {
+    let mut ctx = ctx.await;
+    let timeout_dur = time::Duration::from_secs(10);
+
+    let target = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target_port = target.local_addr().unwrap().port();
+
+    let mut tunnel = ctx
+        .client
+        .udp_tunnel_open("127.0.0.1", target_port)
+        .await
+        .unwrap();
+    let mut writer = tunnel.writer.take().unwrap();
+    let mut reader = tunnel.reader.take().unwrap();
+
+    // Each write arrives as its own datagram rather than being coalesced
+    writer.write(b"first".to_vec()).await.unwrap();
+    writer.write(b"second".to_vec()).await.unwrap();
+
+    let mut buf = vec![0u8; 256];
+    let (n, peer_addr) = time::timeout(timeout_dur, target.recv_from(&mut buf))
+        .await
+        .expect("Timed out waiting for first datagram")
+        .unwrap();
+    assert_eq!(&buf[..n], b"first");
+
+    let (n, _) = time::timeout(timeout_dur, target.recv_from(&mut buf))
+        .await
+        .expect("Timed out waiting for second datagram")
+        .unwrap();
+    assert_eq!(&buf[..n], b"second");
+
+    // Each datagram sent back arrives as its own read
+    target.send_to(b"one", peer_addr).await.unwrap();
+    target.send_to(b"two", peer_addr).await.unwrap();
+
+    let data = time::timeout(timeout_dur, reader.read())
+        .await
+        .expect("Timed out reading first reply")
+        .unwrap();
+    assert_eq!(data, b"one");
+
+    let data = time::timeout(timeout_dur, reader.read())
+        .await
+        .expect("Timed out reading second reply")
+        .unwrap();
+    assert_eq!(data, b"two");
+
+    tunnel.close().await.unwrap();
+    drop(writer);
+    tunnel.wait().await;
+
+    // Session must still be alive after tunnel closes
+    let info = ctx.client.system_info().await.unwrap();
+    assert_eq!(info.family, std::env::consts::FAMILY);
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn udp_tunnel_listen_should_associate_each_peer_separately(#[future] ctx: ClientCtx) {
+    let mut ctx = ctx.await;
+    let timeout_dur = time::Duration::from_secs(10);
+
+    let mut listener = ctx.client.udp_tunnel_listen("127.0.0.1", 0).await.unwrap();
+    let port = listener.port();
+
+    let peer_a = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let peer_b = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    peer_a.connect(("127.0.0.1", port)).await.unwrap();
+    peer_b.connect(("127.0.0.1", port)).await.unwrap();
+
+    peer_a.send(b"from a").await.unwrap();
+    let mut incoming_a = time::timeout(timeout_dur, listener.next())
+        .await
+        .expect("Timed out waiting for first peer")
+        .expect("Listener closed before first peer");
+    assert_eq!(
+        incoming_a.peer_addr.as_deref(),
+        Some(peer_a.local_addr().unwrap().to_string().as_str())
+    );
+
+    peer_b.send(b"from b").await.unwrap();
+    let mut incoming_b = time::timeout(timeout_dur, listener.next())
+        .await
+        .expect("Timed out waiting for second peer")
+        .expect("Listener closed before second peer");
+    assert_ne!(incoming_a.tunnel_id, incoming_b.tunnel_id);
+
+    let data = time::timeout(timeout_dur, incoming_a.reader.read())
+        .await
+        .expect("Timed out reading from first peer")
+        .unwrap();
+    assert_eq!(data, b"from a");
+
+    let data = time::timeout(timeout_dur, incoming_b.reader.read())
+        .await
+        .expect("Timed out reading from second peer")
+        .unwrap();
+    assert_eq!(data, b"from b");
+
+    // A later datagram from a known peer stays within its association
+    peer_a.send(b"again from a").await.unwrap();
+    let data = time::timeout(timeout_dur, incoming_a.reader.read())
+        .await
+        .expect("Timed out reading second datagram from first peer")
+        .unwrap();
+    assert_eq!(data, b"again from a");
+
+    // Replies go back only to the peer that owns the association
+    incoming_b.writer.write(b"to b".to_vec()).await.unwrap();
+    incoming_a.writer.write(b"to a".to_vec()).await.unwrap();
+
+    let mut buf = vec![0u8; 256];
+    let n = time::timeout(timeout_dur, peer_a.recv(&mut buf))
+        .await
+        .expect("Timed out waiting for reply to first peer")
+        .unwrap();
+    assert_eq!(&buf[..n], b"to a");
+
+    let n = time::timeout(timeout_dur, peer_b.recv(&mut buf))
+        .await
+        .expect("Timed out waiting for reply to second peer")
+        .unwrap();
+    assert_eq!(&buf[..n], b"to b");
+
+    listener.close().await.unwrap();
+
+    // Session must still be alive after listener closes
+    let info = ctx.client.system_info().await.unwrap();
+    assert_eq!(info.family, std::env::consts::FAMILY);
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn unix_tunnel_open_should_relay_data_to_socket(#[future] ctx: ClientCtx) {
+    let mut ctx = ctx.await;
+    let timeout_dur = time::Duration::from_secs(10);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("target.sock");
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+    let mut tunnel = ctx
+        .client
+        .unix_tunnel_open(path.to_string_lossy())
+        .await
+        .unwrap();
+    let mut writer = tunnel.writer.take().unwrap();
+    let mut reader = tunnel.reader.take().unwrap();
+
+    let (mut stream, _) = time::timeout(timeout_dur, listener.accept())
+        .await
+        .expect("Timed out waiting for connection to socket")
+        .unwrap();
+
+    writer.write(b"Hello from client".to_vec()).await.unwrap();
+
+    let mut buf = vec![0u8; 256];
+    let n = stream.read(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"Hello from client");
+
+    stream.write_all(b"Hello from socket").await.unwrap();
+
+    let data = time::timeout(timeout_dur, reader.read())
+        .await
+        .expect("Timed out reading from tunnel reader")
+        .unwrap();
+    assert_eq!(data, b"Hello from socket".as_slice());
+
+    drop(writer);
+    tunnel.close().await.unwrap();
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn unix_tunnel_listen_should_relay_data_and_remove_socket_when_closed(
+    #[future] ctx: ClientCtx,
+) {
+    let mut ctx = ctx.await;
+    let timeout_dur = time::Duration::from_secs(10);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("listen.sock");
+
+    let mut listener = ctx
+        .client
+        .unix_tunnel_listen(path.to_string_lossy())
+        .await
+        .unwrap();
+    assert_eq!(listener.port(), 0);
+
+    let mut stream = time::timeout(timeout_dur, tokio::net::UnixStream::connect(&path))
+        .await
+        .expect("Timed out connecting to listener")
+        .unwrap();
+
+    let incoming = time::timeout(timeout_dur, listener.next())
+        .await
+        .expect("Timed out waiting for incoming tunnel")
+        .expect("Listener closed before receiving incoming tunnel");
+    let mut writer = incoming.writer;
+    let mut reader = incoming.reader;
+
+    stream.write_all(b"Hello from socket client").await.unwrap();
+
+    let data = time::timeout(timeout_dur, reader.read())
+        .await
+        .expect("Timed out reading from tunnel reader")
+        .unwrap();
+    assert_eq!(data, b"Hello from socket client".as_slice());
+
+    writer
+        .write(b"Hello from tunnel writer".to_vec())
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 256];
+    let n = time::timeout(timeout_dur, stream.read(&mut buf))
+        .await
+        .expect("Timed out reading from socket")
+        .unwrap();
+    assert_eq!(&buf[..n], b"Hello from tunnel writer");
+
+    drop(stream);
+    drop(writer);
+    listener.close().await.unwrap();
+
+    // The socket file is removed once the server stops listening
+    let deadline = time::Instant::now() + timeout_dur;
+    while path.exists() {
+        assert!(
+            time::Instant::now() < deadline,
+            "Timed out waiting for socket file to be removed"
+        );
+        time::sleep(time::Duration::from_millis(50)).await;
+    }
+}
+
+#[test(tokio::test)]
+async fn tunnel_open_should_be_denied_for_targets_not_allowed_by_policy() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let allowed_port = listener.local_addr().unwrap().port();
+
+    let mut ctx = ClientCtx::initialize_with_config(Config {
+        tunnel: TunnelConfig {
+            allow_target_ports: vec![PortRange::single(allowed_port)],
+            deny_targets: vec!["10.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let mut tunnel = ctx
+        .client
+        .tunnel_open("127.0.0.1", allowed_port)
+        .await
+        .unwrap();
+    let _ = listener.accept().await.unwrap();
+    tunnel.close().await.unwrap();
+
+    let err = ctx
+        .client
+        .tunnel_open("127.0.0.1", allowed_port.wrapping_add(1))
+        .await
+        .expect_err("Expected port outside of allowed ports to be denied");
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied, "{err}");
+
+    let err = ctx
+        .client
+        .udp_tunnel_open("10.1.2.3", allowed_port)
+        .await
+        .expect_err("Expected denied network to be denied");
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied, "{err}");
+}
+
+#[test(tokio::test)]
+async fn tunnel_listen_should_be_denied_for_binds_not_allowed_by_policy() {
+    let mut ctx = ClientCtx::initialize_with_config(Config {
+        tunnel: TunnelConfig {
+            allow_bind_addrs: vec!["127.0.0.1".parse().unwrap()],
+            allow_bind_ports: vec![PortRange::from(1..=1023)],
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let err = ctx
+        .client
+        .tunnel_listen("0.0.0.0", 80)
+        .await
+        .expect_err("Expected address outside of allowed addresses to be denied");
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied, "{err}");
+
+    // Any free port is picked by the OS from outside of the allowed ports
+    let err = ctx
+        .client
+        .tunnel_listen("127.0.0.1", 0)
+        .await
+        .expect_err("Expected free port outside of allowed ports to be denied");
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied, "{err}");
+
+    // Session must still be alive after denials
+    let info = ctx.client.system_info().await.unwrap();
+    assert_eq!(info.family, std::env::consts::FAMILY);
+}
+
+#[cfg(unix)]
+#[test(tokio::test)]
+async fn unix_tunnel_open_should_be_denied_if_policy_disallows_unix_sockets() {
+    let mut ctx = ClientCtx::initialize_with_config(Config {
+        tunnel: TunnelConfig {
+            unix_sockets: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("target.sock");
+    let _listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+    let err = ctx
+        .client
+        .unix_tunnel_open(path.to_string_lossy())
+        .await
+        .expect_err("Expected Unix socket tunnel to be denied");
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied, "{err}");
+}
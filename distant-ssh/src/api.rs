@@ -1,22 +1,24 @@
 use std::collections::{HashMap, VecDeque};
 use std::future::Future;
 use std::io;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 use std::sync::{Arc, Weak};
 
 use async_once_cell::OnceCell;
 use distant_core::constants::{TUNNEL_CHANNEL_CAPACITY, TUNNEL_RELAY_BUFFER_SIZE};
 use distant_core::net::server::Reply;
 use distant_core::protocol::{
-    DirEntry, Environment, Metadata, PROTOCOL_VERSION, Permissions, ProcessId, PtySize, RemotePath,
-    Response, SearchId, SearchQuery, SetPermissionsOptions, StatusInfo, SystemInfo,
-    TunnelDirection, TunnelId, TunnelInfo, Version,
+    Cmd, DirEntry, Environment, ListeningPort, Metadata, PROTOCOL_VERSION, Permissions,
+    ProcOutputStamper, ProcSpawnOptions, ProcessId, PtySize, RemotePath, Response, SearchId,
+    SearchQuery, SetPermissionsOptions, StatusInfo, SystemInfo, TunnelCounters, TunnelDirection,
+    TunnelId, TunnelInfo, TunnelProtocol, TunnelStats, Version, parse_env_file,
 };
 use distant_core::{Api, Ctx};
 use log::*;
 use russh_sftp::client::SftpSession;
 use tokio::sync::{RwLock, mpsc};
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
 
 use crate::SshFamily;
 use crate::pool::{ChannelPool, PooledSftp};
@@ -36,6 +38,18 @@ pub(crate) struct SshTunnel {
     pub(crate) write_tx: mpsc::Sender<Vec<u8>>,
     /// Handle to the background relay task; detached on close to allow graceful drain.
     pub(crate) task: JoinHandle<()>,
+    /// Traffic through the tunnel, which for a listener includes all of its sub-tunnels.
+    pub(crate) counters: Arc<TunnelCounters>,
+}
+
+impl SshTunnel {
+    /// Returns information about the tunnel, including the traffic that has passed through it.
+    pub(crate) fn to_info(&self) -> TunnelInfo {
+        TunnelInfo {
+            stats: self.counters.stats(),
+            ..self.info.clone()
+        }
+    }
 }
 
 /// State for a registered reverse tunnel listener.
@@ -44,16 +58,23 @@ pub(crate) struct SshForwardListener {
     pub id: TunnelId,
     /// Reply channel for sending tunnel events back to the client.
     pub reply: Box<dyn Reply<Data = Response>>,
+    /// Traffic through the listener, updated by each of its sub-tunnels.
+    pub counters: Arc<TunnelCounters>,
 }
 
 /// Shared state between [`SshApi`] and [`ClientHandler`](crate::ClientHandler) for reverse tunnels.
 pub(crate) struct SshTunnelSharedState {
     /// Registered forward listeners keyed by (host, port).
     pub listeners: RwLock<HashMap<(String, u32), SshForwardListener>>,
+    /// Registered forward listeners on remote Unix sockets keyed by socket path.
+    pub unix_listeners: RwLock<HashMap<String, SshForwardListener>>,
     /// All active tunnels (forward connections and reverse sub-tunnels), shared
     /// so that [`ClientHandler::server_channel_open_forwarded_tcpip`] can
     /// register sub-tunnels reachable by [`SshApi::tunnel_write`].
     pub tunnels: Arc<RwLock<HashMap<TunnelId, SshTunnel>>>,
+    /// Number of running processes that requested agent forwarding, which must be nonzero for
+    /// [`ClientHandler`](crate::ClientHandler) to accept agent channels opened by the server.
+    pub agent_forwarding: AtomicUsize,
 }
 
 impl SshTunnelSharedState {
@@ -61,11 +82,29 @@ impl SshTunnelSharedState {
     pub fn new() -> Self {
         Self {
             listeners: RwLock::new(HashMap::new()),
+            unix_listeners: RwLock::new(HashMap::new()),
             tunnels: Arc::new(RwLock::new(HashMap::new())),
+            agent_forwarding: AtomicUsize::new(0),
         }
     }
 }
 
+/// Counts a process towards [`SshTunnelSharedState::agent_forwarding`] until dropped.
+struct AgentForwardingGuard(Arc<SshTunnelSharedState>);
+
+impl AgentForwardingGuard {
+    fn new(state: Arc<SshTunnelSharedState>) -> Self {
+        state.agent_forwarding.fetch_add(1, Ordering::Relaxed);
+        Self(state)
+    }
+}
+
+impl Drop for AgentForwardingGuard {
+    fn drop(&mut self) {
+        self.0.agent_forwarding.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 /// Represents implementation of [`Api`] for SSH.
 pub struct SshApi {
     /// Channel pool managing SSH sessions with reactive eviction.
@@ -137,6 +176,32 @@ impl SshApi {
         SftpPathBuf::from_sftp(s, self.family)
     }
 
+    /// Get the login shell of the remote user, querying it once and caching it thereafter.
+    async fn shell(&self) -> io::Result<String> {
+        let is_windows = self.family == SshFamily::Windows;
+        Ok(self
+            .cached_shell
+            .get_or_try_init(utils::query_shell(&self.pool, is_windows, &self.username))
+            .await?
+            .clone())
+    }
+
+    /// Read and parse an env file of `KEY=VALUE` lines via SFTP.
+    async fn read_env_file(&self, path: &RemotePath) -> io::Result<Environment> {
+        use tokio::io::AsyncReadExt;
+
+        let sftp_path = self.sftp_path(path);
+        let sftp = self.get_sftp().await?;
+        let mut file = sftp
+            .open(sftp_path.as_str())
+            .await
+            .map_err(|e| io::Error::other(format!("SFTP open '{}': {e}", sftp_path)))?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).await?;
+        parse_env_file(&contents)
+    }
+
     /// Apply permissions to a single path via SFTP, reading current mode and merging.
     /// Returns the path if it is a directory (for recursive processing).
     async fn apply_permissions(
@@ -237,7 +302,7 @@ impl Api for SshApi {
         path: RemotePath,
     ) -> impl Future<Output = io::Result<Vec<u8>>> + Send {
         let sftp_path = self.sftp_path(&path);
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!("[Conn {}] Reading file {}", ctx.connection_id, path);
 
             let sftp = self.get_sftp().await?;
@@ -252,7 +317,7 @@ impl Api for SshApi {
             file.read_to_end(&mut contents).await?;
 
             Ok(contents)
-        }
+        })
     }
 
     fn read_file_text(
@@ -273,7 +338,7 @@ impl Api for SshApi {
         data: Vec<u8>,
     ) -> impl Future<Output = io::Result<()>> + Send {
         let sftp_path = self.sftp_path(&path);
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!("[Conn {}] Writing file {}", ctx.connection_id, path);
 
             let sftp = self.get_sftp().await?;
@@ -288,7 +353,7 @@ impl Api for SshApi {
             file.flush().await?;
 
             Ok(())
-        }
+        })
     }
 
     fn write_file_text(
@@ -307,7 +372,7 @@ impl Api for SshApi {
         data: Vec<u8>,
     ) -> impl Future<Output = io::Result<()>> + Send {
         let sftp_path = self.sftp_path(&path);
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!("[Conn {}] Appending to file {}", ctx.connection_id, path);
 
             let sftp = self.get_sftp().await?;
@@ -329,7 +394,7 @@ impl Api for SshApi {
             file.flush().await?;
 
             Ok(())
-        }
+        })
     }
 
     fn append_file_text(
@@ -350,7 +415,7 @@ impl Api for SshApi {
         canonicalize: bool,
         include_root: bool,
     ) -> impl Future<Output = io::Result<(Vec<DirEntry>, Vec<io::Error>)>> + Send {
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!("[Conn {}] Reading directory {}", ctx.connection_id, path);
 
             let sftp = self.get_sftp().await?;
@@ -541,7 +606,7 @@ impl Api for SshApi {
             entries.sort_by(|a, b| a.path.as_str().cmp(b.path.as_str()));
 
             Ok((entries, errors))
-        }
+        })
     }
 
     fn create_dir(
@@ -550,7 +615,7 @@ impl Api for SshApi {
         path: RemotePath,
         all: bool,
     ) -> impl Future<Output = io::Result<()>> + Send {
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!(
                 "[Conn {}] Creating directory {} (all={})",
                 ctx.connection_id, path, all
@@ -589,7 +654,7 @@ impl Api for SshApi {
                     .await
                     .map_err(|e| io::Error::other(format!("SFTP create_dir '{}': {e}", sftp_path)))
             }
-        }
+        })
     }
 
     fn remove(
@@ -598,7 +663,7 @@ impl Api for SshApi {
         path: RemotePath,
         force: bool,
     ) -> impl Future<Output = io::Result<()>> + Send {
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!(
                 "[Conn {}] Removing {} (force={})",
                 ctx.connection_id, path, force
@@ -662,7 +727,7 @@ impl Api for SshApi {
                     .await
                     .map_err(|e| io::Error::other(format!("SFTP remove_file '{}': {e}", sftp_path)))
             }
-        }
+        })
     }
 
     fn copy(
@@ -690,8 +755,9 @@ impl Api for SshApi {
                 format!("cp -r \"{}\" \"{}\"", src_str, dst_str)
             };
 
+            // Bound the command by the deadline of the request, if it has one
             let (channel, _permit) = pool.open_exec().await?.take();
-            let output = utils::execute_output_on_channel(channel, &command, None).await?;
+            let output = utils::execute_output_on_channel(channel, &command, ctx.timeout()).await?;
 
             if !output.success {
                 let stderr_str = String::from_utf8_lossy(&output.stderr);
@@ -708,7 +774,7 @@ impl Api for SshApi {
         src: RemotePath,
         dst: RemotePath,
     ) -> impl Future<Output = io::Result<()>> + Send {
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!("[Conn {}] Renaming {} to {}", ctx.connection_id, src, dst);
 
             let sftp = self.get_sftp().await?;
@@ -720,7 +786,7 @@ impl Api for SshApi {
                 .map_err(|e| {
                     io::Error::other(format!("SFTP rename '{}' -> '{}': {e}", src_path, dst_path))
                 })
-        }
+        })
     }
 
     #[allow(unused_variables)]
@@ -751,7 +817,7 @@ impl Api for SshApi {
     }
 
     fn exists(&self, ctx: Ctx, path: RemotePath) -> impl Future<Output = io::Result<bool>> + Send {
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!(
                 "[Conn {}] Checking existence of {}",
                 ctx.connection_id, path
@@ -764,7 +830,7 @@ impl Api for SshApi {
                 Ok(exists) => Ok(exists),
                 Err(_) => Ok(false),
             }
-        }
+        })
     }
 
     fn metadata(
@@ -774,7 +840,7 @@ impl Api for SshApi {
         canonicalize: bool,
         resolve_file_type: bool,
     ) -> impl Future<Output = io::Result<Metadata>> + Send {
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!("[Conn {}] Getting metadata for {}", ctx.connection_id, path);
 
             let sftp = self.get_sftp().await?;
@@ -859,7 +925,7 @@ impl Api for SshApi {
                 unix: unix_metadata,
                 windows: None,
             })
-        }
+        })
     }
 
     fn set_permissions(
@@ -869,7 +935,7 @@ impl Api for SshApi {
         permissions: Permissions,
         options: SetPermissionsOptions,
     ) -> impl Future<Output = io::Result<()>> + Send {
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!(
                 "[Conn {}] Setting permissions for {}",
                 ctx.connection_id, path
@@ -913,7 +979,7 @@ impl Api for SshApi {
             }
 
             Ok(())
-        }
+        })
     }
 
     fn search(
@@ -936,6 +1002,7 @@ impl Api for SshApi {
 
             let search_cmds = search::build_search_commands(&query, &search_tools)?;
             let search_id: SearchId = rand::random();
+            let deadline = ctx.deadline;
 
             // Register cancellation flag before spawning the search task.
             let cancelled = Arc::new(AtomicBool::new(false));
@@ -956,9 +1023,12 @@ impl Api for SshApi {
                             return Ok(());
                         }
 
+                        // Bound each command by the deadline of the request, if it has one
                         let exec_result = async {
                             let (channel, _permit) = pool.open_exec().await?.take();
-                            utils::execute_output_on_channel(channel, &search_cmd.command, None)
+                            let timeout = deadline
+                                .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+                            utils::execute_output_on_channel(channel, &search_cmd.command, timeout)
                                 .await
                         }
                         .await;
@@ -1049,6 +1119,7 @@ impl Api for SshApi {
         environment: Environment,
         current_dir: Option<RemotePath>,
         pty: Option<PtySize>,
+        options: ProcSpawnOptions,
     ) -> impl Future<Output = io::Result<ProcessId>> + Send {
         let pool = &self.pool;
         let processes = &self.processes;
@@ -1056,15 +1127,44 @@ impl Api for SshApi {
         let family = self.family;
         async move {
             debug!(
-                "[Conn {}] Spawning {} {{environment: {:?}, current_dir: {:?}, pty: {:?}}}",
-                ctx.connection_id, cmd, environment, current_dir, pty
+                "[Conn {}] Spawning {} {{environment: {:?}, current_dir: {:?}, pty: {:?}, options: {:?}}}",
+                ctx.connection_id, cmd, environment, current_dir, pty, options
             );
 
-            use crate::process::{Process, SpawnResult, spawn_pty, spawn_simple};
+            use crate::process::{
+                Process, SpawnResult, spawn_pty, spawn_simple, wrap_with_env_controls,
+                wrap_with_limits,
+            };
+
+            // Explicit variables take precedence over those loaded from the env file
+            let environment = match options.env_file.as_ref() {
+                Some(path) => {
+                    let mut env_file = self.read_env_file(path).await?;
+                    env_file.extend(environment);
+                    env_file
+                }
+                None => environment,
+            };
+
+            // NOTE: An empty command requests an interactive shell, which is already a login shell
+            let cmd = if options.login && !cmd.is_empty() {
+                Cmd::new(cmd).into_login_shell(&self.shell().await?)?.into()
+            } else {
+                cmd
+            };
+
+            let cmd = wrap_with_env_controls(&cmd, &environment, &options, family)?;
+            let cmd = wrap_with_limits(&cmd, &options.limits, family)?;
+
+            // Counts the process as forwarding the agent until it exits, or fails to spawn
+            let agent_forwarding = options
+                .forward_agent
+                .then(|| AgentForwardingGuard::new(Arc::clone(&self.tunnel_state)));
 
             // Create cleanup closure that removes the process from tracking when it exits
             let make_cleanup = |processes_ref: Weak<RwLock<HashMap<ProcessId, Process>>>| {
                 move |id: ProcessId| async move {
+                    drop(agent_forwarding);
                     if let Some(processes) = processes_ref.upgrade() {
                         processes.write().await.remove(&id);
                     }
@@ -1073,6 +1173,12 @@ impl Api for SshApi {
 
             // Open a channel via the pool and extract ownership
             let (channel, permit) = pool.open_exec().await?.take();
+            if options.forward_agent {
+                channel.agent_forward(false).await.map_err(|x| {
+                    io::Error::other(format!("Failed to request agent forwarding: {x}"))
+                })?;
+            }
+            let stamper = ProcOutputStamper::new_if(options.timestamps);
 
             let SpawnResult {
                 id,
@@ -1088,6 +1194,7 @@ impl Api for SshApi {
                         environment,
                         current_dir,
                         family,
+                        stamper,
                         ctx.reply.clone_reply(),
                         make_cleanup(global_processes),
                     )
@@ -1102,6 +1209,7 @@ impl Api for SshApi {
                         current_dir,
                         family,
                         size,
+                        stamper,
                         ctx.reply.clone_reply(),
                         make_cleanup(global_processes),
                     )
@@ -1109,6 +1217,19 @@ impl Api for SshApi {
                 }
             };
 
+            // Kill the process if it is still running once its timeout elapses. A weak sender is
+            // used so that we do not keep the process' kill channel open after it has exited.
+            if let Some(timeout) = options.limits.timeout() {
+                let killer = killer.downgrade();
+                tokio::spawn(async move {
+                    tokio::time::sleep(timeout).await;
+                    if let Some(killer) = killer.upgrade() {
+                        debug!("Process {id} exceeded timeout of {timeout:?}, so killing it");
+                        let _ = killer.send(()).await;
+                    }
+                });
+            }
+
             let process = Process {
                 id,
                 stdin_tx: Some(stdin),
@@ -1180,6 +1301,32 @@ impl Api for SshApi {
         }
     }
 
+    fn proc_stdin_close(
+        &self,
+        ctx: Ctx,
+        id: ProcessId,
+    ) -> impl Future<Output = io::Result<()>> + Send {
+        let processes = &self.processes;
+        async move {
+            debug!(
+                "[Conn {}] Closing stdin of process {}",
+                ctx.connection_id, id
+            );
+
+            let mut processes = processes.write().await;
+            if let Some(process) = processes.get_mut(&id) {
+                // Dropping the sender results in EOF being sent on the SSH channel
+                process.stdin_tx.take();
+                Ok(())
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Process {} not found", id),
+                ))
+            }
+        }
+    }
+
     fn proc_resize_pty(
         &self,
         ctx: Ctx,
@@ -1215,7 +1362,7 @@ impl Api for SshApi {
     }
 
     fn system_info(&self, ctx: Ctx) -> impl Future<Output = io::Result<SystemInfo>> + Send {
-        async move {
+        utils::with_deadline(ctx.deadline, async move {
             debug!("[Conn {}] Reading system information", ctx.connection_id);
 
             let is_windows = self.family == SshFamily::Windows;
@@ -1236,11 +1383,7 @@ impl Api for SshApi {
 
             let username = self.username.clone();
 
-            let shell = self
-                .cached_shell
-                .get_or_try_init(utils::query_shell(&self.pool, is_windows, &username))
-                .await?
-                .clone();
+            let shell = self.shell().await?;
 
             Ok(SystemInfo {
                 family: match self.family {
@@ -1258,7 +1401,7 @@ impl Api for SshApi {
                 username,
                 shell,
             })
-        }
+        })
     }
 
     fn tunnel_open(
@@ -1286,38 +1429,51 @@ impl Api for SshApi {
                     ))
                 })?;
 
-            let id = NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
-            let stream = channel.into_stream();
-            let (read_half, write_half) = tokio::io::split(stream);
-
-            let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(TUNNEL_CHANNEL_CAPACITY);
-            let reply = ctx.reply.clone_reply();
-            let tunnels_task = Arc::clone(&tunnels);
+            Ok(spawn_forward_tunnel(
+                &tunnels,
+                channel,
+                ctx.reply.clone_reply(),
+                host,
+                port,
+                TunnelProtocol::Tcp,
+            )
+            .await)
+        }
+    }
 
-            let task = tokio::spawn(tunnel_relay_task(
-                id,
-                read_half,
-                write_half,
-                write_rx,
-                reply,
-                tunnels_task,
-            ));
-
-            tunnels.write().await.insert(
-                id,
-                SshTunnel {
-                    info: TunnelInfo {
-                        id,
-                        direction: TunnelDirection::Forward,
-                        host,
-                        port,
-                    },
-                    write_tx,
-                    task,
-                },
+    fn unix_tunnel_open(
+        &self,
+        ctx: Ctx,
+        path: String,
+    ) -> impl Future<Output = io::Result<TunnelId>> + Send {
+        let tunnels = Arc::clone(&self.tunnels);
+        async move {
+            debug!(
+                "[Conn {}] Opening forward tunnel to Unix socket {}",
+                ctx.connection_id, path
             );
 
-            Ok(id)
+            let channel = self
+                .pool
+                .handle()
+                .await
+                .channel_open_direct_streamlocal(path.as_str())
+                .await
+                .map_err(|e| {
+                    io::Error::other(format!(
+                        "Failed to open direct-streamlocal channel to {path}: {e}"
+                    ))
+                })?;
+
+            Ok(spawn_forward_tunnel(
+                &tunnels,
+                channel,
+                ctx.reply.clone_reply(),
+                path,
+                0,
+                TunnelProtocol::Unix,
+            )
+            .await)
         }
     }
 
@@ -1356,6 +1512,7 @@ impl Api for SshApi {
             };
 
             let id = NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
+            let counters = TunnelCounters::new();
 
             // Register the listener so incoming forwarded connections can be routed
             self.tunnel_state.listeners.write().await.insert(
@@ -1363,6 +1520,7 @@ impl Api for SshApi {
                 SshForwardListener {
                     id,
                     reply: ctx.reply.clone_reply(),
+                    counters: Arc::clone(&counters),
                 },
             );
 
@@ -1379,9 +1537,12 @@ impl Api for SshApi {
                         direction: TunnelDirection::Reverse,
                         host,
                         port: actual_port as u16,
+                        protocol: TunnelProtocol::Tcp,
+                        stats: TunnelStats::default(),
                     },
                     write_tx: mpsc::channel(1).0,
                     task: tokio::spawn(async {}),
+                    counters,
                 },
             );
 
@@ -1389,6 +1550,63 @@ impl Api for SshApi {
         }
     }
 
+    fn unix_tunnel_listen(
+        &self,
+        ctx: Ctx,
+        path: String,
+    ) -> impl Future<Output = io::Result<TunnelId>> + Send {
+        async move {
+            debug!(
+                "[Conn {}] Starting reverse tunnel listener on Unix socket {path}",
+                ctx.connection_id
+            );
+
+            self.pool
+                .handle()
+                .await
+                .streamlocal_forward(path.as_str())
+                .await
+                .map_err(|e| {
+                    io::Error::other(format!(
+                        "Failed to start streamlocal_forward on {path}: {e}"
+                    ))
+                })?;
+
+            let id = NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
+            let counters = TunnelCounters::new();
+
+            // Register the listener so incoming forwarded connections can be routed
+            self.tunnel_state.unix_listeners.write().await.insert(
+                path.clone(),
+                SshForwardListener {
+                    id,
+                    reply: ctx.reply.clone_reply(),
+                    counters: Arc::clone(&counters),
+                },
+            );
+
+            // Register a placeholder tunnel entry for the listener itself, like tunnel_listen
+            self.tunnels.write().await.insert(
+                id,
+                SshTunnel {
+                    info: TunnelInfo {
+                        id,
+                        direction: TunnelDirection::Reverse,
+                        host: path,
+                        port: 0,
+                        protocol: TunnelProtocol::Unix,
+                        stats: TunnelStats::default(),
+                    },
+                    write_tx: mpsc::channel(1).0,
+                    task: tokio::spawn(async {}),
+                    counters,
+                },
+            );
+
+            Ok(id)
+        }
+    }
+
     fn tunnel_write(
         &self,
         _ctx: Ctx,
@@ -1419,8 +1637,22 @@ impl Api for SshApi {
             drop(tunnel.write_tx);
             drop(tunnel.task);
 
-            // If this was a reverse tunnel listener, unregister it
-            if tunnel.info.direction == TunnelDirection::Reverse {
+            // If this was a reverse tunnel listener on a Unix socket, unregister it
+            if tunnel.info.direction == TunnelDirection::Reverse && tunnel.info.protocol.is_unix() {
+                let mut listeners = self.tunnel_state.unix_listeners.write().await;
+                listeners.retain(|_, listener| listener.id != id);
+
+                // Tell the server to stop forwarding on this socket path
+                if let Err(e) = self
+                    .pool
+                    .handle()
+                    .await
+                    .cancel_streamlocal_forward(tunnel.info.host.as_str())
+                    .await
+                {
+                    debug!("[Tunnel {id}] Failed to cancel streamlocal_forward: {e}");
+                }
+            } else if tunnel.info.direction == TunnelDirection::Reverse {
                 let mut listeners = self.tunnel_state.listeners.write().await;
                 listeners.retain(|_, listener| listener.id != id);
 
@@ -1444,11 +1676,42 @@ impl Api for SshApi {
         async move {
             let tunnels = self.tunnels.read().await;
             Ok(StatusInfo {
-                tunnels: tunnels.values().map(|t| t.info.clone()).collect(),
+                tunnels: tunnels.values().map(SshTunnel::to_info).collect(),
             })
         }
     }
 
+    fn listening_ports(
+        &self,
+        ctx: Ctx,
+    ) -> impl Future<Output = io::Result<Vec<ListeningPort>>> + Send {
+        let family = self.family;
+        let pool = &self.pool;
+        async move {
+            debug!("[Conn {}] Listing listening ports", ctx.connection_id);
+
+            let command = if family == SshFamily::Windows {
+                "netstat -an -p tcp"
+            } else {
+                "ss -ltn 2>/dev/null || netstat -ltn 2>/dev/null"
+            };
+
+            let (channel, _permit) = pool.open_exec().await?.take();
+            let output = utils::execute_output_on_channel(channel, command, ctx.timeout()).await?;
+
+            if !output.success {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Listing listening ports requires ss or netstat on the remote machine",
+                ));
+            }
+
+            Ok(ListeningPort::parse_socket_table(&String::from_utf8_lossy(
+                &output.stdout,
+            )))
+        }
+    }
+
     fn version(&self, ctx: Ctx) -> impl Future<Output = io::Result<Version>> + Send {
         async move {
             debug!("[Conn {}] Querying capabilities", ctx.connection_id);
@@ -1459,8 +1722,16 @@ impl Api for SshApi {
                 Version::CAP_SYS_INFO.to_string(),
                 Version::CAP_TCP_TUNNEL.to_string(),
                 Version::CAP_TCP_REV_TUNNEL.to_string(),
+                Version::CAP_UNIX_TUNNEL.to_string(),
+                Version::CAP_COMPACT_PACKETS.to_string(),
+                Version::CAP_LISTENING_PORTS.to_string(),
             ];
 
+            // Relaying forwarded agent channels needs a Unix socket to the local agent
+            if cfg!(unix) {
+                capabilities.push(Version::CAP_AGENT_FORWARD.to_string());
+            }
+
             // Only advertise search if we have tools
             if self.search_tools.has_any() {
                 capabilities.push(Version::CAP_FS_SEARCH.to_string());
@@ -1494,6 +1765,54 @@ impl Api for SshApi {
     }
 }
 
+/// Registers a forward tunnel relaying data through an SSH `channel` that was opened to
+/// `host:port` (or the socket path in `host` for Unix sockets), returning its id.
+async fn spawn_forward_tunnel(
+    tunnels: &Arc<RwLock<HashMap<TunnelId, SshTunnel>>>,
+    channel: russh::Channel<russh::client::Msg>,
+    reply: Box<dyn Reply<Data = Response>>,
+    host: String,
+    port: u16,
+    protocol: TunnelProtocol,
+) -> TunnelId {
+    let id = NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
+    let stream = channel.into_stream();
+    let (read_half, write_half) = tokio::io::split(stream);
+
+    let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(TUNNEL_CHANNEL_CAPACITY);
+    let counters = TunnelCounters::new();
+
+    let task = tokio::spawn(tunnel_relay_task(
+        id,
+        read_half,
+        write_half,
+        write_rx,
+        reply,
+        Arc::clone(tunnels),
+        Arc::clone(&counters),
+        TunnelDirection::Forward,
+    ));
+
+    tunnels.write().await.insert(
+        id,
+        SshTunnel {
+            info: TunnelInfo {
+                id,
+                direction: TunnelDirection::Forward,
+                host,
+                port,
+                protocol,
+                stats: TunnelStats::default(),
+            },
+            write_tx,
+            task,
+            counters,
+        },
+    );
+
+    id
+}
+
 /// Manages the bidirectional I/O relay for a single SSH tunnel.
 ///
 /// Reads from the SSH channel and sends `TunnelData` responses via the reply channel.
@@ -1501,6 +1820,10 @@ impl Api for SshApi {
 /// exit when either the read side closes or the write channel is dropped (via
 /// `tunnel_close`). Sends `TunnelClosed` and removes the tunnel from the map when
 /// the relay ends.
+///
+/// Traffic is recorded in `counters` relative to the tunnel's target, which the SSH
+/// channel is connected to for a forward tunnel and comes from for a reverse sub-tunnel.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn tunnel_relay_task(
     id: TunnelId,
     mut read_half: tokio::io::ReadHalf<russh::ChannelStream<russh::client::Msg>>,
@@ -1508,14 +1831,25 @@ pub(crate) async fn tunnel_relay_task(
     mut write_rx: mpsc::Receiver<Vec<u8>>,
     reply: Box<dyn Reply<Data = Response>>,
     tunnels: Arc<RwLock<HashMap<TunnelId, SshTunnel>>>,
+    counters: Arc<TunnelCounters>,
+    direction: TunnelDirection,
 ) {
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+    let _connection = counters.connection();
+    let is_target = direction == TunnelDirection::Forward;
+
+    let write_counters = Arc::clone(&counters);
     let mut write_task = tokio::spawn(async move {
         while let Some(data) = write_rx.recv().await {
             if write_half.write_all(&data).await.is_err() {
                 break;
             }
+            if is_target {
+                write_counters.record_out(data.len());
+            } else {
+                write_counters.record_in(data.len());
+            }
         }
     });
 
@@ -1526,6 +1860,11 @@ pub(crate) async fn tunnel_relay_task(
                 match read_half.read(&mut buf).await {
                     Ok(0) => break,
                     Ok(n) => {
+                        if is_target {
+                            counters.record_in(n);
+                        } else {
+                            counters.record_out(n);
+                        }
                         let data = buf[..n].to_vec();
                         if reply.send(Response::TunnelData { id, data }).is_err() {
                             break;
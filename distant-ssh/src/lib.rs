@@ -21,10 +21,13 @@ use distant_core::constants::{
     TUNNEL_CHANNEL_CAPACITY, TUNNEL_RELAY_BUFFER_SIZE, TUNNEL_TRANSPORT_CAPACITY,
 };
 use distant_core::net::auth::{AuthHandlerMap, DummyAuthHandler, Verifier};
-use distant_core::net::client::{Client as NetClient, ClientConfig};
+use distant_core::net::client::{Client as NetClient, ClientConfig, ReconnectStrategy};
 use distant_core::net::common::{InmemoryTransport, OneshotListener, Version};
-use distant_core::net::server::{Server, ServerRef};
-use distant_core::protocol::{PROTOCOL_VERSION, Response, TunnelDirection, TunnelInfo};
+use distant_core::net::server::{Reply, Server, ServerRef};
+use distant_core::protocol::{
+    PROTOCOL_VERSION, Response, TunnelCounters, TunnelDirection, TunnelId, TunnelInfo,
+    TunnelProtocol, TunnelStats,
+};
 use distant_core::{ApiServerHandler, Client, Credentials};
 use log::*;
 use russh::client::{self, Handle};
@@ -442,6 +445,9 @@ struct ClientHandler {
 
     /// Shared state for reverse tunnel listener routing.
     tunnel_state: Arc<SshTunnelSharedState>,
+
+    /// Socket of the local agent from `IdentityAgent`, falling back to `SSH_AUTH_SOCK`.
+    agent_socket: Option<String>,
 }
 
 impl client::Handler for ClientHandler {
@@ -499,66 +505,199 @@ impl client::Handler for ClientHandler {
                 }
             };
 
-            let tunnel_id = api::NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed);
+            let info = TunnelInfo {
+                id: api::NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed),
+                direction: TunnelDirection::Forward,
+                host: connected_address,
+                port: connected_port as u16,
+                protocol: TunnelProtocol::Tcp,
+                stats: TunnelStats::default(),
+            };
             let listener_id = listener.id;
+            let reply = listener.reply.clone_reply();
+            let counters = TunnelCounters::child(&listener.counters);
+            drop(listeners); // release read lock before acquiring write lock below
 
-            // Notify the client about the new incoming connection
-            let _ = listener.reply.send(Response::TunnelIncoming {
+            accept_forwarded_channel(
+                &tunnel_state,
+                channel,
                 listener_id,
-                tunnel_id,
-                peer_addr: Some(format!("{}:{}", originator_address, originator_port)),
-            });
+                reply,
+                info,
+                counters,
+                Some(format!("{}:{}", originator_address, originator_port)),
+            )
+            .await;
+
+            Ok(())
+        }
+    }
+
+    fn server_channel_open_forwarded_streamlocal(
+        &mut self,
+        channel: russh::Channel<client::Msg>,
+        socket_path: &str,
+        _session: &mut russh::client::Session,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send {
+        let socket_path = socket_path.to_string();
+        let tunnel_state = Arc::clone(&self.tunnel_state);
 
-            // Set up bidirectional relay for the forwarded channel
+        async move {
+            let listeners = tunnel_state.unix_listeners.read().await;
+            let listener = match listeners.get(&socket_path) {
+                Some(l) => l,
+                None => {
+                    debug!("No listener for forwarded connection to {}", socket_path);
+                    return Ok(());
+                }
+            };
+
+            let info = TunnelInfo {
+                id: api::NEXT_TUNNEL_ID.fetch_add(1, Ordering::Relaxed),
+                direction: TunnelDirection::Forward,
+                host: socket_path,
+                port: 0,
+                protocol: TunnelProtocol::Unix,
+                stats: TunnelStats::default(),
+            };
+            let listener_id = listener.id;
             let reply = listener.reply.clone_reply();
+            let counters = TunnelCounters::child(&listener.counters);
             drop(listeners); // release read lock before acquiring write lock below
 
-            let stream = channel.into_stream();
-            let (read_half, write_half) = tokio::io::split(stream);
-
-            let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(TUNNEL_CHANNEL_CAPACITY);
-
-            // Register the sub-tunnel BEFORE spawning the relay task to prevent
-            // a race where the task finishes and removes a not-yet-inserted entry,
-            // leaving an orphan after the subsequent insert.
-            tunnel_state.tunnels.write().await.insert(
-                tunnel_id,
-                api::SshTunnel {
-                    info: TunnelInfo {
-                        id: tunnel_id,
-                        direction: TunnelDirection::Forward,
-                        host: connected_address,
-                        port: connected_port as u16,
-                    },
-                    write_tx,
-                    task: tokio::spawn(async {}), // placeholder
-                },
-            );
+            accept_forwarded_channel(
+                &tunnel_state,
+                channel,
+                listener_id,
+                reply,
+                info,
+                counters,
+                None,
+            )
+            .await;
 
-            let tunnels_for_cleanup = Arc::clone(&tunnel_state.tunnels);
+            Ok(())
+        }
+    }
 
-            let task = tokio::spawn(api::tunnel_relay_task(
-                tunnel_id,
-                read_half,
-                write_half,
-                write_rx,
-                reply,
-                tunnels_for_cleanup,
-            ));
+    fn server_channel_open_agent_forward(
+        &mut self,
+        channel: russh::Channel<client::Msg>,
+        _session: &mut russh::client::Session,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send {
+        let requested = self.tunnel_state.agent_forwarding.load(Ordering::Relaxed) > 0;
+        let agent_socket = self.agent_socket.clone();
 
-            // Update the placeholder task with the real handle
-            if let Some(tunnel) = tunnel_state.tunnels.write().await.get_mut(&tunnel_id) {
-                tunnel.task = task;
-            } else {
-                // Entry was removed between insert and here (e.g., tunnel_close)
-                task.abort();
+        async move {
+            // Dropping the channel closes it, refusing servers that open agent channels unasked
+            if !requested {
+                warn!("Refusing agent channel as agent forwarding was not requested");
+                return Ok(());
             }
 
+            tokio::spawn(relay_agent_channel(channel, agent_socket));
             Ok(())
         }
     }
 }
 
+/// Relays an agent channel opened by the server to the local agent at `agent_socket`, or the
+/// one pointed at by `SSH_AUTH_SOCK` if not provided.
+#[cfg(unix)]
+async fn relay_agent_channel(channel: russh::Channel<client::Msg>, agent_socket: Option<String>) {
+    let path = match agent_socket {
+        Some(socket) => expand_tilde(Path::new(&socket)),
+        None => match std::env::var_os("SSH_AUTH_SOCK") {
+            Some(path) => PathBuf::from(path),
+            None => {
+                debug!("Unable to forward agent as SSH_AUTH_SOCK is not set");
+                return;
+            }
+        },
+    };
+
+    let mut agent = match tokio::net::UnixStream::connect(&path).await {
+        Ok(stream) => stream,
+        Err(x) => {
+            debug!("Failed to connect to agent at {}: {x}", path.display());
+            return;
+        }
+    };
+
+    let mut stream = channel.into_stream();
+    if let Err(x) = tokio::io::copy_bidirectional(&mut stream, &mut agent).await {
+        debug!("Agent relay closed: {x}");
+    }
+}
+
+/// Relays an agent channel opened by the server, which is unsupported without Unix sockets.
+#[cfg(not(unix))]
+async fn relay_agent_channel(_channel: russh::Channel<client::Msg>, _agent_socket: Option<String>) {
+    debug!("Unable to forward agent as Unix sockets are not supported on this platform");
+}
+
+/// Notifies the client of a connection forwarded by the server to the reverse tunnel
+/// listener `listener_id`, then relays data through `channel` as the sub-tunnel `info`,
+/// recording its traffic in `counters`.
+async fn accept_forwarded_channel(
+    tunnel_state: &SshTunnelSharedState,
+    channel: russh::Channel<client::Msg>,
+    listener_id: TunnelId,
+    reply: Box<dyn Reply<Data = Response>>,
+    info: TunnelInfo,
+    counters: Arc<TunnelCounters>,
+    peer_addr: Option<String>,
+) {
+    let tunnel_id = info.id;
+
+    // Notify the client about the new incoming connection
+    let _ = reply.send(Response::TunnelIncoming {
+        listener_id,
+        tunnel_id,
+        peer_addr,
+    });
+
+    // Set up bidirectional relay for the forwarded channel
+    let stream = channel.into_stream();
+    let (read_half, write_half) = tokio::io::split(stream);
+
+    let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(TUNNEL_CHANNEL_CAPACITY);
+
+    // Register the sub-tunnel BEFORE spawning the relay task to prevent
+    // a race where the task finishes and removes a not-yet-inserted entry,
+    // leaving an orphan after the subsequent insert.
+    tunnel_state.tunnels.write().await.insert(
+        tunnel_id,
+        api::SshTunnel {
+            info,
+            write_tx,
+            task: tokio::spawn(async {}), // placeholder
+            counters: Arc::clone(&counters),
+        },
+    );
+
+    let tunnels_for_cleanup = Arc::clone(&tunnel_state.tunnels);
+
+    let task = tokio::spawn(api::tunnel_relay_task(
+        tunnel_id,
+        read_half,
+        write_half,
+        write_rx,
+        reply,
+        tunnels_for_cleanup,
+        counters,
+        TunnelDirection::Reverse,
+    ));
+
+    // Update the placeholder task with the real handle
+    if let Some(tunnel) = tunnel_state.tunnels.write().await.get_mut(&tunnel_id) {
+        tunnel.task = task;
+    } else {
+        // Entry was removed between insert and here (e.g., tunnel_close)
+        task.abort();
+    }
+}
+
 /// An SSH connection that has not yet been authenticated.
 ///
 /// Created by [`SshSession::connect`]. Call [`SshSession::authenticate`] to
@@ -816,6 +955,7 @@ impl SshSession {
             known_hosts_files,
             policy,
             tunnel_state: Arc::clone(&tunnel_state),
+            agent_socket: ssh_config.identity_agent.clone(),
         };
 
         let russh_cfg = Arc::new(russh_cfg);
@@ -1325,6 +1465,16 @@ impl Ssh {
             debug!("Attempting to connect to distant server @ {}", addr);
             match NetClient::tcp(addr)
                 .auth_handler(AuthHandlerMap::new().with_static_key(key.clone()))
+                .config(ClientConfig {
+                    reconnect_strategy: ReconnectStrategy::ExponentialBackoff {
+                        base: Duration::from_secs(1),
+                        factor: 2.0,
+                        max_duration: Some(Duration::from_secs(10)),
+                        max_retries: None,
+                        timeout: None,
+                    },
+                    ..Default::default()
+                })
                 .connect_timeout(timeout)
                 .version(Version::new(
                     PROTOCOL_VERSION.major,
@@ -1990,6 +2140,7 @@ mod tests {
             known_hosts_files: vec![kh],
             policy: HostKeyPolicy::AcceptNew,
             tunnel_state: Arc::new(SshTunnelSharedState::new()),
+            agent_socket: None,
         };
 
         let private_key = russh::keys::PrivateKey::random(
@@ -2236,6 +2387,7 @@ mod tests {
             known_hosts_files: vec![],
             policy: HostKeyPolicy::No,
             tunnel_state: Arc::new(SshTunnelSharedState::new()),
+            agent_socket: None,
         };
 
         let private_key = russh::keys::PrivateKey::random(
@@ -3,7 +3,10 @@ use std::io;
 use std::sync::Arc;
 
 use distant_core::net::server::Reply;
-use distant_core::protocol::{Environment, ProcessId, PtySize, RemotePath, Response};
+use distant_core::protocol::{
+    Environment, ProcOutputStamper, ProcSpawnOptions, ProcessId, ProcessLimits, PtySize,
+    RemotePath, Response,
+};
 use russh::{Channel, ChannelMsg, Sig};
 use tokio::sync::mpsc;
 
@@ -42,6 +45,7 @@ pub async fn spawn_simple<F, Fut>(
     environment: Environment,
     current_dir: Option<RemotePath>,
     family: SshFamily,
+    stamper: Option<ProcOutputStamper>,
     reply: Box<dyn Reply<Data = Response>>,
     cleanup: F,
 ) -> io::Result<SpawnResult>
@@ -117,6 +121,7 @@ where
                     let _ = stdout_reply.send(Response::ProcStdout {
                         id: msg_id,
                         data: data.to_vec(),
+                        stamp: stamper.as_ref().map(ProcOutputStamper::next_stamp),
                     });
                 }
                 ChannelMsg::ExtendedData { ref data, ext } => {
@@ -128,6 +133,7 @@ where
                         let _ = stderr_reply.send(Response::ProcStderr {
                             id: msg_id,
                             data: data.to_vec(),
+                            stamp: stamper.as_ref().map(ProcOutputStamper::next_stamp),
                         });
                     }
                 }
@@ -177,12 +183,14 @@ where
                         let _ = stdout_reply.send(Response::ProcStdout {
                             id: msg_id,
                             data: data.to_vec(),
+                            stamp: stamper.as_ref().map(ProcOutputStamper::next_stamp),
                         });
                     }
                     Ok(Some(ChannelMsg::ExtendedData { ref data, ext: 1 })) => {
                         let _ = stderr_reply.send(Response::ProcStderr {
                             id: msg_id,
                             data: data.to_vec(),
+                            stamp: stamper.as_ref().map(ProcOutputStamper::next_stamp),
                         });
                     }
                     _ => break,
@@ -206,17 +214,27 @@ where
         cleanup(msg_id).await;
     });
 
-    // Spawn task to handle stdin and kill signals
+    // Spawn task to handle stdin and kill signals. Once the stdin sender is dropped, EOF is sent
+    // on the channel so that the remote process sees its input end.
     let write_half = write_half;
     tokio::spawn(async move {
+        let mut stdin_open = true;
         loop {
             tokio::select! {
-                Some(data) = stdin_rx.recv() => {
-                    use std::io::Cursor;
-                    if write_half.data(Cursor::new(data)).await.is_err() {
-                        break;
+                data = stdin_rx.recv(), if stdin_open => match data {
+                    Some(data) => {
+                        use std::io::Cursor;
+                        if write_half.data(Cursor::new(data)).await.is_err() {
+                            break;
+                        }
                     }
-                }
+                    None => {
+                        stdin_open = false;
+                        if write_half.eof().await.is_err() {
+                            break;
+                        }
+                    }
+                },
                 Some(()) = kill_rx.recv() => {
                     *was_killed.lock().await = true;
                     let _ = write_half.signal(Sig::KILL).await;
@@ -253,6 +271,7 @@ pub async fn spawn_pty<F, Fut>(
     current_dir: Option<RemotePath>,
     family: SshFamily,
     size: PtySize,
+    stamper: Option<ProcOutputStamper>,
     reply: Box<dyn Reply<Data = Response>>,
     cleanup: F,
 ) -> io::Result<SpawnResult>
@@ -369,6 +388,7 @@ where
                     let _ = stdout_reply.send(Response::ProcStdout {
                         id: msg_id,
                         data: data.to_vec(),
+                        stamp: stamper.as_ref().map(ProcOutputStamper::next_stamp),
                     });
                 }
                 ChannelMsg::Eof => {
@@ -415,6 +435,7 @@ where
                 let _ = stdout_reply.send(Response::ProcStdout {
                     id: msg_id,
                     data: data.to_vec(),
+                    stamp: stamper.as_ref().map(ProcOutputStamper::next_stamp),
                 });
             }
         }
@@ -535,6 +556,113 @@ fn wrap_with_current_dir(cmd: &str, current_dir: Option<&RemotePath>, family: Ss
     }
 }
 
+/// Wraps a command so that the remote shell applies resource limits via `ulimit` and niceness
+/// via `nice` before running it.
+///
+/// The command is run by a nested `sh -c` so that the limits cover everything it does (even
+/// compound commands), and fails if the limits cannot be applied. Running as another user is
+/// not supported as it would require privileges the SSH session does not have, and Windows
+/// targets have no equivalent of `ulimit` or `nice`.
+///
+/// The timeout limit is not handled here, as it is enforced by killing the process.
+pub fn wrap_with_limits(
+    cmd: &str,
+    limits: &ProcessLimits,
+    family: SshFamily,
+) -> io::Result<String> {
+    if limits.user.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Running a process as another user is not supported over SSH",
+        ));
+    }
+
+    let Some(prefix) = limits.to_shell_prefix() else {
+        return Ok(cmd.to_string());
+    };
+
+    if family == SshFamily::Windows {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Only the timeout limit is supported for processes on Windows",
+        ));
+    }
+
+    if cmd.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Resource limits cannot be applied to an interactive shell",
+        ));
+    }
+
+    Ok(format!("{prefix} sh -c {}", shell_words::quote(cmd)))
+}
+
+/// Wraps a command so that the inherited environment of the remote shell is cleared or has
+/// variables unset before the command runs, keeping the variables of `environment`.
+///
+/// The variables of `environment` are still sent with `setenv` channel requests (or inlined
+/// when the server refuses them) by the spawn itself, so their values never appear in the
+/// command line. Clearing is done by a nested `sh` that unsets every exported variable that is
+/// not kept rather than by `env -i`, which would discard the variables set through the
+/// channel. Windows targets have no equivalent.
+pub fn wrap_with_env_controls(
+    cmd: &str,
+    environment: &Environment,
+    options: &ProcSpawnOptions,
+    family: SshFamily,
+) -> io::Result<String> {
+    if !options.overrides_inherited_env() {
+        return Ok(cmd.to_string());
+    }
+
+    if family == SshFamily::Windows {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Clearing or unsetting the environment is not supported for processes on Windows",
+        ));
+    }
+
+    if cmd.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Environment controls cannot be applied to an interactive shell",
+        ));
+    }
+
+    let quoted = shell_words::quote(cmd);
+    if !options.clear_env {
+        let mut parts = vec![String::from("exec env")];
+        for key in options.unset_env.iter() {
+            // Explicit variables take precedence over unsetting inherited ones
+            if !environment.contains_key(key) {
+                parts.push(format!("-u {}", shell_words::quote(key)));
+            }
+        }
+        parts.push(format!("sh -c {quoted}"));
+        return Ok(parts.join(" "));
+    }
+
+    // NOTE: Sort the variables so the resulting command is deterministic
+    let mut keep: Vec<_> = environment
+        .keys()
+        .map(|key| shell_words::quote(key).into_owned())
+        .collect();
+    keep.sort();
+    let unset = match keep.is_empty() {
+        true => String::from("unset \"$k\""),
+        false => format!("case $k in {}) ;; *) unset \"$k\" ;; esac", keep.join("|")),
+    };
+
+    // The path of sh is looked up before PATH is unset, and passed along as $1
+    let script = format!(
+        "set -- \"$(command -v sh)\"; \
+         for k in $(env | sed -n 's/^\\([A-Za-z_][A-Za-z0-9_]*\\)=.*/\\1/p'); do {unset}; done; \
+         exec \"$1\" -c {quoted}"
+    );
+    Ok(format!("exec sh -c {}", shell_words::quote(&script)))
+}
+
 #[cfg(test)]
 mod tests {
     //! Tests for `Process` and `SpawnResult` struct wiring.
@@ -900,4 +1028,125 @@ mod tests {
         // Receiving should return None since the sender was dropped
         assert!(stdin_rx.recv().await.is_none());
     }
+
+    #[test]
+    fn wrap_with_limits_should_return_command_unchanged_without_limits() {
+        let limits = ProcessLimits {
+            timeout_ms: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(
+            wrap_with_limits("echo hi", &limits, SshFamily::Unix).unwrap(),
+            "echo hi"
+        );
+    }
+
+    #[test]
+    fn wrap_with_limits_should_apply_ulimit_and_nice_on_unix() {
+        let limits = ProcessLimits {
+            max_memory: Some(2048),
+            max_cpu_secs: Some(5),
+            max_open_files: Some(64),
+            nice: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            wrap_with_limits("echo hi; echo bye", &limits, SshFamily::Unix).unwrap(),
+            "ulimit -v 2 && ulimit -t 5 && ulimit -n 64 && exec nice -n 10 sh -c 'echo hi; echo bye'"
+        );
+    }
+
+    #[test]
+    fn wrap_with_limits_should_fail_if_user_provided() {
+        let limits = ProcessLimits {
+            user: Some(String::from("nobody")),
+            ..Default::default()
+        };
+        let err = wrap_with_limits("echo hi", &limits, SshFamily::Unix).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn wrap_with_limits_should_fail_for_rlimits_on_windows() {
+        let limits = ProcessLimits {
+            max_open_files: Some(64),
+            ..Default::default()
+        };
+        let err = wrap_with_limits("echo hi", &limits, SshFamily::Windows).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn wrap_with_env_controls_should_return_command_unchanged_if_inheriting_env() {
+        let environment: Environment = [(String::from("KEY"), String::from("value"))]
+            .into_iter()
+            .collect();
+        let cmd = wrap_with_env_controls(
+            "echo hi",
+            &environment,
+            &ProcSpawnOptions::default(),
+            SshFamily::Unix,
+        )
+        .unwrap();
+        assert_eq!(cmd, "echo hi");
+    }
+
+    #[test]
+    fn wrap_with_env_controls_should_unset_variables_not_provided_explicitly() {
+        let environment: Environment = [(String::from("A"), String::from("secret value"))]
+            .into_iter()
+            .collect();
+        let options = ProcSpawnOptions {
+            unset_env: vec![String::from("SECRET"), String::from("A")],
+            ..Default::default()
+        };
+        let cmd =
+            wrap_with_env_controls("echo $A", &environment, &options, SshFamily::Unix).unwrap();
+        assert_eq!(cmd, "exec env -u SECRET sh -c 'echo $A'");
+    }
+
+    #[test]
+    fn wrap_with_env_controls_should_clear_all_but_explicit_variables_without_their_values() {
+        let environment: Environment = [
+            (String::from("B"), String::from("two words")),
+            (String::from("A"), String::from("secret value")),
+        ]
+        .into_iter()
+        .collect();
+        let options = ProcSpawnOptions {
+            clear_env: true,
+            unset_env: vec![String::from("SECRET")],
+            ..Default::default()
+        };
+        let cmd =
+            wrap_with_env_controls("echo $A", &environment, &options, SshFamily::Unix).unwrap();
+        assert_eq!(
+            cmd,
+            r#"exec sh -c 'set -- "$(command -v sh)"; for k in $(env | sed -n '\''s/^\([A-Za-z_][A-Za-z0-9_]*\)=.*/\1/p'\''); do case $k in A|B) ;; *) unset "$k" ;; esac; done; exec "$1" -c '\''echo $A'\'''"#
+        );
+        assert!(!cmd.contains("secret value"));
+    }
+
+    #[test]
+    fn wrap_with_env_controls_should_fail_on_windows() {
+        let options = ProcSpawnOptions {
+            clear_env: true,
+            ..Default::default()
+        };
+        let err =
+            wrap_with_env_controls("echo hi", &Environment::new(), &options, SshFamily::Windows)
+                .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn wrap_with_env_controls_should_fail_for_interactive_shell() {
+        let options = ProcSpawnOptions {
+            unset_env: vec![String::from("SECRET")],
+            ..Default::default()
+        };
+        let err =
+            wrap_with_env_controls("", &Environment::new(), &options, SshFamily::Unix).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
 }
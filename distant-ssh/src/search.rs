@@ -78,7 +78,7 @@ async fn probe_tool(pool: &Arc<pool::ChannelPool>, tool: &str) -> io::Result<uti
         _ => format!("{tool} --version 2>/dev/null"),
     };
     let (channel, _permit) = pool.open_exec().await?.take();
-    utils::execute_output_on_channel(channel, &cmd, None).await
+    utils::execute_output_on_channel(channel, &cmd, utils::SSH_EXEC_TIMEOUT).await
 }
 
 /// Indicates which search tool backs a command.
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::future::Future;
 use std::io;
 use std::sync::Arc;
 use std::time::Duration;
@@ -6,6 +7,7 @@ use std::time::Duration;
 use russh::Channel;
 use russh::client::{Handle, Msg};
 use russh_sftp::client::SftpSession;
+use tokio::time::Instant;
 
 use crate::ClientHandler;
 use crate::SshFamily;
@@ -20,7 +22,7 @@ use crate::pool;
 pub(crate) const SSH_TIMEOUT_SECS: u64 = 60;
 
 /// Timeout for exec-channel operations (typed wrapper for `execute_output()`).
-const SSH_EXEC_TIMEOUT: Option<Duration> = Some(Duration::from_secs(SSH_TIMEOUT_SECS));
+pub(crate) const SSH_EXEC_TIMEOUT: Option<Duration> = Some(Duration::from_secs(SSH_TIMEOUT_SECS));
 
 /// Timeout for individual OS-detection probes (`echo %OS%`, PowerShell).
 const DETECT_TIMEOUT: Option<Duration> = Some(Duration::from_secs(10));
@@ -80,6 +82,20 @@ pub async fn execute_output(
     execute_output_read_loop(channel, cmd, timeout_duration).await
 }
 
+/// Runs `fut` until `deadline` passes, if there is one, so that SFTP operations stop waiting on an
+/// unresponsive server once the request they serve has run out of time.
+pub async fn with_deadline<T>(
+    deadline: Option<Instant>,
+    fut: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, fut)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "SSH request timed out"))?,
+        None => fut.await,
+    }
+}
+
 /// Run a command on a pre-opened channel and collect its output.
 pub async fn execute_output_on_channel(
     channel: Channel<Msg>,
@@ -928,6 +944,29 @@ mod tests {
         assert_eq!(SSH_EXEC_TIMEOUT, Some(Duration::from_secs(60)));
     }
 
+    // --- with_deadline tests ---
+
+    #[test_log::test(tokio::test)]
+    async fn with_deadline_fails_with_timed_out_once_deadline_passes() {
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let err = with_deadline(Some(deadline), std::future::pending::<io::Result<()>>())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn with_deadline_returns_output_of_future_that_finishes_in_time() {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert_eq!(
+            with_deadline(Some(deadline), async { Ok(5) })
+                .await
+                .unwrap(),
+            5
+        );
+        assert_eq!(with_deadline(None, async { Ok(6) }).await.unwrap(), 6);
+    }
+
     // --- contains_subslice logic tests ---
     // Uses the module-level contains_subslice function directly.
 
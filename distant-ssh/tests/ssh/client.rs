@@ -6,12 +6,14 @@ use std::time::Duration;
 
 use assert_fs::TempDir;
 use assert_fs::prelude::*;
+#[cfg(unix)]
+use distant_core::RemoteCommand;
 use distant_core::protocol::{
     ChangeKindSet, Environment, FileType, Metadata, Permissions, PtySize, RemotePath,
     SetPermissionsOptions,
 };
 #[cfg(unix)]
-use distant_core::protocol::{SearchQuery, SearchQueryCondition, SearchQueryTarget};
+use distant_core::protocol::{ProcessLimits, SearchQuery, SearchQueryCondition, SearchQueryTarget};
 use std::sync::LazyLock;
 
 use distant_core::{ChannelExt, Client};
@@ -1933,6 +1935,190 @@ async fn proc_stdin_should_send_stdin_to_process(#[future] client: Ctx<Client>)
     );
 }
 
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn proc_stdin_close_should_send_eof_to_process(#[future] client: Ctx<Client>) {
+    let mut client = client.await;
+
+    let mut proc = client
+        .spawn(
+            "wc -c".to_string(),
+            /* environment */ Environment::new(),
+            /* current_dir */ None,
+            /* pty */ None,
+        )
+        .await
+        .unwrap();
+
+    proc.stdin
+        .as_mut()
+        .unwrap()
+        .write_str("hello\n")
+        .await
+        .unwrap();
+
+    // Dropping stdin closes it remotely, so `wc` can finish reading and exit
+    drop(proc.stdin.take());
+
+    let output = tokio::time::timeout(Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit after closing stdin")
+        .unwrap();
+    assert!(output.success, "Process failed: {:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "6");
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_apply_limits(#[future] client: Ctx<Client>) {
+    let client = client.await;
+
+    let proc = RemoteCommand::new()
+        .limits(ProcessLimits {
+            max_open_files: Some(64),
+            ..Default::default()
+        })
+        .spawn(client.clone_channel(), "sh -c 'ulimit -n'")
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit")
+        .unwrap();
+    assert!(output.success, "Process failed: {:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "64");
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_kill_process_once_timeout_exceeded(#[future] client: Ctx<Client>) {
+    let client = client.await;
+
+    let proc = RemoteCommand::new()
+        .limits(ProcessLimits {
+            timeout_ms: Some(500),
+            ..Default::default()
+        })
+        .spawn(client.clone_channel(), "sleep 60")
+        .await
+        .unwrap();
+
+    let status = tokio::time::timeout(Duration::from_secs(30), proc.wait())
+        .await
+        .expect("Process was not killed after exceeding its timeout")
+        .unwrap();
+    assert!(!status.success, "Timed out process should not succeed");
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_stamp_output_when_timestamps_requested(#[future] client: Ctx<Client>) {
+    let client = client.await;
+
+    let proc = RemoteCommand::new()
+        .timestamps(true)
+        .spawn(
+            client.clone_channel(),
+            "sh -c 'echo out1; sleep 0.2; echo err1 >&2; sleep 0.2; echo out2'",
+        )
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit")
+        .unwrap();
+    assert!(output.success, "Process failed: {:?}", output);
+    assert_eq!(output.stdout, b"out1\nout2\n");
+    assert_eq!(output.stderr, b"err1\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.interleaved().expect("Output should be stamped")),
+        "out1\nerr1\nout2\n"
+    );
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_apply_env_controls_and_env_file(#[future] client: Ctx<Client>) {
+    let client = client.await;
+
+    let temp = TempDir::new().unwrap();
+    let env_file = temp.child(".env");
+    env_file
+        .write_str("# comment\nFROM_FILE=file\nOVERRIDDEN=file\n")
+        .unwrap();
+
+    let mut environment = Environment::new();
+    environment.insert(String::from("OVERRIDDEN"), String::from("explicit"));
+
+    let proc = RemoteCommand::new()
+        .environment(environment)
+        .clear_env(true)
+        .env_file(Some(RemotePath::from(env_file.path())))
+        .spawn(
+            client.clone_channel(),
+            "echo \"$FROM_FILE $OVERRIDDEN ${HOME-unset}\"",
+        )
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit")
+        .unwrap();
+    assert!(output.success, "Process failed: {:?}", output);
+    assert_eq!(output.stdout, b"file explicit unset\n");
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_unset_inherited_env_vars(#[future] client: Ctx<Client>) {
+    let client = client.await;
+
+    let proc = RemoteCommand::new()
+        .unset_env(vec![String::from("HOME")])
+        .spawn(client.clone_channel(), "echo \"${HOME-unset}\"")
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit")
+        .unwrap();
+    assert!(output.success, "Process failed: {:?}", output);
+    assert_eq!(output.stdout, b"unset\n");
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn proc_spawn_should_run_command_through_login_shell_if_requested(
+    #[future] client: Ctx<Client>,
+) {
+    let mut client = client.await;
+    let shell = client.system_info().await.unwrap().shell;
+
+    let proc = RemoteCommand::new()
+        .login(true)
+        .spawn(client.clone_channel(), "echo \"$0\"")
+        .await
+        .unwrap();
+
+    let output = tokio::time::timeout(Duration::from_secs(30), proc.output())
+        .await
+        .expect("Timed out waiting for process to exit")
+        .unwrap();
+    assert!(output.success, "Process failed: {:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), shell);
+}
+
 #[rstest]
 #[test(tokio::test)]
 async fn system_info_should_return_system_info_based_on_binary(#[future] client: Ctx<Client>) {
@@ -291,6 +291,33 @@ pub async fn client_with_tunnel_tools(
     })
 }
 
+/// rstest fixture that provides an [`Option<Ctx<Client>>`] with socat pre-installed in the
+/// container, which is needed for reverse tunnels.
+///
+/// Returns `None` if Docker is not available.
+#[fixture]
+pub async fn client_with_reverse_tunnel_tools(
+    #[future] docker_container: Option<DockerContainer>,
+) -> Option<Ctx<Client>> {
+    let container = docker_container.await?;
+
+    // Install socat before creating the distant connection so TunnelTools detects it
+    container.exec(&["apt-get", "update", "-qq"]).await.ok()?;
+    container
+        .exec(&["apt-get", "install", "-y", "-qq", "socat"])
+        .await
+        .ok()?;
+
+    let docker = Docker::connect(&container.name, DockerOpts::default())
+        .await
+        .ok()?;
+    let client = docker.into_distant_client().await.ok()?;
+    Some(Ctx {
+        value: client,
+        container,
+    })
+}
+
 /// Returns the minimal Cargo.toml content for a standalone binary.
 ///
 /// Each test binary only needs a fraction of the dependencies that the full
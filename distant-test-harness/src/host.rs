@@ -6,7 +6,7 @@ use distant_core::net::client::{Client as NetClient, TcpConnector};
 use distant_core::net::common::PortRange;
 use distant_core::net::server::Server;
 use distant_core::{ApiServerHandler, Client};
-use distant_host::Api;
+use distant_host::{Api, Config};
 use rstest::*;
 use tokio::sync::mpsc;
 
@@ -17,12 +17,17 @@ pub struct ClientCtx {
 
 impl ClientCtx {
     pub async fn initialize() -> Self {
+        Self::initialize_with_config(Config::default()).await
+    }
+
+    /// Starts a server whose api uses `config`, connecting a client to it.
+    pub async fn initialize_with_config(config: Config) -> Self {
         let ip_addr = "127.0.0.1".parse().unwrap();
         let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
         let (started_tx, mut started_rx) = mpsc::channel::<u16>(1);
 
         tokio::spawn(async move {
-            if let Ok(api) = Api::initialize(Default::default()) {
+            if let Ok(api) = Api::initialize(config) {
                 let port: PortRange = "0".parse().unwrap();
                 let port = {
                     let handler = ApiServerHandler::new(api);
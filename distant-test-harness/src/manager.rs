@@ -48,6 +48,16 @@ pub struct HostManagerCtx {
 impl HostManagerCtx {
     /// Starts a manager and server so that clients can connect
     pub fn start() -> Self {
+        Self::start_with_manager_args(std::iter::empty::<&str>())
+    }
+
+    /// Starts a manager with additional `manager listen` arguments and a server so that clients
+    /// can connect
+    pub fn start_with_manager_args<I, S>(manager_args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
         eprintln!("Logging to {:?}", ROOT_LOG_DIR.as_path());
         std::fs::create_dir_all(ROOT_LOG_DIR.as_path()).expect("Failed to create root log dir");
 
@@ -60,6 +70,11 @@ impl HostManagerCtx {
             .arg(random_log_file("manager"))
             .arg("--log-level")
             .arg("trace")
+            .arg("--known-servers-file")
+            .arg(random_state_file("known_servers"))
+            .arg("--server-identity-check")
+            .arg("accept-new")
+            .args(manager_args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
@@ -104,6 +119,8 @@ impl HostManagerCtx {
                 .arg("trace")
                 .arg("--shutdown")
                 .arg("lonely=60")
+                .arg("--identity-file")
+                .arg(random_state_file("server_identity.pem"))
                 .stdin(Stdio::piped())
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped());
@@ -462,6 +479,17 @@ pub(crate) fn wait_for_manager_ready(socket_or_pipe: &str, manager: &mut Child)
     }
 }
 
+/// Path to a file that does not exist yet for a process to keep its state in, so tests neither
+/// depend on nor modify the user's own files.
+fn random_state_file(name: &str) -> PathBuf {
+    ROOT_LOG_DIR.join(format!(
+        "{}.{}.{}",
+        name,
+        *SESSION_RANDOM,
+        rand::random::<u32>()
+    ))
+}
+
 fn random_log_file(prefix: &str) -> PathBuf {
     ROOT_LOG_DIR.join(format!(
         "{}.{}.{}.log",
@@ -504,6 +532,10 @@ impl ManagerOnlyCtx {
             .arg(random_log_file("manager"))
             .arg("--log-level")
             .arg("trace")
+            .arg("--known-servers-file")
+            .arg(random_state_file("known_servers"))
+            .arg("--server-identity-check")
+            .arg("accept-new")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
@@ -543,6 +575,8 @@ impl ManagerOnlyCtx {
             .arg("trace")
             .arg("--shutdown")
             .arg("lonely=60")
+            .arg("--identity-file")
+            .arg(random_state_file("server_identity.pem"))
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
@@ -679,6 +713,10 @@ impl SshManagerCtx {
             .arg(random_log_file("manager"))
             .arg("--log-level")
             .arg("trace")
+            .arg("--known-servers-file")
+            .arg(random_state_file("known_servers"))
+            .arg("--server-identity-check")
+            .arg("accept-new")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
@@ -919,6 +957,10 @@ impl SshLaunchCtx {
             .arg(random_log_file("manager"))
             .arg("--log-level")
             .arg("trace")
+            .arg("--known-servers-file")
+            .arg(random_state_file("known_servers"))
+            .arg("--server-identity-check")
+            .arg("accept-new")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
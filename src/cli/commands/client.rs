@@ -1,20 +1,21 @@
 use std::collections::HashMap;
 use std::io;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
 
 use anyhow::Context;
 use console::style;
+use distant_core::net::client::ConnectionState;
 use distant_core::net::common::{
     ConnectionId, Destination, Host, Map, Request, Response, ensure_scheme, extract_scheme,
 };
 use distant_core::net::manager::ManagerClient;
 use distant_core::protocol::{
-    self, ChangeKind, ChangeKindSet, FileType, Permissions, RemotePath, SearchQuery,
-    SearchQueryContentsMatch, SearchQueryMatch, SearchQueryPathMatch, SetPermissionsOptions,
-    SystemInfo, Version, semver,
+    self, ChangeKind, ChangeKindSet, FileType, Permissions, ProcSpawnOptions, ProcessLimits,
+    RemotePath, SearchQuery, SearchQueryContentsMatch, SearchQueryMatch, SearchQueryPathMatch,
+    SetPermissionsOptions, SystemInfo, Version, semver,
 };
 use distant_core::{Channel, ChannelExt, RemoteCommand, Searcher, Watcher};
 use log::*;
@@ -36,15 +37,17 @@ use crate::options::{
 };
 use crate::{CliError, CliResult};
 
+mod agent;
 mod copy;
 mod lsp;
 mod shell;
-mod tunnel;
+pub(super) mod tunnel;
 
+use agent::AgentForward;
 use lsp::Lsp;
 use shell::Shell;
 
-use super::common::RemoteProcessLink;
+use super::common::{PredictMode, RemoteProcessLink};
 
 const SLEEP_DURATION: Duration = Duration::from_millis(1);
 
@@ -280,15 +283,18 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
                 trace!("Host {:?} is global", new_destination.host.to_string());
             }
 
-            // Trigger our manager to connect to the launched server
+            // Trigger our manager to connect to the launched server, which we already trust as it
+            // was started by us, so there is no need to check or remember its identity
             debug!("Connecting to server at {}", new_destination);
+            let mut connect_options = Map::new();
+            connect_options.insert("server_identity_check".to_string(), "off".to_string());
             let sp = ui.spinner("Connecting to launched server...");
             let id = match format {
                 Format::Shell => {
                     client
                         .connect(
                             new_destination.to_string(),
-                            Map::new(),
+                            connect_options,
                             PromptAuthHandler::with_spinner(&sp),
                         )
                         .await
@@ -297,7 +303,7 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
                     client
                         .connect(
                             new_destination.to_string(),
-                            Map::new(),
+                            connect_options,
                             JsonAuthHandler::default(),
                         )
                         .await
@@ -373,7 +379,15 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
                     .into_rx::<Request<protocol::Msg<protocol::Request>>>();
                 loop {
                     match rx.recv().await {
-                        Some(Ok(request)) => {
+                        Some(Ok(mut request)) => {
+                            // Give the server the timeout as the deadline of any request that
+                            // does not already have one, so it stops working on the request
+                            if let Some(timeout) = timeout
+                                && request.header.deadline().is_none()
+                            {
+                                request.header.set_deadline(timeout.into());
+                            }
+
                             if let Err(x) = msg_tx.send(request).await {
                                 error!("Failed to forward request: {x}");
                                 break;
@@ -464,6 +478,7 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
             current_dir,
             environment,
             predict,
+            forward_agent,
             network,
         } => {
             debug!("Connecting to manager");
@@ -482,20 +497,17 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
             // Convert cmd into string
             let cmd = cmd.map(|cmd| cmd.join(" "));
 
-            debug!(
-                "Spawning shell (environment = {:?}): {}",
-                environment,
-                cmd.as_deref().unwrap_or(r"$SHELL")
-            );
-            Shell::new(channel.into_client().into_channel())
-                .spawn(
-                    cmd,
-                    environment.into_map(),
-                    current_dir,
-                    MAX_PIPE_CHUNK_SIZE,
-                    predict,
-                )
-                .await?;
+            spawn_shell(
+                &mut client,
+                connection_id,
+                channel.into_client().into_channel(),
+                cmd,
+                environment.into_map(),
+                current_dir,
+                predict,
+                forward_agent,
+            )
+            .await?;
         }
         ClientSubcommand::Spawn {
             cache,
@@ -504,6 +516,13 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
             cmd_str,
             current_dir,
             environment,
+            clear_env,
+            unset_env,
+            env_file,
+            login,
+            forward_agent,
+            timeout,
+            memory,
             predict,
             lsp,
             pty,
@@ -561,62 +580,74 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
                     .map_err(|x| anyhow::anyhow!(x))?,
             };
 
+            let limits = ProcessLimits {
+                timeout_ms: timeout.map(|t| t.as_millis().try_into().unwrap_or(u64::MAX)),
+                max_memory: memory.map(|m| m.as_bytes()),
+                ..Default::default()
+            };
+            let mut options = ProcSpawnOptions {
+                limits,
+                clear_env,
+                unset_env,
+                env_file: env_file.map(RemotePath::from),
+                login,
+                ..Default::default()
+            };
+
             if let Some(scheme) = lsp {
                 debug!(
-                    "Spawning LSP server (pty = {}, cwd = {:?}): {}",
-                    pty, current_dir, cmd
+                    "Spawning LSP server (pty = {}, cwd = {:?}, options = {:?}): {}",
+                    pty, current_dir, options, cmd
                 );
                 Lsp::new(channel)
-                    .spawn(cmd, current_dir, scheme, pty, MAX_PIPE_CHUNK_SIZE)
+                    .spawn(cmd, current_dir, scheme, pty, options, MAX_PIPE_CHUNK_SIZE)
                     .await?;
-            } else if pty {
+                return Ok(());
+            }
+
+            let mut environment = environment.into_map();
+            let agent = if forward_agent {
+                Some(
+                    AgentForward::start(
+                        &mut client,
+                        connection_id,
+                        &mut channel,
+                        &mut environment,
+                        &mut options,
+                    )
+                    .await?,
+                )
+            } else {
+                None
+            };
+
+            let result = if pty {
                 debug!(
-                    "Spawning pty process (environment = {:?}, cwd = {:?}): {}",
-                    environment, current_dir, cmd
+                    "Spawning pty process (environment = {:?}, cwd = {:?}, options = {:?}): {}",
+                    environment, current_dir, options, cmd
                 );
-                Shell::new(channel)
+                Shell::new(channel.clone())
                     .spawn(
                         cmd,
-                        environment.into_map(),
+                        environment,
                         current_dir,
+                        options,
                         MAX_PIPE_CHUNK_SIZE,
                         predict,
                     )
-                    .await?;
+                    .await
             } else {
                 debug!(
-                    "Spawning regular process (environment = {:?}, cwd = {:?}): {}",
-                    environment, current_dir, cmd
+                    "Spawning regular process (environment = {:?}, cwd = {:?}, options = {:?}): {}",
+                    environment, current_dir, options, cmd
                 );
-                let mut proc = RemoteCommand::new()
-                    .environment(environment.into_map())
-                    .current_dir(current_dir.map(RemotePath::from))
-                    .pty(None)
-                    .spawn(channel, &cmd)
-                    .await
-                    .with_context(|| format!("Failed to spawn {cmd}"))?;
-
-                // Now, map the remote process' stdin/stdout/stderr to our own process
-                let link = RemoteProcessLink::from_remote_pipes(
-                    proc.stdin.take(),
-                    proc.stdout.take().unwrap(),
-                    proc.stderr.take().unwrap(),
-                    MAX_PIPE_CHUNK_SIZE,
-                );
-
-                let status = proc.wait().await.context("Failed to wait for process")?;
-
-                // Shut down our link
-                link.shutdown().await;
+                spawn_process(channel.clone(), cmd, environment, current_dir, options).await
+            };
 
-                if !status.success {
-                    if let Some(code) = status.code {
-                        return Err(CliError::Exit(code as u8));
-                    } else {
-                        return Err(CliError::FAILURE);
-                    }
-                }
+            if let Some(agent) = agent {
+                agent.stop(&mut client, &mut channel).await;
             }
+            result?;
         }
         ClientSubcommand::SystemInfo {
             cache,
@@ -1147,6 +1178,7 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
             current_dir,
             environment,
             predict,
+            forward_agent,
             new,
             cmd,
         } => {
@@ -1217,20 +1249,17 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
             // Convert cmd into string
             let cmd = cmd.map(|cmd| cmd.join(" "));
 
-            debug!(
-                "Spawning shell (environment = {:?}): {}",
-                environment,
-                cmd.as_deref().unwrap_or(r"$SHELL")
-            );
-            Shell::new(channel.into_client().into_channel())
-                .spawn(
-                    cmd,
-                    environment.into_map(),
-                    current_dir,
-                    MAX_PIPE_CHUNK_SIZE,
-                    predict,
-                )
-                .await?;
+            spawn_shell(
+                &mut client,
+                id,
+                channel.into_client().into_channel(),
+                cmd,
+                environment.into_map(),
+                current_dir,
+                predict,
+                forward_agent,
+            )
+            .await?;
         }
         ClientSubcommand::Status {
             id,
@@ -1265,6 +1294,11 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
 
                             ui.header(&header);
                             ui.write_line(&format!("  {}  {}", style("Host:").bold(), host_str));
+                            ui.write_line(&format!(
+                                "  {}  {}",
+                                style("State:").bold(),
+                                style_connection_state(info.state)
+                            ));
 
                             if let Some(opts) = options_str {
                                 ui.write_line(&format!("  {}  {}", style("Options:").bold(), opts));
@@ -1303,18 +1337,29 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
                                     } else {
                                         ui.header("\nConnections:");
                                         for (id, dest) in list {
+                                            // Only call out connections that are not healthy
+                                            let state = match client.info(id).await {
+                                                Ok(info) if !info.state.is_connected() => format!(
+                                                    " ({})",
+                                                    style_connection_state(info.state)
+                                                ),
+                                                _ => String::new(),
+                                            };
+
                                             if *selected == id {
                                                 ui.write_line(&format!(
-                                                    "  {} {} -> {}",
+                                                    "  {} {} -> {}{}",
                                                     style("*").green(),
                                                     style(id).bold(),
-                                                    dest
+                                                    dest,
+                                                    state
                                                 ));
                                             } else {
                                                 ui.write_line(&format!(
-                                                    "    {} -> {}",
+                                                    "    {} -> {}{}",
                                                     style(id).dim(),
-                                                    dest
+                                                    dest,
+                                                    state
                                                 ));
                                             }
                                         }
@@ -1571,17 +1616,26 @@ async fn async_run(cmd: ClientSubcommand, quiet: bool) -> CliResult {
             cache.write_to_disk().await?;
 
             match sub {
-                ClientTunnelSubcommand::Open { spec, .. } => {
-                    tunnel::handle_open(&mut client, connection_id, &spec).await?;
+                ClientTunnelSubcommand::Open { spec, udp, .. } => {
+                    tunnel::handle_open(&mut client, connection_id, &spec, udp).await?;
+                }
+                ClientTunnelSubcommand::Listen { spec, udp, .. } => {
+                    tunnel::handle_listen(&mut client, connection_id, &spec, udp).await?;
                 }
-                ClientTunnelSubcommand::Listen { spec, .. } => {
-                    tunnel::handle_listen(&mut client, connection_id, &spec).await?;
+                ClientTunnelSubcommand::Socks { spec, .. } => {
+                    tunnel::handle_socks(&mut client, connection_id, &spec).await?;
+                }
+                ClientTunnelSubcommand::Http { routes, bind, .. } => {
+                    tunnel::handle_http(&mut client, connection_id, &bind, &routes).await?;
                 }
                 ClientTunnelSubcommand::Close { id, .. } => {
                     tunnel::handle_close(&mut client, id).await?;
                 }
-                ClientTunnelSubcommand::List { .. } => {
-                    tunnel::handle_list(&mut client).await?;
+                ClientTunnelSubcommand::List { watch, .. } => {
+                    tunnel::handle_list(&mut client, watch.map(Into::into)).await?;
+                }
+                ClientTunnelSubcommand::Auto { .. } => {
+                    tunnel::handle_auto(&mut client, connection_id).await?;
                 }
             }
         }
@@ -1642,6 +1696,17 @@ fn format_connection_detail(
     (header, host_line, options_line)
 }
 
+/// Styles a connection's state for `status` output: green when connected, yellow while
+/// reconnecting, and red once disconnected.
+fn style_connection_state(state: ConnectionState) -> console::StyledObject<String> {
+    let styled = style(state.to_string());
+    match state {
+        ConnectionState::Connected => styled.green(),
+        ConnectionState::Reconnecting => styled.yellow(),
+        ConnectionState::Disconnected => styled.red(),
+    }
+}
+
 /// Parses a permission mode string into a [`Permissions`] value.
 ///
 /// Supports three formats:
@@ -1979,6 +2044,99 @@ fn format_version_shell(version: &Version) -> anyhow::Result<String> {
     Ok(output)
 }
 
+/// Spawns a process without a pty, linking its stdin, stdout, and stderr to our own and exiting
+/// with its status.
+async fn spawn_process(
+    channel: Channel,
+    cmd: String,
+    environment: protocol::Environment,
+    current_dir: Option<PathBuf>,
+    options: ProcSpawnOptions,
+) -> CliResult {
+    let mut proc = RemoteCommand::new()
+        .environment(environment)
+        .current_dir(current_dir.map(RemotePath::from))
+        .pty(None)
+        .options(options)
+        .spawn(channel, &cmd)
+        .await
+        .with_context(|| format!("Failed to spawn {cmd}"))?;
+
+    // Now, map the remote process' stdin/stdout/stderr to our own process
+    let link = RemoteProcessLink::from_remote_pipes(
+        proc.stdin.take(),
+        proc.stdout.take().unwrap(),
+        proc.stderr.take().unwrap(),
+        MAX_PIPE_CHUNK_SIZE,
+    );
+
+    let status = proc.wait().await.context("Failed to wait for process")?;
+
+    // Shut down our link
+    link.shutdown().await;
+
+    if !status.success {
+        if let Some(code) = status.code {
+            return Err(CliError::Exit(code as u8));
+        } else {
+            return Err(CliError::FAILURE);
+        }
+    }
+
+    Ok(())
+}
+
+/// Spawns a shell running `cmd` (or the default shell), forwarding the local SSH agent to it when
+/// `forward_agent` is true.
+#[allow(clippy::too_many_arguments)]
+async fn spawn_shell(
+    client: &mut ManagerClient,
+    connection_id: ConnectionId,
+    mut channel: Channel,
+    cmd: Option<String>,
+    mut environment: protocol::Environment,
+    current_dir: Option<PathBuf>,
+    predict: PredictMode,
+    forward_agent: bool,
+) -> CliResult {
+    let mut options = ProcSpawnOptions::default();
+    let agent = if forward_agent {
+        Some(
+            AgentForward::start(
+                client,
+                connection_id,
+                &mut channel,
+                &mut environment,
+                &mut options,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    debug!(
+        "Spawning shell (environment = {:?}): {}",
+        environment,
+        cmd.as_deref().unwrap_or(r"$SHELL")
+    );
+    let result = Shell::new(channel.clone())
+        .spawn(
+            cmd,
+            environment,
+            current_dir,
+            options,
+            MAX_PIPE_CHUNK_SIZE,
+            predict,
+        )
+        .await;
+
+    if let Some(agent) = agent {
+        agent.stop(client, &mut channel).await;
+    }
+    result
+}
+
 /// Checks for an existing connection matching the given destination string.
 /// Comparison is done case-insensitively against stored destination strings.
 /// Returns the first matching connection ID, or None if no match found.
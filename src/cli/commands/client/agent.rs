@@ -0,0 +1,132 @@
+use std::env;
+
+use anyhow::Context;
+use distant_core::net::common::ConnectionId;
+use distant_core::net::manager::{ManagedTunnelId, ManagerClient};
+use distant_core::protocol::{
+    Environment, Permissions, ProcSpawnOptions, RemotePath, SetPermissionsOptions, Version,
+};
+use distant_core::{Channel, ChannelExt};
+use log::*;
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+
+/// Name of the environment variable pointing at the socket of an SSH agent.
+const SSH_AUTH_SOCK: &str = "SSH_AUTH_SOCK";
+
+/// Forwarding of the local SSH agent to a process spawned on a connection.
+///
+/// When the server advertises the `agent_forward` capability, it forwards the agent itself once
+/// asked through [`ProcSpawnOptions::forward_agent`]. Otherwise, the manager hosts a reverse
+/// tunnel from a Unix socket in a private directory on the remote machine to the local agent,
+/// and the process is pointed at that socket through `SSH_AUTH_SOCK`.
+pub struct AgentForward {
+    /// Managed tunnel and private remote directory of the socket, if one was created
+    tunnel: Option<(ManagedTunnelId, RemotePath)>,
+}
+
+impl AgentForward {
+    /// Sets up forwarding of the local agent for a process that will be spawned with
+    /// `environment` and `options` through `channel`.
+    pub async fn start(
+        client: &mut ManagerClient,
+        connection_id: ConnectionId,
+        channel: &mut Channel,
+        environment: &mut Environment,
+        options: &mut ProcSpawnOptions,
+    ) -> anyhow::Result<Self> {
+        let local_path = env::var(SSH_AUTH_SOCK)
+            .ok()
+            .filter(|path| !path.is_empty())
+            .context("Cannot forward agent because SSH_AUTH_SOCK is not set")?;
+
+        let version = channel
+            .version()
+            .await
+            .context("Failed to retrieve capabilities of the server")?;
+        let supports = |cap: &str| version.capabilities.iter().any(|c| c == cap);
+
+        if supports(Version::CAP_AGENT_FORWARD) {
+            debug!("Server forwards agent natively");
+            options.forward_agent = true;
+            return Ok(Self { tunnel: None });
+        }
+
+        if !supports(Version::CAP_UNIX_TUNNEL) {
+            anyhow::bail!("Cannot forward agent because the server does not support Unix sockets");
+        }
+
+        let info = channel
+            .system_info()
+            .await
+            .context("Failed to retrieve system information")?;
+        if info.family.eq_ignore_ascii_case("windows") {
+            anyhow::bail!("Cannot forward agent to a Windows machine");
+        }
+
+        // Keep the socket in a directory that only the remote user can access, as anyone able
+        // to connect to it can use the agent
+        let suffix: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(12)
+            .map(char::from)
+            .collect();
+        let dir = RemotePath::new(format!("/tmp/distant-agent-{suffix}"));
+        channel
+            .create_dir(dir.clone(), false)
+            .await
+            .with_context(|| format!("Failed to create {dir}"))?;
+
+        let remote_path = format!("{dir}/agent.sock");
+        let result = async {
+            channel
+                .set_permissions(
+                    dir.clone(),
+                    Permissions::from_unix_mode(0o700),
+                    SetPermissionsOptions::default(),
+                )
+                .await
+                .with_context(|| format!("Failed to restrict access to {dir}"))?;
+
+            let (id, _) = client
+                .unix_reverse_tunnel(
+                    connection_id,
+                    0,
+                    Some(remote_path.clone()),
+                    "",
+                    0,
+                    Some(local_path),
+                )
+                .await
+                .context("Failed to forward agent")?;
+            anyhow::Ok(id)
+        }
+        .await;
+
+        let id = match result {
+            Ok(id) => id,
+            Err(x) => {
+                let _ = channel.remove(dir, true).await;
+                return Err(x);
+            }
+        };
+
+        debug!("Forwarding agent through {remote_path} with tunnel {id}");
+        environment.insert(SSH_AUTH_SOCK.to_string(), remote_path);
+        Ok(Self {
+            tunnel: Some((id, dir)),
+        })
+    }
+
+    /// Stops forwarding the agent, closing the tunnel and removing its remote directory.
+    pub async fn stop(self, client: &mut ManagerClient, channel: &mut Channel) {
+        if let Some((id, dir)) = self.tunnel {
+            if let Err(x) = client.close_managed_tunnel(id).await {
+                debug!("Failed to close agent tunnel {id}: {x}");
+            }
+            if let Err(x) = channel.remove(dir.clone(), true).await {
+                debug!("Failed to remove {dir}: {x}");
+            }
+        }
+    }
+}
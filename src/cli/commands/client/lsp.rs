@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use anyhow::Context;
-use distant_core::protocol::{PtySize, RemotePath};
+use distant_core::protocol::{ProcSpawnOptions, PtySize, RemotePath};
 use distant_core::{Channel, RemoteLspCommand};
 use terminal_size::{Height, Width, terminal_size};
 
@@ -22,6 +22,7 @@ impl Lsp {
         current_dir: Option<PathBuf>,
         scheme: Option<String>,
         pty: bool,
+        options: ProcSpawnOptions,
         max_chunk_size: usize,
     ) -> CliResult {
         let cmd = cmd.into();
@@ -35,6 +36,7 @@ impl Lsp {
             })
             .current_dir(current_dir.map(RemotePath::from))
             .scheme(scheme)
+            .options(options)
             .spawn(self.0, &cmd)
             .await
             .with_context(|| format!("Failed to spawn {cmd}"))?;
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use anyhow::Context;
-use distant_core::protocol::{Environment, PtySize, RemotePath};
+use distant_core::protocol::{Environment, ProcSpawnOptions, PtySize, RemotePath};
 use distant_core::{Channel, ChannelExt, RemoteCommand};
 use terminal_size::{Height, Width, terminal_size};
 
@@ -40,6 +40,7 @@ impl Shell {
         cmd: impl Into<Option<String>>,
         mut environment: Environment,
         current_dir: Option<PathBuf>,
+        options: ProcSpawnOptions,
         max_chunk_size: usize,
         predict_mode: PredictMode,
     ) -> CliResult {
@@ -66,6 +67,7 @@ impl Shell {
                     .map(|(Width(cols), Height(rows))| PtySize::from_rows_and_cols(rows, cols)),
             )
             .current_dir(current_dir.map(RemotePath::from))
+            .options(options)
             .spawn(self.0, &cmd)
             .await
             .with_context(|| format!("Failed to spawn {cmd}"))?;
@@ -1,14 +1,20 @@
 use std::io;
 use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::Context;
+use console::Term;
 use distant_core::net::common::ConnectionId;
-use distant_core::net::manager::ManagerClient;
-use distant_core::protocol::TunnelDirection;
+use distant_core::net::manager::{
+    HttpRoute, ManagedTunnelInfo, ManagedTunnelSpec, ManagerClient, PortEvent,
+};
+use distant_core::protocol::{TunnelDirection, TunnelProtocol};
 
-use super::CliResult;
+use super::{CliError, CliResult};
 
-/// Parsed tunnel spec from `PORT:HOST:PORT` or `PORT:PORT` format.
+/// Parsed tunnel spec from `PORT:HOST:PORT` or `PORT:PORT` format, where either side may
+/// instead be an absolute Unix socket path (`PATH:HOST:PORT`, `PATH:PORT`, `PORT:PATH`, or
+/// `PATH:PATH`).
 ///
 /// When only two parts are given (`PORT:PORT`), the host defaults to `"127.0.0.1"`.
 ///
@@ -17,12 +23,76 @@ use super::CliResult;
 /// - `tunnel listen`: `bind_port` is remote, `host`:`peer_port` is local target
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TunnelSpec {
-    /// The port on the side that binds/listens (left side of spec).
+    /// The port on the side that binds/listens (left side of spec), or `0` for a socket path.
     pub bind_port: u16,
-    /// The host to connect to (middle of spec, defaults to `"127.0.0.1"`).
+    /// The host to connect to (middle of spec, defaults to `"127.0.0.1"`), or empty for a
+    /// socket path.
     pub host: String,
-    /// The port to connect to on the host (right side of spec).
+    /// The port to connect to on the host (right side of spec), or `0` for a socket path.
     pub peer_port: u16,
+    /// The Unix socket path to bind/listen on in place of `bind_port`.
+    pub bind_path: Option<String>,
+    /// The Unix socket path to connect to in place of `host`:`peer_port`.
+    pub peer_path: Option<String>,
+}
+
+impl TunnelSpec {
+    /// Returns true if either side of the tunnel is a Unix socket path.
+    pub fn has_path(&self) -> bool {
+        self.bind_path.is_some() || self.peer_path.is_some()
+    }
+
+    /// Converts the spec of `tunnel open` into a description of the forward tunnel it starts.
+    pub fn into_forward(self, udp: bool) -> ManagedTunnelSpec {
+        ManagedTunnelSpec::Forward {
+            protocol: self.protocol(udp),
+            bind_port: self.bind_port,
+            remote_host: self.host,
+            remote_port: self.peer_port,
+            bind_path: self.bind_path,
+            remote_path: self.peer_path,
+        }
+    }
+
+    /// Converts the spec of `tunnel listen` into a description of the reverse tunnel it starts.
+    pub fn into_reverse(self, udp: bool) -> ManagedTunnelSpec {
+        ManagedTunnelSpec::Reverse {
+            protocol: self.protocol(udp),
+            remote_port: self.bind_port,
+            local_host: self.host,
+            local_port: self.peer_port,
+            remote_path: self.bind_path,
+            local_path: self.peer_path,
+        }
+    }
+
+    /// Returns the protocol carried by the tunnel, which is always Unix when a socket path is
+    /// involved.
+    fn protocol(&self, udp: bool) -> TunnelProtocol {
+        if self.has_path() {
+            TunnelProtocol::Unix
+        } else if udp {
+            TunnelProtocol::Udp
+        } else {
+            TunnelProtocol::Tcp
+        }
+    }
+
+    /// Describes the side that binds/listens, given the port that was actually bound.
+    fn bind_display(&self, port: u16) -> String {
+        match &self.bind_path {
+            Some(path) => path.clone(),
+            None => port.to_string(),
+        }
+    }
+
+    /// Describes the side that is connected to.
+    fn peer_display(&self) -> String {
+        match &self.peer_path {
+            Some(path) => path.clone(),
+            None => format!("{}:{}", self.host, self.peer_port),
+        }
+    }
 }
 
 impl FromStr for TunnelSpec {
@@ -32,18 +102,39 @@ impl FromStr for TunnelSpec {
     /// - `PORT:HOST:PORT` — explicit host
     /// - `PORT:PORT` — host defaults to `"127.0.0.1"`
     ///
+    /// Either the leading `PORT` or the trailing `HOST:PORT`/`PORT` may be replaced by an
+    /// absolute Unix socket path, which starts with `/` and cannot contain `:`.
+    ///
     /// Uses `rfind(':')` to handle IPv6 hosts like `[::1]` in the three-part form.
     fn from_str(spec: &str) -> Result<Self, Self::Err> {
         let first_colon = spec
             .find(':')
             .ok_or_else(|| io::Error::other(format!("Invalid tunnel spec: {spec}")))?;
-        let bind_port: u16 = spec[..first_colon]
-            .parse()
-            .map_err(|e| io::Error::other(format!("Invalid bind port: {e}")))?;
+        let (bind_port, bind_path) = if spec.starts_with('/') {
+            (0, Some(spec[..first_colon].to_string()))
+        } else {
+            let bind_port: u16 = spec[..first_colon]
+                .parse()
+                .map_err(|e| io::Error::other(format!("Invalid bind port: {e}")))?;
+            (bind_port, None)
+        };
 
         let rest = &spec[first_colon + 1..];
 
-        if let Some(last_colon) = rest.rfind(':') {
+        if rest.starts_with('/') {
+            // Socket path form: (PORT|PATH):PATH
+            if rest.contains(':') {
+                return Err(io::Error::other(format!("Invalid socket path: {rest}")));
+            }
+
+            Ok(Self {
+                bind_port,
+                host: String::new(),
+                peer_port: 0,
+                bind_path,
+                peer_path: Some(rest.to_string()),
+            })
+        } else if let Some(last_colon) = rest.rfind(':') {
             // Three-part form: PORT:HOST:PORT
             let host = rest[..last_colon].to_string();
             let peer_port: u16 = rest[last_colon + 1..]
@@ -54,6 +145,8 @@ impl FromStr for TunnelSpec {
                 bind_port,
                 host,
                 peer_port,
+                bind_path,
+                peer_path: None,
             })
         } else {
             // Two-part form: PORT:PORT (host defaults to "127.0.0.1")
@@ -65,68 +158,332 @@ impl FromStr for TunnelSpec {
                 bind_port,
                 host: "127.0.0.1".to_string(),
                 peer_port,
+                bind_path,
+                peer_path: None,
             })
         }
     }
 }
 
-/// Handles `distant tunnel open` — requests the manager to start a forward tunnel.
+/// Parsed SOCKS proxy spec from `[BIND_HOST:]PORT` format.
+///
+/// When no host is given (`PORT`), the proxy binds to `"127.0.0.1"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocksSpec {
+    /// The local host to bind the proxy to (defaults to `"127.0.0.1"`).
+    pub bind_host: String,
+    /// The local port to bind the proxy to.
+    pub bind_port: u16,
+}
+
+impl SocksSpec {
+    /// Converts the spec into a description of the SOCKS proxy it starts.
+    pub fn into_socks(self) -> ManagedTunnelSpec {
+        ManagedTunnelSpec::Socks {
+            bind_host: self.bind_host,
+            bind_port: self.bind_port,
+        }
+    }
+}
+
+impl FromStr for SocksSpec {
+    type Err = io::Error;
+
+    /// Parses a SOCKS spec in two formats:
+    /// - `HOST:PORT` — explicit bind host, where IPv6 hosts are wrapped in brackets (`[::1]`)
+    /// - `PORT` — bind host defaults to `"127.0.0.1"`
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (bind_host, port) = split_host_port(spec)
+            .ok_or_else(|| io::Error::other(format!("Invalid socks spec: {spec}")))?;
+
+        let bind_port: u16 = port
+            .parse()
+            .map_err(|e| io::Error::other(format!("Invalid bind port: {e}")))?;
+
+        Ok(Self {
+            bind_host,
+            bind_port,
+        })
+    }
+}
+
+/// Parsed HTTP proxy spec from `--bind [BIND_HOST:]PORT` and one or more
+/// `NAME=[HOST:]PORT` routes.
+///
+/// When no bind host is given, the proxy binds to `"127.0.0.1"`, and when a route has no host,
+/// its target host defaults to `"127.0.0.1"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpSpec {
+    /// The local host to bind the proxy to (defaults to `"127.0.0.1"`).
+    pub bind_host: String,
+    /// The local port to bind the proxy to.
+    pub bind_port: u16,
+    /// Routes served at `http://NAME.localhost:PORT`, with unique names.
+    pub routes: Vec<HttpRoute>,
+}
+
+impl HttpSpec {
+    /// Parses the bind address and routes given to `tunnel http`.
+    pub fn parse(bind: &str, routes: &[String]) -> io::Result<Self> {
+        let (bind_host, port) = split_host_port(bind)
+            .ok_or_else(|| io::Error::other(format!("Invalid bind address: {bind}")))?;
+        let bind_port: u16 = port
+            .parse()
+            .map_err(|e| io::Error::other(format!("Invalid bind port: {e}")))?;
+
+        if routes.is_empty() {
+            return Err(io::Error::other("At least one route is required"));
+        }
+
+        let mut parsed: Vec<HttpRoute> = Vec::with_capacity(routes.len());
+        for route in routes {
+            let route = parse_http_route(route)?;
+            if parsed.iter().any(|r| r.name == route.name) {
+                return Err(io::Error::other(format!(
+                    "Duplicate route name: {}",
+                    route.name
+                )));
+            }
+            parsed.push(route);
+        }
+
+        Ok(Self {
+            bind_host,
+            bind_port,
+            routes: parsed,
+        })
+    }
+}
+
+/// Parses an HTTP route in the format `NAME=[HOST:]PORT`, where `NAME` is lowercased and may
+/// only contain letters, digits, `-`, and `.` so that `NAME.localhost` is a valid host name.
+fn parse_http_route(spec: &str) -> io::Result<HttpRoute> {
+    let invalid = || io::Error::other(format!("Invalid http route: {spec}"));
+
+    let (name, target) = spec.split_once('=').ok_or_else(invalid)?;
+    let valid_name = !name.is_empty()
+        && !name.starts_with(['.', '-'])
+        && !name.ends_with(['.', '-'])
+        && !name.contains("..")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    if !valid_name {
+        return Err(io::Error::other(format!("Invalid route name: {name}")));
+    }
+
+    let (host, port) = split_host_port(target).ok_or_else(invalid)?;
+    let port: u16 = port
+        .parse()
+        .map_err(|e| io::Error::other(format!("Invalid remote port: {e}")))?;
+
+    Ok(HttpRoute {
+        name: name.to_ascii_lowercase(),
+        host,
+        port,
+    })
+}
+
+/// Splits `[HOST:]PORT` into its host, defaulting to `"127.0.0.1"` and with the brackets of an
+/// IPv6 host (`[::1]`) removed, and its unparsed port. Returns `None` if the host is empty.
+fn split_host_port(spec: &str) -> Option<(String, &str)> {
+    match spec.rsplit_once(':') {
+        Some((host, port)) => {
+            let host = host
+                .strip_prefix('[')
+                .and_then(|host| host.strip_suffix(']'))
+                .unwrap_or(host);
+            if host.is_empty() {
+                return None;
+            }
+            Some((host.to_string(), port))
+        }
+        None => Some(("127.0.0.1".to_string(), spec)),
+    }
+}
+
+/// Handles `distant tunnel open` — requests the manager to start a forward tunnel,
+/// carrying UDP datagrams instead of TCP connections when `udp` is set.
 pub async fn handle_open(
     client: &mut ManagerClient,
     connection_id: ConnectionId,
     spec: &str,
+    udp: bool,
+) -> CliResult {
+    let spec: TunnelSpec = spec.parse().context("Failed to parse tunnel spec")?;
+    if udp && spec.has_path() {
+        return Err(CliError::Error(anyhow::anyhow!(
+            "Unix socket paths are not supported for UDP tunnels"
+        )));
+    }
+
+    let host = spec.host.clone();
+    let result = if udp {
+        client
+            .udp_forward_tunnel(connection_id, spec.bind_port, host, spec.peer_port)
+            .await
+    } else if spec.has_path() {
+        client
+            .unix_forward_tunnel(
+                connection_id,
+                spec.bind_port,
+                spec.bind_path.clone(),
+                host,
+                spec.peer_port,
+                spec.peer_path.clone(),
+            )
+            .await
+    } else {
+        client
+            .forward_tunnel(connection_id, spec.bind_port, host, spec.peer_port)
+            .await
+    };
+    let (id, port) = result.with_context(|| {
+        format!(
+            "Failed to start forward tunnel {}:{}",
+            spec.bind_display(spec.bind_port),
+            spec.peer_display()
+        )
+    })?;
+
+    let bind = match &spec.bind_path {
+        Some(path) => path.clone(),
+        None => format!("127.0.0.1:{port}"),
+    };
+    println!(
+        "Tunnel {id} started: {bind} -> {}{}",
+        spec.peer_display(),
+        protocol_suffix(udp)
+    );
+    Ok(())
+}
+
+/// Handles `distant tunnel listen` — requests the manager to start a reverse tunnel,
+/// carrying UDP datagrams instead of TCP connections when `udp` is set.
+pub async fn handle_listen(
+    client: &mut ManagerClient,
+    connection_id: ConnectionId,
+    spec: &str,
+    udp: bool,
 ) -> CliResult {
     let spec: TunnelSpec = spec.parse().context("Failed to parse tunnel spec")?;
+    if udp && spec.has_path() {
+        return Err(CliError::Error(anyhow::anyhow!(
+            "Unix socket paths are not supported for UDP tunnels"
+        )));
+    }
 
-    let (id, port) = client
-        .forward_tunnel(
-            connection_id,
-            spec.bind_port,
-            spec.host.clone(),
-            spec.peer_port,
+    let host = spec.host.clone();
+    let result = if udp {
+        client
+            .udp_reverse_tunnel(connection_id, spec.bind_port, host, spec.peer_port)
+            .await
+    } else if spec.has_path() {
+        client
+            .unix_reverse_tunnel(
+                connection_id,
+                spec.bind_port,
+                spec.bind_path.clone(),
+                host,
+                spec.peer_port,
+                spec.peer_path.clone(),
+            )
+            .await
+    } else {
+        client
+            .reverse_tunnel(connection_id, spec.bind_port, host, spec.peer_port)
+            .await
+    };
+    let (id, port) = result.with_context(|| {
+        format!(
+            "Failed to start reverse tunnel {}:{}",
+            spec.bind_display(spec.bind_port),
+            spec.peer_display()
         )
+    })?;
+
+    let bind = match &spec.bind_path {
+        Some(path) => format!("remote socket {path}"),
+        None => format!("remote port {port}"),
+    };
+    println!(
+        "Tunnel {id} started: {bind} -> {}{}",
+        spec.peer_display(),
+        protocol_suffix(udp)
+    );
+    Ok(())
+}
+
+/// Suffix noting the protocol of a started tunnel, which is only shown for UDP since TCP is the
+/// default.
+fn protocol_suffix(udp: bool) -> &'static str {
+    if udp { " (udp)" } else { "" }
+}
+
+/// Handles `distant tunnel socks` — requests the manager to start a SOCKS proxy.
+pub async fn handle_socks(
+    client: &mut ManagerClient,
+    connection_id: ConnectionId,
+    spec: &str,
+) -> CliResult {
+    let spec: SocksSpec = spec.parse().context("Failed to parse socks spec")?;
+
+    let (id, port) = client
+        .socks_tunnel(connection_id, spec.bind_host.clone(), spec.bind_port)
         .await
         .with_context(|| {
             format!(
-                "Failed to start forward tunnel {}:{}:{}",
-                spec.bind_port, spec.host, spec.peer_port
+                "Failed to start SOCKS proxy on {}:{}",
+                spec.bind_host, spec.bind_port
             )
         })?;
 
     println!(
-        "Tunnel {id} started: 127.0.0.1:{port} -> {}:{}",
-        spec.host, spec.peer_port
+        "Tunnel {id} started: {}:{port} (SOCKS proxy)",
+        spec.bind_host
     );
     Ok(())
 }
 
-/// Handles `distant tunnel listen` — requests the manager to start a reverse tunnel.
-pub async fn handle_listen(
+/// Handles `distant tunnel http` — requests the manager to start an HTTP proxy serving `routes`.
+pub async fn handle_http(
     client: &mut ManagerClient,
     connection_id: ConnectionId,
-    spec: &str,
+    bind: &str,
+    routes: &[String],
 ) -> CliResult {
-    let spec: TunnelSpec = spec.parse().context("Failed to parse tunnel spec")?;
+    let spec = HttpSpec::parse(bind, routes).context("Failed to parse http routes")?;
 
     let (id, port) = client
-        .reverse_tunnel(
+        .http_tunnel(
             connection_id,
+            spec.bind_host.clone(),
             spec.bind_port,
-            spec.host.clone(),
-            spec.peer_port,
+            spec.routes.clone(),
         )
         .await
         .with_context(|| {
             format!(
-                "Failed to start reverse tunnel {}:{}:{}",
-                spec.bind_port, spec.host, spec.peer_port
+                "Failed to start HTTP proxy on {}:{}",
+                spec.bind_host, spec.bind_port
             )
         })?;
 
     println!(
-        "Tunnel {id} started: remote port {port} -> {}:{}",
-        spec.host, spec.peer_port
+        "Tunnel {id} started: {}:{port} (HTTP proxy)",
+        spec.bind_host
     );
+    for route in &spec.routes {
+        let host = if route.host.contains(':') {
+            format!("[{}]", route.host)
+        } else {
+            route.host.clone()
+        };
+        println!(
+            "  http://{}.localhost:{port} -> {host}:{}",
+            route.name, route.port
+        );
+    }
     Ok(())
 }
 
@@ -141,29 +498,170 @@ pub async fn handle_close(client: &mut ManagerClient, id: u32) -> CliResult {
     Ok(())
 }
 
-/// Handles `distant tunnel list` — lists all managed tunnels.
-pub async fn handle_list(client: &mut ManagerClient) -> CliResult {
-    let tunnels = client
+/// Handles `distant tunnel list` — lists all managed tunnels, refreshing the list every `watch`
+/// interval until interrupted when provided.
+pub async fn handle_list(client: &mut ManagerClient, watch: Option<Duration>) -> CliResult {
+    let Some(interval) = watch else {
+        let tunnels = list_tunnels(client).await?;
+        print!("{}", tunnel_table(&tunnels, unix_now()));
+        return Ok(());
+    };
+
+    let term = Term::stdout();
+    loop {
+        let tunnels = list_tunnels(client).await?;
+        let table = tunnel_table(&tunnels, unix_now());
+        if term.is_term() {
+            let _ = term.clear_screen();
+        }
+        print!("{table}");
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn list_tunnels(client: &mut ManagerClient) -> anyhow::Result<Vec<ManagedTunnelInfo>> {
+    client
         .list_managed_tunnels()
         .await
-        .context("Failed to list managed tunnels")?;
+        .context("Failed to list managed tunnels")
+}
+
+/// Seconds since the Unix epoch, which is how tunnels report their last activity.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
 
+/// Renders `tunnels` as the table printed by `distant tunnel list`, describing their last
+/// activity relative to `now`.
+fn tunnel_table(tunnels: &[ManagedTunnelInfo], now: u64) -> String {
     if tunnels.is_empty() {
-        println!("No active tunnels");
-    } else {
-        println!(
-            "{:<6} {:<10} {:<12} {:<30} {:<6}",
-            "ID", "Direction", "Bind Port", "Remote Host", "Remote Port"
+        return String::from("No active tunnels\n");
+    }
+
+    let mut table = format!(
+        "{:<6} {:<10} {:<9} {:<12} {:<30} {:<12} {:<10} {:<10} {:<7} {:<12} State\n",
+        "ID",
+        "Direction",
+        "Protocol",
+        "Bind",
+        "Remote Host",
+        "Remote Port",
+        "In",
+        "Out",
+        "Conns",
+        "Last Active"
+    );
+    for t in tunnels {
+        let direction = match t.direction {
+            TunnelDirection::Forward => "forward",
+            TunnelDirection::Reverse => "reverse",
+            TunnelDirection::Dynamic => "dynamic",
+        };
+        let protocol = match t.protocol {
+            TunnelProtocol::Tcp => "tcp",
+            TunnelProtocol::Udp => "udp",
+            TunnelProtocol::Unix => "unix",
+        };
+        let bind = match &t.bind_path {
+            Some(path) => path.clone(),
+            None => t.bind_port.to_string(),
+        };
+        let (remote_host, remote_port) = match (&t.remote_path, &t.direction) {
+            (Some(path), _) => (path.clone(), "-".to_string()),
+            (None, TunnelDirection::Dynamic) => (t.remote_host.clone(), "*".to_string()),
+            (None, _) => (t.remote_host.clone(), t.remote_port.to_string()),
+        };
+        let conns = format!(
+            "{}/{}",
+            t.stats.active_connections, t.stats.total_connections
         );
-        for t in tunnels {
-            let direction = match t.direction {
-                TunnelDirection::Forward => "forward",
-                TunnelDirection::Reverse => "reverse",
-            };
-            println!(
-                "{:<6} {:<10} {:<12} {:<30} {:<6}",
-                t.id, direction, t.bind_port, t.remote_host, t.remote_port
-            );
+        let last_active = match t.stats.last_activity {
+            Some(at) => format_elapsed(now.saturating_sub(at)),
+            None => "-".to_string(),
+        };
+        let state = match &t.last_error {
+            Some(error) => format!("{} ({error})", t.state),
+            None => t.state.to_string(),
+        };
+        table.push_str(&format!(
+            "{:<6} {:<10} {:<9} {:<12} {:<30} {:<12} {:<10} {:<10} {:<7} {:<12} {}\n",
+            t.id,
+            direction,
+            protocol,
+            bind,
+            remote_host,
+            remote_port,
+            format_bytes(t.stats.bytes_in),
+            format_bytes(t.stats.bytes_out),
+            conns,
+            last_active,
+            state
+        ));
+    }
+
+    table
+}
+
+/// Formats a number of bytes using binary units, such as `1.5 KiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{value:.1} {unit}")
+}
+
+/// Formats the time since some activity, such as `5s ago` or `3m ago`.
+fn format_elapsed(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s ago"),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// Handles `distant tunnel auto` — follows the ports that the manager forwards automatically for
+/// the connection, printing each change until the manager stops sending them.
+pub async fn handle_auto(client: &mut ManagerClient, connection_id: ConnectionId) -> CliResult {
+    let mut watcher = client
+        .watch_ports()
+        .await
+        .context("Failed to watch forwarded ports")?;
+
+    println!("Watching for ports listened on by connection {connection_id}");
+    while let Some(event) = watcher.next().await {
+        let event = event.context("Failed to watch forwarded ports")?;
+        if event.connection_id() != connection_id {
+            continue;
+        }
+
+        match event {
+            PortEvent::Forwarded {
+                id,
+                remote_port,
+                local_port,
+                ..
+            } => println!(
+                "Tunnel {id} started: remote port {remote_port} forwarded to localhost:{local_port}"
+            ),
+            PortEvent::Closed {
+                id, remote_port, ..
+            } => println!("Tunnel {id} closed: remote port {remote_port} is no longer listening"),
         }
     }
 
@@ -305,6 +803,264 @@ mod tests {
         }
     }
 
+    mod parse_unix {
+        use super::*;
+
+        #[test]
+        fn should_parse_port_to_socket_path() {
+            let spec: TunnelSpec = "2375:/var/run/docker.sock".parse().unwrap();
+            assert_eq!(spec.bind_port, 2375);
+            assert_eq!(spec.bind_path, None);
+            assert_eq!(spec.host, "");
+            assert_eq!(spec.peer_port, 0);
+            assert_eq!(spec.peer_path.as_deref(), Some("/var/run/docker.sock"));
+            assert!(spec.has_path());
+        }
+
+        #[test]
+        fn should_parse_socket_path_to_host_and_port() {
+            let spec: TunnelSpec = "/tmp/app.sock:localhost:3000".parse().unwrap();
+            assert_eq!(spec.bind_port, 0);
+            assert_eq!(spec.bind_path.as_deref(), Some("/tmp/app.sock"));
+            assert_eq!(spec.host, "localhost");
+            assert_eq!(spec.peer_port, 3000);
+            assert_eq!(spec.peer_path, None);
+        }
+
+        #[test]
+        fn should_parse_socket_path_to_port_with_default_host() {
+            let spec: TunnelSpec = "/tmp/app.sock:3000".parse().unwrap();
+            assert_eq!(spec.bind_path.as_deref(), Some("/tmp/app.sock"));
+            assert_eq!(spec.host, "127.0.0.1");
+            assert_eq!(spec.peer_port, 3000);
+        }
+
+        #[test]
+        fn should_parse_socket_path_to_socket_path() {
+            let spec: TunnelSpec = "/tmp/local.sock:/tmp/remote.sock".parse().unwrap();
+            assert_eq!(spec.bind_path.as_deref(), Some("/tmp/local.sock"));
+            assert_eq!(spec.peer_path.as_deref(), Some("/tmp/remote.sock"));
+        }
+
+        #[test]
+        fn should_not_report_path_for_host_and_port_spec() {
+            let spec: TunnelSpec = "8080:host:80".parse().unwrap();
+            assert!(!spec.has_path());
+        }
+
+        #[test]
+        fn should_reject_socket_path_followed_by_more_parts() {
+            let err = "8080:/tmp/app.sock:80".parse::<TunnelSpec>().unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid socket path"),
+                "Expected 'Invalid socket path' in error: {}",
+                err
+            );
+        }
+
+        #[test]
+        fn should_reject_socket_path_without_peer() {
+            let err = "/tmp/app.sock".parse::<TunnelSpec>().unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid tunnel spec"),
+                "Expected 'Invalid tunnel spec' in error: {}",
+                err
+            );
+        }
+    }
+
+    mod parse_socks {
+        use super::*;
+
+        #[test]
+        fn should_parse_port_with_default_host() {
+            let spec: SocksSpec = "1080".parse().unwrap();
+            assert_eq!(spec.bind_host, "127.0.0.1");
+            assert_eq!(spec.bind_port, 1080);
+        }
+
+        #[test]
+        fn should_parse_host_and_port() {
+            let spec: SocksSpec = "0.0.0.0:1080".parse().unwrap();
+            assert_eq!(spec.bind_host, "0.0.0.0");
+            assert_eq!(spec.bind_port, 1080);
+        }
+
+        #[test]
+        fn should_parse_bracketed_ipv6_host() {
+            let spec: SocksSpec = "[::1]:1080".parse().unwrap();
+            assert_eq!(spec.bind_host, "::1");
+            assert_eq!(spec.bind_port, 1080);
+        }
+
+        #[test]
+        fn should_reject_empty_host() {
+            let err = ":1080".parse::<SocksSpec>().unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid socks spec"),
+                "Expected 'Invalid socks spec' in error: {}",
+                err
+            );
+        }
+
+        #[test]
+        fn should_reject_invalid_port() {
+            let err = "localhost:abc".parse::<SocksSpec>().unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid bind port"),
+                "Expected 'Invalid bind port' in error: {}",
+                err
+            );
+        }
+    }
+
+    mod parse_http {
+        use super::*;
+
+        fn routes(routes: &[&str]) -> Vec<String> {
+            routes.iter().map(ToString::to_string).collect()
+        }
+
+        #[test]
+        fn should_parse_routes_with_default_hosts() {
+            let spec = HttpSpec::parse("8080", &routes(&["Web=3000", "api=[::1]:4000"])).unwrap();
+            assert_eq!(spec.bind_host, "127.0.0.1");
+            assert_eq!(spec.bind_port, 8080);
+            assert_eq!(
+                spec.routes,
+                vec![
+                    HttpRoute {
+                        name: "web".to_string(),
+                        host: "127.0.0.1".to_string(),
+                        port: 3000,
+                    },
+                    HttpRoute {
+                        name: "api".to_string(),
+                        host: "::1".to_string(),
+                        port: 4000,
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn should_parse_bind_host_and_port() {
+            let spec = HttpSpec::parse("0.0.0.0:9000", &routes(&["docs=docs-host:8000"])).unwrap();
+            assert_eq!(spec.bind_host, "0.0.0.0");
+            assert_eq!(spec.bind_port, 9000);
+            assert_eq!(spec.routes[0].host, "docs-host");
+            assert_eq!(spec.routes[0].port, 8000);
+        }
+
+        #[test]
+        fn should_reject_invalid_route_names() {
+            for route in ["=3000", "we b=3000", ".web=3000", "web-=3000", "a..b=3000"] {
+                let err = HttpSpec::parse("8080", &routes(&[route])).unwrap_err();
+                assert!(
+                    err.to_string().contains("Invalid route name"),
+                    "Expected 'Invalid route name' in error for {route}: {err}"
+                );
+            }
+        }
+
+        #[test]
+        fn should_reject_route_without_target() {
+            let err = HttpSpec::parse("8080", &routes(&["web"])).unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid http route"),
+                "Expected 'Invalid http route' in error: {err}"
+            );
+
+            let err = HttpSpec::parse("8080", &routes(&["web=host:abc"])).unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid remote port"),
+                "Expected 'Invalid remote port' in error: {err}"
+            );
+        }
+
+        #[test]
+        fn should_reject_duplicate_route_names() {
+            let err = HttpSpec::parse("8080", &routes(&["web=3000", "WEB=4000"])).unwrap_err();
+            assert!(
+                err.to_string().contains("Duplicate route name: web"),
+                "Expected 'Duplicate route name' in error: {err}"
+            );
+        }
+
+        #[test]
+        fn should_reject_invalid_bind() {
+            let err = HttpSpec::parse(":8080", &routes(&["web=3000"])).unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid bind address"),
+                "Expected 'Invalid bind address' in error: {err}"
+            );
+        }
+    }
+
+    mod into_managed_spec {
+        use super::*;
+
+        #[test]
+        fn should_convert_open_spec_into_forward_tunnel() {
+            let spec: TunnelSpec = "8080:db-host:5432".parse().unwrap();
+            assert_eq!(
+                spec.into_forward(false),
+                ManagedTunnelSpec::Forward {
+                    bind_port: 8080,
+                    remote_host: "db-host".to_string(),
+                    remote_port: 5432,
+                    protocol: TunnelProtocol::Tcp,
+                    bind_path: None,
+                    remote_path: None,
+                }
+            );
+        }
+
+        #[test]
+        fn should_convert_listen_spec_into_reverse_tunnel() {
+            let spec: TunnelSpec = "9090:3000".parse().unwrap();
+            assert_eq!(
+                spec.into_reverse(true),
+                ManagedTunnelSpec::Reverse {
+                    remote_port: 9090,
+                    local_host: "127.0.0.1".to_string(),
+                    local_port: 3000,
+                    protocol: TunnelProtocol::Udp,
+                    remote_path: None,
+                    local_path: None,
+                }
+            );
+        }
+
+        #[test]
+        fn should_use_unix_protocol_for_socket_paths() {
+            let spec: TunnelSpec = "2375:/var/run/docker.sock".parse().unwrap();
+            assert_eq!(
+                spec.into_forward(false),
+                ManagedTunnelSpec::Forward {
+                    bind_port: 2375,
+                    remote_host: String::new(),
+                    remote_port: 0,
+                    protocol: TunnelProtocol::Unix,
+                    bind_path: None,
+                    remote_path: Some("/var/run/docker.sock".to_string()),
+                }
+            );
+        }
+
+        #[test]
+        fn should_convert_socks_spec_into_socks_tunnel() {
+            let spec: SocksSpec = "0.0.0.0:1080".parse().unwrap();
+            assert_eq!(
+                spec.into_socks(),
+                ManagedTunnelSpec::Socks {
+                    bind_host: "0.0.0.0".to_string(),
+                    bind_port: 1080,
+                }
+            );
+        }
+    }
+
     mod clone_and_equality {
         use super::*;
 
@@ -322,4 +1078,83 @@ mod tests {
             assert_ne!(a, b);
         }
     }
+
+    mod list_table {
+        use distant_core::net::manager::ManagedTunnelState;
+        use distant_core::protocol::TunnelStats;
+
+        use super::*;
+
+        fn tunnel(stats: TunnelStats) -> ManagedTunnelInfo {
+            ManagedTunnelInfo {
+                id: 1,
+                connection_id: 7,
+                direction: TunnelDirection::Forward,
+                bind_port: 8080,
+                remote_host: "db-host".to_string(),
+                remote_port: 5432,
+                protocol: TunnelProtocol::Tcp,
+                bind_path: None,
+                remote_path: None,
+                state: ManagedTunnelState::Active,
+                last_error: None,
+                stats,
+            }
+        }
+
+        #[test]
+        fn should_report_no_tunnels_when_empty() {
+            assert_eq!(tunnel_table(&[], 0), "No active tunnels\n");
+        }
+
+        #[test]
+        fn should_include_traffic_of_each_tunnel() {
+            let table = tunnel_table(
+                &[tunnel(TunnelStats {
+                    bytes_out: 512,
+                    bytes_in: 3 * 1024 * 1024,
+                    active_connections: 2,
+                    total_connections: 9,
+                    last_activity: Some(1000),
+                })],
+                1005,
+            );
+
+            let row = table.lines().nth(1).unwrap();
+            let columns: Vec<&str> = row.split_whitespace().collect();
+            assert_eq!(
+                columns,
+                [
+                    "1", "forward", "tcp", "8080", "db-host", "5432", "3.0", "MiB", "512", "B",
+                    "2/9", "5s", "ago", "active"
+                ]
+            );
+        }
+
+        #[test]
+        fn should_show_dash_for_tunnel_without_activity() {
+            let table = tunnel_table(&[tunnel(TunnelStats::default())], 1005);
+            let row = table.lines().nth(1).unwrap();
+            assert!(row.contains("0 B"), "Unexpected row: {row}");
+            assert!(row.contains("0/0"), "Unexpected row: {row}");
+            assert!(row.contains(" - "), "Unexpected row: {row}");
+        }
+
+        #[test]
+        fn format_bytes_should_use_binary_units() {
+            assert_eq!(format_bytes(0), "0 B");
+            assert_eq!(format_bytes(1023), "1023 B");
+            assert_eq!(format_bytes(1536), "1.5 KiB");
+            assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+        }
+
+        #[test]
+        fn format_elapsed_should_use_largest_whole_unit() {
+            assert_eq!(format_elapsed(0), "0s ago");
+            assert_eq!(format_elapsed(59), "59s ago");
+            assert_eq!(format_elapsed(60), "1m ago");
+            assert_eq!(format_elapsed(7200), "2h ago");
+            assert_eq!(format_elapsed(3 * 86400), "3d ago");
+        }
+    }
 }
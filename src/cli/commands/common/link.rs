@@ -42,6 +42,9 @@ macro_rules! from_pipes {
                             break Err(x);
                         }
                     } else {
+                        // Local stdin reached EOF, so release our handle to close the
+                        // stdin of the remote process as well
+                        drop(stdin_handle);
                         break Ok(());
                     }
                 }
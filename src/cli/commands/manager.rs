@@ -4,7 +4,10 @@ use std::sync::Arc;
 
 use anyhow::Context;
 use distant_core::Plugin;
-use distant_core::net::manager::Config as NetManagerConfig;
+use distant_core::net::common::KnownServers;
+use distant_core::net::manager::{
+    AutoForwardConfig, Config as NetManagerConfig, ManagedTunnelSpec,
+};
 use log::*;
 
 #[cfg(unix)]
@@ -12,7 +15,10 @@ use crate::CliError;
 use crate::CliResult;
 use crate::cli::Manager;
 use crate::cli::common::{Ui, connect_to_manager};
-use crate::options::{Format, ManagerSubcommand};
+use crate::constants::user::KNOWN_SERVERS_FILE_PATH;
+use crate::options::{Format, ManagerSubcommand, ManagerTunnelsConfig};
+
+use super::client::tunnel::{SocksSpec, TunnelSpec};
 
 mod plugins;
 
@@ -21,13 +27,14 @@ mod plugins;
 #[allow(clippy::vec_init_then_push)]
 fn build_plugin_map(
     extra_plugins: Vec<(String, PathBuf)>,
+    #[cfg_attr(not(feature = "host"), allow(unused_variables))] known_servers: KnownServers,
 ) -> anyhow::Result<HashMap<String, Arc<dyn Plugin>>> {
     let mut map: HashMap<String, Arc<dyn Plugin>> = HashMap::new();
 
     // Built-in plugins — conditionally populated based on enabled features
     let builtins: Vec<Arc<dyn Plugin>> = vec![
         #[cfg(feature = "host")]
-        Arc::new(distant_host::HostPlugin::new()),
+        Arc::new(distant_host::HostPlugin::new().with_known_servers(known_servers)),
         #[cfg(feature = "ssh")]
         Arc::new(distant_ssh::SshPlugin),
         #[cfg(feature = "docker")]
@@ -58,6 +65,39 @@ fn build_plugin_map(
     Ok(map)
 }
 
+/// Parse the tunnels declared for each destination in the config file into the tunnels that the
+/// manager starts whenever it connects to that destination.
+fn build_tunnel_map(
+    tunnels: HashMap<String, ManagerTunnelsConfig>,
+) -> anyhow::Result<HashMap<String, Vec<ManagedTunnelSpec>>> {
+    let mut map = HashMap::new();
+
+    for (destination, config) in tunnels {
+        let mut specs = Vec::new();
+        for spec in config.forward {
+            let parsed: TunnelSpec = spec.parse().with_context(|| {
+                format!("Invalid forward tunnel '{spec}' for destination {destination}")
+            })?;
+            specs.push(parsed.into_forward(false));
+        }
+        for spec in config.reverse {
+            let parsed: TunnelSpec = spec.parse().with_context(|| {
+                format!("Invalid reverse tunnel '{spec}' for destination {destination}")
+            })?;
+            specs.push(parsed.into_reverse(false));
+        }
+        for spec in config.socks {
+            let parsed: SocksSpec = spec.parse().with_context(|| {
+                format!("Invalid socks tunnel '{spec}' for destination {destination}")
+            })?;
+            specs.push(parsed.into_socks());
+        }
+        map.insert(destination, specs);
+    }
+
+    Ok(map)
+}
+
 pub fn run(cmd: ManagerSubcommand, quiet: bool) -> CliResult {
     match &cmd {
         ManagerSubcommand::Listen { daemon, .. } if *daemon => run_daemon(cmd, quiet),
@@ -111,6 +151,10 @@ async fn async_run(cmd: ManagerSubcommand, quiet: bool) -> CliResult {
             network,
             user,
             plugin: extra_plugins,
+            known_servers_file,
+            server_identity_check,
+            auto_forward,
+            tunnels,
         } => {
             #[cfg(unix)]
             let access = access.unwrap_or_default();
@@ -128,7 +172,38 @@ async fn async_run(cmd: ManagerSubcommand, quiet: bool) -> CliResult {
                 }
             );
 
-            let plugins = build_plugin_map(extra_plugins).context("Failed to register plugins")?;
+            let known_servers = KnownServers::new(
+                known_servers_file.unwrap_or_else(|| KNOWN_SERVERS_FILE_PATH.to_path_buf()),
+            )
+            .with_policy(server_identity_check.unwrap_or_default());
+            debug!(
+                "Checking server identities ({}) using {:?}",
+                known_servers.policy(),
+                known_servers.path()
+            );
+
+            let plugins = build_plugin_map(extra_plugins, known_servers)
+                .context("Failed to register plugins")?;
+
+            let auto_forward = auto_forward.auto_forward.then(|| AutoForwardConfig {
+                interval: auto_forward
+                    .auto_forward_interval
+                    .map(Into::into)
+                    .unwrap_or(AutoForwardConfig::DEFAULT_INTERVAL),
+                allow: auto_forward.auto_forward_allow,
+                deny: auto_forward.auto_forward_deny,
+            });
+            if let Some(config) = auto_forward.as_ref() {
+                debug!(
+                    "Automatically forwarding listening ports every {:?}",
+                    config.interval
+                );
+            }
+
+            let tunnels = build_tunnel_map(tunnels).context("Failed to read declared tunnels")?;
+            for (destination, specs) in tunnels.iter() {
+                debug!("Starting {} tunnel(s) for {destination}", specs.len());
+            }
 
             let manager = Manager {
                 #[cfg(unix)]
@@ -136,6 +211,8 @@ async fn async_run(cmd: ManagerSubcommand, quiet: bool) -> CliResult {
                 config: NetManagerConfig {
                     user,
                     plugins,
+                    auto_forward,
+                    tunnels,
                     ..Default::default()
                 },
                 network,
@@ -187,12 +264,64 @@ mod tests {
 
     use super::*;
 
+    fn test_known_servers() -> KnownServers {
+        KnownServers::new("known_servers")
+    }
+
+    // -------------------------------------------------------
+    // build_tunnel_map
+    // -------------------------------------------------------
+    #[test]
+    fn build_tunnel_map_parses_specs_for_each_destination() {
+        let tunnels = HashMap::from([(
+            "ssh://example.com".to_string(),
+            ManagerTunnelsConfig {
+                forward: vec!["8080:3000".to_string()],
+                reverse: vec!["9090:3000".to_string()],
+                socks: vec!["1080".to_string()],
+            },
+        )]);
+
+        let map = build_tunnel_map(tunnels).unwrap();
+        assert_eq!(
+            map["ssh://example.com"],
+            vec![
+                "8080:3000"
+                    .parse::<TunnelSpec>()
+                    .unwrap()
+                    .into_forward(false),
+                "9090:3000"
+                    .parse::<TunnelSpec>()
+                    .unwrap()
+                    .into_reverse(false),
+                "1080".parse::<SocksSpec>().unwrap().into_socks(),
+            ]
+        );
+    }
+
+    #[test]
+    fn build_tunnel_map_rejects_invalid_specs() {
+        let tunnels = HashMap::from([(
+            "ssh://example.com".to_string(),
+            ManagerTunnelsConfig {
+                reverse: vec!["not-a-spec".to_string()],
+                ..Default::default()
+            },
+        )]);
+
+        let err = build_tunnel_map(tunnels).unwrap_err().to_string();
+        assert!(
+            err.contains("not-a-spec") && err.contains("ssh://example.com"),
+            "error should mention the spec and destination: {err}"
+        );
+    }
+
     // -------------------------------------------------------
     // build_plugin_map — no extra plugins
     // -------------------------------------------------------
     #[test]
     fn build_plugin_map_with_no_extras_has_builtins() {
-        let map = build_plugin_map(Vec::new()).unwrap();
+        let map = build_plugin_map(Vec::new(), test_known_servers()).unwrap();
         #[cfg(feature = "host")]
         assert!(map.contains_key("distant"), "missing 'distant' scheme");
         #[cfg(feature = "ssh")]
@@ -203,7 +332,7 @@ mod tests {
     #[cfg(all(feature = "host", feature = "ssh"))]
     #[test]
     fn build_plugin_map_builtins_have_correct_names() {
-        let map = build_plugin_map(Vec::new()).unwrap();
+        let map = build_plugin_map(Vec::new(), test_known_servers()).unwrap();
         assert_eq!(map["distant"].name(), "distant");
         assert_eq!(map["ssh"].name(), "ssh");
     }
@@ -213,7 +342,7 @@ mod tests {
     // -------------------------------------------------------
     #[test]
     fn build_plugin_map_schemes_are_lowercase() {
-        let map = build_plugin_map(Vec::new()).unwrap();
+        let map = build_plugin_map(Vec::new(), test_known_servers()).unwrap();
         for key in map.keys() {
             assert_eq!(key, &key.to_lowercase(), "scheme should be lowercase");
         }
@@ -228,7 +357,7 @@ mod tests {
             "myplugin".to_string(),
             PathBuf::from("/usr/local/bin/myplugin"),
         )];
-        let map = build_plugin_map(extras).unwrap();
+        let map = build_plugin_map(extras, test_known_servers()).unwrap();
         #[cfg(feature = "host")]
         assert!(map.contains_key("distant"));
         #[cfg(feature = "ssh")]
@@ -245,7 +374,7 @@ mod tests {
         // Trying to register a plugin with scheme "ssh" should fail
         // because SSH is already a builtin
         let extras = vec![("ssh".to_string(), PathBuf::from("/usr/local/bin/other-ssh"))];
-        let result = build_plugin_map(extras);
+        let result = build_plugin_map(extras, test_known_servers());
         assert!(result.is_err(), "should reject duplicate 'ssh' scheme");
         let err = result.err().unwrap().to_string();
         assert!(
@@ -263,7 +392,7 @@ mod tests {
             ("custom".to_string(), PathBuf::from("/bin/custom-plugin")),
             ("k8s".to_string(), PathBuf::from("/bin/k8s-plugin")),
         ];
-        let map = build_plugin_map(extras).unwrap();
+        let map = build_plugin_map(extras, test_known_servers()).unwrap();
         assert!(map.contains_key("custom"));
         assert!(map.contains_key("k8s"));
         #[cfg(feature = "host")]
@@ -3,12 +3,15 @@ use std::io::{self, Read, Write};
 use anyhow::Context;
 use distant_core::Credentials;
 use distant_core::net::auth::Verifier;
-use distant_core::net::common::{Host, SecretKey32, Version};
+use distant_core::net::common::{Host, SecretKey32, ServerIdentity, TlsServerConfig, Version};
 use distant_core::net::server::{Server, ServerConfig as NetServerConfig};
 use distant_core::protocol::PROTOCOL_VERSION;
-use distant_host::{Config as LocalConfig, WatchConfig as LocalWatchConfig};
+use distant_host::{
+    Config as LocalConfig, TunnelConfig as LocalTunnelConfig, WatchConfig as LocalWatchConfig,
+};
 use log::*;
 
+use crate::constants::user::SERVER_IDENTITY_FILE_PATH;
 use crate::options::ServerSubcommand;
 use crate::{CliError, CliResult};
 
@@ -26,7 +29,7 @@ pub fn run(cmd: ServerSubcommand) -> CliResult {
 fn run_daemon(_cmd: ServerSubcommand) -> CliResult {
     use std::ffi::OsString;
 
-    use distant_core::net::common::{Listener, TransportExt, WindowsPipeListener};
+    use distant_core::net::common::{Destination, Listener, TransportExt, WindowsPipeListener};
 
     use crate::cli::Spawner;
     let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
@@ -57,10 +60,11 @@ fn run_daemon(_cmd: ServerSubcommand) -> CliResult {
         if n == 0 {
             anyhow::bail!("No credentials received from spawned server");
         }
+        // NOTE: Parsed as a destination as tls, quic, and websocket servers report their own scheme
         let credentials = s[..n]
             .trim()
-            .parse::<Credentials>()
-            .context("Failed to parse server credentials")?;
+            .parse::<Destination>()
+            .map_err(|x| anyhow::anyhow!("Failed to parse server credentials: {x}"))?;
 
         println!("\r");
         println!("{}", credentials);
@@ -104,11 +108,16 @@ async fn async_run(cmd: ServerSubcommand, _is_forked: bool) -> CliResult {
             host,
             port,
             use_ipv6,
+            websocket,
+            quic,
+            tls,
             shutdown,
             current_dir,
             watch,
+            tunnel,
             daemon: _,
             key_from_stdin,
+            identity_file,
             output_to_local_pipe,
         } => {
             let host = host.into_inner();
@@ -143,7 +152,19 @@ async fn async_run(cmd: ServerSubcommand, _is_forked: bool) -> CliResult {
                     "using an ephemeral port".to_string()
                 }
             );
+            if tunnel != Default::default() {
+                debug!("Limiting tunnels using {tunnel:?}");
+            }
             let handler = distant_host::new_handler(LocalConfig {
+                tunnel: LocalTunnelConfig {
+                    allow_targets: tunnel.allow_targets,
+                    deny_targets: tunnel.deny_targets,
+                    allow_target_ports: tunnel.allow_target_ports,
+                    deny_target_ports: tunnel.deny_target_ports,
+                    allow_bind_addrs: tunnel.allow_bind_addrs,
+                    allow_bind_ports: tunnel.allow_bind_ports,
+                    unix_sockets: tunnel.unix_sockets,
+                },
                 watch: LocalWatchConfig {
                     native: !watch.watch_polling,
                     poll_interval: watch.watch_poll_interval.map(Into::into),
@@ -153,21 +174,91 @@ async fn async_run(cmd: ServerSubcommand, _is_forked: bool) -> CliResult {
                 },
             })
             .context("Failed to create local distant api")?;
-            let server = Server::tcp()
-                .config(NetServerConfig {
-                    shutdown: shutdown.into_inner(),
-                    ..Default::default()
-                })
-                .handler(handler)
-                .verifier(Verifier::static_key(key.clone()))
-                .version(Version::new(
-                    PROTOCOL_VERSION.major,
-                    PROTOCOL_VERSION.minor,
-                    PROTOCOL_VERSION.patch,
-                ))
-                .start(addr, port)
-                .await
-                .with_context(|| format!("Failed to start server @ {addr} with {port}"))?;
+            // Load the identity used to prove to clients that we are the same server as before,
+            // falling back to one that only lasts as long as this server if the default cannot
+            // be used (e.g. read-only config directory)
+            let identity = match identity_file {
+                Some(path) => ServerIdentity::load_or_generate(&path)
+                    .with_context(|| format!("Failed to load server identity from {path:?}"))?,
+                None => {
+                    let path = SERVER_IDENTITY_FILE_PATH.as_path();
+                    ServerIdentity::load_or_generate(path).unwrap_or_else(|x| {
+                        warn!("Failed to load server identity from {path:?}, using a temporary one: {x}");
+                        ServerIdentity::generate()
+                    })
+                }
+            };
+            debug!(
+                "Using server identity {}",
+                identity.public_key().fingerprint()
+            );
+
+            let config = NetServerConfig {
+                shutdown: shutdown.into_inner(),
+                identity: Some(identity),
+                ..Default::default()
+            };
+            let tls = match tls.tls_cert {
+                Some(cert_path) => {
+                    let key_path = tls
+                        .tls_key
+                        .context("A TLS private key must accompany the certificate")?;
+                    Some(
+                        TlsServerConfig::from_pem_files(
+                            &cert_path,
+                            &key_path,
+                            tls.tls_client_ca.as_deref(),
+                        )
+                        .context("Failed to load TLS certificate")?,
+                    )
+                }
+                None => None,
+            };
+
+            // With mutual TLS, clients authenticate using their certificate instead of the key
+            let verifier = match &tls {
+                Some(tls) if tls.requires_client_certificate() => Verifier::certificate(),
+                _ => Verifier::static_key(key.clone()),
+            };
+            let version = Version::new(
+                PROTOCOL_VERSION.major,
+                PROTOCOL_VERSION.minor,
+                PROTOCOL_VERSION.patch,
+            );
+            let server = if let Some(tls) = tls.clone() {
+                Server::tls(tls)
+                    .config(config)
+                    .handler(handler)
+                    .verifier(verifier)
+                    .version(version)
+                    .start(addr, port)
+                    .await
+            } else if quic {
+                Server::quic()
+                    .config(config)
+                    .handler(handler)
+                    .verifier(verifier)
+                    .version(version)
+                    .start(addr, port)
+                    .await
+            } else if websocket {
+                Server::websocket()
+                    .config(config)
+                    .handler(handler)
+                    .verifier(verifier)
+                    .version(version)
+                    .start(addr, port)
+                    .await
+            } else {
+                Server::tcp()
+                    .config(config)
+                    .handler(handler)
+                    .verifier(verifier)
+                    .version(version)
+                    .start(addr, port)
+                    .await
+            }
+            .with_context(|| format!("Failed to start server @ {addr} with {port}"))?;
 
             let credentials = Credentials {
                 host: Host::from(addr),
@@ -180,6 +271,28 @@ async fn async_run(cmd: ServerSubcommand, _is_forked: bool) -> CliResult {
                 credentials.host, credentials.port
             );
 
+            // TLS, QUIC, and WebSocket servers are reached with their own scheme instead of
+            // distant://
+            let scheme = if tls.is_some() {
+                Some("tls")
+            } else if quic {
+                Some("quic")
+            } else if websocket {
+                Some("ws")
+            } else {
+                None
+            };
+            let credentials = match scheme {
+                Some(scheme) => {
+                    let mut destination = credentials
+                        .try_to_destination()
+                        .context("Failed to convert credentials into destination")?;
+                    destination.scheme = Some(scheme.to_string());
+                    destination.to_string()
+                }
+                None => credentials.to_string(),
+            };
+
             // Print information about port, key, etc.
             // NOTE: Following mosh approach of printing to make sure there's no garbage floating around
             #[cfg(not(windows))]
@@ -28,6 +28,15 @@ pub mod user {
     pub static CONFIG_FILE_PATH: LazyLock<PathBuf> =
         LazyLock::new(|| PROJECT_DIR.config_dir().join("config.toml"));
 
+    /// Path to file of server identities trusted by the distant manager
+    pub static KNOWN_SERVERS_FILE_PATH: LazyLock<PathBuf> =
+        LazyLock::new(|| PROJECT_DIR.config_dir().join("known_servers"));
+
+    /// Path to private key that identifies the distant server to clients
+    #[cfg(feature = "host")]
+    pub static SERVER_IDENTITY_FILE_PATH: LazyLock<PathBuf> =
+        LazyLock::new(|| PROJECT_DIR.config_dir().join("server_identity.pem"));
+
     /// Path to cache file used for arbitrary CLI data
     pub static CACHE_FILE_PATH: LazyLock<PathBuf> =
         LazyLock::new(|| PROJECT_DIR.cache_dir().join("cache.toml"));
@@ -168,6 +177,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn user_known_servers_file_path_ends_with_known_servers() {
+        let path = user::KNOWN_SERVERS_FILE_PATH.as_path();
+        assert!(
+            path.ends_with("known_servers"),
+            "Expected path to end with known_servers, got: {path:?}"
+        );
+    }
+
     #[test]
     fn user_cache_file_path_ends_with_cache_toml() {
         let path = user::CACHE_FILE_PATH.as_path();
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
@@ -15,7 +16,7 @@ use clap_complete::Shell as ClapCompleteShell;
 use derive_more::{Display, Error, From, IsVariant};
 #[cfg(feature = "host")]
 use distant_core::net::common::PortRange;
-use distant_core::net::common::{ConnectionId, Map};
+use distant_core::net::common::{ConnectionId, Map, ServerIdentityPolicy};
 #[cfg(feature = "host")]
 use distant_core::net::server::Shutdown;
 use distant_core::protocol::ChangeKind;
@@ -173,7 +174,7 @@ impl Options {
                         network, options, ..
                     } => {
                         network.merge(config.client.network);
-                        options.merge(config.client.connect.options, /* keep */ true);
+                        options.merge(config.client.connect.into(), /* keep */ true);
                     }
                     ClientSubcommand::Copy { network, .. } => {
                         network.merge(config.client.network);
@@ -196,8 +197,11 @@ impl Options {
                     ClientSubcommand::Tunnel(
                         ClientTunnelSubcommand::Open { network, .. }
                         | ClientTunnelSubcommand::Listen { network, .. }
+                        | ClientTunnelSubcommand::Socks { network, .. }
+                        | ClientTunnelSubcommand::Http { network, .. }
                         | ClientTunnelSubcommand::Close { network, .. }
-                        | ClientTunnelSubcommand::List { network, .. },
+                        | ClientTunnelSubcommand::List { network, .. }
+                        | ClientTunnelSubcommand::Auto { network, .. },
                     ) => {
                         network.merge(config.client.network);
                     }
@@ -258,10 +262,51 @@ impl Options {
                         network.merge(config.manager.network);
                     }
                     ManagerSubcommand::Listen {
-                        access, network, ..
+                        access,
+                        known_servers_file,
+                        server_identity_check,
+                        auto_forward,
+                        tunnels,
+                        network,
+                        ..
                     } => {
                         *access = access.take().or(config.manager.access);
+                        *known_servers_file = known_servers_file
+                            .take()
+                            .or(config.manager.known_servers_file);
+                        *server_identity_check = server_identity_check
+                            .take()
+                            .or(config.manager.server_identity_check);
                         network.merge(config.manager.network);
+
+                        //
+                        // AUTO-FORWARD-SPECIFIC SETTINGS
+                        //
+
+                        if !auto_forward.auto_forward && config.manager.auto_forward.enabled {
+                            auto_forward.auto_forward = true;
+                        }
+
+                        auto_forward.auto_forward_interval = auto_forward
+                            .auto_forward_interval
+                            .take()
+                            .or(config.manager.auto_forward.interval);
+
+                        if auto_forward.auto_forward_allow.is_empty() {
+                            auto_forward.auto_forward_allow = config.manager.auto_forward.allow;
+                        }
+
+                        if auto_forward.auto_forward_deny.is_empty() {
+                            auto_forward.auto_forward_deny = config.manager.auto_forward.deny;
+                        }
+
+                        //
+                        // TUNNEL-SPECIFIC SETTINGS
+                        //
+
+                        if tunnels.is_empty() {
+                            *tunnels = config.manager.tunnels;
+                        }
                     }
                 }
             }
@@ -272,10 +317,13 @@ impl Options {
                     ServerSubcommand::Listen {
                         current_dir,
                         host,
+                        identity_file,
                         port,
                         shutdown,
                         use_ipv6,
+                        tls,
                         watch,
+                        tunnel,
                         ..
                     } => {
                         //
@@ -283,6 +331,8 @@ impl Options {
                         //
 
                         *current_dir = current_dir.take().or(config.server.listen.current_dir);
+                        *identity_file =
+                            identity_file.take().or(config.server.listen.identity_file);
                         if host.is_default()
                             && let Some(host_value) = config.server.listen.host
                         {
@@ -302,6 +352,17 @@ impl Options {
                             *use_ipv6 = true;
                         }
 
+                        //
+                        // TLS-SPECIFIC SETTINGS
+                        //
+
+                        tls.tls_cert = tls.tls_cert.take().or(config.server.listen.tls_cert);
+                        tls.tls_key = tls.tls_key.take().or(config.server.listen.tls_key);
+                        tls.tls_client_ca = tls
+                            .tls_client_ca
+                            .take()
+                            .or(config.server.listen.tls_client_ca);
+
                         //
                         // WATCH-SPECIFIC SETTINGS
                         //
@@ -329,6 +390,12 @@ impl Options {
                             .watch_debounce_tick_rate
                             .take()
                             .or(config.server.watch.debounce_tick_rate);
+
+                        //
+                        // TUNNEL-SPECIFIC SETTINGS
+                        //
+
+                        *tunnel = config.server.tunnel;
                     }
                 }
             }
@@ -386,7 +453,8 @@ pub enum ClientSubcommand {
         )]
         cache: PathBuf,
 
-        /// Represents the maximum time (in seconds) to wait for a network request before timing out.
+        /// Represents the maximum time (in seconds) to wait for a network request before timing out,
+        /// which is also sent to the server as the deadline of any request that lacks one.
         #[clap(long)]
         timeout: Option<Seconds>,
 
@@ -571,6 +639,10 @@ pub enum ClientSubcommand {
         #[clap(long, default_value = "adaptive", value_enum)]
         predict: PredictMode,
 
+        /// Forward the local SSH agent (from SSH_AUTH_SOCK) to the remote process
+        #[clap(short = 'A', long)]
+        forward_agent: bool,
+
         /// Optional command to run instead of $SHELL
         #[clap(name = "CMD", last = true)]
         cmd: Option<Vec<String>>,
@@ -610,6 +682,16 @@ pub enum ClientSubcommand {
         #[clap(long, name = "SHELL")]
         shell: Option<Option<Shell>>,
 
+        /// If specified, will spawn the process through the remote user's login shell so that
+        /// it sees the environment (e.g. PATH) set up by their profile
+        #[clap(long)]
+        login: bool,
+
+        /// Forward the local SSH agent (from SSH_AUTH_SOCK) to the remote process, which is not
+        /// supported for LSP servers
+        #[clap(short = 'A', long, conflicts_with = "SCHEME")]
+        forward_agent: bool,
+
         /// Alternative current directory for the remote process
         #[clap(long)]
         current_dir: Option<PathBuf>,
@@ -618,6 +700,30 @@ pub enum ClientSubcommand {
         #[clap(long, default_value_t)]
         environment: Map,
 
+        /// If specified, the remote process will not inherit the environment of the server,
+        /// only receiving the variables that are explicitly provided
+        #[clap(long)]
+        clear_env: bool,
+
+        /// Environment variable to remove from the environment inherited by the remote process,
+        /// which can be provided multiple times
+        #[clap(long, value_name = "KEY")]
+        unset_env: Vec<String>,
+
+        /// Path on the remote machine to a file of KEY=VALUE lines to load into the environment
+        /// of the remote process, overridden by any variables provided via --environment
+        #[clap(long, value_name = "PATH")]
+        env_file: Option<PathBuf>,
+
+        /// Maximum time (in seconds) the remote process can run before it is killed
+        #[clap(long)]
+        timeout: Option<Seconds>,
+
+        /// Maximum virtual memory of the remote process, in bytes or with a unit suffix
+        /// (e.g. 512M, 2G)
+        #[clap(long)]
+        memory: Option<ByteSize>,
+
         /// Predictive local echo mode for PTY sessions.
         /// Modes: "adaptive" (auto based on RTT), "on" (always), "off" (never),
         /// "fast" (always, skip epoch confirmation), "fast-adaptive" (adaptive + skip epoch).
@@ -724,6 +830,10 @@ pub enum ClientSubcommand {
         #[clap(long, default_value = "adaptive", value_enum)]
         predict: PredictMode,
 
+        /// Forward the local SSH agent (from SSH_AUTH_SOCK) to the remote process
+        #[clap(short = 'A', long)]
+        forward_agent: bool,
+
         /// Force a new connection even if one to the same destination already exists
         #[clap(long)]
         new: bool,
@@ -1285,13 +1395,23 @@ pub enum ClientTunnelSubcommand {
     ///   distant tunnel open 8080:3000                    # remote localhost:3000
     ///
     ///   distant tunnel open 5432:internal-db.corp:5432   # third-party host via remote
+    ///
+    ///   distant tunnel open --udp 5353:10.0.0.2:53       # remote DNS server over UDP
+    ///
+    ///   distant tunnel open 2375:/var/run/docker.sock    # remote Unix socket
     Open {
         /// Tunnel spec: LOCAL_PORT[:REMOTE_HOST]:REMOTE_PORT
         ///
-        /// REMOTE_HOST defaults to 127.0.0.1 when omitted (e.g. 8080:3000).
+        /// REMOTE_HOST defaults to 127.0.0.1 when omitted (e.g. 8080:3000). Either
+        /// LOCAL_PORT or [REMOTE_HOST:]REMOTE_PORT may instead be an absolute Unix
+        /// socket path (e.g. 2375:/var/run/docker.sock).
         #[clap(value_name = "SPEC")]
         spec: String,
 
+        /// Tunnel UDP datagrams instead of TCP connections
+        #[clap(long)]
+        udp: bool,
+
         /// Location to store cached data
         #[clap(
             long,
@@ -1321,13 +1441,103 @@ pub enum ClientTunnelSubcommand {
     ///   distant tunnel listen 9090:3000                  # local localhost:3000
     ///
     ///   distant tunnel listen 9090:dev-server:3000       # third-party host via local machine
+    ///
+    ///   distant tunnel listen --udp 8125:8125            # local StatsD agent over UDP
+    ///
+    ///   distant tunnel listen /tmp/agent.sock:/tmp/agent.sock  # local Unix socket
     Listen {
         /// Tunnel spec: REMOTE_PORT[:LOCAL_HOST]:LOCAL_PORT
         ///
-        /// LOCAL_HOST defaults to 127.0.0.1 when omitted (e.g. 9090:3000).
+        /// LOCAL_HOST defaults to 127.0.0.1 when omitted (e.g. 9090:3000). Either
+        /// REMOTE_PORT or [LOCAL_HOST:]LOCAL_PORT may instead be an absolute Unix
+        /// socket path (e.g. /tmp/agent.sock:3000).
         #[clap(value_name = "SPEC")]
         spec: String,
 
+        /// Tunnel UDP datagrams instead of TCP connections
+        #[clap(long)]
+        udp: bool,
+
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+
+        /// Specify a connection being managed
+        #[clap(long)]
+        connection: Option<ConnectionId>,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+    },
+
+    /// Start a SOCKS proxy that tunnels each connection (local port -> any remote host:port).
+    ///
+    /// Binds a port on your local machine that speaks SOCKS5 and SOCKS4a, like
+    /// `ssh -D`. Every connection through the proxy opens its own tunnel to the
+    /// host and port that it asks for, which the remote server connects to.
+    ///
+    /// Examples:
+    ///
+    ///   distant tunnel socks 1080                        # 127.0.0.1:1080
+    ///
+    ///   distant tunnel socks 0.0.0.0:1080                # reachable from other machines
+    Socks {
+        /// Proxy spec: [BIND_HOST:]PORT
+        ///
+        /// BIND_HOST defaults to 127.0.0.1 when omitted (e.g. 1080).
+        #[clap(value_name = "SPEC")]
+        spec: String,
+
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+
+        /// Specify a connection being managed
+        #[clap(long)]
+        connection: Option<ConnectionId>,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+    },
+
+    /// Start an HTTP proxy that serves remote web apps by name (NAME.localhost -> remote host:port).
+    ///
+    /// Binds a port on your local machine that speaks HTTP/1.1, where each ROUTE is
+    /// served at `http://NAME.localhost:PORT` so that several web apps can share the
+    /// port. Requests are tunneled to the route's remote target with the `Host`
+    /// header rewritten to match it, and WebSocket upgrades are passed through.
+    ///
+    /// Examples:
+    ///
+    ///   distant tunnel http web=3000                     # http://web.localhost:8080
+    ///
+    ///   distant tunnel http web=3000 api=api-host:4000   # two apps on one port
+    ///
+    ///   distant tunnel http --bind 9000 docs=8000        # http://docs.localhost:9000
+    Http {
+        /// Route spec: NAME=[REMOTE_HOST:]REMOTE_PORT
+        ///
+        /// REMOTE_HOST defaults to 127.0.0.1 when omitted (e.g. web=3000). NAME may
+        /// contain letters, digits, '-' and '.'.
+        #[clap(value_name = "ROUTE", required = true)]
+        routes: Vec<String>,
+
+        /// Local address to serve the proxy on: [BIND_HOST:]PORT
+        ///
+        /// BIND_HOST defaults to 127.0.0.1 when omitted.
+        #[clap(long, value_name = "[BIND_HOST:]PORT", default_value = "8080")]
+        bind: String,
+
         /// Location to store cached data
         #[clap(
             long,
@@ -1367,7 +1577,7 @@ pub enum ClientTunnelSubcommand {
         network: NetworkSettings,
     },
 
-    /// List active tunnels
+    /// List active tunnels, along with the traffic that has passed through each of them
     List {
         /// Location to store cached data
         #[clap(
@@ -1382,6 +1592,42 @@ pub enum ClientTunnelSubcommand {
         #[clap(long)]
         connection: Option<ConnectionId>,
 
+        /// Keep refreshing the list until interrupted, every number of seconds (default 1)
+        #[clap(
+            long,
+            value_name = "SECONDS",
+            num_args = 0..=1,
+            default_missing_value = "1"
+        )]
+        watch: Option<Seconds>,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+    },
+
+    /// Follow ports that the manager forwards automatically as remote processes listen on them.
+    ///
+    /// Requires the manager to be started with `--auto-forward` (or `enabled = true` under
+    /// `[manager.auto_forward]` in the config). Prints a line whenever a new remote port is
+    /// forwarded or a forwarded port stops being listened on, until interrupted.
+    ///
+    /// Examples:
+    ///
+    ///   distant tunnel auto                              # active connection
+    Auto {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+
+        /// Specify a connection being managed
+        #[clap(long)]
+        connection: Option<ConnectionId>,
+
         #[clap(flatten)]
         network: NetworkSettings,
     },
@@ -1393,8 +1639,11 @@ impl ClientTunnelSubcommand {
         match self {
             Self::Open { cache, .. }
             | Self::Listen { cache, .. }
+            | Self::Socks { cache, .. }
+            | Self::Http { cache, .. }
             | Self::Close { cache, .. }
-            | Self::List { cache, .. } => cache.as_path(),
+            | Self::List { cache, .. }
+            | Self::Auto { cache, .. } => cache.as_path(),
         }
     }
 
@@ -1403,8 +1652,11 @@ impl ClientTunnelSubcommand {
         match self {
             Self::Open { network, .. }
             | Self::Listen { network, .. }
+            | Self::Socks { network, .. }
+            | Self::Http { network, .. }
             | Self::Close { network, .. }
-            | Self::List { network, .. } => network,
+            | Self::List { network, .. }
+            | Self::Auto { network, .. } => network,
         }
     }
 
@@ -1413,8 +1665,11 @@ impl ClientTunnelSubcommand {
         match self {
             Self::Open { connection, .. }
             | Self::Listen { connection, .. }
+            | Self::Socks { connection, .. }
+            | Self::Http { connection, .. }
             | Self::Close { connection, .. }
-            | Self::List { connection, .. } => *connection,
+            | Self::List { connection, .. }
+            | Self::Auto { connection, .. } => *connection,
         }
     }
 
@@ -1489,6 +1744,31 @@ pub enum ManagerSubcommand {
         #[clap(long = "plugin", value_parser = parse_plugin_flag)]
         plugin: Vec<(String, PathBuf)>,
 
+        /// Path to the file of server identities trusted when connecting directly to distant
+        /// servers, defaulting to a file within the user's config directory
+        #[clap(long, value_name = "PATH")]
+        known_servers_file: Option<PathBuf>,
+
+        /// How to check the identity of distant servers connected to directly
+        ///
+        /// 1. "ask" asks whether to trust a server seen for the first time
+        /// 2. "accept-new" trusts a server seen for the first time without asking
+        /// 3. "strict" refuses to connect to a server seen for the first time
+        /// 4. "off" does not check the identity of servers
+        ///
+        /// Regardless of the choice, connecting to a known server whose identity has changed fails
+        /// unless the check is off. Default is "ask"
+        #[clap(long, value_name = "ask|accept-new|strict|off")]
+        server_identity_check: Option<ServerIdentityPolicy>,
+
+        #[clap(flatten)]
+        auto_forward: ManagerListenAutoForwardOptions,
+
+        /// Tunnels to start whenever the manager connects to a destination, keyed by
+        /// destination, which can only be provided through the config file
+        #[clap(skip)]
+        tunnels: HashMap<String, ManagerTunnelsConfig>,
+
         #[clap(flatten)]
         network: NetworkSettings,
     },
@@ -1514,6 +1794,29 @@ impl ManagerSubcommand {
     }
 }
 
+#[derive(Args, Debug, Default, PartialEq, Eq)]
+pub struct ManagerListenAutoForwardOptions {
+    /// If specified, will watch each connection for ports that remote processes start listening
+    /// on and forward them to the same port on this machine, or any free port if it is taken
+    #[clap(long)]
+    pub auto_forward: bool,
+
+    /// Represents the time (in seconds) between checks for new listening ports, only relevant
+    /// when automatically forwarding ports. Default is 2 seconds
+    #[clap(long, value_name = "SECONDS")]
+    pub auto_forward_interval: Option<Seconds>,
+
+    /// Ports to forward automatically, defaulting to all ports. Can be specified multiple
+    /// times or as a comma-separated list
+    #[clap(long, value_name = "PORT[:PORT2]", value_delimiter = ',')]
+    pub auto_forward_allow: Vec<PortRange>,
+
+    /// Ports to never forward automatically, even if they are also allowed. Can be specified
+    /// multiple times or as a comma-separated list
+    #[clap(long, value_name = "PORT[:PORT2]", value_delimiter = ',')]
+    pub auto_forward_deny: Vec<PortRange>,
+}
+
 /// Subcommands for `distant server`.
 #[cfg(feature = "host")]
 #[derive(Debug, PartialEq, Subcommand, IsVariant)]
@@ -1549,6 +1852,23 @@ pub enum ServerSubcommand {
         #[clap(short = '6', long)]
         use_ipv6: bool,
 
+        /// If specified, will accept WebSocket connections instead of raw TCP, allowing the server
+        /// to be fronted by an HTTP reverse proxy such as nginx
+        ///
+        /// Clients connect using the `ws://` or `wss://` scheme in place of `distant://`
+        #[clap(long)]
+        websocket: bool,
+
+        /// If specified, will accept QUIC connections over UDP instead of raw TCP, which survive
+        /// the client changing networks and avoid head-of-line blocking from lost packets
+        ///
+        /// Clients connect using the `quic://` scheme in place of `distant://`
+        #[clap(long, conflicts_with = "websocket")]
+        quic: bool,
+
+        #[clap(flatten)]
+        tls: ServerListenTlsOptions,
+
         /// Logic to apply to server when determining when to shutdown automatically
         ///
         /// 1. "never" means the server will never automatically shut down
@@ -1570,12 +1890,24 @@ pub enum ServerSubcommand {
         #[clap(flatten)]
         watch: ServerListenWatchOptions,
 
+        /// Limits on where tunnels opened by clients can connect to and listen on, which can
+        /// only be provided through the config file
+        #[clap(skip)]
+        tunnel: ServerTunnelConfig,
+
         /// If specified, the server will not generate a key but instead listen on stdin for the next
         /// 32 bytes that it will use as the key instead. Receiving less than 32 bytes before stdin
         /// is closed is considered an error and any bytes after the first 32 are not used for the key
         #[clap(long)]
         key_from_stdin: bool,
 
+        /// Path to the PEM-encoded private key that identifies this server to clients, which
+        /// remember it and refuse to connect if it ever changes
+        ///
+        /// Generated on first use if missing. Defaults to a file within the user's config directory
+        #[clap(long, value_name = "PATH")]
+        identity_file: Option<PathBuf>,
+
         /// If specified, will send output to the specified named pipe (internal usage)
         #[clap(long, help = None, long_help = None)]
         output_to_local_pipe: Option<std::ffi::OsString>,
@@ -1620,6 +1952,32 @@ pub struct ServerListenWatchOptions {
     pub watch_debounce_tick_rate: Option<Seconds>,
 }
 
+#[cfg(feature = "host")]
+#[derive(Args, Debug, Default, PartialEq)]
+pub struct ServerListenTlsOptions {
+    /// If specified, will accept TLS connections over TCP using the PEM-encoded certificate
+    /// chain at this path, which must be accompanied by `--tls-key`
+    ///
+    /// Clients connect using the `tls://` scheme in place of `distant://`
+    #[clap(
+        long,
+        value_name = "PATH",
+        requires = "tls_key",
+        conflicts_with_all = ["websocket", "quic"]
+    )]
+    pub tls_cert: Option<PathBuf>,
+
+    /// Path to the PEM-encoded private key for the certificate provided by `--tls-cert`
+    #[clap(long, value_name = "PATH", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// If specified, clients must present a certificate issued by one of the PEM-encoded
+    /// certificate authorities at this path (mutual TLS), which is then used to authenticate
+    /// them in place of the static key
+    #[clap(long, value_name = "PATH", requires = "tls_cert")]
+    pub tls_client_ca: Option<PathBuf>,
+}
+
 /// Represents the format to use for output from a command.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
 #[clap(rename_all = "snake_case")]
@@ -1808,6 +2166,7 @@ mod tests {
                 },
                 connect: ClientConnectConfig {
                     options: map!("hello" -> "world"),
+                    ..Default::default()
                 },
                 ..Default::default()
             },
@@ -1868,6 +2227,7 @@ mod tests {
                 },
                 connect: ClientConnectConfig {
                     options: map!("hello" -> "world", "config" -> "value"),
+                    ..Default::default()
                 },
                 ..Default::default()
             },
@@ -1930,6 +2290,8 @@ mod tests {
                 },
                 connect: ClientConnectConfig {
                     options: map!("hello" -> "world"),
+                    tls_ca: Some(PathBuf::from("config-ca.pem")),
+                    ..Default::default()
                 },
                 ..Default::default()
             },
@@ -1947,7 +2309,7 @@ mod tests {
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::Connect {
                     cache: PathBuf::new(),
-                    options: map!("hello" -> "world"),
+                    options: map!("hello" -> "world", "tls_ca" -> "config-ca.pem"),
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
@@ -1994,6 +2356,7 @@ mod tests {
                 },
                 connect: ClientConnectConfig {
                     options: map!("hello" -> "world", "config" -> "value"),
+                    ..Default::default()
                 },
                 ..Default::default()
             },
@@ -2197,6 +2560,7 @@ mod tests {
                 current_dir: None,
                 environment: Default::default(),
                 predict: PredictMode::Adaptive,
+                forward_agent: false,
                 cmd: None,
             }),
         };
@@ -2235,6 +2599,7 @@ mod tests {
                     current_dir: None,
                     environment: map!(),
                     predict: PredictMode::Adaptive,
+                    forward_agent: false,
                     cmd: None,
                 }),
             }
@@ -2260,6 +2625,7 @@ mod tests {
                 current_dir: None,
                 environment: map!(),
                 predict: PredictMode::Adaptive,
+                forward_agent: false,
                 cmd: None,
             }),
         };
@@ -2298,6 +2664,7 @@ mod tests {
                     current_dir: None,
                     environment: map!(),
                     predict: PredictMode::Adaptive,
+                    forward_agent: false,
                     cmd: None,
                 }),
             }
@@ -2322,6 +2689,13 @@ mod tests {
                 },
                 current_dir: None,
                 environment: map!(),
+                timeout: None,
+                memory: None,
+                clear_env: false,
+                unset_env: vec![],
+                env_file: None,
+                login: false,
+                forward_agent: false,
                 predict: PredictMode::Adaptive,
                 lsp: Some(None),
                 shell: Some(None),
@@ -2364,6 +2738,13 @@ mod tests {
                     },
                     current_dir: None,
                     environment: map!(),
+                    timeout: None,
+                    memory: None,
+                    clear_env: false,
+                    unset_env: vec![],
+                    env_file: None,
+                    login: false,
+                    forward_agent: false,
                     predict: PredictMode::Adaptive,
                     lsp: Some(None),
                     shell: Some(None),
@@ -2393,6 +2774,13 @@ mod tests {
                 },
                 current_dir: None,
                 environment: map!(),
+                timeout: None,
+                memory: None,
+                clear_env: false,
+                unset_env: vec![],
+                env_file: None,
+                login: false,
+                forward_agent: false,
                 predict: PredictMode::Adaptive,
                 lsp: Some(None),
                 shell: Some(None),
@@ -2435,6 +2823,13 @@ mod tests {
                     },
                     current_dir: None,
                     environment: map!(),
+                    timeout: None,
+                    memory: None,
+                    clear_env: false,
+                    unset_env: vec![],
+                    env_file: None,
+                    login: false,
+                    forward_agent: false,
                     predict: PredictMode::Adaptive,
                     lsp: Some(None),
                     shell: Some(None),
@@ -4394,6 +4789,10 @@ mod tests {
                 daemon: false,
                 user: false,
                 plugin: Vec::new(),
+                known_servers_file: None,
+                server_identity_check: None,
+                auto_forward: ManagerListenAutoForwardOptions::default(),
+                tunnels: HashMap::new(),
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
@@ -4404,6 +4803,21 @@ mod tests {
         options.merge(Config {
             manager: ManagerConfig {
                 access: Some(AccessControl::Group),
+                known_servers_file: Some(PathBuf::from("config-known-servers")),
+                server_identity_check: Some(ServerIdentityPolicy::Strict),
+                auto_forward: ManagerAutoForwardConfig {
+                    enabled: true,
+                    interval: Some(Seconds::from(5u32)),
+                    allow: vec![PortRange::from(3000..=3999)],
+                    deny: vec![PortRange::single(3306)],
+                },
+                tunnels: HashMap::from([(
+                    "ssh://example.com".to_string(),
+                    ManagerTunnelsConfig {
+                        forward: vec!["8080:3000".to_string()],
+                        ..Default::default()
+                    },
+                )]),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
                     log_level: Some(LogLevel::Trace),
@@ -4430,6 +4844,21 @@ mod tests {
                     daemon: false,
                     user: false,
                     plugin: Vec::new(),
+                    known_servers_file: Some(PathBuf::from("config-known-servers")),
+                    server_identity_check: Some(ServerIdentityPolicy::Strict),
+                    auto_forward: ManagerListenAutoForwardOptions {
+                        auto_forward: true,
+                        auto_forward_interval: Some(Seconds::from(5u32)),
+                        auto_forward_allow: vec![PortRange::from(3000..=3999)],
+                        auto_forward_deny: vec![PortRange::single(3306)],
+                    },
+                    tunnels: HashMap::from([(
+                        "ssh://example.com".to_string(),
+                        ManagerTunnelsConfig {
+                            forward: vec!["8080:3000".to_string()],
+                            ..Default::default()
+                        },
+                    )]),
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
@@ -4453,6 +4882,15 @@ mod tests {
                 daemon: false,
                 user: false,
                 plugin: Vec::new(),
+                known_servers_file: Some(PathBuf::from("cli-known-servers")),
+                server_identity_check: Some(ServerIdentityPolicy::AcceptNew),
+                auto_forward: ManagerListenAutoForwardOptions {
+                    auto_forward: true,
+                    auto_forward_interval: Some(Seconds::from(1u32)),
+                    auto_forward_allow: vec![PortRange::single(8080)],
+                    auto_forward_deny: vec![PortRange::single(8081)],
+                },
+                tunnels: HashMap::new(),
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
@@ -4463,6 +4901,15 @@ mod tests {
         options.merge(Config {
             manager: ManagerConfig {
                 access: Some(AccessControl::Group),
+                known_servers_file: Some(PathBuf::from("config-known-servers")),
+                server_identity_check: Some(ServerIdentityPolicy::Strict),
+                auto_forward: ManagerAutoForwardConfig {
+                    enabled: true,
+                    interval: Some(Seconds::from(5u32)),
+                    allow: vec![PortRange::from(3000..=3999)],
+                    deny: vec![PortRange::single(3306)],
+                },
+                tunnels: HashMap::new(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
                     log_level: Some(LogLevel::Trace),
@@ -4489,6 +4936,15 @@ mod tests {
                     daemon: false,
                     user: false,
                     plugin: Vec::new(),
+                    known_servers_file: Some(PathBuf::from("cli-known-servers")),
+                    server_identity_check: Some(ServerIdentityPolicy::AcceptNew),
+                    auto_forward: ManagerListenAutoForwardOptions {
+                        auto_forward: true,
+                        auto_forward_interval: Some(Seconds::from(1u32)),
+                        auto_forward_allow: vec![PortRange::single(8080)],
+                        auto_forward_deny: vec![PortRange::single(8081)],
+                    },
+                    tunnels: HashMap::new(),
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
@@ -4512,6 +4968,9 @@ mod tests {
                 host: Value::Default(BindAddress::Any),
                 port: Value::Default(PortRange::single(123)),
                 use_ipv6: false,
+                websocket: false,
+                quic: false,
+                tls: ServerListenTlsOptions::default(),
                 shutdown: Value::Default(Shutdown::After(Duration::from_secs(123))),
                 current_dir: None,
                 watch: ServerListenWatchOptions {
@@ -4522,7 +4981,9 @@ mod tests {
                     watch_debounce_tick_rate: None,
                 },
                 daemon: false,
+                tunnel: ServerTunnelConfig::default(),
                 key_from_stdin: false,
+                identity_file: None,
                 output_to_local_pipe: None,
             }),
         };
@@ -4539,6 +5000,15 @@ mod tests {
                     use_ipv6: true,
                     shutdown: Some(Shutdown::Lonely(Duration::from_secs(456))),
                     current_dir: Some(PathBuf::from("config-dir")),
+                    tls_cert: Some(PathBuf::from("config-cert.pem")),
+                    tls_key: Some(PathBuf::from("config-key.pem")),
+                    tls_client_ca: Some(PathBuf::from("config-ca.pem")),
+                    identity_file: Some(PathBuf::from("config-identity.pem")),
+                },
+                tunnel: ServerTunnelConfig {
+                    allow_targets: vec!["10.0.0.0/8".parse().unwrap()],
+                    allow_target_ports: vec![PortRange::single(443)],
+                    ..Default::default()
                 },
                 watch: ServerWatchConfig {
                     native: false,
@@ -4564,6 +5034,13 @@ mod tests {
                     host: Value::Explicit(BindAddress::Ssh),
                     port: Value::Explicit(PortRange::single(456)),
                     use_ipv6: true,
+                    websocket: false,
+                    quic: false,
+                    tls: ServerListenTlsOptions {
+                        tls_cert: Some(PathBuf::from("config-cert.pem")),
+                        tls_key: Some(PathBuf::from("config-key.pem")),
+                        tls_client_ca: Some(PathBuf::from("config-ca.pem")),
+                    },
                     shutdown: Value::Explicit(Shutdown::Lonely(Duration::from_secs(456))),
                     current_dir: Some(PathBuf::from("config-dir")),
                     watch: ServerListenWatchOptions {
@@ -4574,7 +5051,13 @@ mod tests {
                         watch_debounce_tick_rate: Some(Seconds::from(300u32)),
                     },
                     daemon: false,
+                    tunnel: ServerTunnelConfig {
+                        allow_targets: vec!["10.0.0.0/8".parse().unwrap()],
+                        allow_target_ports: vec![PortRange::single(443)],
+                        ..Default::default()
+                    },
                     key_from_stdin: false,
+                    identity_file: Some(PathBuf::from("config-identity.pem")),
                     output_to_local_pipe: None,
                 }),
             }
@@ -4595,6 +5078,13 @@ mod tests {
                 host: Value::Explicit(BindAddress::Any),
                 port: Value::Explicit(PortRange::single(123)),
                 use_ipv6: true,
+                websocket: false,
+                quic: false,
+                tls: ServerListenTlsOptions {
+                    tls_cert: Some(PathBuf::from("cli-cert.pem")),
+                    tls_key: Some(PathBuf::from("cli-key.pem")),
+                    tls_client_ca: Some(PathBuf::from("cli-ca.pem")),
+                },
                 shutdown: Value::Explicit(Shutdown::After(Duration::from_secs(123))),
                 current_dir: Some(PathBuf::from("cli-dir")),
                 watch: ServerListenWatchOptions {
@@ -4605,7 +5095,9 @@ mod tests {
                     watch_debounce_tick_rate: Some(Seconds::from(30u32)),
                 },
                 daemon: false,
+                tunnel: ServerTunnelConfig::default(),
                 key_from_stdin: false,
+                identity_file: Some(PathBuf::from("cli-identity.pem")),
                 output_to_local_pipe: None,
             }),
         };
@@ -4622,7 +5114,12 @@ mod tests {
                     use_ipv6: false,
                     shutdown: Some(Shutdown::Lonely(Duration::from_secs(456))),
                     current_dir: Some(PathBuf::from("config-dir")),
+                    tls_cert: Some(PathBuf::from("config-cert.pem")),
+                    tls_key: Some(PathBuf::from("config-key.pem")),
+                    tls_client_ca: Some(PathBuf::from("config-ca.pem")),
+                    identity_file: Some(PathBuf::from("config-identity.pem")),
                 },
+                tunnel: ServerTunnelConfig::default(),
                 watch: ServerWatchConfig {
                     native: true,
                     poll_interval: Some(Seconds::from(100u32)),
@@ -4647,6 +5144,13 @@ mod tests {
                     host: Value::Explicit(BindAddress::Any),
                     port: Value::Explicit(PortRange::single(123)),
                     use_ipv6: true,
+                    websocket: false,
+                    quic: false,
+                    tls: ServerListenTlsOptions {
+                        tls_cert: Some(PathBuf::from("cli-cert.pem")),
+                        tls_key: Some(PathBuf::from("cli-key.pem")),
+                        tls_client_ca: Some(PathBuf::from("cli-ca.pem")),
+                    },
                     shutdown: Value::Explicit(Shutdown::After(Duration::from_secs(123))),
                     current_dir: Some(PathBuf::from("cli-dir")),
                     watch: ServerListenWatchOptions {
@@ -4657,7 +5161,9 @@ mod tests {
                         watch_debounce_tick_rate: Some(Seconds::from(30u32)),
                     },
                     daemon: false,
+                    tunnel: ServerTunnelConfig::default(),
                     key_from_stdin: false,
+                    identity_file: Some(PathBuf::from("cli-identity.pem")),
                     output_to_local_pipe: None,
                 }),
             }
@@ -4745,6 +5251,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn distant_tunnel_list_should_parse_without_watch() {
+        let options = Options::try_parse_from(["distant", "tunnel", "list"]).unwrap();
+        match options.command {
+            DistantSubcommand::Client(ClientSubcommand::Tunnel(ClientTunnelSubcommand::List {
+                watch,
+                ..
+            })) => {
+                assert_eq!(watch, None);
+            }
+            other => panic!("Expected tunnel List, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn distant_tunnel_list_should_default_watch_interval_to_one_second() {
+        let options = Options::try_parse_from(["distant", "tunnel", "list", "--watch"]).unwrap();
+        match options.command {
+            DistantSubcommand::Client(ClientSubcommand::Tunnel(ClientTunnelSubcommand::List {
+                watch,
+                ..
+            })) => {
+                assert_eq!(watch, Some(Seconds::from(1u32)));
+            }
+            other => panic!("Expected tunnel List with watch, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn distant_tunnel_list_should_parse_watch_interval() {
+        let options =
+            Options::try_parse_from(["distant", "tunnel", "list", "--watch", "5"]).unwrap();
+        match options.command {
+            DistantSubcommand::Client(ClientSubcommand::Tunnel(ClientTunnelSubcommand::List {
+                watch,
+                ..
+            })) => {
+                assert_eq!(watch, Some(Seconds::from(5u32)));
+            }
+            other => panic!("Expected tunnel List with watch, got {other:?}"),
+        }
+    }
+
     #[test]
     fn distant_manager_list_should_not_parse() {
         assert!(Options::try_parse_from(["distant", "manager", "list"]).is_err());
@@ -4788,6 +5337,7 @@ mod tests {
             current_dir: None,
             environment: Default::default(),
             predict: PredictMode::Adaptive,
+            forward_agent: false,
             cmd: None,
         };
         assert_eq!(cmd.format(), Format::Shell);
@@ -4801,6 +5351,13 @@ mod tests {
             network: NetworkSettings::default(),
             current_dir: None,
             environment: Default::default(),
+            timeout: None,
+            memory: None,
+            clear_env: false,
+            unset_env: vec![],
+            env_file: None,
+            login: false,
+            forward_agent: false,
             predict: PredictMode::Adaptive,
             lsp: None,
             shell: None,
@@ -4821,6 +5378,7 @@ mod tests {
             current_dir: None,
             environment: Default::default(),
             predict: PredictMode::Adaptive,
+            forward_agent: false,
             new: false,
             destination: "test://host".to_string(),
             cmd: None,
@@ -4941,6 +5499,9 @@ mod tests {
                 host: Value::Default(BindAddress::Any),
                 port: Value::Default(distant_core::net::common::PortRange::EPHEMERAL),
                 use_ipv6: false,
+                websocket: false,
+                quic: false,
+                tls: ServerListenTlsOptions::default(),
                 shutdown: Value::Default(distant_core::net::server::Shutdown::Never),
                 current_dir: None,
                 daemon: false,
@@ -4951,7 +5512,9 @@ mod tests {
                     watch_debounce_timeout: Value::Default(Seconds::try_from(0.5).unwrap()),
                     watch_debounce_tick_rate: None,
                 },
+                tunnel: ServerTunnelConfig::default(),
                 key_from_stdin: false,
+                identity_file: None,
                 output_to_local_pipe: None,
             });
             assert_eq!(sub.format(), Format::Shell);
@@ -4962,6 +5525,10 @@ mod tests {
             daemon: false,
             user: false,
             plugin: Vec::new(),
+            known_servers_file: None,
+            server_identity_check: None,
+            auto_forward: ManagerListenAutoForwardOptions::default(),
+            tunnels: HashMap::new(),
             network: NetworkSettings::default(),
         });
         assert_eq!(sub.format(), Format::Shell);
@@ -5013,6 +5580,7 @@ mod tests {
                 current_dir: None,
                 environment: Default::default(),
                 predict: PredictMode::Adaptive,
+                forward_agent: false,
                 cmd: None,
             },
             ClientSubcommand::Spawn {
@@ -5021,6 +5589,13 @@ mod tests {
                 network: net.clone(),
                 current_dir: None,
                 environment: Default::default(),
+                timeout: None,
+                memory: None,
+                clear_env: false,
+                unset_env: vec![],
+                env_file: None,
+                login: false,
+                forward_agent: false,
                 predict: PredictMode::Adaptive,
                 lsp: None,
                 shell: None,
@@ -5047,6 +5622,7 @@ mod tests {
                 current_dir: None,
                 environment: Default::default(),
                 predict: PredictMode::Adaptive,
+                forward_agent: false,
                 new: false,
                 destination: "test://host".to_string(),
                 cmd: None,
@@ -5223,6 +5799,7 @@ mod tests {
                 current_dir: None,
                 environment: Default::default(),
                 predict: PredictMode::Adaptive,
+                forward_agent: false,
                 new: false,
                 destination: "test://host".to_string(),
                 cmd: None,
@@ -5431,6 +6008,10 @@ mod tests {
             daemon: false,
             user: false,
             plugin: Vec::new(),
+            known_servers_file: None,
+            server_identity_check: None,
+            auto_forward: ManagerListenAutoForwardOptions::default(),
+            tunnels: HashMap::new(),
             network: NetworkSettings::default(),
         };
         assert!(cmd.is_listen());
@@ -5473,6 +6054,9 @@ mod tests {
             host: Value::Default(BindAddress::Any),
             port: Value::Default(distant_core::net::common::PortRange::EPHEMERAL),
             use_ipv6: false,
+            websocket: false,
+            quic: false,
+            tls: ServerListenTlsOptions::default(),
             shutdown: Value::Default(distant_core::net::server::Shutdown::Never),
             current_dir: None,
             daemon: false,
@@ -5483,7 +6067,9 @@ mod tests {
                 watch_debounce_timeout: Value::Default(Seconds::try_from(0.5).unwrap()),
                 watch_debounce_tick_rate: None,
             },
+            tunnel: ServerTunnelConfig::default(),
             key_from_stdin: false,
+            identity_file: None,
             output_to_local_pipe: None,
         };
         assert_eq!(cmd.format(), Format::Shell);
@@ -5512,6 +6098,7 @@ mod tests {
                 current_dir: None,
                 environment: Default::default(),
                 predict: PredictMode::Adaptive,
+                forward_agent: false,
                 new: false,
                 destination: "test://host".to_string(),
                 cmd: None,
@@ -5530,6 +6117,7 @@ mod tests {
                 },
                 connect: ClientConnectConfig {
                     options: map!("hello" -> "world"),
+                    ..Default::default()
                 },
                 ..Default::default()
             },
@@ -5555,6 +6143,7 @@ mod tests {
                     current_dir: None,
                     environment: Default::default(),
                     predict: PredictMode::Adaptive,
+                    forward_agent: false,
                     new: false,
                     destination: "test://host".to_string(),
                     cmd: None,
@@ -5583,6 +6172,7 @@ mod tests {
                 current_dir: None,
                 environment: Default::default(),
                 predict: PredictMode::Adaptive,
+                forward_agent: false,
                 new: false,
                 destination: "test://host".to_string(),
                 cmd: None,
@@ -5601,6 +6191,7 @@ mod tests {
                 },
                 connect: ClientConnectConfig {
                     options: map!("cli_key" -> "config_val", "config_key" -> "config_val"),
+                    ..Default::default()
                 },
                 ..Default::default()
             },
@@ -5626,6 +6217,7 @@ mod tests {
                     current_dir: None,
                     environment: Default::default(),
                     predict: PredictMode::Adaptive,
+                    forward_agent: false,
                     new: false,
                     destination: "test://host".to_string(),
                     cmd: None,
@@ -5681,6 +6273,31 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn distant_ssh_should_parse_with_forward_agent_flag() {
+        let options = Options::try_parse_from(["distant", "ssh", "-A", "user@host"]).unwrap();
+        match options.command {
+            DistantSubcommand::Client(ClientSubcommand::Ssh { forward_agent, .. }) => {
+                assert!(forward_agent);
+            }
+            other => panic!("Expected Ssh with -A, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn distant_spawn_should_reject_forward_agent_with_lsp() {
+        let result = Options::try_parse_from([
+            "distant",
+            "spawn",
+            "--forward-agent",
+            "--lsp",
+            "--",
+            "rust-analyzer",
+        ]);
+        assert!(result.is_err());
+    }
+
     #[cfg(feature = "host")]
     #[test]
     fn distant_server_listen_should_parse_defaults() {
@@ -5688,11 +6305,15 @@ mod tests {
         match options.command {
             DistantSubcommand::Server(ServerSubcommand::Listen {
                 use_ipv6,
+                websocket,
+                quic,
                 daemon,
                 key_from_stdin,
                 ..
             }) => {
                 assert!(!use_ipv6);
+                assert!(!websocket);
+                assert!(!quic);
                 assert!(!daemon);
                 assert!(!key_from_stdin);
             }
@@ -5708,6 +6329,7 @@ mod tests {
             "server",
             "listen",
             "--use-ipv6",
+            "--websocket",
             "--daemon",
             "--key-from-stdin",
         ])
@@ -5715,11 +6337,13 @@ mod tests {
         match options.command {
             DistantSubcommand::Server(ServerSubcommand::Listen {
                 use_ipv6,
+                websocket,
                 daemon,
                 key_from_stdin,
                 ..
             }) => {
                 assert!(use_ipv6);
+                assert!(websocket);
                 assert!(daemon);
                 assert!(key_from_stdin);
             }
@@ -5727,6 +6351,29 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "host")]
+    #[test]
+    fn distant_server_listen_should_parse_quic_flag() {
+        let options = Options::try_parse_from(["distant", "server", "listen", "--quic"]).unwrap();
+        match options.command {
+            DistantSubcommand::Server(ServerSubcommand::Listen {
+                websocket, quic, ..
+            }) => {
+                assert!(!websocket);
+                assert!(quic);
+            }
+            other => panic!("Expected Server Listen with --quic, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "host")]
+    #[test]
+    fn distant_server_listen_should_reject_quic_with_websocket() {
+        let result =
+            Options::try_parse_from(["distant", "server", "listen", "--quic", "--websocket"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn distant_generate_config_should_parse() {
         let options = Options::try_parse_from(["distant", "generate", "config"]).unwrap();
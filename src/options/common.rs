@@ -4,6 +4,7 @@ mod logging;
 mod network;
 mod search;
 mod shell;
+mod size;
 mod time;
 mod value;
 
@@ -12,6 +13,7 @@ pub use logging::*;
 pub use network::*;
 pub use search::*;
 pub use shell::*;
+pub use size::*;
 pub use time::*;
 #[cfg(feature = "host")]
 pub use value::*;
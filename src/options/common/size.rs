@@ -0,0 +1,144 @@
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+/// Represents a size in bytes, parsed from a number with an optional binary unit suffix such as
+/// `512K`, `64MiB`, or `2G`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ByteSize(u64);
+
+impl ByteSize {
+    /// Returns the size as a number of bytes.
+    pub fn as_bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = ParseByteSizeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+
+        let number: u64 = number.parse().map_err(|_| ParseByteSizeError::NotANumber)?;
+        let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" | "KIB" => 1 << 10,
+            "M" | "MB" | "MIB" => 1 << 20,
+            "G" | "GB" | "GIB" => 1 << 30,
+            "T" | "TB" | "TIB" => 1 << 40,
+            _ => return Err(ParseByteSizeError::UnknownUnit),
+        };
+
+        number
+            .checked_mul(multiplier)
+            .map(Self)
+            .ok_or(ParseByteSizeError::TooLarge)
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Deref for ByteSize {
+    type Target = u64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+/// Represents errors that can occur when parsing a byte size.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ParseByteSizeError {
+    NotANumber,
+    TooLarge,
+    UnknownUnit,
+}
+
+impl std::error::Error for ParseByteSizeError {}
+
+impl fmt::Display for ParseByteSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotANumber => write!(f, "size must start with a non-negative integer"),
+            Self::TooLarge => write!(f, "size is too large"),
+            Self::UnknownUnit => write!(f, "size unit must be one of B, K, M, G, or T"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for the `ByteSize` newtype: `FromStr` with and without unit suffixes, parse
+    //! errors, and `Display`.
+
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn from_str_plain_bytes() {
+        let size: ByteSize = "1234".parse().unwrap();
+        assert_eq!(size.as_bytes(), 1234);
+    }
+
+    #[test]
+    fn from_str_binary_units() {
+        assert_eq!("2K".parse::<ByteSize>().unwrap().as_bytes(), 2 * 1024);
+        assert_eq!("512MiB".parse::<ByteSize>().unwrap().as_bytes(), 512 << 20);
+        assert_eq!("1gb".parse::<ByteSize>().unwrap().as_bytes(), 1 << 30);
+        assert_eq!("3 T".parse::<ByteSize>().unwrap().as_bytes(), 3 << 40);
+        assert_eq!("7B".parse::<ByteSize>().unwrap().as_bytes(), 7);
+    }
+
+    #[test]
+    fn from_str_missing_number_fails() {
+        assert_eq!(
+            "M".parse::<ByteSize>().unwrap_err(),
+            ParseByteSizeError::NotANumber
+        );
+        assert_eq!(
+            "-5".parse::<ByteSize>().unwrap_err(),
+            ParseByteSizeError::NotANumber
+        );
+    }
+
+    #[test]
+    fn from_str_unknown_unit_fails() {
+        assert_eq!(
+            "5X".parse::<ByteSize>().unwrap_err(),
+            ParseByteSizeError::UnknownUnit
+        );
+    }
+
+    #[test]
+    fn from_str_overflow_fails() {
+        assert_eq!(
+            "18446744073709551615T".parse::<ByteSize>().unwrap_err(),
+            ParseByteSizeError::TooLarge
+        );
+    }
+
+    #[test]
+    fn display_should_print_bytes() {
+        assert_eq!(ByteSize::from(2048).to_string(), "2048");
+    }
+}
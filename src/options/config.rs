@@ -137,12 +137,13 @@ mod tests {
     //! Tests for `Config`: default parsing, `default_raw_str()` validity,
     //! `load_multi()` with various path scenarios, and full config file loading.
 
+    use std::collections::HashMap;
     use std::net::Ipv4Addr;
     use std::time::Duration;
 
     use common::*;
     use distant_core::map;
-    use distant_core::net::common::{Host, Map, PortRange};
+    use distant_core::net::common::{Host, Map, PortRange, ServerIdentityPolicy};
     use distant_core::net::server::Shutdown;
     use test_log::test;
 
@@ -159,7 +160,10 @@ mod tests {
                         timeout: Some(Seconds::from(0u32))
                     },
                     connect: ClientConnectConfig {
-                        options: Map::new()
+                        options: Map::new(),
+                        tls_ca: None,
+                        tls_cert: None,
+                        tls_key: None,
                     },
                     launch: ClientLaunchConfig {
                         distant: ClientLaunchDistantConfig {
@@ -186,6 +190,10 @@ mod tests {
                 },
                 manager: ManagerConfig {
                     access: Some(AccessControl::Owner),
+                    known_servers_file: None,
+                    server_identity_check: Some(ServerIdentityPolicy::Ask),
+                    auto_forward: ManagerAutoForwardConfig::default(),
+                    tunnels: HashMap::new(),
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Info),
                         log_file: None
@@ -202,11 +210,16 @@ mod tests {
                         use_ipv6: false,
                         shutdown: Some(Shutdown::Never),
                         current_dir: None,
+                        tls_cert: None,
+                        tls_key: None,
+                        tls_client_ca: None,
+                        identity_file: None,
                     },
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Info),
                         log_file: None
                     },
+                    tunnel: ServerTunnelConfig::default(),
                     watch: ServerWatchConfig {
                         native: true,
                         poll_interval: None,
@@ -324,6 +337,9 @@ timeout = 456
 
 [client.connect]
 options = "key=\"value\",key2=\"value2\""
+tls_ca = "client-ca.pem"
+tls_cert = "client-cert.pem"
+tls_key = "client-key.pem"
 
 [client.launch]
 bin = "some-bin"
@@ -341,6 +357,19 @@ log_level = "warn"
 access = "anyone"
 unix_socket = "manager-unix-socket"
 windows_pipe = "manager-windows-pipe"
+known_servers_file = "manager-known-servers"
+server_identity_check = "strict"
+
+[manager.auto_forward]
+enabled = true
+interval = 5
+allow = ["3000:3999", "8080"]
+deny = ["3306"]
+
+[manager.tunnels."ssh://example.com"]
+forward = ["8080:3000"]
+reverse = ["9090:3000"]
+socks = ["1080"]
 
 [server]
 log_file = "server-log-file"
@@ -352,6 +381,19 @@ port = "8080:8089"
 use_ipv6 = true
 shutdown = "after=123"
 current_dir = "server-current-dir"
+tls_cert = "server-cert.pem"
+tls_key = "server-key.pem"
+tls_client_ca = "server-client-ca.pem"
+identity_file = "server-identity.pem"
+
+[server.tunnel]
+allow_targets = ["10.0.0.0/8", "127.0.0.1"]
+deny_targets = ["10.0.0.1"]
+allow_target_ports = ["5000:5999"]
+deny_target_ports = ["5432"]
+allow_bind_addrs = ["127.0.0.1"]
+allow_bind_ports = ["8000:8999"]
+unix_sockets = false
 
 [server.watch]
 native = false
@@ -373,6 +415,9 @@ debounce_tick_rate = 0.678
                     },
                     connect: ClientConnectConfig {
                         options: map!("key" -> "value", "key2" -> "value2"),
+                        tls_ca: Some(PathBuf::from("client-ca.pem")),
+                        tls_cert: Some(PathBuf::from("client-cert.pem")),
+                        tls_key: Some(PathBuf::from("client-key.pem")),
                     },
                     launch: ClientLaunchConfig {
                         distant: ClientLaunchDistantConfig {
@@ -399,6 +444,22 @@ debounce_tick_rate = 0.678
                 },
                 manager: ManagerConfig {
                     access: Some(AccessControl::Anyone),
+                    known_servers_file: Some(PathBuf::from("manager-known-servers")),
+                    server_identity_check: Some(ServerIdentityPolicy::Strict),
+                    auto_forward: ManagerAutoForwardConfig {
+                        enabled: true,
+                        interval: Some(Seconds::from(5u32)),
+                        allow: vec![PortRange::from(3000..=3999), PortRange::single(8080)],
+                        deny: vec![PortRange::single(3306)],
+                    },
+                    tunnels: HashMap::from([(
+                        "ssh://example.com".to_string(),
+                        ManagerTunnelsConfig {
+                            forward: vec!["8080:3000".to_string()],
+                            reverse: vec!["9090:3000".to_string()],
+                            socks: vec!["1080".to_string()],
+                        }
+                    )]),
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Warn),
                         log_file: Some(PathBuf::from("manager-log-file"))
@@ -418,11 +479,27 @@ debounce_tick_rate = 0.678
                         use_ipv6: true,
                         shutdown: Some(Shutdown::After(Duration::from_secs(123))),
                         current_dir: Some(PathBuf::from("server-current-dir")),
+                        tls_cert: Some(PathBuf::from("server-cert.pem")),
+                        tls_key: Some(PathBuf::from("server-key.pem")),
+                        tls_client_ca: Some(PathBuf::from("server-client-ca.pem")),
+                        identity_file: Some(PathBuf::from("server-identity.pem")),
                     },
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Error),
                         log_file: Some(PathBuf::from("server-log-file")),
                     },
+                    tunnel: ServerTunnelConfig {
+                        allow_targets: vec![
+                            "10.0.0.0/8".parse().unwrap(),
+                            "127.0.0.1".parse().unwrap()
+                        ],
+                        deny_targets: vec!["10.0.0.1".parse().unwrap()],
+                        allow_target_ports: vec![PortRange::from(5000..=5999)],
+                        deny_target_ports: vec![PortRange::single(5432)],
+                        allow_bind_addrs: vec!["127.0.0.1".parse().unwrap()],
+                        allow_bind_ports: vec![PortRange::from(8000..=8999)],
+                        unix_sockets: false,
+                    },
                     watch: ServerWatchConfig {
                         native: false,
                         poll_interval: Some(Seconds::try_from(12.5).unwrap()),
@@ -1,14 +1,24 @@
+use std::path::PathBuf;
+
 use distant_core::net::common::Map;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ClientConnectConfig {
     pub options: Map,
+    pub tls_ca: Option<PathBuf>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
 }
 
 impl From<Map> for ClientConnectConfig {
-    fn from(map: Map) -> Self {
-        Self { options: map }
+    fn from(mut map: Map) -> Self {
+        Self {
+            tls_ca: map.remove("tls_ca").map(PathBuf::from),
+            tls_cert: map.remove("tls_cert").map(PathBuf::from),
+            tls_key: map.remove("tls_key").map(PathBuf::from),
+            options: map,
+        }
     }
 }
 
@@ -16,6 +26,19 @@ impl From<ClientConnectConfig> for Map {
     fn from(config: ClientConnectConfig) -> Self {
         let mut this = Self::new();
         this.extend(config.options);
+
+        if let Some(x) = config.tls_ca {
+            this.insert("tls_ca".to_string(), x.to_string_lossy().to_string());
+        }
+
+        if let Some(x) = config.tls_cert {
+            this.insert("tls_cert".to_string(), x.to_string_lossy().to_string());
+        }
+
+        if let Some(x) = config.tls_key {
+            this.insert("tls_key".to_string(), x.to_string_lossy().to_string());
+        }
+
         this
     }
 }
@@ -36,6 +59,9 @@ mod tests {
     fn default_has_empty_options() {
         let config = ClientConnectConfig::default();
         assert!(config.options.is_empty());
+        assert!(config.tls_ca.is_none());
+        assert!(config.tls_cert.is_none());
+        assert!(config.tls_key.is_none());
     }
 
     // -------------------------------------------------------
@@ -61,6 +87,21 @@ mod tests {
         assert_eq!(config.options.get("key2").unwrap(), "value2");
     }
 
+    #[test]
+    fn from_map_extracts_tls_paths_from_options() {
+        let mut map = Map::new();
+        map.insert("key".to_string(), "value".to_string());
+        map.insert("tls_ca".to_string(), "/tmp/ca.pem".to_string());
+        map.insert("tls_cert".to_string(), "/tmp/cert.pem".to_string());
+        map.insert("tls_key".to_string(), "/tmp/key.pem".to_string());
+
+        let config = ClientConnectConfig::from(map);
+        assert_eq!(config.options.len(), 1);
+        assert_eq!(config.tls_ca, Some(PathBuf::from("/tmp/ca.pem")));
+        assert_eq!(config.tls_cert, Some(PathBuf::from("/tmp/cert.pem")));
+        assert_eq!(config.tls_key, Some(PathBuf::from("/tmp/key.pem")));
+    }
+
     // -------------------------------------------------------
     // ClientConnectConfig -> Map
     // -------------------------------------------------------
@@ -68,10 +109,16 @@ mod tests {
     fn into_map_preserves_options() {
         let mut options = Map::new();
         options.insert("key".to_string(), "value".to_string());
-        let config = ClientConnectConfig { options };
+        let config = ClientConnectConfig {
+            options,
+            tls_ca: Some(PathBuf::from("/tmp/ca.pem")),
+            ..Default::default()
+        };
 
         let map: Map = config.into();
         assert_eq!(map.get("key").unwrap(), "value");
+        assert_eq!(map.get("tls_ca").unwrap(), "/tmp/ca.pem");
+        assert!(map.get("tls_cert").is_none());
     }
 
     // -------------------------------------------------------
@@ -81,7 +128,12 @@ mod tests {
     fn round_trip() {
         let mut options = Map::new();
         options.insert("a".to_string(), "b".to_string());
-        let original = ClientConnectConfig { options };
+        let original = ClientConnectConfig {
+            options,
+            tls_ca: Some(PathBuf::from("/tmp/ca.pem")),
+            tls_cert: Some(PathBuf::from("/tmp/cert.pem")),
+            tls_key: Some(PathBuf::from("/tmp/key.pem")),
+        };
 
         let map: Map = original.clone().into();
         let restored = ClientConnectConfig::from(map);
@@ -103,7 +155,10 @@ mod tests {
     fn serde_round_trip() {
         let mut options = Map::new();
         options.insert("test_key".to_string(), "test_value".to_string());
-        let config = ClientConnectConfig { options };
+        let config = ClientConnectConfig {
+            options,
+            ..Default::default()
+        };
 
         let json = serde_json::to_string(&config).unwrap();
         let restored: ClientConnectConfig = serde_json::from_str(&json).unwrap();
@@ -1,7 +1,17 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use distant_core::net::common::ServerIdentityPolicy;
 use serde::{Deserialize, Serialize};
 
 use super::common::{AccessControl, LoggingSettings, NetworkSettings};
 
+mod auto_forward;
+mod tunnels;
+
+pub use auto_forward::*;
+pub use tunnels::*;
+
 /// Represents configuration settings for the distant manager
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ManagerConfig {
@@ -12,4 +22,12 @@ pub struct ManagerConfig {
     pub network: NetworkSettings,
 
     pub access: Option<AccessControl>,
+    pub known_servers_file: Option<PathBuf>,
+    pub server_identity_check: Option<ServerIdentityPolicy>,
+
+    #[serde(default)]
+    pub auto_forward: ManagerAutoForwardConfig,
+
+    #[serde(default)]
+    pub tunnels: HashMap<String, ManagerTunnelsConfig>,
 }
@@ -0,0 +1,65 @@
+use distant_core::net::common::PortRange;
+use serde::{Deserialize, Serialize};
+
+use crate::options::common::Seconds;
+
+/// Represents configuration settings for forwarding ports that remote processes listen on
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManagerAutoForwardConfig {
+    pub enabled: bool,
+    pub interval: Option<Seconds>,
+    pub allow: Vec<PortRange>,
+    pub deny: Vec<PortRange>,
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for `ManagerAutoForwardConfig`: defaults, serde round-trips, and partial tables.
+
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn default_is_disabled_with_no_port_lists() {
+        let config = ManagerAutoForwardConfig::default();
+        assert!(!config.enabled);
+        assert!(config.interval.is_none());
+        assert!(config.allow.is_empty());
+        assert!(config.deny.is_empty());
+    }
+
+    #[test]
+    fn serde_round_trip_with_all_fields() {
+        let config = ManagerAutoForwardConfig {
+            enabled: true,
+            interval: Some(Seconds::from(5u32)),
+            allow: vec![PortRange::from(3000..=3999), PortRange::single(8080)],
+            deny: vec![PortRange::single(3306)],
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: ManagerAutoForwardConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config, restored);
+    }
+
+    #[test]
+    fn deserialize_should_fill_missing_fields_with_defaults() {
+        let config: ManagerAutoForwardConfig = toml_edit::de::from_str(
+            r#"
+            enabled = true
+            allow = ["3000:3999", 8080]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            ManagerAutoForwardConfig {
+                enabled: true,
+                interval: None,
+                allow: vec![PortRange::from(3000..=3999), PortRange::single(8080)],
+                deny: Vec::new(),
+            }
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+/// Represents configuration settings for the tunnels started whenever the manager connects to
+/// some destination, using the same specs as `distant tunnel open`, `listen`, and `socks`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManagerTunnelsConfig {
+    pub forward: Vec<String>,
+    pub reverse: Vec<String>,
+    pub socks: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for `ManagerTunnelsConfig`: defaults and partial tables.
+
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn default_has_no_tunnels() {
+        let config = ManagerTunnelsConfig::default();
+        assert!(config.forward.is_empty());
+        assert!(config.reverse.is_empty());
+        assert!(config.socks.is_empty());
+    }
+
+    #[test]
+    fn deserialize_should_fill_missing_fields_with_defaults() {
+        let config: ManagerTunnelsConfig = toml_edit::de::from_str(
+            r#"
+            forward = ["8080:3000", "5432:db.internal:5432"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            ManagerTunnelsConfig {
+                forward: vec!["8080:3000".to_string(), "5432:db.internal:5432".to_string()],
+                reverse: Vec::new(),
+                socks: Vec::new(),
+            }
+        );
+    }
+}
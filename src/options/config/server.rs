@@ -3,9 +3,11 @@ use serde::{Deserialize, Serialize};
 use super::common::LoggingSettings;
 
 mod listen;
+mod tunnel;
 mod watch;
 
 pub use listen::*;
+pub use tunnel::*;
 pub use watch::*;
 
 /// Represents configuration settings for the distant server
@@ -15,5 +17,9 @@ pub struct ServerConfig {
     pub logging: LoggingSettings,
 
     pub listen: ServerListenConfig,
+
+    #[serde(default)]
+    pub tunnel: ServerTunnelConfig,
+
     pub watch: ServerWatchConfig,
 }
@@ -13,6 +13,10 @@ pub struct ServerListenConfig {
     pub use_ipv6: bool,
     pub shutdown: Option<Shutdown>,
     pub current_dir: Option<PathBuf>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub tls_client_ca: Option<PathBuf>,
+    pub identity_file: Option<PathBuf>,
 }
 
 impl From<Map> for ServerListenConfig {
@@ -32,6 +36,18 @@ impl From<Map> for ServerListenConfig {
             current_dir: map
                 .remove("current_dir")
                 .and_then(|x| x.parse::<PathBuf>().ok()),
+            tls_cert: map
+                .remove("tls_cert")
+                .and_then(|x| x.parse::<PathBuf>().ok()),
+            tls_key: map
+                .remove("tls_key")
+                .and_then(|x| x.parse::<PathBuf>().ok()),
+            tls_client_ca: map
+                .remove("tls_client_ca")
+                .and_then(|x| x.parse::<PathBuf>().ok()),
+            identity_file: map
+                .remove("identity_file")
+                .and_then(|x| x.parse::<PathBuf>().ok()),
         }
     }
 }
@@ -58,6 +74,22 @@ impl From<ServerListenConfig> for Map {
             this.insert("current_dir".to_string(), x.to_string_lossy().to_string());
         }
 
+        if let Some(x) = config.tls_cert {
+            this.insert("tls_cert".to_string(), x.to_string_lossy().to_string());
+        }
+
+        if let Some(x) = config.tls_key {
+            this.insert("tls_key".to_string(), x.to_string_lossy().to_string());
+        }
+
+        if let Some(x) = config.tls_client_ca {
+            this.insert("tls_client_ca".to_string(), x.to_string_lossy().to_string());
+        }
+
+        if let Some(x) = config.identity_file {
+            this.insert("identity_file".to_string(), x.to_string_lossy().to_string());
+        }
+
         this
     }
 }
@@ -87,6 +119,10 @@ mod tests {
         assert!(!config.use_ipv6);
         assert!(config.shutdown.is_none());
         assert!(config.current_dir.is_none());
+        assert!(config.tls_cert.is_none());
+        assert!(config.tls_key.is_none());
+        assert!(config.tls_client_ca.is_none());
+        assert!(config.identity_file.is_none());
     }
 
     // -------------------------------------------------------
@@ -109,6 +145,10 @@ mod tests {
         map.insert("use_ipv6".to_string(), "true".to_string());
         map.insert("shutdown".to_string(), "after=60".to_string());
         map.insert("current_dir".to_string(), "/tmp/test".to_string());
+        map.insert("tls_cert".to_string(), "/tmp/cert.pem".to_string());
+        map.insert("tls_key".to_string(), "/tmp/key.pem".to_string());
+        map.insert("tls_client_ca".to_string(), "/tmp/ca.pem".to_string());
+        map.insert("identity_file".to_string(), "/tmp/identity.pem".to_string());
 
         let config = ServerListenConfig::from(map);
 
@@ -129,6 +169,13 @@ mod tests {
             Some(Shutdown::After(Duration::from_secs(60)))
         );
         assert_eq!(config.current_dir, Some(PathBuf::from("/tmp/test")));
+        assert_eq!(config.tls_cert, Some(PathBuf::from("/tmp/cert.pem")));
+        assert_eq!(config.tls_key, Some(PathBuf::from("/tmp/key.pem")));
+        assert_eq!(config.tls_client_ca, Some(PathBuf::from("/tmp/ca.pem")));
+        assert_eq!(
+            config.identity_file,
+            Some(PathBuf::from("/tmp/identity.pem"))
+        );
     }
 
     // -------------------------------------------------------
@@ -233,6 +280,10 @@ mod tests {
             use_ipv6: true,
             shutdown: Some(Shutdown::Never),
             current_dir: Some(PathBuf::from("/home/user")),
+            tls_cert: Some(PathBuf::from("/home/user/cert.pem")),
+            tls_key: Some(PathBuf::from("/home/user/key.pem")),
+            tls_client_ca: None,
+            identity_file: Some(PathBuf::from("/home/user/identity.pem")),
         };
 
         let map: Map = config.into();
@@ -241,6 +292,10 @@ mod tests {
         assert_eq!(map.get("use_ipv6").unwrap(), "true");
         assert_eq!(map.get("shutdown").unwrap(), "never");
         assert_eq!(map.get("current_dir").unwrap(), "/home/user");
+        assert_eq!(map.get("tls_cert").unwrap(), "/home/user/cert.pem");
+        assert_eq!(map.get("tls_key").unwrap(), "/home/user/key.pem");
+        assert!(map.get("tls_client_ca").is_none());
+        assert_eq!(map.get("identity_file").unwrap(), "/home/user/identity.pem");
     }
 
     // -------------------------------------------------------
@@ -256,6 +311,7 @@ mod tests {
         assert_eq!(map.get("use_ipv6").unwrap(), "false");
         assert!(map.get("shutdown").is_none());
         assert!(map.get("current_dir").is_none());
+        assert!(map.get("tls_cert").is_none());
     }
 
     // -------------------------------------------------------
@@ -272,6 +328,10 @@ mod tests {
             use_ipv6: false,
             shutdown: Some(Shutdown::After(Duration::from_secs(120))),
             current_dir: Some(PathBuf::from("/var/data")),
+            tls_cert: Some(PathBuf::from("/var/cert.pem")),
+            tls_key: Some(PathBuf::from("/var/key.pem")),
+            tls_client_ca: Some(PathBuf::from("/var/ca.pem")),
+            identity_file: Some(PathBuf::from("/var/identity.pem")),
         };
 
         let map: Map = original.clone().into();
@@ -310,6 +370,10 @@ mod tests {
             use_ipv6: true,
             shutdown: Some(Shutdown::Never),
             current_dir: Some(PathBuf::from("/tmp")),
+            tls_cert: None,
+            tls_key: None,
+            tls_client_ca: None,
+            identity_file: None,
         };
         let json = serde_json::to_string(&config).unwrap();
         let restored: ServerListenConfig = serde_json::from_str(&json).unwrap();
@@ -0,0 +1,96 @@
+use distant_core::net::common::{IpCidr, PortRange};
+use serde::{Deserialize, Serialize};
+
+/// Represents configuration settings limiting where the tunnels opened by clients can connect
+/// to and listen on
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerTunnelConfig {
+    pub allow_targets: Vec<IpCidr>,
+    pub deny_targets: Vec<IpCidr>,
+    pub allow_target_ports: Vec<PortRange>,
+    pub deny_target_ports: Vec<PortRange>,
+    pub allow_bind_addrs: Vec<IpCidr>,
+    pub allow_bind_ports: Vec<PortRange>,
+    pub unix_sockets: bool,
+}
+
+impl Default for ServerTunnelConfig {
+    fn default() -> Self {
+        Self {
+            allow_targets: Vec::new(),
+            deny_targets: Vec::new(),
+            allow_target_ports: Vec::new(),
+            deny_target_ports: Vec::new(),
+            allow_bind_addrs: Vec::new(),
+            allow_bind_ports: Vec::new(),
+            unix_sockets: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for `ServerTunnelConfig`: defaults, serde round-trips, and partial tables.
+
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn default_allows_everything() {
+        let config = ServerTunnelConfig::default();
+        assert!(config.allow_targets.is_empty());
+        assert!(config.deny_targets.is_empty());
+        assert!(config.allow_target_ports.is_empty());
+        assert!(config.deny_target_ports.is_empty());
+        assert!(config.allow_bind_addrs.is_empty());
+        assert!(config.allow_bind_ports.is_empty());
+        assert!(config.unix_sockets);
+    }
+
+    #[test]
+    fn serde_round_trip_with_all_fields() {
+        let config = ServerTunnelConfig {
+            allow_targets: vec!["10.0.0.0/8".parse().unwrap()],
+            deny_targets: vec!["10.0.0.1".parse().unwrap()],
+            allow_target_ports: vec![PortRange::from(5000..=5999)],
+            deny_target_ports: vec![PortRange::single(5432)],
+            allow_bind_addrs: vec!["127.0.0.1".parse().unwrap()],
+            allow_bind_ports: vec![PortRange::from(8000..=8999)],
+            unix_sockets: false,
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: ServerTunnelConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config, restored);
+    }
+
+    #[test]
+    fn deserialize_should_fill_missing_fields_with_defaults() {
+        let config: ServerTunnelConfig = toml_edit::de::from_str(
+            r#"
+            allow_targets = ["10.0.0.0/8", "::1"]
+            deny_target_ports = [22]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            ServerTunnelConfig {
+                allow_targets: vec!["10.0.0.0/8".parse().unwrap(), "::1".parse().unwrap()],
+                deny_target_ports: vec![PortRange::single(22)],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_should_fail_for_invalid_network() {
+        let result: Result<ServerTunnelConfig, _> = toml_edit::de::from_str(
+            r#"
+            allow_targets = ["10.0.0.0/40"]
+            "#,
+        );
+        assert!(result.is_err());
+    }
+}
@@ -249,6 +249,68 @@ async fn should_forward_stdin(#[case] backend: Backend) {
     );
 }
 
+#[cfg(unix)]
+#[rstest]
+#[case::host(Backend::Host)]
+#[case::ssh(Backend::Ssh)]
+#[case::docker(Backend::Docker)]
+#[test_log::test]
+fn should_forward_stdin_eof(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let mut child = ctx
+        .new_std_cmd(["spawn"])
+        .args(["--", "wc", "-c"])
+        .spawn()
+        .expect("Failed to spawn");
+
+    let mut stdin = child.stdin.take().expect("stdin should be piped");
+    stdin
+        .write_all(b"hello\n")
+        .expect("Failed to write to stdin");
+    drop(stdin);
+
+    let output = child
+        .wait_with_output()
+        .expect("Failed to wait for child process");
+
+    assert!(
+        output.status.success(),
+        "spawn reading until EOF should succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "6", "Unexpected stdout: {stdout}");
+}
+
+#[cfg(unix)]
+#[rstest]
+#[case::host(Backend::Host)]
+#[case::ssh(Backend::Ssh)]
+#[case::docker(Backend::Docker)]
+#[test_log::test]
+fn should_kill_process_once_timeout_exceeded(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let start = std::time::Instant::now();
+    let output = ctx
+        .new_std_cmd(["spawn"])
+        .args(["--timeout", "1", "--", "sleep", "30"])
+        .output()
+        .expect("Failed to run spawn");
+
+    assert!(
+        !output.status.success(),
+        "spawn of a process killed by timeout should fail"
+    );
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(20),
+        "Process was not killed within the timeout, took {:?}",
+        start.elapsed()
+    );
+}
+
 #[rstest]
 #[case::host(Backend::Host)]
 #[case::ssh(Backend::Ssh)]
@@ -316,6 +378,65 @@ fn should_forward_environment_variables(#[case] backend: Backend) {
     );
 }
 
+#[cfg(unix)]
+#[rstest]
+#[case::host(Backend::Host)]
+#[case::ssh(Backend::Ssh)]
+#[case::docker(Backend::Docker)]
+#[test_log::test]
+fn should_only_provide_explicit_environment_variables_when_clearing_env(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let output = ctx
+        .new_std_cmd(["spawn"])
+        .args([
+            "--clear-env",
+            "--environment",
+            "DISTANT_TEST_VAR=kept",
+            "-c",
+            "sh -c 'echo $DISTANT_TEST_VAR ${HOME-unset}'",
+        ])
+        .output()
+        .expect("Failed to run spawn");
+
+    assert!(
+        output.status.success(),
+        "spawn with --clear-env should succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "kept unset", "Unexpected stdout: {stdout}");
+}
+
+#[cfg(unix)]
+#[rstest]
+#[case::host(Backend::Host)]
+#[case::ssh(Backend::Ssh)]
+#[case::docker(Backend::Docker)]
+#[test_log::test]
+fn should_run_command_through_login_shell(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let output = ctx
+        .new_std_cmd(["spawn"])
+        .args(["--login", "-c", "echo login-ok"])
+        .output()
+        .expect("Failed to run spawn");
+
+    assert!(
+        output.status.success(),
+        "spawn with --login should succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("login-ok"),
+        "Expected 'login-ok' in stdout, got: {stdout}"
+    );
+}
+
 #[rstest]
 #[case::host(Backend::Host)]
 #[case::ssh(Backend::Ssh)]
@@ -358,3 +479,95 @@ async fn should_propagate_pty_resize(#[case] backend: Backend) {
     session.resize(50, 132);
     session.expect("50");
 }
+
+/// Only the host backend is covered, as the SSH backend relays agent channels to the agent of the
+/// manager rather than the one set for the CLI, and the Docker backend lacks Unix socket tunnels.
+#[cfg(unix)]
+#[rstest]
+#[case::host(Backend::Host)]
+#[tokio::test]
+async fn should_forward_agent_to_process(#[case] backend: Backend) {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let ctx = skip_if_no_backend!(backend);
+
+    // Stand in for an agent with an echo server
+    let dir = assert_fs::TempDir::new().expect("Failed to create temp dir");
+    let agent_path = dir.path().join("agent.sock");
+    let listener = tokio::net::UnixListener::bind(&agent_path).expect("Failed to bind agent");
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (mut reader, mut writer) = stream.into_split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            });
+        }
+    });
+
+    let mut child = ctx
+        .new_std_cmd(["spawn"])
+        .env("SSH_AUTH_SOCK", &agent_path)
+        .args([
+            "-A",
+            "--",
+            "sh",
+            "-c",
+            "'sleep 1; echo $SSH_AUTH_SOCK; sleep 3'",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run spawn");
+
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .expect("Failed to read socket path");
+    let remote_path = line.trim().to_string();
+    assert!(
+        remote_path.starts_with("/tmp/distant-agent-"),
+        "Expected a forwarded agent socket, got: {remote_path}"
+    );
+
+    let mut stream = tokio::net::UnixStream::connect(&remote_path)
+        .await
+        .expect("Failed to connect to forwarded agent");
+    stream.write_all(b"agent-request").await.unwrap();
+    let mut response = [0u8; 13];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(&response, b"agent-request");
+    drop(stream);
+
+    let status = child.wait().expect("Failed to wait for spawn");
+    assert!(status.success(), "spawn should succeed");
+
+    let remote_dir = std::path::Path::new(&remote_path).parent().unwrap();
+    assert!(
+        !remote_dir.exists(),
+        "Expected {} to be removed once the process exits",
+        remote_dir.display()
+    );
+}
+
+#[rstest]
+#[case::host(Backend::Host)]
+#[test_log::test]
+fn should_fail_to_forward_agent_without_ssh_auth_sock(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let output = ctx
+        .new_std_cmd(["spawn"])
+        .env_remove("SSH_AUTH_SOCK")
+        .args(["-A", "--", "echo", "unreachable"])
+        .output()
+        .expect("Failed to run spawn");
+
+    assert!(!output.status.success(), "spawn should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("SSH_AUTH_SOCK is not set"),
+        "Expected missing agent error, got: {stderr}"
+    );
+}
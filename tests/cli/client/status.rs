@@ -64,3 +64,41 @@ fn should_output_detail_for_specific_connection(#[case] backend: Backend) {
         String::from_utf8_lossy(&detail_output.stderr),
     );
 }
+
+#[rstest]
+#[case::host(Backend::Host)]
+#[test_log::test]
+fn should_report_connection_state_in_json_detail(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let output = ctx
+        .new_assert_cmd(vec!["status", "--format", "json"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    let parsed: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    let id = parsed
+        .as_object()
+        .unwrap()
+        .keys()
+        .next()
+        .expect("Should have at least one connection")
+        .clone();
+
+    let detail_output = ctx
+        .new_std_cmd(vec!["status", "--format", "json"])
+        .arg(&id)
+        .output()
+        .expect("Failed to run status <id>");
+    assert!(
+        detail_output.status.success(),
+        "status <id> should succeed, stderr: {}",
+        String::from_utf8_lossy(&detail_output.stderr),
+    );
+
+    let detail: serde_json::Value =
+        serde_json::from_str(String::from_utf8_lossy(&detail_output.stdout).trim())
+            .expect("stdout should be valid JSON");
+    assert_eq!(detail["state"], "connected", "Unexpected detail: {detail}");
+}
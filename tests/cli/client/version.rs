@@ -107,7 +107,7 @@ fn should_report_all_capabilities_via_json(#[case] backend: Backend) {
         .collect();
 
     // All backends unconditionally report these core capabilities.
-    let common = ["exec", "fs_io", "sys_info"];
+    let common = ["exec", "fs_io", "sys_info", "compact_packets"];
     for cap in &common {
         assert!(
             cap_strings.contains(cap),
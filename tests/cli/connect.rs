@@ -98,6 +98,151 @@ fn connect_distant_establishes_connection(manager_only_ctx: ManagerOnlyCtx) {
     );
 }
 
+/// Spawns `distant server listen` with `args` and connects to it through the manager using the
+/// credentials it prints, which are expected to use `scheme`, verifying the connection works by
+/// running a version command. Any `options` are passed along to `distant connect`.
+fn connect_to_server_listening_with(
+    manager_only_ctx: &ManagerOnlyCtx,
+    args: &[&str],
+    scheme: &str,
+    options: Option<&str>,
+) {
+    use std::io::{BufRead, BufReader};
+    use std::process::Command;
+
+    use distant_test_harness::manager;
+    use distant_test_harness::process::TestChild;
+
+    let mut server = TestChild::spawn(
+        Command::new(manager::bin_path())
+            .args(["server", "listen", "--host", "127.0.0.1", "--port", "0"])
+            .args(args),
+    )
+    .expect("Failed to spawn server");
+
+    // NOTE: The reader is kept alive for the duration of the test, otherwise the server fails
+    //       to print the remainder of its output and exits
+    let mut stdout = BufReader::new(server.stdout.take().unwrap());
+    let prefix = format!("{scheme}://");
+    let mut creds = String::new();
+    while !creds.starts_with(&prefix) {
+        creds.clear();
+        let n = stdout
+            .read_line(&mut creds)
+            .expect("Failed to read server stdout");
+        assert!(n > 0, "Server did not print {prefix} credentials");
+        creds = creds.trim().to_string();
+    }
+
+    let mut connect_cmd = manager_only_ctx.new_std_cmd(["connect"]);
+    if let Some(options) = options {
+        connect_cmd.arg("--options").arg(options);
+    }
+    let connect_output = connect_cmd
+        .arg(&creds)
+        .output()
+        .expect("Failed to run connect command");
+
+    assert!(
+        connect_output.status.success(),
+        "connect with {prefix} credentials should succeed, stderr: {}",
+        String::from_utf8_lossy(&connect_output.stderr)
+    );
+
+    let version_output = manager_only_ctx
+        .new_std_cmd(["version"])
+        .output()
+        .expect("Failed to run version command");
+
+    assert!(
+        version_output.status.success(),
+        "version should succeed after {prefix} connect, stderr: {}",
+        String::from_utf8_lossy(&version_output.stderr)
+    );
+
+    drop(stdout);
+    server.kill();
+}
+
+/// Connects to a distant server started with `--websocket` using `ws://` credentials.
+#[rstest]
+#[test_log::test]
+fn connect_websocket_establishes_connection(manager_only_ctx: ManagerOnlyCtx) {
+    connect_to_server_listening_with(&manager_only_ctx, &["--websocket"], "ws", None);
+}
+
+/// Connects to a distant server started with `--quic` using `quic://` credentials.
+#[rstest]
+#[test_log::test]
+fn connect_quic_establishes_connection(manager_only_ctx: ManagerOnlyCtx) {
+    connect_to_server_listening_with(&manager_only_ctx, &["--quic"], "quic", None);
+}
+
+/// Connects to a distant server requiring client certificates (mutual TLS) using `tls://`
+/// credentials, with the certificates issued by a throwaway certificate authority.
+#[rstest]
+#[test_log::test]
+fn connect_tls_with_client_certificate_establishes_connection(manager_only_ctx: ManagerOnlyCtx) {
+    use assert_fs::prelude::*;
+    use rcgen::{
+        BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    };
+
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    ca_params
+        .distinguished_name
+        .push(DnType::CommonName, "distant test ca");
+    let ca_key = KeyPair::generate().unwrap();
+    let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+
+    let dir = assert_fs::TempDir::new().unwrap();
+    dir.child("ca.pem").write_str(&ca_cert.pem()).unwrap();
+
+    for (name, subject_alt_names, usage) in [
+        (
+            "server",
+            vec!["127.0.0.1".to_string()],
+            ExtendedKeyUsagePurpose::ServerAuth,
+        ),
+        ("client", Vec::new(), ExtendedKeyUsagePurpose::ClientAuth),
+    ] {
+        let mut params = CertificateParams::new(subject_alt_names).unwrap();
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.extended_key_usages = vec![usage];
+        let key = KeyPair::generate().unwrap();
+        let cert = params.signed_by(&key, &ca_cert, &ca_key).unwrap();
+        dir.child(format!("{name}-cert.pem"))
+            .write_str(&cert.pem())
+            .unwrap();
+        dir.child(format!("{name}-key.pem"))
+            .write_str(&key.serialize_pem())
+            .unwrap();
+    }
+
+    let path = |name: &str| dir.child(name).path().to_string_lossy().to_string();
+    let options = format!(
+        "tls_ca=\"{}\",tls_cert=\"{}\",tls_key=\"{}\"",
+        path("ca.pem"),
+        path("client-cert.pem"),
+        path("client-key.pem"),
+    );
+
+    connect_to_server_listening_with(
+        &manager_only_ctx,
+        &[
+            "--tls-cert",
+            &path("server-cert.pem"),
+            "--tls-key",
+            &path("server-key.pem"),
+            "--tls-client-ca",
+            &path("ca.pem"),
+        ],
+        "tls",
+        Some(&options),
+    );
+}
+
 /// Connects to a Docker container using `docker://` and verifies the
 /// connection works by running a version command.
 #[cfg(feature = "docker")]
@@ -122,7 +122,7 @@ fn distant_tunnel_help_shows_subcommands() {
     let output = cmd.args(["tunnel", "--help"]).assert().success();
 
     let stdout = String::from_utf8_lossy(&output.get_output().stdout);
-    for subcmd in ["open", "listen", "close", "list"] {
+    for subcmd in ["open", "listen", "socks", "http", "close", "list"] {
         assert!(
             stdout.contains(subcmd),
             "Expected tunnel help to contain '{subcmd}', got:\n{stdout}"
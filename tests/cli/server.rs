@@ -107,6 +107,120 @@ fn server_listen_custom_port() {
     }
 }
 
+#[test]
+fn server_listen_websocket_should_output_ws_credentials() {
+    let mut child = TestChild::spawn(Command::new(manager::bin_path()).args([
+        "server",
+        "listen",
+        "--websocket",
+        "--host",
+        "127.0.0.1",
+        "--port",
+        "0",
+        "--shutdown",
+        "after=1",
+    ]))
+    .expect("Failed to spawn websocket server");
+
+    let mut stdout = child.stdout.take().unwrap();
+    let mut output = String::new();
+    let mut buf = [0u8; 4096];
+
+    let start = std::time::Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        match stdout.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                output.push_str(&String::from_utf8_lossy(&buf[..n]));
+                if output.contains("ws://") {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(_) => break,
+        }
+    }
+
+    child.kill();
+
+    assert!(
+        output.contains("ws://") && output.contains("@127.0.0.1:"),
+        "Expected ws:// credentials in output, got: {output}"
+    );
+    assert!(
+        !output.contains("distant://"),
+        "Expected no distant:// credentials in output, got: {output}"
+    );
+}
+
+#[test]
+fn server_listen_quic_should_output_quic_credentials() {
+    let mut child = TestChild::spawn(Command::new(manager::bin_path()).args([
+        "server",
+        "listen",
+        "--quic",
+        "--host",
+        "127.0.0.1",
+        "--port",
+        "0",
+        "--shutdown",
+        "after=1",
+    ]))
+    .expect("Failed to spawn quic server");
+
+    let mut stdout = child.stdout.take().unwrap();
+    let mut output = String::new();
+    let mut buf = [0u8; 4096];
+
+    let start = std::time::Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        match stdout.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                output.push_str(&String::from_utf8_lossy(&buf[..n]));
+                if output.contains("quic://") {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(_) => break,
+        }
+    }
+
+    child.kill();
+
+    assert!(
+        output.contains("quic://") && output.contains("@127.0.0.1:"),
+        "Expected quic:// credentials in output, got: {output}"
+    );
+}
+
+#[test]
+fn server_listen_tls_should_fail_if_certificate_cannot_be_loaded() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let missing = dir.path().join("missing.pem");
+
+    let output = Command::new(manager::bin_path())
+        .args(["server", "listen", "--host", "127.0.0.1", "--port", "0"])
+        .arg("--tls-cert")
+        .arg(&missing)
+        .arg("--tls-key")
+        .arg(&missing)
+        .output()
+        .expect("Failed to run server listen");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Failed to load TLS certificate"),
+        "Expected TLS load failure, got: {stderr}"
+    );
+}
+
 #[test]
 fn server_listen_help_should_show_options() {
     let output = Command::new(manager::bin_path())
@@ -1,7 +1,8 @@
 //! Integration tests for the `distant tunnel` CLI subcommands.
 //!
-//! Tests forward tunnel creation, data forwarding through tunnels, tunnel
-//! listing, closing, and error handling for missing connections and invalid IDs.
+//! Tests forward tunnel creation, data forwarding through TCP, UDP, and Unix socket tunnels,
+//! SOCKS proxies, tunnel listing, closing, and error handling for missing
+//! connections and invalid IDs.
 
 use std::net::TcpListener;
 use std::process::Stdio;
@@ -32,9 +33,10 @@ const ECHO_SERVER_LIFETIME_SECS: &str = "30";
 
 /// Parses the tunnel ID and actual port from a "Tunnel N started: ..." output line.
 ///
-/// Handles two output formats:
+/// Handles three output formats:
 ///   Forward:  `Tunnel 1 started: 127.0.0.1:54321 -> 127.0.0.1:9999`
 ///   Reverse:  `Tunnel 1 started: remote port 54321 -> 127.0.0.1:9999`
+///   SOCKS:    `Tunnel 1 started: 127.0.0.1:54321 (SOCKS proxy)`
 ///
 /// Uses regex to locate the "Tunnel" keyword and extract fields by name,
 /// avoiding fragile positional word indexing.
@@ -74,6 +76,49 @@ fn parse_tunnel_started(output: &str) -> (u32, u16) {
     (id, port)
 }
 
+/// Spawns a UDP echo server on the host that sends every datagram back to its sender,
+/// returning its listening port. The server stops when the test's runtime shuts down.
+async fn spawn_udp_echo_server() -> u16 {
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0")
+        .await
+        .expect("failed to bind udp echo server");
+    let port = socket.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let mut buf = vec![0u8; 65536];
+        while let Ok((n, peer)) = socket.recv_from(&mut buf).await {
+            let _ = socket.send_to(&buf[..n], peer).await;
+        }
+    });
+
+    port
+}
+
+/// Sends each datagram through a UDP socket connected to `port` and asserts that
+/// each one comes back unchanged as its own datagram.
+async fn assert_udp_echo(port: u16, datagrams: &[&[u8]]) {
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0")
+        .await
+        .expect("failed to bind udp client");
+    socket
+        .connect(("127.0.0.1", port))
+        .await
+        .expect("failed to connect udp client");
+
+    let mut buf = vec![0u8; 65536];
+    for datagram in datagrams {
+        socket
+            .send(datagram)
+            .await
+            .expect("failed to send datagram");
+        let n = time::timeout(TCP_IO_TIMEOUT, socket.recv(&mut buf))
+            .await
+            .expect("timed out waiting for echoed datagram")
+            .expect("failed to receive echoed datagram");
+        assert_eq!(&buf[..n], *datagram, "echoed datagram should match");
+    }
+}
+
 /// Spawns a tcp-echo-server on the host and returns its child process and listening port.
 async fn spawn_echo_server() -> (Child, u16) {
     let echo_bin = exe::build_tcp_echo_server()
@@ -430,8 +475,8 @@ async fn tunnel_open_should_handle_invalid_address(#[case] backend: Backend) {
     }
 }
 
-/// Docker is excluded because the Docker backend does not support
-/// `tunnel_listen` (returns "tunnel_listen is not supported for Docker backends").
+/// Docker is excluded because the Docker backend needs socat or python3 inside the
+/// container to `tunnel_listen`, and the test container has neither.
 #[rstest]
 #[case::host(Backend::Host)]
 #[case::ssh(Backend::Ssh)]
@@ -484,8 +529,8 @@ fn tunnel_listen_should_fail_without_connection(manager_only_ctx: ManagerOnlyCtx
     );
 }
 
-/// Docker is excluded because the Docker backend does not support
-/// `tunnel_listen` (returns "tunnel_listen is not supported for Docker backends").
+/// Docker is excluded because the Docker backend needs socat or python3 inside the
+/// container to `tunnel_listen`, and the test container has neither.
 #[rstest]
 #[case::host(Backend::Host)]
 #[case::ssh(Backend::Ssh)]
@@ -574,8 +619,8 @@ async fn tunnel_open_should_forward_data(#[case] backend: Backend) {
     );
 }
 
-/// Docker is excluded because the Docker backend does not support
-/// `tunnel_listen` (returns "tunnel_listen is not supported for Docker backends").
+/// Docker is excluded because the Docker backend needs socat or python3 inside the
+/// container to `tunnel_listen`, and the test container has neither.
 #[rstest]
 #[case::host(Backend::Host)]
 #[case::ssh(Backend::Ssh)]
@@ -634,3 +679,562 @@ async fn tunnel_listen_should_forward_data(#[case] backend: Backend) {
         "data through reverse tunnel should match via {backend:?}"
     );
 }
+
+#[rstest]
+#[test_log::test]
+fn tunnel_socks_should_fail_without_connection(manager_only_ctx: ManagerOnlyCtx) {
+    let output = manager_only_ctx
+        .new_std_cmd(["tunnel", "socks"])
+        .arg("0")
+        .output()
+        .expect("failed to run tunnel socks");
+
+    assert!(
+        !output.status.success(),
+        "tunnel socks should fail without an active connection"
+    );
+}
+
+/// Docker is excluded for the same half-close reason as
+/// `tunnel_open_should_forward_data`.
+#[rstest]
+#[case::host(Backend::Host)]
+#[case::ssh(Backend::Ssh)]
+#[tokio::test]
+async fn tunnel_socks_should_forward_data(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let (_echo, echo_port) = spawn_reachable_echo_server(&ctx).await;
+
+    let output = ctx
+        .new_std_cmd(["tunnel", "socks"])
+        .arg("0")
+        .output()
+        .expect("failed to run tunnel socks");
+
+    assert!(
+        output.status.success(),
+        "tunnel socks should succeed via {backend:?}, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout_str = String::from_utf8_lossy(&output.stdout);
+    let (_id, proxy_port) = parse_tunnel_started(&stdout_str);
+    assert!(proxy_port > 0, "socks proxy should bind to a real port");
+
+    let mut stream = time::timeout(
+        TCP_IO_TIMEOUT,
+        tokio::net::TcpStream::connect(format!("127.0.0.1:{proxy_port}")),
+    )
+    .await
+    .expect("timed out connecting to socks proxy")
+    .expect("failed to connect to socks proxy");
+
+    // SOCKS5 greeting offering only "no authentication"
+    stream
+        .write_all(&[5, 1, 0])
+        .await
+        .expect("failed to write socks greeting");
+    let mut method = [0u8; 2];
+    time::timeout(TCP_IO_TIMEOUT, stream.read_exact(&mut method))
+        .await
+        .expect("timed out reading socks method")
+        .expect("failed to read socks method");
+    assert_eq!(method, [5, 0], "proxy should select no authentication");
+
+    // CONNECT to the echo server at 127.0.0.1 on the remote side
+    let mut connect = vec![5, 1, 0, 1, 127, 0, 0, 1];
+    connect.extend_from_slice(&echo_port.to_be_bytes());
+    stream
+        .write_all(&connect)
+        .await
+        .expect("failed to write socks connect");
+
+    let mut reply = [0u8; 10];
+    time::timeout(TCP_IO_TIMEOUT, stream.read_exact(&mut reply))
+        .await
+        .expect("timed out reading socks reply")
+        .expect("failed to read socks reply");
+    assert_eq!(reply[1], 0, "socks CONNECT should succeed via {backend:?}");
+
+    let payload = b"socks tunnel data";
+    stream
+        .write_all(payload)
+        .await
+        .expect("failed to write through socks proxy");
+
+    time::sleep(PROPAGATION_DELAY).await;
+
+    stream
+        .shutdown()
+        .await
+        .expect("failed to shut down write half");
+
+    let mut response = Vec::new();
+    time::timeout(TCP_IO_TIMEOUT, stream.read_to_end(&mut response))
+        .await
+        .expect("timed out reading response through socks proxy")
+        .expect("failed to read response through socks proxy");
+
+    assert_eq!(
+        response, payload,
+        "data through socks proxy should match what was sent via {backend:?}"
+    );
+}
+
+#[rstest]
+#[test_log::test]
+fn tunnel_http_should_fail_without_connection(manager_only_ctx: ManagerOnlyCtx) {
+    let output = manager_only_ctx
+        .new_std_cmd(["tunnel", "http", "--bind", "0"])
+        .arg("web=3000")
+        .output()
+        .expect("failed to run tunnel http");
+
+    assert!(
+        !output.status.success(),
+        "tunnel http should fail without an active connection"
+    );
+}
+
+/// Reads from `stream` until the end of an HTTP head (`\r\n\r\n`), returning what was read.
+async fn read_http_head(stream: &mut tokio::net::TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = time::timeout(TCP_IO_TIMEOUT, stream.read(&mut buf))
+            .await
+            .expect("timed out reading through http proxy")
+            .expect("failed to read through http proxy");
+        assert!(n > 0, "http proxy closed before sending a full head");
+        head.extend_from_slice(&buf[..n]);
+    }
+    String::from_utf8_lossy(&head).into_owned()
+}
+
+/// The echo server sends each request back, so the response is the request as the proxy
+/// passed it on. Docker is excluded for the same half-close reason as
+/// `tunnel_open_should_forward_data`.
+#[rstest]
+#[case::host(Backend::Host)]
+#[case::ssh(Backend::Ssh)]
+#[tokio::test]
+async fn tunnel_http_should_route_requests_by_host(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let (_echo, echo_port) = spawn_reachable_echo_server(&ctx).await;
+    let (_drain, drain_port) = spawn_reachable_echo_server(&ctx).await;
+    let (_upgrade, upgrade_port) = spawn_reachable_echo_server(&ctx).await;
+
+    let output = ctx
+        .new_std_cmd(["tunnel", "http", "--bind", "0"])
+        .arg(format!("echo=127.0.0.1:{echo_port}"))
+        .arg(format!("drain=127.0.0.1:{drain_port}"))
+        .arg(format!("upgrade=127.0.0.1:{upgrade_port}"))
+        .output()
+        .expect("failed to run tunnel http");
+
+    assert!(
+        output.status.success(),
+        "tunnel http should succeed via {backend:?}, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout_str = String::from_utf8_lossy(&output.stdout);
+    let (_id, proxy_port) = parse_tunnel_started(&stdout_str);
+    assert!(proxy_port > 0, "http proxy should bind to a real port");
+    assert!(
+        stdout_str.contains(&format!(
+            "http://echo.localhost:{proxy_port} -> 127.0.0.1:{echo_port}"
+        )),
+        "output should list the route, got: {stdout_str}"
+    );
+
+    let connect = || async {
+        time::timeout(
+            TCP_IO_TIMEOUT,
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{proxy_port}")),
+        )
+        .await
+        .expect("timed out connecting to http proxy")
+        .expect("failed to connect to http proxy")
+    };
+
+    let mut stream = connect().await;
+    stream
+        .write_all(
+            format!("GET /path HTTP/1.1\r\nHost: echo.localhost:{proxy_port}\r\n\r\n").as_bytes(),
+        )
+        .await
+        .expect("failed to write through http proxy");
+
+    let echoed = read_http_head(&mut stream).await;
+    assert!(
+        echoed.starts_with(&format!(
+            "GET /path HTTP/1.1\r\nHost: 127.0.0.1:{echo_port}\r\n"
+        )),
+        "request should reach the route with its Host rewritten via {backend:?}, got: {echoed}"
+    );
+    assert!(
+        echoed.contains(&format!(
+            "X-Forwarded-Host: echo.localhost:{proxy_port}\r\n"
+        )),
+        "request should carry the original host via {backend:?}, got: {echoed}"
+    );
+
+    // A client that closes its half of the connection still gets the response to its request
+    let mut stream = connect().await;
+    stream
+        .write_all(
+            format!("GET /half-closed HTTP/1.1\r\nHost: drain.localhost:{proxy_port}\r\n\r\n")
+                .as_bytes(),
+        )
+        .await
+        .expect("failed to write through http proxy");
+    stream
+        .shutdown()
+        .await
+        .expect("failed to shut down write half");
+
+    let echoed = read_http_head(&mut stream).await;
+    assert!(
+        echoed.starts_with("GET /half-closed HTTP/1.1\r\n"),
+        "response should reach a half-closed client via {backend:?}, got: {echoed}"
+    );
+
+    // The echo server refuses the upgrade by not answering with a 101, so what the client sends
+    // after the upgrade request is not relayed and the connection closes after the response
+    let mut stream = connect().await;
+    stream
+        .write_all(
+            format!(
+                "GET /ws HTTP/1.1\r\nHost: upgrade.localhost:{proxy_port}\r\n\
+                 Upgrade: websocket\r\nConnection: Upgrade\r\n\r\nframe"
+            )
+            .as_bytes(),
+        )
+        .await
+        .expect("failed to write through http proxy");
+
+    let mut echoed = Vec::new();
+    time::timeout(Duration::from_secs(30), stream.read_to_end(&mut echoed))
+        .await
+        .expect("http proxy should close a connection whose upgrade is refused")
+        .expect("failed to read through http proxy");
+    let echoed = String::from_utf8_lossy(&echoed);
+    assert!(
+        echoed.starts_with("GET /ws HTTP/1.1\r\n") && !echoed.contains("frame"),
+        "data after a refused upgrade should not be relayed via {backend:?}, got: {echoed}"
+    );
+
+    // Hosts that do not name a route are answered by the proxy itself
+    let mut stream = connect().await;
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: other.localhost\r\n\r\n")
+        .await
+        .expect("failed to write through http proxy");
+
+    let response = read_http_head(&mut stream).await;
+    assert!(
+        response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+        "unknown host should get a 404 via {backend:?}, got: {response}"
+    );
+}
+
+/// Only the host backend implements UDP tunnels.
+#[rstest]
+#[case::host(Backend::Host)]
+#[tokio::test]
+async fn tunnel_open_udp_should_forward_datagrams(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let echo_port = spawn_udp_echo_server().await;
+
+    let spec = format!("0:127.0.0.1:{echo_port}");
+    let output = ctx
+        .new_std_cmd(["tunnel", "open", "--udp"])
+        .arg(&spec)
+        .output()
+        .expect("failed to run tunnel open --udp");
+
+    assert!(
+        output.status.success(),
+        "tunnel open --udp should succeed via {backend:?}, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout_str = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout_str.contains("(udp)"),
+        "output should note the udp protocol, got: {stdout_str}"
+    );
+    let (_id, local_port) = parse_tunnel_started(&stdout_str);
+
+    assert_udp_echo(local_port, &[b"first datagram", b"second", b""]).await;
+
+    let list_output = ctx
+        .new_std_cmd(["tunnel", "list"])
+        .output()
+        .expect("failed to run tunnel list");
+    let list_str = String::from_utf8_lossy(&list_output.stdout);
+    assert!(
+        list_str.contains("udp"),
+        "tunnel list should show the udp protocol, got: {list_str}"
+    );
+}
+
+/// Only the host backend implements UDP tunnels.
+#[rstest]
+#[case::host(Backend::Host)]
+#[tokio::test]
+async fn tunnel_listen_udp_should_forward_datagrams(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let echo_port = spawn_udp_echo_server().await;
+
+    let spec = format!("0:127.0.0.1:{echo_port}");
+    let output = ctx
+        .new_std_cmd(["tunnel", "listen", "--udp"])
+        .arg(&spec)
+        .output()
+        .expect("failed to run tunnel listen --udp");
+
+    assert!(
+        output.status.success(),
+        "tunnel listen --udp should succeed via {backend:?}, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout_str = String::from_utf8_lossy(&output.stdout);
+    let (_id, remote_port) = parse_tunnel_started(&stdout_str);
+
+    assert_udp_echo(remote_port, &[b"reverse datagram", b"another"]).await;
+}
+
+/// Spawns a Unix socket echo server on the host at `path` that writes back everything it
+/// reads from each connection. The server stops when the test's runtime shuts down.
+#[cfg(unix)]
+fn spawn_unix_echo_server(path: &std::path::Path) {
+    let listener = tokio::net::UnixListener::bind(path).expect("failed to bind unix echo server");
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (mut reader, mut writer) = stream.into_split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            });
+        }
+    });
+}
+
+/// Docker is excluded because its backend does not implement Unix socket tunnels.
+#[cfg(unix)]
+#[rstest]
+#[case::host(Backend::Host)]
+#[case::ssh(Backend::Ssh)]
+#[tokio::test]
+async fn tunnel_open_should_forward_data_to_unix_socket(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let dir = assert_fs::TempDir::new().expect("failed to create temp dir");
+    let socket_path = dir.path().join("echo.sock");
+    spawn_unix_echo_server(&socket_path);
+
+    let spec = format!("0:{}", socket_path.display());
+    let output = ctx
+        .new_std_cmd(["tunnel", "open"])
+        .arg(&spec)
+        .output()
+        .expect("failed to run tunnel open");
+
+    assert!(
+        output.status.success(),
+        "tunnel open should succeed via {backend:?}, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout_str = String::from_utf8_lossy(&output.stdout);
+    let (_id, local_port) = parse_tunnel_started(&stdout_str);
+
+    let mut stream = time::timeout(
+        TCP_IO_TIMEOUT,
+        tokio::net::TcpStream::connect(format!("127.0.0.1:{local_port}")),
+    )
+    .await
+    .expect("timed out connecting to tunnel")
+    .expect("failed to connect to tunnel");
+
+    let payload = b"unix socket tunnel data";
+    stream
+        .write_all(payload)
+        .await
+        .expect("failed to write to tunnel");
+
+    let mut response = vec![0u8; payload.len()];
+    time::timeout(TCP_IO_TIMEOUT, stream.read_exact(&mut response))
+        .await
+        .expect("timed out reading response through tunnel")
+        .expect("failed to read response through tunnel");
+    assert_eq!(response, payload);
+
+    let list_output = ctx
+        .new_std_cmd(["tunnel", "list"])
+        .output()
+        .expect("failed to run tunnel list");
+    let list_str = String::from_utf8_lossy(&list_output.stdout);
+    assert!(
+        list_str.contains("unix") && list_str.contains(&socket_path.display().to_string()),
+        "tunnel list should show the unix socket path, got: {list_str}"
+    );
+}
+
+/// Docker is excluded because its backend does not implement Unix socket tunnels.
+#[cfg(unix)]
+#[rstest]
+#[case::host(Backend::Host)]
+#[case::ssh(Backend::Ssh)]
+#[tokio::test]
+async fn tunnel_listen_should_forward_data_from_unix_socket(#[case] backend: Backend) {
+    let ctx = skip_if_no_backend!(backend);
+
+    let (_echo, echo_port) = spawn_echo_server().await;
+
+    let dir = assert_fs::TempDir::new().expect("failed to create temp dir");
+    let socket_path = dir.path().join("listen.sock");
+
+    let spec = format!("{}:127.0.0.1:{echo_port}", socket_path.display());
+    let output = ctx
+        .new_std_cmd(["tunnel", "listen"])
+        .arg(&spec)
+        .output()
+        .expect("failed to run tunnel listen");
+
+    assert!(
+        output.status.success(),
+        "tunnel listen should succeed via {backend:?}, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout_str = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout_str.contains(&format!("remote socket {}", socket_path.display())),
+        "output should show the remote socket path, got: {stdout_str}"
+    );
+
+    let mut stream = time::timeout(
+        TCP_IO_TIMEOUT,
+        tokio::net::UnixStream::connect(&socket_path),
+    )
+    .await
+    .expect("timed out connecting to reverse tunnel")
+    .expect("failed to connect to reverse tunnel");
+
+    let payload = b"reverse unix socket tunnel data";
+    stream
+        .write_all(payload)
+        .await
+        .expect("failed to write to reverse tunnel");
+
+    let mut response = vec![0u8; payload.len()];
+    time::timeout(TCP_IO_TIMEOUT, stream.read_exact(&mut response))
+        .await
+        .expect("timed out reading response through reverse tunnel")
+        .expect("failed to read response through reverse tunnel");
+    assert_eq!(response, payload);
+}
+
+#[rstest]
+#[test_log::test]
+fn tunnel_auto_should_fail_if_manager_is_not_auto_forwarding(ctx: HostManagerCtx) {
+    let output = ctx
+        .new_std_cmd(["tunnel", "auto"])
+        .output()
+        .expect("failed to run tunnel auto");
+
+    assert!(
+        !output.status.success(),
+        "tunnel auto should fail when the manager is not forwarding ports"
+    );
+
+    let stderr_str = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr_str.contains("Automatic port forwarding is not enabled"),
+        "expected auto-forward error in stderr, got: {stderr_str}"
+    );
+}
+
+/// Only the host backend on Linux can list listening ports without external tools, and the
+/// echo server it watches for runs on the same machine as the manager.
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn tunnel_auto_should_forward_ports_that_remote_processes_listen_on() {
+    let ctx = HostManagerCtx::start_with_manager_args([
+        "--auto-forward",
+        "--auto-forward-interval",
+        "0.2",
+    ]);
+
+    let mut auto = Command::from(ctx.new_std_cmd(["tunnel", "auto"]));
+    let mut auto = auto
+        .kill_on_drop(true)
+        .spawn()
+        .expect("failed to spawn tunnel auto");
+    let mut lines =
+        tokio::io::BufReader::new(auto.stdout.take().expect("stdout not captured")).lines();
+
+    let mut next_line_containing = async |needle: &str| -> String {
+        time::timeout(ECHO_SERVER_STARTUP_TIMEOUT, async {
+            loop {
+                let line = lines
+                    .next_line()
+                    .await
+                    .expect("failed to read tunnel auto output")
+                    .expect("tunnel auto exited early");
+                if line.contains(needle) {
+                    return line;
+                }
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("timed out waiting for '{needle}' from tunnel auto"))
+    };
+
+    next_line_containing("Watching for ports").await;
+
+    // The echo server listens on the same machine as the manager, so its port is taken locally
+    // and the manager has to fall back to some other local port
+    let (echo, echo_port) = spawn_echo_server().await;
+    let line = next_line_containing(&format!("remote port {echo_port} forwarded")).await;
+
+    let re = Regex::new(r"Tunnel\s+(?<id>\d+)\s+started:.*localhost:(?<port>\d+)")
+        .expect("tunnel auto regex should compile");
+    let caps = re
+        .captures(&line)
+        .unwrap_or_else(|| panic!("unexpected tunnel auto output: '{line}'"));
+    let id = &caps["id"];
+    let local_port: u16 = caps["port"].parse().expect("local port should be a number");
+
+    let mut stream = time::timeout(
+        TCP_IO_TIMEOUT,
+        tokio::net::TcpStream::connect(("127.0.0.1", local_port)),
+    )
+    .await
+    .expect("timed out connecting to forwarded port")
+    .expect("failed to connect to forwarded port");
+
+    let payload = b"auto forwarded tunnel data";
+    stream
+        .write_all(payload)
+        .await
+        .expect("failed to write to forwarded port");
+
+    let mut response = vec![0u8; payload.len()];
+    time::timeout(TCP_IO_TIMEOUT, stream.read_exact(&mut response))
+        .await
+        .expect("timed out reading response through forwarded port")
+        .expect("failed to read response through forwarded port");
+    assert_eq!(response, payload);
+
+    drop(stream);
+    drop(echo);
+    next_line_containing(&format!("Tunnel {id} closed: remote port {echo_port}")).await;
+}